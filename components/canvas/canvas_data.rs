@@ -2,20 +2,41 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use azure::azure::AzFloat;
+use app_units::Au;
+use azure::azure::{AzFloat, AzGlyph, AzPoint};
 use azure::azure_hl::{AntialiasMode, CapStyle, CompositionOp, JoinStyle};
 use azure::azure_hl::{BackendType, DrawOptions, DrawTarget, Pattern, StrokeOptions, SurfaceFormat};
 use azure::azure_hl::{Color, ColorPattern, DrawSurfaceOptions, Filter, PathBuilder};
+use azure::azure_hl::{ColorMatrixAttribute, ColorMatrixInput, FilterType, Matrix5x4};
 use azure::azure_hl::{ExtendMode, GradientStop, LinearGradientPattern, RadialGradientPattern};
-use azure::azure_hl::SurfacePattern;
+use azure::azure_hl::{GaussianBlurAttribute, GaussianBlurInput};
+use azure::azure_hl::{Path, SourceSurface, SurfacePattern};
+use azure::azure_hl::FillRule as AzureFillRule;
+use azure::scaled_font::{FontInfo, ScaledFont};
 use canvas_traits::canvas::*;
 use cssparser::RGBA;
 use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D};
+use gfx::font::{FontHandleMethods, FontRef, ShapingFlags, ShapingOptions};
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
+use gfx::text::glyph::{ByteIndex, GlyphId};
 use ipc_channel::ipc::IpcSender;
 use num_traits::ToPrimitive;
+use ordered_float::NotNan;
+use range::Range;
 use serde_bytes::ByteBuf;
+use servo_arc::Arc as ServoArc;
+use servo_atoms::Atom;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f32::consts::PI;
 use std::mem;
+use std::rc::Rc;
 use std::sync::Arc;
+use style::computed_values::font_variant_caps::T as FontVariantCaps;
+use style::properties::style_structs::Font as FontStyleStruct;
+use style::values::computed::font::{FontStretch, FontStyle, FontWeight, SingleFontFamily};
+use unicode_script::Script;
 use webrender_api;
 
 pub struct CanvasData<'a> {
@@ -24,6 +45,14 @@ pub struct CanvasData<'a> {
     path_builder: PathBuilder,
     state: CanvasPaintState<'a>,
     saved_states: Vec<CanvasPaintState<'a>>,
+    /// The bitmap drawing operations are rendered to when shadows or filters
+    /// apply, allocated the first time it is needed.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drawing-model>
+    effects_layer: RefCell<Option<DrawTarget>>,
+    /// The fonts text has been drawn with, by font file and size, since
+    /// creating a scaled font parses the font data.
+    scaled_fonts: RefCell<HashMap<(Atom, Au), CanvasScaledFont>>,
     webrender_api: webrender_api::RenderApi,
     image_key: Option<webrender_api::ImageKey>,
     /// An old webrender image key that can be deleted when the next epoch ends.
@@ -48,6 +77,8 @@ impl<'a> CanvasData<'a> {
             path_builder: path_builder,
             state: CanvasPaintState::new(antialias),
             saved_states: vec![],
+            effects_layer: RefCell::new(None),
+            scaled_fonts: RefCell::new(HashMap::new()),
             webrender_api: webrender_api,
             image_key: None,
             old_image_key: None,
@@ -62,27 +93,17 @@ impl<'a> CanvasData<'a> {
         image_size: Size2D<f64>,
         dest_rect: Rect<f64>,
         source_rect: Rect<f64>,
-        smoothing_enabled: bool
+        smoothing: Option<SmoothingQuality>
     ) {
         // We round up the floating pixel values to draw the pixels
         let source_rect = source_rect.ceil();
         // It discards the extra pixels (if any) that won't be painted
         let image_data = crop_image(image_data, image_size, source_rect);
 
-        let writer = |draw_target: &DrawTarget| {
+        self.draw_with_effects(|draw_target: &DrawTarget, draw_options: &DrawOptions| {
             write_image(&draw_target, image_data, source_rect.size, dest_rect,
-                        smoothing_enabled, self.state.draw_options.composition,
-                        self.state.draw_options.alpha);
-        };
-
-        if self.need_to_draw_shadow() {
-            let rect = Rect::new(Point2D::new(dest_rect.origin.x as f32, dest_rect.origin.y as f32),
-                                 Size2D::new(dest_rect.size.width as f32, dest_rect.size.height as f32));
-
-            self.draw_with_shadow(&rect, writer);
-        } else {
-            writer(&self.drawtarget);
-        }
+                        smoothing, draw_options.composition, draw_options.alpha);
+        });
     }
 
     pub fn draw_image_self(
@@ -90,14 +111,14 @@ impl<'a> CanvasData<'a> {
         image_size: Size2D<f64>,
         dest_rect: Rect<f64>,
         source_rect: Rect<f64>,
-        smoothing_enabled: bool
+        smoothing: Option<SmoothingQuality>
     ) {
         // Reads pixels from source image
         // In this case source and target are the same canvas
         let image_data = self.read_pixels(source_rect.to_i32(), image_size);
 
         // The dimensions of image_data are source_rect.size
-        self.draw_image(image_data, source_rect.size, dest_rect, source_rect, smoothing_enabled);
+        self.draw_image(image_data, source_rect.size, dest_rect, source_rect, smoothing);
     }
 
     pub fn save_context_state(&mut self) {
@@ -112,8 +133,175 @@ impl<'a> CanvasData<'a> {
         }
     }
 
-    pub fn fill_text(&self, text: String, x: f64, y: f64, max_width: Option<f64>) {
-        error!("Unimplemented canvas2d.fillText. Values received: {}, {}, {}, {:?}.", text, x, y, max_width);
+    pub fn fill_text(
+        &mut self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+        direction: Direction,
+    ) {
+        if is_zero_size_gradient(&self.state.fill_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        self.draw_text(font_context, text, x, y, max_width, direction, TextPaintOp::Fill)
+    }
+
+    pub fn stroke_text(
+        &mut self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+        direction: Direction,
+    ) {
+        if is_zero_size_gradient(&self.state.stroke_style) {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        self.draw_text(font_context, text, x, y, max_width, direction, TextPaintOp::Stroke)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#text-preparation-algorithm
+    fn draw_text(
+        &mut self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+        direction: Direction,
+        op: TextPaintOp,
+    ) {
+        // Step 1.
+        if let Some(max_width) = max_width {
+            if !max_width.is_finite() || max_width <= 0. {
+                return;
+            }
+        }
+
+        let text = replace_ascii_whitespace(text);
+        let shaped = shape_text(font_context, &self.state.font_style, &text, direction);
+        if shaped.runs.is_empty() {
+            return;
+        }
+
+        let anchor = Point2D::new(x as f32, y as f32);
+        let origin = anchor + Vector2D::new(
+            self.text_align_offset(shaped.width, direction),
+            self.text_baseline_offset(&shaped),
+        );
+
+        // If the text is wider than maxWidth, it is squeezed horizontally
+        // around the anchor point rather than clipped.
+        let old_transform = self.state.transform;
+        if let Some(max_width) = max_width {
+            let max_width = max_width as f32;
+            if shaped.width > max_width {
+                self.state.transform = old_transform
+                    .pre_translate(anchor.to_vector())
+                    .pre_scale(max_width / shaped.width, 1.)
+                    .pre_translate(-anchor.to_vector());
+                self.drawtarget.set_transform(&self.state.transform);
+            }
+        }
+
+        let paths: Vec<Path> = {
+            let mut scaled_fonts = self.scaled_fonts.borrow_mut();
+            shaped.runs.iter()
+                .map(|run| run.glyph_path(&mut scaled_fonts, &self.drawtarget, origin))
+                .collect()
+        };
+        {
+            self.draw_with_effects(|draw_target: &DrawTarget, draw_options: &DrawOptions| {
+                for path in &paths {
+                    match op {
+                        TextPaintOp::Fill => {
+                            draw_target.fill(path,
+                                             self.state.fill_style.to_pattern_ref(),
+                                             draw_options);
+                        },
+                        TextPaintOp::Stroke => {
+                            draw_target.stroke(path,
+                                               self.state.stroke_style.to_pattern_ref(),
+                                               &self.state.stroke_opts,
+                                               draw_options);
+                        },
+                    }
+                }
+            });
+        }
+
+        self.state.transform = old_transform;
+        self.drawtarget.set_transform(&self.state.transform);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    pub fn measure_text(
+        &self,
+        font_context: &mut FontContext<FontCacheThread>,
+        text: String,
+        direction: Direction,
+        chan: IpcSender<TextMetrics>,
+    ) {
+        let text = replace_ascii_whitespace(text);
+        let shaped = shape_text(font_context, &self.state.font_style, &text, direction);
+
+        // All vertical distances are measured from the baseline selected by
+        // textBaseline, positive values pointing up; horizontal ones from the
+        // alignment point selected by textAlign, positive values pointing left.
+        // `alphabetic` is the distance to the alphabetic baseline.
+        let alphabetic = -self.text_baseline_offset(&shaped) as f64;
+        let left = -self.text_align_offset(shaped.width, direction) as f64;
+        let ascent = shaped.ascent as f64;
+        let descent = shaped.descent as f64;
+        let em_ascent = shaped.em_size as f64 * ascent / (ascent + descent).max(1.);
+
+        // FIXME: The actual bounding box is the one of the whole text rather
+        // than of its glyph outlines, which the font stack doesn't expose.
+        let metrics = TextMetrics {
+            width: shaped.width as f64,
+            actual_bounding_box_left: left,
+            actual_bounding_box_right: shaped.width as f64 - left,
+            font_bounding_box_ascent: ascent + alphabetic,
+            font_bounding_box_descent: descent - alphabetic,
+            actual_bounding_box_ascent: ascent + alphabetic,
+            actual_bounding_box_descent: descent - alphabetic,
+            em_height_ascent: em_ascent + alphabetic,
+            em_height_descent: shaped.em_size as f64 - em_ascent - alphabetic,
+            hanging_baseline: HANGING_BASELINE_RATIO * ascent + alphabetic,
+            alphabetic_baseline: alphabetic,
+            ideographic_baseline: alphabetic - descent,
+        };
+        chan.send(metrics).unwrap();
+    }
+
+    /// The horizontal offset from the anchor point to the start of the text.
+    fn text_align_offset(&self, width: f32, direction: Direction) -> f32 {
+        match (self.state.text_align, direction) {
+            (TextAlign::Left, _) |
+            (TextAlign::Start, Direction::Ltr) |
+            (TextAlign::End, Direction::Rtl) => 0.,
+            (TextAlign::Right, _) |
+            (TextAlign::End, Direction::Ltr) |
+            (TextAlign::Start, Direction::Rtl) => -width,
+            (TextAlign::Center, _) => -width / 2.,
+        }
+    }
+
+    /// The vertical offset from the anchor point to the alphabetic baseline.
+    fn text_baseline_offset(&self, shaped: &ShapedText) -> f32 {
+        match self.state.text_baseline {
+            TextBaseline::Top => shaped.ascent,
+            TextBaseline::Hanging => shaped.ascent * HANGING_BASELINE_RATIO as f32,
+            TextBaseline::Middle => (shaped.ascent - shaped.descent) / 2.,
+            TextBaseline::Alphabetic => 0.,
+            TextBaseline::Ideographic |
+            TextBaseline::Bottom => -shaped.descent,
+        }
     }
 
     pub fn fill_rect(&self, rect: &Rect<f32>) {
@@ -136,15 +324,9 @@ impl<'a> CanvasData<'a> {
             }
         );
 
-        if self.need_to_draw_shadow() {
-            self.draw_with_shadow(&draw_rect, |new_draw_target: &DrawTarget| {
-                new_draw_target.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(),
-                                          Some(&self.state.draw_options));
-            });
-        } else {
-            self.drawtarget.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(),
-                                      Some(&self.state.draw_options));
-        }
+        self.draw_with_effects(|draw_target: &DrawTarget, draw_options: &DrawOptions| {
+            draw_target.fill_rect(&draw_rect, self.state.fill_style.to_pattern_ref(), Some(draw_options));
+        });
     }
 
     pub fn clear_rect(&self, rect: &Rect<f32>) {
@@ -156,30 +338,27 @@ impl<'a> CanvasData<'a> {
             return; // Paint nothing if gradient size is zero.
         }
 
-        if self.need_to_draw_shadow() {
-            self.draw_with_shadow(&rect, |new_draw_target: &DrawTarget| {
-                new_draw_target.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
-                                            &self.state.stroke_opts, &self.state.draw_options);
-            });
-        } else if rect.size.width == 0. || rect.size.height == 0. {
-            let cap = match self.state.stroke_opts.line_join {
-                JoinStyle::Round => CapStyle::Round,
-                _ => CapStyle::Butt
-            };
-
-            let stroke_opts =
-                StrokeOptions::new(self.state.stroke_opts.line_width,
-                                   self.state.stroke_opts.line_join,
-                                   cap,
-                                   self.state.stroke_opts.miter_limit,
-                                   self.state.stroke_opts.mDashPattern);
-            self.drawtarget.stroke_line(rect.origin, rect.bottom_right(),
+        self.draw_with_effects(|draw_target: &DrawTarget, draw_options: &DrawOptions| {
+            if rect.size.width == 0. || rect.size.height == 0. {
+                let cap = match self.state.stroke_opts.line_join {
+                    JoinStyle::Round => CapStyle::Round,
+                    _ => CapStyle::Butt
+                };
+
+                let stroke_opts =
+                    StrokeOptions::new(self.state.stroke_opts.line_width,
+                                       self.state.stroke_opts.line_join,
+                                       cap,
+                                       self.state.stroke_opts.miter_limit,
+                                       self.state.stroke_opts.mDashPattern);
+                draw_target.stroke_line(rect.origin, rect.bottom_right(),
                                         self.state.stroke_style.to_pattern_ref(),
-                                        &stroke_opts, &self.state.draw_options);
-        } else {
-            self.drawtarget.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
-                                        &self.state.stroke_opts, &self.state.draw_options);
-        }
+                                        &stroke_opts, draw_options);
+            } else {
+                draw_target.stroke_rect(rect, self.state.stroke_style.to_pattern_ref(),
+                                        &self.state.stroke_opts, draw_options);
+            }
+        });
     }
 
     pub fn begin_path(&mut self) {
@@ -190,44 +369,122 @@ impl<'a> CanvasData<'a> {
         self.path_builder.close()
     }
 
-    pub fn fill(&self) {
+    pub fn fill(&mut self, fill_rule: FillRule) {
+        let path = self.current_path(fill_rule);
+        self.fill_path(&path);
+    }
+
+    pub fn fill_path2d(&self, segments: Vec<PathSegment>, fill_rule: FillRule) {
+        let path = self.path_from_segments(&segments, fill_rule);
+        self.fill_path(&path);
+    }
+
+    fn fill_path(&self, path: &Path) {
         if is_zero_size_gradient(&self.state.fill_style) {
             return; // Paint nothing if gradient size is zero.
         }
 
-        self.drawtarget.fill(&self.path_builder.finish(),
-                             self.state.fill_style.to_pattern_ref(),
-                             &self.state.draw_options);
+        self.draw_with_effects(|draw_target: &DrawTarget, draw_options: &DrawOptions| {
+            draw_target.fill(path, self.state.fill_style.to_pattern_ref(), draw_options);
+        });
+    }
+
+    pub fn stroke(&mut self) {
+        let path = self.current_path(FillRule::Nonzero);
+        self.stroke_path(&path);
     }
 
-    pub fn stroke(&self) {
+    pub fn stroke_path2d(&self, segments: Vec<PathSegment>) {
+        let path = self.path_from_segments(&segments, FillRule::Nonzero);
+        self.stroke_path(&path);
+    }
+
+    fn stroke_path(&self, path: &Path) {
         if is_zero_size_gradient(&self.state.stroke_style) {
             return; // Paint nothing if gradient size is zero.
         }
 
-        self.drawtarget.stroke(&self.path_builder.finish(),
+        self.draw_with_effects(|draw_target: &DrawTarget, draw_options: &DrawOptions| {
+            draw_target.stroke(path,
                                self.state.stroke_style.to_pattern_ref(),
                                &self.state.stroke_opts,
-                               &self.state.draw_options);
+                               draw_options);
+        });
+    }
+
+    pub fn clip(&mut self, fill_rule: FillRule) {
+        let path = self.current_path(fill_rule);
+        self.drawtarget.push_clip(&path);
     }
 
-    pub fn clip(&self) {
-        self.drawtarget.push_clip(&self.path_builder.finish());
+    pub fn clip_path2d(&self, segments: Vec<PathSegment>, fill_rule: FillRule) {
+        let path = self.path_from_segments(&segments, fill_rule);
+        self.drawtarget.push_clip(&path);
     }
 
     pub fn is_point_in_path(
         &mut self,
         x: f64,
         y: f64,
-        _fill_rule: FillRule,
+        fill_rule: FillRule,
         chan: IpcSender<bool>
     ) {
-        let path = self.path_builder.finish();
+        let path = self.current_path(fill_rule);
         let result = path.contains_point(x, y, &self.state.transform);
-        self.path_builder = path.copy_to_builder();
         chan.send(result).unwrap();
     }
 
+    pub fn is_point_in_path2d(
+        &self,
+        segments: Vec<PathSegment>,
+        x: f64,
+        y: f64,
+        fill_rule: FillRule,
+        chan: IpcSender<bool>
+    ) {
+        let path = self.path_from_segments(&segments, fill_rule);
+        let result = path.contains_point(x, y, &self.state.transform);
+        chan.send(result).unwrap();
+    }
+
+    pub fn is_point_in_stroke(&mut self, x: f64, y: f64, chan: IpcSender<bool>) {
+        let path = self.current_path(FillRule::Nonzero);
+        let result = path.stroke_contains_point(x, y, &self.state.stroke_opts, &self.state.transform);
+        chan.send(result).unwrap();
+    }
+
+    pub fn is_point_in_stroke2d(
+        &self,
+        segments: Vec<PathSegment>,
+        x: f64,
+        y: f64,
+        chan: IpcSender<bool>
+    ) {
+        let path = self.path_from_segments(&segments, FillRule::Nonzero);
+        let result = path.stroke_contains_point(x, y, &self.state.stroke_opts, &self.state.transform);
+        chan.send(result).unwrap();
+    }
+
+    /// Returns the current default path with the given fill rule. The path
+    /// builder is replaced so that further segments extend the same path.
+    fn current_path(&mut self, fill_rule: FillRule) -> Path {
+        let path = self.path_builder.finish();
+        self.path_builder = path.copy_to_builder();
+        path.copy_to_builder_with_fill_rule(fill_rule.to_azure_style()).finish()
+    }
+
+    /// Builds the path held by a `Path2D` object.
+    fn path_from_segments(&self, segments: &[PathSegment], fill_rule: FillRule) -> Path {
+        let path_builder = self.drawtarget.create_path_builder_with_fill_rule(fill_rule.to_azure_style());
+        {
+            let mut segment_builder = PathSegmentBuilder::new(&path_builder);
+            for segment in segments {
+                segment_builder.add(segment);
+            }
+        }
+        path_builder.finish()
+    }
+
     pub fn move_to(&self, point: &Point2D<AzFloat>) {
         self.path_builder.move_to(*point)
     }
@@ -280,53 +537,10 @@ impl<'a> CanvasData<'a> {
         radius: AzFloat
     ) {
         let cp0 = self.path_builder.get_current_point();
-        let cp1 = *cp1;
-        let cp2 = *cp2;
-
-        if (cp0.x == cp1.x && cp0.y == cp1.y) || cp1 == cp2 || radius == 0.0 {
-            self.line_to(&cp1);
-            return;
-        }
-
-        // if all three control points lie on a single straight line,
-        // connect the first two by a straight line
-        let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
-        if direction == 0.0 {
-            self.line_to(&cp1);
-            return;
-        }
-
-        // otherwise, draw the Arc
-        let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
-        let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
-        let d = {
-            let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
-            let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
-            let sinx = (1.0 - cosx.powi(2)).sqrt();
-            radius / ((1.0 - cosx) / sinx)
-        };
-
-        // first tangent point
-        let anx = (cp1.x - cp0.x) / a2.sqrt();
-        let any = (cp1.y - cp0.y) / a2.sqrt();
-        let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
-
-        // second tangent point
-        let bnx = (cp1.x - cp2.x) / b2.sqrt();
-        let bny = (cp1.y - cp2.y) / b2.sqrt();
-        let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
-
-        // arc center and angles
-        let anticlockwise = direction < 0.0;
-        let cx = tp1.x + any * radius * if anticlockwise { 1.0 } else { -1.0 };
-        let cy = tp1.y - anx * radius * if anticlockwise { 1.0 } else { -1.0 };
-        let angle_start = (tp1.y - cy).atan2(tp1.x - cx);
-        let angle_end = (tp2.y - cy).atan2(tp2.x - cx);
-
-        self.line_to(&tp1);
-        if [cx, cy, angle_start, angle_end].iter().all(|x| x.is_finite()) {
-            self.arc(&Point2D::new(cx, cy), radius,
-                     angle_start, angle_end, anticlockwise);
+        let (tangent_point, arc) = arc_to_geometry(cp0, *cp1, *cp2, radius);
+        self.line_to(&tangent_point);
+        if let Some((center, angle_start, angle_end, anticlockwise)) = arc {
+            self.arc(&center, radius, angle_start, angle_end, anticlockwise);
         }
     }
 
@@ -384,12 +598,25 @@ impl<'a> CanvasData<'a> {
         self.state.draw_options.set_composition_op(op.to_azure_style());
     }
 
+    pub fn set_font(&mut self, font: CanvasFontStyle) {
+        self.state.font_style = ServoArc::new(font.to_style_struct());
+    }
+
+    pub fn set_text_align(&mut self, text_align: TextAlign) {
+        self.state.text_align = text_align;
+    }
+
+    pub fn set_text_baseline(&mut self, text_baseline: TextBaseline) {
+        self.state.text_baseline = text_baseline;
+    }
+
     pub fn create(size: Size2D<i32>) -> DrawTarget {
         DrawTarget::new(BackendType::Skia, size, SurfaceFormat::B8G8R8A8)
     }
 
     pub fn recreate(&mut self, size: Size2D<i32>) {
         self.drawtarget = CanvasData::create(size);
+        *self.effects_layer.borrow_mut() = None;
         self.state = CanvasPaintState::new(self.state.draw_options.antialias);
         self.saved_states.clear();
         // Webrender doesn't let images change size, so we clear the webrender image key.
@@ -580,31 +807,71 @@ impl<'a> CanvasData<'a> {
          self.state.shadow_blur != 0.0f64)
     }
 
-    fn create_draw_target_for_shadow(&self, source_rect: &Rect<f32>) -> DrawTarget {
-        let draw_target = self.drawtarget.create_similar_draw_target(&Size2D::new(source_rect.size.width as i32,
-                                                                                  source_rect.size.height as i32),
-                                                                     self.drawtarget.get_format());
-        let matrix = Transform2D::identity()
-            .pre_translate(-source_rect.origin.to_vector().cast())
-            .pre_mul(&self.state.transform);
-        draw_target.set_transform(&matrix);
-        draw_target
+    pub fn set_filter(&mut self, filters: Vec<FilterFunction>) {
+        self.state.filters = filters;
     }
 
-    fn draw_with_shadow<F>(&self, rect: &Rect<f32>, draw_shadow_source: F)
-        where F: FnOnce(&DrawTarget)
+    /// Runs a drawing operation following the drawing model: when shadows,
+    /// filters or global alpha have to be applied to its result as a whole,
+    /// the operation draws on the effects layer, which is then composited
+    /// onto the canvas. Otherwise it draws on the canvas directly.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drawing-model>
+    fn draw_with_effects<F>(&self, draw: F)
+        where F: FnOnce(&DrawTarget, &DrawOptions)
     {
-        let shadow_src_rect = self.state.transform.transform_rect(rect);
-        let new_draw_target = self.create_draw_target_for_shadow(&shadow_src_rect);
-        draw_shadow_source(&new_draw_target);
-        self.drawtarget.draw_surface_with_shadow(new_draw_target.snapshot(),
-                                                 &Point2D::new(shadow_src_rect.origin.x as AzFloat,
-                                                               shadow_src_rect.origin.y as AzFloat),
-                                                 &self.state.shadow_color,
-                                                 &Vector2D::new(self.state.shadow_offset_x as AzFloat,
-                                                                self.state.shadow_offset_y as AzFloat),
-                                                 (self.state.shadow_blur / 2.0f64) as AzFloat,
-                                                 self.state.draw_options.composition);
+        let draw_shadow = self.need_to_draw_shadow();
+        if !draw_shadow && self.state.filters.is_empty() {
+            return draw(&self.drawtarget, &self.state.draw_options);
+        }
+
+        let mut effects_layer = self.effects_layer.borrow_mut();
+        let layer = effects_layer.get_or_insert_with(|| {
+            self.drawtarget.create_similar_draw_target(&self.drawtarget.get_size(), self.drawtarget.get_format())
+        });
+        let size = self.drawtarget.get_size();
+        let bounds = Rect::new(Point2D::zero(), Size2D::new(size.width as AzFloat, size.height as AzFloat));
+
+        // Step 1: render the operation to a cleared bitmap, with the current
+        // transform but without global alpha or compositing.
+        layer.set_transform(&Transform2D::identity());
+        layer.clear_rect(&bounds);
+        layer.set_transform(&self.state.transform);
+        draw(layer, &DrawOptions::new(1.0, CompositionOp::Over, self.state.draw_options.antialias));
+        layer.set_transform(&Transform2D::identity());
+
+        // Step 2: apply the filter, then global alpha, in that order.
+        let mut source = layer.snapshot();
+        let global_alpha = FilterFunction::Opacity(self.state.draw_options.alpha);
+        let functions = self.state.filters.iter().chain(Some(&global_alpha).filter(|_| {
+            self.state.draw_options.alpha != 1.0
+        }));
+        for function in functions {
+            layer.clear_rect(&bounds);
+            draw_filtered(layer, source, function, &bounds);
+            source = layer.snapshot();
+        }
+
+        // Steps 3-8: render the shadow, then composite both on the canvas.
+        self.drawtarget.set_transform(&Transform2D::identity());
+        if draw_shadow {
+            self.drawtarget.draw_surface_with_shadow(source,
+                                                     &Point2D::zero(),
+                                                     &self.state.shadow_color,
+                                                     &Vector2D::new(self.state.shadow_offset_x as AzFloat,
+                                                                    self.state.shadow_offset_y as AzFloat),
+                                                     (self.state.shadow_blur / 2.0f64) as AzFloat,
+                                                     self.state.draw_options.composition);
+        } else {
+            self.drawtarget.draw_surface(source,
+                                         bounds,
+                                         bounds,
+                                         DrawSurfaceOptions::new(Filter::Point, true),
+                                         DrawOptions::new(1.0,
+                                                          self.state.draw_options.composition,
+                                                          AntialiasMode::None));
+        }
+        self.drawtarget.set_transform(&self.state.transform);
     }
 
     /// It reads image data from the canvas
@@ -665,6 +932,10 @@ struct CanvasPaintState<'a> {
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: Color,
+    filters: Vec<FilterFunction>,
+    font_style: ServoArc<FontStyleStruct>,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
 }
 
 impl<'a> CanvasPaintState<'a> {
@@ -679,10 +950,369 @@ impl<'a> CanvasPaintState<'a> {
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: Color::transparent(),
+            filters: vec![],
+            font_style: ServoArc::new(default_font_style()),
+            text_align: TextAlign::default(),
+            text_baseline: TextBaseline::default(),
         }
     }
 }
 
+/// The initial value of the `font` attribute, `10px sans-serif`.
+fn default_font_style() -> FontStyleStruct {
+    CanvasFontStyle {
+        font_family: vec![SingleFontFamily::Generic(atom!("sans-serif"))],
+        font_style: FontStyle::Normal,
+        font_variant_caps: FontVariantCaps::Normal,
+        font_weight: FontWeight::normal(),
+        font_size: Au::from_px(10),
+        font_stretch: FontStretch::hundred(),
+    }.to_style_struct()
+}
+
+/// The position of the hanging baseline relative to the font ascent.
+///
+/// FIXME: This should be read from the `BASE` table of the font, which the
+/// font stack doesn't parse.
+const HANGING_BASELINE_RATIO: f64 = 0.8;
+
+#[derive(Clone, Copy)]
+enum TextPaintOp {
+    Fill,
+    Stroke,
+}
+
+/// Text shaped for drawing on a canvas, split into runs of glyphs that share
+/// a font. Glyph positions are relative to the start of the alphabetic
+/// baseline of the whole text.
+struct ShapedText {
+    runs: Vec<ShapedRun>,
+    width: f32,
+    ascent: f32,
+    descent: f32,
+    em_size: f32,
+}
+
+struct ShapedRun {
+    font: FontRef,
+    glyphs: Vec<(GlyphId, Point2D<f32>)>,
+}
+
+/// A font scaled for drawing, with the data it was created from.
+struct CanvasScaledFont {
+    scaled_font: ScaledFont,
+    /// Dropped after `scaled_font`, which may refer to it.
+    _font_data: Vec<u8>,
+}
+
+impl ShapedRun {
+    fn glyph_path(
+        &self,
+        scaled_fonts: &mut HashMap<(Atom, Au), CanvasScaledFont>,
+        draw_target: &DrawTarget,
+        origin: Point2D<f32>,
+    ) -> Path {
+        let font = self.font.borrow();
+        let template = font.handle.template();
+        let key = (template.identifier.clone(), font.actual_pt_size);
+        let scaled_font = scaled_fonts.entry(key).or_insert_with(|| {
+            let font_data = template.bytes();
+            CanvasScaledFont {
+                scaled_font: ScaledFont::new(BackendType::Skia,
+                                             FontInfo::FontData(&font_data),
+                                             font.actual_pt_size.to_f32_px()),
+                _font_data: font_data,
+            }
+        });
+        let glyphs: Vec<AzGlyph> = self.glyphs.iter().map(|&(id, position)| {
+            AzGlyph {
+                mIndex: id,
+                mPosition: AzPoint {
+                    x: origin.x + position.x,
+                    y: origin.y + position.y,
+                },
+            }
+        }).collect();
+        scaled_font.scaled_font.get_path_for_glyphs(&glyphs, draw_target)
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/#text-preparation-algorithm
+// Step 2: replace all ASCII whitespace with U+0020 SPACE.
+fn replace_ascii_whitespace(text: String) -> String {
+    text.chars().map(|c| if c.is_ascii_whitespace() { ' ' } else { c }).collect()
+}
+
+/// Splits `text` into runs by the font of `style` able to render each
+/// character, then shapes each of them with HarfBuzz.
+fn shape_text(
+    font_context: &mut FontContext<FontCacheThread>,
+    style: &ServoArc<FontStyleStruct>,
+    text: &str,
+    direction: Direction,
+) -> ShapedText {
+    let font_group = font_context.font_group(style.clone());
+    let mut font_group = font_group.borrow_mut();
+
+    let mut text_runs: Vec<(FontRef, String)> = vec![];
+    for ch in text.chars() {
+        let font = match font_group.find_by_codepoint(font_context, ch) {
+            Some(font) => font,
+            None => continue,
+        };
+        let same_font = match text_runs.last() {
+            Some(&(ref last_font, _)) => Rc::ptr_eq(last_font, &font),
+            None => false,
+        };
+        if same_font {
+            text_runs.last_mut().unwrap().1.push(ch);
+        } else {
+            text_runs.push((font, ch.to_string()));
+        }
+    }
+
+    let mut flags = ShapingFlags::empty();
+    if direction == Direction::Rtl {
+        // Runs are laid out visually, so right-to-left text starts with the
+        // last logical run.
+        flags.insert(ShapingFlags::RTL_FLAG);
+        text_runs.reverse();
+    }
+    let options = ShapingOptions {
+        letter_spacing: None,
+        word_spacing: (Au(0), NotNan::new(0.).unwrap()),
+        script: Script::Common,
+        flags: flags,
+    };
+
+    let (mut ascent, mut descent, mut em_size) = (Au(0), Au(0), Au(0));
+    let mut advance = Au(0);
+    let mut runs = vec![];
+    for (font, run_text) in text_runs {
+        let glyphs = {
+            let mut font = font.borrow_mut();
+            ascent = ascent.max(font.metrics.ascent);
+            descent = descent.max(font.metrics.descent);
+            em_size = em_size.max(font.metrics.em_size);
+
+            let store = font.shape_text(&run_text, &options);
+            let mut glyphs = vec![];
+            for glyph in store.iter_glyphs_for_byte_range(&Range::new(ByteIndex(0), store.len())) {
+                let offset = glyph.offset().unwrap_or(Point2D::zero());
+                let position = Point2D::new((advance + offset.x).to_f32_px(),
+                                            offset.y.to_f32_px());
+                glyphs.push((glyph.id(), position));
+                advance = advance + glyph.advance();
+            }
+            glyphs
+        };
+        runs.push(ShapedRun { font: font, glyphs: glyphs });
+    }
+
+    ShapedText {
+        runs: runs,
+        width: advance.to_f32_px(),
+        ascent: ascent.to_f32_px(),
+        descent: descent.to_f32_px(),
+        em_size: em_size.to_f32_px(),
+    }
+}
+
+/// Computes the segments appended by `arcTo` after the point `cp0`: a line to
+/// the returned point, followed by the returned arc (center, start angle, end
+/// angle and direction) unless it degenerates into that line.
+fn arc_to_geometry(
+    cp0: Point2D<AzFloat>,
+    cp1: Point2D<AzFloat>,
+    cp2: Point2D<AzFloat>,
+    radius: AzFloat
+) -> (Point2D<AzFloat>, Option<(Point2D<AzFloat>, AzFloat, AzFloat, bool)>) {
+    if (cp0.x == cp1.x && cp0.y == cp1.y) || cp1 == cp2 || radius == 0.0 {
+        return (cp1, None);
+    }
+
+    // if all three control points lie on a single straight line,
+    // connect the first two by a straight line
+    let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
+    if direction == 0.0 {
+        return (cp1, None);
+    }
+
+    // otherwise, draw the Arc
+    let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
+    let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
+    let d = {
+        let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
+        let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
+        let sinx = (1.0 - cosx.powi(2)).sqrt();
+        radius / ((1.0 - cosx) / sinx)
+    };
+
+    // first tangent point
+    let anx = (cp1.x - cp0.x) / a2.sqrt();
+    let any = (cp1.y - cp0.y) / a2.sqrt();
+    let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
+
+    // second tangent point
+    let bnx = (cp1.x - cp2.x) / b2.sqrt();
+    let bny = (cp1.y - cp2.y) / b2.sqrt();
+    let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
+
+    // arc center and angles
+    let anticlockwise = direction < 0.0;
+    let cx = tp1.x + any * radius * if anticlockwise { 1.0 } else { -1.0 };
+    let cy = tp1.y - anx * radius * if anticlockwise { 1.0 } else { -1.0 };
+    let angle_start = (tp1.y - cy).atan2(tp1.x - cx);
+    let angle_end = (tp2.y - cy).atan2(tp2.x - cx);
+
+    if [cx, cy, angle_start, angle_end].iter().all(|x| x.is_finite()) {
+        (tp1, Some((Point2D::new(cx, cy), angle_start, angle_end, anticlockwise)))
+    } else {
+        (tp1, None)
+    }
+}
+
+/// Replays the segments of a `Path2D` into an azure `PathBuilder`, mapping
+/// their points through the transforms set by `PathSegment::Transform`.
+struct PathSegmentBuilder<'a> {
+    path_builder: &'a PathBuilder,
+    transform: Transform2D<AzFloat>,
+    /// The last point of the path, before transformation.
+    current_point: Option<Point2D<AzFloat>>,
+    /// The first point of the current subpath, before transformation.
+    subpath_start: Point2D<AzFloat>,
+}
+
+impl<'a> PathSegmentBuilder<'a> {
+    fn new(path_builder: &'a PathBuilder) -> PathSegmentBuilder<'a> {
+        PathSegmentBuilder {
+            path_builder: path_builder,
+            transform: Transform2D::identity(),
+            current_point: None,
+            subpath_start: Point2D::zero(),
+        }
+    }
+
+    fn add(&mut self, segment: &PathSegment) {
+        match *segment {
+            PathSegment::ClosePath => {
+                self.path_builder.close();
+                self.current_point = Some(self.subpath_start);
+            },
+            PathSegment::MoveTo(point) => self.move_to(point),
+            PathSegment::LineTo(point) => self.line_to(point),
+            PathSegment::QuadraticCurveTo(cp, point) => {
+                self.ensure_subpath(cp);
+                self.path_builder.quadratic_curve_to(&self.transform.transform_point(&cp),
+                                                     &self.transform.transform_point(&point));
+                self.current_point = Some(point);
+            },
+            PathSegment::BezierCurveTo(cp1, cp2, point) => {
+                self.ensure_subpath(cp1);
+                self.bezier_curve_to(cp1, cp2, point);
+            },
+            PathSegment::ArcTo(cp1, cp2, radius) => {
+                self.ensure_subpath(cp1);
+                let cp0 = self.current_point.unwrap_or(cp1);
+                let (tangent_point, arc) = arc_to_geometry(cp0, cp1, cp2, radius);
+                self.line_to(tangent_point);
+                if let Some((center, start, end, anticlockwise)) = arc {
+                    self.ellipse(center, radius, radius, 0., start, end, anticlockwise);
+                }
+            },
+            PathSegment::Ellipse(center, radius_x, radius_y, rotation, start, end, anticlockwise) => {
+                self.ellipse(center, radius_x, radius_y, rotation, start, end, anticlockwise);
+            },
+            PathSegment::Transform(ref transform) => self.transform = *transform,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#ensure-there-is-a-subpath
+    fn ensure_subpath(&mut self, point: Point2D<AzFloat>) {
+        if self.current_point.is_none() {
+            self.move_to(point);
+        }
+    }
+
+    fn move_to(&mut self, point: Point2D<AzFloat>) {
+        self.path_builder.move_to(self.transform.transform_point(&point));
+        self.current_point = Some(point);
+        self.subpath_start = point;
+    }
+
+    fn line_to(&mut self, point: Point2D<AzFloat>) {
+        if self.current_point.is_none() {
+            return self.move_to(point);
+        }
+        self.path_builder.line_to(self.transform.transform_point(&point));
+        self.current_point = Some(point);
+    }
+
+    fn bezier_curve_to(&mut self, cp1: Point2D<AzFloat>, cp2: Point2D<AzFloat>, point: Point2D<AzFloat>) {
+        self.path_builder.bezier_curve_to(&self.transform.transform_point(&cp1),
+                                          &self.transform.transform_point(&cp2),
+                                          &self.transform.transform_point(&point));
+        self.current_point = Some(point);
+    }
+
+    /// Appends an elliptical arc, approximated with cubic Bézier curves so
+    /// that it can be mapped through an arbitrary transform.
+    fn ellipse(
+        &mut self,
+        center: Point2D<AzFloat>,
+        radius_x: AzFloat,
+        radius_y: AzFloat,
+        rotation: AzFloat,
+        start_angle: AzFloat,
+        end_angle: AzFloat,
+        anticlockwise: bool,
+    ) {
+        let sweep = ellipse_sweep(start_angle, end_angle, anticlockwise);
+        let (sin_rotation, cos_rotation) = rotation.sin_cos();
+        let point_at = |x: AzFloat, y: AzFloat| {
+            let (x, y) = (x * radius_x, y * radius_y);
+            Point2D::new(center.x + x * cos_rotation - y * sin_rotation,
+                         center.y + x * sin_rotation + y * cos_rotation)
+        };
+
+        let start = point_at(start_angle.cos(), start_angle.sin());
+        self.line_to(start);
+
+        let pieces = (sweep.abs() / (PI / 2.)).ceil().max(1.);
+        let delta = sweep / pieces;
+        let k = 4. / 3. * (delta / 4.).tan();
+        let mut angle = start_angle;
+        for _ in 0..pieces as u32 {
+            let (sin0, cos0) = angle.sin_cos();
+            let (sin1, cos1) = (angle + delta).sin_cos();
+            self.bezier_curve_to(point_at(cos0 - k * sin0, sin0 + k * cos0),
+                                 point_at(cos1 + k * sin1, sin1 - k * cos1),
+                                 point_at(cos1, sin1));
+            angle += delta;
+        }
+    }
+}
+
+/// The signed angle swept by an arc from `start_angle` to `end_angle`.
+/// <https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse>
+fn ellipse_sweep(start_angle: AzFloat, end_angle: AzFloat, anticlockwise: bool) -> AzFloat {
+    if !anticlockwise && end_angle - start_angle >= 2. * PI {
+        return 2. * PI;
+    }
+    if anticlockwise && start_angle - end_angle >= 2. * PI {
+        return -2. * PI;
+    }
+
+    let mut sweep = (end_angle - start_angle) % (2. * PI);
+    if !anticlockwise && sweep < 0. {
+        sweep += 2. * PI;
+    }
+    if anticlockwise && sweep > 0. {
+        sweep -= 2. * PI;
+    }
+    sweep
+}
+
 fn is_zero_size_gradient(pattern: &Pattern) -> bool {
     if let &Pattern::LinearGradient(ref gradient) = pattern {
         if gradient.is_zero_size() {
@@ -730,13 +1360,13 @@ fn crop_image(
 /// image_data: Pixel information of the image to be written. It takes RGBA8
 /// image_size: The size of the image to be written
 /// dest_rect: Area of the destination target where the pixels will be copied
-/// smoothing_enabled: It determines if smoothing is applied to the image result
+/// smoothing: The quality of the smoothing applied to the image result, if any
 fn write_image(
     draw_target: &DrawTarget,
     mut image_data: Vec<u8>,
     image_size: Size2D<f64>,
     dest_rect: Rect<f64>,
-    smoothing_enabled: bool,
+    smoothing: Option<SmoothingQuality>,
     composition_op: CompositionOp,
     global_alpha: f32
 ) {
@@ -751,10 +1381,10 @@ fn write_image(
     // When scaling up, if the imageSmoothingEnabled attribute is set to true, the user agent should attempt
     // to apply a smoothing algorithm to the image data when it is scaled.
     // Otherwise, the image must be rendered using nearest-neighbor interpolation.
-    let filter = if smoothing_enabled {
-        Filter::Linear
-    } else {
-        Filter::Point
+    let filter = match smoothing {
+        Some(SmoothingQuality::Low) => Filter::Linear,
+        Some(SmoothingQuality::Medium) | Some(SmoothingQuality::High) => Filter::Good,
+        None => Filter::Point,
     };
     // azure_hl operates with integers. We need to cast the image size
     let image_size = image_size.to_i32();
@@ -775,6 +1405,109 @@ fn write_image(
     }
 }
 
+/// Draws `source` on `draw_target` through a filter function.
+///
+/// <https://drafts.fxtf.org/filter-effects/#supported-filter-functions>
+fn draw_filtered(
+    draw_target: &DrawTarget,
+    source: SourceSurface,
+    function: &FilterFunction,
+    bounds: &Rect<AzFloat>,
+) {
+    let filter = match *function {
+        FilterFunction::Blur(std_deviation) => {
+            let blur = draw_target.create_filter(FilterType::GaussianBlur);
+            blur.set_attribute(GaussianBlurAttribute::StdDeviation(std_deviation));
+            blur.set_input(GaussianBlurInput, &source);
+            blur
+        },
+        FilterFunction::DropShadow(offset, blur_radius, color) => {
+            draw_target.draw_surface_with_shadow(source,
+                                                 &Point2D::zero(),
+                                                 &color.to_azure_style(),
+                                                 &offset,
+                                                 blur_radius / 2.,
+                                                 CompositionOp::Over);
+            return;
+        },
+        ref function => {
+            let color_matrix = draw_target.create_filter(FilterType::ColorMatrix);
+            color_matrix.set_attribute(ColorMatrixAttribute::Matrix(filter_color_matrix(function)));
+            color_matrix.set_input(ColorMatrixInput, &source);
+            color_matrix
+        },
+    };
+    draw_target.draw_filter(&filter,
+                            bounds,
+                            &Point2D::zero(),
+                            DrawOptions::new(1.0, CompositionOp::Over, AntialiasMode::None));
+}
+
+/// The color matrix of a filter function that is a linear transformation
+/// of colors, as defined by the equivalent SVG filters.
+fn filter_color_matrix(function: &FilterFunction) -> Matrix5x4 {
+    let identity = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    match *function {
+        FilterFunction::Brightness(amount) => {
+            rgb_color_matrix([[amount, 0., 0.], [0., amount, 0.], [0., 0., amount]], 0., 1.)
+        },
+        FilterFunction::Contrast(amount) => {
+            rgb_color_matrix([[amount, 0., 0.], [0., amount, 0.], [0., 0., amount]], 0.5 - 0.5 * amount, 1.)
+        },
+        FilterFunction::Grayscale(amount) => {
+            let s = 1. - amount;
+            rgb_color_matrix([
+                [0.2126 + 0.7874 * s, 0.7152 - 0.7152 * s, 0.0722 - 0.0722 * s],
+                [0.2126 - 0.2126 * s, 0.7152 + 0.2848 * s, 0.0722 - 0.0722 * s],
+                [0.2126 - 0.2126 * s, 0.7152 - 0.7152 * s, 0.0722 + 0.9278 * s],
+            ], 0., 1.)
+        },
+        FilterFunction::HueRotate(angle) => {
+            let (s, c) = angle.sin_cos();
+            rgb_color_matrix([
+                [0.213 + c * 0.787 - s * 0.213, 0.715 - c * 0.715 - s * 0.715, 0.072 - c * 0.072 + s * 0.928],
+                [0.213 - c * 0.213 + s * 0.143, 0.715 + c * 0.285 + s * 0.140, 0.072 - c * 0.072 - s * 0.283],
+                [0.213 - c * 0.213 - s * 0.787, 0.715 - c * 0.715 + s * 0.715, 0.072 + c * 0.928 + s * 0.072],
+            ], 0., 1.)
+        },
+        FilterFunction::Invert(amount) => {
+            let slope = 1. - 2. * amount;
+            rgb_color_matrix([[slope, 0., 0.], [0., slope, 0.], [0., 0., slope]], amount, 1.)
+        },
+        FilterFunction::Opacity(amount) => rgb_color_matrix(identity, 0., amount),
+        FilterFunction::Saturate(s) => {
+            rgb_color_matrix([
+                [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+                [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+                [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+            ], 0., 1.)
+        },
+        FilterFunction::Sepia(amount) => {
+            let s = 1. - amount;
+            rgb_color_matrix([
+                [0.393 + 0.607 * s, 0.769 - 0.769 * s, 0.189 - 0.189 * s],
+                [0.349 - 0.349 * s, 0.686 + 0.314 * s, 0.168 - 0.168 * s],
+                [0.272 - 0.272 * s, 0.534 - 0.534 * s, 0.131 + 0.869 * s],
+            ], 0., 1.)
+        },
+        FilterFunction::Blur(..) | FilterFunction::DropShadow(..) => {
+            unreachable!("Not a color matrix filter function")
+        },
+    }
+}
+
+/// Builds a color matrix mapping each color channel through a row of `rgb`
+/// plus `offset`, and scaling alpha by `alpha`.
+fn rgb_color_matrix(rgb: [[AzFloat; 3]; 3], offset: AzFloat, alpha: AzFloat) -> Matrix5x4 {
+    Matrix5x4 {
+        m11: rgb[0][0], m12: rgb[1][0], m13: rgb[2][0], m14: 0.,
+        m21: rgb[0][1], m22: rgb[1][1], m23: rgb[2][1], m24: 0.,
+        m31: rgb[0][2], m32: rgb[1][2], m33: rgb[2][2], m34: 0.,
+        m41: 0., m42: 0., m43: 0., m44: alpha,
+        m51: offset, m52: offset, m53: offset, m54: 0.,
+    }
+}
+
 pub trait PointToi32 {
     fn to_i32(&self) -> Point2D<i32>;
 }
@@ -834,6 +1567,17 @@ impl ToAzureStyle for Rect<f64> {
 }
 
 
+impl ToAzureStyle for FillRule {
+    type Target = AzureFillRule;
+
+    fn to_azure_style(self) -> AzureFillRule {
+        match self {
+            FillRule::Nonzero => AzureFillRule::Winding,
+            FillRule::Evenodd => AzureFillRule::EvenOdd,
+        }
+    }
+}
+
 impl ToAzureStyle for LineCapStyle {
     type Target = CapStyle;
 
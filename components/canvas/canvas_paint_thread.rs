@@ -6,6 +6,8 @@ use azure::azure_hl::AntialiasMode;
 use canvas_data::*;
 use canvas_traits::canvas::*;
 use euclid::Size2D;
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
 use ipc_channel::ipc::{self, IpcSender};
 use std::borrow::ToOwned;
 use std::collections::HashMap;
@@ -15,22 +17,25 @@ use webrender_api;
 pub struct CanvasPaintThread <'a> {
     canvases: HashMap<CanvasId, CanvasData<'a>>,
     next_canvas_id: CanvasId,
+    /// The font context shared by all canvases for text shaping.
+    font_context: FontContext<FontCacheThread>,
 }
 
 impl<'a> CanvasPaintThread <'a> {
-    fn new() -> CanvasPaintThread <'a> {
+    fn new(font_cache_thread: FontCacheThread) -> CanvasPaintThread <'a> {
         CanvasPaintThread {
             canvases: HashMap::new(),
             next_canvas_id: CanvasId(0),
+            font_context: FontContext::new(font_cache_thread),
         }
     }
 
     /// Creates a new `CanvasPaintThread` and returns an `IpcSender` to
     /// communicate with it.
-    pub fn start() -> IpcSender<CanvasMsg> {
+    pub fn start(font_cache_thread: FontCacheThread) -> IpcSender<CanvasMsg> {
         let (sender, receiver) = ipc::channel::<CanvasMsg>().unwrap();
         thread::Builder::new().name("CanvasThread".to_owned()).spawn(move || {
-            let mut canvas_paint_thread = CanvasPaintThread::new();
+            let mut canvas_paint_thread = CanvasPaintThread::new(font_cache_thread);
             loop {
                 match receiver.recv() {
                     Ok(msg) => {
@@ -101,8 +106,17 @@ impl<'a> CanvasPaintThread <'a> {
 
     fn process_canvas_2d_message(&mut self, message: Canvas2dMsg, canvas_id: CanvasId) {
         match message {
-            Canvas2dMsg::FillText(text, x, y, max_width) => {
-                self.canvas(canvas_id).fill_text(text, x, y, max_width)
+            Canvas2dMsg::FillText(text, x, y, max_width, direction) => {
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.fill_text(&mut self.font_context, text, x, y, max_width, direction)
+            },
+            Canvas2dMsg::StrokeText(text, x, y, max_width, direction) => {
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.stroke_text(&mut self.font_context, text, x, y, max_width, direction)
+            },
+            Canvas2dMsg::MeasureText(text, direction, chan) => {
+                let canvas = self.canvases.get_mut(&canvas_id).expect("Bogus canvas id");
+                canvas.measure_text(&mut self.font_context, text, direction, chan)
             },
             Canvas2dMsg::FillRect(ref rect) => {
                 self.canvas(canvas_id).fill_rect(rect)
@@ -119,44 +133,62 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::ClosePath => {
                 self.canvas(canvas_id).close_path()
             },
-            Canvas2dMsg::Fill => {
-                self.canvas(canvas_id).fill()
+            Canvas2dMsg::Fill(fill_rule) => {
+                self.canvas(canvas_id).fill(fill_rule)
+            },
+            Canvas2dMsg::FillPath2D(segments, fill_rule) => {
+                self.canvas(canvas_id).fill_path2d(segments, fill_rule)
             },
             Canvas2dMsg::Stroke => {
                 self.canvas(canvas_id).stroke()
             },
-            Canvas2dMsg::Clip => {
-                self.canvas(canvas_id).clip()
+            Canvas2dMsg::StrokePath2D(segments) => {
+                self.canvas(canvas_id).stroke_path2d(segments)
+            },
+            Canvas2dMsg::Clip(fill_rule) => {
+                self.canvas(canvas_id).clip(fill_rule)
+            },
+            Canvas2dMsg::ClipPath2D(segments, fill_rule) => {
+                self.canvas(canvas_id).clip_path2d(segments, fill_rule)
             },
             Canvas2dMsg::IsPointInPath(x, y, fill_rule, chan) => {
                 self.canvas(canvas_id).is_point_in_path(x, y, fill_rule, chan)
             },
+            Canvas2dMsg::IsPointInPath2D(segments, x, y, fill_rule, chan) => {
+                self.canvas(canvas_id).is_point_in_path2d(segments, x, y, fill_rule, chan)
+            },
+            Canvas2dMsg::IsPointInStroke(x, y, chan) => {
+                self.canvas(canvas_id).is_point_in_stroke(x, y, chan)
+            },
+            Canvas2dMsg::IsPointInStroke2D(segments, x, y, chan) => {
+                self.canvas(canvas_id).is_point_in_stroke2d(segments, x, y, chan)
+            },
             Canvas2dMsg::DrawImage(
                 imagedata,
                 image_size,
                 dest_rect,
                 source_rect,
-                smoothing_enabled,
+                smoothing,
             ) => {
                 self.canvas(canvas_id).draw_image(
                     imagedata.into(),
                     image_size,
                     dest_rect,
                     source_rect,
-                    smoothing_enabled,
+                    smoothing,
                 )
             },
             Canvas2dMsg::DrawImageSelf(
                 image_size,
                 dest_rect,
                 source_rect,
-                smoothing_enabled
+                smoothing
             ) => {
                 self.canvas(canvas_id).draw_image_self(
                     image_size,
                     dest_rect,
                     source_rect,
-                    smoothing_enabled
+                    smoothing
                 )
             },
             Canvas2dMsg::DrawImageInOther(
@@ -272,6 +304,18 @@ impl<'a> CanvasPaintThread <'a> {
             Canvas2dMsg::SetShadowColor(ref color) => {
                 self.canvas(canvas_id).set_shadow_color(color.to_azure_style())
             },
+            Canvas2dMsg::SetFilter(filters) => {
+                self.canvas(canvas_id).set_filter(filters)
+            },
+            Canvas2dMsg::SetFont(font) => {
+                self.canvas(canvas_id).set_font(font)
+            },
+            Canvas2dMsg::SetTextAlign(text_align) => {
+                self.canvas(canvas_id).set_text_align(text_align)
+            },
+            Canvas2dMsg::SetTextBaseline(text_baseline) => {
+                self.canvas(canvas_id).set_text_baseline(text_baseline)
+            },
         }
     }
 
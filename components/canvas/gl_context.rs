@@ -16,9 +16,10 @@ use super::webgl_thread::WebGLImpl;
 /// The GLContextFactory is used to create shared GL contexts with the main thread GL context.
 /// Currently, shared textures are used to render WebGL textures into the WR compositor.
 /// In order to create a shared context, the GLContextFactory stores the handle of the main GL context.
+/// Without such a handle, OSMesa contexts can't be shared and their frames are read back instead.
 pub enum GLContextFactory {
     Native(NativeGLContextHandle, Option<MainThreadDispatcher>),
-    OSMesa(OSMesaContextHandle),
+    OSMesa(Option<OSMesaContextHandle>),
 }
 
 impl GLContextFactory {
@@ -40,7 +41,13 @@ impl GLContextFactory {
 
     /// Creates a new GLContextFactory that uses the currently bound OSMesa context to create shared contexts.
     pub fn current_osmesa_handle() -> Option<GLContextFactory> {
-        OSMesaContext::current_handle().map(GLContextFactory::OSMesa)
+        OSMesaContext::current_handle().map(|handle| GLContextFactory::OSMesa(Some(handle)))
+    }
+
+    /// Creates a new GLContextFactory that creates standalone OSMesa contexts, for compositors
+    /// which don't render with a GL context WebGL can share textures with, such as headless ones.
+    pub fn headless_osmesa() -> GLContextFactory {
+        GLContextFactory::OSMesa(None)
     }
 
     /// Creates a new shared GLContext with the main GLContext
@@ -62,7 +69,10 @@ impl GLContextFactory {
                                                                                    dispatcher);
                 ctx.map(GLContextWrapper::Native)
             }
-            GLContextFactory::OSMesa(ref handle) => {
+            GLContextFactory::OSMesa(None) => {
+                Err("No OSMesa context to share textures with")
+            }
+            GLContextFactory::OSMesa(Some(ref handle)) => {
                 let ctx = GLContext::<OSMesaContext>::new_shared_with_dispatcher(size.to_untyped(),
                                                                                  attributes,
                                                                                  ColorAttachmentType::Texture,
@@ -166,6 +176,18 @@ impl GLContextWrapper {
         }
     }
 
+    /// The framebuffer of the drawing buffer.
+    pub fn framebuffer(&self) -> gl::GLuint {
+        match *self {
+            GLContextWrapper::Native(ref ctx) => {
+                ctx.borrow_draw_buffer().unwrap().get_framebuffer()
+            }
+            GLContextWrapper::OSMesa(ref ctx) => {
+                ctx.borrow_draw_buffer().unwrap().get_framebuffer()
+            }
+        }
+    }
+
     pub fn get_info(&self) -> (Size2D<i32>, u32, GLLimits) {
         match *self {
             GLContextWrapper::Native(ref ctx) => {
@@ -41,6 +41,20 @@ impl WebGLThreads {
         (WebGLThreads(channel), Box::new(external), output_handler.map(|b| b as Box<_>))
     }
 
+    /// Creates a new WebGLThreads object for a WebRender instance that doesn't render with GL,
+    /// so WebGL frames are read back and sent to WebRender as raw images.
+    /// Neither external images nor DOM to texture are available in this mode.
+    pub fn new_readback(gl_factory: GLContextFactory,
+                        webrender_api_sender: webrender_api::RenderApiSender,
+                        webvr_compositor: Option<Box<WebVRRenderHandler>>)
+                        -> WebGLThreads {
+        let channel = WebGLThread::start(gl_factory,
+                                         webrender_api_sender,
+                                         webvr_compositor.map(|c| WebVRRenderWrapper(c)),
+                                         PhantomData);
+        WebGLThreads(channel)
+    }
+
     /// Gets the WebGLThread handle for each script pipeline.
     pub fn pipeline(&self) -> WebGLPipeline {
         // This mode creates a single thread, so the existing WebGLChan is just cloned.
@@ -80,7 +94,10 @@ impl WebGLExternalImageApi for WebGLExternalImages {
         // The next glWaitSync call is run on the WR thread and it's used to synchronize the two
         // flows of OpenGL commands in order to avoid WR using a semi-ready WebGL texture.
         // glWaitSync doesn't block WR thread, it affects only internal OpenGL subsystem.
-        self.webrender_gl.wait_sync(gl_sync as gl::GLsync, 0, gl::TIMEOUT_IGNORED);
+        // Lost contexts have nothing to wait for.
+        if gl_sync != 0 {
+            self.webrender_gl.wait_sync(gl_sync as gl::GLsync, 0, gl::TIMEOUT_IGNORED);
+        }
         (image_id, size)
     }
 
@@ -7,13 +7,22 @@ use canvas_traits::webgl::*;
 use euclid::Size2D;
 use fnv::FnvHashMap;
 use gleam::gl;
+use ipc_channel::ipc::IpcSender;
 use offscreen_gl_context::{GLContext, GLContextAttributes, GLLimits, NativeGLContextMethods};
 use serde_bytes::ByteBuf;
+use servo_config::prefs::PREFS;
+use std::ptr;
 use std::thread;
 use super::gl_context::{GLContextFactory, GLContextWrapper};
 use webrender;
 use webrender_api;
 
+/// From EXT_disjoint_timer_query, which gleam doesn't expose.
+const GPU_DISJOINT_EXT: u32 = 0x8FBB;
+
+/// Number of live GL contexts a WebGLThread keeps when `webgl.max_contexts` isn't set.
+const DEFAULT_MAX_CONTEXTS: u64 = 16;
+
 /// WebGL Threading API entry point that lives in the constellation.
 /// It allows to get a WebGLThread handle for each script pipeline.
 pub use ::webgl_mode::WebGLThreads;
@@ -33,6 +42,11 @@ pub struct WebGLThread<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver
     bound_context_id: Option<WebGLContextId>,
     /// Id generator for new WebGLContexts.
     next_webgl_id: usize,
+    /// Maximum number of live GL contexts, the least recently used one is
+    /// lost when a new context would exceed it.
+    max_contexts: usize,
+    /// Counter used to find the least recently used context.
+    use_counter: u64,
     /// Handler user to send WebVR commands.
     webvr_compositor: Option<VR>,
     /// Generic observer that listens WebGLContext creation, resize or removal events.
@@ -53,6 +67,8 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
             cached_context_info: Default::default(),
             bound_context_id: None,
             next_webgl_id: 0,
+            max_contexts: PREFS.get("webgl.max_contexts").as_u64().unwrap_or(DEFAULT_MAX_CONTEXTS) as usize,
+            use_counter: 0,
             webvr_compositor,
             observer: observer,
             dom_outputs: Default::default(),
@@ -90,19 +106,10 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
     #[inline]
     fn handle_msg(&mut self, msg: WebGLMsg, webgl_chan: &WebGLChan) -> bool {
         match msg {
-            WebGLMsg::CreateContext(version, size, attributes, result_sender) => {
-                let result = self.create_webgl_context(version, size, attributes);
+            WebGLMsg::CreateContext(version, size, attributes, loss_sender, result_sender) => {
+                let result = self.create_webgl_context(version, size, attributes, loss_sender, None);
                 result_sender.send(result.map(|(id, limits, share_mode)| {
-                    let ctx = Self::make_current_if_needed(id, &self.contexts, &mut self.bound_context_id)
-                                    .expect("WebGLContext not found");
-                    let glsl_version = Self::get_glsl_version(ctx);
-
-                    WebGLCreateContextResult {
-                        sender: WebGLMsgSender::new(id, webgl_chan.clone()),
-                        limits,
-                        share_mode,
-                        glsl_version,
-                    }
+                    self.create_context_result(id, limits, share_mode, webgl_chan)
                 })).unwrap();
             },
             WebGLMsg::ResizeContext(ctx_id, size, sender) => {
@@ -111,6 +118,15 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
             WebGLMsg::RemoveContext(ctx_id) => {
                 self.remove_webgl_context(ctx_id);
             },
+            WebGLMsg::LoseContext(ctx_id) => {
+                self.lose_webgl_context(ctx_id);
+            },
+            WebGLMsg::RestoreContext(ctx_id, size, loss_sender, result_sender) => {
+                let result = self.restore_webgl_context(ctx_id, size, loss_sender);
+                result_sender.send(result.map(|(id, limits, share_mode)| {
+                    self.create_context_result(id, limits, share_mode, webgl_chan)
+                })).unwrap();
+            },
             WebGLMsg::WebGLCommand(ctx_id, command) => {
                 self.handle_webgl_command(ctx_id, command);
             },
@@ -137,10 +153,40 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
         false
     }
 
+    /// Builds the information sent to script about a newly created GL context.
+    fn create_context_result(&mut self,
+                             id: WebGLContextId,
+                             limits: GLLimits,
+                             share_mode: WebGLContextShareMode,
+                             webgl_chan: &WebGLChan)
+                             -> WebGLCreateContextResult {
+        let ctx = Self::make_current_if_needed(id, &self.contexts, &mut self.bound_context_id)
+                        .expect("WebGLContext not found");
+        let glsl_version = Self::get_glsl_version(ctx);
+        let capabilities = Self::get_capabilities(ctx);
+
+        WebGLCreateContextResult {
+            sender: WebGLMsgSender::new(id, webgl_chan.clone()),
+            limits,
+            share_mode,
+            glsl_version,
+            capabilities,
+        }
+    }
+
     /// Handles a WebGLCommand for a specific WebGLContext
     fn handle_webgl_command(&mut self, context_id: WebGLContextId, command: WebGLCommand) {
-        if let Some(ctx) = Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id) {
-            ctx.apply_command(command);
+        match Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id) {
+            Some(ctx) => {
+                ctx.apply_command(command);
+                self.use_counter += 1;
+                if let Some(info) = self.cached_context_info.get_mut(&context_id) {
+                    info.last_used = self.use_counter;
+                }
+            },
+            // Commands may still be sent to a lost or removed context, make sure
+            // the ones expecting a reply get one.
+            None => WebGLImpl::reply_to_lost_context(command),
         }
     }
 
@@ -158,9 +204,20 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
 
     /// Handles a lock external callback received from webrender::ExternalImageHandler
     fn handle_lock(&mut self, context_id: WebGLContextId, sender: WebGLSender<(u32, Size2D<i32>, usize)>) {
-        let ctx = Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id)
-                        .expect("WebGLContext not found in a WebGLMsg::Lock message");
-        let info = self.cached_context_info.get_mut(&context_id).unwrap();
+        let info = match self.cached_context_info.get_mut(&context_id) {
+            Some(info) => info,
+            None => {
+                // The context was replaced by a restored one while WebRender was drawing its image.
+                return sender.send((0, Size2D::zero(), 0)).unwrap();
+            }
+        };
+        let ctx = match Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id) {
+            Some(ctx) => ctx,
+            None => {
+                // WebRender may still be drawing the image of a context that was just lost.
+                return sender.send((0, info.size, 0)).unwrap();
+            }
+        };
         // Insert a OpenGL Fence sync object that sends a signal when all the WebGL commands are finished.
         // The related gl().wait_sync call is performed in the WR thread. See WebGLExternalImageApi for mor details.
         let gl_sync = ctx.gl().fence_sync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
@@ -174,8 +231,10 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
 
     /// Handles an unlock external callback received from webrender::ExternalImageHandler
     fn handle_unlock(&mut self, context_id: WebGLContextId) {
-        let ctx = Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id)
-                        .expect("WebGLContext not found in a WebGLMsg::Unlock message");
+        let ctx = match Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id) {
+            Some(ctx) => ctx,
+            None => return,
+        };
         let info = self.cached_context_info.get_mut(&context_id).unwrap();
         if let Some(gl_sync) = info.gl_sync.take() {
             // Release the GLSync object.
@@ -183,20 +242,31 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
         }
     }
 
-    /// Creates a new WebGLContext
+    /// Creates a new WebGLContext. A context being restored passes the share
+    /// mode it was created with, to keep using the same kind of WebRender image.
     fn create_webgl_context(&mut self,
                             version: WebGLVersion,
                             size: Size2D<i32>,
-                            attributes: GLContextAttributes)
+                            attributes: GLContextAttributes,
+                            loss_sender: IpcSender<WebGLContextId>,
+                            share_mode: Option<WebGLContextShareMode>)
                             -> Result<(WebGLContextId, GLLimits, WebGLContextShareMode), String> {
+        let restoring = share_mode.is_some();
+        if !self.evict_least_recently_used_context(restoring) {
+            return Err("Too many WebGL contexts to restore a lost one".to_owned());
+        }
+
         // First try to create a shared context for the best performance.
         // Fallback to readback mode if the shared context creation fails.
-        let result = self.gl_factory.new_shared_context(version, size, attributes)
-                                    .map(|r| (r, WebGLContextShareMode::SharedTexture))
-                                    .or_else(|_| {
-                                        let ctx = self.gl_factory.new_context(version, size, attributes);
-                                        ctx.map(|r| (r, WebGLContextShareMode::Readback))
-                                    });
+        let result = match share_mode {
+            Some(WebGLContextShareMode::Readback) => Err("Readback mode requested"),
+            _ => self.gl_factory.new_shared_context(version, size, attributes)
+                                .map(|r| (r, WebGLContextShareMode::SharedTexture)),
+        }.or_else(|error| match share_mode {
+            Some(WebGLContextShareMode::SharedTexture) => Err(error),
+            _ => self.gl_factory.new_context(version, size, attributes)
+                                .map(|r| (r, WebGLContextShareMode::Readback)),
+        });
 
         // Creating a new GLContext may make the current bound context_id dirty.
         // Clear it to ensure that  make_current() is called in subsequent commands.
@@ -208,6 +278,7 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
                 let (size, texture_id, limits) = ctx.get_info();
                 self.next_webgl_id += 1;
                 self.contexts.insert(id, ctx);
+                self.use_counter += 1;
                 self.cached_context_info.insert(id, WebGLContextInfo {
                     texture_id,
                     size,
@@ -215,6 +286,11 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
                     image_key: None,
                     share_mode,
                     gl_sync: None,
+                    version,
+                    attributes,
+                    loss_sender: Some(loss_sender),
+                    last_used: self.use_counter,
+                    restored: restoring,
                 });
 
                 self.observer.on_context_create(id, texture_id, size);
@@ -227,12 +303,96 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
         }
     }
 
+    /// Loses the least recently used context if there's no room for another one,
+    /// and lets its owner know about it. Returns whether there is room for a new context.
+    ///
+    /// Restoring a context never evicts a restored context: pages that restore their
+    /// lost contexts would otherwise make each other lose them forever.
+    fn evict_least_recently_used_context(&mut self, restoring: bool) -> bool {
+        if self.contexts.len() < self.max_contexts {
+            return true;
+        }
+        let least_recently_used = {
+            let contexts = &self.contexts;
+            self.cached_context_info.iter()
+                .filter(|&(id, info)| contexts.contains_key(id) && !(restoring && info.restored))
+                .min_by_key(|&(_, info)| info.last_used)
+                .map(|(id, _)| *id)
+        };
+        let id = match least_recently_used {
+            Some(id) => id,
+            None => return false,
+        };
+        warn!("Too many WebGL contexts, losing {:?}", id);
+        if let Some(ref loss_sender) = self.cached_context_info[&id].loss_sender {
+            let _ = loss_sender.send(id);
+        }
+        self.lose_webgl_context(id);
+        true
+    }
+
+    /// Releases the GL context of a WebGLContext, but keeps its cached
+    /// information around until it's restored or removed.
+    fn lose_webgl_context(&mut self, context_id: WebGLContextId) {
+        if self.contexts.remove(&context_id).is_none() {
+            return;
+        }
+        self.observer.on_context_delete(context_id);
+
+        // The WebRender image is replaced by a transparent one until the
+        // context is restored. See `handle_update_wr_image`.
+        if let Some(info) = self.cached_context_info.get_mut(&context_id) {
+            if let Some(image_key) = info.image_key.take() {
+                let mut txn = webrender_api::Transaction::new();
+                txn.delete_image(image_key);
+                self.webrender_api.update_resources(txn.resource_updates);
+            }
+            info.gl_sync = None;
+            // The owner stops listening once the channel is closed.
+            info.loss_sender = None;
+        }
+
+        // Removing a GLContext may make the current bound context_id dirty.
+        self.bound_context_id = None;
+    }
+
+    /// Creates a new WebGLContext in place of a lost one.
+    fn restore_webgl_context(&mut self,
+                             context_id: WebGLContextId,
+                             size: Size2D<i32>,
+                             loss_sender: IpcSender<WebGLContextId>)
+                             -> Result<(WebGLContextId, GLLimits, WebGLContextShareMode), String> {
+        let (version, attributes, share_mode) = match self.cached_context_info.get(&context_id) {
+            Some(info) if !self.contexts.contains_key(&context_id) => {
+                (info.version, info.attributes, info.share_mode)
+            },
+            _ => return Err("Only lost contexts can be restored".into()),
+        };
+
+        let result = self.create_webgl_context(version, size, attributes, loss_sender, Some(share_mode));
+        if result.is_ok() {
+            // The lost context is replaced by the new one, release its placeholder image.
+            self.remove_webgl_context(context_id);
+        }
+        result
+    }
+
     /// Resizes a WebGLContext
     fn resize_webgl_context(&mut self,
                             context_id: WebGLContextId,
                             size: Size2D<i32>,
                             sender: WebGLSender<Result<(), String>>) {
-        let ctx = Self::make_current_if_needed_mut(context_id, &mut self.contexts, &mut self.bound_context_id);
+        let ctx = match Self::make_current_if_needed_mut(context_id, &mut self.contexts, &mut self.bound_context_id) {
+            Some(ctx) => ctx,
+            None => {
+                // The context was lost before script found out about it. It is created with
+                // the new size of the canvas when restored.
+                if let Some(info) = self.cached_context_info.get_mut(&context_id) {
+                    info.size = size;
+                }
+                return sender.send(Err("WebGLContext is lost".into())).unwrap();
+            }
+        };
         match ctx.resize(size) {
             Ok(_) => {
                 let (real_size, texture_id, _) = ctx.get_info();
@@ -295,6 +455,16 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
         let info = self.cached_context_info.get_mut(&context_id).unwrap();
         let webrender_api = &self.webrender_api;
 
+        if !self.contexts.contains_key(&context_id) {
+            // A lost context is displayed as transparent black.
+            let size = info.size;
+            let image_key = *info.image_key.get_or_insert_with(|| {
+                let pixels = vec![0; size.width as usize * size.height as usize * 4];
+                Self::create_wr_readback_image(webrender_api, size, true, pixels)
+            });
+            return sender.send(image_key).unwrap();
+        }
+
         let image_key = match info.share_mode {
             WebGLContextShareMode::SharedTexture => {
                 let size = info.size;
@@ -306,7 +476,9 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
                 })
             },
             WebGLContextShareMode::Readback => {
-                let pixels = Self::raw_pixels(&self.contexts[&context_id], info.size);
+                let ctx = Self::make_current_if_needed(context_id, &self.contexts, &mut self.bound_context_id)
+                                .unwrap();
+                let pixels = Self::raw_pixels(ctx, info.size);
                 match info.image_key.clone() {
                     Some(image_key) => {
                         // ImageKey was already created, but WR Images must
@@ -405,13 +577,15 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
     /// Gets a mutable reference to a GLContextWrapper for a WebGLContextId and makes it current if required.
     fn make_current_if_needed_mut<'a>(context_id: WebGLContextId,
                                       contexts: &'a mut FnvHashMap<WebGLContextId, GLContextWrapper>,
-                                      bound_id: &mut Option<WebGLContextId>) -> &'a mut GLContextWrapper {
-        let ctx = contexts.get_mut(&context_id).expect("WebGLContext not found!");
-        if Some(context_id) != *bound_id {
-            ctx.make_current();
-            *bound_id = Some(context_id);
-        }
-        ctx
+                                      bound_id: &mut Option<WebGLContextId>)
+                                      -> Option<&'a mut GLContextWrapper> {
+        contexts.get_mut(&context_id).map(|ctx| {
+            if Some(context_id) != *bound_id {
+                ctx.make_current();
+                *bound_id = Some(context_id);
+            }
+            ctx
+        })
     }
 
     /// Creates a `webrender_api::ImageKey` that uses shared textures.
@@ -499,14 +673,30 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
     }
 
     /// Helper function to fetch the raw pixels used in readback mode.
+    /// Reads the drawing buffer of a GLContext, which must be current, whatever
+    /// framebuffer and pixel pack alignment the page set.
+    #[allow(unsafe_code)]
     fn raw_pixels(context: &GLContextWrapper, size: Size2D<i32>) -> Vec<u8> {
         let width = size.width as usize;
         let height = size.height as usize;
 
-        let mut pixels = context.gl().read_pixels(0, 0,
-                                                  size.width as gl::GLsizei,
-                                                  size.height as gl::GLsizei,
-                                                  gl::RGBA, gl::UNSIGNED_BYTE);
+        let gl = context.gl();
+        let mut framebuffer = [0];
+        let mut pack_alignment = [0];
+        unsafe {
+            gl.get_integer_v(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl.get_integer_v(gl::PACK_ALIGNMENT, &mut pack_alignment);
+        }
+        gl.bind_framebuffer(gl::FRAMEBUFFER, context.framebuffer());
+        gl.pixel_store_i(gl::PACK_ALIGNMENT, 4);
+
+        let mut pixels = gl.read_pixels(0, 0,
+                                        size.width as gl::GLsizei,
+                                        size.height as gl::GLsizei,
+                                        gl::RGBA, gl::UNSIGNED_BYTE);
+
+        gl.pixel_store_i(gl::PACK_ALIGNMENT, pack_alignment[0]);
+        gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer[0] as gl::GLuint);
         // flip image vertically (texture is upside down)
         let orig_pixels = pixels.clone();
         let stride = width * 4;
@@ -533,6 +723,40 @@ impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> WebGLThread<VR,
             minor,
         }
     }
+
+    /// Gets the implementation values used by the script thread to gate WebGL extensions.
+    #[allow(unsafe_code)]
+    fn get_capabilities(context: &GLContextWrapper) -> GLContextCapabilities {
+        let gl = context.gl();
+        let version = gl.get_string(gl::VERSION);
+        let extensions = gl.get_string(gl::EXTENSIONS);
+        // Desktop GL always exposes multiple render targets, GLES 2 only through extensions.
+        let supports_mrt = match gl.get_type() {
+            gl::GlType::Gl => true,
+            gl::GlType::Gles => {
+                version.starts_with("OpenGL ES 3") ||
+                extensions.split(' ').any(|e| e == "GL_EXT_draw_buffers" || e == "GL_NV_draw_buffers")
+            },
+        };
+        let (max_draw_buffers, max_color_attachments) = if supports_mrt {
+            let mut max_draw_buffers = [0];
+            let mut max_color_attachments = [0];
+            unsafe {
+                gl.get_integer_v(gl::MAX_DRAW_BUFFERS, &mut max_draw_buffers);
+                gl.get_integer_v(gl::MAX_COLOR_ATTACHMENTS, &mut max_color_attachments);
+            }
+            (max_draw_buffers[0].max(1) as u32, max_color_attachments[0].max(1) as u32)
+        } else {
+            (1, 1)
+        };
+
+        GLContextCapabilities {
+            max_draw_buffers,
+            max_color_attachments,
+            vendor: gl.get_string(gl::VENDOR),
+            renderer: gl.get_string(gl::RENDERER),
+        }
+    }
 }
 
 impl<VR: WebVRRenderHandler + 'static, OB: WebGLThreadObserver> Drop for WebGLThread<VR, OB> {
@@ -559,6 +783,17 @@ struct WebGLContextInfo {
     share_mode: WebGLContextShareMode,
     /// GLSync Object used for a correct synchronization with Webrender external image callbacks.
     gl_sync: Option<gl::GLsync>,
+    /// The WebGL version the context was created for.
+    version: WebGLVersion,
+    /// The attributes the context was created with.
+    attributes: GLContextAttributes,
+    /// Channel used to tell the owner of the context that it was lost,
+    /// dropped once the context is lost.
+    loss_sender: Option<IpcSender<WebGLContextId>>,
+    /// Value of the use counter the last time a command was run in the context.
+    last_used: u64,
+    /// True if the context replaces a lost one.
+    restored: bool,
 }
 
 /// Trait used to observe events in a WebGL Thread.
@@ -635,6 +870,214 @@ struct DOMToTextureData {
 pub struct WebGLImpl;
 
 impl WebGLImpl {
+    /// Answers the commands expecting a reply that were sent to a lost context.
+    /// GetContextAttributes is left unanswered, a closed channel tells script
+    /// that the context is lost.
+    pub fn reply_to_lost_context(command: WebGLCommand) {
+        let _ = match command {
+            WebGLCommand::CreateBuffer(sender) => sender.send(None),
+            WebGLCommand::CreateFramebuffer(sender) => sender.send(None),
+            WebGLCommand::CreateRenderbuffer(sender) => sender.send(None),
+            WebGLCommand::CreateTexture(sender) => sender.send(None),
+            WebGLCommand::CreateProgram(sender) => sender.send(None),
+            WebGLCommand::CreateShader(_, sender) => sender.send(None),
+            WebGLCommand::CreateVertexArray(sender) => sender.send(None),
+            WebGLCommand::CreateSampler(sender) => sender.send(None),
+            WebGLCommand::CreateQuery(sender) => sender.send(None),
+            WebGLCommand::FenceSync(sender) => sender.send(None),
+            WebGLCommand::CreateTransformFeedback(sender) => sender.send(None),
+            WebGLCommand::LinkProgram(_, sender) => {
+                sender.send(ProgramLinkInfo {
+                    linked: false,
+                    active_attribs: vec![].into(),
+                    active_uniforms: vec![].into(),
+                })
+            },
+            WebGLCommand::GetBufferSubData(_, _, length, sender) => sender.send(ByteBuf::from(vec![0; length])),
+            WebGLCommand::ReadPixels(_, _, width, height, _, _, sender) => {
+                // Large enough for any format and type.
+                let bytes_per_pixel = 4 * 4;
+                sender.send(ByteBuf::from(vec![0; (width * height * bytes_per_pixel) as usize]))
+            },
+            WebGLCommand::GetUniformLocation(_, _, sender) => sender.send(-1),
+            WebGLCommand::ClientWaitSync(_, _, _, sender) => sender.send(gl::WAIT_FAILED),
+            WebGLCommand::Finish(sender) => sender.send(()),
+            WebGLCommand::GetExtensions(sender) |
+            WebGLCommand::GetShaderInfoLog(_, sender) |
+            WebGLCommand::GetProgramInfoLog(_, sender) => sender.send(Default::default()),
+            WebGLCommand::GetFramebufferAttachmentParameter(_, _, _, sender) |
+            WebGLCommand::GetRenderbufferParameter(_, _, sender) |
+            WebGLCommand::DrawingBufferWidth(sender) |
+            WebGLCommand::DrawingBufferHeight(sender) |
+            WebGLCommand::GetSamplerParameterInt(_, _, sender) |
+            WebGLCommand::GetParameterInt(_, sender) |
+            WebGLCommand::GetProgramActiveUniforms(_, sender) |
+            WebGLCommand::GetTexParameterInt(_, _, sender) |
+            WebGLCommand::GetUniformInt(_, _, sender) => sender.send(0),
+            WebGLCommand::GetSamplerParameterFloat(_, _, sender) |
+            WebGLCommand::GetParameterFloat(_, sender) |
+            WebGLCommand::GetTexParameterFloat(_, _, sender) |
+            WebGLCommand::GetUniformFloat(_, _, sender) => sender.send(0.),
+            WebGLCommand::GetQueryState(_, _, sender) => sender.send(0),
+            WebGLCommand::GetQueryStateU64(_, _, sender) |
+            WebGLCommand::GetTimestamp(sender) => sender.send(0),
+            WebGLCommand::GetGpuDisjoint(sender) |
+            WebGLCommand::IsSyncSignaled(_, sender) |
+            WebGLCommand::GetParameterBool(_, sender) |
+            WebGLCommand::GetProgramValidateStatus(_, sender) |
+            WebGLCommand::GetUniformBool(_, _, sender) => sender.send(false),
+            WebGLCommand::GetShaderPrecisionFormat(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetInternalFormatSamples(_, _, sender) => sender.send(vec![]),
+            WebGLCommand::GetTransformFeedbackVarying(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetUniformBool2(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetShaderCompileStatus(_, sender) => sender.send(Err(String::new())),
+            WebGLCommand::GetUniformBool3(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetParameterBool4(_, sender) |
+            WebGLCommand::GetUniformBool4(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetParameterInt2(_, sender) |
+            WebGLCommand::GetUniformInt2(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetUniformInt3(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetParameterInt4(_, sender) |
+            WebGLCommand::GetUniformInt4(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetParameterFloat2(_, sender) |
+            WebGLCommand::GetUniformFloat2(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetUniformFloat3(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetParameterFloat4(_, sender) |
+            WebGLCommand::GetCurrentVertexAttrib(_, sender) |
+            WebGLCommand::GetUniformFloat4(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetUniformFloat9(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetUniformFloat16(_, _, sender) => sender.send(Default::default()),
+            WebGLCommand::GetContextAttributes(_) => Ok(()),
+            // Every other command is listed so that new commands expecting a reply
+            // can't be left unanswered.
+            WebGLCommand::ActiveTexture(..) |
+            WebGLCommand::BlendColor(..) |
+            WebGLCommand::BlendEquation(..) |
+            WebGLCommand::BlendEquationSeparate(..) |
+            WebGLCommand::BlendFunc(..) |
+            WebGLCommand::BlendFuncSeparate(..) |
+            WebGLCommand::AttachShader(..) |
+            WebGLCommand::DetachShader(..) |
+            WebGLCommand::BindAttribLocation(..) |
+            WebGLCommand::BufferData(..) |
+            WebGLCommand::BufferSubData(..) |
+            WebGLCommand::CopyBufferSubData(..) |
+            WebGLCommand::BindBufferBase(..) |
+            WebGLCommand::BindBufferRange(..) |
+            WebGLCommand::Clear(..) |
+            WebGLCommand::ClearColor(..) |
+            WebGLCommand::ClearDepth(..) |
+            WebGLCommand::ClearStencil(..) |
+            WebGLCommand::ColorMask(..) |
+            WebGLCommand::CullFace(..) |
+            WebGLCommand::FrontFace(..) |
+            WebGLCommand::DepthFunc(..) |
+            WebGLCommand::DepthMask(..) |
+            WebGLCommand::DepthRange(..) |
+            WebGLCommand::Enable(..) |
+            WebGLCommand::Disable(..) |
+            WebGLCommand::CopyTexImage2D(..) |
+            WebGLCommand::CopyTexSubImage2D(..) |
+            WebGLCommand::DeleteBuffer(..) |
+            WebGLCommand::DeleteFramebuffer(..) |
+            WebGLCommand::DeleteRenderbuffer(..) |
+            WebGLCommand::DeleteTexture(..) |
+            WebGLCommand::DeleteProgram(..) |
+            WebGLCommand::DeleteShader(..) |
+            WebGLCommand::BindBuffer(..) |
+            WebGLCommand::BindFramebuffer(..) |
+            WebGLCommand::BindRenderbuffer(..) |
+            WebGLCommand::BindTexture(..) |
+            WebGLCommand::DisableVertexAttribArray(..) |
+            WebGLCommand::EnableVertexAttribArray(..) |
+            WebGLCommand::FramebufferRenderbuffer(..) |
+            WebGLCommand::FramebufferTexture2D(..) |
+            WebGLCommand::FramebufferTextureLayer(..) |
+            WebGLCommand::BlitFramebuffer(..) |
+            WebGLCommand::InvalidateFramebuffer(..) |
+            WebGLCommand::InvalidateSubFramebuffer(..) |
+            WebGLCommand::ReadBuffer(..) |
+            WebGLCommand::DrawBuffers(..) |
+            WebGLCommand::PolygonOffset(..) |
+            WebGLCommand::RenderbufferStorage(..) |
+            WebGLCommand::RenderbufferStorageMultisample(..) |
+            WebGLCommand::SampleCoverage(..) |
+            WebGLCommand::Scissor(..) |
+            WebGLCommand::StencilFunc(..) |
+            WebGLCommand::StencilFuncSeparate(..) |
+            WebGLCommand::StencilMask(..) |
+            WebGLCommand::StencilMaskSeparate(..) |
+            WebGLCommand::StencilOp(..) |
+            WebGLCommand::StencilOpSeparate(..) |
+            WebGLCommand::Hint(..) |
+            WebGLCommand::LineWidth(..) |
+            WebGLCommand::PixelStorei(..) |
+            WebGLCommand::Uniform1f(..) |
+            WebGLCommand::Uniform1fv(..) |
+            WebGLCommand::Uniform1i(..) |
+            WebGLCommand::Uniform1iv(..) |
+            WebGLCommand::Uniform2f(..) |
+            WebGLCommand::Uniform2fv(..) |
+            WebGLCommand::Uniform2i(..) |
+            WebGLCommand::Uniform2iv(..) |
+            WebGLCommand::Uniform3f(..) |
+            WebGLCommand::Uniform3fv(..) |
+            WebGLCommand::Uniform3i(..) |
+            WebGLCommand::Uniform3iv(..) |
+            WebGLCommand::Uniform4f(..) |
+            WebGLCommand::Uniform4fv(..) |
+            WebGLCommand::Uniform4i(..) |
+            WebGLCommand::Uniform4iv(..) |
+            WebGLCommand::UniformMatrix2fv(..) |
+            WebGLCommand::UniformMatrix3fv(..) |
+            WebGLCommand::UniformMatrix4fv(..) |
+            WebGLCommand::UseProgram(..) |
+            WebGLCommand::ValidateProgram(..) |
+            WebGLCommand::VertexAttrib(..) |
+            WebGLCommand::VertexAttribPointer(..) |
+            WebGLCommand::VertexAttribPointer2f(..) |
+            WebGLCommand::SetViewport(..) |
+            WebGLCommand::TexImage2D(..) |
+            WebGLCommand::TexSubImage2D(..) |
+            WebGLCommand::CompressedTexImage2D { .. } |
+            WebGLCommand::CompressedTexSubImage2D { .. } |
+            WebGLCommand::TexStorage2D(..) |
+            WebGLCommand::TexStorage3D(..) |
+            WebGLCommand::TexImage3D { .. } |
+            WebGLCommand::TexSubImage3D { .. } |
+            WebGLCommand::TexSubImage3DPbo { .. } |
+            WebGLCommand::CopyTexSubImage3D(..) |
+            WebGLCommand::Flush |
+            WebGLCommand::GenerateMipmap(..) |
+            WebGLCommand::DeleteVertexArray(..) |
+            WebGLCommand::BindVertexArray(..) |
+            WebGLCommand::DeleteSampler(..) |
+            WebGLCommand::BindSampler(..) |
+            WebGLCommand::SetSamplerParameterInt(..) |
+            WebGLCommand::SetSamplerParameterFloat(..) |
+            WebGLCommand::DeleteQuery(..) |
+            WebGLCommand::BeginQuery(..) |
+            WebGLCommand::EndQuery(..) |
+            WebGLCommand::QueryCounter(..) |
+            WebGLCommand::WaitSync(..) |
+            WebGLCommand::DeleteSync(..) |
+            WebGLCommand::DeleteTransformFeedback(..) |
+            WebGLCommand::BindTransformFeedback(..) |
+            WebGLCommand::BeginTransformFeedback(..) |
+            WebGLCommand::EndTransformFeedback |
+            WebGLCommand::PauseTransformFeedback |
+            WebGLCommand::ResumeTransformFeedback |
+            WebGLCommand::TransformFeedbackVaryings(..) |
+            WebGLCommand::TexParameteri(..) |
+            WebGLCommand::TexParameterf(..) |
+            WebGLCommand::DrawArrays { .. } |
+            WebGLCommand::DrawArraysInstanced { .. } |
+            WebGLCommand::DrawElements { .. } |
+            WebGLCommand::DrawElementsInstanced { .. } |
+            WebGLCommand::VertexAttribDivisor { .. } => Ok(()),
+        };
+    }
+
     #[allow(unsafe_code)]
     pub fn apply<Native: NativeGLContextMethods>(ctx: &GLContext<Native>, command: WebGLCommand) {
         match command {
@@ -663,6 +1106,16 @@ impl WebGLImpl {
                 gl::buffer_data(ctx.gl(), buffer_type, data, usage),
             WebGLCommand::BufferSubData(buffer_type, offset, ref data) =>
                 gl::buffer_sub_data(ctx.gl(), buffer_type, offset, data),
+            WebGLCommand::CopyBufferSubData(read_target, write_target, read_offset, write_offset, size) =>
+                ctx.gl().copy_buffer_sub_data(read_target, write_target,
+                                              read_offset as isize, write_offset as isize, size as isize),
+            WebGLCommand::GetBufferSubData(target, offset, length, ref chan) =>
+                Self::get_buffer_sub_data(ctx.gl(), target, offset, length, chan),
+            WebGLCommand::BindBufferBase(target, index, id) =>
+                ctx.gl().bind_buffer_base(target, index, id.map_or(0, WebGLBufferId::get)),
+            WebGLCommand::BindBufferRange(target, index, id, offset, size) =>
+                ctx.gl().bind_buffer_range(target, index, id.map_or(0, WebGLBufferId::get),
+                                           offset as isize, size as isize),
             WebGLCommand::Clear(mask) =>
                 ctx.gl().clear(mask),
             WebGLCommand::ClearColor(r, g, b, a) =>
@@ -697,6 +1150,21 @@ impl WebGLImpl {
             WebGLCommand::FramebufferTexture2D(target, attachment, textarget, texture, level) =>
                 ctx.gl().framebuffer_texture_2d(target, attachment, textarget,
                                                 texture.map_or(0, WebGLTextureId::get), level),
+            WebGLCommand::FramebufferTextureLayer(target, attachment, texture, level, layer) =>
+                ctx.gl().framebuffer_texture_layer(target, attachment,
+                                                   texture.map_or(0, WebGLTextureId::get), level, layer),
+            WebGLCommand::BlitFramebuffer(src_x0, src_y0, src_x1, src_y1,
+                                          dst_x0, dst_y0, dst_x1, dst_y1, mask, filter) =>
+                ctx.gl().blit_framebuffer(src_x0, src_y0, src_x1, src_y1,
+                                          dst_x0, dst_y0, dst_x1, dst_y1, mask, filter),
+            WebGLCommand::InvalidateFramebuffer(target, ref attachments) =>
+                ctx.gl().invalidate_framebuffer(target, attachments),
+            WebGLCommand::InvalidateSubFramebuffer(target, ref attachments, x, y, width, height) =>
+                ctx.gl().invalidate_sub_framebuffer(target, attachments, x, y, width, height),
+            WebGLCommand::ReadBuffer(mode) =>
+                ctx.gl().read_buffer(mode),
+            WebGLCommand::DrawBuffers(ref buffers) =>
+                ctx.gl().draw_buffers(buffers),
             WebGLCommand::FrontFace(mode) =>
                 ctx.gl().front_face(mode),
             WebGLCommand::DisableVertexAttribArray(attrib_id) =>
@@ -715,6 +1183,10 @@ impl WebGLImpl {
                 Self::read_pixels(ctx.gl(), x, y, width, height, format, pixel_type, chan),
             WebGLCommand::RenderbufferStorage(target, format, width, height) =>
                 ctx.gl().renderbuffer_storage(target, format, width, height),
+            WebGLCommand::RenderbufferStorageMultisample(target, samples, format, width, height) =>
+                ctx.gl().renderbuffer_storage_multisample(target, samples, format, width, height),
+            WebGLCommand::GetInternalFormatSamples(target, internal_format, ref chan) =>
+                Self::get_internal_format_samples(ctx.gl(), target, internal_format, chan),
             WebGLCommand::SampleCoverage(value, invert) =>
                 ctx.gl().sample_coverage(value, invert),
             WebGLCommand::Scissor(x, y, width, height) =>
@@ -747,6 +1219,8 @@ impl WebGLImpl {
                 Self::program_info_log(ctx.gl(), program_id, chan),
             WebGLCommand::CompileShader(shader_id, ref source) =>
                 Self::compile_shader(ctx.gl(), shader_id, &source),
+            WebGLCommand::GetShaderCompileStatus(shader_id, ref chan) =>
+                Self::shader_compile_status(ctx.gl(), shader_id, chan),
             WebGLCommand::CreateBuffer(ref chan) =>
                 Self::create_buffer(ctx.gl(), chan),
             WebGLCommand::CreateFramebuffer(ref chan) =>
@@ -836,6 +1310,37 @@ impl WebGLImpl {
                                       /*border*/0, format, data_type, Some(data)),
             WebGLCommand::TexSubImage2D(target, level, xoffset, yoffset, x, y, width, height, ref data) =>
                 ctx.gl().tex_sub_image_2d(target, level, xoffset, yoffset, x, y, width, height, data),
+            WebGLCommand::CompressedTexImage2D { target, level, internal_format, width, height, ref data } =>
+                ctx.gl().compressed_tex_image_2d(target, level, internal_format, width, height, /*border*/0, data),
+            WebGLCommand::CompressedTexSubImage2D {
+                target, level, xoffset, yoffset, width, height, format, ref data,
+            } => {
+                ctx.gl().compressed_tex_sub_image_2d(target, level, xoffset, yoffset, width, height, format, data)
+            }
+            WebGLCommand::TexStorage2D(target, levels, internal_format, width, height) =>
+                ctx.gl().tex_storage_2d(target, levels, internal_format, width, height),
+            WebGLCommand::TexStorage3D(target, levels, internal_format, width, height, depth) =>
+                ctx.gl().tex_storage_3d(target, levels, internal_format, width, height, depth),
+            WebGLCommand::TexImage3D {
+                target, level, internal_format, width, height, depth, format, data_type, ref data,
+            } => {
+                ctx.gl().tex_image_3d(target, level, internal_format, width, height, depth,
+                                      /*border*/0, format, data_type, Some(data))
+            }
+            WebGLCommand::TexSubImage3D {
+                target, level, xoffset, yoffset, zoffset, width, height, depth, format, data_type, ref data,
+            } => {
+                ctx.gl().tex_sub_image_3d(target, level, xoffset, yoffset, zoffset,
+                                          width, height, depth, format, data_type, data)
+            }
+            WebGLCommand::TexSubImage3DPbo {
+                target, level, xoffset, yoffset, zoffset, width, height, depth, format, data_type, offset,
+            } => {
+                ctx.gl().tex_sub_image_3d_pbo(target, level, xoffset, yoffset, zoffset,
+                                              width, height, depth, format, data_type, offset as usize)
+            }
+            WebGLCommand::CopyTexSubImage3D(target, level, xoffset, yoffset, zoffset, x, y, width, height) =>
+                ctx.gl().copy_tex_sub_image_3d(target, level, xoffset, yoffset, zoffset, x, y, width, height),
             WebGLCommand::DrawingBufferWidth(ref sender) =>
                 sender.send(ctx.borrow_draw_buffer().unwrap().size().width).unwrap(),
             WebGLCommand::DrawingBufferHeight(ref sender) =>
@@ -852,6 +1357,100 @@ impl WebGLImpl {
                 ctx.gl().delete_vertex_arrays(&[id.get()]),
             WebGLCommand::BindVertexArray(id) =>
                 ctx.gl().bind_vertex_array(id.map_or(0, WebGLVertexArrayId::get)),
+            WebGLCommand::CreateSampler(ref chan) =>
+                Self::create_sampler(ctx.gl(), chan),
+            WebGLCommand::DeleteSampler(id) =>
+                ctx.gl().delete_samplers(&[id.get()]),
+            WebGLCommand::BindSampler(unit, id) =>
+                ctx.gl().bind_sampler(unit, id.map_or(0, WebGLSamplerId::get)),
+            WebGLCommand::SetSamplerParameterInt(id, pname, value) =>
+                ctx.gl().sampler_parameter_i(id.get(), pname, value),
+            WebGLCommand::SetSamplerParameterFloat(id, pname, value) =>
+                ctx.gl().sampler_parameter_f(id.get(), pname, value),
+            WebGLCommand::GetSamplerParameterInt(id, pname, ref sender) => {
+                let mut value = [0];
+                unsafe {
+                    ctx.gl().get_sampler_parameter_iv(id.get(), pname, &mut value);
+                }
+                sender.send(value[0]).unwrap()
+            }
+            WebGLCommand::GetSamplerParameterFloat(id, pname, ref sender) => {
+                let mut value = [0.];
+                unsafe {
+                    ctx.gl().get_sampler_parameter_fv(id.get(), pname, &mut value);
+                }
+                sender.send(value[0]).unwrap()
+            }
+            WebGLCommand::CreateQuery(ref chan) =>
+                Self::create_query(ctx.gl(), chan),
+            WebGLCommand::DeleteQuery(id) =>
+                ctx.gl().delete_queries(&[id.get()]),
+            WebGLCommand::BeginQuery(target, id) =>
+                ctx.gl().begin_query(target, id.get()),
+            WebGLCommand::EndQuery(target) =>
+                ctx.gl().end_query(target),
+            WebGLCommand::GetQueryState(id, pname, ref sender) =>
+                sender.send(ctx.gl().get_query_object_uiv(id.get(), pname)).unwrap(),
+            WebGLCommand::GetQueryStateU64(id, pname, ref sender) =>
+                sender.send(ctx.gl().get_query_object_ui64v(id.get(), pname)).unwrap(),
+            WebGLCommand::QueryCounter(id, target) =>
+                ctx.gl().query_counter(id.get(), target),
+            WebGLCommand::GetTimestamp(ref sender) => {
+                let mut value = [0];
+                unsafe {
+                    ctx.gl().get_integer_64v(gl::TIMESTAMP, &mut value);
+                }
+                sender.send(value[0] as u64).unwrap()
+            }
+            WebGLCommand::GetGpuDisjoint(ref sender) => {
+                // GPU_DISJOINT_EXT only exists in GLES' EXT_disjoint_timer_query,
+                // desktop GL timers never report disjoint operations.
+                let disjoint = match ctx.gl().get_type() {
+                    gl::GlType::Gles => {
+                        let mut value = [0];
+                        unsafe {
+                            ctx.gl().get_integer_v(GPU_DISJOINT_EXT, &mut value);
+                        }
+                        value[0] != 0
+                    }
+                    gl::GlType::Gl => false,
+                };
+                sender.send(disjoint).unwrap()
+            }
+            WebGLCommand::FenceSync(ref chan) =>
+                Self::fence_sync(ctx.gl(), chan),
+            WebGLCommand::ClientWaitSync(id, flags, timeout, ref sender) =>
+                sender.send(ctx.gl().client_wait_sync(id.get() as gl::GLsync, flags, timeout)).unwrap(),
+            WebGLCommand::WaitSync(id) =>
+                ctx.gl().wait_sync(id.get() as gl::GLsync, 0, gl::TIMEOUT_IGNORED),
+            WebGLCommand::IsSyncSignaled(id, ref sender) => {
+                let status = ctx.gl().client_wait_sync(id.get() as gl::GLsync, 0, 0);
+                sender.send(status == gl::ALREADY_SIGNALED || status == gl::CONDITION_SATISFIED).unwrap()
+            }
+            WebGLCommand::DeleteSync(id) =>
+                ctx.gl().delete_sync(id.get() as gl::GLsync),
+            WebGLCommand::CreateTransformFeedback(ref chan) =>
+                Self::create_transform_feedback(ctx.gl(), chan),
+            WebGLCommand::DeleteTransformFeedback(id) =>
+                ctx.gl().delete_transform_feedbacks(&[id.get()]),
+            WebGLCommand::BindTransformFeedback(target, id) =>
+                ctx.gl().bind_transform_feedback(target, id.map_or(0, WebGLTransformFeedbackId::get)),
+            WebGLCommand::BeginTransformFeedback(primitive_mode) =>
+                ctx.gl().begin_transform_feedback(primitive_mode),
+            WebGLCommand::EndTransformFeedback =>
+                ctx.gl().end_transform_feedback(),
+            WebGLCommand::PauseTransformFeedback =>
+                ctx.gl().pause_transform_feedback(),
+            WebGLCommand::ResumeTransformFeedback =>
+                ctx.gl().resume_transform_feedback(),
+            WebGLCommand::TransformFeedbackVaryings(program_id, ref varyings, buffer_mode) => {
+                let varyings = varyings.iter().map(|name| to_name_in_compiled_shader(name)).collect::<Vec<_>>();
+                ctx.gl().transform_feedback_varyings(program_id.get(), &varyings, buffer_mode)
+            }
+            WebGLCommand::GetTransformFeedbackVarying(program_id, index, ref sender) => {
+                let (size, type_, name) = ctx.gl().get_transform_feedback_varying(program_id.get(), index);
+                sender.send((size, type_, from_name_in_compiled_shader(&name))).unwrap()
+            }
             WebGLCommand::GetParameterBool(param, ref sender) => {
                 let mut value = [0];
                 unsafe {
@@ -1308,6 +1907,89 @@ impl WebGLImpl {
         chan.send(vao).unwrap();
     }
 
+    #[allow(unsafe_code)]
+    fn create_sampler(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLSamplerId>>) {
+        let sampler = gl.gen_samplers(1)[0];
+        let sampler = if sampler == 0 {
+            None
+        } else {
+            Some(unsafe { WebGLSamplerId::new(sampler) })
+        };
+        chan.send(sampler).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn create_query(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLQueryId>>) {
+        let query = gl.gen_queries(1)[0];
+        let query = if query == 0 {
+            None
+        } else {
+            Some(unsafe { WebGLQueryId::new(query) })
+        };
+        chan.send(query).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn fence_sync(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLSyncId>>) {
+        let sync = gl.fence_sync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+        let sync = if sync.is_null() {
+            None
+        } else {
+            Some(unsafe { WebGLSyncId::new(sync as u64) })
+        };
+        chan.send(sync).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn create_transform_feedback(gl: &gl::Gl, chan: &WebGLSender<Option<WebGLTransformFeedbackId>>) {
+        let transform_feedback = gl.gen_transform_feedbacks(1)[0];
+        let transform_feedback = if transform_feedback == 0 {
+            None
+        } else {
+            Some(unsafe { WebGLTransformFeedbackId::new(transform_feedback) })
+        };
+        chan.send(transform_feedback).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn get_buffer_sub_data(
+        gl: &gl::Gl,
+        target: u32,
+        offset: usize,
+        length: usize,
+        chan: &WebGLSender<ByteBuf>,
+    ) {
+        let mut data = vec![0; length];
+        if length > 0 {
+            let ptr = gl.map_buffer_range(target, offset as isize, length as isize, gl::MAP_READ_BIT);
+            unsafe {
+                ptr::copy_nonoverlapping(ptr as *const u8, data.as_mut_ptr(), length);
+            }
+            gl.unmap_buffer(target);
+        }
+        chan.send(data.into()).unwrap();
+    }
+
+    #[allow(unsafe_code)]
+    fn get_internal_format_samples(
+        gl: &gl::Gl,
+        target: u32,
+        internal_format: u32,
+        chan: &WebGLSender<Vec<i32>>,
+    ) {
+        let mut count = [0];
+        unsafe {
+            gl.get_internal_format_iv(target, internal_format, gl::NUM_SAMPLE_COUNTS, &mut count);
+        }
+        let mut values = vec![0; count[0].max(0) as usize];
+        if !values.is_empty() {
+            unsafe {
+                gl.get_internal_format_iv(target, internal_format, gl::SAMPLES, &mut values);
+            }
+        }
+        chan.send(values).unwrap();
+    }
+
     #[inline]
     fn bind_framebuffer<Native: NativeGLContextMethods>(gl: &gl::Gl,
                                                         target: u32,
@@ -1323,11 +2005,30 @@ impl WebGLImpl {
     }
 
 
-    #[inline]
+    /// Compiles a shader translated by ANGLE, replying with the driver's info log if it
+    /// is rejected. The translated source carries `#line` directives, so the log refers
+    /// to the lines of the source given to the script.
+    #[allow(unsafe_code)]
     fn compile_shader(gl: &gl::Gl, shader_id: WebGLShaderId, source: &str) {
         gl.shader_source(shader_id.get(), &[source.as_bytes()]);
         gl.compile_shader(shader_id.get());
     }
+
+    fn shader_compile_status(
+        gl: &gl::Gl,
+        shader_id: WebGLShaderId,
+        chan: &WebGLSender<Result<(), String>>,
+    ) {
+        let mut compiled = [0];
+        unsafe {
+            gl.get_shader_iv(shader_id.get(), gl::COMPILE_STATUS, &mut compiled);
+        }
+        if compiled[0] != 0 {
+            return chan.send(Ok(())).unwrap();
+        }
+        let log = gl.get_shader_info_log(shader_id.get());
+        chan.send(Err(from_names_in_compiled_log(&log))).unwrap();
+    }
 }
 
 /// ANGLE adds a `_u` prefix to variable names:
@@ -1355,6 +2056,27 @@ fn from_name_in_compiled_shader(s: &str) -> String {
     })
 }
 
+/// Strips the ANGLE prefix from the identifiers quoted by a driver's info log.
+fn from_names_in_compiled_log(log: &str) -> String {
+    let is_identifier = |c: char| c == '_' || c.is_alphanumeric();
+    let mut mapped = String::with_capacity(log.len());
+    let mut rest = log;
+    while let Some(start) = rest.find(is_identifier) {
+        mapped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c: char| !is_identifier(c)).unwrap_or(rest.len());
+        let word = &rest[..end];
+        mapped.push_str(if word.len() > ANGLE_NAME_PREFIX.len() && word.starts_with(ANGLE_NAME_PREFIX) {
+            &word[ANGLE_NAME_PREFIX.len()..]
+        } else {
+            word
+        });
+        rest = &rest[end..];
+    }
+    mapped.push_str(rest);
+    mapped
+}
+
 fn map_dot_separated<F: Fn(&str, &mut String)>(s: &str, f: F) -> String {
     let mut iter = s.split('.');
     let mut mapped = String::new();
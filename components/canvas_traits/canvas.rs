@@ -2,15 +2,21 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app_units::Au;
 use cssparser::RGBA;
 use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D};
 use ipc_channel::ipc::IpcSender;
 use serde_bytes::ByteBuf;
 use std::default::Default;
 use std::str::FromStr;
+use style::computed_values::font_variant_caps::T as FontVariantCaps;
+use style::properties::ComputedValues;
+use style::properties::style_structs::Font as FontStyleStruct;
+use style::values::computed::font::{FontFamily, FontFamilyList, FontSize, FontStretch};
+use style::values::computed::font::{FontStyle, FontWeight, SingleFontFamily};
 use webrender_api;
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub enum FillRule {
     Nonzero,
     Evenodd,
@@ -38,22 +44,28 @@ pub struct CanvasImageData {
 pub enum Canvas2dMsg {
     Arc(Point2D<f32>, f32, f32, f32, bool),
     ArcTo(Point2D<f32>, Point2D<f32>, f32),
-    DrawImage(ByteBuf, Size2D<f64>, Rect<f64>, Rect<f64>, bool),
-    DrawImageSelf(Size2D<f64>, Rect<f64>, Rect<f64>, bool),
+    DrawImage(ByteBuf, Size2D<f64>, Rect<f64>, Rect<f64>, Option<SmoothingQuality>),
+    DrawImageSelf(Size2D<f64>, Rect<f64>, Rect<f64>, Option<SmoothingQuality>),
     DrawImageInOther(
-        CanvasId, Size2D<f64>, Rect<f64>, Rect<f64>, bool),
+        CanvasId, Size2D<f64>, Rect<f64>, Rect<f64>, Option<SmoothingQuality>),
     BeginPath,
     BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
     ClearRect(Rect<f32>),
-    Clip,
+    Clip(FillRule),
+    ClipPath2D(Vec<PathSegment>, FillRule),
     ClosePath,
     Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
-    Fill,
-    FillText(String, f64, f64, Option<f64>),
+    Fill(FillRule),
+    FillPath2D(Vec<PathSegment>, FillRule),
+    FillText(String, f64, f64, Option<f64>, Direction),
     FillRect(Rect<f32>),
     GetImageData(Rect<i32>, Size2D<f64>, IpcSender<ByteBuf>),
     IsPointInPath(f64, f64, FillRule, IpcSender<bool>),
+    IsPointInPath2D(Vec<PathSegment>, f64, f64, FillRule, IpcSender<bool>),
+    IsPointInStroke(f64, f64, IpcSender<bool>),
+    IsPointInStroke2D(Vec<PathSegment>, f64, f64, IpcSender<bool>),
     LineTo(Point2D<f32>),
+    MeasureText(String, Direction, IpcSender<TextMetrics>),
     MoveTo(Point2D<f32>),
     PutImageData(ByteBuf, Vector2D<f64>, Size2D<f64>, Rect<f64>),
     QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
@@ -62,6 +74,8 @@ pub enum Canvas2dMsg {
     SaveContext,
     StrokeRect(Rect<f32>),
     Stroke,
+    StrokePath2D(Vec<PathSegment>),
+    StrokeText(String, f64, f64, Option<f64>, Direction),
     SetFillStyle(FillOrStrokeStyle),
     SetStrokeStyle(FillOrStrokeStyle),
     SetLineWidth(f32),
@@ -75,6 +89,10 @@ pub enum Canvas2dMsg {
     SetShadowOffsetY(f64),
     SetShadowBlur(f64),
     SetShadowColor(RGBA),
+    SetFilter(Vec<FilterFunction>),
+    SetFont(CanvasFontStyle),
+    SetTextAlign(TextAlign),
+    SetTextBaseline(TextBaseline),
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -87,6 +105,21 @@ pub enum FromScriptMsg {
     SendPixels(IpcSender<Option<ByteBuf>>),
 }
 
+/// A segment of the path held by a `Path2D` object, which is replayed by the
+/// canvas paint thread whenever the path is drawn or hit tested.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum PathSegment {
+    ClosePath,
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
+    BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    ArcTo(Point2D<f32>, Point2D<f32>, f32),
+    Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
+    /// Maps the points of the following segments through this transform.
+    Transform(Transform2D<f32>),
+}
+
 #[derive(Clone, Deserialize, MallocSizeOf, Serialize)]
 pub struct CanvasGradientStop {
     pub offset: f64,
@@ -174,6 +207,42 @@ pub enum FillOrStrokeStyle {
     Surface(SurfaceStyle),
 }
 
+/// The quality of the smoothing applied to scaled images, `None` being used
+/// where smoothing is disabled.
+///
+/// <https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingquality>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum SmoothingQuality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Default for SmoothingQuality {
+    fn default() -> SmoothingQuality {
+        SmoothingQuality::Low
+    }
+}
+
+/// A function of the `filter` attribute of a 2D context, with lengths in
+/// pixels and amounts as numbers.
+///
+/// <https://drafts.fxtf.org/filter-effects/#typedef-filter-function>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum FilterFunction {
+    Blur(f32),
+    Brightness(f32),
+    Contrast(f32),
+    DropShadow(Vector2D<f32>, f32, RGBA),
+    Grayscale(f32),
+    /// A rotation of the hue, in radians.
+    HueRotate(f32),
+    Invert(f32),
+    Opacity(f32),
+    Saturate(f32),
+    Sepia(f32),
+}
+
 #[derive(Clone, Copy, Deserialize, MallocSizeOf, PartialEq, Serialize)]
 pub enum LineCapStyle {
     Butt = 0,
@@ -214,6 +283,140 @@ impl FromStr for LineJoinStyle {
     }
 }
 
+/// The computed value of the `font` attribute of a 2D context.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CanvasFontStyle {
+    pub font_family: Vec<SingleFontFamily>,
+    pub font_style: FontStyle,
+    pub font_variant_caps: FontVariantCaps,
+    pub font_weight: FontWeight,
+    pub font_size: Au,
+    pub font_stretch: FontStretch,
+}
+
+impl CanvasFontStyle {
+    pub fn from_style_struct(font: &FontStyleStruct) -> CanvasFontStyle {
+        CanvasFontStyle {
+            font_family: font.font_family.0.iter().cloned().collect(),
+            font_style: font.font_style,
+            font_variant_caps: font.font_variant_caps,
+            font_weight: font.font_weight,
+            font_size: font.font_size.size(),
+            font_stretch: font.font_stretch,
+        }
+    }
+
+    pub fn to_style_struct(&self) -> FontStyleStruct {
+        let families = self.font_family.clone().into_boxed_slice();
+        // The properties that the canvas `font` attribute doesn't set keep their initial values.
+        let mut font = ComputedValues::initial_values().get_font().clone();
+        font.set_font_family(FontFamily(FontFamilyList::new(families)));
+        font.set_font_style(self.font_style);
+        font.set_font_variant_caps(self.font_variant_caps);
+        font.set_font_weight(self.font_weight);
+        font.set_font_size(FontSize {
+            size: self.font_size.into(),
+            keyword_info: None,
+        });
+        font.set_font_stretch(self.font_stretch);
+        font.compute_font_hash();
+        font
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+}
+
+impl FromStr for TextAlign {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<TextAlign, ()> {
+        match string {
+            "start" => Ok(TextAlign::Start),
+            "end" => Ok(TextAlign::End),
+            "left" => Ok(TextAlign::Left),
+            "right" => Ok(TextAlign::Right),
+            "center" => Ok(TextAlign::Center),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for TextAlign {
+    fn default() -> TextAlign {
+        TextAlign::Start
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextBaseline {
+    Top,
+    Hanging,
+    Middle,
+    Alphabetic,
+    Ideographic,
+    Bottom,
+}
+
+impl FromStr for TextBaseline {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<TextBaseline, ()> {
+        match string {
+            "top" => Ok(TextBaseline::Top),
+            "hanging" => Ok(TextBaseline::Hanging),
+            "middle" => Ok(TextBaseline::Middle),
+            "alphabetic" => Ok(TextBaseline::Alphabetic),
+            "ideographic" => Ok(TextBaseline::Ideographic),
+            "bottom" => Ok(TextBaseline::Bottom),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for TextBaseline {
+    fn default() -> TextBaseline {
+        TextBaseline::Alphabetic
+    }
+}
+
+/// The resolved direction of canvas text; `inherit` is resolved by script
+/// against the canvas element before text is drawn or measured.
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Default for Direction {
+    fn default() -> Direction {
+        Direction::Ltr
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/#textmetrics>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct TextMetrics {
+    pub width: f64,
+    pub actual_bounding_box_left: f64,
+    pub actual_bounding_box_right: f64,
+    pub font_bounding_box_ascent: f64,
+    pub font_bounding_box_descent: f64,
+    pub actual_bounding_box_ascent: f64,
+    pub actual_bounding_box_descent: f64,
+    pub em_height_ascent: f64,
+    pub em_height_descent: f64,
+    pub hanging_baseline: f64,
+    pub alphabetic_baseline: f64,
+    pub ideographic_baseline: f64,
+}
+
 #[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
 pub enum RepetitionStyle {
     Repeat,
@@ -7,6 +7,7 @@
 
 #![deny(unsafe_code)]
 
+extern crate app_units;
 extern crate cssparser;
 extern crate euclid;
 extern crate gleam;
@@ -18,6 +19,7 @@ extern crate offscreen_gl_context;
 #[macro_use] extern crate serde;
 extern crate serde_bytes;
 extern crate servo_config;
+extern crate style;
 extern crate webrender_api;
 
 pub mod canvas;
@@ -4,10 +4,11 @@
 
 use euclid::Size2D;
 use gleam::gl;
+use ipc_channel::ipc::IpcSender;
 use offscreen_gl_context::{GLContextAttributes, GLLimits};
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU64};
 use webrender_api::{DocumentId, ImageKey, PipelineId};
 
 /// Sender type used in WebGLCommands.
@@ -26,13 +27,21 @@ pub use ::webgl_channel::WebGLChan;
 /// WebGL Message API
 #[derive(Clone, Deserialize, Serialize)]
 pub enum WebGLMsg {
-    /// Creates a new WebGLContext.
-    CreateContext(WebGLVersion, Size2D<i32>, GLContextAttributes,
+    /// Creates a new WebGLContext. The `IpcSender` is notified if the WebGL thread
+    /// loses the context to make room for other contexts.
+    CreateContext(WebGLVersion, Size2D<i32>, GLContextAttributes, IpcSender<WebGLContextId>,
                   WebGLSender<Result<(WebGLCreateContextResult), String>>),
     /// Resizes a WebGLContext.
     ResizeContext(WebGLContextId, Size2D<i32>, WebGLSender<Result<(), String>>),
     /// Drops a WebGLContext.
     RemoveContext(WebGLContextId),
+    /// Releases the GL context of a WebGLContext, which keeps answering the
+    /// WebGLCommands it receives with default values until it's restored.
+    LoseContext(WebGLContextId),
+    /// Creates a new GL context with the attributes of a lost WebGLContext.
+    /// The restored context gets a new WebGLContextId.
+    RestoreContext(WebGLContextId, Size2D<i32>, IpcSender<WebGLContextId>,
+                   WebGLSender<Result<(WebGLCreateContextResult), String>>),
     /// Runs a WebGLCommand in a specific WebGLContext.
     WebGLCommand(WebGLContextId, WebGLCommand),
     /// Runs a WebVRCommand in a specific WebGLContext.
@@ -66,7 +75,9 @@ pub struct WebGLCreateContextResult {
     /// How the WebGLContext is shared with WebRender.
     pub share_mode: WebGLContextShareMode,
     /// The GLSL version supported by the context.
-    pub glsl_version: WebGLSLVersion
+    pub glsl_version: WebGLSLVersion,
+    /// Implementation limits and strings used to gate WebGL extensions.
+    pub capabilities: GLContextCapabilities,
 }
 
 #[derive(Clone, Copy, Deserialize, MallocSizeOf, Serialize)]
@@ -97,6 +108,20 @@ pub struct WebGLSLVersion {
     pub minor: u32,
 }
 
+/// Implementation-dependent values of a GL context that aren't part of `GLLimits`
+/// but are needed to decide which WebGL extensions can be exposed.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct GLContextCapabilities {
+    /// MAX_DRAW_BUFFERS, 1 when multiple render targets aren't available.
+    pub max_draw_buffers: u32,
+    /// MAX_COLOR_ATTACHMENTS, 1 when multiple render targets aren't available.
+    pub max_color_attachments: u32,
+    /// The unmasked VENDOR string.
+    pub vendor: String,
+    /// The unmasked RENDERER string.
+    pub renderer: String,
+}
+
 /// Helper struct to send WebGLCommands to a specific WebGLContext.
 #[derive(Clone, Deserialize, MallocSizeOf, Serialize)]
 pub struct WebGLMsgSender {
@@ -144,6 +169,22 @@ impl WebGLMsgSender {
         self.sender.send(WebGLMsg::RemoveContext(self.ctx_id))
     }
 
+    /// Send a lose context message
+    #[inline]
+    pub fn send_lose(&self) -> WebGLSendResult {
+        self.sender.send(WebGLMsg::LoseContext(self.ctx_id))
+    }
+
+    /// Send a restore context message
+    #[inline]
+    pub fn send_restore(&self,
+                        size: Size2D<i32>,
+                        loss_sender: IpcSender<WebGLContextId>,
+                        sender: WebGLSender<Result<(WebGLCreateContextResult), String>>)
+                        -> WebGLSendResult {
+        self.sender.send(WebGLMsg::RestoreContext(self.ctx_id, size, loss_sender, sender))
+    }
+
     #[inline]
     pub fn send_update_wr_image(&self, sender: WebGLSender<ImageKey>) -> WebGLSendResult {
         self.sender.send(WebGLMsg::UpdateWebRenderImage(self.ctx_id, sender))
@@ -169,6 +210,10 @@ pub enum WebGLCommand {
     BindAttribLocation(WebGLProgramId, u32, String),
     BufferData(u32, ByteBuf, u32),
     BufferSubData(u32, isize, ByteBuf),
+    CopyBufferSubData(u32, u32, i64, i64, i64),
+    GetBufferSubData(u32, usize, usize, WebGLSender<ByteBuf>),
+    BindBufferBase(u32, u32, Option<WebGLBufferId>),
+    BindBufferRange(u32, u32, Option<WebGLBufferId>, i64, i64),
     Clear(u32),
     ClearColor(f32, f32, f32, f32),
     ClearDepth(f32),
@@ -204,15 +249,25 @@ pub enum WebGLCommand {
     EnableVertexAttribArray(u32),
     FramebufferRenderbuffer(u32, u32, u32, Option<WebGLRenderbufferId>),
     FramebufferTexture2D(u32, u32, u32, Option<WebGLTextureId>, i32),
+    FramebufferTextureLayer(u32, u32, Option<WebGLTextureId>, i32, i32),
+    BlitFramebuffer(i32, i32, i32, i32, i32, i32, i32, i32, u32, u32),
+    InvalidateFramebuffer(u32, Vec<u32>),
+    InvalidateSubFramebuffer(u32, Vec<u32>, i32, i32, i32, i32),
+    ReadBuffer(u32),
+    DrawBuffers(Vec<u32>),
     GetExtensions(WebGLSender<String>),
     GetShaderPrecisionFormat(u32, u32, WebGLSender<(i32, i32, i32)>),
     GetUniformLocation(WebGLProgramId, String, WebGLSender<i32>),
     GetShaderInfoLog(WebGLShaderId, WebGLSender<String>),
+    /// Whether the driver compiled the shader, or the driver's errors if it didn't.
+    GetShaderCompileStatus(WebGLShaderId, WebGLSender<Result<(), String>>),
     GetProgramInfoLog(WebGLProgramId, WebGLSender<String>),
     GetFramebufferAttachmentParameter(u32, u32, u32, WebGLSender<i32>),
     GetRenderbufferParameter(u32, u32, WebGLSender<i32>),
     PolygonOffset(f32, f32),
     RenderbufferStorage(u32, u32, i32, i32),
+    RenderbufferStorageMultisample(u32, i32, u32, i32, i32),
+    GetInternalFormatSamples(u32, u32, WebGLSender<Vec<i32>>),
     ReadPixels(i32, i32, i32, i32, u32, u32, WebGLSender<ByteBuf>),
     SampleCoverage(f32, bool),
     Scissor(i32, i32, i32, i32),
@@ -253,6 +308,64 @@ pub enum WebGLCommand {
     SetViewport(i32, i32, i32, i32),
     TexImage2D(u32, i32, i32, i32, i32, u32, u32, ByteBuf),
     TexSubImage2D(u32, i32, i32, i32, i32, i32, u32, u32, ByteBuf),
+    CompressedTexImage2D {
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        data: ByteBuf,
+    },
+    CompressedTexSubImage2D {
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data: ByteBuf,
+    },
+    TexStorage2D(u32, i32, u32, i32, i32),
+    TexStorage3D(u32, i32, u32, i32, i32, i32),
+    TexImage3D {
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        data: ByteBuf,
+    },
+    TexSubImage3D {
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        data: ByteBuf,
+    },
+    TexSubImage3DPbo {
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        offset: i64,
+    },
+    CopyTexSubImage3D(u32, i32, i32, i32, i32, i32, i32, i32, i32),
     DrawingBufferWidth(WebGLSender<i32>),
     DrawingBufferHeight(WebGLSender<i32>),
     Finish(WebGLSender<()>),
@@ -261,6 +374,36 @@ pub enum WebGLCommand {
     CreateVertexArray(WebGLSender<Option<WebGLVertexArrayId>>),
     DeleteVertexArray(WebGLVertexArrayId),
     BindVertexArray(Option<WebGLVertexArrayId>),
+    CreateSampler(WebGLSender<Option<WebGLSamplerId>>),
+    DeleteSampler(WebGLSamplerId),
+    BindSampler(u32, Option<WebGLSamplerId>),
+    SetSamplerParameterInt(WebGLSamplerId, u32, i32),
+    SetSamplerParameterFloat(WebGLSamplerId, u32, f32),
+    GetSamplerParameterInt(WebGLSamplerId, u32, WebGLSender<i32>),
+    GetSamplerParameterFloat(WebGLSamplerId, u32, WebGLSender<f32>),
+    CreateQuery(WebGLSender<Option<WebGLQueryId>>),
+    DeleteQuery(WebGLQueryId),
+    BeginQuery(u32, WebGLQueryId),
+    EndQuery(u32),
+    GetQueryState(WebGLQueryId, u32, WebGLSender<u32>),
+    GetQueryStateU64(WebGLQueryId, u32, WebGLSender<u64>),
+    QueryCounter(WebGLQueryId, u32),
+    GetTimestamp(WebGLSender<u64>),
+    GetGpuDisjoint(WebGLSender<bool>),
+    FenceSync(WebGLSender<Option<WebGLSyncId>>),
+    ClientWaitSync(WebGLSyncId, u32, u64, WebGLSender<u32>),
+    WaitSync(WebGLSyncId),
+    IsSyncSignaled(WebGLSyncId, WebGLSender<bool>),
+    DeleteSync(WebGLSyncId),
+    CreateTransformFeedback(WebGLSender<Option<WebGLTransformFeedbackId>>),
+    DeleteTransformFeedback(WebGLTransformFeedbackId),
+    BindTransformFeedback(u32, Option<WebGLTransformFeedbackId>),
+    BeginTransformFeedback(u32),
+    EndTransformFeedback,
+    PauseTransformFeedback,
+    ResumeTransformFeedback,
+    TransformFeedbackVaryings(WebGLProgramId, Vec<String>, u32),
+    GetTransformFeedbackVarying(WebGLProgramId, u32, WebGLSender<(i32, u32, String)>),
     GetParameterBool(ParameterBool, WebGLSender<bool>),
     GetParameterBool4(ParameterBool4, WebGLSender<[bool; 4]>),
     GetParameterInt(ParameterInt, WebGLSender<i32>),
@@ -298,19 +441,19 @@ pub enum WebGLCommand {
 }
 
 macro_rules! define_resource_id_struct {
-    ($name:ident) => {
+    ($name:ident, $non_zero:ident, $ty:ty) => {
         #[derive(Clone, Copy, Eq, Hash, PartialEq)]
-        pub struct $name(NonZeroU32);
+        pub struct $name($non_zero);
 
         impl $name {
             #[allow(unsafe_code)]
             #[inline]
-            pub unsafe fn new(id: u32) -> Self {
-                $name(NonZeroU32::new_unchecked(id))
+            pub unsafe fn new(id: $ty) -> Self {
+                $name($non_zero::new_unchecked(id))
             }
 
             #[inline]
-            pub fn get(self) -> u32 {
+            pub fn get(self) -> $ty {
                 self.0.get()
             }
         }
@@ -320,14 +463,17 @@ macro_rules! define_resource_id_struct {
 
 macro_rules! define_resource_id {
     ($name:ident) => {
-        define_resource_id_struct!($name);
+        define_resource_id!($name, NonZeroU32, u32);
+    };
+    ($name:ident, $non_zero:ident, $ty:ty) => {
+        define_resource_id_struct!($name, $non_zero, $ty);
 
         #[allow(unsafe_code)]
         impl<'de> ::serde::Deserialize<'de> for $name {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where D: ::serde::Deserializer<'de>
             {
-                let id = try!(u32::deserialize(deserializer));
+                let id = try!(<$ty as ::serde::Deserialize>::deserialize(deserializer));
                 if id == 0 {
                     Err(::serde::de::Error::custom("expected a non-zero value"))
                 } else {
@@ -373,6 +519,11 @@ define_resource_id!(WebGLTextureId);
 define_resource_id!(WebGLProgramId);
 define_resource_id!(WebGLShaderId);
 define_resource_id!(WebGLVertexArrayId);
+define_resource_id!(WebGLSamplerId);
+define_resource_id!(WebGLQueryId);
+define_resource_id!(WebGLTransformFeedbackId);
+// Sync objects are opaque pointers rather than integer names in GL.
+define_resource_id!(WebGLSyncId, NonZeroU64, u64);
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, MallocSizeOf, Ord)]
 #[derive(PartialEq, PartialOrd, Serialize)]
@@ -531,7 +682,18 @@ parameters! {
             FrontFace = gl::FRONT_FACE,
             GenerateMipmapHint = gl::GENERATE_MIPMAP_HINT,
             GreenBits = gl::GREEN_BITS,
+            Max3DTextureSize = gl::MAX_3D_TEXTURE_SIZE,
+            MaxArrayTextureLayers = gl::MAX_ARRAY_TEXTURE_LAYERS,
+            MaxColorAttachments = gl::MAX_COLOR_ATTACHMENTS,
+            MaxSamples = gl::MAX_SAMPLES,
+            MaxTransformFeedbackSeparateAttribs = gl::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS,
+            MaxUniformBufferBindings = gl::MAX_UNIFORM_BUFFER_BINDINGS,
+            MaxUniformBufferOffsetAlignment = gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT,
             PackAlignment = gl::PACK_ALIGNMENT,
+            PackRowLength = gl::PACK_ROW_LENGTH,
+            PackSkipPixels = gl::PACK_SKIP_PIXELS,
+            PackSkipRows = gl::PACK_SKIP_ROWS,
+            ReadBuffer = gl::READ_BUFFER,
             RedBits = gl::RED_BITS,
             SampleBuffers = gl::SAMPLE_BUFFERS,
             Samples = gl::SAMPLES,
@@ -553,6 +715,11 @@ parameters! {
             StencilWritemask = gl::STENCIL_WRITEMASK,
             SubpixelBits = gl::SUBPIXEL_BITS,
             UnpackAlignment = gl::UNPACK_ALIGNMENT,
+            UnpackImageHeight = gl::UNPACK_IMAGE_HEIGHT,
+            UnpackRowLength = gl::UNPACK_ROW_LENGTH,
+            UnpackSkipImages = gl::UNPACK_SKIP_IMAGES,
+            UnpackSkipPixels = gl::UNPACK_SKIP_PIXELS,
+            UnpackSkipRows = gl::UNPACK_SKIP_ROWS,
         }),
         Int2(ParameterInt2 {
             MaxViewportDims = gl::MAX_VIEWPORT_DIMS,
@@ -585,8 +752,17 @@ parameters! {
     TexParameter {
         Float(TexParameterFloat {
             TextureMaxAnisotropyExt = gl::TEXTURE_MAX_ANISOTROPY_EXT,
+            TextureMaxLod = gl::TEXTURE_MAX_LOD,
+            TextureMinLod = gl::TEXTURE_MIN_LOD,
         }),
         Int(TexParameterInt {
+            TextureBaseLevel = gl::TEXTURE_BASE_LEVEL,
+            TextureCompareFunc = gl::TEXTURE_COMPARE_FUNC,
+            TextureCompareMode = gl::TEXTURE_COMPARE_MODE,
+            TextureImmutableFormat = gl::TEXTURE_IMMUTABLE_FORMAT,
+            TextureImmutableLevels = gl::TEXTURE_IMMUTABLE_LEVELS,
+            TextureMaxLevel = gl::TEXTURE_MAX_LEVEL,
+            TextureWrapR = gl::TEXTURE_WRAP_R,
             TextureWrapS = gl::TEXTURE_WRAP_S,
             TextureWrapT = gl::TEXTURE_WRAP_T,
         }),
@@ -18,9 +18,11 @@ use net_traits::image::base::Image;
 use net_traits::image::base::PixelFormat;
 use profile_traits::time::{self, ProfilerCategory, profile};
 use script_traits::{AnimationState, AnimationTickType, ConstellationMsg, LayoutControlMsg};
-use script_traits::{MouseButton, MouseEventType, ScrollState, TouchEventType, TouchId};
+use script_traits::{MouseButton, MouseEventType, ScrollSnapPoints};
+use script_traits::{ScrollState, TouchEventType, TouchId};
 use script_traits::{UntrustedNodeAddress, WindowSizeData, WindowSizeType};
 use script_traits::CompositorEvent::{MouseMoveEvent, MouseButtonEvent, TouchEvent};
+use scroll::{SmoothScroll, snap_position};
 use servo_config::opts;
 use servo_geometry::DeviceIndependentPixel;
 use std::collections::HashMap;
@@ -30,14 +32,14 @@ use std::io::Write;
 use std::num::NonZeroU32;
 use std::rc::Rc;
 use std::sync::mpsc::Sender;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use style_traits::{CSSPixel, DevicePixel, PinchZoomFactor};
 use style_traits::cursor::CursorKind;
 use style_traits::viewport::ViewportConstraints;
 use time::{now, precise_time_ns, precise_time_s};
 use touch::{TouchHandler, TouchAction};
 use webrender;
-use webrender_api::{self, DeviceIntPoint, DevicePoint, HitTestFlags, HitTestResult};
+use webrender_api::{self, DeviceIntPoint, DevicePoint, ExternalScrollId, HitTestFlags, HitTestResult, LayoutPoint};
 use webrender_api::{LayoutVector2D, ScrollLocation, DeviceUintRect, DeviceUintPoint, DeviceUintSize};
 use windowing::{self, EmbedderCoordinates, MouseWindowEvent, WebRenderDebugOption, WindowMethods};
 
@@ -59,6 +61,10 @@ enum NotReadyToPaint {
 const MAX_ZOOM: f32 = 8.0;
 const MIN_ZOOM: f32 = 0.1;
 
+/// How long to wait after the last scroll event before snapping the scroll position, in
+/// milliseconds, so that wheel gestures and flings come to rest first.
+const SCROLL_SNAP_DELAY_MS: u64 = 100;
+
 trait ConvertPipelineIdFromWebRender {
     fn from_webrender(&self) -> PipelineId;
 }
@@ -168,7 +174,14 @@ pub struct IOCompositor<Window: WindowMethods, Back: gfx_hal::Backend> {
     /// Whether a scroll is in progress; i.e. whether the user's fingers are down.
     scroll_in_progress: bool,
 
-    in_scroll_transaction: Option<Instant>,
+    /// The scroll gesture in progress, until the scroll positions have been snapped.
+    in_scroll_transaction: Option<ScrollTransaction>,
+
+    /// The scroll snap points of each scroll frame, as exported by layout.
+    scroll_snap_points: HashMap<ExternalScrollId, ScrollSnapPoints>,
+
+    /// The scrolls being animated, from smooth scrolls requested by script and from snapping.
+    smooth_scrolls: Vec<SmoothScroll>,
 
     /// The webrender renderer.
     webrender: webrender::Renderer<Back>,
@@ -190,6 +203,15 @@ pub struct IOCompositor<Window: WindowMethods, Back: gfx_hal::Backend> {
     embedder_coordinates: EmbedderCoordinates,
 }
 
+/// A scroll gesture, which lasts until it has come to rest.
+struct ScrollTransaction {
+    /// The time of the last scroll event.
+    last_scroll_time: Instant,
+    /// The scroll offsets of the scroll frames with snap points when the gesture started, to
+    /// find out which of them it scrolled.
+    start_offsets: HashMap<ExternalScrollId, LayoutVector2D>,
+}
+
 #[derive(Clone, Copy)]
 struct ScrollZoomEvent {
     /// Change the pinch zoom level by this factor
@@ -322,6 +344,8 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
             ready_to_save_state: ReadyState::Unknown,
             scroll_in_progress: false,
             in_scroll_transaction: None,
+            scroll_snap_points: HashMap::new(),
+            smooth_scrolls: Vec::new(),
             webrender: state.webrender,
             webrender_document: state.webrender_document,
             webrender_api: state.webrender_api,
@@ -425,6 +449,16 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
                 self.constrain_viewport(pipeline_id, constraints);
             }
 
+            (Msg::SetScrollSnapPoints(pipeline_id, snap_points),
+             ShutdownState::NotShuttingDown) => {
+                self.set_scroll_snap_points(pipeline_id, snap_points);
+            }
+
+            (Msg::SmoothScroll(scroll_state), ShutdownState::NotShuttingDown) => {
+                let end = LayoutPoint::new(-scroll_state.scroll_offset.x, -scroll_state.scroll_offset.y);
+                self.start_smooth_scroll(scroll_state.scroll_id, end);
+            }
+
             (Msg::IsReadyToSaveImageReply(is_ready), ShutdownState::NotShuttingDown) => {
                 assert_eq!(self.ready_to_save_state, ReadyState::WaitingForConstellationReply);
                 if is_ready {
@@ -470,7 +504,8 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
 
             (Msg::LoadComplete(_), ShutdownState::NotShuttingDown) => {
                 // If we're painting in headless mode, schedule a recomposite.
-                if opts::get().output_file.is_some() || opts::get().exit_after_load {
+                if opts::get().output_file.is_some() || opts::get().print_to_pdf.is_some() ||
+                   opts::get().exit_after_load {
                     self.composite_if_necessary(CompositingReason::Headless);
                 }
             },
@@ -582,6 +617,10 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
 
     fn remove_pipeline_root_layer(&mut self, pipeline_id: PipelineId) {
         self.pipeline_details.remove(&pipeline_id);
+
+        let webrender_pipeline_id = pipeline_id.to_webrender();
+        self.scroll_snap_points.retain(|scroll_id, _| scroll_id.pipeline_id() != webrender_pipeline_id);
+        self.smooth_scrolls.retain(|scroll| scroll.scroll_id.pipeline_id() != webrender_pipeline_id);
     }
 
     fn send_window_size(&self, size_type: WindowSizeType) {
@@ -834,7 +873,7 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
     fn on_scroll_window_event(&mut self,
                               scroll_location: ScrollLocation,
                               cursor: DeviceIntPoint) {
-        self.in_scroll_transaction = Some(Instant::now());
+        self.start_or_continue_scroll_transaction();
         self.pending_scroll_zoom_events.push(ScrollZoomEvent {
             magnification: 1.0,
             scroll_location: scroll_location,
@@ -843,6 +882,29 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
         });
     }
 
+    /// Notes a scroll event of the current scroll gesture, starting a new one if there is none.
+    fn start_or_continue_scroll_transaction(&mut self) {
+        if let Some(ref mut transaction) = self.in_scroll_transaction {
+            transaction.last_scroll_time = Instant::now();
+            return;
+        }
+
+        // Pending scroll events have not been sent to WebRender yet, so these are the offsets
+        // from before the gesture.
+        let mut start_offsets = HashMap::new();
+        if !self.scroll_snap_points.is_empty() {
+            for scroll_layer_state in self.webrender_api.get_scroll_node_state(self.webrender_document) {
+                if self.scroll_snap_points.contains_key(&scroll_layer_state.id) {
+                    start_offsets.insert(scroll_layer_state.id, scroll_layer_state.scroll_offset);
+                }
+            }
+        }
+        self.in_scroll_transaction = Some(ScrollTransaction {
+            last_scroll_time: Instant::now(),
+            start_offsets: start_offsets,
+        });
+    }
+
     fn on_scroll_start_window_event(&mut self,
                                     scroll_location: ScrollLocation,
                                     cursor: DeviceIntPoint) {
@@ -955,7 +1017,7 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
                    pipeline_ids.push(*pipeline_id);
             }
         }
-        let animation_state = if pipeline_ids.is_empty() {
+        let animation_state = if pipeline_ids.is_empty() && !self.scroll_animations_active() {
             windowing::AnimationState::Idle
         } else {
             windowing::AnimationState::Animating
@@ -985,6 +1047,116 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
         }
     }
 
+    /// Whether there are scrolls to animate, or a scroll gesture to wait for to come to rest in
+    /// order to snap it.
+    fn scroll_animations_active(&self) -> bool {
+        !self.smooth_scrolls.is_empty() ||
+            (self.in_scroll_transaction.is_some() && !self.scroll_snap_points.is_empty())
+    }
+
+    fn set_scroll_snap_points(&mut self, pipeline_id: PipelineId, snap_points: Vec<ScrollSnapPoints>) {
+        let webrender_pipeline_id = pipeline_id.to_webrender();
+        self.scroll_snap_points.retain(|scroll_id, _| scroll_id.pipeline_id() != webrender_pipeline_id);
+        for scroll_snap_points in snap_points {
+            self.scroll_snap_points.insert(scroll_snap_points.scroll_id, scroll_snap_points);
+        }
+    }
+
+    /// Starts animating a scroll of the given scroll frame to the given position, replacing any
+    /// scroll of it that is already being animated.
+    fn start_smooth_scroll(&mut self, scroll_id: ExternalScrollId, end: LayoutPoint) {
+        self.smooth_scrolls.retain(|scroll| scroll.scroll_id != scroll_id);
+
+        let start = self.webrender_api
+            .get_scroll_node_state(self.webrender_document)
+            .into_iter()
+            .find(|scroll_layer_state| scroll_layer_state.id == scroll_id)
+            .map(|scroll_layer_state| {
+                LayoutPoint::new(-scroll_layer_state.scroll_offset.x, -scroll_layer_state.scroll_offset.y)
+            });
+        let start = match start {
+            Some(start) => start,
+            None => {
+                // WebRender doesn't know about this scroll frame yet, so there is nothing to
+                // animate from; just scroll it once it does.
+                let mut txn = webrender_api::Transaction::new();
+                txn.scroll_node_with_id(end, scroll_id, webrender_api::ScrollClamping::ToContentBounds);
+                txn.generate_frame();
+                self.webrender_api.send_transaction(self.webrender_document, txn);
+                return;
+            },
+        };
+
+        self.smooth_scrolls.push(SmoothScroll {
+            scroll_id: scroll_id,
+            start: start,
+            end: end,
+            start_time: precise_time_s(),
+        });
+        self.window.set_animation_state(windowing::AnimationState::Animating);
+    }
+
+    /// Advances the scrolls being animated to the current time.
+    fn tick_smooth_scrolls(&mut self) {
+        let now = precise_time_s();
+        let mut txn = webrender_api::Transaction::new();
+        self.smooth_scrolls.retain(|scroll| {
+            let (position, finished) = scroll.position_at(now);
+            txn.scroll_node_with_id(position, scroll.scroll_id, webrender_api::ScrollClamping::ToContentBounds);
+            !finished
+        });
+        txn.generate_frame();
+        self.webrender_api.send_transaction(self.webrender_document, txn);
+        self.waiting_for_results_of_scroll = true;
+
+        if self.smooth_scrolls.is_empty() {
+            // Let layout know where the scroll frames came to rest.
+            self.send_viewport_rects();
+        }
+    }
+
+    /// Snaps the scroll frames with scroll snap points that the last scroll gesture scrolled once
+    /// it has come to rest, by animating them to the closest snap position.
+    fn snap_scroll_positions_if_at_rest(&mut self) {
+        let at_rest = match self.in_scroll_transaction {
+            Some(ref transaction) => {
+                !self.scroll_in_progress && self.pending_scroll_zoom_events.is_empty() &&
+                    transaction.last_scroll_time.elapsed() >= Duration::from_millis(SCROLL_SNAP_DELAY_MS)
+            },
+            None => false,
+        };
+        if !at_rest {
+            return;
+        }
+        let transaction = self.in_scroll_transaction.take().unwrap();
+
+        if !self.scroll_snap_points.is_empty() {
+            for scroll_layer_state in self.webrender_api.get_scroll_node_state(self.webrender_document) {
+                // Frames that the gesture didn't scroll keep their position, as do frames being
+                // scrolled smoothly by script.
+                let scrolled = transaction.start_offsets.get(&scroll_layer_state.id)
+                    .map_or(false, |offset| *offset != scroll_layer_state.scroll_offset);
+                let animating = self.smooth_scrolls.iter().any(|scroll| scroll.scroll_id == scroll_layer_state.id);
+                if !scrolled || animating {
+                    continue;
+                }
+                let position =
+                    LayoutPoint::new(-scroll_layer_state.scroll_offset.x, -scroll_layer_state.scroll_offset.y);
+                let destination = match self.scroll_snap_points.get(&scroll_layer_state.id) {
+                    Some(snap_points) => snap_position(snap_points, position),
+                    None => continue,
+                };
+                if destination != position {
+                    self.start_smooth_scroll(scroll_layer_state.id, destination);
+                }
+            }
+        }
+
+        if !self.scroll_animations_active() && !self.animations_active() {
+            self.window.set_animation_state(windowing::AnimationState::Idle);
+        }
+    }
+
     fn constrain_viewport(&mut self, pipeline_id: PipelineId, constraints: ViewportConstraints) {
         let is_root = self.root_pipeline.as_ref().map_or(false, |root_pipeline| {
             root_pipeline.id == pipeline_id
@@ -1142,7 +1314,11 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
     pub fn composite(&mut self) {
         let target = self.composite_target;
         match self.composite_specific_target(target) {
-            Ok(_) => if opts::get().output_file.is_some() || opts::get().exit_after_load {
+            Ok(_) => if let Some(ref path) = opts::get().print_to_pdf {
+                self.print_to_pdf(path);
+                println!("Shutting down the Constellation after printing to PDF");
+                self.start_shutting_down();
+            } else if opts::get().output_file.is_some() || opts::get().exit_after_load {
                 println!("Shutting down the Constellation after generating an output file or exit flag specified");
                 self.start_shutting_down();
             },
@@ -1154,6 +1330,29 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
         }
     }
 
+    /// Asks the constellation for the root document laid out for print media, and writes the
+    /// resulting PDF document to the given file.
+    fn print_to_pdf(&self, path: &str) {
+        let top_level_browsing_context_id = match self.root_pipeline {
+            Some(ref pipeline) => pipeline.top_level_browsing_context_id,
+            None => return warn!("No document to print."),
+        };
+        let (sender, receiver) = ipc::channel().unwrap();
+        let msg = ConstellationMsg::PrintToPdf(top_level_browsing_context_id, sender);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            return warn!("Sending print request to constellation failed ({}).", e);
+        }
+        match receiver.recv() {
+            Ok(Some(pdf)) => {
+                if let Err(e) = File::create(path).and_then(|mut file| file.write_all(&pdf)) {
+                    error!("Failed to write PDF to {} ({}).", path, e);
+                }
+            },
+            Ok(None) => warn!("The document could not be printed."),
+            Err(e) => warn!("Receiving PDF from constellation failed ({:?}).", e),
+        }
+    }
+
     /// Composite either to the screen or to a png image or both.
     /// Returns Ok if composition was performed or Err if it was not possible to composite
     /// for some reason. If CompositeTarget is Window or Png no image data is returned;
@@ -1172,7 +1371,7 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
 
         let wait_for_stable_image = match target {
             CompositeTarget::WindowAndPng | CompositeTarget::PngFile => true,
-            CompositeTarget::Window => opts::get().exit_after_load,
+            CompositeTarget::Window => opts::get().exit_after_load || opts::get().print_to_pdf.is_some(),
         };
 
         if wait_for_stable_image {
@@ -1376,6 +1575,10 @@ impl<Window: WindowMethods, Back: gfx_hal::Backend> IOCompositor<Window, Back> {
         if !self.pending_scroll_zoom_events.is_empty() && !self.waiting_for_results_of_scroll {
             self.process_pending_scroll_events()
         }
+        if !self.smooth_scrolls.is_empty() && !self.waiting_for_results_of_scroll {
+            self.tick_smooth_scrolls()
+        }
+        self.snap_scroll_positions_if_at_rest();
         self.shutdown_state != ShutdownState::FinishedShuttingDown
     }
 
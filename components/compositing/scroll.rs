@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script_traits::{ScrollSnapAxis, ScrollSnapPoints, ScrollSnapStrictness};
+use std::cmp::Ordering;
+use webrender_api::{ExternalScrollId, LayoutPoint};
+
+/// The duration of smooth scrolls, in seconds.
+pub const SMOOTH_SCROLL_DURATION: f64 = 0.3;
+
+/// How close a snap position has to be for proximity snapping, as a fraction of the scrollport.
+pub const SCROLL_SNAP_PROXIMITY: f32 = 0.3;
+
+/// A scroll that is animated over several frames.
+pub struct SmoothScroll {
+    /// The scroll frame being scrolled.
+    pub scroll_id: ExternalScrollId,
+    /// The scroll position at the start of the animation.
+    pub start: LayoutPoint,
+    /// The scroll position at the end of the animation.
+    pub end: LayoutPoint,
+    /// The time the animation started, in seconds.
+    pub start_time: f64,
+}
+
+impl SmoothScroll {
+    /// Returns the scroll position at the given time, and whether the animation has finished.
+    pub fn position_at(&self, time: f64) -> (LayoutPoint, bool) {
+        let progress = ((time - self.start_time) / SMOOTH_SCROLL_DURATION).min(1.0) as f32;
+        let eased_progress = progress * progress * (3.0 - 2.0 * progress);
+        (self.start.lerp(self.end, eased_progress), progress >= 1.0)
+    }
+}
+
+/// Returns the scroll position that a scroll frame with the given snap points comes to rest at
+/// after a scroll gesture that left it at `position`.
+///
+/// <https://drafts.csswg.org/css-scroll-snap/#choosing>
+pub fn snap_position(snap_points: &ScrollSnapPoints, position: LayoutPoint) -> LayoutPoint {
+    LayoutPoint::new(
+        snap_position_along_axis(snap_points.x.as_ref(), position.x, snap_points.scrollport_size.width),
+        snap_position_along_axis(snap_points.y.as_ref(), position.y, snap_points.scrollport_size.height),
+    )
+}
+
+fn snap_position_along_axis(axis: Option<&ScrollSnapAxis>, position: f32, scrollport_size: f32) -> f32 {
+    let axis = match axis {
+        Some(axis) => axis,
+        None => return position,
+    };
+    let distance = |snap_position: f32| (snap_position - position).abs();
+    let closest = axis.positions.iter().cloned().min_by(|a, b| {
+        distance(*a).partial_cmp(&distance(*b)).unwrap_or(Ordering::Equal)
+    });
+    match closest {
+        Some(closest) if axis.strictness == ScrollSnapStrictness::Mandatory ||
+                         distance(closest) <= scrollport_size * SCROLL_SNAP_PROXIMITY => closest,
+        _ => position,
+    }
+}
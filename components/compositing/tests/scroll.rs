@@ -0,0 +1,76 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate compositing;
+extern crate euclid;
+extern crate script_traits;
+extern crate webrender_api;
+
+use compositing::scroll::{SMOOTH_SCROLL_DURATION, SmoothScroll, snap_position};
+use euclid::Size2D;
+use script_traits::{ScrollSnapAxis, ScrollSnapPoints, ScrollSnapStrictness};
+use webrender_api::{ExternalScrollId, LayoutPoint, PipelineId};
+
+fn scroll_id() -> ExternalScrollId {
+    ExternalScrollId(1, PipelineId(0, 0))
+}
+
+/// Snap points at every 100px vertically in a 100px by 100px scrollport.
+fn snap_points(strictness: ScrollSnapStrictness) -> ScrollSnapPoints {
+    ScrollSnapPoints {
+        scroll_id: scroll_id(),
+        scrollport_size: Size2D::new(100., 100.),
+        x: None,
+        y: Some(ScrollSnapAxis {
+            strictness: strictness,
+            positions: vec![0., 100., 200., 300.],
+        }),
+    }
+}
+
+#[test]
+fn test_mandatory_snapping() {
+    let snap_points = snap_points(ScrollSnapStrictness::Mandatory);
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 40.)), LayoutPoint::new(0., 0.));
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 60.)), LayoutPoint::new(0., 100.));
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 200.)), LayoutPoint::new(0., 200.));
+    // Mandatory snapping always comes to rest on a snap position, however far it is.
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 1000.)), LayoutPoint::new(0., 300.));
+    // The horizontal axis doesn't snap.
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(35., 90.)), LayoutPoint::new(35., 100.));
+}
+
+#[test]
+fn test_proximity_snapping() {
+    let snap_points = snap_points(ScrollSnapStrictness::Proximity);
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 20.)), LayoutPoint::new(0., 0.));
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 75.)), LayoutPoint::new(0., 100.));
+    // Snap positions further than 30% of the scrollport away are ignored.
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(0., 450.)), LayoutPoint::new(0., 450.));
+}
+
+#[test]
+fn test_snapping_without_positions() {
+    let mut snap_points = snap_points(ScrollSnapStrictness::Mandatory);
+    snap_points.y.as_mut().unwrap().positions.clear();
+    assert_eq!(snap_position(&snap_points, LayoutPoint::new(10., 20.)), LayoutPoint::new(10., 20.));
+}
+
+#[test]
+fn test_smooth_scroll() {
+    let scroll = SmoothScroll {
+        scroll_id: scroll_id(),
+        start: LayoutPoint::new(0., 0.),
+        end: LayoutPoint::new(0., 200.),
+        start_time: 10.,
+    };
+    assert_eq!(scroll.position_at(10.), (LayoutPoint::new(0., 0.), false));
+    // The animation eases in and out, so it is halfway through at half of its duration.
+    assert_eq!(scroll.position_at(10. + SMOOTH_SCROLL_DURATION / 2.), (LayoutPoint::new(0., 100.), false));
+    let (position, finished) = scroll.position_at(10. + SMOOTH_SCROLL_DURATION / 4.);
+    assert!(position.y > 0. && position.y < 50., "{:?} should be eased in", position);
+    assert!(!finished);
+    assert_eq!(scroll.position_at(10. + SMOOTH_SCROLL_DURATION), (LayoutPoint::new(0., 200.), true));
+    assert_eq!(scroll.position_at(20.), (LayoutPoint::new(0., 200.), true));
+}
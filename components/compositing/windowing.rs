@@ -76,6 +76,9 @@ pub enum WindowEvent {
     KeyEvent(Option<char>, Key, KeyState, KeyModifiers),
     /// Sent when Ctr+R/Apple+R is called to reload the current page.
     Reload(TopLevelBrowsingContextId),
+    /// Lay the active document of a top level browsing context out for print media, and send
+    /// it back as a PDF document.
+    PrintToPdf(TopLevelBrowsingContextId, IpcSender<Option<Vec<u8>>>),
     /// Create a new top level browsing context
     NewBrowser(ServoUrl, IpcSender<TopLevelBrowsingContextId>),
     /// Close a top level browsing context
@@ -109,6 +112,7 @@ impl Debug for WindowEvent {
             WindowEvent::Navigation(..) => write!(f, "Navigation"),
             WindowEvent::Quit => write!(f, "Quit"),
             WindowEvent::Reload(..) => write!(f, "Reload"),
+            WindowEvent::PrintToPdf(..) => write!(f, "PrintToPdf"),
             WindowEvent::NewBrowser(..) => write!(f, "NewBrowser"),
             WindowEvent::SendError(..) => write!(f, "SendError"),
             WindowEvent::CloseBrowser(..) => write!(f, "CloseBrowser"),
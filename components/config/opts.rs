@@ -67,6 +67,9 @@ pub struct Opts {
 
     pub output_file: Option<String>,
 
+    /// Where to write the page laid out for print media as a PDF document, if anywhere.
+    pub print_to_pdf: Option<String>,
+
     /// Replace unpaires surrogates in DOM strings with U+FFFD.
     /// See <https://github.com/servo/servo/issues/6564>
     pub replace_surrogates: bool,
@@ -126,6 +129,10 @@ pub struct Opts {
     /// remote Firefox devtools connections.
     pub devtools_port: Option<u16>,
 
+    /// `None` to disable the Chrome DevTools Protocol endpoint or `Some` with a port number
+    /// to listen to remote CDP connections.
+    pub cdp_port: Option<u16>,
+
     /// `None` to disable WebDriver or `Some` with a port number to start a server to listen to
     /// remote WebDriver commands.
     pub webdriver_port: Option<u16>,
@@ -504,6 +511,7 @@ pub fn default_opts() -> Opts {
         userscripts: None,
         user_stylesheets: Vec::new(),
         output_file: None,
+        print_to_pdf: None,
         replace_surrogates: false,
         gc_profile: false,
         load_webfonts_synchronously: false,
@@ -518,6 +526,7 @@ pub fn default_opts() -> Opts {
         trace_layout: false,
         debugger_port: None,
         devtools_port: None,
+        cdp_port: None,
         webdriver_port: None,
         initial_window_size: TypedSize2D::new(1024, 740),
         user_agent: default_user_agent_string(DEFAULT_USER_AGENT).into(),
@@ -562,6 +571,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
     opts.optflag("c", "cpu", "CPU painting");
     opts.optflag("g", "gpu", "GPU painting");
     opts.optopt("o", "output", "Output file", "output.png");
+    opts.optopt("", "print-to-pdf", "Print the page to a PDF file after load", "output.pdf");
     opts.optopt("s", "size", "Size of tiles", "512");
     opts.optopt("", "device-pixel-ratio", "Device pixels per px", "");
     opts.optflagopt("p", "profile", "Time profiler flag and either a TSV output filename \
@@ -585,6 +595,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
     opts.optflag("F", "soft-fail", "Display about:failure on thread failure instead of exiting");
     opts.optflagopt("", "remote-debugging-port", "Start remote debugger server on port", "2794");
     opts.optflagopt("", "devtools", "Start remote devtools server on port", "6000");
+    opts.optflagopt("", "cdp", "Start Chrome DevTools Protocol server on port", "9222");
     opts.optflagopt("", "webdriver", "Start remote WebDriver server on port", "7000");
     opts.optopt("", "resolution", "Set window resolution.", "1024x740");
     opts.optopt("u",
@@ -748,6 +759,10 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         port.parse().unwrap_or_else(|err| args_fail(&format!("Error parsing option: --devtools ({})", err)))
     });
 
+    let cdp_port = opt_match.opt_default("cdp", "9222").map(|port| {
+        port.parse().unwrap_or_else(|err| args_fail(&format!("Error parsing option: --cdp ({})", err)))
+    });
+
     let webdriver_port = opt_match.opt_default("webdriver", "7000").map(|port| {
         port.parse().unwrap_or_else(|err| args_fail(&format!("Error parsing option: --webdriver ({})", err)))
     });
@@ -805,6 +820,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         userscripts: opt_match.opt_default("userscripts", ""),
         user_stylesheets: user_stylesheets,
         output_file: opt_match.opt_str("o"),
+        print_to_pdf: opt_match.opt_str("print-to-pdf"),
         replace_surrogates: debug_options.replace_surrogates,
         gc_profile: debug_options.gc_profile,
         load_webfonts_synchronously: debug_options.load_webfonts_synchronously,
@@ -816,6 +832,7 @@ pub fn from_cmdline_args(args: &[String]) -> ArgumentParsingResult {
         trace_layout: debug_options.trace_layout,
         debugger_port: debugger_port,
         devtools_port: devtools_port,
+        cdp_port: cdp_port,
         webdriver_port: webdriver_port,
         initial_window_size: initial_window_size,
         user_agent: user_agent,
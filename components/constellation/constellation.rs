@@ -585,6 +585,8 @@ where
 
                 PipelineNamespace::install(PipelineNamespaceId(0));
 
+                let canvas_chan = CanvasPaintThread::start(state.font_cache_thread.clone());
+
                 let mut constellation: Constellation<Message, LTF, STF> = Constellation {
                     script_sender: ipc_script_sender,
                     layout_sender: ipc_layout_sender,
@@ -654,7 +656,7 @@ where
                     ),
                     webgl_threads: state.webgl_threads,
                     webvr_chan: state.webvr_chan,
-                    canvas_chan: CanvasPaintThread::start(),
+                    canvas_chan: canvas_chan,
                 };
 
                 constellation.run();
@@ -1068,6 +1070,9 @@ where
             FromCompositorMsg::Reload(top_level_browsing_context_id) => {
                 self.handle_reload_msg(top_level_browsing_context_id);
             },
+            FromCompositorMsg::PrintToPdf(top_level_browsing_context_id, sender) => {
+                self.handle_print_to_pdf_msg(top_level_browsing_context_id, sender);
+            },
             FromCompositorMsg::LogEntry(top_level_browsing_context_id, thread_name, entry) => {
                 self.handle_log_entry(top_level_browsing_context_id, thread_name, entry);
             },
@@ -1217,6 +1222,10 @@ where
             FromScriptMsg::SetDocumentState(state) => {
                 self.document_states.insert(source_pipeline_id, state);
             },
+            FromScriptMsg::SmoothScroll(scroll_state) => {
+                self.compositor_proxy
+                    .send(ToCompositorMsg::SmoothScroll(scroll_state));
+            },
             FromScriptMsg::GetClientWindow(send) => {
                 self.compositor_proxy
                     .send(ToCompositorMsg::GetClientWindow(send));
@@ -1305,6 +1314,10 @@ where
                 self.handle_pending_paint_metric(pipeline_id, epoch);
             },
             FromLayoutMsg::SetCursor(cursor) => self.handle_set_cursor_msg(cursor),
+            FromLayoutMsg::SetScrollSnapPoints(pipeline_id, snap_points) => {
+                self.compositor_proxy
+                    .send(ToCompositorMsg::SetScrollSnapPoints(pipeline_id, snap_points));
+            },
             FromLayoutMsg::ViewportConstrained(pipeline_id, constraints) => {
                 self.handle_viewport_constrained_msg(pipeline_id, constraints);
             },
@@ -2547,6 +2560,32 @@ where
         }
     }
 
+    fn handle_print_to_pdf_msg(
+        &mut self,
+        top_level_browsing_context_id: TopLevelBrowsingContextId,
+        sender: IpcSender<Option<Vec<u8>>>,
+    ) {
+        let browsing_context_id = BrowsingContextId::from(top_level_browsing_context_id);
+        let pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
+            Some(browsing_context) => browsing_context.pipeline_id,
+            None => {
+                let _ = sender.send(None);
+                return warn!(
+                    "Browsing context {} got print event after closure.",
+                    browsing_context_id
+                );
+            },
+        };
+        let msg = ConstellationControlMsg::PrintToPdf(pipeline_id, sender);
+        let result = match self.pipelines.get(&pipeline_id) {
+            None => return warn!("Pipeline {} got print event after closure.", pipeline_id),
+            Some(pipeline) => pipeline.event_loop.send(msg),
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
     fn handle_post_message_msg(
         &mut self,
         browsing_context_id: BrowsingContextId,
@@ -6,15 +6,19 @@
 //! (http://mxr.mozilla.org/mozilla-central/source/toolkit/devtools/server/actors/inspector.js).
 
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
-use devtools_traits::{ComputedNodeLayout, DevtoolScriptControlMsg, NodeInfo};
-use devtools_traits::DevtoolScriptControlMsg::{GetChildren, GetDocumentElement, GetRootNode};
-use devtools_traits::DevtoolScriptControlMsg::{GetLayout, ModifyAttribute};
+use devtools_traits::{ComputedNodeLayout, DevtoolScriptControlMsg, DomMutation, NodeInfo};
+use devtools_traits::DevtoolScriptControlMsg::{DropMutationWatcher, GetChildren, GetDocumentElement};
+use devtools_traits::DevtoolScriptControlMsg::{GetLayout, GetRootNode, ModifyAttribute};
+use devtools_traits::DevtoolScriptControlMsg::SetMutationWatcher;
 use ipc_channel::ipc::{self, IpcSender};
 use msg::constellation_msg::PipelineId;
 use protocol::JsonPacketStream;
 use serde_json::{self, Map, Value};
 use std::cell::RefCell;
+use std::mem;
 use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
 
 pub struct InspectorActor {
     pub name: String,
@@ -241,6 +245,157 @@ struct WalkerActor {
     name: String,
     script_chan: IpcSender<DevtoolScriptControlMsg>,
     pipeline: PipelineId,
+    /// Mutations received from script that the client has not yet asked for.
+    pending_mutations: Arc<Mutex<Vec<Value>>>,
+}
+
+#[derive(Serialize)]
+struct NewMutationsMsg {
+    from: String,
+    #[serde(rename = "type")]
+    type_: String,
+}
+
+#[derive(Serialize)]
+struct GetMutationsReply {
+    from: String,
+    mutations: Vec<Value>,
+}
+
+#[derive(Serialize)]
+struct ChildListMutationMsg {
+    #[serde(rename = "type")]
+    type_: String,
+    target: String,
+    added: Vec<NodeActorMsg>,
+    removed: Vec<String>,
+    numChildren: usize,
+}
+
+#[derive(Serialize)]
+struct AttributesMutationMsg {
+    #[serde(rename = "type")]
+    type_: String,
+    target: String,
+    attributeName: String,
+    newValue: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CharacterDataMutationMsg {
+    #[serde(rename = "type")]
+    type_: String,
+    target: String,
+    newValue: String,
+}
+
+impl WalkerActor {
+    fn new(name: String,
+           script_chan: IpcSender<DevtoolScriptControlMsg>,
+           pipeline: PipelineId,
+           registry: Arc<Mutex<ActorRegistry>>,
+           stream: TcpStream) -> WalkerActor {
+        let walker = WalkerActor {
+            name: name,
+            script_chan: script_chan,
+            pipeline: pipeline,
+            pending_mutations: Arc::new(Mutex::new(vec![])),
+        };
+        walker.watch_mutations(registry, stream);
+        walker
+    }
+
+    /// Ask script for the mutations of the inspected document, queueing them until
+    /// the client fetches them with `getMutations`. The client is told about new
+    /// mutations with a `newMutations` packet whenever the queue stops being empty.
+    fn watch_mutations(&self, registry: Arc<Mutex<ActorRegistry>>, mut stream: TcpStream) {
+        let (tx, rx) = ipc::channel().unwrap();
+        self.script_chan.send(SetMutationWatcher(self.pipeline, tx)).unwrap();
+
+        let name = self.name();
+        let script_chan = self.script_chan.clone();
+        let pipeline = self.pipeline;
+        let pending_mutations = self.pending_mutations.clone();
+        thread::Builder::new().name("WalkerMutations".to_owned()).spawn(move || {
+            while let Ok(mutations) = rx.recv() {
+                // The client handler writes replies to the stream while holding the
+                // registry lock, so holding it until the notification is written keeps
+                // the packets from interleaving.
+                let registry = registry.lock().unwrap();
+                let mutations: Vec<Value> = mutations.into_iter().filter_map(|mutation| {
+                    encode_mutation(&registry, mutation, script_chan.clone(), pipeline)
+                }).collect();
+                if mutations.is_empty() {
+                    continue;
+                }
+
+                let mut pending = pending_mutations.lock().unwrap();
+                let notify = pending.is_empty();
+                pending.extend(mutations);
+                if notify {
+                    stream.write_json_packet(&NewMutationsMsg {
+                        from: name.clone(),
+                        type_: "newMutations".to_owned(),
+                    });
+                }
+            }
+        }).expect("Thread spawning failed");
+    }
+}
+
+impl Drop for WalkerActor {
+    fn drop(&mut self) {
+        let _ = self.script_chan.send(DropMutationWatcher(self.pipeline));
+    }
+}
+
+/// Convert a mutation into its protocol form. Mutations of nodes the client has never
+/// been sent are dropped, since it has nothing to update.
+fn encode_mutation(registry: &ActorRegistry,
+                   mutation: DomMutation,
+                   script_chan: IpcSender<DevtoolScriptControlMsg>,
+                   pipeline: PipelineId) -> Option<Value> {
+    let msg = match mutation {
+        DomMutation::ChildList { target, added, removed, numChildren } => {
+            if !registry.script_actor_registered(target.clone()) {
+                return None;
+            }
+            serde_json::to_value(ChildListMutationMsg {
+                type_: "childList".to_owned(),
+                target: registry.script_to_actor(target),
+                added: added.into_iter().map(|node| {
+                    node.encode(registry, true, script_chan.clone(), pipeline)
+                }).collect(),
+                removed: removed.into_iter()
+                                .filter(|node| registry.script_actor_registered(node.clone()))
+                                .map(|node| registry.script_to_actor(node))
+                                .collect(),
+                numChildren: numChildren,
+            })
+        }
+        DomMutation::Attributes { target, attributeName, newValue } => {
+            if !registry.script_actor_registered(target.clone()) {
+                return None;
+            }
+            serde_json::to_value(AttributesMutationMsg {
+                type_: "attributes".to_owned(),
+                target: registry.script_to_actor(target),
+                attributeName: attributeName,
+                newValue: newValue,
+            })
+        }
+        DomMutation::CharacterData { target, newValue } => {
+            if !registry.script_actor_registered(target.clone()) {
+                return None;
+            }
+            serde_json::to_value(CharacterDataMutationMsg {
+                type_: "characterData".to_owned(),
+                target: registry.script_to_actor(target),
+                newValue: newValue,
+            })
+        }
+    };
+    msg.ok()
 }
 
 #[derive(Serialize)]
@@ -329,6 +484,16 @@ impl Actor for WalkerActor {
                 ActorMessageStatus::Processed
             }
 
+            "getMutations" => {
+                let mutations = mem::replace(&mut *self.pending_mutations.lock().unwrap(), vec![]);
+                let msg = GetMutationsReply {
+                    from: self.name(),
+                    mutations: mutations,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
             _ => ActorMessageStatus::Ignored,
         })
     }
@@ -551,11 +716,11 @@ impl Actor for InspectorActor {
         Ok(match msg_type {
             "getWalker" => {
                 if self.walker.borrow().is_none() {
-                    let walker = WalkerActor {
-                        name: registry.new_name("walker"),
-                        script_chan: self.script_chan.clone(),
-                        pipeline: self.pipeline,
-                    };
+                    let walker = WalkerActor::new(registry.new_name("walker"),
+                                                  self.script_chan.clone(),
+                                                  self.pipeline,
+                                                  registry.shareable(),
+                                                  stream.try_clone().unwrap());
                     let mut walker_name = self.walker.borrow_mut();
                     *walker_name = Some(walker.name());
                     registry.register_later(Box::new(walker));
@@ -2,9 +2,23 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+//! Liberally derived from the [Firefox JS implementation]
+//! (https://searchfox.org/mozilla-central/source/devtools/server/actors/memory.js).
+//! Reports the memory used by a tab, as measured by the memory profiler's reporters,
+//! and summarizes snapshots of its JS heap.
+
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
+use devtools_traits::{DevtoolScriptControlMsg, HeapGraph};
+use devtools_traits::DevtoolScriptControlMsg::TakeHeapSnapshot;
+use ipc_channel::ipc::{self, IpcSender};
+use msg::constellation_msg::PipelineId;
+use profile_traits::mem::{ProfilerChan, ProfilerMsg};
+use protocol::JsonPacketStream;
 use serde_json::{Map, Value};
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::net::TcpStream;
+use time::precise_time_ns;
 
 #[derive(Serialize)]
 pub struct TimelineMemoryReply {
@@ -19,8 +33,81 @@ pub struct TimelineMemoryReply {
     nonJSMilliseconds: f64,
 }
 
+#[derive(Serialize)]
+struct AttachedReply {
+    from: String,
+    #[serde(rename = "type")]
+    type_: String,
+}
+
+#[derive(Serialize)]
+struct GetStateReply {
+    from: String,
+    state: String,
+}
+
+#[derive(Serialize)]
+struct MeasureReply {
+    from: String,
+    total: u64,
+    domSize: u64,
+    styleSize: u64,
+    jsObjectsSize: u64,
+    jsStringsSize: u64,
+    jsOtherSize: u64,
+    otherSize: u64,
+    jsMilliseconds: f64,
+    nonJSMilliseconds: f64,
+}
+
+#[derive(Serialize)]
+struct CensusCount {
+    count: u32,
+    bytes: u64,
+}
+
+#[derive(Serialize)]
+struct TakeCensusReply {
+    from: String,
+    census: Census,
+}
+
+#[derive(Serialize)]
+struct Census {
+    objects: BTreeMap<String, CensusCount>,
+}
+
+#[derive(Serialize)]
+struct HeapSnapshotEntry {
+    constructor: String,
+    count: u32,
+    shallowSize: u64,
+    retainedSize: u64,
+}
+
+#[derive(Serialize)]
+struct TakeHeapSnapshotReply {
+    from: String,
+    nodeCount: u32,
+    totalSize: u64,
+    /// Sorted by decreasing retained size.
+    entries: Vec<HeapSnapshotEntry>,
+}
+
+#[derive(Serialize)]
+struct ErrorReply {
+    from: String,
+    error: String,
+    message: String,
+}
+
 pub struct MemoryActor {
     pub name: String,
+    pipeline: PipelineId,
+    script_chan: IpcSender<DevtoolScriptControlMsg>,
+    url: String,
+    mem_profiler_chan: ProfilerChan,
+    attached: Cell<bool>,
 }
 
 impl Actor for MemoryActor {
@@ -30,37 +117,309 @@ impl Actor for MemoryActor {
 
     fn handle_message(&self,
                       _registry: &ActorRegistry,
-                      _msg_type: &str,
+                      msg_type: &str,
                       _msg: &Map<String, Value>,
-                      _stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
-        Ok(ActorMessageStatus::Ignored)
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            "attach" => {
+                self.attached.set(true);
+                let msg = AttachedReply {
+                    from: self.name(),
+                    type_: "attached".to_owned(),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "detach" => {
+                self.attached.set(false);
+                let msg = AttachedReply {
+                    from: self.name(),
+                    type_: "detached".to_owned(),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "getState" => {
+                let msg = GetStateReply {
+                    from: self.name(),
+                    state: if self.attached.get() { "attached" } else { "detached" }.to_owned(),
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "measure" => {
+                let measurement = self.measure();
+                let msg = MeasureReply {
+                    from: self.name(),
+                    total: measurement.totalSize,
+                    domSize: measurement.domSize,
+                    styleSize: measurement.styleSize,
+                    jsObjectsSize: measurement.jsObjectSize,
+                    jsStringsSize: measurement.jsStringSize,
+                    jsOtherSize: measurement.jsOtherSize,
+                    otherSize: measurement.otherSize,
+                    jsMilliseconds: measurement.jsMilliseconds,
+                    nonJSMilliseconds: measurement.nonJSMilliseconds,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "takeCensus" | "takeHeapSnapshot" => {
+                let graph = match self.take_heap_snapshot() {
+                    Some(graph) => graph,
+                    None => {
+                        let msg = ErrorReply {
+                            from: self.name(),
+                            error: "unknownError".to_owned(),
+                            message: "The page is no longer available".to_owned(),
+                        };
+                        stream.write_json_packet(&msg);
+                        return Ok(ActorMessageStatus::Processed);
+                    }
+                };
+                let entries = summarize(&graph);
+                if msg_type == "takeCensus" {
+                    let objects = entries.into_iter().map(|entry| {
+                        (entry.constructor, CensusCount {
+                            count: entry.count,
+                            bytes: entry.shallowSize,
+                        })
+                    }).collect();
+                    let msg = TakeCensusReply {
+                        from: self.name(),
+                        census: Census {
+                            objects: objects,
+                        },
+                    };
+                    stream.write_json_packet(&msg);
+                } else {
+                    let msg = TakeHeapSnapshotReply {
+                        from: self.name(),
+                        nodeCount: graph.nodes.len() as u32,
+                        totalSize: graph.nodes.iter().map(|node| node.size).sum(),
+                        entries: entries,
+                    };
+                    stream.write_json_packet(&msg);
+                }
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
     }
 }
 
 impl MemoryActor {
-    /// return name of actor
-    pub fn create(registry: &ActorRegistry) -> String {
-        let actor_name = registry.new_name("memory");
-        let actor = MemoryActor {
-            name: actor_name.clone()
+    pub fn new(name: String,
+               pipeline: PipelineId,
+               script_chan: IpcSender<DevtoolScriptControlMsg>,
+               url: String,
+               mem_profiler_chan: ProfilerChan) -> MemoryActor {
+        MemoryActor {
+            name: name,
+            pipeline: pipeline,
+            script_chan: script_chan,
+            url: url,
+            mem_profiler_chan: mem_profiler_chan,
+            attached: Cell::new(false),
+        }
+    }
+
+    /// Sum the memory reports concerning this tab. The JS engine reports memory per
+    /// script thread, so its measurements are shared with same-origin tabs.
+    pub fn measure(&self) -> TimelineMemoryReply {
+        let start = precise_time_ns();
+        let (chan, port) = ipc::channel().unwrap();
+        self.mem_profiler_chan.send(ProfilerMsg::Report(chan));
+        let reports = port.recv().unwrap_or(vec![]);
+
+        let mut measurement = TimelineMemoryReply {
+            jsObjectSize: 0,
+            // The JS engine only reports the GC heap as a whole, so strings are counted
+            // in `jsObjectSize` and this is always zero.
+            jsStringSize: 0,
+            jsOtherSize: 0,
+            domSize: 0,
+            styleSize: 0,
+            otherSize: 0,
+            totalSize: 0,
+            jsMilliseconds: 0.,
+            nonJSMilliseconds: 0.,
         };
+        for report in reports {
+            let is_tab_report = report.path.first().map_or(false, |segment| reports_url(segment, &self.url));
+            if !is_tab_report {
+                continue;
+            }
+            let size = report.size as u64;
+            let path = report.path[1..].join("/");
+            if path == "js/gc-heap/decommitted" {
+                continue;
+            } else if path == "js/gc-heap/used" {
+                measurement.jsObjectSize += size;
+            } else if path.starts_with("js/") {
+                measurement.jsOtherSize += size;
+            } else if path == "dom-tree" {
+                measurement.domSize += size;
+            } else if path == "layout-thread/stylist" {
+                measurement.styleSize += size;
+            } else {
+                measurement.otherSize += size;
+            }
+            measurement.totalSize += size;
+        }
+        measurement.nonJSMilliseconds = (precise_time_ns() - start) as f64 / 1000000.;
+        measurement
+    }
 
-        registry.register_later(Box::new(actor));
-        actor_name
+    fn take_heap_snapshot(&self) -> Option<HeapGraph> {
+        let (chan, port) = ipc::channel().unwrap();
+        self.script_chan.send(TakeHeapSnapshot(self.pipeline, chan)).unwrap();
+        port.recv().ok().and_then(|graph| graph)
     }
+}
 
-    pub fn measure(&self) -> TimelineMemoryReply {
-        //TODO:
-        TimelineMemoryReply {
-            jsObjectSize: 1,
-            jsStringSize: 1,
-            jsOtherSize: 1,
-            domSize: 1,
-            styleSize: 1,
-            otherSize: 1,
-            totalSize: 1,
-            jsMilliseconds: 1.1,
-            nonJSMilliseconds: 1.1,
+/// Whether a memory report whose path starts with `segment` concerns the page at `url`.
+/// Reports for a page are prefixed with "url(...)", listing every URL that shares the
+/// reporting thread, separated by ", ".
+fn reports_url(segment: &str, url: &str) -> bool {
+    if !segment.starts_with("url(") || !segment.ends_with(")") {
+        return false;
+    }
+    segment["url(".len()..segment.len() - 1].split(", ").any(|segment_url| segment_url == url)
+}
+
+/// Group the nodes of a heap graph by constructor. The retained size of a group
+/// only counts objects that are not retained by another object of the same group,
+/// so that nested objects aren't counted twice.
+fn summarize(graph: &HeapGraph) -> Vec<HeapSnapshotEntry> {
+    let idom = dominators(graph);
+    let retained = retained_sizes(graph, &idom);
+
+    let mut groups: BTreeMap<&str, HeapSnapshotEntry> = BTreeMap::new();
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let entry = groups.entry(&*node.className).or_insert_with(|| HeapSnapshotEntry {
+            constructor: node.className.clone(),
+            count: 0,
+            shallowSize: 0,
+            retainedSize: 0,
+        });
+        entry.count += 1;
+        entry.shallowSize += node.size;
+
+        let mut dominator = idom[index];
+        let mut nested = false;
+        while let Some(ancestor) = dominator {
+            if graph.nodes[ancestor].className == node.className {
+                nested = true;
+                break;
+            }
+            dominator = idom[ancestor];
+        }
+        if !nested {
+            entry.retainedSize += retained[index];
+        }
+    }
+
+    let mut entries: Vec<HeapSnapshotEntry> = groups.into_iter().map(|(_, entry)| entry).collect();
+    entries.sort_by(|a, b| b.retainedSize.cmp(&a.retainedSize));
+    entries
+}
+
+/// The nodes of a heap graph reachable from its root, in reverse postorder.
+fn reverse_postorder(graph: &HeapGraph) -> Vec<usize> {
+    let mut order = vec![];
+    let mut visited = vec![false; graph.nodes.len()];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some((node, edge)) = stack.pop() {
+        match graph.nodes[node].edges.get(edge) {
+            Some(&next) => {
+                stack.push((node, edge + 1));
+                let next = next as usize;
+                if !visited[next] {
+                    visited[next] = true;
+                    stack.push((next, 0));
+                }
+            }
+            None => order.push(node),
+        }
+    }
+    order.reverse();
+    order
+}
+
+/// The immediate dominator of every node of a heap graph, i.e. the closest object
+/// that every path from the root to the node passes through. See "A Simple, Fast
+/// Dominance Algorithm" by Cooper, Harvey and Kennedy.
+fn dominators(graph: &HeapGraph) -> Vec<Option<usize>> {
+    let node_count = graph.nodes.len();
+    if node_count == 0 {
+        return vec![];
+    }
+    let order = reverse_postorder(graph);
+    let mut position = vec![usize::max_value(); node_count];
+    for (index, &node) in order.iter().enumerate() {
+        position[node] = index;
+    }
+    let mut predecessors = vec![vec![]; node_count];
+    for (index, node) in graph.nodes.iter().enumerate() {
+        for &edge in &node.edges {
+            predecessors[edge as usize].push(index);
+        }
+    }
+
+    let mut idom: Vec<Option<usize>> = vec![None; node_count];
+    idom[0] = Some(0);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in order.iter().skip(1) {
+            let mut new_idom = None;
+            for &predecessor in &predecessors[node] {
+                if idom[predecessor].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => predecessor,
+                    Some(mut finger) => {
+                        let mut other = predecessor;
+                        while finger != other {
+                            while position[finger] > position[other] {
+                                finger = idom[finger].unwrap();
+                            }
+                            while position[other] > position[finger] {
+                                other = idom[other].unwrap();
+                            }
+                        }
+                        finger
+                    }
+                });
+            }
+            if new_idom.is_some() && idom[node] != new_idom {
+                idom[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    // The root has no dominator of its own.
+    idom[0] = None;
+    idom
+}
+
+/// The size of every node of a heap graph plus that of the nodes it dominates.
+fn retained_sizes(graph: &HeapGraph, idom: &[Option<usize>]) -> Vec<u64> {
+    let mut retained: Vec<u64> = graph.nodes.iter().map(|node| node.size).collect();
+    // Dominators come before the nodes they dominate in reverse postorder.
+    for &node in reverse_postorder(graph).iter().rev() {
+        if let Some(dominator) = idom[node] {
+            retained[dominator] += retained[node];
         }
     }
+    retained
 }
@@ -3,7 +3,10 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use actor::{Actor, ActorMessageStatus, ActorRegistry};
-use serde_json::{Map, Value};
+use devtools_traits::{EvaluateJSReply, ObjectPreview};
+use devtools_traits::EvaluateJSReply::{ActorValue, BooleanValue, StringValue};
+use devtools_traits::EvaluateJSReply::{NullValue, NumberValue, VoidValue};
+use serde_json::{Map, Number, Value};
 use std::net::TcpStream;
 
 pub struct ObjectActor {
@@ -42,3 +45,88 @@ impl ObjectActor {
         }
     }
 }
+
+/// Convert a serialized JS value into a protocol grip, registering an `ObjectActor`
+/// for each object it refers to.
+pub fn value_to_grip(registry: &ActorRegistry, value: EvaluateJSReply) -> Value {
+    match value {
+        VoidValue => {
+            let mut m = Map::new();
+            m.insert("type".to_owned(), Value::String("undefined".to_owned()));
+            Value::Object(m)
+        }
+        NullValue => {
+            let mut m = Map::new();
+            m.insert("type".to_owned(), Value::String("null".to_owned()));
+            Value::Object(m)
+        }
+        BooleanValue(val) => Value::Bool(val),
+        NumberValue(val) => {
+            if val.is_nan() {
+                let mut m = Map::new();
+                m.insert("type".to_owned(), Value::String("NaN".to_owned()));
+                Value::Object(m)
+            } else if val.is_infinite() {
+                let mut m = Map::new();
+                if val < 0. {
+                    m.insert("type".to_owned(), Value::String("-Infinity".to_owned()));
+                } else {
+                    m.insert("type".to_owned(), Value::String("Infinity".to_owned()));
+                }
+                Value::Object(m)
+            } else if val == 0. && val.is_sign_negative() {
+                let mut m = Map::new();
+                m.insert("type".to_owned(), Value::String("-0".to_owned()));
+                Value::Object(m)
+            } else {
+                Value::Number(Number::from_f64(val).unwrap())
+            }
+        }
+        StringValue(s) => Value::String(s),
+        ActorValue { class, uuid, preview } => {
+            let mut m = Map::new();
+            let actor = ObjectActor::new(registry, uuid);
+
+            m.insert("type".to_owned(), Value::String("object".to_owned()));
+            m.insert("class".to_owned(), Value::String(class));
+            m.insert("actor".to_owned(), Value::String(actor));
+            m.insert("extensible".to_owned(), Value::Bool(true));
+            m.insert("frozen".to_owned(), Value::Bool(false));
+            m.insert("sealed".to_owned(), Value::Bool(false));
+            if let Some(preview) = preview {
+                m.insert("ownPropertyLength".to_owned(), Value::from(preview.ownPropertiesLength));
+                m.insert("preview".to_owned(), preview_to_json(registry, preview));
+            }
+            Value::Object(m)
+        }
+    }
+}
+
+fn preview_to_json(registry: &ActorRegistry, preview: ObjectPreview) -> Value {
+    let mut m = Map::new();
+    match preview.length {
+        Some(length) => {
+            let items = preview.ownProperties.into_iter().map(|(_, value)| {
+                value_to_grip(registry, value)
+            }).collect();
+            m.insert("kind".to_owned(), Value::String("ArrayLike".to_owned()));
+            m.insert("length".to_owned(), Value::from(length));
+            m.insert("items".to_owned(), Value::Array(items));
+        }
+        None => {
+            let mut properties = Map::new();
+            for (name, value) in preview.ownProperties {
+                let mut descriptor = Map::new();
+                descriptor.insert("value".to_owned(), value_to_grip(registry, value));
+                descriptor.insert("configurable".to_owned(), Value::Bool(true));
+                descriptor.insert("enumerable".to_owned(), Value::Bool(true));
+                descriptor.insert("writable".to_owned(), Value::Bool(true));
+                properties.insert(name, Value::Object(descriptor));
+            }
+            m.insert("kind".to_owned(), Value::String("Object".to_owned()));
+            m.insert("ownProperties".to_owned(), Value::Object(properties));
+            m.insert("ownPropertiesLength".to_owned(), Value::from(preview.ownPropertiesLength));
+        }
+    }
+    Value::Object(m)
+}
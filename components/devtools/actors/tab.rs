@@ -65,6 +65,8 @@ pub struct TabActorMsg {
     timelineActor: String,
     profilerActor: String,
     performanceActor: String,
+    memoryActor: String,
+    webglActor: String,
 }
 
 pub struct TabActor {
@@ -76,6 +78,8 @@ pub struct TabActor {
     pub timeline: String,
     pub profiler: String,
     pub performance: String,
+    pub memory: String,
+    pub webgl: String,
     pub thread: String,
 }
 
@@ -164,6 +168,8 @@ impl TabActor {
             timelineActor: self.timeline.clone(),
             profilerActor: self.profiler.clone(),
             performanceActor: self.performance.clone(),
+            memoryActor: self.memory.clone(),
+            webglActor: self.webgl.clone(),
         }
     }
 }
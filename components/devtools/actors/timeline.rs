@@ -28,6 +28,9 @@ pub struct TimelineActor {
     stream: RefCell<Option<TcpStream>>,
 
     framerate_actor: RefCell<Option<String>>,
+    /// The memory actor of the tab, which reports measurements while recording
+    /// if requested.
+    memory: String,
     memory_actor: RefCell<Option<String>>,
 }
 
@@ -124,7 +127,8 @@ static DEFAULT_TIMELINE_DATA_PULL_TIMEOUT: u64 = 200; //ms
 impl TimelineActor {
     pub fn new(name: String,
                pipeline: PipelineId,
-               script_sender: IpcSender<DevtoolScriptControlMsg>) -> TimelineActor {
+               script_sender: IpcSender<DevtoolScriptControlMsg>,
+               memory: String) -> TimelineActor {
         let marker_types = vec!(TimelineMarkerType::Reflow,
                                 TimelineMarkerType::DOMEvent);
 
@@ -137,6 +141,7 @@ impl TimelineActor {
             stream: RefCell::new(None),
 
             framerate_actor: RefCell::new(None),
+            memory: memory,
             memory_actor: RefCell::new(None),
         }
     }
@@ -190,7 +195,7 @@ impl Actor for TimelineActor {
                 // init memory actor
                 if let Some(with_memory) = msg.get("withMemory") {
                     if let Some(true) = with_memory.as_bool() {
-                        *self.memory_actor.borrow_mut() = Some(MemoryActor::create(registry));
+                        *self.memory_actor.borrow_mut() = Some(self.memory.clone());
                     }
                 }
 
@@ -234,9 +239,7 @@ impl Actor for TimelineActor {
                     registry.drop_actor_later(actor_name.clone());
                 }
 
-                if let Some(ref actor_name) = *self.memory_actor.borrow() {
-                    registry.drop_actor_later(actor_name.clone());
-                }
+                self.memory_actor.borrow_mut().take();
 
                 **self.is_recording.lock().as_mut().unwrap() = false;
                 self.stream.borrow_mut().take();
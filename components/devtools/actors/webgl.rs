@@ -0,0 +1,285 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Liberally derived from the [Firefox JS implementation]
+//! (https://searchfox.org/mozilla-central/source/devtools/server/actors/webgl.js).
+//! Backs the shader editor, which lists the linked programs of the WebGL contexts
+//! of a tab and replaces the sources of their shaders while the page runs.
+
+use actor::{Actor, ActorMessageStatus, ActorRegistry};
+use devtools_traits::{DevtoolScriptControlMsg, WebGLProgramInfo, WebGLProgramKey};
+use devtools_traits::DevtoolScriptControlMsg::{GetWebGLPrograms, ReplaceWebGLShaderSource};
+use ipc_channel::ipc::{self, IpcSender};
+use msg::constellation_msg::PipelineId;
+use protocol::JsonPacketStream;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::TcpStream;
+
+const VERTEX_SHADER: u32 = 0x8B31;
+const FRAGMENT_SHADER: u32 = 0x8B30;
+
+#[derive(Serialize)]
+struct EmptyReply {
+    from: String,
+}
+
+#[derive(Serialize)]
+struct ProgramMsg {
+    actor: String,
+    contextId: usize,
+    programId: u32,
+}
+
+#[derive(Serialize)]
+struct GetProgramsReply {
+    from: String,
+    programs: Vec<ProgramMsg>,
+}
+
+#[derive(Serialize)]
+struct GetShaderReply {
+    from: String,
+    shader: String,
+}
+
+#[derive(Serialize)]
+struct GetTextReply {
+    from: String,
+    text: String,
+}
+
+#[derive(Serialize)]
+struct ErrorReply {
+    from: String,
+    error: String,
+    message: String,
+}
+
+impl ErrorReply {
+    /// The reply to a request about a page that has gone away.
+    fn page_closed(from: String) -> ErrorReply {
+        ErrorReply {
+            from: from,
+            error: "unknownError".to_owned(),
+            message: "The page is no longer available".to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CompileReply {
+    from: String,
+    /// The info log of the compilation or link that rejected the new source.
+    error: Option<String>,
+}
+
+pub struct WebGLActor {
+    pub name: String,
+    pipeline: PipelineId,
+    script_chan: IpcSender<DevtoolScriptControlMsg>,
+    /// The program actors created so far, so that a program keeps its actor across
+    /// `getPrograms` requests.
+    programs: RefCell<HashMap<WebGLProgramKey, String>>,
+}
+
+impl Actor for WebGLActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      registry: &ActorRegistry,
+                      msg_type: &str,
+                      _msg: &Map<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            // Programs are tracked whether or not the editor is open, so there is
+            // nothing to set up.
+            "setup" | "finalize" => {
+                stream.write_json_packet(&EmptyReply { from: self.name() });
+                ActorMessageStatus::Processed
+            }
+
+            "getPrograms" => {
+                let programs = match get_programs(&self.script_chan, self.pipeline) {
+                    Some(programs) => programs,
+                    None => {
+                        stream.write_json_packet(&ErrorReply::page_closed(self.name()));
+                        return Ok(ActorMessageStatus::Processed);
+                    }
+                };
+                let mut actors = self.programs.borrow_mut();
+                let programs = programs.into_iter().map(|program| {
+                    let key = program.key;
+                    let actor = actors.entry(key).or_insert_with(|| {
+                        let program_actor = ProgramActor::new(registry, self.pipeline, self.script_chan.clone(), key);
+                        let name = program_actor.name();
+                        registry.register_later(Box::new(program_actor));
+                        name
+                    });
+                    ProgramMsg {
+                        actor: actor.clone(),
+                        contextId: key.contextId,
+                        programId: key.programId,
+                    }
+                }).collect();
+                let msg = GetProgramsReply {
+                    from: self.name(),
+                    programs: programs,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
+    }
+}
+
+impl WebGLActor {
+    pub fn new(name: String,
+               pipeline: PipelineId,
+               script_chan: IpcSender<DevtoolScriptControlMsg>) -> WebGLActor {
+        WebGLActor {
+            name: name,
+            pipeline: pipeline,
+            script_chan: script_chan,
+            programs: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+pub struct ProgramActor {
+    name: String,
+    vertex_shader: String,
+    fragment_shader: String,
+}
+
+impl Actor for ProgramActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      _registry: &ActorRegistry,
+                      msg_type: &str,
+                      _msg: &Map<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        let shader = match msg_type {
+            "getVertexShader" => &self.vertex_shader,
+            "getFragmentShader" => &self.fragment_shader,
+            _ => return Ok(ActorMessageStatus::Ignored),
+        };
+        let msg = GetShaderReply {
+            from: self.name(),
+            shader: shader.clone(),
+        };
+        stream.write_json_packet(&msg);
+        Ok(ActorMessageStatus::Processed)
+    }
+}
+
+impl ProgramActor {
+    fn new(registry: &ActorRegistry,
+           pipeline: PipelineId,
+           script_chan: IpcSender<DevtoolScriptControlMsg>,
+           key: WebGLProgramKey) -> ProgramActor {
+        let shader_actor = |shader_type| {
+            let shader = ShaderActor {
+                name: registry.new_name("shader"),
+                pipeline: pipeline,
+                script_chan: script_chan.clone(),
+                key: key,
+                shader_type: shader_type,
+            };
+            let name = shader.name();
+            registry.register_later(Box::new(shader));
+            name
+        };
+        ProgramActor {
+            name: registry.new_name("program"),
+            vertex_shader: shader_actor(VERTEX_SHADER),
+            fragment_shader: shader_actor(FRAGMENT_SHADER),
+        }
+    }
+}
+
+pub struct ShaderActor {
+    name: String,
+    pipeline: PipelineId,
+    script_chan: IpcSender<DevtoolScriptControlMsg>,
+    key: WebGLProgramKey,
+    shader_type: u32,
+}
+
+impl Actor for ShaderActor {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn handle_message(&self,
+                      _registry: &ActorRegistry,
+                      msg_type: &str,
+                      msg: &Map<String, Value>,
+                      stream: &mut TcpStream) -> Result<ActorMessageStatus, ()> {
+        Ok(match msg_type {
+            "getText" => {
+                let programs = match get_programs(&self.script_chan, self.pipeline) {
+                    Some(programs) => programs,
+                    None => {
+                        stream.write_json_packet(&ErrorReply::page_closed(self.name()));
+                        return Ok(ActorMessageStatus::Processed);
+                    }
+                };
+                let program = programs.into_iter().find(|program| program.key == self.key);
+                let text = program.map_or(String::new(), |program| {
+                    if self.shader_type == VERTEX_SHADER {
+                        program.vertexShaderSource
+                    } else {
+                        program.fragmentShaderSource
+                    }
+                });
+                let msg = GetTextReply {
+                    from: self.name(),
+                    text: text,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            "compile" => {
+                let text = msg.get("text").and_then(|text| text.as_str()).unwrap_or("").to_owned();
+                let (chan, port) = ipc::channel().unwrap();
+                let msg = ReplaceWebGLShaderSource(self.pipeline, self.key, self.shader_type, text, chan);
+                let result = self.script_chan.send(msg).ok().and_then(|()| port.recv().ok());
+                let error = match result {
+                    Some(result) => result.err(),
+                    None => {
+                        stream.write_json_packet(&ErrorReply::page_closed(self.name()));
+                        return Ok(ActorMessageStatus::Processed);
+                    }
+                };
+                let msg = CompileReply {
+                    from: self.name(),
+                    error: error,
+                };
+                stream.write_json_packet(&msg);
+                ActorMessageStatus::Processed
+            }
+
+            _ => ActorMessageStatus::Ignored,
+        })
+    }
+}
+
+/// The linked programs of the page, or `None` if the page has gone away.
+fn get_programs(script_chan: &IpcSender<DevtoolScriptControlMsg>,
+                pipeline: PipelineId)
+                -> Option<Vec<WebGLProgramInfo>> {
+    let (chan, port) = ipc::channel().unwrap();
+    script_chan.send(GetWebGLPrograms(pipeline, chan)).ok()?;
+    port.recv().ok()
+}
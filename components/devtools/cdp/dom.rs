@@ -0,0 +1,121 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The [DOM domain](https://chromedevtools.github.io/devtools-protocol/tot/DOM).
+
+use cdp::server::{CommandError, CommandResult, Domain, Session, recv, str_param, u64_param};
+use devtools_traits::{Modification, NodeInfo};
+use devtools_traits::DevtoolScriptControlMsg::{GetChildren, GetRootNode, ModifyAttribute};
+use ipc_channel::ipc;
+use serde_json::{Map, Value};
+
+/// The default number of levels of children returned with a node.
+const DEFAULT_DEPTH: i64 = 1;
+
+pub fn handle_command(session: &Session, command: &str, params: &Map<String, Value>) -> CommandResult {
+    match command {
+        "enable" => {
+            session.set_enabled(Domain::Dom, true);
+            Ok(json!({}))
+        }
+
+        "disable" => {
+            session.set_enabled(Domain::Dom, false);
+            Ok(json!({}))
+        }
+
+        "getDocument" => {
+            let (tx, rx) = ipc::channel().unwrap();
+            session.target.send(GetRootNode(session.target.pipeline, tx))?;
+            let root = recv(rx)?.ok_or_else(|| CommandError::server_error("Document is not available"))?;
+            let depth = depth(params);
+            Ok(json!({ "root": describe(session, root, depth)? }))
+        }
+
+        "requestChildNodes" => {
+            let node_id = u64_param(params, "nodeId")? as u32;
+            let unique_id = find_node(session, node_id)?;
+            let nodes = children(session, &unique_id, depth(params))?;
+            session.send_event("DOM.setChildNodes", &json!({
+                "parentId": node_id,
+                "nodes": nodes
+            }));
+            Ok(json!({}))
+        }
+
+        "setAttributeValue" => {
+            let unique_id = find_node(session, u64_param(params, "nodeId")? as u32)?;
+            let modification = Modification {
+                attributeName: str_param(params, "name")?.to_owned(),
+                newValue: Some(str_param(params, "value")?.to_owned()),
+            };
+            session.target.send(ModifyAttribute(session.target.pipeline, unique_id, vec![modification]))?;
+            Ok(json!({}))
+        }
+
+        "removeAttribute" => {
+            let unique_id = find_node(session, u64_param(params, "nodeId")? as u32)?;
+            let modification = Modification {
+                attributeName: str_param(params, "name")?.to_owned(),
+                newValue: None,
+            };
+            session.target.send(ModifyAttribute(session.target.pipeline, unique_id, vec![modification]))?;
+            Ok(json!({}))
+        }
+
+        _ => Err(CommandError::method_not_found(command)),
+    }
+}
+
+/// The requested subtree depth; -1 means the entire subtree.
+fn depth(params: &Map<String, Value>) -> i64 {
+    params.get("depth").and_then(|depth| depth.as_i64()).unwrap_or(DEFAULT_DEPTH)
+}
+
+fn find_node(session: &Session, node_id: u32) -> Result<String, CommandError> {
+    session.unique_id(node_id).ok_or_else(|| CommandError::server_error("Could not find node with given id"))
+}
+
+fn children(session: &Session, unique_id: &str, depth: i64) -> Result<Vec<Value>, CommandError> {
+    let (tx, rx) = ipc::channel().unwrap();
+    session.target.send(GetChildren(session.target.pipeline, unique_id.to_owned(), tx))?;
+    recv(rx)?.unwrap_or(vec![]).into_iter().map(|child| {
+        describe(session, child, depth - 1)
+    }).collect()
+}
+
+/// Convert a node summary into a `DOM.Node`, including `depth` levels of children.
+fn describe(session: &Session, node: NodeInfo, depth: i64) -> Result<Value, CommandError> {
+    let node_id = session.node_id(&node.uniqueId);
+    let mut attributes = vec![];
+    for attr in &node.attrs {
+        attributes.push(attr.name.clone());
+        attributes.push(attr.value.clone());
+    }
+    let mut value = json!({
+        "nodeId": node_id,
+        "backendNodeId": node_id,
+        "nodeType": node.nodeType,
+        "nodeName": node.nodeName,
+        "localName": node.nodeName.to_lowercase(),
+        "nodeValue": node.shortValue,
+        "childNodeCount": node.numChildren,
+        "attributes": attributes
+    });
+    {
+        let object = value.as_object_mut().unwrap();
+        if node.nodeType == 9 {
+            object.insert("documentURL".to_owned(), json!(node.baseURI));
+            object.insert("baseURL".to_owned(), json!(node.baseURI));
+        }
+        if node.nodeType == 10 {
+            object.insert("publicId".to_owned(), json!(node.publicId));
+            object.insert("systemId".to_owned(), json!(node.systemId));
+        }
+        if depth != 0 && node.numChildren > 0 {
+            object.insert("children".to_owned(), Value::Array(children(session, &node.uniqueId, depth)?));
+        }
+    }
+    Ok(value)
+}
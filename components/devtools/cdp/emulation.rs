@@ -0,0 +1,33 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The [Emulation domain](https://chromedevtools.github.io/devtools-protocol/tot/Emulation).
+//! Servo has no viewport override yet, so device metrics are applied by
+//! resizing the window.
+
+use cdp::server::{CommandError, CommandResult, Session, u64_param};
+use devtools_traits::DevtoolScriptControlMsg::ResizeWindow;
+use serde_json::{Map, Value};
+
+pub fn handle_command(session: &Session, command: &str, params: &Map<String, Value>) -> CommandResult {
+    match command {
+        "setDeviceMetricsOverride" => {
+            let width = u64_param(params, "width")? as i32;
+            let height = u64_param(params, "height")? as i32;
+            if width == 0 || height == 0 {
+                return Err(CommandError::server_error("Resetting the window size is not supported"));
+            }
+            let scale_factor = params.get("deviceScaleFactor").and_then(|factor| factor.as_f64());
+            if scale_factor.map_or(false, |factor| factor != 0.) {
+                warn!("Ignoring unsupported deviceScaleFactor override");
+            }
+            session.target.send(ResizeWindow(session.target.pipeline, width, height))?;
+            Ok(json!({}))
+        }
+
+        "canEmulate" => Ok(json!({ "result": true })),
+
+        _ => Err(CommandError::method_not_found(command)),
+    }
+}
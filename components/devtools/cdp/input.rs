@@ -0,0 +1,74 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The [Input domain](https://chromedevtools.github.io/devtools-protocol/tot/Input).
+
+use cdp::server::{CommandError, CommandResult, Session, f64_param, str_param};
+use devtools_traits::{DevtoolsInputEvent, DevtoolsMouseButton, DevtoolsMouseEventType};
+use devtools_traits::DevtoolScriptControlMsg::DispatchInputEvent;
+use msg::constellation_msg::{KeyModifiers, KeyState};
+use serde_json::{Map, Value};
+
+pub fn handle_command(session: &Session, command: &str, params: &Map<String, Value>) -> CommandResult {
+    let event = match command {
+        "dispatchMouseEvent" => {
+            let event_type = match str_param(params, "type")? {
+                "mousePressed" => DevtoolsMouseEventType::Pressed,
+                "mouseReleased" => DevtoolsMouseEventType::Released,
+                "mouseMoved" => DevtoolsMouseEventType::Moved,
+                _ => return Err(CommandError::invalid_params("type: unsupported mouse event type")),
+            };
+            let button = match params.get("button").and_then(|button| button.as_str()) {
+                Some("left") => Some(DevtoolsMouseButton::Left),
+                Some("middle") => Some(DevtoolsMouseButton::Middle),
+                Some("right") => Some(DevtoolsMouseButton::Right),
+                _ => None,
+            };
+            DevtoolsInputEvent::Mouse {
+                event_type: event_type,
+                button: button,
+                x: f64_param(params, "x")? as f32,
+                y: f64_param(params, "y")? as f32,
+                click_count: params.get("clickCount").and_then(|count| count.as_u64()).unwrap_or(0) as u32,
+            }
+        }
+
+        "dispatchKeyEvent" => {
+            let state = match str_param(params, "type")? {
+                "keyDown" | "rawKeyDown" | "char" => KeyState::Pressed,
+                "keyUp" => KeyState::Released,
+                _ => return Err(CommandError::invalid_params("type: unsupported key event type")),
+            };
+            let text = params.get("text").and_then(|text| text.as_str()).and_then(|text| text.chars().next());
+            let key = match params.get("key").and_then(|key| key.as_str()) {
+                Some(key) => key.to_owned(),
+                None => match text {
+                    Some(text) => text.to_string(),
+                    None => return Err(CommandError::invalid_params("key is missing")),
+                },
+            };
+            DevtoolsInputEvent::Key {
+                key: key,
+                text: text,
+                state: state,
+                modifiers: modifiers(params),
+            }
+        }
+
+        _ => return Err(CommandError::method_not_found(command)),
+    };
+    session.target.send(DispatchInputEvent(session.target.pipeline, event))?;
+    Ok(json!({}))
+}
+
+/// Decode the protocol's modifier bit field (Alt=1, Ctrl=2, Meta/Command=4, Shift=8).
+fn modifiers(params: &Map<String, Value>) -> KeyModifiers {
+    let bits = params.get("modifiers").and_then(|modifiers| modifiers.as_u64()).unwrap_or(0);
+    let mut modifiers = KeyModifiers::empty();
+    modifiers.set(KeyModifiers::ALT, bits & 1 != 0);
+    modifiers.set(KeyModifiers::CONTROL, bits & 2 != 0);
+    modifiers.set(KeyModifiers::SUPER, bits & 4 != 0);
+    modifiers.set(KeyModifiers::SHIFT, bits & 8 != 0);
+    modifiers
+}
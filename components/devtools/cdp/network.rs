@@ -0,0 +1,176 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The [Network domain](https://chromedevtools.github.io/devtools-protocol/tot/Network).
+//! Events are derived from the same `NetworkEvent` feed as the Firefox
+//! `NetworkEventActor`.
+
+use cdp::server::{CommandError, CommandResult, Domain, Session, str_param};
+use devtools_traits::{HttpRequest, HttpResponse, NetworkEvent};
+use hyper::header::{ContentType, Headers};
+use msg::constellation_msg::PipelineId;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use time::get_time;
+
+struct RequestInfo {
+    pipeline: PipelineId,
+    url: String,
+    body: Option<Vec<u8>>,
+}
+
+/// The requests seen so far, so that responses can be matched to them and
+/// bodies handed out through `Network.getResponseBody`.
+pub struct NetworkState {
+    requests: HashMap<String, RequestInfo>,
+}
+
+impl NetworkState {
+    pub fn new() -> NetworkState {
+        NetworkState {
+            requests: HashMap::new(),
+        }
+    }
+
+    /// Record a network event, returning the protocol events it corresponds to.
+    pub fn handle_network_event(&mut self,
+                                request_id: String,
+                                network_event: NetworkEvent)
+                                -> Vec<(PipelineId, &'static str, Value)> {
+        match network_event {
+            NetworkEvent::HttpRequest(request) => {
+                let event = request_will_be_sent(&request_id, &request);
+                self.requests.insert(request_id, RequestInfo {
+                    pipeline: request.pipeline_id,
+                    url: request.url.into_string(),
+                    body: None,
+                });
+                vec![(request.pipeline_id, "Network.requestWillBeSent", event)]
+            }
+            NetworkEvent::HttpResponse(response) => {
+                let url = self.requests.get(&request_id)
+                                       .map(|request| request.url.clone())
+                                       .unwrap_or_default();
+                let now = timestamp();
+                let encoded_length = response.body.as_ref().map_or(0, |body| body.len());
+                let events = vec![
+                    (response.pipeline_id, "Network.responseReceived", json!({
+                        "requestId": request_id,
+                        "loaderId": "",
+                        "timestamp": now,
+                        "type": "Other",
+                        "response": response_description(&url, &response)
+                    })),
+                    (response.pipeline_id, "Network.loadingFinished", json!({
+                        "requestId": request_id,
+                        "timestamp": now,
+                        "encodedDataLength": encoded_length
+                    })),
+                ];
+                if let Some(request) = self.requests.get_mut(&request_id) {
+                    request.body = response.body;
+                }
+                events
+            }
+        }
+    }
+}
+
+pub fn handle_command(session: &Session, command: &str, params: &Map<String, Value>) -> CommandResult {
+    match command {
+        "enable" => {
+            session.set_enabled(Domain::Network, true);
+            Ok(json!({}))
+        }
+
+        "disable" => {
+            session.set_enabled(Domain::Network, false);
+            Ok(json!({}))
+        }
+
+        "getResponseBody" => {
+            let request_id = str_param(params, "requestId")?;
+            let pipeline = session.target.pipeline;
+            let body = session.with_state(|state| {
+                state.network.requests.get(request_id)
+                                      .filter(|request| request.pipeline == pipeline)
+                                      .and_then(|request| request.body.clone())
+            });
+            let body = body.ok_or_else(|| {
+                CommandError::server_error("No resource with given identifier found")
+            })?;
+            // We have no base64 encoder here, so only textual bodies can be returned.
+            let body = String::from_utf8(body).map_err(|_| {
+                CommandError::server_error("Response body is not text")
+            })?;
+            Ok(json!({ "body": body, "base64Encoded": false }))
+        }
+
+        _ => Err(CommandError::method_not_found(command)),
+    }
+}
+
+/// Seconds since the epoch, as used by protocol timestamps.
+fn timestamp() -> f64 {
+    let now = get_time();
+    now.sec as f64 + now.nsec as f64 / 1000000000.
+}
+
+fn headers_object(headers: &Headers) -> Value {
+    let mut object = Map::new();
+    for header in headers.iter() {
+        object.insert(header.name().to_owned(), Value::String(header.value_string()));
+    }
+    Value::Object(object)
+}
+
+fn request_will_be_sent(request_id: &str, request: &HttpRequest) -> Value {
+    let mut description = json!({
+        "url": request.url.as_str(),
+        "method": request.method.to_string(),
+        "headers": headers_object(&request.headers),
+        "initialPriority": "High",
+        "referrerPolicy": "no-referrer-when-downgrade"
+    });
+    if let Some(ref body) = request.body {
+        description.as_object_mut().unwrap().insert("postData".to_owned(),
+                                                    json!(String::from_utf8_lossy(body)));
+    }
+    let type_ = if request.is_xhr { "XHR" } else { "Other" };
+    json!({
+        "requestId": request_id,
+        "loaderId": "",
+        "documentURL": request.url.as_str(),
+        "request": description,
+        "timestamp": timestamp(),
+        "wallTime": request.startedDateTime.to_timespec().sec,
+        "initiator": { "type": "other" },
+        "type": type_
+    })
+}
+
+fn response_description(url: &str, response: &HttpResponse) -> Value {
+    let (status, status_text) = match response.status {
+        Some((code, ref text)) => (code, String::from_utf8_lossy(text).into_owned()),
+        None => (0, String::new()),
+    };
+    let mime_type = response.headers.as_ref()
+                                    .and_then(|headers| headers.get::<ContentType>())
+                                    .map(|content_type| {
+                                        let ContentType(ref mime) = *content_type;
+                                        format!("{}/{}", mime.0, mime.1)
+                                    })
+                                    .unwrap_or_default();
+    json!({
+        "url": url,
+        "status": status,
+        "statusText": status_text,
+        "headers": response.headers.as_ref().map_or(json!({}), headers_object),
+        "mimeType": mime_type,
+        "connectionReused": false,
+        "connectionId": 0,
+        "encodedDataLength": response.body.as_ref().map_or(0, |body| body.len()),
+        "securityState": "unknown"
+    })
+}
@@ -0,0 +1,57 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The [Page domain](https://chromedevtools.github.io/devtools-protocol/tot/Page).
+
+use cdp::server::{CommandError, CommandResult, Domain, Session, Target, str_param};
+use devtools_traits::DevtoolScriptControlMsg::{Navigate, Reload};
+use serde_json::{Map, Value};
+use servo_url::ServoUrl;
+
+pub fn handle_command(session: &Session, command: &str, params: &Map<String, Value>) -> CommandResult {
+    match command {
+        "enable" => {
+            session.set_enabled(Domain::Page, true);
+            Ok(json!({}))
+        }
+
+        "disable" => {
+            session.set_enabled(Domain::Page, false);
+            Ok(json!({}))
+        }
+
+        "getFrameTree" => Ok(json!({ "frameTree": { "frame": frame(&session.target) } })),
+
+        "navigate" => {
+            let url = ServoUrl::parse(str_param(params, "url")?)
+                .map_err(|_| CommandError::invalid_params("url: invalid URL"))?;
+            session.target.send(Navigate(session.target.pipeline, url))?;
+            Ok(json!({ "frameId": session.target.id }))
+        }
+
+        "reload" => {
+            session.target.send(Reload(session.target.pipeline))?;
+            Ok(json!({}))
+        }
+
+        _ => Err(CommandError::method_not_found(command)),
+    }
+}
+
+fn frame(target: &Target) -> Value {
+    let security_origin = ServoUrl::parse(&target.url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_default();
+    json!({
+        "id": target.id,
+        "loaderId": target.execution_context_id.to_string(),
+        "url": target.url,
+        "securityOrigin": security_origin,
+        "mimeType": "text/html"
+    })
+}
+
+pub fn frame_navigated(target: &Target) -> Value {
+    json!({ "frame": frame(target) })
+}
@@ -0,0 +1,135 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The [Runtime domain](https://chromedevtools.github.io/devtools-protocol/tot/Runtime).
+
+use cdp::server::{CommandError, CommandResult, Domain, Session, Target, recv, str_param};
+use devtools_traits::{ConsoleMessage, EvaluateJSReply, LogLevel};
+use devtools_traits::DevtoolScriptControlMsg::{EvaluateJS, WantsLiveNotifications};
+use ipc_channel::ipc;
+use serde_json::{Map, Value};
+use time::get_time;
+
+pub fn handle_command(session: &Session, command: &str, params: &Map<String, Value>) -> CommandResult {
+    match command {
+        "enable" => {
+            session.set_enabled(Domain::Runtime, true);
+            enable_live_notifications(&session.target)?;
+            session.send_event("Runtime.executionContextCreated",
+                               &execution_context_created(&session.target));
+            Ok(json!({}))
+        }
+
+        "disable" => {
+            session.set_enabled(Domain::Runtime, false);
+            Ok(json!({}))
+        }
+
+        "evaluate" => {
+            let expression = str_param(params, "expression")?;
+            let (chan, port) = ipc::channel().unwrap();
+            session.target.send(EvaluateJS(session.target.pipeline, expression.to_owned(), chan))?;
+            let reply = recv(port)?;
+            Ok(json!({ "result": remote_object(reply) }))
+        }
+
+        _ => Err(CommandError::method_not_found(command)),
+    }
+}
+
+pub fn enable_live_notifications(target: &Target) -> Result<(), CommandError> {
+    target.send(WantsLiveNotifications(target.pipeline, true))
+}
+
+pub fn execution_context_created(target: &Target) -> Value {
+    json!({
+        "context": {
+            "id": target.execution_context_id,
+            "origin": target.url,
+            "name": "",
+            "auxData": {
+                "frameId": target.id,
+                "isDefault": true
+            }
+        }
+    })
+}
+
+/// Convert a serialized JS value into a `Runtime.RemoteObject`.
+fn remote_object(reply: EvaluateJSReply) -> Value {
+    match reply {
+        EvaluateJSReply::VoidValue => json!({ "type": "undefined" }),
+        EvaluateJSReply::NullValue => json!({ "type": "object", "subtype": "null", "value": null }),
+        EvaluateJSReply::BooleanValue(value) => json!({ "type": "boolean", "value": value }),
+        EvaluateJSReply::NumberValue(value) => json!({
+            "type": "number",
+            "value": value,
+            "description": value.to_string()
+        }),
+        EvaluateJSReply::StringValue(value) => json!({ "type": "string", "value": value }),
+        EvaluateJSReply::ActorValue { class, uuid, .. } => json!({
+            "type": "object",
+            "className": class,
+            "description": class,
+            "objectId": uuid
+        }),
+    }
+}
+
+pub fn console_api_called(target: &Target, console_message: ConsoleMessage) -> Value {
+    let level = match console_message.logLevel {
+        LogLevel::Log => "log",
+        LogLevel::Debug => "debug",
+        LogLevel::Info => "info",
+        LogLevel::Warn => "warning",
+        LogLevel::Error => "error",
+        LogLevel::Assert => "assert",
+        LogLevel::Trace => "trace",
+        LogLevel::Dir => "dir",
+        LogLevel::DirXml => "dirxml",
+        LogLevel::Table => "table",
+        LogLevel::Count | LogLevel::CountReset => "count",
+        LogLevel::Group => "startGroup",
+        LogLevel::GroupCollapsed => "startGroupCollapsed",
+        LogLevel::GroupEnd => "endGroup",
+        LogLevel::Time => "log",
+        LogLevel::TimeEnd => "timeEnd",
+    };
+    // Counters and timers are reported as preformatted text, as Chrome does.
+    let args: Vec<Value> = match (console_message.counter, console_message.timer) {
+        (Some(counter), _) => vec![json!({
+            "type": "string",
+            "value": format!("{}: {}", counter.label, counter.count)
+        })],
+        (_, Some(ref timer)) if timer.duration.is_some() => vec![json!({
+            "type": "string",
+            "value": format!("{}: {}ms", timer.name, timer.duration.unwrap())
+        })],
+        _ => console_message.arguments.into_iter().map(remote_object).collect(),
+    };
+    let call_frames: Vec<Value> = match console_message.stacktrace {
+        Some(frames) => frames.into_iter().map(|frame| json!({
+            "functionName": frame.functionName,
+            "scriptId": "0",
+            "url": frame.filename,
+            "lineNumber": frame.lineNumber,
+            "columnNumber": frame.columnNumber
+        })).collect(),
+        None => vec![json!({
+            "functionName": "",
+            "scriptId": "0",
+            "url": console_message.filename,
+            "lineNumber": console_message.lineNumber,
+            "columnNumber": console_message.columnNumber
+        })],
+    };
+    let now = get_time();
+    json!({
+        "type": level,
+        "args": args,
+        "executionContextId": target.execution_context_id,
+        "timestamp": now.sec as f64 * 1000. + now.nsec as f64 / 1000000.,
+        "stackTrace": { "callFrames": call_frames }
+    })
+}
@@ -0,0 +1,559 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! A [Chrome DevTools Protocol](https://chromedevtools.github.io/devtools-protocol/)
+//! endpoint. Each browsing context is exposed as a `page` target that can be
+//! discovered through the `/json/list` HTTP endpoint and debugged over a WebSocket
+//! at `/devtools/page/<id>`. Commands are routed to the per-domain modules, which
+//! talk to script through the same `DevtoolScriptControlMsg` messages as the
+//! Firefox actors.
+
+use cdp::{dom, emulation, input, network, page, runtime};
+use devtools_traits::{ConsoleMessage, DevtoolScriptControlMsg, NetworkEvent};
+use ipc_channel::ipc::{IpcReceiver, IpcSender};
+use msg::constellation_msg::{BrowsingContextId, PipelineId};
+use serde::{Deserialize, Serialize};
+use serde_json::{self, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Sender, channel};
+use std::thread;
+use ws::{self, CloseCode, Handler, Handshake, Request, Response};
+
+/// The protocol version we advertise to clients.
+const PROTOCOL_VERSION: &'static str = "1.3";
+
+/// Messages from the devtools server thread that CDP clients may need to know about.
+pub enum CdpControlMsg {
+    /// A new window global was created for the given browsing context.
+    NewGlobal(BrowsingContextId, PipelineId, IpcSender<DevtoolScriptControlMsg>, String, String),
+    /// The given pipeline was shut down.
+    PipelineExited(PipelineId),
+    /// A page invoked the console API.
+    ConsoleAPI(PipelineId, ConsoleMessage),
+    /// A network event occurred for the request with the given id.
+    NetworkEvent(String, NetworkEvent),
+    /// The browser is shutting down.
+    Exit,
+}
+
+/// The protocol domains that produce events once enabled by a client.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Domain {
+    Dom,
+    Network,
+    Page,
+    Runtime,
+}
+
+/// A browsing context that clients can attach to.
+#[derive(Clone)]
+pub struct Target {
+    pub id: String,
+    pub pipeline: PipelineId,
+    pub script_chan: IpcSender<DevtoolScriptControlMsg>,
+    pub title: String,
+    pub url: String,
+    /// Incremented each time the browsing context gets a new global.
+    pub execution_context_id: u32,
+}
+
+impl Target {
+    /// Send a message to the script thread of this target, failing if its pipeline
+    /// has gone away.
+    pub fn send(&self, msg: DevtoolScriptControlMsg) -> Result<(), CommandError> {
+        self.script_chan.send(msg).map_err(|_| CommandError::target_closed())
+    }
+}
+
+/// Wait for script's reply to a message sent to a target, failing if its pipeline
+/// went away before answering.
+pub fn recv<T>(port: IpcReceiver<T>) -> Result<T, CommandError>
+    where T: for<'de> Deserialize<'de> + Serialize
+{
+    port.recv().map_err(|_| CommandError::target_closed())
+}
+
+struct SessionInfo {
+    target: String,
+    sender: ws::Sender,
+    enabled: HashSet<Domain>,
+    /// Maps script's unique node ids to the integer ids used by the DOM domain.
+    nodes: HashMap<String, u32>,
+    next_node_id: u32,
+}
+
+/// State shared between the connection handlers and the devtools server thread.
+pub struct CdpState {
+    targets: HashMap<BrowsingContextId, Target>,
+    sessions: HashMap<u32, SessionInfo>,
+    next_session_id: u32,
+    next_target_id: u32,
+    pub network: network::NetworkState,
+}
+
+impl CdpState {
+    fn new() -> CdpState {
+        CdpState {
+            targets: HashMap::new(),
+            sessions: HashMap::new(),
+            next_session_id: 0,
+            next_target_id: 0,
+            network: network::NetworkState::new(),
+        }
+    }
+
+    fn find_target(&self, id: &str) -> Option<&Target> {
+        self.targets.values().find(|target| target.id == id)
+    }
+
+    fn target_for_pipeline(&self, pipeline: PipelineId) -> Option<&Target> {
+        self.targets.values().find(|target| target.pipeline == pipeline)
+    }
+
+    /// Send an event to every session attached to `target` with `domain` enabled.
+    fn broadcast<T: Serialize>(&self, target: &str, domain: Domain, method: &str, params: &T) {
+        let packet = event_packet(method, params);
+        for session in self.sessions.values() {
+            if session.target == target && session.enabled.contains(&domain) {
+                let _ = session.sender.send(packet.clone());
+            }
+        }
+    }
+}
+
+/// A protocol-level error, reported to the client in the `error` member of the reply.
+#[derive(Debug, Serialize)]
+pub struct CommandError {
+    code: i32,
+    message: String,
+}
+
+impl CommandError {
+    pub fn method_not_found(method: &str) -> CommandError {
+        CommandError {
+            code: -32601,
+            message: format!("'{}' wasn't found", method),
+        }
+    }
+
+    pub fn invalid_params(message: &str) -> CommandError {
+        CommandError {
+            code: -32602,
+            message: format!("Invalid parameters: {}", message),
+        }
+    }
+
+    pub fn server_error(message: &str) -> CommandError {
+        CommandError {
+            code: -32000,
+            message: message.to_owned(),
+        }
+    }
+
+    pub fn target_closed() -> CommandError {
+        CommandError::server_error("Target closed")
+    }
+}
+
+pub type CommandResult = Result<Value, CommandError>;
+
+/// The context in which a single command is processed.
+pub struct Session {
+    id: u32,
+    state: Arc<Mutex<CdpState>>,
+    pub target: Target,
+}
+
+impl Session {
+    pub fn set_enabled(&self, domain: Domain, enabled: bool) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(session) = state.sessions.get_mut(&self.id) {
+            if enabled {
+                session.enabled.insert(domain);
+            } else {
+                session.enabled.remove(&domain);
+            }
+        }
+    }
+
+    /// Send an event to this session only.
+    pub fn send_event<T: Serialize>(&self, method: &str, params: &T) {
+        let state = self.state.lock().unwrap();
+        if let Some(session) = state.sessions.get(&self.id) {
+            let _ = session.sender.send(event_packet(method, params));
+        }
+    }
+
+    /// Return the protocol node id for the given script node id, allocating one if needed.
+    pub fn node_id(&self, unique_id: &str) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        let session = state.sessions.get_mut(&self.id).unwrap();
+        if let Some(id) = session.nodes.get(unique_id) {
+            return *id;
+        }
+        session.next_node_id += 1;
+        let id = session.next_node_id;
+        session.nodes.insert(unique_id.to_owned(), id);
+        id
+    }
+
+    /// Return the script node id for a node id previously handed out to this session.
+    pub fn unique_id(&self, node_id: u32) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state.sessions.get(&self.id).and_then(|session| {
+            session.nodes.iter()
+                         .find(|&(_, id)| *id == node_id)
+                         .map(|(unique_id, _)| unique_id.clone())
+        })
+    }
+
+    pub fn with_state<F, R>(&self, f: F) -> R where F: FnOnce(&mut CdpState) -> R {
+        f(&mut *self.state.lock().unwrap())
+    }
+}
+
+pub fn param<'a>(params: &'a Map<String, Value>, name: &str) -> Result<&'a Value, CommandError> {
+    params.get(name).ok_or_else(|| CommandError::invalid_params(&format!("{} is missing", name)))
+}
+
+pub fn str_param<'a>(params: &'a Map<String, Value>, name: &str) -> Result<&'a str, CommandError> {
+    param(params, name)?.as_str()
+        .ok_or_else(|| CommandError::invalid_params(&format!("{}: string value expected", name)))
+}
+
+pub fn f64_param(params: &Map<String, Value>, name: &str) -> Result<f64, CommandError> {
+    param(params, name)?.as_f64()
+        .ok_or_else(|| CommandError::invalid_params(&format!("{}: number value expected", name)))
+}
+
+pub fn u64_param(params: &Map<String, Value>, name: &str) -> Result<u64, CommandError> {
+    param(params, name)?.as_u64()
+        .ok_or_else(|| CommandError::invalid_params(&format!("{}: integer value expected", name)))
+}
+
+#[derive(Serialize)]
+struct EventPacket<'a, T: 'a> {
+    method: &'a str,
+    params: &'a T,
+}
+
+fn event_packet<T: Serialize>(method: &str, params: &T) -> String {
+    serde_json::to_string(&EventPacket {
+        method: method,
+        params: params,
+    }).unwrap()
+}
+
+#[derive(Serialize)]
+struct ResultPacket {
+    id: u64,
+    result: Value,
+}
+
+#[derive(Serialize)]
+struct ErrorPacket {
+    id: u64,
+    error: CommandError,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetDescription {
+    description: String,
+    devtools_frontend_url: String,
+    id: String,
+    title: String,
+    #[serde(rename = "type")]
+    type_: String,
+    url: String,
+    web_socket_debugger_url: String,
+}
+
+#[derive(Serialize)]
+struct VersionDescription {
+    #[serde(rename = "Browser")]
+    browser: String,
+    #[serde(rename = "Protocol-Version")]
+    protocol_version: String,
+}
+
+struct Connection {
+    out: ws::Sender,
+    state: Arc<Mutex<CdpState>>,
+    port: u16,
+    session: Option<u32>,
+}
+
+impl Connection {
+    fn json_response<T: Serialize>(value: &T) -> Response {
+        let body = serde_json::to_vec(value).unwrap();
+        let mut response = Response::new(200, "OK", body);
+        response.headers_mut().push(("Content-Type".to_owned(), b"application/json".to_vec()));
+        response
+    }
+
+    fn list_targets(&self) -> Vec<TargetDescription> {
+        let state = self.state.lock().unwrap();
+        state.targets.values().map(|target| {
+            TargetDescription {
+                description: String::new(),
+                devtools_frontend_url: String::new(),
+                id: target.id.clone(),
+                title: target.title.clone(),
+                type_: "page".to_owned(),
+                url: target.url.clone(),
+                web_socket_debugger_url: format!("ws://127.0.0.1:{}/devtools/page/{}",
+                                                 self.port, target.id),
+            }
+        }).collect()
+    }
+
+    fn handle_command(&self, method: &str, params: &Map<String, Value>) -> CommandResult {
+        let session_id = match self.session {
+            Some(id) => id,
+            None => return Err(CommandError::server_error("Not attached to a target")),
+        };
+        let target = {
+            let state = self.state.lock().unwrap();
+            match state.sessions.get(&session_id).and_then(|session| state.find_target(&session.target)) {
+                Some(target) => target.clone(),
+                None => return Err(CommandError::target_closed()),
+            }
+        };
+        let session = Session {
+            id: session_id,
+            state: self.state.clone(),
+            target: target,
+        };
+
+        let mut parts = method.splitn(2, '.');
+        let domain = parts.next().unwrap();
+        let command = match parts.next() {
+            Some(command) => command,
+            None => return Err(CommandError::method_not_found(method)),
+        };
+        let result = match domain {
+            "Browser" if command == "getVersion" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "product": "Servo",
+                "revision": "",
+                "userAgent": "",
+                "jsVersion": ""
+            })),
+            "DOM" => dom::handle_command(&session, command, params),
+            "Emulation" => emulation::handle_command(&session, command, params),
+            "Input" => input::handle_command(&session, command, params),
+            "Network" => network::handle_command(&session, command, params),
+            "Page" => page::handle_command(&session, command, params),
+            "Runtime" => runtime::handle_command(&session, command, params),
+            _ => Err(CommandError::method_not_found(method)),
+        };
+        // Domains report unknown commands without the domain prefix.
+        result.map_err(|error| {
+            if error.code == -32601 {
+                CommandError::method_not_found(method)
+            } else {
+                error
+            }
+        })
+    }
+}
+
+impl Handler for Connection {
+    fn on_request(&mut self, req: &Request) -> ws::Result<Response> {
+        let resource = req.resource().trim_right_matches('/').to_owned();
+        match &*resource {
+            "/json" | "/json/list" => Ok(Connection::json_response(&self.list_targets())),
+            "/json/version" => Ok(Connection::json_response(&VersionDescription {
+                browser: "Servo".to_owned(),
+                protocol_version: PROTOCOL_VERSION.to_owned(),
+            })),
+            _ => {
+                let known_target = resource.starts_with("/devtools/page/") && {
+                    let state = self.state.lock().unwrap();
+                    state.find_target(&resource["/devtools/page/".len()..]).is_some()
+                };
+                if known_target {
+                    Response::from_request(req)
+                } else {
+                    Ok(Response::new(404, "Not Found", b"No such target".to_vec()))
+                }
+            }
+        }
+    }
+
+    fn on_open(&mut self, shake: Handshake) -> ws::Result<()> {
+        let target = shake.request.resource()["/devtools/page/".len()..].to_owned();
+        debug!("CDP session opened for target {}", target);
+        let mut state = self.state.lock().unwrap();
+        state.next_session_id += 1;
+        let id = state.next_session_id;
+        state.sessions.insert(id, SessionInfo {
+            target: target,
+            sender: self.out.clone(),
+            enabled: HashSet::new(),
+            nodes: HashMap::new(),
+            next_node_id: 0,
+        });
+        self.session = Some(id);
+        Ok(())
+    }
+
+    fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+        let text = msg.into_text()?;
+        debug!("CDP -> {}", text);
+        let packet: Value = match serde_json::from_str(&text) {
+            Ok(packet) => packet,
+            Err(err) => {
+                warn!("Malformed CDP message: {}", err);
+                return Ok(());
+            }
+        };
+        let id = match packet.get("id").and_then(|id| id.as_u64()) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let method = packet.get("method").and_then(|method| method.as_str()).unwrap_or("");
+        let empty = Map::new();
+        let params = packet.get("params").and_then(|params| params.as_object()).unwrap_or(&empty);
+
+        let reply = match self.handle_command(method, params) {
+            Ok(result) => serde_json::to_string(&ResultPacket { id: id, result: result }),
+            Err(error) => serde_json::to_string(&ErrorPacket { id: id, error: error }),
+        }.unwrap();
+        debug!("CDP <- {}", reply);
+        self.out.send(reply)
+    }
+
+    fn on_close(&mut self, _: CloseCode, _: &str) {
+        if let Some(id) = self.session.take() {
+            debug!("CDP session {} closed", id);
+            self.state.lock().unwrap().sessions.remove(&id);
+        }
+    }
+}
+
+fn handle_new_global(state: &mut CdpState,
+                     browsing_context: BrowsingContextId,
+                     pipeline: PipelineId,
+                     script_chan: IpcSender<DevtoolScriptControlMsg>,
+                     title: String,
+                     url: String) {
+    let target = match state.targets.entry(browsing_context) {
+        Entry::Occupied(mut entry) => {
+            let target = entry.get_mut();
+            target.pipeline = pipeline;
+            target.script_chan = script_chan;
+            target.title = title;
+            target.url = url;
+            target.execution_context_id += 1;
+            target.clone()
+        }
+        Entry::Vacant(entry) => {
+            state.next_target_id += 1;
+            entry.insert(Target {
+                id: format!("page-{}", state.next_target_id),
+                pipeline: pipeline,
+                script_chan: script_chan,
+                title: title,
+                url: url,
+                execution_context_id: 1,
+            });
+            return;
+        }
+    };
+
+    // An existing target navigated; let attached clients know about the new document.
+    for session in state.sessions.values_mut() {
+        if session.target == target.id {
+            session.nodes.clear();
+            session.next_node_id = 0;
+        }
+    }
+    state.broadcast(&target.id, Domain::Page, "Page.frameNavigated", &page::frame_navigated(&target));
+    state.broadcast(&target.id, Domain::Dom, "DOM.documentUpdated", &json!({}));
+    let runtime_enabled = state.sessions.values().any(|session| {
+        session.target == target.id && session.enabled.contains(&Domain::Runtime)
+    });
+    if runtime_enabled {
+        let _ = runtime::enable_live_notifications(&target);
+        state.broadcast(&target.id, Domain::Runtime, "Runtime.executionContextsCleared", &json!({}));
+        state.broadcast(&target.id, Domain::Runtime, "Runtime.executionContextCreated",
+                        &runtime::execution_context_created(&target));
+    }
+}
+
+/// Remove the target whose current document belongs to `pipeline`, detaching the
+/// clients attached to it. Pipelines that were navigated away from no longer back a
+/// target, so this only happens when the browsing context is closed.
+fn handle_pipeline_exited(state: &mut CdpState, pipeline: PipelineId) {
+    let browsing_context = match state.targets.iter().find(|&(_, target)| target.pipeline == pipeline) {
+        Some((browsing_context, _)) => *browsing_context,
+        None => return,
+    };
+    let target = state.targets.remove(&browsing_context).unwrap();
+    debug!("CDP target {} closed", target.id);
+    let detached = event_packet("Inspector.detached", &json!({ "reason": "target_closed" }));
+    state.sessions.retain(|_, session| {
+        if session.target != target.id {
+            return true;
+        }
+        let _ = session.sender.send(detached.clone());
+        let _ = session.sender.close(CloseCode::Away);
+        false
+    });
+}
+
+/// Spin up a CDP server that listens for connections on the specified port.
+pub fn start_server(port: u16) -> Sender<CdpControlMsg> {
+    let (sender, receiver) = channel();
+    let state = Arc::new(Mutex::new(CdpState::new()));
+    thread::Builder::new().name("CdpServer".to_owned()).spawn(move || {
+        let connection_state = state.clone();
+        let socket = ws::Builder::new().build(move |out: ws::Sender| {
+            Connection {
+                out: out,
+                state: connection_state.clone(),
+                port: port,
+                session: None,
+            }
+        }).unwrap();
+        let broadcaster = socket.broadcaster();
+        thread::Builder::new().name("CdpWebSocket".to_owned()).spawn(move || {
+            socket.listen(("127.0.0.1", port)).unwrap();
+        }).expect("Thread spawning failed");
+
+        while let Ok(msg) = receiver.recv() {
+            let mut state = state.lock().unwrap();
+            match msg {
+                CdpControlMsg::NewGlobal(browsing_context, pipeline, script_chan, title, url) =>
+                    handle_new_global(&mut state, browsing_context, pipeline, script_chan, title, url),
+                CdpControlMsg::PipelineExited(pipeline) => handle_pipeline_exited(&mut state, pipeline),
+                CdpControlMsg::ConsoleAPI(pipeline, console_message) => {
+                    let target = match state.target_for_pipeline(pipeline) {
+                        Some(target) => target.clone(),
+                        None => continue,
+                    };
+                    state.broadcast(&target.id, Domain::Runtime, "Runtime.consoleAPICalled",
+                                    &runtime::console_api_called(&target, console_message));
+                }
+                CdpControlMsg::NetworkEvent(request_id, network_event) => {
+                    let events = state.network.handle_network_event(request_id, network_event);
+                    for (pipeline, method, params) in events {
+                        let target = match state.target_for_pipeline(pipeline) {
+                            Some(target) => target.id.clone(),
+                            None => continue,
+                        };
+                        state.broadcast(&target, Domain::Network, method, &params);
+                    }
+                }
+                CdpControlMsg::Exit => break,
+            }
+        }
+        let _ = broadcaster.shutdown();
+    }).expect("Thread spawning failed");
+    sender
+}
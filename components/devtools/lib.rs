@@ -6,6 +6,9 @@
 //! nightly Firefox versions at time of writing. Largely based on
 //! reverse-engineering of Firefox chrome devtool logs and reading of
 //! [code](http://mxr.mozilla.org/mozilla-central/source/toolkit/devtools/server/).
+//!
+//! The same browser events can also be exposed through a Chrome DevTools
+//! Protocol endpoint; see the `cdp` module.
 
 #![crate_name = "devtools"]
 #![crate_type = "rlib"]
@@ -19,28 +22,38 @@ extern crate ipc_channel;
 #[macro_use]
 extern crate log;
 extern crate msg;
+extern crate profile_traits;
 #[macro_use] extern crate serde;
-extern crate serde_json;
+#[macro_use] extern crate serde_json;
+extern crate servo_url;
 extern crate time;
+extern crate ws;
 
 use actor::{Actor, ActorRegistry};
 use actors::console::ConsoleActor;
 use actors::framerate::FramerateActor;
 use actors::inspector::InspectorActor;
+use actors::memory::MemoryActor;
 use actors::network_event::{EventActor, NetworkEventActor, ResponseStartMsg};
+use actors::object::value_to_grip;
 use actors::performance::PerformanceActor;
 use actors::profiler::ProfilerActor;
 use actors::root::RootActor;
 use actors::tab::TabActor;
 use actors::thread::ThreadActor;
 use actors::timeline::TimelineActor;
+use actors::webgl::WebGLActor;
 use actors::worker::WorkerActor;
-use devtools_traits::{ChromeToDevtoolsControlMsg, ConsoleMessage, DevtoolsControlMsg};
-use devtools_traits::{DevtoolScriptControlMsg, DevtoolsPageInfo, LogLevel, NetworkEvent};
+use cdp::server::CdpControlMsg;
+use devtools_traits::{ChromeToDevtoolsControlMsg, ConsoleCounter, ConsoleMessage, ConsoleTimer};
+use devtools_traits::{DevtoolScriptControlMsg, DevtoolsControlMsg, DevtoolsPageInfo, EvaluateJSReply};
+use devtools_traits::{LogLevel, NetworkEvent, StackFrame};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
+use profile_traits::mem::ProfilerChan;
 use protocol::JsonPacketStream;
+use serde_json::Value;
 use std::borrow::ToOwned;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -66,8 +79,19 @@ mod actors {
     pub mod tab;
     pub mod thread;
     pub mod timeline;
+    pub mod webgl;
     pub mod worker;
 }
+/// Corresponds to https://chromedevtools.github.io/devtools-protocol/
+mod cdp {
+    pub mod dom;
+    pub mod emulation;
+    pub mod input;
+    pub mod network;
+    pub mod page;
+    pub mod runtime;
+    pub mod server;
+}
 mod protocol;
 
 #[derive(Serialize)]
@@ -82,10 +106,14 @@ struct ConsoleAPICall {
 struct ConsoleMsg {
     level: String,
     timeStamp: u64,
-    arguments: Vec<String>,
+    arguments: Vec<Value>,
     filename: String,
     lineNumber: usize,
     columnNumber: usize,
+    groupName: String,
+    stacktrace: Option<Vec<StackFrame>>,
+    counter: Option<ConsoleCounter>,
+    timer: Option<ConsoleTimer>,
 }
 
 #[derive(Serialize)]
@@ -123,13 +151,17 @@ struct ResponseStartUpdateMsg {
     response: ResponseStartMsg,
 }
 
-/// Spin up a devtools server that listens for connections on the specified port.
-pub fn start_server(port: u16) -> Sender<DevtoolsControlMsg> {
+/// Spin up a devtools server that listens for Firefox remote protocol connections
+/// on `port` and Chrome DevTools Protocol connections on `cdp_port`, if specified.
+pub fn start_server(port: Option<u16>,
+                    cdp_port: Option<u16>,
+                    mem_profiler_chan: ProfilerChan)
+                    -> Sender<DevtoolsControlMsg> {
     let (sender, receiver) = channel();
     {
         let sender = sender.clone();
         thread::Builder::new().name("Devtools".to_owned()).spawn(move || {
-            run_server(sender, receiver, port)
+            run_server(sender, receiver, port, cdp_port, mem_profiler_chan)
         }).expect("Thread spawning failed");
     }
     sender
@@ -137,8 +169,10 @@ pub fn start_server(port: u16) -> Sender<DevtoolsControlMsg> {
 
 fn run_server(sender: Sender<DevtoolsControlMsg>,
               receiver: Receiver<DevtoolsControlMsg>,
-              port: u16) {
-    let listener = TcpListener::bind(&("127.0.0.1", port)).unwrap();
+              port: Option<u16>,
+              cdp_port: Option<u16>,
+              mem_profiler_chan: ProfilerChan) {
+    let cdp_chan = cdp_port.map(cdp::server::start_server);
 
     let mut registry = ActorRegistry::new();
 
@@ -204,13 +238,14 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                          script_sender: IpcSender<DevtoolScriptControlMsg>,
                          actor_pipelines: &mut HashMap<PipelineId, String>,
                          actor_workers: &mut HashMap<(PipelineId, WorkerId), String>,
-                         page_info: DevtoolsPageInfo) {
+                         page_info: DevtoolsPageInfo,
+                         mem_profiler_chan: &ProfilerChan) {
         let mut actors = actors.lock().unwrap();
 
         let (pipeline, worker_id) = ids;
 
         //TODO: move all this actor creation into a constructor method on TabActor
-        let (tab, console, inspector, timeline, profiler, performance, thread) = {
+        let (tab, console, inspector, timeline, profiler, performance, memory, webgl, thread) = {
             let console = ConsoleActor {
                 name: actors.new_name("console"),
                 script_chan: script_sender.clone(),
@@ -226,16 +261,28 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                 pipeline: pipeline,
             };
 
+            let DevtoolsPageInfo { title, url, .. } = page_info;
+
+            let memory = MemoryActor::new(actors.new_name("memory"),
+                                          pipeline,
+                                          script_sender.clone(),
+                                          url.as_str().to_owned(),
+                                          mem_profiler_chan.clone());
+
+            let webgl = WebGLActor::new(actors.new_name("webgl"),
+                                        pipeline,
+                                        script_sender.clone());
+
             let timeline = TimelineActor::new(actors.new_name("timeline"),
                                               pipeline,
-                                              script_sender);
+                                              script_sender,
+                                              memory.name());
 
             let profiler = ProfilerActor::new(actors.new_name("profiler"));
             let performance = PerformanceActor::new(actors.new_name("performance"));
 
             let thread = ThreadActor::new(actors.new_name("context"));
 
-            let DevtoolsPageInfo { title, url } = page_info;
             let tab = TabActor {
                 name: actors.new_name("tab"),
                 title: String::from(title),
@@ -245,13 +292,15 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                 timeline: timeline.name(),
                 profiler: profiler.name(),
                 performance: performance.name(),
+                memory: memory.name(),
+                webgl: webgl.name(),
                 thread: thread.name(),
             };
 
             let root = actors.find_mut::<RootActor>("root");
             root.tabs.push(tab.name.clone());
 
-            (tab, console, inspector, timeline, profiler, performance, thread)
+            (tab, console, inspector, timeline, profiler, performance, memory, webgl, thread)
         };
 
         if let Some(id) = worker_id {
@@ -271,6 +320,8 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         actors.register(Box::new(timeline));
         actors.register(Box::new(profiler));
         actors.register(Box::new(performance));
+        actors.register(Box::new(memory));
+        actors.register(Box::new(webgl));
         actors.register(Box::new(thread));
     }
 
@@ -287,22 +338,49 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         };
         let actors = actors.lock().unwrap();
         let console_actor = actors.find::<ConsoleActor>(&console_actor_name);
+        let group_name = match console_message.logLevel {
+            LogLevel::Group | LogLevel::GroupCollapsed => {
+                console_message.arguments.iter().filter_map(|argument| match *argument {
+                    EvaluateJSReply::StringValue(ref s) => Some(s.clone()),
+                    _ => None,
+                }).collect::<Vec<_>>().join(" ")
+            },
+            _ => String::new(),
+        };
         let msg = ConsoleAPICall {
             from: console_actor.name.clone(),
             type_: "consoleAPICall".to_owned(),
             message: ConsoleMsg {
                 level: match console_message.logLevel {
+                    LogLevel::Log => "log",
                     LogLevel::Debug => "debug",
                     LogLevel::Info => "info",
                     LogLevel::Warn => "warn",
                     LogLevel::Error => "error",
-                    _ => "log"
+                    LogLevel::Assert => "assert",
+                    LogLevel::Trace => "trace",
+                    LogLevel::Dir => "dir",
+                    LogLevel::DirXml => "dirxml",
+                    LogLevel::Table => "table",
+                    LogLevel::Count => "count",
+                    LogLevel::CountReset => "countReset",
+                    LogLevel::Group => "group",
+                    LogLevel::GroupCollapsed => "groupCollapsed",
+                    LogLevel::GroupEnd => "groupEnd",
+                    LogLevel::Time => "time",
+                    LogLevel::TimeEnd => "timeEnd",
                 }.to_owned(),
                 timeStamp: precise_time_ns(),
-                arguments: vec!(console_message.message),
+                arguments: console_message.arguments.into_iter().map(|argument| {
+                    value_to_grip(&actors, argument)
+                }).collect(),
                 filename: console_message.filename,
                 lineNumber: console_message.lineNumber,
                 columnNumber: console_message.columnNumber,
+                groupName: group_name,
+                stacktrace: console_message.stacktrace,
+                counter: console_message.counter,
+                timer: console_message.timer,
             },
         };
         for stream in &mut *console_actor.streams.borrow_mut() {
@@ -466,15 +544,18 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
         }
     }
 
-    let sender_clone = sender.clone();
-    thread::Builder::new().name("DevtoolsClientAcceptor".to_owned()).spawn(move || {
-        // accept connections and process them, spawning a new thread for each one
-        for stream in listener.incoming() {
-            // connection succeeded
-            sender_clone.send(DevtoolsControlMsg::FromChrome(
-                    ChromeToDevtoolsControlMsg::AddClient(stream.unwrap()))).unwrap();
-        }
-    }).expect("Thread spawning failed");
+    if let Some(port) = port {
+        let listener = TcpListener::bind(&("127.0.0.1", port)).unwrap();
+        let sender_clone = sender.clone();
+        thread::Builder::new().name("DevtoolsClientAcceptor".to_owned()).spawn(move || {
+            // accept connections and process them, spawning a new thread for each one
+            for stream in listener.incoming() {
+                // connection succeeded
+                sender_clone.send(DevtoolsControlMsg::FromChrome(
+                        ChromeToDevtoolsControlMsg::AddClient(stream.unwrap()))).unwrap();
+            }
+        }).expect("Thread spawning failed");
+    }
 
     while let Ok(msg) = receiver.recv() {
         match msg {
@@ -489,28 +570,51 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                         actor_name, tick)) =>
                 handle_framerate_tick(actors.clone(), actor_name, tick),
             DevtoolsControlMsg::FromScript(ScriptToDevtoolsControlMsg::NewGlobal(
-                        ids, script_sender, pageinfo)) =>
+                        ids, script_sender, pageinfo)) => {
+                if let (Some(chan), Some(browsing_context)) = (cdp_chan.as_ref(), pageinfo.browsing_context) {
+                    let _ = chan.send(CdpControlMsg::NewGlobal(browsing_context,
+                                                               ids.0,
+                                                               script_sender.clone(),
+                                                               pageinfo.title.clone(),
+                                                               pageinfo.url.as_str().to_owned()));
+                }
                 handle_new_global(actors.clone(), ids, script_sender, &mut actor_pipelines,
-                                  &mut actor_workers, pageinfo),
+                                  &mut actor_workers, pageinfo, &mem_profiler_chan)
+            }
             DevtoolsControlMsg::FromScript(ScriptToDevtoolsControlMsg::ConsoleAPI(
                         id,
                         console_message,
-                        worker_id)) =>
+                        worker_id)) => {
+                if let (Some(chan), None) = (cdp_chan.as_ref(), worker_id) {
+                    let _ = chan.send(CdpControlMsg::ConsoleAPI(id, console_message.clone()));
+                }
                 handle_console_message(actors.clone(), id, worker_id, console_message,
-                                       &actor_pipelines, &actor_workers),
+                                       &actor_pipelines, &actor_workers)
+            }
             DevtoolsControlMsg::FromScript(ScriptToDevtoolsControlMsg::ReportCSSError(
                         id,
                         css_error)) => {
                 let console_message =  ConsoleMessage {
-                    message: css_error.msg,
+                    arguments: vec![EvaluateJSReply::StringValue(css_error.msg)],
                     logLevel: LogLevel::Warn,
                     filename: css_error.filename,
                     lineNumber: css_error.line as usize,
                     columnNumber: css_error.column as usize,
+                    stacktrace: None,
+                    counter: None,
+                    timer: None,
                 };
+                if let Some(ref chan) = cdp_chan {
+                    let _ = chan.send(CdpControlMsg::ConsoleAPI(id, console_message.clone()));
+                }
                 handle_console_message(actors.clone(), id, None, console_message,
                                        &actor_pipelines, &actor_workers)
             },
+            DevtoolsControlMsg::FromScript(ScriptToDevtoolsControlMsg::PipelineExited(id)) => {
+                if let Some(ref chan) = cdp_chan {
+                    let _ = chan.send(CdpControlMsg::PipelineExited(id));
+                }
+            }
             DevtoolsControlMsg::FromChrome(ChromeToDevtoolsControlMsg::NetworkEvent(
                         request_id, network_event)) => {
                 // copy the accepted_connections vector
@@ -523,12 +627,18 @@ fn run_server(sender: Sender<DevtoolsControlMsg>,
                     NetworkEvent::HttpResponse(ref response) => response.pipeline_id,
                     NetworkEvent::HttpRequest(ref request) => request.pipeline_id,
                 };
+                if let Some(ref chan) = cdp_chan {
+                    let _ = chan.send(CdpControlMsg::NetworkEvent(request_id.clone(), network_event.clone()));
+                }
                 handle_network_event(actors.clone(), connections, &actor_pipelines, &mut actor_requests,
                                      &actor_workers, pipeline_id, request_id, network_event);
             },
             DevtoolsControlMsg::FromChrome(ChromeToDevtoolsControlMsg::ServerExitMsg) => break
         }
     }
+    if let Some(ref chan) = cdp_chan {
+        let _ = chan.send(CdpControlMsg::Exit);
+    }
     for connection in &mut accepted_connections {
         let _ = connection.shutdown(Shutdown::Both);
     }
@@ -0,0 +1,163 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate devtools;
+extern crate devtools_traits;
+extern crate ipc_channel;
+extern crate msg;
+extern crate profile_traits;
+#[macro_use] extern crate serde_json;
+extern crate servo_url;
+extern crate ws;
+
+use devtools_traits::{DevtoolScriptControlMsg, DevtoolsControlMsg, DevtoolsPageInfo, EvaluateJSReply};
+use devtools_traits::ScriptToDevtoolsControlMsg;
+use ipc_channel::ipc::{self, IpcReceiver};
+use msg::constellation_msg::{BrowsingContextId, PipelineId, PipelineNamespace, PipelineNamespaceId};
+use profile_traits::mem::ProfilerChan;
+use serde_json::Value;
+use servo_url::ServoUrl;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+/// A devtools server with its CDP endpoint on a free port, and a page target whose
+/// script thread is played by the test.
+struct Server {
+    port: u16,
+    devtools: Sender<DevtoolsControlMsg>,
+    pipeline: PipelineId,
+    script: IpcReceiver<DevtoolScriptControlMsg>,
+}
+
+fn start_server(namespace: u32) -> Server {
+    PipelineNamespace::install(PipelineNamespaceId(namespace));
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let (mem_profiler_chan, _) = ipc::channel().unwrap();
+    let devtools = devtools::start_server(None, Some(port), ProfilerChan(mem_profiler_chan));
+
+    let pipeline = PipelineId::new();
+    let (script_chan, script) = ipc::channel().unwrap();
+    let page_info = DevtoolsPageInfo {
+        title: "Test page".to_owned(),
+        url: ServoUrl::parse("http://example.com/").unwrap(),
+        browsing_context: Some(BrowsingContextId::new()),
+    };
+    devtools.send(DevtoolsControlMsg::FromScript(
+        ScriptToDevtoolsControlMsg::NewGlobal((pipeline, None), script_chan, page_info))).unwrap();
+
+    Server {
+        port: port,
+        devtools: devtools,
+        pipeline: pipeline,
+        script: script,
+    }
+}
+
+/// Fetch `/json/list`, retrying until the server has started and knows about the
+/// expected number of targets.
+fn list_targets(port: u16, expected: usize) -> Vec<Value> {
+    for _ in 0..100 {
+        if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)) {
+            stream.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+            stream.write_all(b"GET /json/list HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n").unwrap();
+            let mut response = vec![];
+            let _ = stream.read_to_end(&mut response);
+            let response = String::from_utf8(response).unwrap();
+            if let Some(index) = response.find("\r\n\r\n") {
+                if let Ok(Value::Array(targets)) = serde_json::from_str(&response[index + 4..]) {
+                    if targets.len() == expected {
+                        return targets;
+                    }
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("The server never listed {} targets", expected);
+}
+
+/// Send a single command over a new WebSocket connection and return the reply.
+fn send_command(url: &str, command: Value) -> Value {
+    let (sender, receiver) = channel();
+    ws::connect(url, move |out: ws::Sender| {
+        out.send(command.to_string()).unwrap();
+        let sender = sender.clone();
+        move |msg: ws::Message| {
+            let reply: Value = serde_json::from_str(&msg.into_text()?).unwrap();
+            // Skip events; the reply is the only packet with an id.
+            if reply.get("id").is_some() {
+                sender.send(reply).unwrap();
+                out.close(ws::CloseCode::Normal)?;
+            }
+            Ok(())
+        }
+    }).unwrap();
+    receiver.recv().unwrap()
+}
+
+#[test]
+fn test_list_targets() {
+    let server = start_server(1);
+    let targets = list_targets(server.port, 1);
+    let target = &targets[0];
+    assert_eq!(target["type"], "page");
+    assert_eq!(target["title"], "Test page");
+    assert_eq!(target["url"], "http://example.com/");
+    let id = target["id"].as_str().unwrap();
+    assert_eq!(target["webSocketDebuggerUrl"],
+               Value::String(format!("ws://127.0.0.1:{}/devtools/page/{}", server.port, id)));
+
+    // Closing the target's pipeline removes the target.
+    server.devtools.send(DevtoolsControlMsg::FromScript(
+        ScriptToDevtoolsControlMsg::PipelineExited(server.pipeline))).unwrap();
+    assert!(list_targets(server.port, 0).is_empty());
+}
+
+#[test]
+fn test_evaluate_round_trip() {
+    let server = start_server(2);
+    let targets = list_targets(server.port, 1);
+    let url = targets[0]["webSocketDebuggerUrl"].as_str().unwrap().to_owned();
+
+    let script = server.script;
+    let pipeline = server.pipeline;
+    let script_thread = thread::spawn(move || {
+        match script.recv().unwrap() {
+            DevtoolScriptControlMsg::EvaluateJS(id, expression, reply) => {
+                assert_eq!(id, pipeline);
+                assert_eq!(expression, "1 + 1");
+                reply.send(EvaluateJSReply::NumberValue(2.)).unwrap();
+            }
+            _ => panic!("Unexpected message"),
+        }
+        // The script thread goes away without answering the second evaluation.
+        match script.recv().unwrap() {
+            DevtoolScriptControlMsg::EvaluateJS(..) => {}
+            _ => panic!("Unexpected message"),
+        }
+    });
+
+    let reply = send_command(&url, json!({
+        "id": 1,
+        "method": "Runtime.evaluate",
+        "params": { "expression": "1 + 1" }
+    }));
+    assert_eq!(reply["id"], 1);
+    assert_eq!(reply["result"]["result"]["type"], "number");
+    assert_eq!(reply["result"]["result"]["value"], 2.);
+
+    let reply = send_command(&url, json!({
+        "id": 2,
+        "method": "Runtime.evaluate",
+        "params": { "expression": "2 + 2" }
+    }));
+    assert_eq!(reply["id"], 2);
+    assert_eq!(reply["error"]["code"], -32000);
+    assert_eq!(reply["error"]["message"], "Target closed");
+
+    script_thread.join().unwrap();
+}
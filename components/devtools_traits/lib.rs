@@ -26,7 +26,7 @@ extern crate time;
 use hyper::header::Headers;
 use hyper::method::Method;
 use ipc_channel::ipc::IpcSender;
-use msg::constellation_msg::PipelineId;
+use msg::constellation_msg::{BrowsingContextId, KeyModifiers, KeyState, PipelineId};
 use servo_url::ServoUrl;
 use std::net::TcpStream;
 use time::Duration;
@@ -38,6 +38,8 @@ use time::Tm;
 pub struct DevtoolsPageInfo {
     pub title: String,
     pub url: ServoUrl,
+    /// The browsing context of a window global; `None` for worker globals.
+    pub browsing_context: Option<BrowsingContextId>,
 }
 
 #[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
@@ -86,18 +88,31 @@ pub enum ScriptToDevtoolsControlMsg {
 
     /// Report a CSS parse error for the given pipeline
     ReportCSSError(PipelineId, CSSError),
+
+    /// The given pipeline was shut down.
+    PipelineExited(PipelineId),
 }
 
-/// Serialized JS return values
-/// TODO: generalize this beyond the EvaluateJS message?
-#[derive(Debug, Deserialize, Serialize)]
+/// Serialized JS values, as returned by `EvaluateJS` or passed to the console API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum EvaluateJSReply {
     VoidValue,
     NullValue,
     BooleanValue(bool),
     NumberValue(f64),
     StringValue(String),
-    ActorValue { class: String, uuid: String },
+    ActorValue { class: String, uuid: String, preview: Option<ObjectPreview> },
+}
+
+/// A shallow copy of an object's own enumerable properties, so that clients can
+/// display console arguments without further round trips to script.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ObjectPreview {
+    /// The length of array-like objects.
+    pub length: Option<u32>,
+    pub ownProperties: Vec<(String, EvaluateJSReply)>,
+    /// The number of own properties, which may exceed the number previewed.
+    pub ownPropertiesLength: u32,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -129,6 +144,30 @@ pub struct NodeInfo {
     pub incompleteValue: bool,
 }
 
+/// A change to a document being inspected, reported once the script that caused it
+/// reaches a microtask checkpoint.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum DomMutation {
+    /// Children were added to or removed from the target node.
+    ChildList {
+        target: String,
+        added: Vec<NodeInfo>,
+        removed: Vec<String>,
+        numChildren: usize,
+    },
+    /// An attribute of the target element was set or removed.
+    Attributes {
+        target: String,
+        attributeName: String,
+        newValue: Option<String>,
+    },
+    /// The data of the target text, comment or processing instruction node changed.
+    CharacterData {
+        target: String,
+        newValue: String,
+    },
+}
+
 pub struct StartedTimelineMarker {
     name: String,
     start_time: PreciseTime,
@@ -210,11 +249,95 @@ pub enum DevtoolScriptControlMsg {
     SetTimelineMarkers(PipelineId, Vec<TimelineMarkerType>, IpcSender<Option<TimelineMarker>>),
     /// Withdraw request for live timeline notifications for a given pipeline.
     DropTimelineMarkers(PipelineId, Vec<TimelineMarkerType>),
+    /// Request live notifications of DOM mutations in the document of a given pipeline.
+    SetMutationWatcher(PipelineId, IpcSender<Vec<DomMutation>>),
+    /// Withdraw request for live DOM mutation notifications for a given pipeline.
+    DropMutationWatcher(PipelineId),
     /// Request a callback directed at the given actor name from the next animation frame
     /// executed in the given pipeline.
     RequestAnimationFrame(PipelineId, String),
     /// Direct the given pipeline to reload the current page.
     Reload(PipelineId),
+    /// Direct the given pipeline to navigate to a new URL.
+    Navigate(PipelineId, ServoUrl),
+    /// Dispatch a synthetic input event to the document of the given pipeline.
+    DispatchInputEvent(PipelineId, DevtoolsInputEvent),
+    /// Request that the window containing the given pipeline be resized to the
+    /// given size in CSS pixels.
+    ResizeWindow(PipelineId, i32, i32),
+    /// Capture the graph of JS objects reachable from the global of the given pipeline.
+    TakeHeapSnapshot(PipelineId, IpcSender<Option<HeapGraph>>),
+    /// Retrieve the linked programs of the WebGL contexts of the document of the given pipeline.
+    GetWebGLPrograms(PipelineId, IpcSender<Vec<WebGLProgramInfo>>),
+    /// Replace the source of the shader of the given type attached to a WebGL program and
+    /// relink the program, replying with the info log if the new source is rejected.
+    ReplaceWebGLShaderSource(PipelineId, WebGLProgramKey, u32, String, IpcSender<Result<(), String>>),
+}
+
+/// Identifies a WebGL program by the GL context it belongs to and its GL name.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct WebGLProgramKey {
+    pub contextId: usize,
+    pub programId: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebGLProgramInfo {
+    pub key: WebGLProgramKey,
+    pub vertexShaderSource: String,
+    pub fragmentShaderSource: String,
+}
+
+/// The JS objects reachable from a global, as captured by `TakeHeapSnapshot`.
+/// The first node is the global itself.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HeapGraph {
+    pub nodes: Vec<HeapGraphNode>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HeapGraphNode {
+    /// The name of the object's constructor, or its class if that is unknown.
+    pub className: String,
+    /// The size of the object itself, in bytes. This is an estimate for plain JS
+    /// objects, and includes the heap allocations of DOM objects.
+    pub size: u64,
+    /// The indices of the nodes this object refers to.
+    pub edges: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DevtoolsMouseEventType {
+    Pressed,
+    Released,
+    Moved,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DevtoolsMouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+/// A synthetic input event requested by a devtools client, in page coordinates.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum DevtoolsInputEvent {
+    Mouse {
+        event_type: DevtoolsMouseEventType,
+        button: Option<DevtoolsMouseButton>,
+        x: f32,
+        y: f32,
+        click_count: u32,
+    },
+    Key {
+        /// The DOM `key` value, e.g. "a" or "Enter".
+        key: String,
+        /// The text generated by the key press, if any.
+        text: Option<char>,
+        state: KeyState,
+        modifiers: KeyModifiers,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -223,22 +346,61 @@ pub struct Modification {
     pub newValue: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum LogLevel {
     Log,
     Debug,
     Info,
     Warn,
     Error,
+    Assert,
+    Trace,
+    Dir,
+    DirXml,
+    Table,
+    Count,
+    CountReset,
+    Group,
+    GroupCollapsed,
+    GroupEnd,
+    Time,
+    TimeEnd,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ConsoleMessage {
-    pub message: String,
+    pub arguments: Vec<EvaluateJSReply>,
     pub logLevel: LogLevel,
     pub filename: String,
     pub lineNumber: usize,
     pub columnNumber: usize,
+    /// The JS stack at the time of the call, innermost frame first.
+    pub stacktrace: Option<Vec<StackFrame>>,
+    /// The counter updated by `console.count` and `console.countReset`.
+    pub counter: Option<ConsoleCounter>,
+    /// The timer started or stopped by `console.time` and `console.timeEnd`.
+    pub timer: Option<ConsoleTimer>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StackFrame {
+    pub filename: String,
+    pub functionName: String,
+    pub lineNumber: u32,
+    pub columnNumber: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConsoleCounter {
+    pub label: String,
+    pub count: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConsoleTimer {
+    pub name: String,
+    /// The elapsed time in milliseconds, for timers that were stopped.
+    pub duration: Option<u64>,
 }
 
 bitflags! {
@@ -286,7 +448,7 @@ pub enum CachedConsoleMessage {
     ConsoleAPI(ConsoleAPI),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct HttpRequest {
     pub url: ServoUrl,
     pub method: Method,
@@ -300,7 +462,7 @@ pub struct HttpRequest {
     pub is_xhr: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct HttpResponse {
     pub headers: Option<Headers>,
     pub status: Option<(u16, Vec<u8>)>,
@@ -308,7 +470,7 @@ pub struct HttpResponse {
     pub pipeline_id: PipelineId,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum NetworkEvent {
     HttpRequest(HttpRequest),
     HttpResponse(HttpResponse),
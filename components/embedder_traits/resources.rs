@@ -49,6 +49,9 @@ pub enum Resource {
     PresentationalHintsCSS,
     QuirksModeCSS,
     RippyPNG,
+    HyphenationPatternsEnUs,
+    HyphenationPatternsEs,
+    HyphenationPatternsFr,
 }
 
 pub trait ResourceReaderMethods {
@@ -80,6 +83,9 @@ fn resources_for_tests() -> Box<ResourceReaderMethods + Sync + Send> {
                 Resource::PresentationalHintsCSS => "presentational-hints.css",
                 Resource::QuirksModeCSS => "quirks-mode.css",
                 Resource::RippyPNG => "rippy.png",
+                Resource::HyphenationPatternsEnUs => "hyphenation/hyph-en-us.pat.txt",
+                Resource::HyphenationPatternsEs => "hyphenation/hyph-es.pat.txt",
+                Resource::HyphenationPatternsFr => "hyphenation/hyph-fr.pat.txt",
             };
             let mut path = env::current_exe().unwrap();
             path = path.canonicalize().unwrap();
@@ -16,14 +16,17 @@ use smallvec::SmallVec;
 use std::borrow::ToOwned;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::iter;
 use std::rc::Rc;
 use std::str;
 use std::sync::Arc;
 use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
-use style::computed_values::{font_stretch, font_style, font_variant_caps, font_weight};
+use style::computed_values::{font_kerning, font_stretch, font_style, font_variant_caps};
+use style::computed_values::{font_variant_position, font_weight};
 use style::properties::style_structs::Font as FontStyleStruct;
 use style::values::computed::font::SingleFontFamily;
+use style::values::specified::font::{VariantEastAsian, VariantLigatures, VariantNumeric};
 use text::Shaper;
 use text::glyph::{ByteIndex, GlyphData, GlyphId, GlyphStore};
 use text::shaping::ShaperMethods;
@@ -40,6 +43,11 @@ macro_rules! ot_tag {
 pub const GPOS: u32 = ot_tag!('G', 'P', 'O', 'S');
 pub const GSUB: u32 = ot_tag!('G', 'S', 'U', 'B');
 pub const KERN: u32 = ot_tag!('k', 'e', 'r', 'n');
+pub const LIGA: u32 = ot_tag!('l', 'i', 'g', 'a');
+const CALT: u32 = ot_tag!('c', 'a', 'l', 't');
+const CLIG: u32 = ot_tag!('c', 'l', 'i', 'g');
+const DLIG: u32 = ot_tag!('d', 'l', 'i', 'g');
+const HLIG: u32 = ot_tag!('h', 'l', 'i', 'g');
 
 static TEXT_SHAPING_PERFORMANCE_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
 
@@ -53,6 +61,7 @@ pub trait FontHandleMethods: Sized {
         fctx: &FontContextHandle,
         template: Arc<FontTemplateData>,
         pt_size: Option<Au>,
+        variations: &[FontVariation],
     ) -> Result<Self, ()>;
 
     fn template(&self) -> Arc<FontTemplateData>;
@@ -115,6 +124,38 @@ pub struct FontMetrics {
     pub line_gap:         Au,
 }
 
+/// An OpenType feature to apply when shaping text. A value of 0 disables the feature, 1 enables
+/// it, and larger values select one of its alternates.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FontFeature {
+    pub tag: u32,
+    pub value: u32,
+}
+
+impl FontFeature {
+    pub fn new(tag: u32, value: u32) -> FontFeature {
+        FontFeature { tag, value }
+    }
+}
+
+/// A position on one of the design axes of a variable font, e.g. `wght` for its weight.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct FontVariation {
+    pub tag: u32,
+    pub value: f32,
+}
+
+/// FontVariation contains a float, which is not Eq because of NaN. However, it comes from a
+/// computed `<number>`, which is never NaN, so we can manually implement Eq.
+impl Eq for FontVariation {}
+
+impl Hash for FontVariation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tag.hash(state);
+        self.value.to_bits().hash(state);
+    }
+}
+
 /// `FontDescriptor` describes the parameters of a `Font`. It represents rendering a given font
 /// template at a particular size, with a particular font-variant-caps applied, etc. This contrasts
 /// with `FontTemplateDescriptor` in that the latter represents only the parameters inherent in the
@@ -124,6 +165,10 @@ pub struct FontDescriptor {
     pub template_descriptor: FontTemplateDescriptor,
     pub variant: font_variant_caps::T,
     pub pt_size: Au,
+    /// The OpenType features to apply when shaping, in order of increasing precedence.
+    pub features: Vec<FontFeature>,
+    /// The instance of a variable font to render, from `font-variation-settings`.
+    pub variations: Vec<FontVariation>,
 }
 
 impl<'a> From<&'a FontStyleStruct> for FontDescriptor {
@@ -132,10 +177,99 @@ impl<'a> From<&'a FontStyleStruct> for FontDescriptor {
             template_descriptor: FontTemplateDescriptor::from(style),
             variant: style.font_variant_caps,
             pt_size: style.font_size.size(),
+            features: font_features(style),
+            variations: style.font_variation_settings.0.iter().map(|variation| {
+                FontVariation {
+                    tag: variation.tag.0,
+                    value: variation.value,
+                }
+            }).collect(),
         }
     }
 }
 
+/// Returns the OpenType features requested by `font-kerning`, the `font-variant-*` longhands and
+/// `font-feature-settings`, in that order, so that later features override earlier ones with the
+/// same tag.
+///
+/// https://drafts.csswg.org/css-fonts-3/#feature-precedence
+fn font_features(style: &FontStyleStruct) -> Vec<FontFeature> {
+    let mut features = vec![];
+
+    match style.font_kerning {
+        font_kerning::T::Auto => {}
+        font_kerning::T::Normal => features.push(FontFeature::new(KERN, 1)),
+        font_kerning::T::None => features.push(FontFeature::new(KERN, 0)),
+    }
+
+    let ligatures = style.font_variant_ligatures;
+    if ligatures.contains(VariantLigatures::NONE) {
+        for &tag in &[LIGA, CLIG, DLIG, HLIG, CALT] {
+            features.push(FontFeature::new(tag, 0));
+        }
+    }
+    for &(flag, tag, value) in &[
+        (VariantLigatures::COMMON_LIGATURES, LIGA, 1),
+        (VariantLigatures::COMMON_LIGATURES, CLIG, 1),
+        (VariantLigatures::NO_COMMON_LIGATURES, LIGA, 0),
+        (VariantLigatures::NO_COMMON_LIGATURES, CLIG, 0),
+        (VariantLigatures::DISCRETIONARY_LIGATURES, DLIG, 1),
+        (VariantLigatures::NO_DISCRETIONARY_LIGATURES, DLIG, 0),
+        (VariantLigatures::HISTORICAL_LIGATURES, HLIG, 1),
+        (VariantLigatures::NO_HISTORICAL_LIGATURES, HLIG, 0),
+        (VariantLigatures::CONTEXTUAL, CALT, 1),
+        (VariantLigatures::NO_CONTEXTUAL, CALT, 0),
+    ] {
+        if ligatures.contains(flag) {
+            features.push(FontFeature::new(tag, value));
+        }
+    }
+
+    let numeric = style.font_variant_numeric;
+    for &(flag, tag) in &[
+        (VariantNumeric::LINING_NUMS, ot_tag!('l', 'n', 'u', 'm')),
+        (VariantNumeric::OLDSTYLE_NUMS, ot_tag!('o', 'n', 'u', 'm')),
+        (VariantNumeric::PROPORTIONAL_NUMS, ot_tag!('p', 'n', 'u', 'm')),
+        (VariantNumeric::TABULAR_NUMS, ot_tag!('t', 'n', 'u', 'm')),
+        (VariantNumeric::DIAGONAL_FRACTIONS, ot_tag!('f', 'r', 'a', 'c')),
+        (VariantNumeric::STACKED_FRACTIONS, ot_tag!('a', 'f', 'r', 'c')),
+        (VariantNumeric::ORDINAL, ot_tag!('o', 'r', 'd', 'n')),
+        (VariantNumeric::SLASHED_ZERO, ot_tag!('z', 'e', 'r', 'o')),
+    ] {
+        if numeric.contains(flag) {
+            features.push(FontFeature::new(tag, 1));
+        }
+    }
+
+    let east_asian = style.font_variant_east_asian;
+    for &(flag, tag) in &[
+        (VariantEastAsian::JIS78, ot_tag!('j', 'p', '7', '8')),
+        (VariantEastAsian::JIS83, ot_tag!('j', 'p', '8', '3')),
+        (VariantEastAsian::JIS90, ot_tag!('j', 'p', '9', '0')),
+        (VariantEastAsian::JIS04, ot_tag!('j', 'p', '0', '4')),
+        (VariantEastAsian::SIMPLIFIED, ot_tag!('s', 'm', 'p', 'l')),
+        (VariantEastAsian::TRADITIONAL, ot_tag!('t', 'r', 'a', 'd')),
+        (VariantEastAsian::FULL_WIDTH, ot_tag!('f', 'w', 'i', 'd')),
+        (VariantEastAsian::PROPORTIONAL_WIDTH, ot_tag!('p', 'w', 'i', 'd')),
+        (VariantEastAsian::RUBY, ot_tag!('r', 'u', 'b', 'y')),
+    ] {
+        if east_asian.contains(flag) {
+            features.push(FontFeature::new(tag, 1));
+        }
+    }
+
+    match style.font_variant_position {
+        font_variant_position::T::Normal => {}
+        font_variant_position::T::Sub => features.push(FontFeature::new(ot_tag!('s', 'u', 'b', 's'), 1)),
+        font_variant_position::T::Super => features.push(FontFeature::new(ot_tag!('s', 'u', 'p', 's'), 1)),
+    }
+
+    features.extend(style.font_feature_settings.0.iter().map(|feature| {
+        FontFeature::new(feature.tag.0, feature.value as u32)
+    }));
+    features
+}
+
 #[derive(Debug)]
 pub struct Font {
     pub handle: FontHandle,
@@ -146,13 +280,17 @@ pub struct Font {
     shape_cache: RefCell<HashMap<ShapeCacheEntry, Arc<GlyphStore>>>,
     glyph_advance_cache: RefCell<HashMap<u32, FractionalPixel>>,
     pub font_key: webrender_api::FontInstanceKey,
+    /// The font instance that draws glyphs turned a quarter turn clockwise, for sideways text in
+    /// vertical writing modes.
+    pub sideways_font_key: webrender_api::FontInstanceKey,
 }
 
 impl Font {
     pub fn new(handle: FontHandle,
                descriptor: FontDescriptor,
                actual_pt_size: Au,
-               font_key: webrender_api::FontInstanceKey) -> Font {
+               font_key: webrender_api::FontInstanceKey,
+               sideways_font_key: webrender_api::FontInstanceKey) -> Font {
         let metrics = handle.metrics();
 
         Font {
@@ -164,6 +302,7 @@ impl Font {
             shape_cache: RefCell::new(HashMap::new()),
             glyph_advance_cache: RefCell::new(HashMap::new()),
             font_key,
+            sideways_font_key,
         }
     }
 
@@ -185,6 +324,8 @@ bitflags! {
         const RTL_FLAG = 0x08;
         #[doc = "Set if word-break is set to keep-all."]
         const KEEP_ALL_FLAG = 0x10;
+        #[doc = "Set if the glyphs are set upright in a vertical line."]
+        const VERTICAL_UPRIGHT_FLAG = 0x20;
     }
 }
 
@@ -246,7 +387,8 @@ impl Font {
 
     fn can_do_fast_shaping(&self, text: &str, options: &ShapingOptions) -> bool {
         options.script == Script::Latin &&
-            !options.flags.contains(ShapingFlags::RTL_FLAG) &&
+            !options.flags.intersects(ShapingFlags::RTL_FLAG | ShapingFlags::VERTICAL_UPRIGHT_FLAG) &&
+            self.descriptor.features.is_empty() &&
             self.handle.can_do_fast_shaping() &&
             text.is_ascii()
     }
@@ -320,6 +462,14 @@ impl Font {
             }
         })
     }
+
+    /// Returns the advance of the given glyph when it is set upright in a vertical line.
+    ///
+    /// TODO: Use the `vmtx` table of fonts that have one. Until then every glyph advances by the
+    /// height of the font, which suits the full-width glyphs of CJK fonts.
+    pub fn glyph_v_advance(&self, _glyph: GlyphId) -> FractionalPixel {
+        (self.metrics.ascent + self.metrics.descent).to_f64_px()
+    }
 }
 
 pub type FontRef = Rc<RefCell<Font>>;
@@ -390,6 +540,21 @@ impl FontGroup {
             .or_else(|| self.find_fallback(&mut font_context, None, |_| true))
     }
 
+    /// Returns whether text in this group is waiting for a web font in the block period of its
+    /// `font-display`, because no family before the one being loaded has a font. Such text is laid
+    /// out with a fallback font, but not painted.
+    pub fn is_blocked_by_web_font<S: FontSource>(&mut self, font_context: &mut FontContext<S>) -> bool {
+        for family in &mut self.families {
+            if family.font(font_context).is_some() {
+                return false;
+            }
+            if font_context.is_web_font_blocked(&family.family_descriptor) {
+                return true;
+            }
+        }
+        false
+    }
+
     /// Find a font which returns true for `predicate`. This method mutates because we may need to
     /// load new font data in the process of finding a suitable font.
     fn find<S, P>(
@@ -3,7 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use app_units::Au;
-use font::{FontFamilyDescriptor, FontFamilyName, FontSearchScope};
+use font::{FontFamilyDescriptor, FontFamilyName, FontSearchScope, FontVariation};
 use font_context::FontSource;
 use font_template::{FontTemplate, FontTemplateDescriptor};
 use fontsan;
@@ -23,7 +23,9 @@ use std::borrow::ToOwned;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
-use style::font_face::{EffectiveSources, Source};
+use std::sync::mpsc::{RecvTimeoutError, Sender, channel};
+use std::time::{Duration, Instant};
+use style::font_face::{EffectiveSources, FontDisplay, Source};
 use style::values::computed::font::FamilyName;
 use webrender_api;
 
@@ -87,30 +89,139 @@ impl FontTemplates {
         None
     }
 
-    pub fn add_template(&mut self, identifier: Atom, maybe_data: Option<Vec<u8>>) {
+    /// Adds a template to this family, returning whether it wasn't already in it.
+    pub fn add_template(&mut self, identifier: Atom, maybe_data: Option<Vec<u8>>) -> bool {
         for template in &self.templates {
             if *template.identifier() == identifier {
-                return;
+                return false;
             }
         }
 
-        if let Ok(template) = FontTemplate::new(identifier, maybe_data) {
-            self.templates.push(template);
+        match FontTemplate::new(identifier, maybe_data) {
+            Ok(template) => {
+                self.templates.push(template);
+                true
+            },
+            Err(_) => false,
         }
     }
+
+    pub fn remove_template(&mut self, identifier: &Atom) {
+        self.templates.retain(|template| template.identifier() != identifier);
+    }
 }
 
 /// Commands that the FontContext sends to the font cache thread.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Command {
     GetFontTemplate(FontTemplateDescriptor, FontFamilyDescriptor, IpcSender<Reply>),
-    GetFontInstance(webrender_api::FontKey, Au, IpcSender<webrender_api::FontInstanceKey>),
-    AddWebFont(LowercaseString, EffectiveSources, IpcSender<()>),
-    AddDownloadedWebFont(LowercaseString, ServoUrl, Vec<u8>, IpcSender<()>),
+    GetFontInstance(
+        webrender_api::FontKey,
+        Au,
+        webrender_api::FontInstanceFlags,
+        Vec<FontVariation>,
+        IpcSender<webrender_api::FontInstanceKey>,
+    ),
+    AddWebFont(LowercaseString, EffectiveSources, FontDisplay, Option<Atom>, IpcSender<WebFontLoadUpdate>),
+    AddWebFontData(LowercaseString, Vec<u8>, Option<Atom>, IpcSender<WebFontLoadUpdate>),
+    RemoveWebFont(Atom),
+    LoadWebFontSource(WebFontLoadId, EffectiveSources),
+    AddDownloadedWebFont(WebFontLoadId, ServoUrl, Vec<u8>),
+    WebFontBlockPeriodEnded(WebFontLoadId),
+    IsWebFontBlocked(FontFamilyName, IpcSender<bool>),
     Exit(IpcSender<()>),
     Ping,
 }
 
+/// Identifies a web font load started by `FontCacheThread::add_web_font`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct WebFontLoadId(usize);
+
+/// What the font cache thread reports about a web font that is added through
+/// `FontCacheThread::add_web_font` or `FontCacheThread::add_web_font_data`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum WebFontLoadUpdate {
+    /// The block period of the `font-display` of the font ended before the font loaded, so
+    /// text waiting for it is now rendered with a visible fallback font.
+    BlockPeriodEnded,
+    /// The load finished, and the font is used if the value is true.
+    Finished(bool),
+}
+
+/// A web font load that is in progress.
+struct WebFontLoad {
+    family_name: LowercaseString,
+    /// The key to remove the font with, if it was added with one.
+    key: Option<Atom>,
+    display: FontDisplay,
+    start: Instant,
+    sender: IpcSender<WebFontLoadUpdate>,
+}
+
+impl WebFontLoad {
+    /// Whether text waiting for this font is rendered invisibly.
+    fn is_in_block_period(&self) -> bool {
+        self.start.elapsed() < font_display_periods(self.display).0
+    }
+
+    /// Whether the font won't be used anymore if it loads.
+    fn has_failed(&self) -> bool {
+        let (block_period, swap_period) = font_display_periods(self.display);
+        swap_period.map_or(false, |swap_period| self.start.elapsed() >= block_period + swap_period)
+    }
+}
+
+/// Returns the block period and the swap period of a `font-display` value, where a swap period
+/// of `None` is infinite.
+///
+/// <https://drafts.csswg.org/css-fonts-4/#font-display-timeline>
+fn font_display_periods(display: FontDisplay) -> (Duration, Option<Duration>) {
+    match display {
+        FontDisplay::Auto | FontDisplay::Block => (Duration::from_secs(3), None),
+        FontDisplay::Swap => (Duration::from_millis(0), None),
+        FontDisplay::Fallback => (Duration::from_millis(100), Some(Duration::from_secs(3))),
+        FontDisplay::Optional => (Duration::from_millis(100), Some(Duration::from_millis(0))),
+    }
+}
+
+/// Spawns a thread that tells the font cache thread when each web font load it is sent reaches
+/// the end of its block period.
+fn spawn_block_period_timer(channel_to_font_cache: IpcSender<Command>) -> Sender<(Instant, WebFontLoadId)> {
+    let (sender, receiver) = channel();
+    thread::Builder::new().name("FontBlockPeriodTimer".to_owned()).spawn(move || {
+        let mut deadlines: Vec<(Instant, WebFontLoadId)> = vec![];
+        loop {
+            let next_deadline = deadlines.iter().map(|&(deadline, _)| deadline).min();
+            let received = match next_deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline > now {
+                        receiver.recv_timeout(deadline - now)
+                    } else {
+                        Err(RecvTimeoutError::Timeout)
+                    }
+                },
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(deadline) => deadlines.push(deadline),
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    deadlines.retain(|&(deadline, load)| {
+                        if deadline > now {
+                            return true;
+                        }
+                        let _ = channel_to_font_cache.send(Command::WebFontBlockPeriodEnded(load));
+                        false
+                    });
+                },
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }).expect("Thread spawning failed");
+    sender
+}
+
 /// Reply messages sent from the font cache thread to the FontContext caller.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Reply {
@@ -129,7 +240,20 @@ struct FontCache {
     core_resource_thread: CoreResourceThread,
     webrender_api: webrender_api::RenderApi,
     webrender_fonts: HashMap<Atom, webrender_api::FontKey>,
-    font_instances: HashMap<(webrender_api::FontKey, Au), webrender_api::FontInstanceKey>,
+    /// The web font loads that are in progress.
+    web_font_loads: HashMap<WebFontLoadId, WebFontLoad>,
+    next_web_font_load_id: usize,
+    /// The channel on which to schedule the end of the block period of a web font load.
+    block_period_timer: Sender<(Instant, WebFontLoadId)>,
+    /// The number of web fonts that have been added from data rather than from a `src` list,
+    /// used to give each of them a unique identifier.
+    web_font_data_count: usize,
+    /// The family and the templates of the web fonts that were added with a key, by key.
+    removable_web_fonts: HashMap<Atom, (LowercaseString, Vec<Atom>)>,
+    font_instances: HashMap<
+        (webrender_api::FontKey, Au, webrender_api::FontInstanceFlags, Vec<FontVariation>),
+        webrender_api::FontInstanceKey,
+    >,
 }
 
 fn populate_generic_fonts() -> HashMap<FontFamilyName, LowercaseString> {
@@ -170,33 +294,62 @@ impl FontCache {
                     let maybe_font_template = self.find_font_template(&template_descriptor, &family_descriptor);
                     let _ = result.send(Reply::GetFontTemplateReply(maybe_font_template));
                 }
-                Command::GetFontInstance(font_key, size, result) => {
+                Command::GetFontInstance(font_key, size, flags, variations, result) => {
                     let webrender_api = &self.webrender_api;
 
                     let instance_key = *self.font_instances
-                                            .entry((font_key, size))
+                                            .entry((font_key, size, flags, variations.clone()))
                                             .or_insert_with(|| {
                                                 let key = webrender_api.generate_font_instance_key();
+                                                let options = if flags.is_empty() {
+                                                    None
+                                                } else {
+                                                    let mut options =
+                                                        webrender_api::FontInstanceOptions::default();
+                                                    options.flags |= flags;
+                                                    Some(options)
+                                                };
                                                 let mut txn = webrender_api::Transaction::new();
+                                                let variations = variations.iter().map(|variation| {
+                                                    webrender_api::FontVariation {
+                                                        tag: variation.tag,
+                                                        value: variation.value,
+                                                    }
+                                                }).collect();
                                                 txn.add_font_instance(key,
                                                                           font_key,
                                                                           size,
+                                                                          options,
                                                                           None,
-                                                                          None,
-                                                                          Vec::new());
+                                                                          variations);
                                                 webrender_api.update_resources(txn.resource_updates);
                                                 key
                                             });
 
                     let _ = result.send(instance_key);
                 }
-                Command::AddWebFont(family_name, sources, result) => {
-                    self.handle_add_web_font(family_name, sources, result);
+                Command::AddWebFont(family_name, sources, display, key, result) => {
+                    self.handle_add_web_font(family_name, sources, display, key, result);
+                }
+                Command::LoadWebFontSource(load, sources) => {
+                    self.load_web_font_source(load, sources);
+                }
+                Command::AddDownloadedWebFont(load, url, bytes) => {
+                    self.finish_web_font_load(load, vec![(Atom::from(url.to_string()), Some(bytes))]);
+                }
+                Command::AddWebFontData(family_name, bytes, key, result) => {
+                    self.handle_add_web_font_data(family_name, bytes, key, result);
                 }
-                Command::AddDownloadedWebFont(family_name, url, bytes, result) => {
-                    let templates = &mut self.web_families.get_mut(&family_name).unwrap();
-                    templates.add_template(Atom::from(url.to_string()), Some(bytes));
-                    drop(result.send(()));
+                Command::RemoveWebFont(key) => {
+                    self.handle_remove_web_font(key);
+                }
+                Command::WebFontBlockPeriodEnded(load) => {
+                    if let Some(load) = self.web_font_loads.get(&load) {
+                        let _ = load.sender.send(WebFontLoadUpdate::BlockPeriodEnded);
+                    }
+                }
+                Command::IsWebFontBlocked(family_name, result) => {
+                    let _ = result.send(self.is_web_font_blocked(&family_name));
                 }
                 Command::Ping => (),
                 Command::Exit(result) => {
@@ -209,26 +362,53 @@ impl FontCache {
 
     fn handle_add_web_font(&mut self,
                            family_name: LowercaseString,
-                           mut sources: EffectiveSources,
-                           sender: IpcSender<()>) {
+                           sources: EffectiveSources,
+                           display: FontDisplay,
+                           key: Option<Atom>,
+                           sender: IpcSender<WebFontLoadUpdate>) {
+        self.web_families.entry(family_name.clone()).or_insert_with(FontTemplates::new);
+
+        self.next_web_font_load_id += 1;
+        let load = WebFontLoadId(self.next_web_font_load_id);
+        let start = Instant::now();
+        let (block_period, _) = font_display_periods(display);
+        if block_period > Duration::from_millis(0) {
+            let _ = self.block_period_timer.send((start + block_period, load));
+        }
+        self.web_font_loads.insert(load, WebFontLoad {
+            family_name,
+            key,
+            display,
+            start,
+            sender,
+        });
+        self.load_web_font_source(load, sources);
+    }
+
+    /// Tries to load the next of the remaining sources of a web font.
+    fn load_web_font_source(&mut self, load: WebFontLoadId, mut sources: EffectiveSources) {
+        let family_name = match self.web_font_loads.get(&load) {
+            Some(web_font_load) => web_font_load.family_name.clone(),
+            None => return,
+        };
+
         let src = if let Some(src) = sources.next() {
             src
         } else {
-            sender.send(()).unwrap();
+            self.finish_web_font_load(load, vec![]);
             return;
         };
 
-        if !self.web_families.contains_key(&family_name) {
-            let templates = FontTemplates::new();
-            self.web_families.insert(family_name.clone(), templates);
-        }
-
         match src {
             Source::Url(url_source) => {
                 // https://drafts.csswg.org/css-fonts/#font-fetching-requirements
                 let url = match url_source.url.url() {
                     Some(url) => url.clone(),
-                    None => return,
+                    None => {
+                        let msg = Command::LoadWebFontSource(load, sources);
+                        self.channel_to_self.send(msg).unwrap();
+                        return;
+                    },
                 };
 
                 let request = RequestInit {
@@ -260,7 +440,7 @@ impl FontCache {
                         FetchResponseMsg::ProcessResponseEOF(response) => {
                             trace!("@font-face {} EOF={:?}", family_name, response);
                             if response.is_err() || !*response_valid.lock().unwrap() {
-                                let msg = Command::AddWebFont(family_name.clone(), sources.clone(), sender.clone());
+                                let msg = Command::LoadWebFontSource(load, sources.clone());
                                 channel_to_self.send(msg).unwrap();
                                 return;
                             }
@@ -272,16 +452,12 @@ impl FontCache {
                                     // FIXME(servo/fontsan#1): get an error message
                                     debug!("Sanitiser rejected web font: \
                                             family={} url={:?}", family_name, url);
-                                    let msg = Command::AddWebFont(family_name.clone(), sources.clone(), sender.clone());
+                                    let msg = Command::LoadWebFontSource(load, sources.clone());
                                     channel_to_self.send(msg).unwrap();
                                     return;
                                 },
                             };
-                            let command =
-                                Command::AddDownloadedWebFont(family_name.clone(),
-                                                              url.clone(),
-                                                              bytes,
-                                                              sender.clone());
+                            let command = Command::AddDownloadedWebFont(load, url.clone(), bytes);
                             channel_to_self.send(command).unwrap();
                         }
                     }
@@ -289,22 +465,97 @@ impl FontCache {
             }
             Source::Local(ref font) => {
                 let font_face_name = LowercaseString::new(&font.name);
-                let templates = &mut self.web_families.get_mut(&family_name).unwrap();
-                let mut found = false;
+                let mut templates = vec![];
                 for_each_variation(&font_face_name, |path| {
-                    found = true;
-                    templates.add_template(Atom::from(&*path), None);
+                    templates.push((Atom::from(&*path), None));
                 });
-                if found {
-                    sender.send(()).unwrap();
-                } else {
-                    let msg = Command::AddWebFont(family_name, sources, sender);
+                if templates.is_empty() {
+                    let msg = Command::LoadWebFontSource(load, sources);
                     self.channel_to_self.send(msg).unwrap();
+                } else {
+                    self.finish_web_font_load(load, templates);
+                }
+            }
+        }
+    }
+
+    /// Finishes a web font load with the templates of the font that was loaded, if any. The
+    /// templates aren't used if the font arrived after the swap period of its `font-display`.
+    fn finish_web_font_load(&mut self, load: WebFontLoadId, templates: Vec<(Atom, Option<Vec<u8>>)>) {
+        let load = match self.web_font_loads.remove(&load) {
+            Some(load) => load,
+            None => return,
+        };
+
+        let loaded = !templates.is_empty() && !load.has_failed();
+        if loaded {
+            let family = self.web_families.get_mut(&load.family_name).unwrap();
+            let mut added = vec![];
+            for (identifier, bytes) in templates {
+                // A template shared with another web font belongs to the font that added it.
+                if family.add_template(identifier.clone(), bytes) {
+                    added.push(identifier);
                 }
             }
+            if let Some(key) = load.key {
+                self.removable_web_fonts.insert(key, (load.family_name.clone(), added));
+            }
+        } else if !templates.is_empty() {
+            debug!("Web font {} arrived after its swap period", load.family_name);
+        }
+        let _ = load.sender.send(WebFontLoadUpdate::Finished(loaded));
+    }
+
+    fn handle_add_web_font_data(&mut self,
+                                family_name: LowercaseString,
+                                bytes: Vec<u8>,
+                                key: Option<Atom>,
+                                sender: IpcSender<WebFontLoadUpdate>) {
+        let bytes = match fontsan::process(&bytes) {
+            Ok(san) => san,
+            Err(_) => {
+                debug!("Sanitiser rejected web font data: family={}", family_name);
+                let _ = sender.send(WebFontLoadUpdate::Finished(false));
+                return;
+            },
+        };
+        self.web_font_data_count += 1;
+        let identifier = Atom::from(format!("font-face-data:{}", self.web_font_data_count));
+        self.web_families.entry(family_name.clone())
+                         .or_insert_with(FontTemplates::new)
+                         .add_template(identifier.clone(), Some(bytes));
+        if let Some(key) = key {
+            self.removable_web_fonts.insert(key, (family_name, vec![identifier]));
+        }
+        let _ = sender.send(WebFontLoadUpdate::Finished(true));
+    }
+
+    /// Removes the templates of the web font that was added with the given key. Nothing is
+    /// removed if the font is still loading or failed to load.
+    fn handle_remove_web_font(&mut self, key: Atom) {
+        let (family_name, identifiers) = match self.removable_web_fonts.remove(&key) {
+            Some(font) => font,
+            None => return,
+        };
+        if let Some(family) = self.web_families.get_mut(&family_name) {
+            for identifier in &identifiers {
+                family.remove_template(identifier);
+            }
         }
     }
 
+    /// Returns whether text in the given family is rendered invisibly, because the family has no
+    /// font yet and one of its web fonts is in the block period of its `font-display`.
+    fn is_web_font_blocked(&self, family_name: &FontFamilyName) -> bool {
+        let family_name = LowercaseString::from(family_name);
+        let has_font = self.web_families.get(&family_name).map_or(false, |family| {
+            !family.templates.is_empty()
+        });
+        !has_font && self.web_font_loads.values().any(|load| {
+            load.family_name == family_name && load.is_in_block_period()
+        })
+    }
+
     fn refresh_local_families(&mut self) {
         self.local_families.clear();
         for_each_available_family(|family_name| {
@@ -434,6 +685,11 @@ impl FontCacheThread {
                 core_resource_thread,
                 webrender_api,
                 webrender_fonts: HashMap::new(),
+                web_font_loads: HashMap::new(),
+                next_web_font_load_id: 0,
+                block_period_timer: spawn_block_period_timer(channel_to_self.clone()),
+                web_font_data_count: 0,
+                removable_web_fonts: HashMap::new(),
                 font_instances: HashMap::new(),
             };
 
@@ -446,8 +702,34 @@ impl FontCacheThread {
         }
     }
 
-    pub fn add_web_font(&self, family: FamilyName, sources: EffectiveSources, sender: IpcSender<()>) {
-        self.chan.send(Command::AddWebFont(LowercaseString::new(&family.name), sources, sender)).unwrap();
+    /// Adds a web font that is loaded from the first of `sources` that can be loaded, and is
+    /// displayed while it loads as `display` specifies. A font added with a key can be
+    /// removed with `remove_web_font` once it has loaded.
+    pub fn add_web_font(&self,
+                        family: FamilyName,
+                        sources: EffectiveSources,
+                        display: FontDisplay,
+                        key: Option<Atom>,
+                        sender: IpcSender<WebFontLoadUpdate>) {
+        let family_name = LowercaseString::new(&family.name);
+        self.chan.send(Command::AddWebFont(family_name, sources, display, key, sender)).unwrap();
+    }
+
+    /// Adds a web font whose data is already available, such as one created from an
+    /// `ArrayBuffer` by script. The sender is told whether the data was a usable font.
+    pub fn add_web_font_data(&self,
+                             family: FamilyName,
+                             bytes: Vec<u8>,
+                             key: Option<Atom>,
+                             sender: IpcSender<WebFontLoadUpdate>) {
+        let family_name = LowercaseString::new(&family.name);
+        self.chan.send(Command::AddWebFontData(family_name, bytes, key, sender)).unwrap();
+    }
+
+    /// Removes the web font that was added with the given key, such as a `FontFace` that
+    /// script removed from its document's `FontFaceSet`.
+    pub fn remove_web_font(&self, key: Atom) {
+        self.chan.send(Command::RemoveWebFont(key)).unwrap();
     }
 
     pub fn exit(&self) {
@@ -458,10 +740,16 @@ impl FontCacheThread {
 }
 
 impl FontSource for FontCacheThread {
-    fn get_font_instance(&mut self, key: webrender_api::FontKey, size: Au) -> webrender_api::FontInstanceKey {
+    fn get_font_instance(
+        &mut self,
+        key: webrender_api::FontKey,
+        size: Au,
+        flags: webrender_api::FontInstanceFlags,
+        variations: &[FontVariation],
+    ) -> webrender_api::FontInstanceKey {
         let (response_chan, response_port) =
             ipc::channel().expect("failed to create IPC channel");
-        self.chan.send(Command::GetFontInstance(key, size, response_chan))
+        self.chan.send(Command::GetFontInstance(key, size, flags, variations.to_vec(), response_chan))
             .expect("failed to send message to font cache thread");
 
         let instance_key = response_port.recv();
@@ -497,6 +785,14 @@ impl FontSource for FontCacheThread {
             }
         }
     }
+
+    fn is_web_font_blocked(&mut self, family_descriptor: FontFamilyDescriptor) -> bool {
+        let (response_chan, response_port) =
+            ipc::channel().expect("failed to create IPC channel");
+        self.chan.send(Command::IsWebFontBlocked(family_descriptor.name, response_chan))
+            .expect("failed to send message to font cache thread");
+        response_port.recv().unwrap_or(false)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
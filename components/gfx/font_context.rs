@@ -5,6 +5,7 @@
 use app_units::Au;
 use fnv::FnvHasher;
 use font::{Font, FontDescriptor, FontFamilyDescriptor, FontGroup, FontHandleMethods, FontRef};
+use font::{FontSearchScope, FontVariation};
 use font_cache_thread::FontTemplateInfo;
 use font_template::FontTemplateDescriptor;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
@@ -28,13 +29,23 @@ static SMALL_CAPS_SCALE_FACTOR: f32 = 0.8;      // Matches FireFox (see gfxFont.
 static FONT_CACHE_EPOCH: AtomicUsize = ATOMIC_USIZE_INIT;
 
 pub trait FontSource {
-    fn get_font_instance(&mut self, key: webrender_api::FontKey, size: Au) -> webrender_api::FontInstanceKey;
+    fn get_font_instance(
+        &mut self,
+        key: webrender_api::FontKey,
+        size: Au,
+        flags: webrender_api::FontInstanceFlags,
+        variations: &[FontVariation],
+    ) -> webrender_api::FontInstanceKey;
 
     fn font_template(
         &mut self,
         template_descriptor: FontTemplateDescriptor,
         family_descriptor: FontFamilyDescriptor,
     ) -> Option<FontTemplateInfo>;
+
+    /// Returns whether text in the given family is rendered invisibly while a web font for it
+    /// is in the block period of its `font-display`.
+    fn is_web_font_blocked(&mut self, family_descriptor: FontFamilyDescriptor) -> bool;
 }
 
 /// The FontContext represents the per-thread/thread state necessary for
@@ -55,6 +66,8 @@ pub struct FontContext<S: FontSource> {
     font_group_cache:
         HashMap<FontGroupCacheKey, Rc<RefCell<FontGroup>>, BuildHasherDefault<FnvHasher>>,
 
+    web_font_blocked_cache: HashMap<FontFamilyDescriptor, bool>,
+
     epoch: usize,
 }
 
@@ -67,6 +80,7 @@ impl<S: FontSource> FontContext<S> {
             font_cache: HashMap::new(),
             font_template_cache: HashMap::new(),
             font_group_cache: HashMap::with_hasher(Default::default()),
+            web_font_blocked_cache: HashMap::new(),
             epoch: 0,
         }
     }
@@ -80,6 +94,7 @@ impl<S: FontSource> FontContext<S> {
         self.font_cache.clear();
         self.font_template_cache.clear();
         self.font_group_cache.clear();
+        self.web_font_blocked_cache.clear();
         self.epoch = current_epoch
     }
 
@@ -159,6 +174,23 @@ impl<S: FontSource> FontContext<S> {
         })
     }
 
+    /// Returns whether text in the given family is rendered invisibly because it is waiting for
+    /// a web font. Since the font cache thread invalidates font caches when a block period ends,
+    /// the answer is cached until then.
+    pub fn is_web_font_blocked(&mut self, family_descriptor: &FontFamilyDescriptor) -> bool {
+        if family_descriptor.scope == FontSearchScope::Local {
+            return false;
+        }
+
+        if let Some(&blocked) = self.web_font_blocked_cache.get(family_descriptor) {
+            return blocked;
+        }
+
+        let blocked = self.font_source.is_web_font_blocked(family_descriptor.clone());
+        self.web_font_blocked_cache.insert(family_descriptor.clone(), blocked);
+        blocked
+    }
+
     /// Create a `Font` for use in layout calculations, from a `FontTemplateData` returned by the
     /// cache thread and a `FontDescriptor` which contains the styling parameters.
     fn create_font(
@@ -177,11 +209,31 @@ impl<S: FontSource> FontContext<S> {
         let handle = FontHandle::new_from_template(
             &self.platform_handle,
             info.font_template,
-            Some(actual_pt_size)
+            Some(actual_pt_size),
+            &descriptor.variations,
         )?;
 
-        let font_instance_key = self.font_source.get_font_instance(info.font_key, actual_pt_size);
-        Ok(Font::new(handle, descriptor.to_owned(), actual_pt_size, font_instance_key))
+        let font_instance_key = self.font_source.get_font_instance(
+            info.font_key,
+            actual_pt_size,
+            webrender_api::FontInstanceFlags::empty(),
+            &descriptor.variations,
+        );
+        // Sideways text in vertical writing modes is drawn with its glyphs turned a quarter turn
+        // clockwise.
+        let sideways_font_instance_key = self.font_source.get_font_instance(
+            info.font_key,
+            actual_pt_size,
+            webrender_api::FontInstanceFlags::TRANSPOSE | webrender_api::FontInstanceFlags::FLIP_X,
+            &descriptor.variations,
+        );
+        Ok(Font::new(
+            handle,
+            descriptor.to_owned(),
+            actual_pt_size,
+            font_instance_key,
+            sideways_font_instance_key,
+        ))
     }
 }
 
@@ -5,7 +5,7 @@
 use font::FontHandleMethods;
 use platform::font::FontHandle;
 use platform::font_context::FontContextHandle;
-use platform::font_template::FontTemplateData;
+pub use platform::font_template::FontTemplateData;
 use servo_atoms::Atom;
 use std::fmt::{Debug, Error, Formatter};
 use std::io::Error as IoError;
@@ -190,7 +190,8 @@ impl FontTemplate {
         let data = self.data().map_err(|_| ())?;
         let handle: Result<FontHandle, ()> = FontHandleMethods::new_from_template(font_context,
                                                                                   data,
-                                                                                  None);
+                                                                                  None,
+                                                                                  &[]);
         self.is_valid = handle.is_ok();
         let handle = handle?;
         self.descriptor = Some(FontTemplateDescriptor::new(
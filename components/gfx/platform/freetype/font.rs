@@ -3,10 +3,10 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use app_units::Au;
-use font::{FontHandleMethods, FontMetrics, FontTableMethods};
+use font::{FontHandleMethods, FontMetrics, FontTableMethods, FontVariation};
 use font::{FontTableTag, FractionalPixel, GPOS, GSUB, KERN};
 use freetype::freetype::{FT_Done_Face, FT_New_Face, FT_New_Memory_Face};
-use freetype::freetype::{FT_F26Dot6, FT_Face, FT_FaceRec};
+use freetype::freetype::{FT_Error, FT_F26Dot6, FT_Face, FT_FaceRec, FT_Fixed};
 use freetype::freetype::{FT_Get_Char_Index, FT_Get_Postscript_Name};
 use freetype::freetype::{FT_Get_Kerning, FT_Get_Sfnt_Table, FT_Load_Sfnt_Table};
 use freetype::freetype::{FT_GlyphSlot, FT_Library, FT_Long, FT_ULong};
@@ -19,9 +19,9 @@ use freetype::tt_os2::TT_OS2;
 use platform::font_context::FontContextHandle;
 use platform::font_template::FontTemplateData;
 use servo_atoms::Atom;
-use std::{mem, ptr};
+use std::{mem, ptr, slice};
 use std::ffi::CString;
-use std::os::raw::{c_char, c_long};
+use std::os::raw::{c_char, c_long, c_void};
 use std::sync::Arc;
 use style::computed_values::font_stretch::T as FontStretch;
 use style::computed_values::font_weight::T as FontWeight;
@@ -45,6 +45,35 @@ fn fixed_to_float_ft(f: i32) -> f64 {
     fixed_to_float(6, f)
 }
 
+// The multiple masters API, which variable fonts are accessed through, is declared in `ftmm.h`,
+// which the freetype bindings do not cover.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct FT_Var_Axis {
+    name: *mut c_char,
+    minimum: FT_Fixed,
+    def: FT_Fixed,
+    maximum: FT_Fixed,
+    tag: FT_ULong,
+    strid: FT_UInt,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct FT_MM_Var {
+    num_axis: FT_UInt,
+    num_designs: FT_UInt,
+    num_namedstyles: FT_UInt,
+    axis: *mut FT_Var_Axis,
+    namedstyle: *mut c_void,
+}
+
+extern "C" {
+    fn FT_Get_MM_Var(face: FT_Face, amaster: *mut *mut FT_MM_Var) -> FT_Error;
+    fn FT_Set_Var_Design_Coordinates(face: FT_Face, num_coords: FT_UInt, coords: *mut FT_Fixed)
+                                     -> FT_Error;
+}
+
 #[derive(Debug)]
 pub struct FontTable {
     buffer: Vec<u8>,
@@ -92,6 +121,7 @@ fn create_face(
     lib: FT_Library,
     template: &FontTemplateData,
     pt_size: Option<Au>,
+    variations: &[FontVariation],
 ) -> Result<FT_Face, ()> {
     unsafe {
         let mut face: FT_Face = ptr::null_mut();
@@ -117,6 +147,10 @@ fn create_face(
             FontHandle::set_char_size(face, s).or(Err(()))?
         }
 
+        if !variations.is_empty() {
+            FontHandle::set_variations(face, variations);
+        }
+
         Ok(face)
     }
 }
@@ -124,12 +158,13 @@ fn create_face(
 impl FontHandleMethods for FontHandle {
     fn new_from_template(fctx: &FontContextHandle,
                        template: Arc<FontTemplateData>,
-                       pt_size: Option<Au>)
+                       pt_size: Option<Au>,
+                       variations: &[FontVariation])
                         -> Result<FontHandle, ()> {
         let ft_ctx: FT_Library = fctx.ctx.ctx;
         if ft_ctx.is_null() { return Err(()); }
 
-        let face = create_face(ft_ctx, &template, pt_size)?;
+        let face = create_face(ft_ctx, &template, pt_size, variations)?;
 
         let mut handle = FontHandle {
               face: face,
@@ -342,6 +377,42 @@ impl<'a> FontHandle {
         }
     }
 
+    /// Selects the instance of a variable font at the given positions on its design axes. Axes
+    /// that are not mentioned keep their default positions, positions are clamped to the range
+    /// the font supports, and variations of axes the font doesn't have are ignored.
+    fn set_variations(face: FT_Face, variations: &[FontVariation]) {
+        unsafe {
+            let mut mm_var = ptr::null_mut();
+            if !succeeded(FT_Get_MM_Var(face, &mut mm_var)) || mm_var.is_null() {
+                debug!("Ignoring font-variation-settings for a font that isn't variable");
+                return
+            }
+
+            let axes = slice::from_raw_parts((*mm_var).axis, (*mm_var).num_axis as usize);
+            let mut coords: Vec<FT_Fixed> = axes.iter().map(|axis| {
+                // Later variations of the same axis take precedence.
+                match variations.iter().rev().find(|variation| variation.tag as FT_ULong == axis.tag) {
+                    Some(variation) => {
+                        let value = (variation.value as f64 * 65536.0) as FT_Fixed;
+                        value.max(axis.minimum).min(axis.maximum)
+                    }
+                    None => axis.def,
+                }
+            }).collect();
+
+            if !succeeded(FT_Set_Var_Design_Coordinates(face, coords.len() as FT_UInt, coords.as_mut_ptr())) {
+                debug!("Failed to set the variation coordinates of a font");
+            }
+
+            // `FT_Done_MM_Var` only exists since FreeType 2.9. Before that the record had to be
+            // released through the memory handler of the face's library, which works everywhere.
+            let memory = (*face).memory;
+            if let Some(free) = (*memory).free {
+                free(memory, mm_var as *mut c_void);
+            }
+        }
+    }
+
     fn has_table(&self, tag: FontTableTag) -> bool {
         unsafe {
             succeeded(FT_Load_Sfnt_Table(self.face, tag as FT_ULong, 0, ptr::null_mut(), &mut 0))
@@ -15,7 +15,7 @@ use core_text::font::CTFont;
 use core_text::font_descriptor::{SymbolicTraitAccessors, TraitAccessors};
 use core_text::font_descriptor::kCTFontDefaultOrientation;
 use font::{FontHandleMethods, FontMetrics, FontTableMethods, FontTableTag, FractionalPixel};
-use font::{GPOS, GSUB, KERN};
+use font::{FontVariation, GPOS, GSUB, KERN};
 use platform::font_template::FontTemplateData;
 use platform::macos::font_context::FontContextHandle;
 use servo_atoms::Atom;
@@ -162,9 +162,11 @@ impl fmt::Debug for CachedKernTable {
 
 
 impl FontHandleMethods for FontHandle {
+    // TODO: Select the instance of variable fonts given by `variations`.
     fn new_from_template(_fctx: &FontContextHandle,
                          template: Arc<FontTemplateData>,
-                         pt_size: Option<Au>)
+                         pt_size: Option<Au>,
+                         _variations: &[FontVariation])
                          -> Result<FontHandle, ()> {
         let size = match pt_size {
             Some(s) => s.to_f64_px(),
@@ -11,7 +11,7 @@ use dwrote;
 use dwrote::{Font, FontFace, FontFile};
 use dwrote::{FontWeight, FontStretch, FontStyle};
 use font::{FontHandleMethods, FontMetrics, FontTableMethods};
-use font::{FontTableTag, FractionalPixel};
+use font::{FontTableTag, FontVariation, FractionalPixel};
 use platform::font_template::FontTemplateData;
 use platform::windows::font_context::FontContextHandle;
 use platform::windows::font_list::font_from_atom;
@@ -250,7 +250,9 @@ impl FontHandle {
 }
 
 impl FontHandleMethods for FontHandle {
-    fn new_from_template(_: &FontContextHandle, template: Arc<FontTemplateData>, pt_size: Option<Au>)
+    // TODO: Select the instance of variable fonts given by `variations`.
+    fn new_from_template(_: &FontContextHandle, template: Arc<FontTemplateData>, pt_size: Option<Au>,
+                         _variations: &[FontVariation])
                          -> Result<Self, ()>
     {
         let (info, face) = if let Some(ref raw_font) = template.bytes {
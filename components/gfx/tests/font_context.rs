@@ -10,6 +10,7 @@ extern crate style;
 extern crate webrender_api;
 
 use app_units::Au;
+use gfx::font::{FontFeature, FontVariation, KERN, LIGA};
 use gfx::font::{fallback_font_families, FontDescriptor, FontFamilyDescriptor, FontFamilyName, FontSearchScope};
 use gfx::font_cache_thread::{FontTemplates, FontTemplateInfo};
 use gfx::font_context::{FontContext, FontContextHandle, FontSource};
@@ -22,16 +23,20 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
 use std::rc::Rc;
+use style::properties::ComputedValues;
+use style::properties::longhands::font_kerning::computed_value::T as FontKerning;
 use style::properties::longhands::font_variant_caps::computed_value::T as FontVariantCaps;
 use style::properties::style_structs::Font as FontStyleStruct;
 use style::values::computed::font::{FamilyName, FamilyNameSyntax, FontFamily, FontFamilyList, FontSize};
 use style::values::computed::font::{FontStretch, FontWeight, SingleFontFamily};
-use style::values::generics::font::FontStyle;
+use style::values::generics::font::{FeatureTagValue, FontSettings, FontStyle, FontTag};
+use style::values::generics::font::VariationValue;
 
 struct TestFontSource {
     handle: FontContextHandle,
     families: HashMap<String, FontTemplates>,
     find_font_count: Rc<Cell<isize>>,
+    blocked_families: Vec<String>,
 }
 
 impl TestFontSource {
@@ -54,6 +59,7 @@ impl TestFontSource {
             handle: FontContextHandle::new(),
             families,
             find_font_count: Rc::new(Cell::new(0)),
+            blocked_families: vec![],
         }
     }
 
@@ -72,12 +78,18 @@ impl TestFontSource {
         family.add_template(
             identifier,
             Some(file.bytes().map(|b| b.unwrap()).collect())
-        )
+        );
     }
 }
 
 impl FontSource for TestFontSource {
-    fn get_font_instance(&mut self, _key: webrender_api::FontKey, _size: Au) -> webrender_api::FontInstanceKey {
+    fn get_font_instance(
+        &mut self,
+        _key: webrender_api::FontKey,
+        _size: Au,
+        _flags: webrender_api::FontInstanceFlags,
+        _variations: &[FontVariation],
+    ) -> webrender_api::FontInstanceKey {
         webrender_api::FontInstanceKey(webrender_api::IdNamespace(0), 0)
     }
 
@@ -99,18 +111,20 @@ impl FontSource for TestFontSource {
                 }
             })
     }
+
+    fn is_web_font_blocked(&mut self, family_descriptor: FontFamilyDescriptor) -> bool {
+        self.blocked_families.iter().any(|name| name == family_descriptor.name())
+    }
 }
 
 fn style() -> FontStyleStruct {
-    let mut style = FontStyleStruct {
-        font_family: FontFamily::serif(),
-        font_style: FontStyle::Normal,
-        font_variant_caps: FontVariantCaps::Normal,
-        font_weight: FontWeight::normal(),
-        font_size: FontSize::medium(),
-        font_stretch: FontStretch::hundred(),
-        hash: 0,
-    };
+    let mut style = ComputedValues::initial_values().get_font().clone();
+    style.set_font_family(FontFamily::serif());
+    style.set_font_style(FontStyle::Normal);
+    style.set_font_variant_caps(FontVariantCaps::Normal);
+    style.set_font_weight(FontWeight::normal());
+    style.set_font_size(FontSize::medium());
+    style.set_font_stretch(FontStretch::hundred());
     style.compute_font_hash();
     style
 }
@@ -196,6 +210,29 @@ fn test_font_fallback() {
     );
 }
 
+#[test]
+fn test_font_group_is_blocked_by_web_font() {
+    let mut source = TestFontSource::new();
+    source.blocked_families.push("CSSTest Web".to_owned());
+    let mut context = FontContext::new(source);
+
+    let mut style1 = style();
+    style1.set_font_family(font_family(vec!("CSSTest Web", "CSSTest ASCII")));
+    let group = context.font_group(Arc::new(style1));
+    assert!(
+        group.borrow_mut().is_blocked_by_web_font(&mut context),
+        "text should wait for a web font that is in its block period"
+    );
+
+    let mut style2 = style();
+    style2.set_font_family(font_family(vec!("CSSTest ASCII", "CSSTest Web")));
+    let group = context.font_group(Arc::new(style2));
+    assert!(
+        !group.borrow_mut().is_blocked_by_web_font(&mut context),
+        "text shouldn't wait for a web font if an earlier family has a font"
+    );
+}
+
 #[test]
 fn test_font_template_is_cached() {
     let source = TestFontSource::new();
@@ -210,6 +247,8 @@ fn test_font_template_is_cached() {
         },
         variant: FontVariantCaps::Normal,
         pt_size: Au(10),
+        features: vec![],
+        variations: vec![],
     };
 
     let family_descriptor = FontFamilyDescriptor::new(
@@ -230,3 +269,26 @@ fn test_font_template_is_cached() {
 
     assert_eq!(count.get(), 1, "we should only have fetched the template data from the cache thread once");
 }
+
+#[test]
+fn test_font_descriptor_features_and_variations() {
+    const WGHT: u32 = 0x77676874;
+
+    let mut style = style();
+    style.set_font_kerning(FontKerning::None);
+    style.set_font_feature_settings(FontSettings(vec![
+        FeatureTagValue { tag: FontTag(LIGA), value: 0 },
+        FeatureTagValue { tag: FontTag(KERN), value: 1 },
+    ].into_boxed_slice()));
+    style.set_font_variation_settings(FontSettings(vec![
+        VariationValue { tag: FontTag(WGHT), value: 650. },
+    ].into_boxed_slice()));
+
+    let descriptor = FontDescriptor::from(&style);
+    assert_eq!(
+        descriptor.features,
+        vec![FontFeature::new(KERN, 0), FontFeature::new(LIGA, 0), FontFeature::new(KERN, 1)],
+        "font-feature-settings should come after, and so override, font-kerning"
+    );
+    assert_eq!(descriptor.variations, vec![FontVariation { tag: WGHT, value: 650. }]);
+}
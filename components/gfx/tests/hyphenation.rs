@@ -0,0 +1,48 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate gfx;
+
+use gfx::text::hyphenation::Hyphenator;
+
+// The subset of the US English patterns that apply to the words below.
+const PATTERNS: &'static str = "\
+1na
+1tio
+2io
+he2n
+hen5at
+hena4
+hy3ph
+n2at
+o2n
+1gr
+1phy
+1ty
+3raphy
+4graphy
+5po4g
+y3po
+";
+
+#[test]
+fn test_hyphenation_points() {
+    let hyphenator = Hyphenator::new(PATTERNS, 2, 3);
+    assert_eq!(hyphenator.hyphenation_points("hyphenation"), vec![2, 6]);
+    assert_eq!(hyphenator.hyphenation_points("typography"), vec![2, 5, 7]);
+}
+
+#[test]
+fn test_hyphenation_points_ignore_case() {
+    let hyphenator = Hyphenator::new(PATTERNS, 2, 3);
+    assert_eq!(hyphenator.hyphenation_points("Hyphenation"), vec![2, 6]);
+    assert_eq!(hyphenator.hyphenation_points("TYPOGRAPHY"), vec![2, 5, 7]);
+}
+
+#[test]
+fn test_hyphenation_points_respect_minimums() {
+    assert_eq!(Hyphenator::new(PATTERNS, 3, 3).hyphenation_points("typography"), vec![5, 7]);
+    assert_eq!(Hyphenator::new(PATTERNS, 2, 4).hyphenation_points("typography"), vec![2, 5]);
+    assert!(Hyphenator::new(PATTERNS, 2, 3).hyphenation_points("type").is_empty());
+}
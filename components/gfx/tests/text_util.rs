@@ -4,7 +4,7 @@
 
 extern crate gfx;
 
-use gfx::text::util::{CompressionMode, transform_text};
+use gfx::text::util::{CompressionMode, is_upright_in_vertical_text, transform_text};
 
 #[test]
 fn test_transform_compress_none() {
@@ -160,3 +160,13 @@ fn test_transform_compress_whitespace_newline_no_incoming() {
         assert_eq!(trimmed_str, oracle)
     }
 }
+
+#[test]
+fn test_upright_in_vertical_text() {
+    for &c in ['漢', 'か', 'カ', '한', '。', '、', '©', '①', 'Ａ', 'ー', '「', '（', '\u{20B9F}'].iter() {
+        assert!(is_upright_in_vertical_text(c), "{:?} should be upright", c);
+    }
+    for &c in ['a', 'Z', '1', ' ', '-', 'α', 'я', '→'].iter() {
+        assert!(!is_upright_in_vertical_text(c), "{:?} should be sideways", c);
+    }
+}
@@ -377,6 +377,14 @@ impl<'a> GlyphInfo<'a> {
         }
     }
 
+    /// Returns the index of the first byte of the character that this glyph was shaped from,
+    /// relative to the start of the glyph store.
+    pub fn byte_index(self) -> ByteIndex {
+        match self {
+            GlyphInfo::Simple(_, entry_i) | GlyphInfo::Detail(_, entry_i, _) => entry_i,
+        }
+    }
+
     pub fn char_is_space(self) -> bool {
         let (store, entry_i) = match self {
             GlyphInfo::Simple(store, entry_i) => (store, entry_i),
@@ -551,14 +559,15 @@ impl<'a> GlyphStore {
     // and advance of the glyph in the range at the given advance, if reached. Otherwise, returns the
     // the number of glyphs and the advance for the given range.
     #[inline]
-    pub fn range_index_of_advance(&self, range: &Range<ByteIndex>, advance: Au, extra_word_spacing: Au) -> (usize, Au) {
+    pub fn range_index_of_advance(&self, range: &Range<ByteIndex>, advance: Au, extra_word_spacing: Au,
+                                  extra_letter_spacing: Au) -> (usize, Au) {
         let mut index = 0;
         let mut current_advance = Au(0);
         for glyph in self.iter_glyphs_for_byte_range(range) {
             if glyph.char_is_space() {
-                current_advance += glyph.advance() + extra_word_spacing
+                current_advance += glyph.advance() + extra_word_spacing + extra_letter_spacing
             } else {
-                current_advance += glyph.advance()
+                current_advance += glyph.advance() + extra_letter_spacing
             }
             if current_advance > advance {
                 break;
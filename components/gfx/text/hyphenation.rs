@@ -0,0 +1,121 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Dictionary-based hyphenation using Liang's algorithm, as in TeX.
+//!
+//! The patterns for each supported language are bundled in `resources/hyphenation` and are
+//! loaded the first time text in that language needs to be hyphenated.
+
+use embedder_traits::resources::{self, Resource};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    static ref HYPHENATORS: Mutex<HashMap<&'static str, Arc<Hyphenator>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// A set of hyphenation patterns for one language.
+pub struct Hyphenator {
+    /// The inter-letter values of each pattern, keyed by the letters of the pattern.
+    patterns: HashMap<String, Vec<u8>>,
+    /// The number of letters in the longest pattern.
+    max_pattern_length: usize,
+    /// The minimum number of characters to leave on the line before a hyphen.
+    left_min: usize,
+    /// The minimum number of characters to carry over to the next line after a hyphen.
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Creates a hyphenator from patterns in the format of the `hyph-*.pat.txt` files: one
+    /// pattern per line, with the letters interleaved with their inter-letter values and `.`
+    /// marking the start or end of a word.
+    pub fn new(patterns: &str, left_min: usize, right_min: usize) -> Hyphenator {
+        let mut hyphenator = Hyphenator {
+            patterns: HashMap::new(),
+            max_pattern_length: 0,
+            left_min: left_min,
+            right_min: right_min,
+        };
+        for pattern in patterns.split_whitespace() {
+            let mut letters = String::new();
+            let mut values = vec![0];
+            for c in pattern.chars() {
+                match c.to_digit(10) {
+                    Some(value) => *values.last_mut().unwrap() = value as u8,
+                    None => {
+                        letters.push(c);
+                        values.push(0);
+                    }
+                }
+            }
+            hyphenator.max_pattern_length = max(hyphenator.max_pattern_length, values.len() - 1);
+            hyphenator.patterns.insert(letters, values);
+        }
+        hyphenator
+    }
+
+    /// Returns the byte offsets within `word` at which it may be hyphenated, in ascending order.
+    ///
+    /// `word` is expected to consist of letters only; any surrounding punctuation should be
+    /// trimmed off by the caller.
+    pub fn hyphenation_points(&self, word: &str) -> Vec<usize> {
+        let offsets: Vec<usize> = word.char_indices().map(|(offset, _)| offset).collect();
+        let length = offsets.len();
+        if length < self.left_min + self.right_min {
+            return vec![]
+        }
+
+        let mut dotted = vec!['.'];
+        dotted.extend(word.chars().map(|c| {
+            let mut lowercase = c.to_lowercase();
+            match (lowercase.next(), lowercase.next()) {
+                (Some(lowercase), None) => lowercase,
+                _ => c,
+            }
+        }));
+        dotted.push('.');
+
+        // `values[i]` is the highest value of any pattern between `dotted[i - 1]` and
+        // `dotted[i]`.
+        let mut values = vec![0; dotted.len() + 1];
+        let mut key = String::new();
+        for start in 0..dotted.len() {
+            key.clear();
+            for &c in &dotted[start..min(dotted.len(), start + self.max_pattern_length)] {
+                key.push(c);
+                if let Some(pattern_values) = self.patterns.get(&key) {
+                    for (index, &value) in pattern_values.iter().enumerate() {
+                        values[start + index] = max(values[start + index], value)
+                    }
+                }
+            }
+        }
+
+        // Odd values are hyphenation points. The point before the character at `index` in the
+        // word is `values[index + 1]`, because of the leading dot.
+        (self.left_min..(length - self.right_min + 1)).filter(|&index| {
+            values[index + 1] % 2 == 1
+        }).map(|index| offsets[index]).collect()
+    }
+}
+
+/// Returns the hyphenator for the given BCP 47 language tag, loading its patterns if necessary,
+/// or `None` if no patterns are bundled for the language.
+pub fn hyphenator_for_language(language: &str) -> Option<Arc<Hyphenator>> {
+    let primary_language = language.split(|c| c == '-' || c == '_').next().unwrap_or("");
+    let (key, resource, left_min, right_min) = match &*primary_language.to_ascii_lowercase() {
+        "en" => ("en", Resource::HyphenationPatternsEnUs, 2, 3),
+        "es" => ("es", Resource::HyphenationPatternsEs, 2, 2),
+        "fr" => ("fr", Resource::HyphenationPatternsFr, 2, 2),
+        _ => return None,
+    };
+
+    let mut hyphenators = HYPHENATORS.lock().unwrap();
+    Some(hyphenators.entry(key).or_insert_with(|| {
+        Arc::new(Hyphenator::new(&resources::read_string(resource), left_min, right_min))
+    }).clone())
+}
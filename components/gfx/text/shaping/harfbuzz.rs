@@ -6,8 +6,8 @@
 
 use app_units::Au;
 use euclid::Point2D;
-use font::{ShapingFlags, Font, FontTableMethods, FontTableTag, ShapingOptions, KERN};
-use harfbuzz::{HB_DIRECTION_LTR, HB_DIRECTION_RTL, HB_MEMORY_MODE_READONLY};
+use font::{ShapingFlags, Font, FontTableMethods, FontTableTag, ShapingOptions, KERN, LIGA};
+use harfbuzz::{HB_DIRECTION_LTR, HB_DIRECTION_RTL, HB_DIRECTION_TTB, HB_MEMORY_MODE_READONLY};
 use harfbuzz::{hb_blob_create, hb_face_create_for_tables};
 use harfbuzz::{hb_buffer_create, hb_font_destroy};
 use harfbuzz::{hb_buffer_get_glyph_infos, hb_shape};
@@ -27,6 +27,8 @@ use harfbuzz::hb_font_create;
 use harfbuzz::hb_font_funcs_create;
 use harfbuzz::hb_font_funcs_set_glyph_h_advance_func;
 use harfbuzz::hb_font_funcs_set_glyph_h_kerning_func;
+use harfbuzz::hb_font_funcs_set_glyph_v_advance_func;
+use harfbuzz::hb_font_funcs_set_glyph_v_origin_func;
 use harfbuzz::hb_font_funcs_set_nominal_glyph_func;
 use harfbuzz::hb_font_set_funcs;
 use harfbuzz::hb_font_set_ppem;
@@ -41,7 +43,6 @@ use text::shaping::ShaperMethods;
 use text::util::{fixed_to_float, float_to_fixed, is_bidi_control};
 
 const NO_GLYPH: i32 = -1;
-const LIGA: u32 = ot_tag!('l', 'i', 'g', 'a');
 
 pub struct ShapedGlyphData {
     count: usize,
@@ -89,7 +90,10 @@ impl ShapedGlyphData {
     }
 
     /// Returns shaped glyph data for one glyph, and updates the y-position of the pen.
-    pub fn entry_for_glyph(&self, i: usize, y_pos: &mut Au) -> ShapedGlyphEntry {
+    ///
+    /// If `vertical` is set, the text was shaped top to bottom, and the returned advance is
+    /// vertical.
+    pub fn entry_for_glyph(&self, i: usize, y_pos: &mut Au, vertical: bool) -> ShapedGlyphEntry {
         assert!(i < self.count);
 
         unsafe {
@@ -105,6 +109,17 @@ impl ShapedGlyphData {
             let x_advance = Au::from_f64_px(x_advance);
             let y_advance = Au::from_f64_px(y_advance);
 
+            if vertical {
+                // HarfBuzz measures upwards, so vertical advances down the line are negative.
+                // The offset moves the pen from the vertical origin at the top center of the
+                // glyph to its baseline origin.
+                return ShapedGlyphEntry {
+                    codepoint: (*glyph_info_i).codepoint as GlyphId,
+                    advance: -y_advance,
+                    offset: Some(Point2D::new(x_offset, -y_offset)),
+                };
+            }
+
             let offset = if x_offset == Au(0) && y_offset == Au(0) && y_advance == Au(0) {
                 None
             } else {
@@ -188,11 +203,14 @@ impl ShaperMethods for Shaper {
     fn shape_text(&self, text: &str, options: &ShapingOptions, glyphs: &mut GlyphStore) {
         unsafe {
             let hb_buffer: *mut hb_buffer_t = hb_buffer_create();
-            hb_buffer_set_direction(hb_buffer, if options.flags.contains(ShapingFlags::RTL_FLAG) {
+            let direction = if options.flags.contains(ShapingFlags::VERTICAL_UPRIGHT_FLAG) {
+                HB_DIRECTION_TTB
+            } else if options.flags.contains(ShapingFlags::RTL_FLAG) {
                 HB_DIRECTION_RTL
             } else {
                 HB_DIRECTION_LTR
-            });
+            };
+            hb_buffer_set_direction(hb_buffer, direction);
 
             hb_buffer_set_script(hb_buffer, options.script.to_hb_script());
 
@@ -219,6 +237,16 @@ impl ShaperMethods for Shaper {
                     end: hb_buffer_get_length(hb_buffer),
                 })
             }
+            // The features requested by the font properties come last, so that they take
+            // precedence over the defaults above.
+            for feature in &(*self.font).descriptor.features {
+                features.push(hb_feature_t {
+                    tag: feature.tag,
+                    value: feature.value,
+                    start: 0,
+                    end: hb_buffer_get_length(hb_buffer),
+                })
+            }
 
             hb_shape(self.hb_font, hb_buffer, features.as_mut_ptr(), features.len() as u32);
             self.save_glyph_results(text, options, glyphs, hb_buffer);
@@ -267,6 +295,7 @@ impl Shaper {
         let mut byte_range = 0..0;
 
         let mut y_pos = Au(0);
+        let vertical = options.flags.contains(ShapingFlags::VERTICAL_UPRIGHT_FLAG);
 
         // main loop over each glyph. each iteration usually processes 1 glyph and 1+ chars.
         // in cases with complex glyph-character associations, 2+ glyphs and 1+ chars can be
@@ -355,7 +384,7 @@ impl Shaper {
                                               true);
                     glyphs.add_glyph_for_byte_index(byte_idx, character, &data);
                 } else {
-                    let shape = glyph_data.entry_for_glyph(glyph_span.start, &mut y_pos);
+                    let shape = glyph_data.entry_for_glyph(glyph_span.start, &mut y_pos, vertical);
                     let advance = self.advance_for_shaped_glyph(shape.advance, character, options);
                     let data = GlyphData::new(shape.codepoint,
                                               advance,
@@ -369,7 +398,7 @@ impl Shaper {
                 let mut datas = vec!();
 
                 for glyph_i in glyph_span.clone() {
-                    let shape = glyph_data.entry_for_glyph(glyph_i, &mut y_pos);
+                    let shape = glyph_data.entry_for_glyph(glyph_i, &mut y_pos, vertical);
                     datas.push(GlyphData::new(shape.codepoint,
                                               shape.advance,
                                               shape.offset,
@@ -423,6 +452,10 @@ lazy_static! {
             hb_funcs, Some(glyph_h_advance_func), ptr::null_mut(), None);
         hb_font_funcs_set_glyph_h_kerning_func(
             hb_funcs, Some(glyph_h_kerning_func), ptr::null_mut(), None);
+        hb_font_funcs_set_glyph_v_advance_func(
+            hb_funcs, Some(glyph_v_advance_func), ptr::null_mut(), None);
+        hb_font_funcs_set_glyph_v_origin_func(
+            hb_funcs, Some(glyph_v_origin_func), ptr::null_mut(), None);
 
         FontFuncs(hb_funcs)
     };
@@ -462,6 +495,40 @@ extern fn glyph_h_advance_func(_: *mut hb_font_t,
     }
 }
 
+extern fn glyph_v_advance_func(_: *mut hb_font_t,
+                               font_data: *mut c_void,
+                               glyph: hb_codepoint_t,
+                               _: *mut c_void)
+                            -> hb_position_t {
+    let font: *mut Font = font_data as *mut Font;
+    assert!(!font.is_null());
+
+    unsafe {
+        // HarfBuzz measures upwards, so advancing down the line is negative.
+        let advance = (*font).glyph_v_advance(glyph as GlyphId);
+        -Shaper::float_to_fixed(advance)
+    }
+}
+
+/// Places the vertical origin of a glyph at the top of the font, centered horizontally.
+extern fn glyph_v_origin_func(_: *mut hb_font_t,
+                              font_data: *mut c_void,
+                              glyph: hb_codepoint_t,
+                              x: *mut hb_position_t,
+                              y: *mut hb_position_t,
+                              _: *mut c_void)
+                           -> hb_bool_t {
+    let font: *mut Font = font_data as *mut Font;
+    assert!(!font.is_null());
+
+    unsafe {
+        let h_advance = (*font).glyph_h_advance(glyph as GlyphId);
+        *x = Shaper::float_to_fixed(h_advance / 2.0);
+        *y = Shaper::float_to_fixed((*font).metrics.ascent.to_f64_px());
+        true as hb_bool_t
+    }
+}
+
 fn glyph_space_advance(font: *const Font) -> (hb_codepoint_t, f64) {
     let space_unicode = ' ';
     let space_glyph: hb_codepoint_t;
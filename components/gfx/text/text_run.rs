@@ -17,6 +17,9 @@ use unicode_bidi as bidi;
 use webrender_api;
 use xi_unicode::LineBreakLeafIter;
 
+/// U+00AD SOFT HYPHEN, which marks a hyphenation opportunity.
+pub const SOFT_HYPHEN: char = '\u{ad}';
+
 thread_local! {
     static INDEX_OF_FIRST_GLYPH_RUN_CACHE: Cell<Option<(*const TextRun, ByteIndex, usize)>> =
         Cell::new(None)
@@ -31,10 +34,20 @@ pub struct TextRun {
     pub actual_pt_size: Au,
     pub font_metrics: FontMetrics,
     pub font_key: webrender_api::FontInstanceKey,
+    /// The font instance used to draw this run sideways in a vertical line.
+    pub sideways_font_key: webrender_api::FontInstanceKey,
     /// The glyph runs that make up this text run.
     pub glyphs: Arc<Vec<GlyphRun>>,
     pub bidi_level: bidi::Level,
     pub extra_word_spacing: Au,
+    /// Extra space added after every glyph, for `text-justify: inter-character`.
+    pub extra_letter_spacing: Au,
+    /// The glyphs of the hyphenate character, which is shown at the end of a line that breaks
+    /// within a word of this run. This is `None` if the run may not be hyphenated.
+    pub hyphen: Option<Arc<GlyphStore>>,
+    /// Whether the glyphs of this run were shaped to be set upright in a vertical line, in which
+    /// case their advances are vertical.
+    pub vertical_upright: bool,
 }
 
 impl Drop for TextRun {
@@ -186,10 +199,14 @@ impl<'a> TextRun {
             font_metrics: font.metrics.clone(),
             font_template: font.handle.template(),
             font_key: font.font_key,
+            sideways_font_key: font.sideways_font_key,
             actual_pt_size: font.actual_pt_size,
             glyphs: Arc::new(glyphs),
             bidi_level: bidi_level,
             extra_word_spacing: Au(0),
+            extra_letter_spacing: Au(0),
+            hyphen: None,
+            vertical_upright: options.flags.contains(ShapingFlags::VERTICAL_UPRIGHT_FLAG),
         }, break_at_zero)
     }
 
@@ -233,6 +250,10 @@ impl<'a> TextRun {
                     // If there's no whitespace and word-break is set to
                     // keep-all, try increasing the slice.
                     continue;
+                } else if idx != text.len() && word.ends_with(SOFT_HYPHEN) {
+                    // Soft hyphens are hyphenation opportunities within a word rather than
+                    // natural word boundaries; layout breaks at them depending on `hyphens`.
+                    continue;
                 }
             if slice.len() > 0 {
                 glyphs.push(GlyphRun {
@@ -255,6 +276,20 @@ impl<'a> TextRun {
         (glyphs, break_at_zero)
     }
 
+    /// Shapes the given hyphenate character with the font and options of this run, allowing it
+    /// to be hyphenated.
+    pub fn shape_hyphenate_character(&mut self, font: &mut Font, hyphenate_character: &str,
+                                     options: &ShapingOptions) {
+        self.hyphen = Some(font.shape_text(hyphenate_character, options));
+    }
+
+    /// Returns the advance of the hyphenate character of this run.
+    pub fn hyphen_advance(&self) -> Au {
+        self.hyphen.as_ref().map_or(Au(0), |hyphen| {
+            hyphen.advance_for_byte_range(&Range::new(ByteIndex(0), hyphen.len()), Au(0))
+        })
+    }
+
     pub fn ascent(&self) -> Au {
         self.font_metrics.ascent
     }
@@ -272,10 +307,20 @@ impl<'a> TextRun {
         // TODO(Issue #98): using inter-char and inter-word spacing settings when measuring text
         self.natural_word_slices_in_range(range)
             .fold(Au(0), |advance, slice| {
-                advance + slice.glyphs.advance_for_byte_range(&slice.range, self.extra_word_spacing)
+                advance + slice.glyphs.advance_for_byte_range(&slice.range, self.extra_word_spacing) +
+                    self.extra_letter_spacing_for_slice(slice.glyphs, &slice.range)
             })
     }
 
+    /// Returns the space added to the glyphs in the given range by `extra_letter_spacing`.
+    fn extra_letter_spacing_for_slice(&self, glyphs: &GlyphStore, slice_range: &Range<ByteIndex>)
+                                      -> Au {
+        if self.extra_letter_spacing == Au(0) {
+            return Au(0)
+        }
+        self.extra_letter_spacing * glyphs.iter_glyphs_for_byte_range(slice_range).count() as i32
+    }
+
     pub fn metrics_for_range(&self, range: &Range<ByteIndex>) -> RunMetrics {
         RunMetrics::new(self.advance_for_range(range),
                         self.font_metrics.ascent,
@@ -284,7 +329,8 @@ impl<'a> TextRun {
 
     pub fn metrics_for_slice(&self, glyphs: &GlyphStore, slice_range: &Range<ByteIndex>)
                              -> RunMetrics {
-        RunMetrics::new(glyphs.advance_for_byte_range(slice_range, self.extra_word_spacing),
+        RunMetrics::new(glyphs.advance_for_byte_range(slice_range, self.extra_word_spacing) +
+                            self.extra_letter_spacing_for_slice(glyphs, slice_range),
                         self.font_metrics.ascent,
                         self.font_metrics.descent)
     }
@@ -339,8 +385,8 @@ impl<'a> TextRun {
         let mut remaining = advance;
         self.natural_word_slices_in_range(range)
             .map(|slice| {
-                let (slice_index, slice_advance) =
-                    slice.glyphs.range_index_of_advance(&slice.range, remaining, self.extra_word_spacing);
+                let (slice_index, slice_advance) = slice.glyphs.range_index_of_advance(
+                    &slice.range, remaining, self.extra_word_spacing, self.extra_letter_spacing);
                 remaining -= slice_advance;
                 slice_index
             })
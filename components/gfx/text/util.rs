@@ -95,7 +95,8 @@ pub fn transform_text(text: &str,
     }
 
     fn is_always_discardable_char(ch: char) -> bool {
-        // TODO: check for soft hyphens.
+        // Soft hyphens are kept, since they mark hyphenation opportunities. The shaper doesn't
+        // render them.
         is_bidi_control(ch)
     }
 }
@@ -155,3 +156,54 @@ pub fn is_cjk(codepoint: char) -> bool {
     // https://en.wikipedia.org/wiki/Plane_(Unicode)#Supplementary_Ideographic_Plane
     unicode_plane(codepoint) == 2
 }
+
+/// Returns whether the character is set upright, rather than sideways, in a vertical line with
+/// `text-orientation: mixed`.
+///
+/// This follows the `Vertical_Orientation` property of Unicode Standard Annex #50, treating the
+/// `Tu` and `Tr` values like `U`: brackets, long vowel marks and the like stay upright, and the
+/// font's vertical alternates give them their rotated shape.
+pub fn is_upright_in_vertical_text(codepoint: char) -> bool {
+    match codepoint {
+        '\u{00A7}' | '\u{00A9}' | '\u{00AE}' | '\u{00B1}' | '\u{00BC}'...'\u{00BE}' |
+        '\u{00D7}' | '\u{00F7}' | '\u{02EA}' | '\u{02EB}' |
+        // Hangul Jamo, Unified Canadian Aboriginal Syllabics.
+        '\u{1100}'...'\u{11FF}' | '\u{1401}'...'\u{167F}' | '\u{18B0}'...'\u{18FF}' |
+        '\u{2016}' | '\u{2020}' | '\u{2021}' | '\u{2030}' | '\u{2031}' | '\u{203B}' |
+        '\u{203C}' | '\u{2042}' | '\u{2047}'...'\u{2049}' | '\u{2051}' | '\u{2065}' |
+        '\u{20DD}'...'\u{20E0}' | '\u{20E2}'...'\u{20E4}' |
+        // Letterlike Symbols and Number Forms, apart from the arrows.
+        '\u{2100}' | '\u{2101}' | '\u{2103}'...'\u{2109}' | '\u{210F}' | '\u{2113}' |
+        '\u{2114}' | '\u{2116}' | '\u{2117}' | '\u{211E}'...'\u{2123}' | '\u{2125}' |
+        '\u{2127}' | '\u{2129}' | '\u{212E}' | '\u{2135}'...'\u{213F}' |
+        '\u{2145}'...'\u{214A}' | '\u{214C}' | '\u{214D}' | '\u{214F}'...'\u{2189}' |
+        '\u{218C}'...'\u{218F}' | '\u{221E}' | '\u{2234}' | '\u{2235}' |
+        // Miscellaneous Technical, Control Pictures, Enclosed Alphanumerics, Geometric Shapes,
+        // Miscellaneous Symbols and Dingbats.
+        '\u{2300}'...'\u{2307}' | '\u{230C}'...'\u{231F}' | '\u{2322}'...'\u{237B}' |
+        '\u{237D}'...'\u{239A}' | '\u{23BE}'...'\u{23CD}' | '\u{23CF}' | '\u{23D1}'...'\u{23DB}' |
+        '\u{23E2}'...'\u{2422}' |
+        '\u{2424}'...'\u{24FF}' | '\u{25A0}'...'\u{2619}' | '\u{2620}'...'\u{2767}' |
+        '\u{2776}'...'\u{2793}' | '\u{2B12}'...'\u{2B2F}' | '\u{2B50}'...'\u{2B59}' |
+        '\u{2BB8}'...'\u{2BFF}' |
+        // CJK radicals, symbols and punctuation, kana, Bopomofo, ideographs and Yi.
+        '\u{2E80}'...'\u{A4CF}' |
+        // Hangul Jamo Extended-A, Hangul Syllables and Hangul Jamo Extended-B.
+        '\u{A960}'...'\u{A97F}' | '\u{AC00}'...'\u{D7FF}' |
+        // Private use, CJK Compatibility Ideographs.
+        '\u{E000}'...'\u{FAFF}' |
+        // Vertical Forms, CJK Compatibility Forms and Small Form Variants.
+        '\u{FE10}'...'\u{FE1F}' | '\u{FE30}'...'\u{FE48}' | '\u{FE50}'...'\u{FE6F}' |
+        // Fullwidth forms and specials.
+        '\u{FF01}'...'\u{FF60}' | '\u{FFE0}'...'\u{FFE7}' | '\u{FFF0}'...'\u{FFF8}' |
+        '\u{FFFC}' | '\u{FFFD}' |
+        // Egyptian hieroglyphs, Tangut, kana supplements, emoji and symbols.
+        '\u{13000}'...'\u{1342F}' | '\u{17000}'...'\u{18AFF}' | '\u{1B000}'...'\u{1B2FF}' |
+        '\u{1D000}'...'\u{1D1FF}' | '\u{1D300}'...'\u{1D37F}' | '\u{1F000}'...'\u{1F7FF}' |
+        '\u{1F900}'...'\u{1FAFF}' |
+        // The Supplementary and Tertiary Ideographic Planes, and supplementary private use.
+        '\u{20000}'...'\u{3FFFD}' | '\u{F0000}'...'\u{10FFFD}' => true,
+
+        _ => false,
+    }
+}
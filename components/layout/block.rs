@@ -46,6 +46,7 @@ use model::{AdjoiningMargins, CollapsibleMargins, IntrinsicISizes, MarginCollaps
 use sequential;
 use serde::{Serialize, Serializer};
 use servo_geometry::MaxRect;
+use shape_outside::ShapeOutline;
 use std::cmp::{max, min};
 use std::fmt;
 use std::sync::Arc;
@@ -53,6 +54,9 @@ use style::computed_values::box_sizing::T as BoxSizing;
 use style::computed_values::display::T as Display;
 use style::computed_values::float::T as Float;
 use style::computed_values::overflow_x::T as StyleOverflow;
+use style::computed_values::page_break_after::T as PageBreakAfter;
+use style::computed_values::page_break_before::T as PageBreakBefore;
+use style::computed_values::page_break_inside::T as PageBreakInside;
 use style::computed_values::position::T as Position;
 use style::computed_values::text_align::T as TextAlign;
 use style::context::SharedStyleContext;
@@ -75,6 +79,10 @@ pub struct FloatedBlockInfo {
 
     /// Left or right?
     pub float_kind: FloatKind,
+
+    /// The float area from `shape-outside`, computed once the block size is known. If this is
+    /// `None`, the float area is the margin box.
+    pub shape_outline: Option<ShapeOutline>,
 }
 
 impl FloatedBlockInfo {
@@ -83,6 +91,7 @@ impl FloatedBlockInfo {
             containing_inline_size: Au(0),
             float_ceiling: Au(0),
             float_kind: float_kind,
+            shape_outline: None,
         }
     }
 }
@@ -425,6 +434,11 @@ enum CandidateBSizeIteratorStatus {
     Found,
 }
 
+/// Returns whether flows in the two writing modes have perpendicular block axes.
+fn is_orthogonal(mode: WritingMode, other_mode: WritingMode) -> bool {
+    mode.is_vertical() != other_mode.is_vertical()
+}
+
 // A helper function used in block-size calculation.
 fn translate_including_floats(cur_b: &mut Au, delta: Au, floats: &mut Floats) {
     *cur_b = *cur_b + delta;
@@ -432,6 +446,45 @@ fn translate_including_floats(cur_b: &mut Au, delta: Au, floats: &mut Floats) {
     floats.translate(LogicalSize::new(writing_mode, Au(0), -delta));
 }
 
+/// Returns the style of `flow` if it is a block-level box, to which the `page-break-*`
+/// properties apply.
+fn block_level_style(flow: &Flow) -> Option<&ComputedValues> {
+    if flow.is_block_like() {
+        Some(&*flow.as_block().fragment.style)
+    } else {
+        None
+    }
+}
+
+fn forces_page_break_before(flow: &Flow) -> bool {
+    block_level_style(flow).map_or(false, |style| {
+        style.get_box().page_break_before != PageBreakBefore::Auto &&
+        style.get_box().page_break_before != PageBreakBefore::Avoid
+    })
+}
+
+fn forces_page_break_after(flow: &Flow) -> bool {
+    block_level_style(flow).map_or(false, |style| {
+        style.get_box().page_break_after != PageBreakAfter::Auto &&
+        style.get_box().page_break_after != PageBreakAfter::Avoid
+    })
+}
+
+fn avoids_page_break_inside(flow: &Flow) -> bool {
+    block_level_style(flow).map_or(false, |style| {
+        style.get_box().page_break_inside == PageBreakInside::Avoid
+    })
+}
+
+/// Marks a flow subtree for reflow, so that a flow that was laid out for one page can be
+/// fragmented again when it is pushed to the next.
+fn damage_for_refragmentation(base: &mut BaseFlow) {
+    base.restyle_damage.insert(ServoRestyleDamage::REFLOW_OUT_OF_FLOW | ServoRestyleDamage::REFLOW);
+    for kid in base.child_iter_mut() {
+        damage_for_refragmentation(kid.mut_base());
+    }
+}
+
 /// The real assign-block-sizes traversal for flows with position 'absolute'.
 ///
 /// This is a traversal of an Absolute Flow tree.
@@ -741,6 +794,42 @@ impl BlockFlow {
         }
     }
 
+    /// Positions the children in orthogonal writing modes now that our size is known, given the
+    /// index of each such child and the offset of its border edge along our block axis.
+    fn place_orthogonal_children(&mut self, block_starts: &[(usize, Au)]) {
+        if block_starts.is_empty() {
+            return
+        }
+
+        let writing_mode = self.base.writing_mode;
+        let container_size = self.base.position.size.to_physical(writing_mode);
+        let inline_start_content_edge = self.fragment.border_box.start.i +
+            self.fragment.border_padding.inline_start;
+
+        let mut block_starts = block_starts.iter().peekable();
+        for (index, kid) in self.base.child_iter_mut().enumerate() {
+            let block_start = match block_starts.peek() {
+                Some(&&(kid_index, block_start)) if kid_index == index => block_start,
+                Some(_) => continue,
+                None => break,
+            };
+            block_starts.next();
+
+            // The child extends along our inline axis by its block size, and along our block
+            // axis by its inline size.
+            let kid_base = kid.mut_base();
+            let kid_mode = kid_base.writing_mode;
+            let position = LogicalRect::new(
+                writing_mode,
+                inline_start_content_edge,
+                block_start,
+                kid_base.position.size.block,
+                kid_base.position.size.inline,
+            );
+            kid_base.position = position.convert(writing_mode, kid_mode, container_size);
+        }
+    }
+
     /// Writes in the size of the relative containing block for children. (This information
     /// is also needed to handle RTL.)
     fn propagate_early_absolute_position_info_to_children(&mut self) {
@@ -815,6 +904,7 @@ impl BlockFlow {
             let mut floats = self.base.floats.clone();
             let thread_id = self.base.thread_id;
             let (mut had_floated_children, mut had_children_with_clearance) = (false, false);
+            let mut orthogonal_children = Vec::new();
             for (child_index, kid) in self.base.child_iter_mut().enumerate() {
                 if kid.base().flags.contains(FlowFlags::IS_ABSOLUTELY_POSITIONED) {
                     // Assume that the *hypothetical box* for an absolute flow starts immediately
@@ -840,14 +930,33 @@ impl BlockFlow {
                 }
 
                 let previous_b = cur_b;
+                let mut forces_break_after = false;
                 if let Some(ctx) = fragmentation_context {
+                    if ctx.is_paged && !ctx.this_fragment_is_empty && forces_page_break_before(kid) {
+                        break_at = Some((child_index, None));
+                        break
+                    }
+                    forces_break_after = ctx.is_paged && forces_page_break_after(kid);
+
+                    // A child that avoids breaks inside is laid out whole, and pushed to the next
+                    // page below if it does not fit on this one.
+                    let avoids_break = ctx.is_paged && !ctx.this_fragment_is_empty &&
+                        avoids_page_break_inside(kid);
                     let child_ctx = FragmentationContext {
-                        available_block_size: ctx.available_block_size - cur_b,
-                        this_fragment_is_empty: ctx.this_fragment_is_empty,
+                        available_block_size: if avoids_break {
+                            MAX_AU
+                        } else {
+                            ctx.available_block_size - cur_b
+                        },
+                        this_fragment_is_empty: ctx.this_fragment_is_empty || avoids_break,
+                        is_paged: ctx.is_paged,
                     };
                     if let Some(remaining) = kid.fragment(layout_context, Some(child_ctx)) {
                         break_at = Some((child_index + 1, Some(remaining)));
                     }
+                } else if kid.base().flags.contains(FlowFlags::CAN_BE_FRAGMENTED) {
+                    // The block-size traversal leaves fragmentable flows to their parent.
+                    kid.fragment(layout_context, None);
                 }
 
                 // Assign block-size now for the child if it might have floats in and we couldn't
@@ -892,6 +1001,14 @@ impl BlockFlow {
                     had_children_with_clearance = true
                 }
 
+                // A child in an orthogonal writing mode establishes an independent formatting
+                // context, and its margins along our block axis are inline margins, which are
+                // part of its margin box. So none of its margins collapse with ours.
+                let kid_is_orthogonal = is_orthogonal(kid.base().writing_mode, writing_mode);
+                if kid_is_orthogonal {
+                    kid.mut_base().collapsible_margins = CollapsibleMargins::new();
+                }
+
                 // Handle any (possibly collapsed) top margin.
                 let delta = margin_collapse_info.advance_block_start_margin(
                     &kid.base().collapsible_margins,
@@ -914,8 +1031,13 @@ impl BlockFlow {
                 };
                 translate_including_floats(&mut cur_b, clearance, &mut floats);
 
-                // At this point, `cur_b` is at the border edge of the child.
-                kid.mut_base().position.start.b = cur_b;
+                // At this point, `cur_b` is at the border edge of the child. Children in
+                // orthogonal writing modes are positioned once our own size is known.
+                if kid_is_orthogonal {
+                    orthogonal_children.push((child_index, cur_b));
+                } else {
+                    kid.mut_base().position.start.b = cur_b;
+                }
 
                 // Now pull out the child's outgoing floats. We didn't do this immediately after
                 // the `assign_block_size_for_inorder_child_if_necessary` call because clearance on
@@ -928,7 +1050,11 @@ impl BlockFlow {
                 // function here because the child has already translated floats past its border
                 // box.
                 let kid_base = kid.mut_base();
-                cur_b = cur_b + kid_base.position.size.block;
+                cur_b = cur_b + if kid_is_orthogonal {
+                    kid_base.position.size.inline
+                } else {
+                    kid_base.position.size.block
+                };
 
                 // Handle any (possibly collapsed) block-end margin.
                 let delta =
@@ -954,9 +1080,18 @@ impl BlockFlow {
                     if cur_b > ctx.available_block_size && !ctx.this_fragment_is_empty {
                         break_at = Some((child_index, None));
                         cur_b = previous_b;
+                        if ctx.is_paged {
+                            // The child was laid out to fit this page; have it fragmented afresh
+                            // on the next one.
+                            damage_for_refragmentation(kid_base);
+                        }
+                        break
+                    }
+                    ctx.this_fragment_is_empty = false;
+                    if forces_break_after {
+                        break_at = Some((child_index + 1, None));
                         break
                     }
-                    ctx.this_fragment_is_empty = false
                 }
 
                 // For consecutive collapse-through flows, their top margin should be calculated
@@ -1041,6 +1176,7 @@ impl BlockFlow {
             self.fragment.border_box.start.b = Au(0);
             self.base.position.size.block = cur_b;
 
+            self.place_orthogonal_children(&orthogonal_children);
             self.propagate_early_absolute_position_info_to_children();
 
             // Translate the current set of floats back into the parent coordinate system in the
@@ -1128,6 +1264,7 @@ impl BlockFlow {
             ceiling: clearance + float_info.float_ceiling,
             max_inline_size: float_info.containing_inline_size,
             kind: float_info.float_kind,
+            shape_outline: float_info.shape_outline.map(Arc::new),
         };
 
         // Place the float and return the `Floats` back to the parent flow.
@@ -1371,13 +1508,25 @@ impl BlockFlow {
         let mut inline_start_margin_edge = inline_start_content_edge;
         let mut inline_end_margin_edge = inline_end_content_edge;
 
+        // Per CSS Writing Modes § 7.3, the available inline space of a child in an orthogonal
+        // writing mode is our block size, or the size of the initial containing block along that
+        // axis if our block size is indefinite.
+        let orthogonal_content_inline_size = explicit_content_size.unwrap_or_else(|| {
+            LogicalSize::from_physical(containing_block_mode, shared_context.viewport_size()).block
+        });
+
         let mut iterator = self.base.child_iter_mut().enumerate().peekable();
         while let Some((i, kid)) = iterator.next() {
-            kid.mut_base().block_container_explicit_block_size = explicit_content_size;
-
             // The inline-start margin edge of the child flow is at our inline-start content edge,
-            // and its inline-size is our content inline-size.
+            // and its inline-size is our content inline-size. Children in orthogonal writing
+            // modes are positioned during block-size assignment instead.
             let kid_mode = kid.base().writing_mode;
+            let kid_is_orthogonal = is_orthogonal(kid_mode, containing_block_mode);
+            kid.mut_base().block_container_explicit_block_size = if kid_is_orthogonal {
+                Some(content_inline_size)
+            } else {
+                explicit_content_size
+            };
             {
                 // Don't assign positions to children unless they're going to be reflowed.
                 // Otherwise, the position we assign might be incorrect and never fixed up. (Issue
@@ -1393,7 +1542,8 @@ impl BlockFlow {
                 } else {
                     ServoRestyleDamage::REFLOW
                 };
-                if kid_base.flags.contains(FlowFlags::INLINE_POSITION_IS_STATIC) &&
+                if !kid_is_orthogonal &&
+                        kid_base.flags.contains(FlowFlags::INLINE_POSITION_IS_STATIC) &&
                         kid_base.restyle_damage.contains(reflow_damage) {
                     kid_base.position.start.i =
                         if kid_mode.is_bidi_ltr() == containing_block_mode.is_bidi_ltr() {
@@ -1403,7 +1553,11 @@ impl BlockFlow {
                             inline_end_content_edge
                         };
                 }
-                kid_base.block_container_inline_size = content_inline_size;
+                kid_base.block_container_inline_size = if kid_is_orthogonal {
+                    orthogonal_content_inline_size
+                } else {
+                    content_inline_size
+                };
                 kid_base.block_container_writing_mode = containing_block_mode;
             }
 
@@ -1449,7 +1603,9 @@ impl BlockFlow {
             Display::TableRowGroup |
             Display::Table |
             Display::InlineBlock |
-            Display::Flex => {
+            Display::Flex |
+            Display::Grid |
+            Display::InlineGrid => {
                 FormattingContextType::Other
             }
             _ if style.get_box().overflow_x != StyleOverflow::Visible ||
@@ -1568,7 +1724,8 @@ impl BlockFlow {
 
     fn is_inline_block_or_inline_flex(&self) -> bool {
         self.fragment.style().get_box().display == Display::InlineBlock ||
-        self.fragment.style().get_box().display == Display::InlineFlex
+        self.fragment.style().get_box().display == Display::InlineFlex ||
+        self.fragment.style().get_box().display == Display::InlineGrid
     }
 
     /// Computes the content portion (only) of the intrinsic inline sizes of this flow. This is
@@ -1922,6 +2079,12 @@ impl Flow for BlockFlow {
     fn assign_block_size(&mut self, ctx: &LayoutContext) {
         let remaining = Flow::fragment(self, ctx, None);
         debug_assert!(remaining.is_none());
+
+        if let Some(ref mut float_info) = self.float {
+            float_info.shape_outline = ShapeOutline::for_fragment(&self.fragment,
+                                                                  float_info.containing_inline_size,
+                                                                  ctx);
+        }
     }
 
     fn fragment(&mut self, layout_context: &LayoutContext,
@@ -28,6 +28,7 @@ use fragment::{Fragment, GeneratedContentInfo, IframeFragmentInfo, FragmentFlags
 use fragment::{InlineAbsoluteHypotheticalFragmentInfo, TableColumnFragmentInfo};
 use fragment::{InlineBlockFragmentInfo, SpecificFragmentInfo, UnscannedTextFragmentInfo};
 use fragment::WhitespaceStrippingResult;
+use grid::GridFlow;
 use inline::{InlineFlow, InlineFragmentNodeInfo, InlineFragmentNodeFlags};
 use linked_list::prepend_from;
 use list_item::{ListItemFlow, ListStyleTypeContent};
@@ -367,6 +368,29 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         node.set_flow_construction_result(result);
     }
 
+    /// Returns the children of the given node whose construction results take part in its
+    /// construction. Elements with `display: contents` generate no boxes of their own, so their
+    /// children are returned in their place.
+    ///
+    /// https://drafts.csswg.org/css-display/#valdef-display-contents
+    fn box_tree_children(&self, node: &ConcreteThreadSafeLayoutNode) -> Vec<ConcreteThreadSafeLayoutNode> {
+        let mut children = vec![];
+        for kid in node.children() {
+            let is_contents = match kid.type_id() {
+                Some(LayoutNodeType::Element(_)) => {
+                    kid.style(self.style_context()).get_box().display.is_contents()
+                }
+                _ => false,
+            };
+            if is_contents {
+                children.extend(self.box_tree_children(&kid));
+            } else {
+                children.push(kid);
+            }
+        }
+        children
+    }
+
     /// Builds the fragment for the given block or subclass thereof.
     fn build_fragment_for_block(&self, node: &ConcreteThreadSafeLayoutNode) -> Fragment {
         let specific_fragment_info = match node.type_id() {
@@ -631,7 +655,7 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         let mut abs_descendants = AbsoluteDescendants::new();
         let mut legalizer = Legalizer::new();
         if !node.is_replaced_content() {
-            for kid in node.children() {
+            for kid in self.box_tree_children(node) {
                 if kid.get_pseudo_element_type() != PseudoElementType::Normal {
                     self.process(&kid);
                 }
@@ -817,7 +841,7 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
 
         // Concatenate all the fragments of our kids, creating {ib} splits as necessary.
         let mut is_empty = true;
-        for kid in node.children() {
+        for kid in self.box_tree_children(node) {
             is_empty = false;
             if kid.get_pseudo_element_type() != PseudoElementType::Normal {
                 self.process(&kid);
@@ -991,7 +1015,8 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         let block_flow_result = match display {
             Display::InlineBlock => self.build_flow_for_block(node, None),
             Display::InlineFlex => self.build_flow_for_flex(node, None),
-            _ => panic!("The flag should be inline-block or inline-flex")
+            Display::InlineGrid => self.build_flow_for_grid(node, None),
+            _ => panic!("The flag should be inline-block, inline-flex or inline-grid")
         };
         let (block_flow, abs_descendants) = match block_flow_result {
             ConstructionResult::Flow(block_flow, abs_descendants) => (block_flow, abs_descendants),
@@ -1088,7 +1113,7 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
                                                        node: &ConcreteThreadSafeLayoutNode,
                                                        side: CaptionSide) {
         // Only flows that are table captions are matched here.
-        for kid in node.children() {
+        for kid in self.box_tree_children(node) {
             match kid.get_construction_result() {
                 ConstructionResult::Flow(kid_flow, _) => {
                     if kid_flow.is_table_caption() &&
@@ -1257,7 +1282,7 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         // if the cell has any in-flow elements (even empty ones!) and has `empty-cells` set to
         // `hide`.
         let hide = node.style(self.style_context()).get_inherited_table().empty_cells == EmptyCells::Hide &&
-            node.children().all(|kid| {
+            self.box_tree_children(node).iter().all(|kid| {
                 let position = kid.style(self.style_context()).get_box().position;
                 !kid.is_content() ||
                 position == Position::Absolute ||
@@ -1359,7 +1384,7 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
                           SpecificFragmentInfo::TableColumn(TableColumnFragmentInfo::new(node)),
                           self.layout_context);
         let mut col_fragments = vec!();
-        for kid in node.children() {
+        for kid in self.box_tree_children(node) {
             // CSS 2.1 § 17.2.1. Treat all non-column child fragments of `table-column-group`
             // as `display: none`.
             if let ConstructionResult::ConstructionItem(ConstructionItem::TableColumnFragment(fragment)) =
@@ -1388,6 +1413,16 @@ impl<'a, ConcreteThreadSafeLayoutNode: ThreadSafeLayoutNode>
         self.build_flow_for_block_like(flow, node)
     }
 
+    /// Builds a flow for a node with 'display: grid'.
+    fn build_flow_for_grid(&mut self,
+                           node: &ConcreteThreadSafeLayoutNode,
+                           float_kind: Option<FloatKind>)
+                           -> ConstructionResult {
+        let fragment = self.build_fragment_for_block(node);
+        let flow = FlowRef::new(Arc::new(GridFlow::from_fragment(fragment, float_kind)));
+        self.build_flow_for_block_like(flow, node)
+    }
+
     /// Attempts to perform incremental repair to account for recent changes to this node. This
     /// can fail and return false, indicating that flows will need to be reconstructed.
     ///
@@ -1570,6 +1605,12 @@ impl<'a, ConcreteThreadSafeLayoutNode> PostorderNodeMutTraversal<ConcreteThreadS
                 self.set_flow_construction_result(node, ConstructionResult::None);
             }
 
+            // `display: contents` contributes no flow construction result either. Its children
+            // are constructed in its place by its parent.
+            (Display::Contents, _, _) => {
+                self.set_flow_construction_result(node, ConstructionResult::None);
+            }
+
             // Table items contribute table flow construction results.
             (Display::Table, float_value, _) => {
                 let construction_result = self.build_flow_for_table(node, float_value);
@@ -1669,6 +1710,19 @@ impl<'a, ConcreteThreadSafeLayoutNode> PostorderNodeMutTraversal<ConcreteThreadS
                 self.set_flow_construction_result(node, construction_result)
             }
 
+            // Grid items contribute grid flow construction results.
+            (Display::Grid, float_value, _) => {
+                let float_kind = FloatKind::from_property(float_value);
+                let construction_result = self.build_flow_for_grid(node, float_kind);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
+            (Display::InlineGrid, _, _) => {
+                let construction_result = self.build_fragment_for_inline_block_or_inline_flex(node,
+                                                                                              Display::InlineGrid);
+                self.set_flow_construction_result(node, construction_result)
+            }
+
             // Block flows that are not floated contribute block flow construction results.
             //
             // TODO(pcwalton): Make this only trigger for blocks and handle the other `display`
@@ -2073,6 +2127,39 @@ impl Legalizer {
                 true
             }
 
+            // Grid items size themselves in the inline direction against their grid area, like
+            // the items of a row flex container do against their main size.
+            (FlowClass::Grid, FlowClass::Inline) => {
+                FlowRef::deref_mut(child).mut_base().flags.insert(FlowFlags::MARGINS_CANNOT_COLLAPSE);
+                let mut block_wrapper = Legalizer::create_anonymous_flow::<E, _>(
+                    context,
+                    parent,
+                    &[PseudoElement::ServoAnonymousBlock],
+                    SpecificFragmentInfo::Generic,
+                    BlockFlow::from_fragment,
+                );
+
+                {
+                    let block = FlowRef::deref_mut(&mut block_wrapper).as_mut_block();
+                    block.base.flags.insert(FlowFlags::MARGINS_CANNOT_COLLAPSE);
+                    block.fragment.flags.insert(FragmentFlags::IS_INLINE_FLEX_ITEM);
+                }
+                block_wrapper.add_new_child((*child).clone());
+                block_wrapper.finish();
+                parent.add_new_child(block_wrapper);
+                true
+            }
+
+            (FlowClass::Grid, _) => {
+                {
+                    let block = FlowRef::deref_mut(child).as_mut_block();
+                    block.base.flags.insert(FlowFlags::MARGINS_CANNOT_COLLAPSE);
+                    block.fragment.flags.insert(FragmentFlags::IS_INLINE_FLEX_ITEM);
+                }
+                parent.add_new_child((*child).clone());
+                true
+            }
+
             _ => {
                 parent.add_new_child((*child).clone());
                 true
@@ -83,6 +83,10 @@ pub struct LayoutContext<'a> {
     /// A list of nodes that have just initiated a CSS transition.
     /// A None value means that this layout was not initiated by the script thread.
     pub newly_transitioning_nodes: Option<Mutex<Vec<UntrustedNodeAddress>>>,
+
+    /// Whether every flow must be rebuilt rather than repaired, as when switching between
+    /// continuous and paged media.
+    pub reconstruct_all_flows: bool,
 }
 
 impl<'a> Drop for LayoutContext<'a> {
@@ -38,8 +38,9 @@ use fnv::FnvHashMap;
 use fragment::{CanvasFragmentSource, CoordinateSystem, Fragment, ScannedTextFragmentInfo};
 use fragment::SpecificFragmentInfo;
 use gfx::text::TextRun;
-use gfx::text::glyph::ByteIndex;
+use gfx::text::glyph::{ByteIndex, GlyphInfo};
 use gfx_traits::{combine_id_with_fragment_type, FragmentType, StackingContextId};
+use grid::GridFlow;
 use inline::{InlineFlow, InlineFragmentNodeFlags};
 use ipc_channel::ipc;
 use list_item::ListItemFlow;
@@ -47,8 +48,10 @@ use model::MaybeAuto;
 use msg::constellation_msg::{BrowsingContextId, PipelineId};
 use net_traits::image_cache::UsePlaceholder;
 use range::Range;
+use script_traits::{ScrollSnapAxis, ScrollSnapPoints, ScrollSnapStrictness};
 use servo_config::opts;
 use servo_geometry::MaxRect;
+use std::cmp::max;
 use std::default::Default;
 use std::f32;
 use std::mem;
@@ -58,11 +61,11 @@ use style::computed_values::overflow_x::T as StyleOverflow;
 use style::computed_values::pointer_events::T as PointerEvents;
 use style::computed_values::position::T as StylePosition;
 use style::computed_values::visibility::T as Visibility;
-use style::logical_geometry::{LogicalMargin, LogicalPoint, LogicalRect};
+use style::logical_geometry::{LogicalMargin, LogicalRect};
 use style::properties::{style_structs, ComputedValues};
 use style::servo::restyle_damage::ServoRestyleDamage;
 use style::values::{Either, RGBA};
-use style::values::computed::Gradient;
+use style::values::computed::{Gradient, ScrollSnapPoint, ScrollSnapType};
 use style::values::computed::effects::SimpleShadow;
 use style::values::generics::background::BackgroundSize;
 use style::values::generics::image::{GradientKind, Image, PaintWorklet};
@@ -226,10 +229,20 @@ pub struct StackingContextCollectionState {
 
     /// The flow parent's content box, used to calculate sticky constraints.
     parent_stacking_relative_content_box: Rect<Au>,
+
+    /// The size of the viewport, which is the scrollport of the root scroll frame.
+    viewport_size: Size2D<Au>,
+
+    /// The snap points of every scroll frame with scroll snapping.
+    pub scroll_snap_points: Vec<ScrollSnapPoints>,
+
+    /// The nearest ancestor scroll frame with scroll snapping, to which the snap coordinates
+    /// of descendants contribute snap positions.
+    current_scroll_snap_container: Option<ScrollSnapContainer>,
 }
 
 impl StackingContextCollectionState {
-    pub fn new(pipeline_id: PipelineId) -> StackingContextCollectionState {
+    pub fn new(pipeline_id: PipelineId, viewport_size: Size2D<Au>) -> StackingContextCollectionState {
         let root_clip_indices =
             ClippingAndScrolling::simple(ClipScrollNodeIndex::root_scroll_node());
 
@@ -260,6 +273,9 @@ impl StackingContextCollectionState {
             clip_stack: Vec::new(),
             containing_block_clip_stack: Vec::new(),
             parent_stacking_relative_content_box: Rect::zero(),
+            viewport_size,
+            scroll_snap_points: Vec::new(),
+            current_scroll_snap_container: None,
         }
     }
 
@@ -356,6 +372,9 @@ pub struct DisplayListBuildState<'a> {
 
     /// Stores text runs to answer text queries used to place a cursor inside text.
     pub indexable_text: IndexableText,
+
+    /// The snap points of every scroll frame with scroll snapping.
+    pub scroll_snap_points: Vec<ScrollSnapPoints>,
 }
 
 impl<'a> DisplayListBuildState<'a> {
@@ -375,6 +394,7 @@ impl<'a> DisplayListBuildState<'a> {
                 ClippingAndScrolling::simple(ClipScrollNodeIndex::root_scroll_node()),
             iframe_sizes: Vec::new(),
             indexable_text: IndexableText::default(),
+            scroll_snap_points: state.scroll_snap_points,
         }
     }
 
@@ -466,6 +486,7 @@ impl<'a> DisplayListBuildState<'a> {
         DisplayList {
             list: list,
             clip_scroll_nodes: self.clip_scroll_nodes,
+            scroll_snap_points: self.scroll_snap_points,
         }
     }
 
@@ -1985,6 +2006,13 @@ impl FragmentDisplayListBuilding for Fragment {
         text_shadows: &[SimpleShadow],
         clip: Rect<Au>,
     ) {
+        // Text waiting for a web font takes up the space of its fallback font, but is invisible
+        // until the font arrives or the block period ends.
+        // https://drafts.csswg.org/css-fonts-4/#font-display-timeline
+        if text_fragment.blocked_by_web_font() {
+            return;
+        }
+
         // NB: The order for painting text components (CSS Text Decoration Module Level 3) is:
         // shadows, underline, overline, text, text-emphasis, and then line-through.
 
@@ -1996,24 +2024,36 @@ impl FragmentDisplayListBuilding for Fragment {
             self.style().get_color().color
         };
 
-        // Determine the orientation and cursor to use.
-        let (_orientation, cursor) = if self.style.writing_mode.is_vertical() {
+        // Determine the orientation and cursor to use. Text combined upright in a vertical line
+        // is laid out like horizontal text.
+        let vertical = self.style.writing_mode.is_vertical() && !self.combines_text_upright();
+        let (orientation, cursor) = if !vertical {
+            (TextOrientation::Upright, CursorKind::Text)
+        } else if text_fragment.run.vertical_upright {
+            (TextOrientation::Upright, CursorKind::VerticalText)
+        } else {
             // TODO: Distinguish between 'sideways-lr' and 'sideways-rl' writing modes in CSS
             // Writing Modes Level 4.
             (TextOrientation::SidewaysRight, CursorKind::VerticalText)
-        } else {
-            (TextOrientation::Upright, CursorKind::Text)
         };
 
-        // Compute location of the baseline.
+        // Compute location of the baseline. In vertical lines, this is where the pen starts:
+        // the top of the alphabetic baseline of sideways glyphs, which have their ascent to the
+        // right, or the top center of upright glyphs.
         //
         // FIXME(pcwalton): Get the real container size.
         let container_size = Size2D::zero();
         let metrics = &text_fragment.run.font_metrics;
-        let baseline_origin = stacking_relative_content_box.origin +
-            LogicalPoint::new(self.style.writing_mode, Au(0), metrics.ascent)
-                .to_physical(self.style.writing_mode, container_size)
-                .to_vector();
+        let content_box = stacking_relative_content_box;
+        let baseline_origin = match orientation {
+            _ if !vertical => content_box.origin + Vector2D::new(Au(0), metrics.ascent),
+            TextOrientation::Upright => {
+                content_box.origin + Vector2D::new(content_box.size.width / 2, Au(0))
+            },
+            TextOrientation::SidewaysLeft | TextOrientation::SidewaysRight => {
+                Point2D::new(content_box.max_x() - metrics.ascent, content_box.origin.y)
+            },
+        };
 
         // Base item for all text/shadows
         let base = state.create_base_display_item(
@@ -2082,6 +2122,9 @@ impl FragmentDisplayListBuilding for Fragment {
             text_fragment.run.clone(),
             text_fragment.range,
             baseline_origin,
+            text_fragment.hyphenated(),
+            vertical,
+            &orientation,
         );
         if !glyphs.is_empty() {
             let indexable_text = IndexableTextItem {
@@ -2089,13 +2132,20 @@ impl FragmentDisplayListBuilding for Fragment {
                 text_run: text_fragment.run.clone(),
                 range: text_fragment.range,
                 baseline_origin,
+                vertical,
             };
             state.indexable_text.insert(self.node, indexable_text);
 
+            let font_key = match orientation {
+                TextOrientation::SidewaysLeft | TextOrientation::SidewaysRight => {
+                    text_fragment.run.sideways_font_key
+                },
+                TextOrientation::Upright => text_fragment.run.font_key,
+            };
             state.add_display_item(DisplayItem::Text(Box::new(TextDisplayItem {
                 base: base.clone(),
                 glyphs: glyphs,
-                font_key: text_fragment.run.font_key,
+                font_key,
                 text_color: text_color.to_layout(),
             })));
         }
@@ -2209,6 +2259,14 @@ pub trait BlockFlowDisplayListBuilding {
         preserved_state: &mut SavedStackingContextCollectionState,
         stacking_relative_border_box: Rect<Au>,
     );
+    fn collect_scroll_snap_points(&self, state: &mut StackingContextCollectionState);
+    fn setup_scroll_snap_container(
+        &self,
+        state: &mut StackingContextCollectionState,
+        scroll_id: ExternalScrollId,
+        scrollport: Rect<Au>,
+        scrollable_area: Rect<Au>,
+    );
     fn create_pseudo_stacking_context_for_block(
         &mut self,
         stacking_context_type: StackingContextType,
@@ -2253,6 +2311,51 @@ pub trait BlockFlowDisplayListBuilding {
 /// that the state is restored for siblings. To use this structure, we must call
 /// SavedStackingContextCollectionState::restore in order to restore the state.
 /// TODO(mrobinson): It would be nice to use RAII here to avoid having to call restore.
+/// A scroll frame with scroll snapping, whose snap positions are being collected.
+#[derive(Clone, Copy)]
+pub struct ScrollSnapContainer {
+    /// The index of the snap points of this scroll frame in
+    /// `StackingContextCollectionState::scroll_snap_points`.
+    index: usize,
+    /// The point of the scrollport, at scroll position zero, that snap coordinates are aligned
+    /// to. This is in the coordinate system of the block currently being collected.
+    destination: Point2D<Au>,
+}
+
+/// The most snap positions generated by a `repeat()` in `scroll-snap-points-x` or
+/// `scroll-snap-points-y`, so that small intervals can't produce unbounded lists.
+const MAX_REPEATED_SNAP_POSITIONS: i32 = 1000;
+
+/// Returns the snap positions along one axis of a scroll snap container, or `None` if the
+/// container doesn't snap along that axis.
+fn scroll_snap_axis(
+    snap_type: ScrollSnapType,
+    snap_points: &ScrollSnapPoint,
+    scrollport_size: Au,
+    max_scroll_position: Au,
+) -> Option<ScrollSnapAxis> {
+    let strictness = match snap_type {
+        ScrollSnapType::None => return None,
+        ScrollSnapType::Mandatory => ScrollSnapStrictness::Mandatory,
+        ScrollSnapType::Proximity => ScrollSnapStrictness::Proximity,
+    };
+
+    let interval = snap_points
+        .repeated()
+        .map_or(Au(0), |interval| interval.to_used_value(scrollport_size));
+    let positions = if interval > Au(0) {
+        (0..MAX_REPEATED_SNAP_POSITIONS)
+            .map(|index| interval * index)
+            .take_while(|position| *position <= max_scroll_position)
+            .map(|position| position.to_f32_px())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Some(ScrollSnapAxis { strictness, positions })
+}
+
 pub struct SavedStackingContextCollectionState {
     stacking_context_id: StackingContextId,
     real_stacking_context_id: StackingContextId,
@@ -2262,6 +2365,7 @@ pub struct SavedStackingContextCollectionState {
     clips_pushed: usize,
     containing_block_clips_pushed: usize,
     stacking_relative_content_box: Rect<Au>,
+    scroll_snap_container: Option<ScrollSnapContainer>,
 }
 
 impl SavedStackingContextCollectionState {
@@ -2275,6 +2379,7 @@ impl SavedStackingContextCollectionState {
             clips_pushed: 0,
             containing_block_clips_pushed: 0,
             stacking_relative_content_box: state.parent_stacking_relative_content_box,
+            scroll_snap_container: state.current_scroll_snap_container,
         }
     }
 
@@ -2296,6 +2401,7 @@ impl SavedStackingContextCollectionState {
         state.containing_block_clipping_and_scrolling =
             self.containing_block_clipping_and_scrolling;
         state.parent_stacking_relative_content_box = self.stacking_relative_content_box;
+        state.current_scroll_snap_container = self.scroll_snap_container;
 
         let truncate_length = state.clip_stack.len() - self.clips_pushed;
         state.clip_stack.truncate(truncate_length);
@@ -2431,6 +2537,8 @@ impl BlockFlowDisplayListBuilding for BlockFlow {
             None
         };
 
+        self.collect_scroll_snap_points(state);
+
         // We are getting the id of the scroll root that contains us here, not the id of
         // any scroll root that we create. If we create a scroll root, its index will be
         // stored in state.current_clipping_and_scrolling. If we create a stacking context,
@@ -2678,20 +2786,118 @@ impl BlockFlowDisplayListBuilding for BlockFlow {
         let content_size = self.base.overflow.scroll.origin + self.base.overflow.scroll.size;
         let content_size = Size2D::new(content_size.x, content_size.y);
 
+        let content_rect = Rect::new(content_box.origin, content_size);
+
         let external_id =
             ExternalScrollId(self.fragment.unique_id(), state.pipeline_id.to_webrender());
         let new_clip_scroll_index = state.add_clip_scroll_node(ClipScrollNode {
             parent_index: self.clipping_and_scrolling().scrolling,
             clip: clip,
-            content_rect: Rect::new(content_box.origin, content_size).to_layout(),
+            content_rect: content_rect.to_layout(),
             node_type: ClipScrollNodeType::ScrollFrame(sensitivity, external_id),
         });
+        self.setup_scroll_snap_container(state, external_id, clip_rect, content_rect);
 
         let new_clipping_and_scrolling = ClippingAndScrolling::simple(new_clip_scroll_index);
         self.base.clipping_and_scrolling = Some(new_clipping_and_scrolling);
         state.current_clipping_and_scrolling = new_clipping_and_scrolling;
     }
 
+    /// Adds the `scroll-snap-coordinate` points of this block to the snap points of the
+    /// enclosing scroll snap container and, if this block is the root, sets up the viewport as
+    /// a scroll snap container. Scroll frames of other blocks become scroll snap containers in
+    /// `setup_clip_scroll_node_for_overflow`.
+    fn collect_scroll_snap_points(&self, state: &mut StackingContextCollectionState) {
+        let border_box = self.stacking_relative_border_box(CoordinateSystem::Parent);
+
+        if let Some(container) = state.current_scroll_snap_container {
+            let snap_points = &mut state.scroll_snap_points[container.index];
+            for coordinate in self.fragment.style.get_box().scroll_snap_coordinate.0.iter() {
+                let point = border_box.origin +
+                    Vector2D::new(
+                        coordinate.horizontal.to_used_value(border_box.size.width),
+                        coordinate.vertical.to_used_value(border_box.size.height),
+                    ) -
+                    container.destination.to_vector();
+                if let Some(ref mut axis) = snap_points.x {
+                    axis.positions.push(point.x.to_f32_px());
+                }
+                if let Some(ref mut axis) = snap_points.y {
+                    axis.positions.push(point.y.to_f32_px());
+                }
+            }
+        }
+
+        if self.is_root() {
+            let scroll_id = state.pipeline_id.root_scroll_id();
+            let viewport = Rect::new(Point2D::zero(), state.viewport_size);
+            let scrollable_area = Rect::new(Point2D::zero(), self.base.overflow.scroll.size);
+            self.setup_scroll_snap_container(state, scroll_id, viewport, scrollable_area);
+        }
+
+        // Descendants of a stacking context are positioned relative to its border box.
+        if self.fragment.establishes_stacking_context() {
+            if let Some(ref mut container) = state.current_scroll_snap_container {
+                container.destination = container.destination - border_box.origin.to_vector();
+            }
+        }
+    }
+
+    /// Makes the scroll frame with the given scrollport a scroll snap container for the
+    /// descendants of this block. Scroll frames without scroll snapping still hide the snap
+    /// coordinates of their descendants from the scroll snap containers around them.
+    fn setup_scroll_snap_container(
+        &self,
+        state: &mut StackingContextCollectionState,
+        scroll_id: ExternalScrollId,
+        scrollport: Rect<Au>,
+        scrollable_area: Rect<Au>,
+    ) {
+        state.current_scroll_snap_container = None;
+
+        let box_style = self.fragment.style.get_box();
+        let max_scroll_position = Point2D::new(
+            max(scrollable_area.max_x() - scrollport.max_x(), Au(0)),
+            max(scrollable_area.max_y() - scrollport.max_y(), Au(0)),
+        );
+        let x = scroll_snap_axis(
+            box_style.scroll_snap_type_x,
+            &box_style.scroll_snap_points_x,
+            scrollport.size.width,
+            max_scroll_position.x,
+        );
+        let y = scroll_snap_axis(
+            box_style.scroll_snap_type_y,
+            &box_style.scroll_snap_points_y,
+            scrollport.size.height,
+            max_scroll_position.y,
+        );
+        if x.is_none() && y.is_none() {
+            return;
+        }
+
+        let destination = &box_style.scroll_snap_destination;
+        let destination = scrollport.origin +
+            Vector2D::new(
+                destination.horizontal.to_used_value(scrollport.size.width),
+                destination.vertical.to_used_value(scrollport.size.height),
+            );
+
+        state.scroll_snap_points.push(ScrollSnapPoints {
+            scroll_id,
+            scrollport_size: Size2D::new(
+                scrollport.size.width.to_f32_px(),
+                scrollport.size.height.to_f32_px(),
+            ),
+            x,
+            y,
+        });
+        state.current_scroll_snap_container = Some(ScrollSnapContainer {
+            index: state.scroll_snap_points.len() - 1,
+            destination,
+        });
+    }
+
     /// Adds a scroll root for a block to take the `clip` property into account
     /// per CSS 2.1 § 11.1.2.
     fn setup_clip_scroll_node_for_css_clip(
@@ -3029,6 +3235,18 @@ impl FlexFlowDisplayListBuilding for FlexFlow {
     }
 }
 
+pub trait GridFlowDisplayListBuilding {
+    fn build_display_list_for_grid(&mut self, state: &mut DisplayListBuildState);
+}
+
+impl GridFlowDisplayListBuilding for GridFlow {
+    fn build_display_list_for_grid(&mut self, state: &mut DisplayListBuildState) {
+        // Draw the rest of the block.
+        self.as_mut_block()
+            .build_display_list_for_block(state, BorderPaintingMode::Separate)
+    }
+}
+
 trait BaseFlowDisplayListBuilding {
     fn build_display_items_for_debugging_tint(
         &self,
@@ -3136,30 +3354,71 @@ pub enum BorderPaintingMode<'a> {
     Hidden,
 }
 
+/// Positions the glyphs of the given range of a text run, starting at `origin`, followed by the
+/// hyphenate character of the run if `hyphenated` is set. In vertical lines the pen advances down
+/// the page, and the offsets of sideways glyphs are turned with them.
 fn convert_text_run_to_glyphs(
     text_run: Arc<TextRun>,
     range: Range<ByteIndex>,
     mut origin: Point2D<Au>,
+    hyphenated: bool,
+    vertical: bool,
+    orientation: &TextOrientation,
 ) -> Vec<GlyphInstance> {
+    fn glyph_instance(
+        glyph: GlyphInfo,
+        origin: Point2D<Au>,
+        vertical: bool,
+        orientation: &TextOrientation,
+    ) -> GlyphInstance {
+        let glyph_offset = glyph.offset().unwrap_or(Point2D::zero());
+        let glyph_offset = match *orientation {
+            TextOrientation::SidewaysRight if vertical => {
+                Point2D::new(-glyph_offset.y, glyph_offset.x)
+            },
+            TextOrientation::SidewaysLeft if vertical => {
+                Point2D::new(glyph_offset.y, -glyph_offset.x)
+            },
+            _ => glyph_offset,
+        };
+        let point = origin + glyph_offset.to_vector();
+        GlyphInstance {
+            index: glyph.id(),
+            point: point.to_layout(),
+        }
+    }
+
     let mut glyphs = vec![];
 
     for slice in text_run.natural_word_slices_in_visual_order(&range) {
         for glyph in slice.glyphs.iter_glyphs_for_byte_range(&slice.range) {
             let glyph_advance = if glyph.char_is_space() {
-                glyph.advance() + text_run.extra_word_spacing
+                glyph.advance() + text_run.extra_word_spacing + text_run.extra_letter_spacing
             } else {
-                glyph.advance()
+                glyph.advance() + text_run.extra_letter_spacing
             };
             if !slice.glyphs.is_whitespace() {
-                let glyph_offset = glyph.offset().unwrap_or(Point2D::zero());
-                let point = origin + glyph_offset.to_vector();
-                let glyph = GlyphInstance {
-                    index: glyph.id(),
-                    point: point.to_layout(),
-                };
-                glyphs.push(glyph);
+                glyphs.push(glyph_instance(glyph, origin, vertical, orientation));
+            }
+            if vertical {
+                origin.y += glyph_advance;
+            } else {
+                origin.x += glyph_advance;
+            }
+        }
+    }
+
+    if hyphenated {
+        if let Some(ref hyphen) = text_run.hyphen {
+            let hyphen_range = Range::new(ByteIndex(0), hyphen.len());
+            for glyph in hyphen.iter_glyphs_for_byte_range(&hyphen_range) {
+                glyphs.push(glyph_instance(glyph, origin, vertical, orientation));
+                if vertical {
+                    origin.y += glyph.advance();
+                } else {
+                    origin.x += glyph.advance();
+                }
             }
-            origin.x += glyph_advance;
         }
     }
     return glyphs;
@@ -3174,6 +3433,8 @@ pub struct IndexableTextItem {
     pub range: Range<ByteIndex>,
     /// The position of the start of the baseline of this text.
     pub baseline_origin: Point2D<Au>,
+    /// Whether this text advances down the page.
+    pub vertical: bool,
 }
 
 #[derive(Default)]
@@ -3197,10 +3458,11 @@ impl IndexableText {
         // TODO(#20020): access all elements
         let point = point_in_item + item[0].origin.to_vector();
         let offset = point - item[0].baseline_origin;
+        let advance = if item[0].vertical { offset.y } else { offset.x };
         Some(
             item[0]
                 .text_run
-                .range_index_of_advance(&item[0].range, offset.x),
+                .range_index_of_advance(&item[0].range, advance),
         )
     }
 }
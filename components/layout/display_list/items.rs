@@ -17,6 +17,7 @@ use gfx_traits::{self, StackingContextId};
 use gfx_traits::print_tree::PrintTree;
 use msg::constellation_msg::PipelineId;
 use net_traits::image::base::Image;
+use script_traits::ScrollSnapPoints;
 use servo_geometry::MaxRect;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -98,6 +99,8 @@ impl ClippingAndScrolling {
 pub struct DisplayList {
     pub list: Vec<DisplayItem>,
     pub clip_scroll_nodes: Vec<ClipScrollNode>,
+    /// The snap points of the scroll frames in this display list that have scroll snapping.
+    pub scroll_snap_points: Vec<ScrollSnapPoints>,
 }
 
 impl DisplayList {
@@ -0,0 +1,678 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Serialises the display lists of a paged document as a PDF file.
+//!
+//! The output is an uncompressed PDF 1.4 document. Fonts are embedded whole as CID-keyed fonts
+//! addressed by glyph index, with a `ToUnicode` map from each glyph to the text it was shaped
+//! from so that the text can be searched and copied. Images are drawn as RGB or greyscale with a
+//! soft mask for transparency, and gradients as shadings.
+//!
+//! Not everything is drawn yet:
+//!
+//! * box shadows, text shadows and filters;
+//! * the contents of iframes, which are laid out by the layout thread of their own document;
+//! * border images and gradient borders;
+//! * rounded corners, which are drawn square;
+//! * the transparency of gradient stops, and the repetition of repeating gradients, which are
+//!   drawn clamped;
+//! * wavy lines and double, groove, ridge, inset and outset borders, which are drawn solid.
+
+use app_units::Au;
+use display_list::items::{BaseDisplayItem, BorderDetails, ClipScrollNodeType, DisplayItem, DisplayList};
+use euclid::TypedTransform2D;
+use gfx::font_template::FontTemplateData;
+use net_traits::image::base::{Image, PixelFormat};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use webrender_api::{BorderStyle, ColorF, FontInstanceKey, GradientStop, ImageKey, LayoutPixel};
+use webrender_api::{LayoutPoint, LayoutRect, LayoutSize, LineStyle};
+
+/// The number of PDF points in a CSS pixel.
+const POINTS_PER_PX: f32 = 0.75;
+
+/// The object numbers of the document catalog and of the page tree, which are written last.
+const CATALOG_OBJECT: usize = 1;
+const PAGE_TREE_OBJECT: usize = 2;
+
+type PageTransform = TypedTransform2D<f32, LayoutPixel, LayoutPixel>;
+
+/// A font file referenced by text on some page.
+struct EmbeddedFont {
+    template: Arc<FontTemplateData>,
+    /// The object number of the `Type0` font dictionary, once the font has been written.
+    object: Option<usize>,
+    /// The object number of the `ToUnicode` map of the font, which is written by `finish` once
+    /// the text of all the glyphs is known.
+    to_unicode: Option<usize>,
+    /// The text that each glyph was shaped from.
+    glyph_text: HashMap<u32, String>,
+}
+
+/// An image referenced by some page.
+struct EmbeddedImage {
+    image: Arc<Image>,
+    /// The object number of the image XObject, once the image has been written.
+    object: Option<usize>,
+}
+
+/// Accumulates pages and the resources they use, and produces the final PDF file.
+pub struct PdfWriter {
+    /// The bodies of the indirect objects; object `n` is at index `n - 1`.
+    objects: Vec<Vec<u8>>,
+    /// The object numbers of the pages, in order.
+    pages: Vec<usize>,
+    fonts: Vec<EmbeddedFont>,
+    /// The font and size in CSS pixels of each WebRender font instance.
+    font_instances: HashMap<FontInstanceKey, (usize, f32)>,
+    images: HashMap<ImageKey, EmbeddedImage>,
+}
+
+/// The content stream and resource names of the page being written.
+struct PageContent {
+    ops: String,
+    fonts: Vec<usize>,
+    images: Vec<usize>,
+    alphas: Vec<f32>,
+    shadings: Vec<String>,
+}
+
+impl PdfWriter {
+    pub fn new() -> PdfWriter {
+        PdfWriter {
+            // The catalog and the page tree are filled in by `finish`.
+            objects: vec![vec![], vec![]],
+            pages: vec![],
+            fonts: vec![],
+            font_instances: HashMap::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    /// Registers the font behind a WebRender font instance, so that text using it can be drawn.
+    pub fn add_font(&mut self, key: FontInstanceKey, template: Arc<FontTemplateData>, size: Au) {
+        if self.font_instances.contains_key(&key) {
+            return;
+        }
+        let index = match self.fonts.iter().position(|font| Arc::ptr_eq(&font.template, &template)) {
+            Some(index) => index,
+            None => {
+                self.fonts.push(EmbeddedFont {
+                    template: template,
+                    object: None,
+                    to_unicode: None,
+                    glyph_text: HashMap::new(),
+                });
+                self.fonts.len() - 1
+            },
+        };
+        self.font_instances.insert(key, (index, size.to_f32_px()));
+    }
+
+    /// Records the text that a glyph of a registered font was shaped from. A glyph that is
+    /// shaped from different texts, as with ligatures, keeps the first one.
+    pub fn add_glyph_text(&mut self, key: FontInstanceKey, glyph: u32, text: &str) {
+        if let Some(&(index, _)) = self.font_instances.get(&key) {
+            self.fonts[index].glyph_text.entry(glyph).or_insert_with(|| text.to_owned());
+        }
+    }
+
+    /// Registers a decoded image, so that image display items referring to it can be drawn.
+    pub fn add_image(&mut self, image: Arc<Image>) {
+        if let Some(key) = image.id {
+            self.images.entry(key).or_insert(EmbeddedImage {
+                image: image,
+                object: None,
+            });
+        }
+    }
+
+    /// Appends a page of the given size, drawing the display list with its origin at
+    /// `origin`, which is the top left corner of the page area.
+    pub fn add_page(&mut self, display_list: &DisplayList, page_size: LayoutSize, origin: LayoutPoint) {
+        let mut page = PageContent {
+            ops: String::new(),
+            fonts: vec![],
+            images: vec![],
+            alphas: vec![],
+            shadings: vec![],
+        };
+
+        // PDF user space is in points with the origin at the bottom left corner of the page.
+        let page_height = page_size.height * POINTS_PER_PX;
+        let _ = write!(page.ops, "{} 0 0 {} 0 {} cm\n", POINTS_PER_PX, -POINTS_PER_PX, page_height);
+
+        let page_box = LayoutRect::new(LayoutPoint::zero(), page_size);
+        let mut transforms = vec![PageTransform::create_translation(origin.x, origin.y)];
+        let mut clips: Vec<Option<LayoutRect>> = vec![None; display_list.clip_scroll_nodes.len()];
+
+        for item in &display_list.list {
+            let transform = *transforms.last().unwrap();
+            match *item {
+                DisplayItem::PushStackingContext(ref item) => {
+                    let stacking_context = &item.stacking_context;
+                    let mut transform = transform.pre_translate(stacking_context.bounds.origin.to_vector());
+                    if let Some(ref stacking_context_transform) = stacking_context.transform {
+                        transform = transform.pre_mul(&stacking_context_transform.to_2d());
+                    }
+                    transforms.push(transform);
+                },
+                DisplayItem::PopStackingContext(_) => {
+                    transforms.pop();
+                },
+                DisplayItem::DefineClipScrollNode(ref item) => {
+                    let index = item.node_index.to_index();
+                    let node = &display_list.clip_scroll_nodes[index];
+                    // Scroll frames are printed at their initial scroll position, so they clip
+                    // like any other overflow clip. Rounded corners are not supported.
+                    match node.node_type {
+                        ClipScrollNodeType::Clip | ClipScrollNodeType::ScrollFrame(..) => {
+                            clips[index] = Some(transform.transform_rect(&node.clip.main));
+                        },
+                        ClipScrollNodeType::StickyFrame(_) | ClipScrollNodeType::Placeholder => {},
+                    }
+                },
+                _ => {
+                    let clip = match item_clip(item.base(), display_list, &clips, &transform, &page_box) {
+                        Some(clip) => clip,
+                        None => continue,
+                    };
+                    let _ = write!(page.ops, "q\n{} {} {} {} re W n\n",
+                                   clip.origin.x, clip.origin.y, clip.size.width, clip.size.height);
+                    let _ = write!(page.ops, "{} {} {} {} {} {} cm\n",
+                                   transform.m11, transform.m12, transform.m21,
+                                   transform.m22, transform.m31, transform.m32);
+                    self.draw_item(item, &mut page);
+                    page.ops.push_str("Q\n");
+                },
+            }
+        }
+
+        let contents = self.add_stream("", page.ops.as_bytes());
+
+        let mut resources = String::from("<< /Font <<");
+        for &index in &page.fonts {
+            if let Some(object) = self.fonts[index].object {
+                let _ = write!(resources, " /F{} {} 0 R", index, object);
+            }
+        }
+        resources.push_str(" >> /XObject <<");
+        for &object in &page.images {
+            let _ = write!(resources, " /Im{} {} 0 R", object, object);
+        }
+        resources.push_str(" >> /ExtGState <<");
+        for (index, alpha) in page.alphas.iter().enumerate() {
+            let _ = write!(resources, " /GS{} << /ca {} /CA {} >>", index, alpha, alpha);
+        }
+        resources.push_str(" >> /Shading <<");
+        for (index, shading) in page.shadings.iter().enumerate() {
+            let _ = write!(resources, " /Sh{} {}", index, shading);
+        }
+        resources.push_str(" >> >>");
+
+        let page_object = self.add_object(format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources {} /Contents {} 0 R >>",
+            PAGE_TREE_OBJECT,
+            page_size.width * POINTS_PER_PX,
+            page_height,
+            resources,
+            contents,
+        ).into_bytes());
+        self.pages.push(page_object);
+    }
+
+    /// Writes the document catalog, the page tree and the cross-reference table, and returns
+    /// the bytes of the PDF file.
+    pub fn finish(mut self) -> Vec<u8> {
+        for font in &self.fonts {
+            if let Some(object) = font.to_unicode {
+                self.objects[object - 1] = stream_body("", to_unicode_cmap(&font.glyph_text).as_bytes());
+            }
+        }
+        self.objects[CATALOG_OBJECT - 1] =
+            format!("<< /Type /Catalog /Pages {} 0 R >>", PAGE_TREE_OBJECT).into_bytes();
+        let kids: Vec<String> = self.pages.iter().map(|page| format!("{} 0 R", page)).collect();
+        self.objects[PAGE_TREE_OBJECT - 1] =
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len()).into_bytes();
+
+        // The binary comment marks the file as containing binary data.
+        let mut output = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (index, object) in self.objects.iter().enumerate() {
+            offsets.push(output.len());
+            output.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            output.extend_from_slice(object);
+            output.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = output.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+        for offset in offsets {
+            let _ = write!(xref, "{:010} 00000 n \n", offset);
+        }
+        let _ = write!(xref, "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+                       self.objects.len() + 1, CATALOG_OBJECT, xref_offset);
+        output.extend_from_slice(xref.as_bytes());
+        output
+    }
+
+    fn add_object(&mut self, body: Vec<u8>) -> usize {
+        self.objects.push(body);
+        self.objects.len()
+    }
+
+    fn add_stream(&mut self, dictionary: &str, data: &[u8]) -> usize {
+        self.add_object(stream_body(dictionary, data))
+    }
+
+    fn draw_item(&mut self, item: &DisplayItem, page: &mut PageContent) {
+        match *item {
+            DisplayItem::SolidColor(ref item) => {
+                fill_rect(page, &item.base.bounds, item.color);
+            },
+            DisplayItem::Line(ref item) => {
+                let bounds = &item.base.bounds;
+                let dotted = match item.style {
+                    LineStyle::Dotted => true,
+                    LineStyle::Dashed => false,
+                    // FIXME: Wavy lines are drawn solid.
+                    LineStyle::Solid | LineStyle::Wavy => return fill_rect(page, bounds, item.color),
+                };
+                // Lines are drawn along the middle of their bounds, in their longest direction.
+                let (from, to, width) = if bounds.size.width >= bounds.size.height {
+                    let y = bounds.origin.y + bounds.size.height / 2.;
+                    ((bounds.origin.x, y), (bounds.max_x(), y), bounds.size.height)
+                } else {
+                    let x = bounds.origin.x + bounds.size.width / 2.;
+                    ((x, bounds.origin.y), (x, bounds.max_y()), bounds.size.width)
+                };
+                stroke_dashed(page, from, to, width, item.color, dotted);
+            },
+            DisplayItem::Border(ref item) => {
+                let border = match item.details {
+                    BorderDetails::Normal(ref border) => border,
+                    // TODO: Border images and gradients.
+                    _ => return,
+                };
+                // FIXME: Rounded corners are ignored, and double, groove, ridge, inset and
+                // outset borders are drawn solid.
+                let rect = &item.base.bounds;
+                let widths = &item.border_widths;
+                let (x0, y0) = (rect.origin.x, rect.origin.y);
+                let (x1, y1) = (rect.max_x(), rect.max_y());
+                let (left, top) = (x0 + widths.left, y0 + widths.top);
+                let (right, bottom) = (x1 - widths.right, y1 - widths.bottom);
+                let sides = [
+                    (&border.top, widths.top, [(x0, y0), (x1, y0), (right, top), (left, top)]),
+                    (&border.right, widths.right, [(x1, y0), (x1, y1), (right, bottom), (right, top)]),
+                    (&border.bottom, widths.bottom, [(x1, y1), (x0, y1), (left, bottom), (right, bottom)]),
+                    (&border.left, widths.left, [(x0, y1), (x0, y0), (left, top), (left, bottom)]),
+                ];
+                for &(side, width, ref corners) in &sides {
+                    match side.style {
+                        _ if width <= 0. => continue,
+                        BorderStyle::None | BorderStyle::Hidden => continue,
+                        BorderStyle::Dashed | BorderStyle::Dotted => {
+                            // The side is stroked along its middle, between its outer and inner
+                            // edges.
+                            let middle = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2., (a.1 + b.1) / 2.);
+                            let from = middle(corners[0], corners[3]);
+                            let to = middle(corners[1], corners[2]);
+                            stroke_dashed(page, from, to, width, side.color, side.style == BorderStyle::Dotted);
+                            continue;
+                        },
+                        _ => {},
+                    }
+                    set_fill_color(page, side.color);
+                    let _ = write!(page.ops, "{} {} m {} {} l {} {} l {} {} l h f\n",
+                                   corners[0].0, corners[0].1, corners[1].0, corners[1].1,
+                                   corners[2].0, corners[2].1, corners[3].0, corners[3].1);
+                }
+            },
+            DisplayItem::Text(ref item) => {
+                let (font, size) = match self.font_instances.get(&item.font_key) {
+                    Some(&(font, size)) => (font, size),
+                    None => return,
+                };
+                if self.font_object(font).is_none() {
+                    return;
+                }
+                if !page.fonts.contains(&font) {
+                    page.fonts.push(font);
+                }
+                set_fill_color(page, item.text_color);
+                let _ = write!(page.ops, "BT\n/F{} {} Tf\n", font, size);
+                for glyph in &item.glyphs {
+                    if glyph.index > 0xFFFF {
+                        continue;
+                    }
+                    // Undo the flip of the page coordinates, so that glyphs are drawn upright.
+                    let _ = write!(page.ops, "1 0 0 -1 {} {} Tm <{:04X}> Tj\n",
+                                   glyph.point.x, glyph.point.y, glyph.index);
+                }
+                page.ops.push_str("ET\n");
+            },
+            DisplayItem::Image(ref item) => {
+                let stretch_size = item.stretch_size;
+                if stretch_size.width <= 0. || stretch_size.height <= 0. {
+                    return;
+                }
+                let object = match self.image_object(item.id) {
+                    Some(object) => object,
+                    None => return,
+                };
+                if !page.images.contains(&object) {
+                    page.images.push(object);
+                }
+                for origin in tile_origins(&item.base.bounds, stretch_size, item.tile_spacing) {
+                    // Images are drawn in the unit square, with their first row at the top.
+                    let _ = write!(page.ops, "q {} 0 0 {} {} {} cm /Im{} Do Q\n",
+                                   stretch_size.width, -stretch_size.height,
+                                   origin.x, origin.y + stretch_size.height, object);
+                }
+            },
+            DisplayItem::Gradient(ref item) => {
+                let gradient = &item.gradient;
+                let shading = format!("/ShadingType 2 /Coords [{} {} {} {}]",
+                                      gradient.start_point.x, gradient.start_point.y,
+                                      gradient.end_point.x, gradient.end_point.y);
+                fill_gradient_tiles(page, &item.base.bounds, item.tile, item.tile_spacing,
+                                    &gradient.stops, &shading, "");
+            },
+            DisplayItem::RadialGradient(ref item) => {
+                let gradient = &item.gradient;
+                if gradient.radius.width <= 0. || gradient.radius.height <= 0. {
+                    return;
+                }
+                // The shading is a circle of radius 1, which is scaled to the ellipse.
+                let transform = format!("{} 0 0 {} {} {} cm",
+                                        gradient.radius.width, gradient.radius.height,
+                                        gradient.center.x, gradient.center.y);
+                fill_gradient_tiles(page, &item.base.bounds, item.tile, item.tile_spacing,
+                                    &gradient.stops, "/ShadingType 3 /Coords [0 0 0 0 0 1]", &transform);
+            },
+            // TODO: Shadows. The contents of iframes are laid out by another layout thread, so
+            // only the iframe's own background and borders are printed.
+            DisplayItem::BoxShadow(_) |
+            DisplayItem::PushTextShadow(_) |
+            DisplayItem::PopAllTextShadows(_) |
+            DisplayItem::Iframe(_) => {},
+            DisplayItem::PushStackingContext(_) |
+            DisplayItem::PopStackingContext(_) |
+            DisplayItem::DefineClipScrollNode(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the object number of the `Type0` font dictionary of the given font, writing the
+    /// font the first time it is used. Returns `None` for fonts that can't be embedded.
+    fn font_object(&mut self, index: usize) -> Option<usize> {
+        if let Some(object) = self.fonts[index].object {
+            return Some(object);
+        }
+
+        let bytes = self.fonts[index].template.bytes();
+        let (subtype, font_file) = if bytes.starts_with(b"OTTO") {
+            ("CIDFontType0", format!("/FontFile3 {} 0 R", self.add_stream("/Subtype /OpenType", &bytes)))
+        } else if bytes.starts_with(b"\x00\x01\x00\x00") || bytes.starts_with(b"true") {
+            ("CIDFontType2", format!("/FontFile2 {} 0 R", self.add_stream("", &bytes)))
+        } else {
+            // TODO: Extract the face from font collections.
+            return None;
+        };
+
+        // The map is filled in by `finish`, as later pages may use more glyphs.
+        let to_unicode = self.add_object(vec![]);
+        self.fonts[index].to_unicode = Some(to_unicode);
+
+        let name = format!("ServoFont{}", index);
+        let descriptor = self.add_object(format!(
+            "<< /Type /FontDescriptor /FontName /{} /Flags 4 /FontBBox [0 -1000 1000 1000] \
+             /ItalicAngle 0 /Ascent 1000 /Descent -1000 /CapHeight 1000 /StemV 80 {} >>",
+            name,
+            font_file,
+        ).into_bytes());
+        // Glyphs are positioned one by one, so their advances don't matter.
+        let descendant = self.add_object(format!(
+            "<< /Type /Font /Subtype /{} /BaseFont /{} \
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+             /FontDescriptor {} 0 R /CIDToGIDMap /Identity /DW 0 >>",
+            subtype,
+            name,
+            descriptor,
+        ).into_bytes());
+        let object = self.add_object(format!(
+            "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H \
+             /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+            name,
+            descendant,
+            to_unicode,
+        ).into_bytes());
+
+        self.fonts[index].object = Some(object);
+        Some(object)
+    }
+
+    /// Returns the object number of the image XObject for the given image, writing the image
+    /// the first time it is used.
+    fn image_object(&mut self, key: ImageKey) -> Option<usize> {
+        let image = match self.images.get(&key) {
+            Some(&EmbeddedImage { object: Some(object), .. }) => return Some(object),
+            Some(embedded) => embedded.image.clone(),
+            None => return None,
+        };
+
+        let pixel_count = (image.width * image.height) as usize;
+        let mut color = Vec::with_capacity(pixel_count * 3);
+        let mut alpha = Vec::with_capacity(pixel_count);
+        let color_space = match image.format {
+            PixelFormat::K8 => {
+                color.extend_from_slice(&image.bytes);
+                "DeviceGray"
+            },
+            PixelFormat::KA8 => {
+                for pixel in image.bytes.chunks(2) {
+                    color.push(pixel[0]);
+                    alpha.push(pixel[1]);
+                }
+                "DeviceGray"
+            },
+            PixelFormat::RGB8 => {
+                color.extend_from_slice(&image.bytes);
+                "DeviceRGB"
+            },
+            PixelFormat::BGRA8 => {
+                // Decoded images have premultiplied alpha.
+                for pixel in image.bytes.chunks(4) {
+                    let a = pixel[3] as u32;
+                    let unpremultiply = |c: u8| if a == 0 { 0 } else { (c as u32 * 255 / a) as u8 };
+                    color.push(unpremultiply(pixel[2]));
+                    color.push(unpremultiply(pixel[1]));
+                    color.push(unpremultiply(pixel[0]));
+                    alpha.push(pixel[3]);
+                }
+                "DeviceRGB"
+            },
+        };
+
+        let mut dictionary = format!("/Type /XObject /Subtype /Image /Width {} /Height {} \
+                                      /BitsPerComponent 8 /ColorSpace /{}",
+                                     image.width, image.height, color_space);
+        if alpha.iter().any(|&a| a != 255) {
+            let mask = self.add_stream(&format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /BitsPerComponent 8 \
+                 /ColorSpace /DeviceGray",
+                image.width,
+                image.height,
+            ), &alpha);
+            let _ = write!(dictionary, " /SMask {} 0 R", mask);
+        }
+        let object = self.add_stream(&dictionary, &color);
+
+        self.images.get_mut(&key).unwrap().object = Some(object);
+        Some(object)
+    }
+}
+
+/// Returns the clip of a display item in page coordinates, or `None` if nothing of it is visible.
+fn item_clip(base: &BaseDisplayItem,
+             display_list: &DisplayList,
+             clips: &[Option<LayoutRect>],
+             transform: &PageTransform,
+             page_box: &LayoutRect)
+             -> Option<LayoutRect> {
+    let mut clip = transform.transform_rect(&base.clip_rect).intersection(page_box)?;
+
+    let clipping_and_scrolling = base.clipping_and_scrolling;
+    let mut index = clipping_and_scrolling.clipping.unwrap_or(clipping_and_scrolling.scrolling).to_index();
+    loop {
+        if let Some(ref node_clip) = clips[index] {
+            clip = clip.intersection(node_clip)?;
+        }
+        let parent = display_list.clip_scroll_nodes[index].parent_index.to_index();
+        if parent == index {
+            break;
+        }
+        index = parent;
+    }
+    Some(clip)
+}
+
+/// Returns the body of a stream object with the given dictionary entries.
+fn stream_body(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(b"\nendstream");
+    body
+}
+
+/// Returns a CMap that maps glyph indices to the text they were shaped from, as UTF-16.
+fn to_unicode_cmap(glyph_text: &HashMap<u32, String>) -> String {
+    let mut glyphs: Vec<_> = glyph_text.iter()
+        .filter(|&(&glyph, text)| glyph <= 0xFFFF && !text.is_empty())
+        .collect();
+    glyphs.sort_by_key(|&(&glyph, _)| glyph);
+
+    let mut cmap = String::from("/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+                                 /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+                                 /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+                                 1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n");
+    // A `bfchar` section has at most 100 entries.
+    for chunk in glyphs.chunks(100) {
+        let _ = write!(cmap, "{} beginbfchar\n", chunk.len());
+        for &(&glyph, text) in chunk {
+            let _ = write!(cmap, "<{:04X}> <", glyph);
+            for unit in text.encode_utf16() {
+                let _ = write!(cmap, "{:04X}", unit);
+            }
+            cmap.push_str(">\n");
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend");
+    cmap
+}
+
+/// Returns the origins of the tiles of the given size that cover `bounds`.
+fn tile_origins(bounds: &LayoutRect, tile: LayoutSize, spacing: LayoutSize) -> Vec<LayoutPoint> {
+    let mut origins = vec![];
+    if tile.width <= 0. || tile.height <= 0. {
+        return origins;
+    }
+    let mut y = bounds.origin.y;
+    while y < bounds.max_y() {
+        let mut x = bounds.origin.x;
+        while x < bounds.max_x() {
+            origins.push(LayoutPoint::new(x, y));
+            x += tile.width + spacing.width;
+        }
+        y += tile.height + spacing.height;
+    }
+    origins
+}
+
+/// Returns a function that maps gradient offsets to the colors of the stops, or `None` if there
+/// are no stops.
+fn gradient_function(stops: &[GradientStop]) -> Option<String> {
+    let color = |color: &ColorF| format!("[{} {} {}]", color.r, color.g, color.b);
+    let (first, last) = (stops.first()?, stops.last()?);
+    if first.offset >= last.offset {
+        // Every stop is at the same offset, so the gradient is the color of the last one.
+        let color = color(&last.color);
+        return Some(format!("<< /FunctionType 2 /Domain [0 1] /C0 {} /C1 {} /N 1 >>", color, color));
+    }
+
+    // The stops are joined by linear interpolations. Offsets outside of the domain are clamped
+    // to it, which extends the first and last colors.
+    let mut functions = vec![];
+    let mut bounds = vec![];
+    for pair in stops.windows(2) {
+        functions.push(format!("<< /FunctionType 2 /Domain [0 1] /C0 {} /C1 {} /N 1 >>",
+                               color(&pair[0].color), color(&pair[1].color)));
+        bounds.push(pair[1].offset.to_string());
+    }
+    bounds.pop();
+    let encode = vec!["0 1"; functions.len()];
+    Some(format!("<< /FunctionType 3 /Domain [{} {}] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+                 first.offset, last.offset, functions.join(" "), bounds.join(" "), encode.join(" ")))
+}
+
+/// Fills the tiles of a gradient with a shading, whose coordinates are relative to the origin
+/// of each tile once `transform` is applied.
+fn fill_gradient_tiles(page: &mut PageContent,
+                       bounds: &LayoutRect,
+                       tile: LayoutSize,
+                       tile_spacing: LayoutSize,
+                       stops: &[GradientStop],
+                       shading: &str,
+                       transform: &str) {
+    let function = match gradient_function(stops) {
+        Some(function) => function,
+        None => return,
+    };
+    let index = page.shadings.len();
+    page.shadings.push(format!("<< {} /ColorSpace /DeviceRGB /Function {} /Extend [true true] >>",
+                               shading, function));
+    for origin in tile_origins(bounds, tile, tile_spacing) {
+        let _ = write!(page.ops, "q 1 0 0 1 {} {} cm 0 0 {} {} re W n {} /Sh{} sh Q\n",
+                       origin.x, origin.y, tile.width, tile.height, transform, index);
+    }
+}
+
+/// Strokes a dashed or dotted line of the given width from `from` to `to`.
+fn stroke_dashed(page: &mut PageContent, from: (f32, f32), to: (f32, f32), width: f32, color: ColorF, dotted: bool) {
+    set_color(page, color, "RG");
+    if dotted {
+        // Dashes of length zero with round caps are drawn as dots.
+        let _ = write!(page.ops, "{} w 1 J [0 {}] 0 d\n", width, width * 2.);
+    } else {
+        let _ = write!(page.ops, "{} w 0 J [{} {}] 0 d\n", width, width * 3., width * 3.);
+    }
+    let _ = write!(page.ops, "{} {} m {} {} l S\n", from.0, from.1, to.0, to.1);
+}
+
+fn set_fill_color(page: &mut PageContent, color: ColorF) {
+    set_color(page, color, "rg")
+}
+
+/// Sets the fill or stroke color with the given operator, and the transparency of both.
+fn set_color(page: &mut PageContent, color: ColorF, operator: &str) {
+    let _ = write!(page.ops, "{} {} {} {}\n", color.r, color.g, color.b, operator);
+    if color.a < 1. {
+        let index = match page.alphas.iter().position(|&alpha| alpha == color.a) {
+            Some(index) => index,
+            None => {
+                page.alphas.push(color.a);
+                page.alphas.len() - 1
+            },
+        };
+        let _ = write!(page.ops, "/GS{} gs\n", index);
+    }
+}
+
+fn fill_rect(page: &mut PageContent, rect: &LayoutRect, color: ColorF) {
+    set_fill_color(page, color);
+    let _ = write!(page.ops, "{} {} {} {} re f\n",
+                   rect.origin.x, rect.origin.y, rect.size.width, rect.size.height);
+}
@@ -594,7 +594,7 @@ impl FlexFlow {
                 JustifyContent::Center | JustifyContent::SpaceAround => {
                     cur_i += (line.free_space - item_interval * (item_count - 1)) / 2;
                 }
-                JustifyContent::FlexEnd => {
+                JustifyContent::FlexEnd | JustifyContent::End => {
                     cur_i += line.free_space;
                 }
                 _ => {}
@@ -728,7 +728,7 @@ impl FlexFlow {
                 AlignContent::Center | AlignContent::SpaceAround => {
                     cur_b += (free_space - line_interval * (line_count - 1)) / 2;
                 }
-                AlignContent::FlexEnd => {
+                AlignContent::FlexEnd | AlignContent::End => {
                     cur_b += free_space;
                 }
                 _ => {}
@@ -784,7 +784,7 @@ impl FlexFlow {
                 // TODO(stshine): support baseline alignment.
                 if free_space != Au(0) {
                     let flex_cross = match self_align {
-                        AlignSelf::FlexEnd => free_space,
+                        AlignSelf::FlexEnd | AlignSelf::End => free_space,
                         AlignSelf::Center => free_space / 2,
                         _ => Au(0),
                     };
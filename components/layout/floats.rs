@@ -6,8 +6,10 @@ use app_units::{Au, MAX_AU};
 use block::FormattingContextType;
 use flow::{Flow, FlowFlags, GetBaseFlow, ImmutableFlowUtils};
 use persistent_list::PersistentList;
+use shape_outside::ShapeOutline;
 use std::cmp::{max, min};
 use std::fmt;
+use std::sync::Arc;
 use style::computed_values::float::T as StyleFloat;
 use style::logical_geometry::{LogicalRect, LogicalSize, WritingMode};
 use style::values::computed::LengthOrPercentageOrAuto;
@@ -37,13 +39,25 @@ pub enum ClearType {
     Both,
 }
 
+/// Which area of a float other content must avoid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FloatExclusion {
+    /// The margin box, which is what other floats and block formatting contexts avoid.
+    MarginBox,
+    /// The float area defined by `shape-outside`, which is what line boxes avoid.
+    FloatArea,
+}
+
 /// Information about a single float.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct Float {
     /// The boundaries of this float.
     bounds: LogicalRect<Au>,
     /// The kind of float: left or right.
     kind: FloatKind,
+    /// The float area from `shape-outside`, relative to `bounds`. If this is `None`, the float
+    /// area is the margin box.
+    shape_outline: Option<Arc<ShapeOutline>>,
 }
 
 impl fmt::Debug for Float {
@@ -95,7 +109,9 @@ pub struct PlacementInfo {
     /// The maximum inline-end position of the float, generally determined by the containing block.
     pub max_inline_size: Au,
     /// The kind of float.
-    pub kind: FloatKind
+    pub kind: FloatKind,
+    /// The float area from `shape-outside`, if any.
+    pub shape_outline: Option<Arc<ShapeOutline>>,
 }
 
 impl fmt::Debug for PlacementInfo {
@@ -164,6 +180,18 @@ impl Floats {
     /// inline-size.)
     pub fn available_rect(&self, block_start: Au, block_size: Au, max_x: Au)
                           -> Option<LogicalRect<Au>> {
+        self.available_rect_avoiding(block_start, block_size, max_x, FloatExclusion::MarginBox)
+    }
+
+    /// Like `available_rect`, but avoids the given area of each float. When avoiding float areas,
+    /// the returned rectangle of a shaped float only covers the block range that was asked for,
+    /// since the shape may be wider or narrower elsewhere.
+    fn available_rect_avoiding(&self,
+                               block_start: Au,
+                               block_size: Au,
+                               max_x: Au,
+                               exclusion: FloatExclusion)
+                               -> Option<LogicalRect<Au>> {
         let list = &self.list;
         let block_start = block_start - self.offset.block;
 
@@ -185,26 +213,51 @@ impl Floats {
             let float_size = float.bounds.size;
 
             debug!("float_pos: {:?}, float_size: {:?}", float_pos, float_size);
+            if float_pos.b + float_size.block <= block_start ||
+                    float_pos.b >= block_start + block_size {
+                continue
+            }
+
+            // The inline and block ranges of the area of the float that we must avoid.
+            let (float_inline_start, float_inline_end, float_block_start, float_block_end) =
+                match float.shape_outline {
+                    Some(ref outline) if exclusion == FloatExclusion::FloatArea => {
+                        let band_start = block_start - float_pos.b;
+                        let band_end = band_start + max(block_size, Au::from_px(1));
+                        match outline.extent(band_start, band_end) {
+                            Some((start, end)) => {
+                                (float_pos.i + start,
+                                 float_pos.i + end,
+                                 float_pos.b + max(band_start, Au(0)),
+                                 float_pos.b + min(band_end, float_size.block))
+                            }
+                            None => continue,
+                        }
+                    }
+                    _ => {
+                        (float_pos.i,
+                         float_pos.i + float_size.inline,
+                         float_pos.b,
+                         float_pos.b + float_size.block)
+                    }
+                };
+
             match float.kind {
-                FloatKind::Left if float_pos.i + float_size.inline > max_inline_start &&
-                        float_pos.b + float_size.block > block_start &&
-                        float_pos.b < block_start + block_size => {
-                    max_inline_start = float_pos.i + float_size.inline;
+                FloatKind::Left if float_inline_end > max_inline_start => {
+                    max_inline_start = float_inline_end;
 
-                    l_block_start = Some(float_pos.b);
-                    l_block_end = Some(float_pos.b + float_size.block);
+                    l_block_start = Some(float_block_start);
+                    l_block_end = Some(float_block_end);
 
                     debug!("available_rect: collision with inline_start float: new \
                             max_inline_start is {:?}",
                            max_inline_start);
                 }
-                FloatKind::Right if float_pos.i < min_inline_end &&
-                       float_pos.b + float_size.block > block_start &&
-                       float_pos.b < block_start + block_size => {
-                    min_inline_end = float_pos.i;
+                FloatKind::Right if float_inline_start < min_inline_end => {
+                    min_inline_end = float_inline_start;
 
-                    r_block_start = Some(float_pos.b);
-                    r_block_end = Some(float_pos.b + float_size.block);
+                    r_block_start = Some(float_block_start);
+                    r_block_end = Some(float_block_end);
                     debug!("available_rect: collision with inline_end float: new min_inline_end \
                             is {:?}",
                             min_inline_end);
@@ -260,7 +313,8 @@ impl Floats {
                 Some(max_block_start) => max(info.ceiling, max_block_start + self.offset.block),
             },
             max_inline_size: info.max_inline_size,
-            kind: info.kind
+            kind: info.kind,
+            shape_outline: None,
         };
 
         debug!("add_float: added float with info {:?}", new_info);
@@ -271,20 +325,25 @@ impl Floats {
                 self.place_between_floats(&new_info).start - self.offset,
                 info.size,
             ),
-            kind: info.kind
+            kind: info.kind,
+            shape_outline: info.shape_outline.clone(),
         };
 
-        self.list.floats = self.list.floats.prepend_elem(new_float);
         self.list.max_block_start = match self.list.max_block_start {
             None => Some(new_float.bounds.start.b),
             Some(max_block_start) => Some(max(max_block_start, new_float.bounds.start.b)),
-        }
+        };
+        self.list.floats = self.list.floats.prepend_elem(new_float);
     }
 
     /// Given the three sides of the bounding rectangle in the block-start direction, finds the
     /// largest block-size that will result in the rectangle not colliding with any floats. Returns
     /// `None` if that block-size is infinite.
-    fn max_block_size_for_bounds(&self, inline_start: Au, block_start: Au, inline_size: Au)
+    fn max_block_size_for_bounds(&self,
+                                 inline_start: Au,
+                                 block_start: Au,
+                                 inline_size: Au,
+                                 exclusion: FloatExclusion)
                                  -> Option<Au> {
         let list = &self.list;
 
@@ -296,7 +355,19 @@ impl Floats {
             if float.bounds.start.b + float.bounds.size.block > block_start &&
                    float.bounds.start.i + float.bounds.size.inline > inline_start &&
                    float.bounds.start.i < inline_start + inline_size {
-               let new_y = float.bounds.start.b;
+               let new_y = match float.shape_outline {
+                   Some(ref outline) if exclusion == FloatExclusion::FloatArea => {
+                       let row = outline.first_intersecting_row(
+                           block_start - float.bounds.start.b,
+                           inline_start - float.bounds.start.i,
+                           inline_start + inline_size - float.bounds.start.i);
+                       match row {
+                           Some(row) => float.bounds.start.b + row,
+                           None => continue,
+                       }
+                   }
+                   _ => float.bounds.start.b,
+               };
                max_block_size = Some(min(max_block_size.unwrap_or(new_y), new_y));
             }
         }
@@ -307,6 +378,16 @@ impl Floats {
     /// Given placement information, finds the closest place a fragment can be positioned without
     /// colliding with any floats.
     pub fn place_between_floats(&self, info: &PlacementInfo) -> LogicalRect<Au> {
+        self.place_avoiding(info, FloatExclusion::MarginBox)
+    }
+
+    /// Like `place_between_floats`, but for a line box, which avoids the float areas defined by
+    /// `shape-outside` rather than the margin boxes of the floats.
+    pub fn place_line_between_floats(&self, info: &PlacementInfo) -> LogicalRect<Au> {
+        self.place_avoiding(info, FloatExclusion::FloatArea)
+    }
+
+    fn place_avoiding(&self, info: &PlacementInfo, exclusion: FloatExclusion) -> LogicalRect<Au> {
         debug!("place_between_floats: Placing object with {:?}", info.size);
 
         // If no floats, use this fast path.
@@ -334,9 +415,10 @@ impl Floats {
         // Can't go any higher than previous floats or previous elements in the document.
         let mut float_b = info.ceiling;
         loop {
-            let maybe_location = self.available_rect(float_b,
-                                                     info.size.block,
-                                                     info.max_inline_size);
+            let maybe_location = self.available_rect_avoiding(float_b,
+                                                              info.size.block,
+                                                              info.max_inline_size,
+                                                              exclusion);
             debug!("place_float: got available rect: {:?} for block-pos: {:?}",
                    maybe_location,
                    float_b);
@@ -371,7 +453,8 @@ impl Floats {
                     if rect.size.inline >= info.size.inline {
                         let block_size = self.max_block_size_for_bounds(rect.start.i,
                                                                         rect.start.b,
-                                                                        rect.size.inline);
+                                                                        rect.size.inline,
+                                                                        exclusion);
                         let block_size = block_size.unwrap_or(MAX_AU);
                         return match info.kind {
                             FloatKind::Left => {
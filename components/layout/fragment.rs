@@ -18,7 +18,8 @@ use flow::{GetBaseFlow, ImmutableFlowUtils};
 use flow_ref::FlowRef;
 use gfx;
 use gfx::text::glyph::ByteIndex;
-use gfx::text::text_run::{TextRun, TextRunSlice};
+use gfx::text::hyphenation;
+use gfx::text::text_run::{SOFT_HYPHEN, TextRun, TextRunSlice};
 use gfx_traits::StackingContextId;
 use inline::{InlineFragmentNodeFlags, InlineFragmentContext, InlineFragmentNodeInfo};
 use inline::{InlineMetrics, LineMetrics};
@@ -46,10 +47,12 @@ use style::computed_values::box_sizing::T as BoxSizing;
 use style::computed_values::clear::T as Clear;
 use style::computed_values::color::T as Color;
 use style::computed_values::display::T as Display;
+use style::computed_values::hyphens::T as Hyphens;
 use style::computed_values::mix_blend_mode::T as MixBlendMode;
 use style::computed_values::overflow_wrap::T as OverflowWrap;
 use style::computed_values::overflow_x::T as StyleOverflow;
 use style::computed_values::position::T as Position;
+use style::computed_values::text_combine_upright::T as TextCombineUpright;
 use style::computed_values::text_decoration_line::T as TextDecorationLine;
 use style::computed_values::transform_style::T as TransformStyle;
 use style::computed_values::white_space::T as WhiteSpace;
@@ -491,6 +494,14 @@ bitflags! {
         ///
         /// This handles cases like Foo<span>bar</span>
         const SUPPRESS_LINE_BREAK_BEFORE = 0x04;
+
+        /// Whether the line breaks within a word at the end of this fragment, in which case the
+        /// hyphenate character of the text run is shown after it.
+        const HYPHENATED = 0x08;
+
+        /// Whether the text is waiting for a web font in the block period of its `font-display`,
+        /// in which case it is laid out with a fallback font but not painted.
+        const BLOCKED_BY_WEB_FONT = 0x10;
     }
 }
 
@@ -523,6 +534,14 @@ impl ScannedTextFragmentInfo {
     pub fn selected(&self) -> bool {
         self.flags.contains(ScannedTextFlags::SELECTED)
     }
+
+    pub fn hyphenated(&self) -> bool {
+        self.flags.contains(ScannedTextFlags::HYPHENATED)
+    }
+
+    pub fn blocked_by_web_font(&self) -> bool {
+        self.flags.contains(ScannedTextFlags::BLOCKED_BY_WEB_FONT)
+    }
 }
 
 /// Describes how to split a fragment. This is used during line breaking as part of the return
@@ -533,6 +552,8 @@ pub struct SplitInfo {
     // currently needed for splitting in the `inline::try_append_*` functions.
     pub range: Range<ByteIndex>,
     pub inline_size: Au,
+    /// Whether this split ends within a word, so that the hyphenate character follows it.
+    pub hyphenated: bool,
 }
 
 impl SplitInfo {
@@ -541,6 +562,18 @@ impl SplitInfo {
         SplitInfo {
             range: range,
             inline_size: inline_size,
+            hyphenated: false,
+        }
+    }
+
+    /// Creates a split ending at a hyphenation opportunity. Its inline-size includes the
+    /// hyphenate character.
+    fn hyphenated(range: Range<ByteIndex>, info: &ScannedTextFragmentInfo) -> SplitInfo {
+        let inline_size = info.run.advance_for_range(&range) + info.run.hyphen_advance();
+        SplitInfo {
+            range: range,
+            inline_size: inline_size,
+            hyphenated: true,
         }
     }
 }
@@ -751,6 +784,7 @@ impl Fragment {
         if !first {
             flags.set(ScannedTextFlags::SUPPRESS_LINE_BREAK_BEFORE, false);
         }
+        flags.set(ScannedTextFlags::HYPHENATED, split.hyphenated);
 
         let info = Box::new(ScannedTextFragmentInfo::new(
             text_run,
@@ -1641,6 +1675,10 @@ impl Fragment {
 
         match self.style().get_inherited_text().word_break {
             WordBreak::Normal | WordBreak::KeepAll => {
+                if text_fragment_info.run.hyphen.is_some() {
+                    flags.insert(SplitOptions::HYPHENATE)
+                }
+
                 // Break at normal word boundaries. keep-all forbids soft wrap opportunities.
                 let natural_word_breaking_strategy =
                     text_fragment_info.run.natural_word_slices_in_range(&text_fragment_info.range);
@@ -1683,7 +1721,7 @@ impl Fragment {
         }
         let info = self.calculate_truncate_to_inline_size(max_inline_size);
         let (size, text_info) = match info {
-            Some(TruncationResult { split: SplitInfo { inline_size, range }, text_run } ) => {
+            Some(TruncationResult { split: SplitInfo { inline_size, range, .. }, text_run } ) => {
                 let size = LogicalSize::new(self.style.writing_mode,
                                             inline_size,
                                             self.border_box.size.block);
@@ -1762,6 +1800,7 @@ impl Fragment {
         let mut inline_start_range = Range::new(text_fragment_info.range.begin(), ByteIndex(0));
         let mut inline_end_range = None;
         let mut overflowing = false;
+        let mut hyphenated = false;
 
         debug!("calculate_split_position_using_breaking_strategy: splitting text fragment \
                 (strlen={}, range={:?}, max_inline_size={:?})",
@@ -1790,8 +1829,31 @@ impl Fragment {
                 continue
             }
 
-            // The advance is more than the remaining inline-size, so split here. First, check to
-            // see if we're going to overflow the line. If so, perform a best-effort split.
+            // The advance is more than the remaining inline-size. If the word may be hyphenated,
+            // break it at the last hyphenation opportunity that leaves room for the hyphen.
+            if flags.contains(SplitOptions::HYPHENATE) {
+                let run = &text_fragment_info.run;
+                let slice_range = slice.text_run_range();
+                let hyphen_advance = run.hyphen_advance();
+                let opportunities = self.hyphenation_opportunities(run, &slice_range);
+                let hyphenation_point = opportunities.into_iter().rev().find(|&point| {
+                    let range = Range::new(slice_range.begin(), point - slice_range.begin());
+                    run.advance_for_range(&range) + hyphen_advance <= remaining_inline_size
+                });
+                if let Some(hyphenation_point) = hyphenation_point {
+                    debug!("calculate_split_position_using_breaking_strategy: hyphenating at {:?}",
+                           hyphenation_point);
+                    inline_start_range.extend_to(hyphenation_point);
+                    let mut inline_end = Range::new(hyphenation_point, ByteIndex(0));
+                    inline_end.extend_to(text_fragment_info.range.end());
+                    inline_end_range = Some(inline_end);
+                    hyphenated = true;
+                    break
+                }
+            }
+
+            // Otherwise, split before this slice. First, check to see if we're going to overflow
+            // the line. If so, perform a best-effort split.
             let mut remaining_range = slice.text_run_range();
             let split_is_empty = inline_start_range.is_empty() &&
                     !(self.requires_line_break_afterward_if_wrapping_on_newlines() &&
@@ -1845,7 +1907,9 @@ impl Fragment {
 
         let split_is_empty = inline_start_range.is_empty() &&
                 !self.requires_line_break_afterward_if_wrapping_on_newlines();
-        let inline_start = if !split_is_empty {
+        let inline_start = if hyphenated {
+            Some(SplitInfo::hyphenated(inline_start_range, &**text_fragment_info))
+        } else if !split_is_empty {
             Some(SplitInfo::new(inline_start_range, &**text_fragment_info))
         } else {
             None
@@ -1861,6 +1925,48 @@ impl Fragment {
         })
     }
 
+    /// Returns the positions within the text run at which the word in `word_range` may be
+    /// hyphenated according to `hyphens`, in ascending order.
+    fn hyphenation_opportunities(&self, run: &TextRun, word_range: &Range<ByteIndex>)
+                                 -> Vec<ByteIndex> {
+        let word_start = word_range.begin().to_usize();
+        let word = &run.text[word_start..word_range.end().to_usize()];
+        let to_run_index = |offset: usize| ByteIndex((word_start + offset) as isize);
+
+        // Soft hyphens are hyphenation opportunities unless `hyphens` is `none`. Automatic
+        // hyphenation opportunities are ignored in words that contain them.
+        if word.contains(SOFT_HYPHEN) {
+            return word.match_indices(SOFT_HYPHEN)
+                       .map(|(offset, _)| offset + SOFT_HYPHEN.len_utf8())
+                       .filter(|&offset| offset < word.len())
+                       .map(to_run_index)
+                       .collect()
+        }
+
+        if self.style().get_inherited_text().hyphens != Hyphens::Auto {
+            return vec![]
+        }
+        let language = &self.style().get_font()._x_lang.0;
+        let hyphenator = match hyphenation::hyphenator_for_language(language) {
+            Some(hyphenator) => hyphenator,
+            None => return vec![],
+        };
+
+        // Only hyphenate the letters of the word, not any punctuation around it.
+        let letters_start = word.find(char::is_alphabetic).unwrap_or(word.len());
+        let letters_end = word.char_indices()
+                              .rev()
+                              .find(|&(_, c)| c.is_alphabetic())
+                              .map_or(letters_start, |(offset, c)| offset + c.len_utf8());
+        if letters_start >= letters_end {
+            return vec![]
+        }
+        hyphenator.hyphenation_points(&word[letters_start..letters_end])
+                  .into_iter()
+                  .map(|offset| to_run_index(letters_start + offset))
+                  .collect()
+    }
+
     /// The opposite of `calculate_split_position_using_breaking_strategy`: merges this fragment
     /// with the next one.
     pub fn merge_with(&mut self, next_fragment: Fragment) {
@@ -1868,6 +1974,7 @@ impl Fragment {
             (&mut SpecificFragmentInfo::ScannedText(ref mut this_info),
              &SpecificFragmentInfo::ScannedText(ref other_info)) => {
                 debug_assert!(Arc::ptr_eq(&this_info.run, &other_info.run));
+                this_info.flags.remove(ScannedTextFlags::HYPHENATED);
                 this_info.range_end_including_stripped_whitespace =
                     other_info.range_end_including_stripped_whitespace;
                 if other_info.requires_line_break_afterward_if_wrapping_on_newlines() {
@@ -1889,8 +1996,10 @@ impl Fragment {
     /// if necessary.
     pub fn reset_text_range_and_inline_size(&mut self) {
         if let SpecificFragmentInfo::ScannedText(ref mut info) = self.specific {
-            if info.run.extra_word_spacing != Au(0) {
-                Arc::make_mut(&mut info.run).extra_word_spacing = Au(0);
+            if info.run.extra_word_spacing != Au(0) || info.run.extra_letter_spacing != Au(0) {
+                let run = Arc::make_mut(&mut info.run);
+                run.extra_word_spacing = Au(0);
+                run.extra_letter_spacing = Au(0);
             }
 
             // FIXME (mbrubeck): Do we need to restore leading too?
@@ -2151,7 +2260,9 @@ impl Fragment {
                 text::font_metrics_for_style(font_context, self_.style.clone_font())
             });
             let line_height = text::line_height_from_style(&*self_.style, &font_metrics);
-            InlineMetrics::from_font_metrics(&info.run.font_metrics, line_height)
+            InlineMetrics::from_font_metrics(&info.run.font_metrics,
+                                             line_height,
+                                             self_.style.writing_mode)
         }
 
         fn inline_metrics_of_block(flow: &FlowRef, style: &ComputedValues) -> InlineMetrics {
@@ -2556,7 +2667,7 @@ impl Fragment {
         }
 
         match self.style().get_box().display {
-            Display::Flex => self.style().get_position().z_index.integer_or(0),
+            Display::Flex | Display::Grid => self.style().get_position().z_index.integer_or(0),
             _ => 0,
         }
     }
@@ -2841,6 +2952,13 @@ impl Fragment {
         }
     }
 
+    /// Returns true if the text of this fragment is combined into a single upright character cell
+    /// of a vertical line, per `text-combine-upright: all`.
+    pub fn combines_text_upright(&self) -> bool {
+        self.style.writing_mode.is_vertical() &&
+            self.style.get_inherited_text().text_combine_upright == TextCombineUpright::All
+    }
+
     /// Returns true if any of the inline styles associated with this fragment have
     /// `vertical-align` set to `top` or `bottom`.
     pub fn is_vertically_aligned_to_top_or_bottom(&self) -> bool {
@@ -2995,6 +3113,8 @@ bitflags! {
         #[doc = "True if we should attempt to split at character boundaries if this split fails. \
                  This is used to implement `overflow-wrap: break-word`."]
         const RETRY_AT_CHARACTER_BOUNDARIES = 0x02;
+        #[doc = "True if words that don't fit may be hyphenated, per the `hyphens` property."]
+        const HYPHENATE = 0x04;
     }
 }
 
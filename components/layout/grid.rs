@@ -0,0 +1,1553 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Layout for elements with a CSS `display` property of `grid`.
+
+#![deny(unsafe_code)]
+
+use ServoArc;
+use app_units::Au;
+use block::{AbsoluteAssignBSizesTraversal, BlockFlow, CandidateBSizeIterator};
+use context::LayoutContext;
+use display_list::{DisplayListBuildState, GridFlowDisplayListBuilding};
+use display_list::StackingContextCollectionState;
+use euclid::Point2D;
+use floats::FloatKind;
+use flow::{Flow, FlowClass, FlowFlags, GetBaseFlow, OpaqueFlow};
+use fragment::{Fragment, FragmentBorderBoxIterator, Overflow};
+use layout_debug;
+use model::{AdjoiningMargins, CollapsibleMargins, MaybeAuto};
+use servo_atoms::Atom;
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+use style::computed_values::align_content::T as AlignContent;
+use style::computed_values::align_self::T as AlignSelf;
+use style::computed_values::justify_content::T as JustifyContent;
+use style::computed_values::justify_self::T as JustifySelf;
+use style::logical_geometry::{Direction, LogicalSize};
+use style::properties::ComputedValues;
+use style::servo::restyle_damage::ServoRestyleDamage;
+use style::values::Either;
+use style::values::computed::{GridLine, GridTemplateComponent, LengthOrPercentage};
+use style::values::computed::{LengthOrPercentageOrAuto, TrackBreadth, TrackSize};
+use style::values::computed::length::NonNegativeLengthOrPercentageOrNormal;
+use style::values::generics::grid::{GridTemplateComponent as GenericGridTemplateComponent, RepeatCount};
+use style::values::generics::grid::{TrackBreadth as GenericTrackBreadth, TrackKeyword, TrackListType};
+use style::values::generics::grid::{TrackListValue, TrackSize as GenericTrackSize};
+use style::values::specified::position::AutoFlow;
+use traversal::PreorderFlowTraversal;
+
+/// The largest line number a grid item may be placed at, in either direction. Like other
+/// engines we clamp line numbers so that absurd placements don't create millions of tracks.
+const MAX_GRID_LINE: i32 = 10000;
+
+/// Splits a `<length-percentage>` into its length and unresolved percentage parts.
+fn length_and_percentage(value: LengthOrPercentage) -> (Au, Option<f32>) {
+    match value {
+        LengthOrPercentage::Length(length) => (Au::from(length), None),
+        LengthOrPercentage::Percentage(percentage) => (Au(0), Some(percentage.0)),
+        LengthOrPercentage::Calc(calc) => {
+            (Au::from(calc.unclamped_length()), calc.percentage.map(|percentage| percentage.0))
+        }
+    }
+}
+
+/// Resolves a length and percentage pair against `basis`. Percentages of an indefinite size
+/// cannot be resolved.
+fn resolve_length(length: Au, percentage: Option<f32>, basis: Option<Au>) -> Option<Au> {
+    match (percentage, basis) {
+        (None, _) => Some(length),
+        (Some(percentage), Some(basis)) => Some(length + basis.scale_by(percentage)),
+        (Some(_), None) => None,
+    }
+}
+
+/// Resolves the used value of `row-gap` or `column-gap`. Percentages of an indefinite size
+/// resolve to zero.
+fn resolve_gap(gap: &NonNegativeLengthOrPercentageOrNormal, basis: Option<Au>) -> Au {
+    match *gap {
+        Either::First(ref gap) => {
+            let (length, percentage) = length_and_percentage(gap.0);
+            resolve_length(length, percentage, basis).unwrap_or(length)
+        }
+        Either::Second(_) => Au(0),
+    }
+}
+
+/// One end of a track sizing function.
+#[derive(Clone, Copy, Debug, Serialize)]
+enum Breadth {
+    /// A `<length-percentage>`. The percentage is kept unresolved since the size of the grid
+    /// container may not be known yet.
+    Fixed(Au, Option<f32>),
+    /// A flexible breadth, in `fr` units.
+    Flex(f32),
+    Auto,
+    MinContent,
+    MaxContent,
+    /// `fit-content(<length-percentage>)`, which is only valid as a maximum.
+    FitContent(Au, Option<f32>),
+}
+
+impl Breadth {
+    fn new(breadth: &TrackBreadth) -> Breadth {
+        match *breadth {
+            GenericTrackBreadth::Breadth(value) => {
+                let (length, percentage) = length_and_percentage(value);
+                Breadth::Fixed(length, percentage)
+            }
+            GenericTrackBreadth::Fr(factor) => Breadth::Flex(factor),
+            GenericTrackBreadth::Keyword(TrackKeyword::Auto) => Breadth::Auto,
+            GenericTrackBreadth::Keyword(TrackKeyword::MinContent) => Breadth::MinContent,
+            GenericTrackBreadth::Keyword(TrackKeyword::MaxContent) => Breadth::MaxContent,
+        }
+    }
+
+    /// Returns the size of a fixed breadth, or `None` if this breadth is intrinsic or flexible
+    /// or a percentage of an indefinite size.
+    fn fixed(&self, basis: Option<Au>) -> Option<Au> {
+        match *self {
+            Breadth::Fixed(length, percentage) => resolve_length(length, percentage, basis),
+            _ => None,
+        }
+    }
+}
+
+/// The sizing function of a grid track.
+///
+/// https://drafts.csswg.org/css-grid/#track-sizing-function
+#[derive(Clone, Copy, Debug, Serialize)]
+struct TrackSizingFunction {
+    min: Breadth,
+    max: Breadth,
+}
+
+impl TrackSizingFunction {
+    fn new(size: &TrackSize) -> TrackSizingFunction {
+        // A flexible minimum is treated as `auto`.
+        fn minimum(breadth: &TrackBreadth) -> Breadth {
+            match Breadth::new(breadth) {
+                Breadth::Flex(_) => Breadth::Auto,
+                other => other,
+            }
+        }
+
+        match *size {
+            GenericTrackSize::Breadth(ref breadth) => {
+                TrackSizingFunction {
+                    min: minimum(breadth),
+                    max: Breadth::new(breadth),
+                }
+            }
+            GenericTrackSize::Minmax(ref min, ref max) => {
+                TrackSizingFunction {
+                    min: minimum(min),
+                    max: Breadth::new(max),
+                }
+            }
+            GenericTrackSize::FitContent(value) => {
+                let (length, percentage) = length_and_percentage(value);
+                TrackSizingFunction {
+                    min: Breadth::Auto,
+                    max: Breadth::FitContent(length, percentage),
+                }
+            }
+        }
+    }
+
+    fn flex_factor(&self) -> Option<f32> {
+        match self.max {
+            Breadth::Flex(factor) => Some(factor),
+            _ => None,
+        }
+    }
+
+    fn has_intrinsic_min(&self, basis: Option<Au>) -> bool {
+        self.min.fixed(basis).is_none()
+    }
+
+    fn has_intrinsic_max(&self, basis: Option<Au>) -> bool {
+        self.max.fixed(basis).is_none() && self.flex_factor().is_none()
+    }
+
+    /// The size used for this track when computing the number of `auto-fill` and `auto-fit`
+    /// repetitions.
+    ///
+    /// https://drafts.csswg.org/css-grid/#auto-repeat
+    fn repetition_size(&self, basis: Option<Au>) -> Au {
+        match (self.min.fixed(basis), self.max.fixed(basis)) {
+            (Some(min_size), Some(max_size)) => max(min_size, max_size),
+            (min_size, max_size) => max_size.or(min_size).unwrap_or(Au(0)),
+        }
+    }
+}
+
+/// A row or column of the grid.
+#[derive(Clone, Debug, Serialize)]
+struct GridTrack {
+    /// The sizing function of this track.
+    sizing: TrackSizingFunction,
+    /// The base size of the track, which is its used size once track sizing is done.
+    base_size: Au,
+    /// The growth limit of the track, or `None` if it is infinite.
+    growth_limit: Option<Au>,
+    /// The offset of the start of the track from the content edge of the grid container.
+    position: Au,
+    /// True if this is an empty `auto-fit` track, which collapses along with its gutters.
+    collapsed: bool,
+}
+
+impl GridTrack {
+    fn new(sizing: TrackSizingFunction) -> GridTrack {
+        GridTrack {
+            sizing: sizing,
+            base_size: Au(0),
+            growth_limit: None,
+            position: Au(0),
+            collapsed: false,
+        }
+    }
+
+    /// Sizes the track to fit an item that spans only this track.
+    ///
+    /// https://drafts.csswg.org/css-grid/#algo-single-span-items
+    fn accommodate(&mut self, contribution: &Contribution, basis: Option<Au>) {
+        if self.sizing.has_intrinsic_min(basis) {
+            let size = match self.sizing.min {
+                Breadth::MaxContent => contribution.max_content,
+                _ => contribution.min_content,
+            };
+            self.base_size = max(self.base_size, size);
+        }
+
+        if self.sizing.has_intrinsic_max(basis) {
+            let limit = match self.sizing.max {
+                Breadth::MinContent => contribution.min_content,
+                Breadth::FitContent(length, percentage) => {
+                    let clamp = resolve_length(length, percentage, basis).unwrap_or(contribution.max_content);
+                    min(contribution.max_content, clamp)
+                }
+                _ => contribution.max_content,
+            };
+            self.growth_limit = Some(max(self.growth_limit.unwrap_or(Au(0)), limit));
+        }
+    }
+}
+
+/// The space the tracks of an axis are sized into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AvailableSpace {
+    Definite(Au),
+    MinContent,
+    MaxContent,
+}
+
+impl AvailableSpace {
+    fn definite(&self) -> Option<Au> {
+        match *self {
+            AvailableSpace::Definite(size) => Some(size),
+            _ => None,
+        }
+    }
+}
+
+/// The outer size of a grid item in one axis, and the tracks it spans in that axis.
+struct Contribution {
+    tracks: Range<usize>,
+    min_content: Au,
+    max_content: Au,
+}
+
+/// Returns the total size of `tracks`, including the gutters between them.
+fn used_size(tracks: &[GridTrack], gap: Au) -> Au {
+    let mut size = Au(0);
+    let mut visible = 0;
+    for track in tracks.iter().filter(|track| !track.collapsed) {
+        size += track.base_size;
+        visible += 1;
+    }
+    if visible > 1 {
+        size += gap * (visible - 1);
+    }
+    size
+}
+
+/// Divides `space` equally between `count` tracks.
+fn share(space: Au, count: usize) -> Au {
+    if count == 0 {
+        Au(0)
+    } else {
+        space / count as i32
+    }
+}
+
+/// Increases the sizes of tracks to fit an item that spans several tracks, none of which is
+/// flexible.
+///
+/// https://drafts.csswg.org/css-grid/#algo-spanning-items
+fn accommodate_spanning_item(tracks: &mut [GridTrack], contribution: &Contribution, gap: Au,
+                             basis: Option<Au>) {
+    let range = contribution.tracks.clone();
+    let visible = tracks[range.clone()].iter().filter(|track| !track.collapsed).count();
+    let gaps = gap * max(visible as i32 - 1, 0);
+
+    let base_sizes = tracks[range.clone()].iter().fold(Au(0), |sum, track| sum + track.base_size);
+    let targets: Vec<usize> = range.clone().filter(|&index| {
+        !tracks[index].collapsed && tracks[index].sizing.has_intrinsic_min(basis)
+    }).collect();
+    let extra = share(contribution.min_content - base_sizes - gaps, targets.len());
+    if extra > Au(0) {
+        for &index in &targets {
+            tracks[index].base_size += extra;
+        }
+    }
+
+    let limits = tracks[range.clone()].iter().fold(Au(0), |sum, track| {
+        sum + track.growth_limit.unwrap_or(track.base_size)
+    });
+    let targets: Vec<usize> = range.filter(|&index| {
+        !tracks[index].collapsed && tracks[index].sizing.has_intrinsic_max(basis)
+    }).collect();
+    let extra = share(contribution.max_content - limits - gaps, targets.len());
+    if extra > Au(0) {
+        for &index in &targets {
+            let track = &mut tracks[index];
+            track.growth_limit = Some(track.growth_limit.unwrap_or(track.base_size) + extra);
+        }
+    }
+}
+
+/// Increases the base sizes of the flexible tracks spanned by an item so that they fit its
+/// minimum contribution.
+///
+/// https://drafts.csswg.org/css-grid/#algo-spanning-flex-items
+fn accommodate_item_spanning_flexible_tracks(tracks: &mut [GridTrack], contribution: &Contribution, gap: Au) {
+    let range = contribution.tracks.clone();
+    let visible = tracks[range.clone()].iter().filter(|track| !track.collapsed).count();
+    let gaps = gap * max(visible as i32 - 1, 0);
+    let base_sizes = tracks[range.clone()].iter().fold(Au(0), |sum, track| sum + track.base_size);
+    let targets: Vec<usize> = range.filter(|&index| {
+        !tracks[index].collapsed && tracks[index].sizing.flex_factor().is_some()
+    }).collect();
+    let extra = share(contribution.min_content - base_sizes - gaps, targets.len());
+    if extra > Au(0) {
+        for &index in &targets {
+            tracks[index].base_size += extra;
+        }
+    }
+}
+
+/// Finds the size of an `fr` so that the tracks in `range` fill `space`.
+///
+/// https://drafts.csswg.org/css-grid/#algo-find-fr-size
+fn find_fr_size(tracks: &[GridTrack], range: Range<usize>, space: Au) -> Au {
+    let mut inflexible = vec![false; range.len()];
+    loop {
+        let mut leftover = space;
+        let mut flex_factors = 0.;
+        for (index, track) in tracks[range.clone()].iter().enumerate() {
+            match track.sizing.flex_factor() {
+                Some(factor) if !inflexible[index] && !track.collapsed => flex_factors += factor,
+                _ => leftover -= track.base_size,
+            }
+        }
+
+        let fr_size = if leftover > Au(0) {
+            leftover.scale_by(1. / flex_factors.max(1.))
+        } else {
+            Au(0)
+        };
+
+        let mut done = true;
+        for (index, track) in tracks[range.clone()].iter().enumerate() {
+            if let Some(factor) = track.sizing.flex_factor() {
+                if !inflexible[index] && !track.collapsed && track.base_size > fr_size.scale_by(factor) {
+                    inflexible[index] = true;
+                    done = false;
+                }
+            }
+        }
+        if done {
+            return fr_size;
+        }
+    }
+}
+
+/// Runs the grid track sizing algorithm over the tracks of one axis.
+///
+/// https://drafts.csswg.org/css-grid/#algo-track-sizing
+fn size_tracks(tracks: &mut [GridTrack], contributions: &[Contribution], gap: Au, space: AvailableSpace,
+               stretch: bool) {
+    let basis = space.definite();
+
+    // https://drafts.csswg.org/css-grid/#algo-init
+    for track in tracks.iter_mut() {
+        if track.collapsed {
+            track.base_size = Au(0);
+            track.growth_limit = Some(Au(0));
+            continue;
+        }
+        track.base_size = track.sizing.min.fixed(basis).unwrap_or(Au(0));
+        track.growth_limit = track.sizing.max.fixed(basis).map(|limit| max(limit, track.base_size));
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-content
+    let mut contributions: Vec<&Contribution> =
+        contributions.iter().filter(|contribution| contribution.tracks.len() > 0).collect();
+    contributions.sort_by_key(|contribution| contribution.tracks.len());
+    for contribution in contributions.iter() {
+        let spans_flexible_track = tracks[contribution.tracks.clone()].iter().any(|track| {
+            track.sizing.flex_factor().is_some()
+        });
+        if contribution.tracks.len() == 1 {
+            let track = &mut tracks[contribution.tracks.start];
+            if !track.collapsed {
+                track.accommodate(contribution, basis);
+            }
+        } else if spans_flexible_track {
+            accommodate_item_spanning_flexible_tracks(tracks, contribution, gap);
+        } else {
+            accommodate_spanning_item(tracks, contribution, gap, basis);
+        }
+    }
+    for track in tracks.iter_mut() {
+        let limit = track.growth_limit.unwrap_or(track.base_size);
+        track.growth_limit = Some(max(limit, track.base_size));
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-grow-tracks
+    match space {
+        AvailableSpace::Definite(size) => {
+            let mut free_space = size - used_size(tracks, gap);
+            loop {
+                let growable: Vec<usize> = (0..tracks.len()).filter(|&index| {
+                    tracks[index].growth_limit.unwrap() > tracks[index].base_size
+                }).collect();
+                let extra = share(free_space, growable.len());
+                if extra <= Au(0) {
+                    break;
+                }
+                for &index in &growable {
+                    let track = &mut tracks[index];
+                    let growth = min(extra, track.growth_limit.unwrap() - track.base_size);
+                    track.base_size += growth;
+                    free_space -= growth;
+                }
+            }
+        }
+        AvailableSpace::MaxContent => {
+            for track in tracks.iter_mut() {
+                track.base_size = track.growth_limit.unwrap();
+            }
+        }
+        AvailableSpace::MinContent => {}
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-flex-tracks
+    if tracks.iter().any(|track| !track.collapsed && track.sizing.flex_factor().is_some()) {
+        let fr_size = match space {
+            AvailableSpace::Definite(size) => {
+                let visible = tracks.iter().filter(|track| !track.collapsed).count() as i32;
+                find_fr_size(tracks, 0..tracks.len(), size - gap * max(visible - 1, 0))
+            }
+            AvailableSpace::MinContent | AvailableSpace::MaxContent => {
+                let mut fr_size = Au(0);
+                for track in tracks.iter().filter(|track| !track.collapsed) {
+                    if let Some(factor) = track.sizing.flex_factor() {
+                        let size = if factor > 1. {
+                            track.base_size.scale_by(1. / factor)
+                        } else {
+                            track.base_size
+                        };
+                        fr_size = max(fr_size, size);
+                    }
+                }
+                for contribution in contributions.iter() {
+                    let range = contribution.tracks.clone();
+                    if !tracks[range.clone()].iter().any(|track| track.sizing.flex_factor().is_some()) {
+                        continue;
+                    }
+                    let size = if space == AvailableSpace::MaxContent {
+                        contribution.max_content
+                    } else {
+                        contribution.min_content
+                    };
+                    let visible = tracks[range.clone()].iter().filter(|track| !track.collapsed).count() as i32;
+                    fr_size = max(fr_size, find_fr_size(tracks, range, size - gap * max(visible - 1, 0)));
+                }
+                fr_size
+            }
+        };
+        for track in tracks.iter_mut().filter(|track| !track.collapsed) {
+            if let Some(factor) = track.sizing.flex_factor() {
+                track.base_size = max(track.base_size, fr_size.scale_by(factor));
+            }
+        }
+    }
+
+    // https://drafts.csswg.org/css-grid/#algo-stretch
+    if let (true, Some(size)) = (stretch, basis) {
+        let free_space = size - used_size(tracks, gap);
+        let auto_tracks: Vec<usize> = (0..tracks.len()).filter(|&index| {
+            match tracks[index].sizing.max {
+                Breadth::Auto => !tracks[index].collapsed,
+                _ => false,
+            }
+        }).collect();
+        let extra = share(free_space, auto_tracks.len());
+        if extra > Au(0) {
+            for &index in &auto_tracks {
+                tracks[index].base_size += extra;
+            }
+        }
+    }
+}
+
+/// How free space is distributed between the tracks of an axis.
+///
+/// https://drafts.csswg.org/css-align/#content-distribution
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ContentDistribution {
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    Stretch,
+}
+
+impl ContentDistribution {
+    fn from_justify_content(value: JustifyContent) -> ContentDistribution {
+        match value {
+            JustifyContent::Normal | JustifyContent::Stretch => ContentDistribution::Stretch,
+            JustifyContent::FlexStart | JustifyContent::Start => ContentDistribution::Start,
+            JustifyContent::FlexEnd | JustifyContent::End => ContentDistribution::End,
+            JustifyContent::Center => ContentDistribution::Center,
+            JustifyContent::SpaceBetween => ContentDistribution::SpaceBetween,
+            JustifyContent::SpaceAround => ContentDistribution::SpaceAround,
+        }
+    }
+
+    fn from_align_content(value: AlignContent) -> ContentDistribution {
+        match value {
+            AlignContent::Stretch => ContentDistribution::Stretch,
+            AlignContent::FlexStart | AlignContent::Start => ContentDistribution::Start,
+            AlignContent::FlexEnd | AlignContent::End => ContentDistribution::End,
+            AlignContent::Center => ContentDistribution::Center,
+            AlignContent::SpaceBetween => ContentDistribution::SpaceBetween,
+            AlignContent::SpaceAround => ContentDistribution::SpaceAround,
+        }
+    }
+}
+
+/// Computes the position of each track, distributing the free space of the grid container
+/// according to `distribution`.
+fn position_tracks(tracks: &mut [GridTrack], gap: Au, size: Au, distribution: ContentDistribution) {
+    let visible = tracks.iter().filter(|track| !track.collapsed).count() as i32;
+    let free_space = size - used_size(tracks, gap);
+    let (mut position, spacing) = match distribution {
+        ContentDistribution::End => (free_space, Au(0)),
+        ContentDistribution::Center => (free_space / 2, Au(0)),
+        ContentDistribution::SpaceBetween if free_space > Au(0) && visible > 1 => {
+            (Au(0), free_space / (visible - 1))
+        }
+        ContentDistribution::SpaceAround if free_space > Au(0) && visible > 0 => {
+            (free_space / visible / 2, free_space / visible)
+        }
+        _ => (Au(0), Au(0)),
+    };
+
+    let mut first = true;
+    for track in tracks.iter_mut() {
+        if !track.collapsed {
+            if !first {
+                position += gap + spacing;
+            }
+            first = false;
+        }
+        track.position = position;
+        if !track.collapsed {
+            position += track.base_size;
+        }
+    }
+}
+
+/// Returns the offset and the size of the grid area covering `range`.
+fn area(tracks: &[GridTrack], range: &Range<usize>) -> (Au, Au) {
+    let start = tracks[range.start].position;
+    let last = &tracks[range.end - 1];
+    (start, max(last.position + last.base_size - start, Au(0)))
+}
+
+/// Returns the size of the grid area covering `range` if all its tracks have a fixed size.
+fn definite_area_size(tracks: &[GridTrack], range: &Range<usize>, gap: Au, basis: Option<Au>) -> Option<Au> {
+    let mut size = Au(0);
+    let mut visible = 0;
+    for track in tracks[range.clone()].iter().filter(|track| !track.collapsed) {
+        match (track.sizing.min.fixed(basis), track.sizing.max.fixed(basis)) {
+            (Some(min_size), Some(max_size)) => size += max(min_size, max_size),
+            _ => return None,
+        }
+        visible += 1;
+    }
+    Some(size + gap * max(visible - 1, 0))
+}
+
+/// Returns the number of repetitions of the `auto-fill` or `auto-fit` repeat of `template` that
+/// fit in `size`.
+///
+/// https://drafts.csswg.org/css-grid/#auto-repeat
+fn auto_repetitions(template: &GridTemplateComponent, gap: Au, size: Option<Au>) -> usize {
+    let list = match *template {
+        GenericGridTemplateComponent::TrackList(ref list) => list,
+        _ => return 1,
+    };
+    let (repeat, size) = match (list.auto_repeat.as_ref(), size) {
+        (Some(repeat), Some(size)) => (repeat, size),
+        _ => return 1,
+    };
+
+    let mut fixed_size = Au(0);
+    for value in &list.values {
+        if let TrackListValue::TrackSize(ref track_size) = *value {
+            fixed_size += TrackSizingFunction::new(track_size).repetition_size(Some(size)) + gap;
+        }
+    }
+    let repetition_size = repeat.track_sizes.iter().fold(Au(0), |sum, track_size| {
+        sum + TrackSizingFunction::new(track_size).repetition_size(Some(size)) + gap
+    });
+    if repetition_size <= Au(0) {
+        return 1;
+    }
+
+    // The last track is not followed by a gutter.
+    let repetitions = (size + gap - fixed_size).0 / repetition_size.0;
+    min(max(repetitions, 1), MAX_GRID_LINE / max(repeat.track_sizes.len() as i32, 1)) as usize
+}
+
+/// The line names of the explicit grid along one axis.
+struct GridLines {
+    /// The names of each line, including those implied by `grid-template-areas`.
+    names: Vec<Vec<Atom>>,
+}
+
+/// The resolved value of a `grid-row-*` or `grid-column-*` property.
+enum LinePosition {
+    Auto,
+    /// A line index, where zero is the first line of the explicit grid.
+    Line(i32),
+    Span(i32, Option<Atom>),
+}
+
+/// The placement of an item along one axis.
+#[derive(Clone, Debug)]
+enum Placement {
+    Definite(Range<i32>),
+    /// An automatic placement spanning the given number of tracks.
+    Auto(i32),
+}
+
+impl GridLines {
+    fn track_count(&self) -> i32 {
+        self.names.len() as i32 - 1
+    }
+
+    fn has_name(&self, line: i32, name: &Atom) -> bool {
+        line >= 0 && self.names.get(line as usize).map_or(false, |names| names.contains(name))
+    }
+
+    /// Returns the index of the `n`th line named `name`, counting from the end if `n` is
+    /// negative. Lines outside the explicit grid are all assumed to have that name.
+    fn nth_named_line(&self, name: &Atom, n: i32) -> i32 {
+        let mut count = 0;
+        if n > 0 {
+            for line in 0..(self.track_count() + 1) {
+                if self.has_name(line, name) {
+                    count += 1;
+                    if count == n {
+                        return line;
+                    }
+                }
+            }
+            self.track_count() + n - count
+        } else {
+            for line in (0..(self.track_count() + 1)).rev() {
+                if self.has_name(line, name) {
+                    count += 1;
+                    if count == -n {
+                        return line;
+                    }
+                }
+            }
+            n + count
+        }
+    }
+
+    /// Returns the line `span` lines away from `from` in the given direction, counting only
+    /// lines named `name` if there is one.
+    fn span_from(&self, from: i32, span: i32, name: Option<Atom>, forward: bool) -> i32 {
+        let name = match name {
+            Some(name) => name,
+            None => return if forward { from + span } else { from - span },
+        };
+        let mut count = 0;
+        if forward {
+            for line in (from + 1)..(self.track_count() + 1) {
+                if self.has_name(line, &name) {
+                    count += 1;
+                    if count == span {
+                        return line;
+                    }
+                }
+            }
+            max(from, self.track_count()) + span - count
+        } else {
+            for line in (0..min(from, self.track_count() + 1)).rev() {
+                if self.has_name(line, &name) {
+                    count += 1;
+                    if count == span {
+                        return line;
+                    }
+                }
+            }
+            min(from, 0) - span + count
+        }
+    }
+
+    /// https://drafts.csswg.org/css-grid/#line-placement
+    fn position(&self, line: &GridLine, is_start: bool) -> LinePosition {
+        if line.is_auto() {
+            return LinePosition::Auto;
+        }
+        let number = min(max(line.line_num.unwrap_or(1), -MAX_GRID_LINE), MAX_GRID_LINE);
+        if line.is_span {
+            return LinePosition::Span(max(number, 1), line.ident.as_ref().map(|ident| ident.0.clone()));
+        }
+        let index = match line.ident {
+            None if number > 0 => number - 1,
+            None => self.track_count() + 1 + number,
+            Some(ref ident) => {
+                if line.line_num.is_none() {
+                    let suffix = if is_start { "start" } else { "end" };
+                    let implicit_name = Atom::from(format!("{}-{}", ident.0, suffix));
+                    if let Some(index) = self.names.iter().position(|names| names.contains(&implicit_name)) {
+                        return LinePosition::Line(index as i32);
+                    }
+                }
+                self.nth_named_line(&ident.0, number)
+            }
+        };
+        LinePosition::Line(min(max(index, -MAX_GRID_LINE), MAX_GRID_LINE))
+    }
+
+    /// https://drafts.csswg.org/css-grid/#grid-placement-errors
+    fn placement(&self, start: &GridLine, end: &GridLine) -> Placement {
+        match (self.position(start, true), self.position(end, false)) {
+            (LinePosition::Line(start), LinePosition::Line(end)) => {
+                if start < end {
+                    Placement::Definite(start..end)
+                } else if start > end {
+                    Placement::Definite(end..start)
+                } else {
+                    Placement::Definite(start..(start + 1))
+                }
+            }
+            (LinePosition::Line(start), LinePosition::Auto) => Placement::Definite(start..(start + 1)),
+            (LinePosition::Auto, LinePosition::Line(end)) => Placement::Definite((end - 1)..end),
+            (LinePosition::Line(start), LinePosition::Span(span, name)) => {
+                Placement::Definite(start..self.span_from(start, span, name, true))
+            }
+            (LinePosition::Span(span, name), LinePosition::Line(end)) => {
+                Placement::Definite(self.span_from(end, span, name, false)..end)
+            }
+            (LinePosition::Span(span, None), _) |
+            (LinePosition::Auto, LinePosition::Span(span, None)) => Placement::Auto(span),
+            // An automatic position with a span of named lines spans a single track.
+            (LinePosition::Span(_, Some(_)), _) |
+            (LinePosition::Auto, LinePosition::Span(_, Some(_))) |
+            (LinePosition::Auto, LinePosition::Auto) => Placement::Auto(1),
+        }
+    }
+}
+
+/// Expands a `grid-template-rows` or `grid-template-columns` value into the tracks and line
+/// names of the explicit grid, with the given number of automatic repetitions. Also returns
+/// the range of tracks created by an `auto-fit` repeat, if any.
+fn explicit_tracks(template: &GridTemplateComponent, repetitions: usize)
+                   -> (Vec<TrackSizingFunction>, Vec<Vec<Atom>>, Option<Range<usize>>) {
+    let list = match *template {
+        GenericGridTemplateComponent::TrackList(ref list) => list,
+        GenericGridTemplateComponent::None | GenericGridTemplateComponent::Subgrid(_) => {
+            return (vec![], vec![vec![]], None)
+        }
+    };
+    let auto_index = match list.list_type {
+        TrackListType::Auto(index) => Some(index as usize),
+        _ => None,
+    };
+
+    let mut sizes = vec![];
+    let mut lines = vec![];
+    let mut auto_fit = None;
+    let mut pending = vec![];
+    let mut line_names = list.line_names.iter();
+    for index in 0..(list.values.len() + 1) {
+        if let Some(names) = line_names.next() {
+            pending.extend(names.iter().map(|ident| ident.0.clone()));
+        }
+
+        if let (Some(ref repeat), true) = (list.auto_repeat.as_ref(), auto_index == Some(index)) {
+            let start = sizes.len();
+            for _ in 0..repetitions {
+                for (track_index, size) in repeat.track_sizes.iter().enumerate() {
+                    if let Some(names) = repeat.line_names.get(track_index) {
+                        pending.extend(names.iter().map(|ident| ident.0.clone()));
+                    }
+                    lines.push(mem::replace(&mut pending, vec![]));
+                    sizes.push(TrackSizingFunction::new(size));
+                }
+                if let Some(names) = repeat.line_names.get(repeat.track_sizes.len()) {
+                    pending.extend(names.iter().map(|ident| ident.0.clone()));
+                }
+            }
+            if repeat.count == RepeatCount::AutoFit {
+                auto_fit = Some(start..sizes.len());
+            }
+            if let Some(names) = line_names.next() {
+                pending.extend(names.iter().map(|ident| ident.0.clone()));
+            }
+        }
+
+        // Numeric repeats have already been expanded in the computed value.
+        if let Some(&TrackListValue::TrackSize(ref size)) = list.values.get(index) {
+            lines.push(mem::replace(&mut pending, vec![]));
+            sizes.push(TrackSizingFunction::new(size));
+        }
+    }
+    lines.push(pending);
+    (sizes, lines, auto_fit)
+}
+
+/// Adds the `<name>-start` and `<name>-end` line names implied by a named grid area spanning
+/// `range`, which is in one-based line numbers.
+fn add_area_line_names(lines: &mut Vec<Vec<Atom>>, name: &str, range: &Range<u32>) {
+    let end = range.end as usize;
+    if lines.len() < end {
+        lines.resize(end, vec![]);
+    }
+    lines[range.start as usize - 1].push(Atom::from(format!("{}-start", name)));
+    lines[end - 1].push(Atom::from(format!("{}-end", name)));
+}
+
+/// The cells of the grid occupied by the items placed so far, indexed by track of the axis
+/// the grid grows along during auto-placement and then by track of the other axis.
+struct OccupancyGrid {
+    cells: Vec<Vec<bool>>,
+}
+
+impl OccupancyGrid {
+    fn is_free(&self, major: &Range<usize>, minor: &Range<usize>) -> bool {
+        major.clone().all(|index| match self.cells.get(index) {
+            Some(line) => minor.clone().all(|cell| !line.get(cell).cloned().unwrap_or(false)),
+            None => true,
+        })
+    }
+
+    fn occupy(&mut self, major: &Range<usize>, minor: &Range<usize>) {
+        if self.cells.len() < major.end {
+            self.cells.resize(major.end, vec![]);
+        }
+        for line in &mut self.cells[major.clone()] {
+            if line.len() < minor.end {
+                line.resize(minor.end, false);
+            }
+            for cell in &mut line[minor.clone()] {
+                *cell = true;
+            }
+        }
+    }
+}
+
+/// An item placement along one axis, translated so that the first track of the grid has
+/// index zero.
+enum Slot {
+    Definite(Range<usize>),
+    Auto(usize),
+}
+
+impl Slot {
+    fn new(placement: &Placement, offset: i32) -> Slot {
+        match *placement {
+            Placement::Definite(ref range) => {
+                Slot::Definite((range.start + offset) as usize..(range.end + offset) as usize)
+            }
+            Placement::Auto(span) => Slot::Auto(span as usize),
+        }
+    }
+}
+
+/// Places items along the axis the grid grows in (`major`) and the other axis (`minor`).
+/// `minor_count` is the number of tracks of the minor axis that the explicit grid has.
+///
+/// https://drafts.csswg.org/css-grid/#auto-placement-algo
+fn auto_place(slots: &[(Slot, Slot)], minor_count: usize, dense: bool) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut grid = OccupancyGrid { cells: vec![] };
+    let mut placed: Vec<Option<(Range<usize>, Range<usize>)>> = vec![None; slots.len()];
+
+    // Position anything that's not auto-positioned.
+    for (index, slot) in slots.iter().enumerate() {
+        if let (&Slot::Definite(ref major), &Slot::Definite(ref minor)) = (&slot.0, &slot.1) {
+            grid.occupy(major, minor);
+            placed[index] = Some((major.clone(), minor.clone()));
+        }
+    }
+
+    // Process the items locked to a given track of the major axis.
+    let mut cursors = HashMap::new();
+    for (index, slot) in slots.iter().enumerate() {
+        if let (&Slot::Definite(ref major), &Slot::Auto(span)) = (&slot.0, &slot.1) {
+            let mut start = if dense { 0 } else { *cursors.get(&major.start).unwrap_or(&0) };
+            while !grid.is_free(major, &(start..(start + span))) {
+                start += 1;
+            }
+            let minor = start..(start + span);
+            grid.occupy(major, &minor);
+            cursors.insert(major.start, minor.end);
+            placed[index] = Some((major.clone(), minor));
+        }
+    }
+
+    // Determine the tracks of the minor axis in the implicit grid.
+    let mut minor_count = minor_count;
+    for (index, slot) in slots.iter().enumerate() {
+        minor_count = max(minor_count, match (&placed[index], &slot.1) {
+            (&Some((_, ref minor)), _) => minor.end,
+            (&None, &Slot::Definite(ref minor)) => minor.end,
+            (&None, &Slot::Auto(span)) => span,
+        });
+    }
+
+    // Position the remaining grid items.
+    let mut cursor = (0, 0);
+    for (index, slot) in slots.iter().enumerate() {
+        if placed[index].is_some() {
+            continue;
+        }
+        let span = match slot.0 {
+            Slot::Auto(span) => span,
+            Slot::Definite(ref major) => major.len(),
+        };
+        match slot.1 {
+            Slot::Definite(ref minor) => {
+                if dense {
+                    cursor.0 = 0;
+                } else if minor.start < cursor.1 {
+                    cursor.0 += 1;
+                }
+                cursor.1 = minor.start;
+                while !grid.is_free(&(cursor.0..(cursor.0 + span)), minor) {
+                    cursor.0 += 1;
+                }
+            }
+            Slot::Auto(minor_span) => {
+                if dense {
+                    cursor = (0, 0);
+                }
+                loop {
+                    if cursor.1 + minor_span > minor_count {
+                        cursor = (cursor.0 + 1, 0);
+                    }
+                    if grid.is_free(&(cursor.0..(cursor.0 + span)), &(cursor.1..(cursor.1 + minor_span))) {
+                        break;
+                    }
+                    cursor.1 += 1;
+                }
+            }
+        }
+        let minor = match slot.1 {
+            Slot::Definite(ref minor) => minor.clone(),
+            Slot::Auto(minor_span) => cursor.1..(cursor.1 + minor_span),
+        };
+        let major = cursor.0..(cursor.0 + span);
+        grid.occupy(&major, &minor);
+        placed[index] = Some((major, minor));
+    }
+
+    placed.into_iter().map(|placement| placement.unwrap()).collect()
+}
+
+/// Creates the tracks of one axis of the grid: the explicit tracks, offset by `offset`
+/// leading implicit tracks, followed by enough implicit tracks to contain `count` tracks.
+fn create_tracks(explicit: &[TrackSizingFunction], implicit: TrackSizingFunction, offset: usize, count: usize,
+                 auto_fit: Option<Range<usize>>, spans: &[&Range<usize>]) -> Vec<GridTrack> {
+    (0..count).map(|index| {
+        let explicit_index = index.checked_sub(offset);
+        let sizing = explicit_index.and_then(|index| explicit.get(index)).cloned().unwrap_or(implicit);
+        let mut track = GridTrack::new(sizing);
+        if let (Some(explicit_index), Some(ref auto_fit)) = (explicit_index, auto_fit.as_ref()) {
+            track.collapsed = explicit_index >= auto_fit.start && explicit_index < auto_fit.end &&
+                !spans.iter().any(|span| index >= span.start && index < span.end);
+        }
+        track
+    }).collect()
+}
+
+/// A child of a grid container, in the order of the `order` property.
+#[derive(Debug, Serialize)]
+struct GridItem {
+    /// The index of the actual flow in our child list.
+    index: usize,
+    /// The `order` property of this item.
+    order: i32,
+    /// The rows this item spans.
+    rows: Range<usize>,
+    /// The columns this item spans.
+    columns: Range<usize>,
+}
+
+/// A block with the CSS `display` property equal to `grid` or `inline-grid`.
+#[derive(Debug, Serialize)]
+pub struct GridFlow {
+    /// Data common to all block flows.
+    block_flow: BlockFlow,
+    /// The grid items, sorted by `order`.
+    items: Vec<GridItem>,
+    /// The rows of the grid, including implicit ones.
+    rows: Vec<GridTrack>,
+    /// The columns of the grid, including implicit ones.
+    columns: Vec<GridTrack>,
+    /// The number of repetitions of an automatic repeat in `grid-template-rows` the items were
+    /// placed with.
+    row_repetitions: usize,
+    /// The number of repetitions of an automatic repeat in `grid-template-columns` the items
+    /// were placed with.
+    column_repetitions: usize,
+}
+
+#[allow(unsafe_code)]
+unsafe impl ::flow::HasBaseFlow for GridFlow {}
+
+impl GridFlow {
+    pub fn from_fragment(fragment: Fragment, flotation: Option<FloatKind>) -> GridFlow {
+        GridFlow {
+            block_flow: BlockFlow::from_fragment_and_float_kind(fragment, flotation),
+            items: Vec::new(),
+            rows: Vec::new(),
+            columns: Vec::new(),
+            row_repetitions: 1,
+            column_repetitions: 1,
+        }
+    }
+
+    /// Resolves the explicit grid and places every item in it, creating implicit tracks as
+    /// needed.
+    ///
+    /// https://drafts.csswg.org/css-grid/#placement
+    fn place_items(&mut self, row_repetitions: usize, column_repetitions: usize) {
+        self.row_repetitions = row_repetitions;
+        self.column_repetitions = column_repetitions;
+
+        let style = self.block_flow.fragment.style.clone();
+        let position = style.get_position();
+        let (mut row_sizes, mut row_names, row_auto_fit) =
+            explicit_tracks(&position.grid_template_rows, row_repetitions);
+        let (mut column_sizes, mut column_names, column_auto_fit) =
+            explicit_tracks(&position.grid_template_columns, column_repetitions);
+        if let Either::First(ref areas) = position.grid_template_areas {
+            for area in areas.0.areas.iter() {
+                add_area_line_names(&mut row_names, &area.name, &area.rows);
+                add_area_line_names(&mut column_names, &area.name, &area.columns);
+            }
+        }
+
+        // Tracks created by `grid-template-areas` are sized like implicit tracks.
+        let auto_rows = TrackSizingFunction::new(&position.grid_auto_rows);
+        let auto_columns = TrackSizingFunction::new(&position.grid_auto_columns);
+        let row_count = row_names.len() - 1;
+        let column_count = column_names.len() - 1;
+        row_sizes.resize(row_count, auto_rows);
+        column_sizes.resize(column_count, auto_columns);
+
+        let rows = GridLines { names: row_names };
+        let columns = GridLines { names: column_names };
+        let kid_styles: Vec<ServoArc<ComputedValues>> = self.block_flow.base.children.iter().map(|kid| {
+            kid.as_block().fragment.style.clone()
+        }).collect();
+        let placements: Vec<(Placement, Placement)> = self.items.iter().map(|item| {
+            let position = kid_styles[item.index].get_position();
+            (rows.placement(&position.grid_row_start, &position.grid_row_end),
+             columns.placement(&position.grid_column_start, &position.grid_column_end))
+        }).collect();
+
+        // Lines before the start of the explicit grid create leading implicit tracks.
+        let mut row_offset = 0;
+        let mut column_offset = 0;
+        for &(ref row, ref column) in &placements {
+            if let Placement::Definite(ref range) = *row {
+                row_offset = max(row_offset, -range.start);
+            }
+            if let Placement::Definite(ref range) = *column {
+                column_offset = max(column_offset, -range.start);
+            }
+        }
+
+        let row_flow = position.grid_auto_flow.autoflow == AutoFlow::Row;
+        let slots: Vec<(Slot, Slot)> = placements.iter().map(|&(ref row, ref column)| {
+            let row = Slot::new(row, row_offset);
+            let column = Slot::new(column, column_offset);
+            if row_flow { (row, column) } else { (column, row) }
+        }).collect();
+        let minor_count = if row_flow {
+            column_offset as usize + column_count
+        } else {
+            row_offset as usize + row_count
+        };
+        let placed = auto_place(&slots, minor_count, position.grid_auto_flow.dense);
+
+        let mut total_rows = row_offset as usize + row_count;
+        let mut total_columns = column_offset as usize + column_count;
+        for (item, (major, minor)) in self.items.iter_mut().zip(placed.into_iter()) {
+            let (rows, columns) = if row_flow { (major, minor) } else { (minor, major) };
+            total_rows = max(total_rows, rows.end);
+            total_columns = max(total_columns, columns.end);
+            item.rows = rows;
+            item.columns = columns;
+        }
+
+        self.rows = {
+            let spans: Vec<&Range<usize>> = self.items.iter().map(|item| &item.rows).collect();
+            create_tracks(&row_sizes, auto_rows, row_offset as usize, total_rows, row_auto_fit, &spans)
+        };
+        self.columns = {
+            let spans: Vec<&Range<usize>> = self.items.iter().map(|item| &item.columns).collect();
+            create_tracks(&column_sizes, auto_columns, column_offset as usize, total_columns,
+                          column_auto_fit, &spans)
+        };
+    }
+
+    /// Returns the contributions of the items to the columns they span.
+    fn column_contributions(&self) -> Vec<Contribution> {
+        let kids = &self.block_flow.base.children;
+        let sizes: Vec<(Au, Au)> = kids.iter().map(|kid| {
+            let sizes = kid.base().intrinsic_inline_sizes;
+            (sizes.minimum_inline_size, sizes.preferred_inline_size)
+        }).collect();
+        self.items.iter().map(|item| {
+            Contribution {
+                tracks: item.columns.clone(),
+                min_content: sizes[item.index].0,
+                max_content: sizes[item.index].1,
+            }
+        }).collect()
+    }
+
+    /// Computes the used sizes of the rows from the laid out items and positions the items in
+    /// the block direction.
+    fn assign_row_sizes(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!("grid::assign_row_sizes");
+
+        let box_border = self.block_flow.fragment.box_sizing_boundary(Direction::Block);
+        let parent_container_size =
+            self.block_flow.explicit_block_containing_size(layout_context.shared_context());
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        let explicit_content_size = self
+                                    .block_flow
+                                    .explicit_block_size(parent_container_size)
+                                    .map(|x| max(x - box_border, Au(0)));
+
+        let contributions: Vec<Contribution> = {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            self.items.iter().map(|item| {
+                let fragment = &children.get(item.index).as_block().fragment;
+                let size = fragment.border_box.size.block + fragment.margin.block_start_end();
+                Contribution {
+                    tracks: item.rows.clone(),
+                    min_content: size,
+                    max_content: size,
+                }
+            }).collect()
+        };
+
+        let (row_gap, distribution) = {
+            let position = self.block_flow.fragment.style().get_position();
+            (resolve_gap(&position.row_gap, explicit_content_size),
+             ContentDistribution::from_align_content(position.align_content))
+        };
+        let stretch = distribution == ContentDistribution::Stretch;
+        let space = explicit_content_size.map_or(AvailableSpace::MaxContent, AvailableSpace::Definite);
+        size_tracks(&mut self.rows, &contributions, row_gap, space, stretch);
+
+        // Clamp the grid container by its min and max block sizes, and size the rows again if
+        // that made their size definite.
+        let mut content_block_size = used_size(&self.rows, row_gap);
+        if explicit_content_size.is_none() {
+            let mut candidate_block_size_iterator = CandidateBSizeIterator::new(
+                &self.block_flow.fragment,
+                self.block_flow.base.block_container_explicit_block_size);
+            while let Some(candidate_block_size) = candidate_block_size_iterator.next() {
+                candidate_block_size_iterator.candidate_value =
+                    match candidate_block_size {
+                        MaybeAuto::Auto => content_block_size,
+                        MaybeAuto::Specified(value) => value
+                    }
+            }
+            if candidate_block_size_iterator.candidate_value != content_block_size {
+                content_block_size = candidate_block_size_iterator.candidate_value;
+                size_tracks(&mut self.rows, &contributions, row_gap,
+                            AvailableSpace::Definite(content_block_size), stretch);
+            }
+        } else {
+            content_block_size = explicit_content_size.unwrap();
+        }
+        position_tracks(&mut self.rows, row_gap, content_block_size, distribution);
+
+        let block_start_content_edge = self.block_flow.fragment.border_padding.block_start;
+        let mut children = self.block_flow.base.children.random_access_mut();
+        for item in &self.items {
+            let (area_start, area_size) = area(&self.rows, &item.rows);
+            let block = children.get(item.index).as_mut_block();
+            let style = block.fragment.style.clone();
+            let margin = style.logical_margin();
+            let auto_margin_count = (margin.block_start == LengthOrPercentageOrAuto::Auto) as i32 +
+                (margin.block_end == LengthOrPercentageOrAuto::Auto) as i32;
+            let self_align = style.get_position().align_self;
+
+            let mut margin_block_start = block.fragment.margin.block_start;
+            let margin_block_end = block.fragment.margin.block_end;
+            let stretch = self_align == AlignSelf::Stretch && auto_margin_count == 0 &&
+                !block.fragment.is_replaced() && style.content_block_size() == LengthOrPercentageOrAuto::Auto;
+            if stretch {
+                block.base.block_container_explicit_block_size = Some(area_size);
+                block.base.position.size.block = max(area_size - margin_block_start - margin_block_end, Au(0));
+                block.fragment.border_box.size.block = block.base.position.size.block;
+                // FIXME: like stretched flex items, the contents of stretched grid items are
+                // not laid out again against their new block size.
+            }
+
+            let free_space = area_size - block.fragment.border_box.size.block - margin_block_start - margin_block_end;
+            let offset = if auto_margin_count > 0 {
+                if margin.block_start == LengthOrPercentageOrAuto::Auto && free_space > Au(0) {
+                    margin_block_start += free_space / auto_margin_count;
+                }
+                Au(0)
+            } else {
+                match self_align {
+                    AlignSelf::FlexEnd | AlignSelf::End => free_space,
+                    AlignSelf::Center => free_space / 2,
+                    _ => Au(0),
+                }
+            };
+            block.fragment.margin.block_start = margin_block_start;
+            // TODO: support baseline alignment.
+            block.base.position.start.b = block_start_content_edge + area_start + margin_block_start + offset;
+        }
+
+        let total_block_size = content_block_size + self.block_flow.fragment.border_padding.block_start_end();
+        self.block_flow.fragment.border_box.size.block = total_block_size;
+        self.block_flow.base.position.size.block = total_block_size;
+    }
+}
+
+impl Flow for GridFlow {
+    fn class(&self) -> FlowClass {
+        FlowClass::Grid
+    }
+
+    fn as_mut_grid(&mut self) -> &mut GridFlow {
+        self
+    }
+
+    fn as_grid(&self) -> &GridFlow {
+        self
+    }
+
+    fn as_block(&self) -> &BlockFlow {
+        &self.block_flow
+    }
+
+    fn as_mut_block(&mut self) -> &mut BlockFlow {
+        &mut self.block_flow
+    }
+
+    fn mark_as_root(&mut self) {
+        self.block_flow.mark_as_root();
+    }
+
+    fn bubble_inline_sizes(&mut self) {
+        let _scope = layout_debug_scope!("grid::bubble_inline_sizes {:x}",
+                                         self.block_flow.base.debug_id());
+
+        // Grid items are laid out in order-modified document order.
+        // https://drafts.csswg.org/css-grid/#order-property
+        let mut items: Vec<GridItem> =
+            self.block_flow
+                .base
+                .children
+                .iter()
+                .enumerate()
+                .filter(|&(_, flow)| {
+                    !flow.as_block().base.flags.contains(FlowFlags::IS_ABSOLUTELY_POSITIONED)
+                })
+                .map(|(index, flow)| {
+                    GridItem {
+                        index: index,
+                        order: flow.as_block().fragment.style.get_position().order,
+                        rows: 0..1,
+                        columns: 0..1,
+                    }
+                })
+                .collect();
+        items.sort_by_key(|item| item.order);
+        self.items = items;
+
+        // The size of the grid container isn't known yet, so place automatic repeats once.
+        self.place_items(1, 1);
+
+        let fixed_width = match self.block_flow.fragment.style().get_position().width {
+            LengthOrPercentageOrAuto::Length(_) => true,
+            _ => false,
+        };
+
+        let mut computation = self.block_flow.fragment.compute_intrinsic_inline_sizes();
+        if !fixed_width {
+            let contributions = self.column_contributions();
+            let column_gap = resolve_gap(&self.block_flow.fragment.style().get_position().column_gap, None);
+            let mut columns = self.columns.clone();
+            size_tracks(&mut columns, &contributions, column_gap, AvailableSpace::MinContent, false);
+            computation.content_intrinsic_sizes.minimum_inline_size = used_size(&columns, column_gap);
+            size_tracks(&mut columns, &contributions, column_gap, AvailableSpace::MaxContent, false);
+            computation.content_intrinsic_sizes.preferred_inline_size = used_size(&columns, column_gap);
+        }
+        self.block_flow.base.intrinsic_inline_sizes = computation.finish();
+    }
+
+    fn assign_inline_sizes(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!("grid::assign_inline_sizes {:x}", self.block_flow.base.debug_id());
+        debug!("assign_inline_sizes");
+
+        if !self.block_flow.base.restyle_damage.intersects(ServoRestyleDamage::REFLOW_OUT_OF_FLOW |
+                                                           ServoRestyleDamage::REFLOW) {
+            return
+        }
+
+        self.block_flow.initialize_container_size_for_root(layout_context.shared_context());
+
+        // Our inline-size was set to the inline-size of the containing block by the flow's parent.
+        // Now compute the real value.
+        let containing_block_inline_size = self.block_flow.base.block_container_inline_size;
+        self.block_flow.compute_used_inline_size(layout_context.shared_context(),
+                                                 containing_block_inline_size);
+        if self.block_flow.base.flags.is_float() {
+            self.block_flow.float.as_mut().unwrap().containing_inline_size = containing_block_inline_size
+        }
+
+        // Move in from the inline-start border edge.
+        let inline_start_content_edge = self.block_flow.fragment.border_box.start.i +
+            self.block_flow.fragment.border_padding.inline_start;
+        let padding_and_borders = self.block_flow.fragment.border_padding.inline_start_end();
+        let content_inline_size = self.block_flow.fragment.border_box.size.inline - padding_and_borders;
+
+        // Calculate non-auto block size to pass to children.
+        let box_border = self.block_flow.fragment.box_sizing_boundary(Direction::Block);
+        let parent_container_size =
+            self.block_flow.explicit_block_containing_size(layout_context.shared_context());
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        let explicit_content_size = self
+                                    .block_flow
+                                    .explicit_block_size(parent_container_size)
+                                    .map(|x| max(x - box_border, Au(0)));
+
+        let style = self.block_flow.fragment.style.clone();
+        let position = style.get_position();
+        let column_gap = resolve_gap(&position.column_gap, Some(content_inline_size));
+        let row_gap = resolve_gap(&position.row_gap, explicit_content_size);
+        let containing_block_text_align = style.get_inherited_text().text_align;
+        let container_mode = self.block_flow.base.block_container_writing_mode;
+
+        // Now that the size of the grid container is known, place the items again if that
+        // changes the number of automatic repetitions.
+        let row_repetitions = auto_repetitions(&position.grid_template_rows, row_gap, explicit_content_size);
+        let column_repetitions =
+            auto_repetitions(&position.grid_template_columns, column_gap, Some(content_inline_size));
+        if row_repetitions != self.row_repetitions || column_repetitions != self.column_repetitions {
+            self.place_items(row_repetitions, column_repetitions);
+        }
+
+        let distribution = ContentDistribution::from_justify_content(position.justify_content);
+        let contributions = self.column_contributions();
+        size_tracks(&mut self.columns, &contributions, column_gap,
+                    AvailableSpace::Definite(content_inline_size),
+                    distribution == ContentDistribution::Stretch);
+        position_tracks(&mut self.columns, column_gap, content_inline_size, distribution);
+
+        {
+            let mut children = self.block_flow.base.children.random_access_mut();
+            for item in &self.items {
+                let (area_start, area_size) = area(&self.columns, &item.columns);
+                let block = children.get(item.index).as_mut_block();
+
+                block.base.block_container_writing_mode = container_mode;
+                block.base.block_container_inline_size = area_size;
+                block.base.block_container_explicit_block_size =
+                    definite_area_size(&self.rows, &item.rows, row_gap, explicit_content_size);
+                // Per CSS 2.1 § 16.3.1, text alignment propagates to all children in flow.
+                //
+                // TODO(#2265, pcwalton): Do this in the cascade instead.
+                block.base.flags.set_text_align(containing_block_text_align);
+
+                // These methods compute auto margins to zero length.
+                block.fragment.compute_border_and_padding(area_size);
+                block.fragment.compute_inline_direction_margins(area_size);
+                block.fragment.compute_block_direction_margins(area_size);
+
+                let item_style = block.fragment.style.clone();
+                let margin = item_style.logical_margin();
+                let auto_margin_count = (margin.inline_start == LengthOrPercentageOrAuto::Auto) as i32 +
+                    (margin.inline_end == LengthOrPercentageOrAuto::Auto) as i32;
+                let self_justify = item_style.get_position().justify_self;
+                let margins = block.fragment.margin.inline_start_end();
+                let border_padding = block.fragment.border_padding.inline_start_end();
+                let box_sizing_boundary = block.fragment.box_sizing_boundary(Direction::Inline);
+
+                let item_inline_size = match MaybeAuto::from_style(item_style.content_inline_size(), area_size) {
+                    MaybeAuto::Specified(size) => size - box_sizing_boundary + border_padding,
+                    MaybeAuto::Auto => {
+                        if self_justify == JustifySelf::Stretch && auto_margin_count == 0 &&
+                            !block.fragment.is_replaced() {
+                            area_size - margins
+                        } else {
+                            // Shrink-to-fit, with the intrinsic sizes of the item.
+                            let (_, intrinsic_margins) = block.fragment.surrounding_intrinsic_inline_size();
+                            let sizes = block.base.intrinsic_inline_sizes;
+                            min(max(sizes.minimum_inline_size - intrinsic_margins, area_size - margins),
+                                sizes.preferred_inline_size - intrinsic_margins)
+                        }
+                    }
+                };
+                let min_inline_size = item_style.min_inline_size().to_used_value(area_size) -
+                    box_sizing_boundary + border_padding;
+                let item_inline_size = match item_style.max_inline_size().to_used_value(area_size) {
+                    Some(max_inline_size) => {
+                        min(item_inline_size, max_inline_size - box_sizing_boundary + border_padding)
+                    }
+                    None => item_inline_size,
+                };
+                let item_inline_size = max(max(item_inline_size, min_inline_size), border_padding);
+
+                let free_space = area_size - item_inline_size - margins;
+                let mut margin_inline_start = block.fragment.margin.inline_start;
+                let mut margin_inline_end = block.fragment.margin.inline_end;
+                let offset = if auto_margin_count > 0 {
+                    let auto_margin = max(free_space / auto_margin_count, Au(0));
+                    if margin.inline_start == LengthOrPercentageOrAuto::Auto {
+                        margin_inline_start = auto_margin;
+                    }
+                    if margin.inline_end == LengthOrPercentageOrAuto::Auto {
+                        margin_inline_end = auto_margin;
+                    }
+                    Au(0)
+                } else {
+                    match self_justify {
+                        JustifySelf::End => free_space,
+                        JustifySelf::Center => free_space / 2,
+                        _ => Au(0),
+                    }
+                };
+
+                block.fragment.margin.inline_start = margin_inline_start;
+                block.fragment.margin.inline_end = margin_inline_end;
+                block.fragment.border_box.start.i = margin_inline_start;
+                block.fragment.border_box.size.inline = item_inline_size;
+                block.base.position.start.i = inline_start_content_edge + area_start + offset;
+                block.base.position.size.inline = item_inline_size + margin_inline_start + margin_inline_end;
+            }
+        }
+
+        // Absolutely positioned children are not grid items, but use the content box of the
+        // grid container as their static position and containing block.
+        for kid in self.block_flow.base.children.iter_mut() {
+            let kid_base = kid.mut_base();
+            if kid_base.flags.contains(FlowFlags::IS_ABSOLUTELY_POSITIONED) {
+                kid_base.block_container_writing_mode = container_mode;
+                kid_base.block_container_inline_size = content_inline_size;
+                if kid_base.flags.contains(FlowFlags::INLINE_POSITION_IS_STATIC) {
+                    kid_base.position.start.i = inline_start_content_edge;
+                }
+            }
+        }
+    }
+
+    fn assign_block_size(&mut self, layout_context: &LayoutContext) {
+        self.assign_row_sizes(layout_context);
+        let block_start = AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_start);
+        let block_end = AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_end);
+        self.block_flow.base.collapsible_margins = CollapsibleMargins::Collapse(block_start, block_end);
+
+        // TODO: assign proper static position for absolute descendants.
+        if (&*self as &Flow).contains_roots_of_absolute_flow_tree() {
+            // Assign block-sizes for all flows in this absolute flow tree.
+            // This is preorder because the block-size of an absolute flow may depend on
+            // the block-size of its containing block, which may also be an absolute flow.
+            let assign_abs_b_sizes = AbsoluteAssignBSizesTraversal(layout_context.shared_context());
+            assign_abs_b_sizes.traverse_absolute_flows(&mut *self);
+        }
+    }
+
+    fn compute_stacking_relative_position(&mut self, layout_context: &LayoutContext) {
+        self.block_flow.compute_stacking_relative_position(layout_context)
+    }
+
+    fn place_float_if_applicable<'a>(&mut self) {
+        self.block_flow.place_float_if_applicable()
+    }
+
+    fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
+        self.block_flow.update_late_computed_inline_position_if_necessary(inline_position)
+    }
+
+    fn update_late_computed_block_position_if_necessary(&mut self, block_position: Au) {
+        self.block_flow.update_late_computed_block_position_if_necessary(block_position)
+    }
+
+    fn build_display_list(&mut self, state: &mut DisplayListBuildState) {
+        self.build_display_list_for_grid(state);
+    }
+
+    fn collect_stacking_contexts(&mut self, state: &mut StackingContextCollectionState) {
+        self.block_flow.collect_stacking_contexts(state);
+    }
+
+    fn repair_style(&mut self, new_style: &::ServoArc<ComputedValues>) {
+        self.block_flow.repair_style(new_style)
+    }
+
+    fn compute_overflow(&self) -> Overflow {
+        self.block_flow.compute_overflow()
+    }
+
+    fn contains_roots_of_absolute_flow_tree(&self) -> bool {
+        self.block_flow.contains_roots_of_absolute_flow_tree()
+    }
+
+    fn is_absolute_containing_block(&self) -> bool {
+        self.block_flow.is_absolute_containing_block()
+    }
+
+    fn generated_containing_block_size(&self, flow: OpaqueFlow) -> LogicalSize<Au> {
+        self.block_flow.generated_containing_block_size(flow)
+    }
+
+    fn iterate_through_fragment_border_boxes(&self,
+                                             iterator: &mut FragmentBorderBoxIterator,
+                                             level: i32,
+                                             stacking_context_position: &Point2D<Au>) {
+        self.block_flow.iterate_through_fragment_border_boxes(iterator, level, stacking_context_position);
+    }
+
+    fn mutate_fragments(&mut self, mutator: &mut FnMut(&mut Fragment)) {
+        self.block_flow.mutate_fragments(mutator);
+    }
+}
@@ -14,7 +14,7 @@ use display_list::items::OpaqueNode;
 use euclid::{Point2D, Size2D};
 use floats::{FloatKind, Floats, PlacementInfo};
 use flow::{BaseFlow, Flow, FlowClass, ForceNonfloatedFlag};
-use flow::{FlowFlags, EarlyAbsolutePositionInfo, GetBaseFlow, OpaqueFlow};
+use flow::{FlowFlags, EarlyAbsolutePositionInfo, FragmentationContext, GetBaseFlow, OpaqueFlow};
 use flow_ref::FlowRef;
 use fragment::{CoordinateSystem, Fragment, FragmentBorderBoxIterator, Overflow};
 use fragment::FragmentFlags;
@@ -472,13 +472,14 @@ impl LineBreaker {
         };
 
         // Try to place the fragment between floats.
-        let line_bounds = self.floats.place_between_floats(&PlacementInfo {
+        let line_bounds = self.floats.place_line_between_floats(&PlacementInfo {
             size: LogicalSize::new(self.floats.writing_mode,
                                    placement_inline_size,
                                    first_fragment.border_box.size.block),
             ceiling: ceiling,
             max_inline_size: flow.base.position.size.inline,
             kind: FloatKind::Left,
+            shape_outline: None,
         });
 
         let fragment_margin_box_inline_size = first_fragment.margin_box_inline_size();
@@ -955,7 +956,10 @@ impl InlineFlow {
         let mut inline_start_position_for_fragment = line.bounds.start.i + indentation;
         match line_align {
             TextAlign::Justify if !is_last_line && text_justify != TextJustify::None => {
-                InlineFlow::justify_inline_fragments(fragments, line, slack_inline_size)
+                InlineFlow::justify_inline_fragments(fragments,
+                                                     line,
+                                                     slack_inline_size,
+                                                     text_justify)
             }
             TextAlign::Justify | TextAlign::Start => {}
             TextAlign::Center | TextAlign::ServoCenter => {
@@ -1043,13 +1047,16 @@ impl InlineFlow {
     /// of them according to the value of `text-justify`.
     fn justify_inline_fragments(fragments: &mut InlineFragments,
                                 line: &Line,
-                                slack_inline_size: Au) {
+                                slack_inline_size: Au,
+                                text_justify: TextJustify) {
         // Fast path.
         if slack_inline_size == Au(0) {
             return
         }
 
-        // First, calculate the number of expansion opportunities (spaces, normally).
+        // First, calculate the number of expansion opportunities: the spaces between words, or
+        // every character if justifying between characters.
+        let inter_character = text_justify == TextJustify::InterCharacter;
         let mut expansion_opportunities = 0;
         for fragment_index in line.range.each_index() {
             let fragment = fragments.get(fragment_index.to_usize());
@@ -1060,7 +1067,11 @@ impl InlineFlow {
             let fragment_range = scanned_text_fragment_info.range;
 
             for slice in scanned_text_fragment_info.run.character_slices_in_range(&fragment_range) {
-                expansion_opportunities += slice.glyphs.space_count_in_range(&slice.range)
+                expansion_opportunities += if inter_character {
+                    slice.glyphs.iter_glyphs_for_byte_range(&slice.range).count() as u32
+                } else {
+                    slice.glyphs.space_count_in_range(&slice.range)
+                }
             }
         }
 
@@ -1077,11 +1088,19 @@ impl InlineFlow {
                 _ => continue
             };
             let fragment_range = scanned_text_fragment_info.range;
+            let hyphenated = scanned_text_fragment_info.hyphenated();
             let run = Arc::make_mut(&mut scanned_text_fragment_info.run);
-            run.extra_word_spacing = space_per_expansion_opportunity;
+            if inter_character {
+                run.extra_letter_spacing = space_per_expansion_opportunity;
+            } else {
+                run.extra_word_spacing = space_per_expansion_opportunity;
+            }
 
             // Recompute the fragment's border box size.
-            let new_inline_size = run.advance_for_range(&fragment_range);
+            let mut new_inline_size = run.advance_for_range(&fragment_range);
+            if hyphenated {
+                new_inline_size += run.hyphen_advance();
+            }
             let new_size = LogicalSize::new(fragment.style.writing_mode,
                                             new_inline_size,
                                             fragment.border_box.size.block);
@@ -1156,7 +1175,7 @@ impl InlineFlow {
         let font_metrics = text::font_metrics_for_style(font_context, font_style);
         let line_height = text::line_height_from_style(style, &font_metrics);
         let inline_metrics = if fragments.iter().any(Fragment::is_text_or_replaced) {
-            InlineMetrics::from_font_metrics(&font_metrics, line_height)
+            InlineMetrics::from_font_metrics(&font_metrics, line_height, style.writing_mode)
         } else {
             InlineMetrics::new(Au(0), Au(0), Au(0))
         };
@@ -1182,7 +1201,9 @@ impl InlineFlow {
                 let font_style = node.style.clone_font();
                 let font_metrics = text::font_metrics_for_style(font_context, font_style);
                 let line_height = text::line_height_from_style(&*node.style, &font_metrics);
-                let inline_metrics = InlineMetrics::from_font_metrics(&font_metrics, line_height);
+                let inline_metrics = InlineMetrics::from_font_metrics(&font_metrics,
+                                                                      line_height,
+                                                                      node.style.writing_mode);
 
                 update_line_metrics_for_fragment(&mut line_metrics,
                                                  &inline_metrics,
@@ -1214,6 +1235,7 @@ impl InlineFlow {
                 (Display::Inline, GenericVerticalAlign::Top) |
                 (Display::Block, GenericVerticalAlign::Top) |
                 (Display::InlineFlex, GenericVerticalAlign::Top) |
+                (Display::InlineGrid, GenericVerticalAlign::Top) |
                 (Display::InlineBlock, GenericVerticalAlign::Top) if
                         inline_metrics.space_above_baseline >= Au(0) => {
                     *largest_block_size_for_top_fragments = max(
@@ -1223,6 +1245,7 @@ impl InlineFlow {
                 (Display::Inline, GenericVerticalAlign::Bottom) |
                 (Display::Block, GenericVerticalAlign::Bottom) |
                 (Display::InlineFlex, GenericVerticalAlign::Bottom) |
+                (Display::InlineGrid, GenericVerticalAlign::Bottom) |
                 (Display::InlineBlock, GenericVerticalAlign::Bottom) if
                         inline_metrics.space_below_baseline >= Au(0) => {
                     *largest_block_size_for_bottom_fragments = max(
@@ -1562,6 +1585,81 @@ impl Flow for InlineFlow {
         }
     }
 
+    fn fragment(&mut self,
+                layout_context: &LayoutContext,
+                fragmentation_context: Option<FragmentationContext>)
+                -> Option<Arc<Flow>> {
+        for kid in self.base.child_iter_mut() {
+            kid.fragment(layout_context, None);
+        }
+        self.assign_block_size(layout_context);
+
+        // TODO: Break lines across columns too.
+        let ctx = match fragmentation_context {
+            Some(ctx) if ctx.is_paged => ctx,
+            _ => return None,
+        };
+        let line_count = self.lines.len();
+        let mut break_index = self.lines.iter().position(|line| {
+            line.bounds.start.b + line.bounds.size.block > ctx.available_block_size
+        })?;
+
+        // Honor `orphans` and `widows`, unless this is the only content of the page, in which
+        // case at least one line has to be placed to make progress.
+        //
+        // FIXME: These are taken from the first fragment rather than from the block container.
+        let (orphans, widows) = match self.fragments.fragments.first() {
+            Some(fragment) => {
+                let inherited_text = fragment.style.get_inherited_text();
+                (inherited_text.orphans.0 as usize, inherited_text.widows.0 as usize)
+            }
+            None => return None,
+        };
+        if line_count - break_index < widows {
+            break_index = line_count.saturating_sub(widows);
+        }
+        if break_index < orphans {
+            if !ctx.this_fragment_is_empty {
+                // Let the parent push this whole flow to the next fragmentainer.
+                return None
+            }
+            break_index = max(break_index, 1);
+        }
+        if break_index >= line_count {
+            return None
+        }
+
+        // Move the fragments of the lines that do not fit, and the flows of any inline-blocks
+        // among them, into a new flow. It will break its lines again once it is laid out.
+        let first_moved = self.lines[break_index].range.begin().get() as usize;
+        let moved_kid_count = self.fragments.fragments[first_moved..].iter().filter(|fragment| {
+            match fragment.specific {
+                SpecificFragmentInfo::InlineAbsoluteHypothetical(_) |
+                SpecificFragmentInfo::InlineAbsolute(_) |
+                SpecificFragmentInfo::InlineBlock(_) => true,
+                _ => false,
+            }
+        }).count();
+        let kept_kid_count = self.base.children.len() - moved_kid_count;
+        let children = self.base.children.split_off(kept_kid_count);
+        let fragments = self.fragments.fragments.split_off(first_moved);
+        self.lines.truncate(break_index);
+        self.base.position.size.block = match self.last_line_containing_real_fragments() {
+            Some(last_line) => last_line.bounds.start.b + last_line.bounds.size.block,
+            None => Au(0),
+        };
+
+        Some(Arc::new(InlineFlow {
+            base: self.base.clone_with_children(children),
+            fragments: InlineFragments {
+                fragments: fragments,
+            },
+            lines: Vec::new(),
+            minimum_line_metrics: self.minimum_line_metrics,
+            first_line_indentation: Au(0),
+        }) as Arc<Flow>)
+    }
+
     fn compute_stacking_relative_position(&mut self, _: &LayoutContext) {
         // First, gather up the positions of all the containing blocks (if any).
         //
@@ -1870,9 +1968,22 @@ impl InlineMetrics {
     }
 
     /// Calculates inline metrics from font metrics and line block-size per CSS 2.1 § 10.8.1.
+    ///
+    /// In vertical writing modes, text is aligned on the central baseline, halfway between the
+    /// ascent and descent, per CSS Writing Modes § 4.2.
     #[inline]
-    pub fn from_font_metrics(font_metrics: &FontMetrics, line_height: Au) -> InlineMetrics {
-        let leading = line_height - (font_metrics.ascent + font_metrics.descent);
+    pub fn from_font_metrics(font_metrics: &FontMetrics,
+                             line_height: Au,
+                             writing_mode: WritingMode)
+                             -> InlineMetrics {
+        let font_block_size = font_metrics.ascent + font_metrics.descent;
+        let (ascent, descent) = if writing_mode.is_vertical() {
+            let half_font_block_size = font_block_size.scale_by(0.5);
+            (half_font_block_size, font_block_size - half_font_block_size)
+        } else {
+            (font_metrics.ascent, font_metrics.descent)
+        };
+        let leading = line_height - font_block_size;
 
         // Calculating the half leading here and then using leading - half_leading
         // below ensure that we don't introduce any rounding accuracy issues here.
@@ -1880,9 +1991,9 @@ impl InlineMetrics {
         // equal the requested line_height.
         let half_leading = leading.scale_by(0.5);
         InlineMetrics {
-            space_above_baseline: font_metrics.ascent + half_leading,
-            space_below_baseline: font_metrics.descent + leading - half_leading,
-            ascent: font_metrics.ascent,
+            space_above_baseline: ascent + half_leading,
+            space_below_baseline: descent + leading - half_leading,
+            ascent: ascent,
         }
     }
 
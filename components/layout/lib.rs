@@ -60,6 +60,7 @@ mod flow_list;
 pub mod flow_ref;
 mod fragment;
 mod generated_content;
+mod grid;
 pub mod incremental;
 mod inline;
 mod linked_list;
@@ -67,10 +68,12 @@ mod list_item;
 mod model;
 mod multicol;
 pub mod opaque_node;
+pub mod paged;
 pub mod parallel;
 mod persistent_list;
 pub mod query;
 pub mod sequential;
+mod shape_outside;
 mod table;
 mod table_caption;
 mod table_cell;
@@ -142,6 +142,7 @@ impl Flow for MulticolFlow {
 
         let fragmentation_context = Some(FragmentationContext {
             this_fragment_is_empty: true,
+            is_paged: false,
             available_block_size: {
                 let style = &self.block_flow.fragment.style;
                 if let LengthOrPercentageOrAuto::Length(length) = style.content_block_size() {
@@ -0,0 +1,173 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Paged media: fragmenting the flow tree into pages and printing them.
+//!
+//! https://drafts.csswg.org/css-page-3/
+
+use app_units::Au;
+use context::LayoutContext;
+use display_list::ToLayout;
+use display_list::pdf::PdfWriter;
+use euclid::{Point2D, Rect, SideOffsets2D, Size2D, Vector2D};
+use flow::{FragmentationContext, GetBaseFlow};
+use flow_ref::FlowRef;
+use fragment::{Fragment, FragmentBorderBoxIterator, SpecificFragmentInfo};
+use gfx::text::glyph::ByteIndex;
+use gfx::text::text_run::{GlyphRun, TextRun};
+use model::MaybeAuto;
+use range::Range;
+use sequential;
+use servo_geometry::MaxRect;
+use std::cmp::max;
+use style::properties::ComputedValues;
+use style::values::generics::box_::PageSize;
+use traversal::{ComputeStackingRelativePositions, PreorderFlowTraversal};
+
+/// The size of an A4 sheet in CSS pixels, which we use when `@page` rules don't specify a size.
+const DEFAULT_PAGE_WIDTH_PX: f32 = 210. * 96. / 25.4;
+const DEFAULT_PAGE_HEIGHT_PX: f32 = 297. * 96. / 25.4;
+
+/// The geometry of the page box, as set by `@page` rules.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageGeometry {
+    /// The size of the page box.
+    pub size: Size2D<Au>,
+    /// The page margins. The page area is what remains of the page box once these are removed.
+    pub margins: SideOffsets2D<Au>,
+}
+
+impl PageGeometry {
+    /// Computes the page geometry from the style of the page context.
+    pub fn from_style(style: &ComputedValues) -> PageGeometry {
+        let default_size = Size2D::new(Au::from_f32_px(DEFAULT_PAGE_WIDTH_PX),
+                                       Au::from_f32_px(DEFAULT_PAGE_HEIGHT_PX));
+        let size = match style.get_box().size {
+            PageSize::Auto | PageSize::Portrait => default_size,
+            PageSize::Landscape => Size2D::new(default_size.height, default_size.width),
+            PageSize::Size(width, height) => Size2D::new(Au::from(width), Au::from(height)),
+        };
+
+        // Unlike in the document, vertical page margins resolve against the page height.
+        let margin = style.get_margin();
+        let margins = SideOffsets2D::new(
+            MaybeAuto::from_style(margin.margin_top, size.height).specified_or_zero(),
+            MaybeAuto::from_style(margin.margin_right, size.width).specified_or_zero(),
+            MaybeAuto::from_style(margin.margin_bottom, size.height).specified_or_zero(),
+            MaybeAuto::from_style(margin.margin_left, size.width).specified_or_zero(),
+        );
+
+        PageGeometry {
+            size: size,
+            margins: margins,
+        }
+    }
+
+    /// Returns the rectangle of the page area within the page box.
+    pub fn page_area(&self) -> Rect<Au> {
+        let page_area = Rect::new(Point2D::zero(), self.size).inner_rect(self.margins);
+        Rect::new(page_area.origin, Size2D::new(max(page_area.size.width, Au(0)),
+                                                max(page_area.size.height, Au(0))))
+    }
+}
+
+/// Fragments the laid out root flow into pages of the given block size. The first page is the
+/// root flow itself; every following page is a continuation of the previous one.
+pub fn paginate(root: FlowRef, layout_context: &LayoutContext, page_block_size: Au) -> Vec<FlowRef> {
+    let mut pages = vec![];
+    let mut next_page = Some(root);
+    while let Some(mut page) = next_page.take() {
+        let remaining = FlowRef::deref_mut(&mut page).fragment(layout_context, Some(FragmentationContext {
+            available_block_size: page_block_size,
+            this_fragment_is_empty: true,
+            is_paged: true,
+        }));
+        pages.push(page);
+        next_page = remaining.map(FlowRef::new);
+    }
+    pages
+}
+
+/// Paginates the laid out root flow and serialises the pages as a PDF document.
+pub fn print_to_pdf(root: FlowRef, layout_context: &LayoutContext, page: &PageGeometry) -> Vec<u8> {
+    let page_area = page.page_area();
+    let mut writer = PdfWriter::new();
+    for mut page_flow in paginate(root, layout_context, page_area.size.height) {
+        let flow = FlowRef::deref_mut(&mut page_flow);
+        sequential::store_overflow(layout_context, flow);
+
+        flow.mut_base().stacking_relative_position = Vector2D::zero();
+        flow.mut_base().clip = Rect::max_rect();
+        ComputeStackingRelativePositions { layout_context: layout_context }.traverse(flow);
+
+        let mut resources = PageResources { writer: &mut writer };
+        sequential::iterate_through_flow_tree_fragment_border_boxes(flow, &mut resources);
+
+        let mut build_state = sequential::build_display_list_for_subtree(flow, layout_context);
+        let bounds = Rect::new(Point2D::zero(), page_area.size).to_layout();
+        build_state.root_stacking_context.bounds = bounds;
+        build_state.root_stacking_context.overflow = bounds;
+        writer.add_page(&build_state.to_display_list(), page.size.to_layout(), page_area.origin.to_layout());
+    }
+    writer.finish()
+}
+
+/// Records the text that each glyph of a glyph run was shaped from, which is the text from the
+/// character of the glyph up to the character of the next glyph.
+fn add_glyph_text(writer: &mut PdfWriter, run: &TextRun, glyph_run: &GlyphRun) {
+    let store = &glyph_run.glyph_store;
+    if store.len() == ByteIndex(0) {
+        return;
+    }
+    let glyphs: Vec<_> = store.iter_glyphs_for_byte_range(&Range::new(ByteIndex(0), store.len()))
+        .map(|glyph| (glyph.byte_index(), glyph.id()))
+        .collect();
+    let mut starts: Vec<_> = glyphs.iter().map(|&(index, _)| index).collect();
+    starts.sort();
+    starts.dedup();
+
+    let offset = glyph_run.range.begin().to_usize();
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).cloned().unwrap_or(store.len());
+        let text = match run.text.get(offset + start.to_usize()..offset + end.to_usize()) {
+            Some(text) => text,
+            None => continue,
+        };
+        // A character shaped into several glyphs is mapped from the first one only.
+        if let Some(&(_, id)) = glyphs.iter().find(|&&(index, _)| index == start) {
+            writer.add_glyph_text(run.font_key, id, text);
+        }
+    }
+}
+
+/// Registers the fonts and images that the display list of a page refers to with the PDF writer,
+/// since display items only carry WebRender keys.
+struct PageResources<'a> {
+    writer: &'a mut PdfWriter,
+}
+
+impl<'a> FragmentBorderBoxIterator for PageResources<'a> {
+    fn process(&mut self, fragment: &Fragment, _: i32, _: &Rect<Au>) {
+        match fragment.specific {
+            SpecificFragmentInfo::ScannedText(ref info) => {
+                let run = &info.run;
+                self.writer.add_font(run.font_key, run.font_template.clone(), run.actual_pt_size);
+                for glyph_run in run.glyphs.iter() {
+                    add_glyph_text(self.writer, run, glyph_run);
+                }
+            },
+            SpecificFragmentInfo::Image(ref info) => {
+                // TODO: Background and border images.
+                if let Some(ref image) = info.image {
+                    self.writer.add_image(image.clone());
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn should_process(&mut self, _: &Fragment) -> bool {
+        true
+    }
+}
@@ -4,6 +4,7 @@
 
 //! Utilities for querying the layout, as needed by the layout thread.
 
+use ServoArc;
 use app_units::Au;
 use construct::ConstructionResult;
 use context::LayoutContext;
@@ -26,6 +27,7 @@ use script_layout_interface::wrapper_traits::{LayoutNode, ThreadSafeLayoutElemen
 use script_traits::LayoutMsg as ConstellationMsg;
 use script_traits::UntrustedNodeAddress;
 use sequential;
+use servo_url::ServoUrl;
 use std::cmp::{min, max};
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
@@ -35,9 +37,12 @@ use style::computed_values::visibility::T as Visibility;
 use style::context::{StyleContext, ThreadLocalStyleContext};
 use style::dom::TElement;
 use style::logical_geometry::{WritingMode, BlockFlowDirection, InlineBaseDirection};
+use style::properties::{Importance, PropertyDeclarationBlock, SourcePropertyDeclaration};
 use style::properties::{style_structs, PropertyId, PropertyDeclarationId, LonghandId};
+use style::properties::parse_one_declaration_into;
 use style::selector_parser::PseudoElement;
-use style_traits::ToCss;
+use style::shared_lock::SharedRwLock;
+use style_traits::{ParsingMode, ToCss};
 use webrender_api::ExternalScrollId;
 use wrapper::LayoutNodeLayoutData;
 
@@ -71,6 +76,9 @@ pub struct LayoutThreadData {
     /// A queued response for the resolved style property of an element.
     pub resolved_style_response: String,
 
+    /// A queued response for the resolved font style for canvas.
+    pub resolved_font_style_response: Option<ServoArc<style_structs::Font>>,
+
     /// A queued response for the offset parent/rect of a node.
     pub offset_parent_response: OffsetParentResponse,
 
@@ -88,6 +96,9 @@ pub struct LayoutThreadData {
 
     /// A queued response for the inner text of a given element.
     pub element_inner_text_response: String,
+
+    /// A queued response for the PDF serialisation of the paged document.
+    pub pdf_response: Option<Vec<u8>>,
 }
 
 pub struct LayoutRPCImpl(pub Arc<Mutex<LayoutThreadData>>);
@@ -153,6 +164,13 @@ impl LayoutRPC for LayoutRPCImpl {
         ResolvedStyleResponse(rw_data.resolved_style_response.clone())
     }
 
+    /// Retrieves the resolved font style for canvas.
+    fn resolved_font_style(&self) -> Option<ServoArc<style_structs::Font>> {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        rw_data.resolved_font_style_response.clone()
+    }
+
     fn offset_parent(&self) -> OffsetParentResponse {
         let &LayoutRPCImpl(ref rw_data) = self;
         let rw_data = rw_data.lock().unwrap();
@@ -176,6 +194,12 @@ impl LayoutRPC for LayoutRPCImpl {
         let rw_data = rw_data.lock().unwrap();
         rw_data.element_inner_text_response.clone()
     }
+
+    fn pdf(&self) -> Option<Vec<u8>> {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let mut rw_data = rw_data.lock().unwrap();
+        rw_data.pdf_response.take()
+    }
 }
 
 struct UnioningFragmentBorderBoxIterator {
@@ -716,6 +740,62 @@ pub fn process_resolved_style_request<'a, N>(context: &LayoutContext,
     style.computed_value_to_string(PropertyDeclarationId::Longhand(longhand_id))
 }
 
+/// Return the computed `font` of a canvas 2D context whose `font` attribute is set to
+/// `value`, with relative values resolved against the style of the canvas element.
+/// <https://html.spec.whatwg.org/multipage/#dom-context-2d-font>
+pub fn process_resolved_font_style_request<'a, N>(context: &LayoutContext,
+                                                  node: N,
+                                                  value: &str,
+                                                  property: &PropertyId,
+                                                  url_data: ServoUrl,
+                                                  shared_lock: &SharedRwLock)
+                                                  -> Option<ServoArc<style_structs::Font>>
+    where N: LayoutNode,
+{
+    use style::stylist::RuleInclusion;
+    use style::traversal::resolve_style;
+
+    // Parse the given font property value.
+    let quirks_mode = context.style_context.quirks_mode();
+    let mut declarations = SourcePropertyDeclaration::new();
+    let result = parse_one_declaration_into(&mut declarations,
+                                            property.clone(),
+                                            value,
+                                            &url_data,
+                                            None,
+                                            ParsingMode::DEFAULT,
+                                            quirks_mode);
+    if result.is_err() {
+        return None;
+    }
+    let mut block = PropertyDeclarationBlock::new();
+    block.extend(declarations.drain(), Importance::Normal);
+
+    // Get the style of the canvas element, which is not styled if it is in a
+    // display: none subtree.
+    let element = node.as_element().unwrap();
+    let parent_style = if element.get_data().is_some() {
+        node.to_threadsafe().as_element().unwrap().resolved_style()
+    } else {
+        let mut tlc = ThreadLocalStyleContext::new(&context.style_context);
+        let mut context = StyleContext {
+            shared: &context.style_context,
+            thread_local: &mut tlc,
+        };
+        let styles = resolve_style(&mut context, element, RuleInclusion::All, None);
+        styles.primary().clone()
+    };
+
+    // Compute the parsed value as if it was set on a child of the canvas element.
+    let declarations = ServoArc::new(shared_lock.wrap(block));
+    let computed = context.style_context.stylist.compute_for_declarations::<N::ConcreteElement>(
+        &context.style_context.guards,
+        &*parent_style,
+        declarations,
+    );
+    Some(computed.clone_font())
+}
+
 /// The primary resolution logic, which assumes that the element is styled.
 fn process_resolved_style_request_internal<'a, N>(
     requested_node: N,
@@ -776,6 +856,13 @@ where
     // There are probably other quirks.
     let applies = true;
 
+    // Elements with `display: none` or `display: contents` have no box to take used values
+    // from, so their resolved values are their computed values.
+    let generates_box = match style.get_box().display {
+        Display::None | Display::Contents => false,
+        _ => true,
+    };
+
     fn used_value_for_position_property<N: LayoutNode>(
             layout_el: <N::ConcreteThreadSafeLayoutNode as ThreadSafeLayoutNode>::ConcreteThreadSafeLayoutElement,
             layout_root: &mut Flow,
@@ -815,7 +902,7 @@ where
         LonghandId::MarginLeft | LonghandId::MarginRight |
         LonghandId::PaddingBottom | LonghandId::PaddingTop |
         LonghandId::PaddingLeft | LonghandId::PaddingRight
-        if applies && style.get_box().display != Display::None => {
+        if applies && generates_box => {
             let (margin_padding, side) = match longhand_id {
                 LonghandId::MarginBottom => (MarginPadding::Margin, Side::Bottom),
                 LonghandId::MarginTop => (MarginPadding::Margin, Side::Top),
@@ -838,11 +925,11 @@ where
         },
 
         LonghandId::Bottom | LonghandId::Top | LonghandId::Right | LonghandId::Left
-        if applies && positioned && style.get_box().display != Display::None => {
+        if applies && positioned && generates_box => {
             used_value_for_position_property(layout_el, layout_root, requested_node, longhand_id)
         }
         LonghandId::Width | LonghandId::Height
-        if applies && style.get_box().display != Display::None => {
+        if applies && generates_box => {
             used_value_for_position_property(layout_el, layout_root, requested_node, longhand_id)
         }
         // FIXME: implement used value computation for line-height
@@ -1002,7 +1089,7 @@ fn inner_text_collection_steps<N: LayoutNode>(node: N,
                     "\u{000A}", /* line feed */
                 )));
             },
-            Display::Block | Display::Flex | Display::TableCaption | Display::Table => {
+            Display::Block | Display::Flex | Display::Grid | Display::TableCaption | Display::Table => {
                 // Step 9.
                 items.insert(0, InnerTextItem::RequiredLineBreakCount(1));
                 items.push(InnerTextItem::RequiredLineBreakCount(1));
@@ -0,0 +1,529 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Float areas defined by `shape-outside`, as described in CSS Shapes Level 1.
+//!
+//! The float area of a float is sampled into rows of one CSS pixel each. For every row we record
+//! the inline extent of the shape in that row, which is all that line layout needs in order to
+//! wrap content around the float.
+
+use app_units::{Au, AU_PER_PX};
+use context::LayoutContext;
+use euclid::{Point2D, Rect, SideOffsets2D, Size2D};
+use fragment::Fragment;
+use model;
+use net_traits::image::base::{Image, PixelFormat};
+use net_traits::image_cache::{ImageOrMetadataAvailable, UsePlaceholder};
+use std::cmp::{max, min};
+use style::properties::ComputedValues;
+use style::values::computed::LengthOrPercentage;
+use style::values::computed::basic_shape::{BasicShape, ShapeRadius};
+use style::values::generics::basic_shape::{ShapeBox, ShapeSource};
+use style::values::generics::image::Image as StyleImage;
+use style::values::generics::size::Size;
+
+/// The block size of a single row of a shape outline.
+const ROW_SIZE: Au = Au(AU_PER_PX);
+
+/// The float area of a float with a `shape-outside`, relative to the inline-start and block-start
+/// edges of its margin box.
+#[derive(Clone, Debug, Serialize)]
+pub struct ShapeOutline {
+    /// The inline-start and inline-end edges of the shape in each row, or `None` if the shape
+    /// does not extend into that row.
+    rows: Vec<Option<(Au, Au)>>,
+}
+
+impl ShapeOutline {
+    /// Computes the float area of the given fragment, which must have its final border box and
+    /// margins. Percentages in `shape-margin` are resolved against `containing_block_inline_size`.
+    /// Returns `None` if the float area is just the margin box, either because there is no
+    /// `shape-outside` or because the shape cannot be used (e.g. its image has not loaded).
+    ///
+    /// FIXME: Shapes are only supported in horizontal writing modes.
+    pub fn for_fragment(fragment: &Fragment,
+                        containing_block_inline_size: Au,
+                        layout_context: &LayoutContext)
+                        -> Option<ShapeOutline> {
+        let style = &*fragment.style;
+        let box_style = style.get_box();
+        if let ShapeSource::None = box_style.shape_outside {
+            return None
+        }
+
+        let writing_mode = style.writing_mode;
+        if writing_mode.is_vertical() {
+            return None
+        }
+
+        let boxes = ReferenceBoxes::new(fragment);
+        if boxes.margin.size.width <= 0.0 || boxes.margin.size.height <= 0.0 {
+            return None
+        }
+
+        let row_count = (boxes.margin.size.height.ceil() as usize).max(1);
+        let mut rows = match box_style.shape_outside {
+            ShapeSource::None => return None,
+            ShapeSource::Box(ref reference_box) => {
+                let rect = boxes.get(reference_box);
+                let radii = boxes.radii(reference_box, style);
+                sample_rows(row_count, |top, bottom| rounded_rect_extent(&rect, &radii, top, bottom))
+            }
+            ShapeSource::Shape(ref shape, ref reference_box) => {
+                let rect = boxes.get(reference_box.as_ref().unwrap_or(&ShapeBox::MarginBox));
+                basic_shape_rows(shape, &rect, row_count)
+            }
+            ShapeSource::ImageOrUrl(StyleImage::Url(ref image_url)) => {
+                let url = image_url.url()?;
+                let image = match layout_context.get_or_request_image_or_meta(fragment.node,
+                                                                               url.clone(),
+                                                                               UsePlaceholder::No) {
+                    Some(ImageOrMetadataAvailable::ImageAvailable(image, _)) => image,
+                    _ => return None,
+                };
+                image_rows(&image, &boxes.content, box_style.shape_image_threshold, row_count)
+            }
+            // TODO: Support gradients and the other image types.
+            ShapeSource::ImageOrUrl(_) => return None,
+        };
+
+        let shape_margin =
+            box_style.shape_margin.to_used_value(containing_block_inline_size).to_f32_px();
+        if shape_margin > 0.0 {
+            rows = expand_rows(&rows, shape_margin);
+        }
+
+        // The float area is clipped to the margin box.
+        let width = boxes.margin.size.width;
+        let is_ltr = writing_mode.is_bidi_ltr();
+        let rows = rows.into_iter().map(|row| {
+            let (left, right) = row?;
+            let (left, right) = (left.max(0.0), right.min(width));
+            if left > right {
+                return None
+            }
+            let (start, end) = if is_ltr { (left, right) } else { (width - right, width - left) };
+            Some((Au::from_f32_px(start), Au::from_f32_px(end)))
+        }).collect();
+
+        Some(ShapeOutline {
+            rows: rows,
+        })
+    }
+
+    /// Returns the union of the inline extents of the shape in the rows between `block_start` and
+    /// `block_end`, or `None` if the shape does not extend into any of those rows. At least one
+    /// row is always examined.
+    pub fn extent(&self, block_start: Au, block_end: Au) -> Option<(Au, Au)> {
+        let first_row = row_index(block_start);
+        let last_row = max(row_index(block_end - Au(1)), first_row);
+        let mut extent: Option<(Au, Au)> = None;
+        for row in self.rows.iter().take(last_row + 1).skip(first_row) {
+            if let Some((start, end)) = *row {
+                extent = Some(match extent {
+                    None => (start, end),
+                    Some((old_start, old_end)) => (min(start, old_start), max(end, old_end)),
+                })
+            }
+        }
+        extent
+    }
+
+    /// Returns the block-start position of the first row at or after `block_start` in which the
+    /// shape intersects the inline range from `inline_start` to `inline_end`.
+    pub fn first_intersecting_row(&self, block_start: Au, inline_start: Au, inline_end: Au)
+                                  -> Option<Au> {
+        let first_row = row_index(block_start);
+        self.rows.iter().enumerate().skip(first_row).find(|&(_, row)| {
+            match *row {
+                Some((start, end)) => start < inline_end && end > inline_start,
+                None => false,
+            }
+        }).map(|(index, _)| max(ROW_SIZE * index as i32, block_start))
+    }
+}
+
+fn row_index(position: Au) -> usize {
+    if position <= Au(0) {
+        0
+    } else {
+        (position.0 / ROW_SIZE.0) as usize
+    }
+}
+
+/// The reference boxes of a fragment in CSS pixels, relative to the top left corner of its margin
+/// box.
+struct ReferenceBoxes {
+    margin: Rect<f32>,
+    border: Rect<f32>,
+    padding: Rect<f32>,
+    content: Rect<f32>,
+    margin_widths: SideOffsets2D<f32>,
+    border_widths: SideOffsets2D<f32>,
+    padding_widths: SideOffsets2D<f32>,
+}
+
+impl ReferenceBoxes {
+    fn new(fragment: &Fragment) -> ReferenceBoxes {
+        let writing_mode = fragment.style.writing_mode;
+        let to_px = |offsets: SideOffsets2D<Au>| {
+            SideOffsets2D::new(offsets.top.to_f32_px(),
+                               offsets.right.to_f32_px(),
+                               offsets.bottom.to_f32_px(),
+                               offsets.left.to_f32_px())
+        };
+        let margin_widths = to_px(fragment.margin.to_physical(writing_mode));
+        let border_widths = to_px(fragment.style.logical_border_width().to_physical(writing_mode));
+        let border_padding = to_px(fragment.border_padding.to_physical(writing_mode));
+        let padding_widths = SideOffsets2D::new(border_padding.top - border_widths.top,
+                                                border_padding.right - border_widths.right,
+                                                border_padding.bottom - border_widths.bottom,
+                                                border_padding.left - border_widths.left);
+
+        let border_box_size = fragment.border_box.size.to_physical(writing_mode);
+        let border = Rect::new(Point2D::new(margin_widths.left, margin_widths.top),
+                               Size2D::new(border_box_size.width.to_f32_px(),
+                                           border_box_size.height.to_f32_px()));
+        let padding = deflate(&border, &border_widths);
+        ReferenceBoxes {
+            margin: inflate(&border, &margin_widths),
+            border: border,
+            padding: padding,
+            content: deflate(&padding, &padding_widths),
+            margin_widths: margin_widths,
+            border_widths: border_widths,
+            padding_widths: padding_widths,
+        }
+    }
+
+    fn get(&self, reference_box: &ShapeBox) -> Rect<f32> {
+        match *reference_box {
+            ShapeBox::MarginBox => self.margin,
+            ShapeBox::BorderBox => self.border,
+            ShapeBox::PaddingBox => self.padding,
+            ShapeBox::ContentBox => self.content,
+        }
+    }
+
+    /// Returns the corner radii of the given box, derived from `border-radius` as described in
+    /// CSS Shapes § 4.2.
+    fn radii(&self, reference_box: &ShapeBox, style: &ComputedValues) -> Radii {
+        let border_style = style.get_border();
+        let size = Size2D::new(Au::from_f32_px(self.border.size.width),
+                               Au::from_f32_px(self.border.size.height));
+        let corner = |radius| {
+            let radius = model::specified_border_radius(radius, size);
+            Size2D::new(radius.width.to_f32_px(), radius.height.to_f32_px())
+        };
+        let radii = Radii {
+            top_left: corner(border_style.border_top_left_radius),
+            top_right: corner(border_style.border_top_right_radius),
+            bottom_right: corner(border_style.border_bottom_right_radius),
+            bottom_left: corner(border_style.border_bottom_left_radius),
+        };
+        match *reference_box {
+            ShapeBox::MarginBox => radii.adjusted(&self.margin_widths, 1.0),
+            ShapeBox::BorderBox => radii,
+            ShapeBox::PaddingBox => radii.adjusted(&self.border_widths, -1.0),
+            ShapeBox::ContentBox => {
+                radii.adjusted(&self.border_widths, -1.0).adjusted(&self.padding_widths, -1.0)
+            }
+        }
+    }
+}
+
+fn inflate(rect: &Rect<f32>, offsets: &SideOffsets2D<f32>) -> Rect<f32> {
+    Rect::new(Point2D::new(rect.origin.x - offsets.left, rect.origin.y - offsets.top),
+              Size2D::new(rect.size.width + offsets.left + offsets.right,
+                          rect.size.height + offsets.top + offsets.bottom))
+}
+
+fn deflate(rect: &Rect<f32>, offsets: &SideOffsets2D<f32>) -> Rect<f32> {
+    inflate(rect, &SideOffsets2D::new(-offsets.top, -offsets.right, -offsets.bottom, -offsets.left))
+}
+
+/// The elliptical corner radii of a rectangle, in CSS pixels.
+#[derive(Clone, Copy)]
+struct Radii {
+    top_left: Size2D<f32>,
+    top_right: Size2D<f32>,
+    bottom_right: Size2D<f32>,
+    bottom_left: Size2D<f32>,
+}
+
+impl Radii {
+    /// Grows (`sign` = 1) or shrinks (`sign` = -1) every nonzero radius by the adjacent offsets.
+    fn adjusted(self, offsets: &SideOffsets2D<f32>, sign: f32) -> Radii {
+        let adjust = |radius: Size2D<f32>, horizontal: f32, vertical: f32| {
+            if radius.width <= 0.0 || radius.height <= 0.0 {
+                return Size2D::zero()
+            }
+            Size2D::new((radius.width + sign * horizontal).max(0.0),
+                        (radius.height + sign * vertical).max(0.0))
+        };
+        Radii {
+            top_left: adjust(self.top_left, offsets.left, offsets.top),
+            top_right: adjust(self.top_right, offsets.right, offsets.top),
+            bottom_right: adjust(self.bottom_right, offsets.right, offsets.bottom),
+            bottom_left: adjust(self.bottom_left, offsets.left, offsets.bottom),
+        }
+    }
+
+    /// Scales the radii down so that adjacent corners do not overlap, per CSS Backgrounds § 5.5.
+    fn normalized(self, size: &Size2D<f32>) -> Radii {
+        let factor = |first: f32, second: f32, length: f32| {
+            if first + second > length { length / (first + second) } else { 1.0 }
+        };
+        let scale = factor(self.top_left.width, self.top_right.width, size.width)
+            .min(factor(self.bottom_left.width, self.bottom_right.width, size.width))
+            .min(factor(self.top_left.height, self.bottom_left.height, size.height))
+            .min(factor(self.top_right.height, self.bottom_right.height, size.height));
+        Radii {
+            top_left: self.top_left * scale,
+            top_right: self.top_right * scale,
+            bottom_right: self.bottom_right * scale,
+            bottom_left: self.bottom_left * scale,
+        }
+    }
+}
+
+/// Samples the horizontal extent of a shape in each one-pixel row of the margin box.
+fn sample_rows<F>(row_count: usize, extent: F) -> Vec<Option<(f32, f32)>>
+                  where F: Fn(f32, f32) -> Option<(f32, f32)> {
+    (0..row_count).map(|row| extent(row as f32, row as f32 + 1.0)).collect()
+}
+
+fn resolve(length: &LengthOrPercentage, basis: f32) -> f32 {
+    length.to_used_value(Au::from_f32_px(basis)).to_f32_px()
+}
+
+fn basic_shape_rows(shape: &BasicShape, reference_box: &Rect<f32>, row_count: usize)
+                    -> Vec<Option<(f32, f32)>> {
+    let size = reference_box.size;
+    let origin = reference_box.origin;
+    match *shape {
+        BasicShape::Inset(ref inset) => {
+            let offsets = SideOffsets2D::new(resolve(&inset.rect.0, size.height),
+                                             resolve(&inset.rect.1, size.width),
+                                             resolve(&inset.rect.2, size.height),
+                                             resolve(&inset.rect.3, size.width));
+            let rect = deflate(reference_box, &offsets);
+            let corner = |radius: &Size<LengthOrPercentage>| {
+                Size2D::new(resolve(radius.width(), rect.size.width),
+                            resolve(radius.height(), rect.size.height))
+            };
+            let radii = match inset.round {
+                Some(ref round) => Radii {
+                    top_left: corner(&round.top_left.0),
+                    top_right: corner(&round.top_right.0),
+                    bottom_right: corner(&round.bottom_right.0),
+                    bottom_left: corner(&round.bottom_left.0),
+                },
+                None => Radii {
+                    top_left: Size2D::zero(),
+                    top_right: Size2D::zero(),
+                    bottom_right: Size2D::zero(),
+                    bottom_left: Size2D::zero(),
+                },
+            };
+            sample_rows(row_count, |top, bottom| rounded_rect_extent(&rect, &radii, top, bottom))
+        }
+        BasicShape::Circle(ref circle) => {
+            let center = Point2D::new(origin.x + resolve(&circle.position.horizontal, size.width),
+                                      origin.y + resolve(&circle.position.vertical, size.height));
+            let (left, right) = (center.x - origin.x, origin.x + size.width - center.x);
+            let (top, bottom) = (center.y - origin.y, origin.y + size.height - center.y);
+            let radius = match circle.radius {
+                ShapeRadius::Length(ref length) => {
+                    let diagonal = (size.width * size.width + size.height * size.height).sqrt();
+                    resolve(length, diagonal / 2.0f32.sqrt())
+                }
+                ShapeRadius::ClosestSide => {
+                    left.abs().min(right.abs()).min(top.abs()).min(bottom.abs())
+                }
+                ShapeRadius::FarthestSide => {
+                    left.abs().max(right.abs()).max(top.abs()).max(bottom.abs())
+                }
+            };
+            sample_rows(row_count, |top, bottom| ellipse_extent(&center, radius, radius, top, bottom))
+        }
+        BasicShape::Ellipse(ref ellipse) => {
+            let center = Point2D::new(origin.x + resolve(&ellipse.position.horizontal, size.width),
+                                      origin.y + resolve(&ellipse.position.vertical, size.height));
+            let radius = |radius: &ShapeRadius, near: f32, far: f32, basis: f32| {
+                match *radius {
+                    ShapeRadius::Length(ref length) => resolve(length, basis),
+                    ShapeRadius::ClosestSide => near.abs().min(far.abs()),
+                    ShapeRadius::FarthestSide => near.abs().max(far.abs()),
+                }
+            };
+            let radius_x = radius(&ellipse.semiaxis_x,
+                                  center.x - origin.x,
+                                  origin.x + size.width - center.x,
+                                  size.width);
+            let radius_y = radius(&ellipse.semiaxis_y,
+                                  center.y - origin.y,
+                                  origin.y + size.height - center.y,
+                                  size.height);
+            sample_rows(row_count,
+                        |top, bottom| ellipse_extent(&center, radius_x, radius_y, top, bottom))
+        }
+        BasicShape::Polygon(ref polygon) => {
+            let vertices: Vec<Point2D<f32>> = polygon.coordinates.iter().map(|&(ref x, ref y)| {
+                Point2D::new(origin.x + resolve(x, size.width), origin.y + resolve(y, size.height))
+            }).collect();
+            sample_rows(row_count, |top, bottom| polygon_extent(&vertices, top, bottom))
+        }
+    }
+}
+
+/// Returns the horizontal extent of a rounded rectangle between `top` and `bottom`.
+fn rounded_rect_extent(rect: &Rect<f32>, radii: &Radii, top: f32, bottom: f32)
+                       -> Option<(f32, f32)> {
+    if rect.size.width < 0.0 || rect.size.height < 0.0 ||
+            bottom <= rect.min_y() || top >= rect.max_y() {
+        return None
+    }
+    let (top, bottom) = (top.max(rect.min_y()), bottom.min(rect.max_y()));
+    let radii = radii.normalized(&rect.size);
+
+    // The indentation of a side at `y` caused by its two corners. It is nonincreasing and then
+    // nondecreasing, so its minimum within the band is at one of the candidates below.
+    let indentation = |upper: &Size2D<f32>, lower: &Size2D<f32>, y: f32| {
+        let corner = |radius: &Size2D<f32>, distance: f32| {
+            if distance <= 0.0 || radius.height <= 0.0 {
+                return 0.0
+            }
+            let t = (distance / radius.height).min(1.0);
+            radius.width * (1.0 - (1.0 - t * t).sqrt())
+        };
+        corner(upper, rect.min_y() + upper.height - y).max(corner(lower, y - (rect.max_y() - lower.height)))
+    };
+    let min_indentation = |upper: &Size2D<f32>, lower: &Size2D<f32>| {
+        let flat_top = (rect.min_y() + upper.height).max(top).min(bottom);
+        let flat_bottom = (rect.max_y() - lower.height).max(top).min(bottom);
+        [top, bottom, flat_top, flat_bottom].iter()
+                                            .map(|&y| indentation(upper, lower, y))
+                                            .fold(::std::f32::MAX, f32::min)
+    };
+
+    Some((rect.min_x() + min_indentation(&radii.top_left, &radii.bottom_left),
+          rect.max_x() - min_indentation(&radii.top_right, &radii.bottom_right)))
+}
+
+/// Returns the horizontal extent of an ellipse between `top` and `bottom`.
+fn ellipse_extent(center: &Point2D<f32>, radius_x: f32, radius_y: f32, top: f32, bottom: f32)
+                  -> Option<(f32, f32)> {
+    if radius_x <= 0.0 || radius_y <= 0.0 ||
+            bottom <= center.y - radius_y || top >= center.y + radius_y {
+        return None
+    }
+    // The ellipse is widest at the point of the band nearest to its center.
+    let y = center.y.max(top).min(bottom);
+    let t = (y - center.y) / radius_y;
+    let half_width = radius_x * (1.0 - t * t).max(0.0).sqrt();
+    Some((center.x - half_width, center.x + half_width))
+}
+
+/// Returns the horizontal extent of a polygon between `top` and `bottom`. The part of the polygon
+/// within the band is bounded by the vertices inside the band and the points where the edges
+/// cross its top and bottom.
+fn polygon_extent(vertices: &[Point2D<f32>], top: f32, bottom: f32) -> Option<(f32, f32)> {
+    let mut extent: Option<(f32, f32)> = None;
+    {
+        let mut add = |x: f32| {
+            extent = Some(match extent {
+                None => (x, x),
+                Some((left, right)) => (left.min(x), right.max(x)),
+            })
+        };
+        for (index, vertex) in vertices.iter().enumerate() {
+            if vertex.y >= top && vertex.y <= bottom {
+                add(vertex.x)
+            }
+            let next = &vertices[(index + 1) % vertices.len()];
+            for &y in &[top, bottom] {
+                if (vertex.y < y && next.y > y) || (vertex.y > y && next.y < y) {
+                    add(vertex.x + (y - vertex.y) / (next.y - vertex.y) * (next.x - vertex.x))
+                }
+            }
+        }
+    }
+    extent
+}
+
+/// Computes the extents of the pixels of `image` whose alpha exceeds `threshold`, with the image
+/// stretched over the content box as if it were a replaced element.
+fn image_rows(image: &Image, content_box: &Rect<f32>, threshold: f32, row_count: usize)
+              -> Vec<Option<(f32, f32)>> {
+    let (width, height) = (image.width as usize, image.height as usize);
+    if width == 0 || height == 0 || content_box.size.width <= 0.0 ||
+            content_box.size.height <= 0.0 {
+        return vec![None; row_count]
+    }
+
+    let alpha = |x: usize, y: usize| -> u8 {
+        match image.format {
+            PixelFormat::BGRA8 => image.bytes[(y * width + x) * 4 + 3],
+            PixelFormat::KA8 => image.bytes[(y * width + x) * 2 + 1],
+            PixelFormat::K8 | PixelFormat::RGB8 => 255,
+        }
+    };
+    let threshold = (threshold.max(0.0).min(1.0) * 255.0) as u8;
+    let pixel_rows: Vec<Option<(usize, usize)>> = (0..height).map(|y| {
+        let first = (0..width).find(|&x| alpha(x, y) > threshold)?;
+        let last = (0..width).rev().find(|&x| alpha(x, y) > threshold)?;
+        Some((first, last + 1))
+    }).collect();
+
+    let scale_x = content_box.size.width / width as f32;
+    let scale_y = content_box.size.height / height as f32;
+    sample_rows(row_count, |top, bottom| {
+        if bottom <= content_box.min_y() || top >= content_box.max_y() {
+            return None
+        }
+        let first = min(((top - content_box.min_y()) / scale_y).floor().max(0.0) as usize,
+                        height - 1);
+        let last = (((bottom - content_box.min_y()) / scale_y).ceil() as usize).min(height);
+        pixel_rows[first..max(last, first + 1).min(height)].iter().filter_map(|row| *row).fold(
+            None,
+            |extent, (start, end)| {
+                let (start, end) = (content_box.min_x() + start as f32 * scale_x,
+                                    content_box.min_x() + end as f32 * scale_x);
+                Some(match extent {
+                    None => (start, end),
+                    Some((left, right)) => (start.min(left), end.max(right)),
+                })
+            })
+    })
+}
+
+/// Grows every row by `shape_margin` in all directions, approximating the margin around each row
+/// with a circle.
+fn expand_rows(rows: &[Option<(f32, f32)>], shape_margin: f32) -> Vec<Option<(f32, f32)>> {
+    let reach = shape_margin.ceil() as usize + 1;
+    let mut expanded = vec![None; rows.len()];
+    for (index, row) in rows.iter().enumerate() {
+        let (left, right) = match *row {
+            Some(extent) => extent,
+            None => continue,
+        };
+        let first = index.saturating_sub(reach);
+        let last = min(index + reach, rows.len() - 1);
+        for (other_index, other_row) in expanded.iter_mut().enumerate().take(last + 1).skip(first) {
+            let distance = (max(index, other_index) - min(index, other_index)) as f32;
+            let dy = (distance - 1.0).max(0.0);
+            if dy > shape_margin {
+                continue
+            }
+            let dx = (shape_margin * shape_margin - dy * dy).sqrt();
+            let (new_left, new_right) = (left - dx, right + dx);
+            *other_row = Some(match *other_row {
+                None => (new_left, new_right),
+                Some((old_left, old_right)) => (old_left.min(new_left), old_right.max(new_right)),
+            });
+        }
+    }
+    expanded
+}
@@ -0,0 +1,143 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+extern crate layout;
+extern crate webrender_api;
+
+use layout::display_list::items::{BaseDisplayItem, ClipScrollNode, DisplayItem, DisplayList};
+use layout::display_list::items::{Gradient, GradientDisplayItem, SolidColorDisplayItem};
+use layout::display_list::pdf::PdfWriter;
+use std::str;
+use webrender_api::{ColorF, ExtendMode, GradientStop, LayoutPoint, LayoutRect, LayoutSize};
+
+fn display_list(list: Vec<DisplayItem>) -> DisplayList {
+    DisplayList {
+        list: list,
+        // The root reference frame and the root scroll node, which don't clip.
+        clip_scroll_nodes: vec![ClipScrollNode::placeholder(), ClipScrollNode::placeholder()],
+        scroll_snap_points: vec![],
+    }
+}
+
+fn base(bounds: LayoutRect) -> BaseDisplayItem {
+    let mut base = BaseDisplayItem::empty();
+    base.bounds = bounds;
+    base.clip_rect = bounds;
+    base
+}
+
+fn find(haystack: &[u8], needle: &str) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle.as_bytes())
+}
+
+fn rfind(haystack: &[u8], needle: &str) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle.as_bytes())
+}
+
+fn contains(pdf: &[u8], needle: &str) -> bool {
+    find(pdf, needle).is_some()
+}
+
+/// Returns the line that starts at `offset`, without its line feed.
+fn line_at(pdf: &[u8], offset: usize) -> &str {
+    let end = pdf[offset..].iter().position(|&byte| byte == b'\n').unwrap();
+    str::from_utf8(&pdf[offset..offset + end]).unwrap()
+}
+
+/// Checks the cross-reference table and the trailer of a PDF file, and returns the number of
+/// objects in it.
+fn check_xref(pdf: &[u8]) -> usize {
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+
+    let startxref = rfind(pdf, "startxref\n").unwrap() + "startxref\n".len();
+    let xref: usize = line_at(pdf, startxref).parse().unwrap();
+    assert_eq!(line_at(pdf, xref), "xref");
+
+    let header = line_at(pdf, xref + "xref\n".len());
+    let size: usize = header.trim_left_matches("0 ").parse().unwrap();
+    let mut entry = xref + "xref\n".len() + header.len() + 1;
+    assert_eq!(&pdf[entry..entry + 20], b"0000000000 65535 f \n");
+    for object in 1..size {
+        entry += 20;
+        let entry_line = str::from_utf8(&pdf[entry..entry + 20]).unwrap();
+        assert!(entry_line.ends_with(" 00000 n \n"), "Malformed entry {:?}", entry_line);
+        let offset: usize = entry_line[..10].parse().unwrap();
+        assert_eq!(line_at(pdf, offset), format!("{} 0 obj", object));
+    }
+    assert_eq!(line_at(pdf, entry + 20), "trailer");
+    assert!(contains(pdf, &format!("<< /Size {} /Root 1 0 R >>", size)));
+    size - 1
+}
+
+#[test]
+fn test_empty_page() {
+    let mut writer = PdfWriter::new();
+    writer.add_page(&display_list(vec![]), LayoutSize::new(100., 200.), LayoutPoint::zero());
+    let pdf = writer.finish();
+
+    // The catalog, the page tree, the page and its content stream.
+    assert_eq!(check_xref(&pdf), 4);
+    assert!(contains(&pdf, "1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n"));
+    assert!(contains(&pdf, "2 0 obj\n<< /Type /Pages /Kids [4 0 R] /Count 1 >>\nendobj\n"));
+    assert!(contains(&pdf, "/Type /Page /Parent 2 0 R /MediaBox [0 0 75 150]"));
+    assert!(contains(&pdf, "/Contents 3 0 R >>"));
+    // Pixels are scaled to points, with the origin at the top left corner.
+    let content = "0.75 0 0 -0.75 0 150 cm\n";
+    assert!(contains(&pdf, &format!("3 0 obj\n<<  /Length {} >>\nstream\n{}\nendstream\nendobj\n",
+                                    content.len(), content)));
+}
+
+#[test]
+fn test_pages() {
+    let mut writer = PdfWriter::new();
+    let red = ColorF::new(1., 0., 0., 1.);
+    let rect = LayoutRect::new(LayoutPoint::new(10., 20.), LayoutSize::new(30., 40.));
+    let item = DisplayItem::SolidColor(Box::new(SolidColorDisplayItem { base: base(rect), color: red }));
+    let size = LayoutSize::new(100., 100.);
+    writer.add_page(&display_list(vec![item.clone()]), size, LayoutPoint::zero());
+    // The second page shows the document from 50px down.
+    writer.add_page(&display_list(vec![item]), size, LayoutPoint::new(0., -50.));
+    let pdf = writer.finish();
+
+    assert_eq!(check_xref(&pdf), 6);
+    assert!(contains(&pdf, "<< /Type /Pages /Kids [4 0 R 6 0 R] /Count 2 >>"));
+    assert!(contains(&pdf, "q\n10 20 30 40 re W n\n1 0 0 1 0 0 cm\n1 0 0 rg\n10 20 30 40 re f\nQ\n"));
+    // Only the part of the item that is on the page is drawn.
+    assert!(contains(&pdf, "q\n10 0 30 10 re W n\n1 0 0 1 0 -50 cm\n1 0 0 rg\n10 20 30 40 re f\nQ\n"));
+}
+
+#[test]
+fn test_linear_gradient() {
+    let stops = vec![
+        GradientStop { offset: 0., color: ColorF::new(1., 0., 0., 1.) },
+        GradientStop { offset: 0.5, color: ColorF::new(0., 1., 0., 1.) },
+        GradientStop { offset: 1., color: ColorF::new(0., 0., 1., 1.) },
+    ];
+    let bounds = LayoutRect::new(LayoutPoint::zero(), LayoutSize::new(100., 50.));
+    let item = DisplayItem::Gradient(Box::new(GradientDisplayItem {
+        base: base(bounds),
+        gradient: Gradient {
+            start_point: LayoutPoint::zero(),
+            end_point: LayoutPoint::new(50., 0.),
+            stops: stops,
+            extend_mode: ExtendMode::Clamp,
+        },
+        tile: LayoutSize::new(50., 50.),
+        tile_spacing: LayoutSize::zero(),
+    }));
+    let mut writer = PdfWriter::new();
+    writer.add_page(&display_list(vec![item]), LayoutSize::new(100., 100.), LayoutPoint::zero());
+    let pdf = writer.finish();
+
+    check_xref(&pdf);
+    assert!(contains(&pdf, "/Shading << /Sh0 << /ShadingType 2 /Coords [0 0 50 0] /ColorSpace /DeviceRGB \
+                            /Function << /FunctionType 3 /Domain [0 1] /Functions \
+                            [<< /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 1 0] /N 1 >> \
+                            << /FunctionType 2 /Domain [0 1] /C0 [0 1 0] /C1 [0 0 1] /N 1 >>] \
+                            /Bounds [0.5] /Encode [0 1 0 1] >> /Extend [true true] >> >>"));
+    // Each tile is filled with the shading.
+    assert!(contains(&pdf, "q 1 0 0 1 0 0 cm 0 0 50 50 re W n  /Sh0 sh Q\n"));
+    assert!(contains(&pdf, "q 1 0 0 1 50 0 cm 0 0 50 50 re W n  /Sh0 sh Q\n"));
+}
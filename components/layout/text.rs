@@ -23,6 +23,8 @@ use std::borrow::ToOwned;
 use std::collections::LinkedList;
 use std::mem;
 use std::sync::Arc;
+use style::computed_values::hyphens::T as Hyphens;
+use style::computed_values::text_orientation::T as TextOrientation;
 use style::computed_values::text_rendering::T as TextRendering;
 use style::computed_values::text_transform::T as TextTransform;
 use style::computed_values::white_space::T as WhiteSpace;
@@ -30,6 +32,7 @@ use style::computed_values::word_break::T as WordBreak;
 use style::logical_geometry::{LogicalSize, WritingMode};
 use style::properties::ComputedValues;
 use style::properties::style_structs::Font as FontStyleStruct;
+use style::values::computed::HyphenateCharacter;
 use style::values::generics::text::LineHeight;
 use unicode_bidi as bidi;
 use unicode_script::{Script, get_script};
@@ -159,6 +162,7 @@ impl TextRunScanner {
 
         // Concatenate all of the transformed strings together, saving the new character indices.
         let mut mappings: Vec<RunMapping> = Vec::new();
+        let blocked_by_web_font;
         let runs = {
             let font_group;
             let compression;
@@ -167,11 +171,15 @@ impl TextRunScanner {
             let word_spacing;
             let text_rendering;
             let word_break;
+            let hyphens;
+            let hyphenate_character;
+            let text_orientation;
             {
                 let in_fragment = self.clump.front().unwrap();
                 let font_style = in_fragment.style().clone_font();
                 let inherited_text_style = in_fragment.style().get_inherited_text();
                 font_group = font_context.font_group(font_style);
+                blocked_by_web_font = font_group.borrow_mut().is_blocked_by_web_font(&mut font_context);
                 compression = match in_fragment.white_space() {
                     WhiteSpace::Normal |
                     WhiteSpace::Nowrap => CompressionMode::CompressWhitespaceNewline,
@@ -186,6 +194,17 @@ impl TextRunScanner {
                                .unwrap_or((Au(0), NotNan::new(0.0).unwrap()));
                 text_rendering = inherited_text_style.text_rendering;
                 word_break = inherited_text_style.word_break;
+                hyphens = inherited_text_style.hyphens;
+                hyphenate_character = inherited_text_style.hyphenate_character.clone();
+                // Glyphs are only set upright in vertical lines, and combined text is laid out
+                // horizontally.
+                text_orientation = if in_fragment.style.writing_mode.is_vertical() &&
+                    !in_fragment.combines_text_upright()
+                {
+                    Some(in_fragment.style().get_inherited_box().text_orientation)
+                } else {
+                    None
+                };
             }
 
             // First, transform/compress text of all the nodes.
@@ -239,10 +258,18 @@ impl TextRunScanner {
                             None => false
                         };
 
+                        // Break the run where the glyph orientation changes in vertical text.
+                        let upright = match text_orientation {
+                            Some(TextOrientation::Upright) => true,
+                            Some(TextOrientation::Mixed) => util::is_upright_in_vertical_text(character),
+                            Some(TextOrientation::Sideways) | None => false,
+                        };
+
                         // Now, if necessary, flush the mapping we were building up.
                         let flush_run = !run_info.has_font(&font) ||
                                         run_info.bidi_level != bidi_level ||
-                                        !compatible_script;
+                                        !compatible_script ||
+                                        run_info.upright != upright;
                         let new_mapping_needed = flush_run || mapping.selected != selected;
 
                         if new_mapping_needed {
@@ -270,6 +297,7 @@ impl TextRunScanner {
                             run_info.font = font;
                             run_info.bidi_level = bidi_level;
                             run_info.script = script;
+                            run_info.upright = upright;
                             mapping.selected = selected;
                         }
                     }
@@ -324,6 +352,9 @@ impl TextRunScanner {
                 if run_info.bidi_level.is_rtl() {
                     options.flags.insert(ShapingFlags::RTL_FLAG);
                 }
+                if run_info.upright {
+                    options.flags.insert(ShapingFlags::VERTICAL_UPRIGHT_FLAG);
+                }
 
                 // If no font is found (including fallbacks), there's no way we can render.
                 let font =
@@ -331,11 +362,24 @@ impl TextRunScanner {
                         .or_else(|| font_group.borrow_mut().first(&mut font_context))
                         .expect("No font found for text run!");
 
-                let (run, break_at_zero) = TextRun::new(&mut *font.borrow_mut(),
-                                                        run_info.text,
-                                                        &options,
-                                                        run_info.bidi_level,
-                                                        linebreaker);
+                let mut font = font.borrow_mut();
+                let (mut run, break_at_zero) = TextRun::new(&mut *font,
+                                                            run_info.text,
+                                                            &options,
+                                                            run_info.bidi_level,
+                                                            linebreaker);
+                if hyphens != Hyphens::None {
+                    let hyphenate_character = match hyphenate_character {
+                        // Prefer U+2010 HYPHEN, but fall back to U+002D HYPHEN-MINUS, which
+                        // many more fonts have a glyph for.
+                        HyphenateCharacter::Auto if font.glyph_index('\u{2010}').is_some() => {
+                            "\u{2010}"
+                        },
+                        HyphenateCharacter::Auto => "-",
+                        HyphenateCharacter::String(ref string) => &**string,
+                    };
+                    run.shape_hyphenate_character(&mut *font, hyphenate_character, &options);
+                }
                 result.push((ScannedTextRun {
                     run: Arc::new(run),
                     insertion_point: run_info.insertion_point,
@@ -394,6 +438,10 @@ impl TextRunScanner {
                     flags.insert(ScannedTextFlags::SELECTED);
                 }
 
+                if blocked_by_web_font {
+                    flags.insert(ScannedTextFlags::BLOCKED_BY_WEB_FONT);
+                }
+
                 let insertion_point = if mapping.contains_insertion_point(scanned_run.insertion_point) {
                     scanned_run.insertion_point
                 } else {
@@ -410,7 +458,11 @@ impl TextRunScanner {
 
                 let new_metrics = new_text_fragment_info.run.metrics_for_range(&byte_range);
                 let writing_mode = old_fragment.style.writing_mode;
-                let bounding_box_size = bounding_box_for_run_metrics(&new_metrics, writing_mode);
+                let bounding_box_size = bounding_box_for_run_metrics(
+                    &new_metrics,
+                    writing_mode,
+                    old_fragment.combines_text_upright(),
+                );
                 new_text_fragment_info.content_size = bounding_box_size;
 
                 let mut new_fragment = old_fragment.transform(
@@ -446,11 +498,22 @@ impl TextRunScanner {
     }
 }
 
+/// Returns the size of the text with the given metrics in a line of the given writing mode.
+///
+/// The advance of sideways and upright glyphs alike runs along the line, but text combined
+/// upright in a vertical line is laid out horizontally across it.
 #[inline]
-fn bounding_box_for_run_metrics(metrics: &RunMetrics, writing_mode: WritingMode)
+fn bounding_box_for_run_metrics(metrics: &RunMetrics,
+                                writing_mode: WritingMode,
+                                combines_text_upright: bool)
                                 -> LogicalSize<Au> {
-    // TODO: When the text-orientation property is supported, the block and inline directions may
-    // be swapped for horizontal glyphs in vertical lines.
+    if combines_text_upright {
+        // TODO: Compress text that is wider than 1em to fit.
+        return LogicalSize::new(
+            writing_mode,
+            metrics.bounding_box.size.height,
+            metrics.bounding_box.size.width)
+    }
     LogicalSize::new(
         writing_mode,
         metrics.bounding_box.size.width,
@@ -558,6 +621,8 @@ struct RunInfo {
     bidi_level: bidi::Level,
     /// The Unicode script property of this text run.
     script: Script,
+    /// Whether the glyphs of this text run are set upright in a vertical line.
+    upright: bool,
 }
 
 impl RunInfo {
@@ -568,6 +633,7 @@ impl RunInfo {
             font: None,
             bidi_level: bidi::Level::ltr(),
             script: Script::Common,
+            upright: false,
         }
     }
 
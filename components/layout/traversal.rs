@@ -200,8 +200,8 @@ fn construct_flows_at<N>(context: &LayoutContext, node: N)
     {
         let tnode = node.to_threadsafe();
 
-        // Always reconstruct if incremental layout is turned off.
-        let nonincremental_layout = opts::get().nonincremental_layout;
+        // Always reconstruct if incremental layout is turned off, or if layout asked for it.
+        let nonincremental_layout = opts::get().nonincremental_layout || context.reconstruct_all_flows;
         if nonincremental_layout || tnode.restyle_damage() != RestyleDamage::empty() ||
            node.as_element().map_or(false, |el| el.has_dirty_descendants()) {
             let mut flow_constructor = FlowConstructor::new(context);
@@ -60,7 +60,7 @@ use embedder_traits::resources::{self, Resource};
 use euclid::{Point2D, Rect, Size2D, TypedScale, TypedSize2D};
 use fnv::FnvHashMap;
 use gfx::font;
-use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_cache_thread::{FontCacheThread, WebFontLoadUpdate};
 use gfx::font_context;
 use gfx_traits::{Epoch, node_id_from_scroll_id};
 use histogram::Histogram;
@@ -78,11 +78,13 @@ use layout::flow::{Flow, GetBaseFlow, ImmutableFlowUtils, MutableOwnedFlowUtils}
 use layout::flow_ref::FlowRef;
 use layout::incremental::{LayoutDamageComputation, RelayoutMode, SpecialRestyleDamage};
 use layout::layout_debug;
+use layout::paged::{self, PageGeometry};
 use layout::parallel;
 use layout::query::{LayoutRPCImpl, LayoutThreadData, process_content_box_request, process_content_boxes_request};
 use layout::query::{process_element_inner_text_query, process_node_geometry_request};
 use layout::query::{process_node_scroll_area_request, process_node_scroll_id_request};
-use layout::query::{process_offset_parent_query, process_resolved_style_request, process_style_query};
+use layout::query::{process_offset_parent_query, process_resolved_font_style_request};
+use layout::query::{process_resolved_style_request, process_style_query};
 use layout::sequential;
 use layout::traversal::{ComputeStackingRelativePositions, PreorderFlowTraversal, RecalcStyleAndConstructFlows};
 use layout::wrapper::LayoutNodeLayoutData;
@@ -170,10 +172,10 @@ pub struct LayoutThread {
     pipeline_port: Receiver<LayoutControlMsg>,
 
     /// The port on which we receive messages from the font cache thread.
-    font_cache_receiver: Receiver<()>,
+    font_cache_receiver: Receiver<WebFontLoadUpdate>,
 
     /// The channel on which the font cache can send messages to us.
-    font_cache_sender: IpcSender<()>,
+    font_cache_sender: IpcSender<WebFontLoadUpdate>,
 
     /// The channel on which messages can be sent to the constellation.
     constellation_chan: IpcSender<ConstellationMsg>,
@@ -417,7 +419,7 @@ fn add_font_face_rules(stylesheet: &Stylesheet,
                        guard: &SharedRwLockReadGuard,
                        device: &Device,
                        font_cache_thread: &FontCacheThread,
-                       font_cache_sender: &IpcSender<()>,
+                       font_cache_sender: &IpcSender<WebFontLoadUpdate>,
                        outstanding_web_fonts_counter: &Arc<AtomicUsize>) {
     if opts::get().load_webfonts_synchronously {
         let (sender, receiver) = ipc::channel().unwrap();
@@ -426,8 +428,10 @@ fn add_font_face_rules(stylesheet: &Stylesheet,
                 let effective_sources = font_face.effective_sources();
                 font_cache_thread.add_web_font(font_face.family().clone(),
                                                effective_sources,
+                                               font_face.display(),
+                                               None,
                                                sender.clone());
-                receiver.recv().unwrap();
+                while let WebFontLoadUpdate::BlockPeriodEnded = receiver.recv().unwrap() {}
             }
         })
     } else {
@@ -437,6 +441,8 @@ fn add_font_face_rules(stylesheet: &Stylesheet,
                 outstanding_web_fonts_counter.fetch_add(1, Ordering::SeqCst);
                 font_cache_thread.add_web_font(font_face.family().clone(),
                                               effective_sources,
+                                              font_face.display(),
+                                              None,
                                               (*font_cache_sender).clone());
             }
         })
@@ -535,12 +541,14 @@ impl LayoutThread {
                     scroll_id_response: None,
                     scroll_area_response: Rect::zero(),
                     resolved_style_response: String::new(),
+                    resolved_font_style_response: None,
                     offset_parent_response: OffsetParentResponse::empty(),
                     style_response: StyleResponse(None),
                     scroll_offsets: HashMap::new(),
                     text_index_response: TextIndexResponse(None),
                     nodes_from_point_response: vec![],
                     element_inner_text_response: String::new(),
+                    pdf_response: None,
                 })),
             webrender_image_cache:
                 Arc::new(RwLock::new(FnvHashMap::default())),
@@ -600,6 +608,7 @@ impl LayoutThread {
             pending_images: if script_initiated_layout { Some(Mutex::new(vec![])) } else { None },
             newly_transitioning_nodes: if script_initiated_layout { Some(Mutex::new(vec![])) } else { None },
             registered_painters: &self.registered_painters,
+            reconstruct_all_flows: false,
         }
     }
 
@@ -608,7 +617,7 @@ impl LayoutThread {
         enum Request {
             FromPipeline(LayoutControlMsg),
             FromScript(Msg),
-            FromFontCache,
+            FromFontCache(WebFontLoadUpdate),
         }
 
         let request = {
@@ -623,8 +632,7 @@ impl LayoutThread {
                     Request::FromScript(msg.unwrap())
                 },
                 msg = port_from_font_cache.recv() => {
-                    msg.unwrap();
-                    Request::FromFontCache
+                    Request::FromFontCache(msg.unwrap())
                 }
             }
         };
@@ -654,9 +662,13 @@ impl LayoutThread {
             Request::FromScript(msg) => {
                 self.handle_request_helper(msg, possibly_locked_rw_data)
             },
-            Request::FromFontCache => {
+            Request::FromFontCache(update) => {
                 let _rw_data = possibly_locked_rw_data.lock();
-                self.outstanding_web_fonts.fetch_sub(1, Ordering::SeqCst);
+                // When a block period ends, the text waiting for the font is reflowed to make
+                // its fallback font visible, but the font is still outstanding.
+                if let WebFontLoadUpdate::Finished(_) = update {
+                    self.outstanding_web_fonts.fetch_sub(1, Ordering::SeqCst);
+                }
                 font_context::invalidate_font_caches();
                 self.script_chan.send(ConstellationControlMsg::WebFontLoaded(self.id)).unwrap();
                 true
@@ -748,6 +760,20 @@ impl LayoutThread {
                 let outstanding_web_fonts = self.outstanding_web_fonts.load(Ordering::SeqCst);
                 sender.send(outstanding_web_fonts != 0).unwrap();
             },
+            Msg::AddWebFont(family, sources, display, key, sender) => {
+                let sender = self.web_font_load_sender(sender);
+                self.font_cache_thread.add_web_font(family, sources, display, Some(key), sender);
+            },
+            Msg::AddWebFontData(family, bytes, key, sender) => {
+                let sender = self.web_font_load_sender(sender);
+                self.font_cache_thread.add_web_font_data(family, bytes, Some(key), sender);
+            },
+            Msg::RemoveWebFont(key) => {
+                self.font_cache_thread.remove_web_font(key);
+                // Text in the family of the font has to be reflowed, like when a font loads.
+                font_context::invalidate_font_caches();
+                self.script_chan.send(ConstellationControlMsg::WebFontLoaded(self.id)).unwrap();
+            },
             Msg::CreateLayoutThread(info) => {
                 self.create_layout_thread(info)
             }
@@ -905,6 +931,24 @@ impl LayoutThread {
         }
     }
 
+    /// Returns a sender for the font cache to report the load of a web font added by script.
+    ///
+    /// The result is forwarded to `script_sender`, and the load is otherwise treated like that
+    /// of a `@font-face` rule, so that text is reflowed once the font is available.
+    fn web_font_load_sender(&self, script_sender: IpcSender<bool>) -> IpcSender<WebFontLoadUpdate> {
+        self.outstanding_web_fonts.fetch_add(1, Ordering::SeqCst);
+        let font_cache_sender = self.font_cache_sender.clone();
+        let (sender, receiver) = ipc::channel().unwrap();
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let update = message.to().unwrap();
+            if let WebFontLoadUpdate::Finished(loaded) = update {
+                let _ = script_sender.send(loaded);
+            }
+            let _ = font_cache_sender.send(update);
+        }));
+        sender
+    }
+
     /// Advances the animation clock of the document.
     fn handle_advance_clock_ms<'a, 'b>(&mut self,
                                        how_many_ms: i32,
@@ -1032,7 +1076,27 @@ impl LayoutThread {
                     rw_data.indexable_text = std::mem::replace(
                         &mut build_state.indexable_text,
                         IndexableText::default());
-                    rw_data.display_list = Some(Arc::new(build_state.to_display_list()));
+                    let display_list = build_state.to_display_list();
+
+                    // Only tell the compositor about scroll snap points when they change, since
+                    // most reflows leave them alone.
+                    let scroll_snap_points_changed = match rw_data.display_list {
+                        Some(ref old_display_list) => {
+                            old_display_list.scroll_snap_points != display_list.scroll_snap_points
+                        },
+                        None => !display_list.scroll_snap_points.is_empty(),
+                    };
+                    if scroll_snap_points_changed {
+                        let msg = ConstellationMsg::SetScrollSnapPoints(
+                            self.id,
+                            display_list.scroll_snap_points.clone(),
+                        );
+                        if let Err(e) = self.constellation_chan.send(msg) {
+                            warn!("Layout scroll snap points to constellation failed ({}).", e);
+                        }
+                    }
+
+                    rw_data.display_list = Some(Arc::new(display_list));
                 }
             }
 
@@ -1136,6 +1200,9 @@ impl LayoutThread {
                         &QueryMsg::ResolvedStyleQuery(_, _, _) => {
                             rw_data.resolved_style_response = String::new();
                         },
+                        &QueryMsg::ResolvedFontStyleQuery(..) => {
+                            rw_data.resolved_font_style_response = None;
+                        },
                         &QueryMsg::OffsetParentQuery(_) => {
                             rw_data.offset_parent_response = OffsetParentResponse::empty();
                         },
@@ -1149,6 +1216,9 @@ impl LayoutThread {
                             rw_data.element_inner_text_response = String::new();
                         },
                     },
+                    ReflowGoal::Print => {
+                        rw_data.pdf_response = None;
+                    },
                     ReflowGoal::Full | ReflowGoal:: TickAnimations => {}
                 }
                 return;
@@ -1179,8 +1249,14 @@ impl LayoutThread {
             ua_or_user: &ua_or_user_guard,
         };
 
+        // Printing lays the document out in pages, so switching between print and screen media
+        // means rebuilding every flow.
+        let printing = data.reflow_goal == ReflowGoal::Print;
+        let media_type_changed = printing != self.stylist.device().is_paged();
+        let media_type = if printing { MediaType::print() } else { MediaType::screen() };
+
         let had_used_viewport_units = self.stylist.device().used_viewport_units();
-        let device = Device::new(MediaType::screen(), initial_viewport, device_pixel_ratio);
+        let device = Device::new(media_type, initial_viewport, device_pixel_ratio);
         let sheet_origins_affected_by_device_change =
             self.stylist.set_device(device, &guards);
 
@@ -1241,6 +1317,12 @@ impl LayoutThread {
             }
         }
 
+        if media_type_changed {
+            if let Some(mut data) = element.mutate_data() {
+                data.hint.insert(RestyleHint::restyle_subtree());
+            }
+        }
+
         let restyles = document.drain_pending_restyles();
         debug!("Draining restyles: {}", restyles.len());
 
@@ -1285,9 +1367,26 @@ impl LayoutThread {
 
         self.stylist.flush(&guards, Some(element), Some(&map));
 
+        // The viewport of paged media is the page area, which depends on the `@page` rules.
+        let page = if printing {
+            let page = self.page_geometry(&guards, document_shared_lock, element);
+            let page_area = page.page_area().size;
+            let viewport = TypedSize2D::new(page_area.width.to_f32_px(), page_area.height.to_f32_px());
+            let device = Device::new(MediaType::print(), viewport, device_pixel_ratio);
+            let sheet_origins_affected_by_device_change =
+                self.stylist.set_device(device, &guards);
+            self.stylist.force_stylesheet_origins_dirty(sheet_origins_affected_by_device_change);
+            self.stylist.flush(&guards, Some(element), Some(&map));
+            self.viewport_size = page_area;
+            Some(page)
+        } else {
+            None
+        };
+
         // Create a layout context for use throughout the following passes.
         let mut layout_context =
             self.build_layout_context(guards.clone(), true, &map);
+        layout_context.reconstruct_all_flows = media_type_changed;
 
         let thread_pool = if self.parallel_flag {
             self.parallel_traversal.as_ref()
@@ -1350,12 +1449,19 @@ impl LayoutThread {
 
         // Perform post-style recalculation layout passes.
         if let Some(mut root_flow) = self.root_flow.borrow().clone() {
-            self.perform_post_style_recalc_layout_passes(&mut root_flow,
-                                                         &data.reflow_info,
-                                                         &data.reflow_goal,
-                                                         Some(&document),
-                                                         &mut rw_data,
-                                                         &mut layout_context);
+            if let Some(ref page) = page {
+                rw_data.pdf_response = Some(self.print_to_pdf(&mut root_flow, page, &layout_context));
+                // The flow tree now holds the first page only, so the next reflow for display
+                // must rebuild it.
+                document.needs_paint_from_layout();
+            } else {
+                self.perform_post_style_recalc_layout_passes(&mut root_flow,
+                                                             &data.reflow_info,
+                                                             &data.reflow_goal,
+                                                             Some(&document),
+                                                             &mut rw_data,
+                                                             &mut layout_context);
+            }
         }
 
         self.first_reflow.set(false);
@@ -1429,6 +1535,18 @@ impl LayoutThread {
                                                        property,
                                                        root_flow);
                 },
+                &QueryMsg::ResolvedFontStyleQuery(node, ref property, ref value) => {
+                    let node = unsafe { ServoLayoutNode::new(&node) };
+                    let url = self.url.clone();
+                    let shared_lock = self.document_shared_lock.clone().unwrap();
+                    rw_data.resolved_font_style_response =
+                        process_resolved_font_style_request(context,
+                                                            node,
+                                                            value,
+                                                            property,
+                                                            url,
+                                                            &shared_lock);
+                },
                 &QueryMsg::OffsetParentQuery(node) => {
                     let node = unsafe { ServoLayoutNode::new(&node) };
                     rw_data.offset_parent_response = process_offset_parent_query(node, root_flow);
@@ -1465,7 +1583,7 @@ impl LayoutThread {
                         process_element_inner_text_query(node, &rw_data.indexable_text);
                 },
             },
-            ReflowGoal::Full | ReflowGoal::TickAnimations => {}
+            ReflowGoal::Print | ReflowGoal::Full | ReflowGoal::TickAnimations => {}
         }
     }
 
@@ -1667,6 +1785,42 @@ impl LayoutThread {
         self.generation.set(self.generation.get() + 1);
     }
 
+    /// Computes the page box from the `@page` rules. The page context inherits from the root
+    /// element.
+    fn page_geometry(&self,
+                     guards: &StylesheetGuards,
+                     shared_lock: &SharedRwLock,
+                     root: ServoLayoutElement)
+                     -> PageGeometry {
+        let root_style = root.borrow_data().and_then(|data| data.styles.get_primary().cloned());
+        let parent_style = match root_style {
+            Some(ref style) => &**style,
+            None => self.stylist.device().default_computed_values(),
+        };
+        let page_style =
+            self.stylist.compute_page_style::<ServoLayoutElement>(guards, shared_lock, parent_style);
+        PageGeometry::from_style(&page_style)
+    }
+
+    /// Lays the document out in pages and serialises them as a PDF document.
+    fn print_to_pdf(&self, root_flow: &mut FlowRef, page: &PageGeometry, context: &LayoutContext) -> Vec<u8> {
+        profile(time::ProfilerCategory::LayoutMain,
+                self.profiler_metadata(),
+                self.time_profiler_chan.clone(),
+                || {
+            FlowRef::deref_mut(root_flow).compute_layout_damage();
+            FlowRef::deref_mut(root_flow).reflow_entire_document();
+            sequential::resolve_generated_content(FlowRef::deref_mut(root_flow), context);
+            sequential::guess_float_placement(FlowRef::deref_mut(root_flow));
+
+            // Fragmentable flows, which in paged media are all of them, are left alone by the
+            // block-size traversal and get their block size when they are paginated.
+            LayoutThread::solve_constraints(FlowRef::deref_mut(root_flow), context);
+        });
+
+        paged::print_to_pdf(root_flow.clone(), context, page)
+    }
+
     fn reflow_all_nodes(flow: &mut Flow) {
         debug!("reflowing all nodes!");
         flow.mut_base()
@@ -7,7 +7,7 @@
 use ipc_channel::ipc::{self, IpcReceiver};
 use ipc_channel::router::ROUTER;
 use profile_traits::mem::{ProfilerChan, ProfilerMsg, ReportKind, Reporter, ReporterRequest};
-use profile_traits::mem::ReportsChan;
+use profile_traits::mem::{Report, ReportsChan};
 use std::borrow::ToOwned;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -111,10 +111,28 @@ impl Profiler {
                 true
             },
 
+            ProfilerMsg::Report(sender) => {
+                let _ = sender.send(self.collect_reports());
+                true
+            },
+
             ProfilerMsg::Exit => false
         }
     }
 
+    /// Collect the reports of every registered reporter, skipping any that fail.
+    fn collect_reports(&self) -> Vec<Report> {
+        let mut all_reports = vec![];
+        for reporter in self.reporters.values() {
+            let (chan, port) = ipc::channel().unwrap();
+            reporter.collect_reports(ReportsChan(chan));
+            if let Ok(mut reports) = port.recv() {
+                all_reports.append(&mut reports);
+            }
+        }
+        all_reports
+    }
+
     fn handle_print_msg(&self) {
         let elapsed = self.created.elapsed();
         println!("Begin memory reports {}", elapsed.as_secs());
@@ -139,45 +157,39 @@ impl Profiler {
         let mut jemalloc_heap_allocated_size: Option<usize> = None;
         let mut system_heap_allocated_size: Option<usize> = None;
 
-        for reporter in self.reporters.values() {
-            let (chan, port) = ipc::channel().unwrap();
-            reporter.collect_reports(ReportsChan(chan));
-            if let Ok(mut reports) = port.recv() {
-                for report in &mut reports {
-                    // Add "explicit" to the start of the path, when appropriate.
-                    match report.kind {
-                        ReportKind::ExplicitJemallocHeapSize |
-                        ReportKind::ExplicitSystemHeapSize |
-                        ReportKind::ExplicitNonHeapSize |
-                        ReportKind::ExplicitUnknownLocationSize =>
-                            report.path.insert(0, String::from("explicit")),
-                        ReportKind::NonExplicitSize => {},
-                    }
-
-                    // Update the reported fractions of the heaps, when appropriate.
-                    match report.kind {
-                        ReportKind::ExplicitJemallocHeapSize =>
-                            jemalloc_heap_reported_size += report.size,
-                        ReportKind::ExplicitSystemHeapSize =>
-                            system_heap_reported_size += report.size,
-                        _ => {},
-                    }
+        for mut report in self.collect_reports() {
+            // Add "explicit" to the start of the path, when appropriate.
+            match report.kind {
+                ReportKind::ExplicitJemallocHeapSize |
+                ReportKind::ExplicitSystemHeapSize |
+                ReportKind::ExplicitNonHeapSize |
+                ReportKind::ExplicitUnknownLocationSize =>
+                    report.path.insert(0, String::from("explicit")),
+                ReportKind::NonExplicitSize => {},
+            }
 
-                    // Record total size of the heaps, when we see them.
-                    if report.path.len() == 1 {
-                        if report.path[0] == JEMALLOC_HEAP_ALLOCATED_STR {
-                            assert!(jemalloc_heap_allocated_size.is_none());
-                            jemalloc_heap_allocated_size = Some(report.size);
-                        } else if report.path[0] == SYSTEM_HEAP_ALLOCATED_STR {
-                            assert!(system_heap_allocated_size.is_none());
-                            system_heap_allocated_size = Some(report.size);
-                        }
-                    }
+            // Update the reported fractions of the heaps, when appropriate.
+            match report.kind {
+                ReportKind::ExplicitJemallocHeapSize =>
+                    jemalloc_heap_reported_size += report.size,
+                ReportKind::ExplicitSystemHeapSize =>
+                    system_heap_reported_size += report.size,
+                _ => {},
+            }
 
-                    // Insert the report.
-                    forest.insert(&report.path, report.size);
+            // Record total size of the heaps, when we see them.
+            if report.path.len() == 1 {
+                if report.path[0] == JEMALLOC_HEAP_ALLOCATED_STR {
+                    assert!(jemalloc_heap_allocated_size.is_none());
+                    jemalloc_heap_allocated_size = Some(report.size);
+                } else if report.path[0] == SYSTEM_HEAP_ALLOCATED_STR {
+                    assert!(system_heap_allocated_size.is_none());
+                    system_heap_allocated_size = Some(report.size);
                 }
             }
+
+            // Insert the report.
+            forest.insert(&report.path, report.size);
         }
 
         // Compute and insert the heap-unclassified values.
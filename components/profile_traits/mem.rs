@@ -185,6 +185,10 @@ pub enum ProfilerMsg {
     /// Triggers printing of the memory profiling metrics.
     Print,
 
+    /// Collects the reports of every registered reporter and sends them on the given channel,
+    /// so that they can be examined by other components such as devtools.
+    Report(IpcSender<Vec<Report>>),
+
     /// Tells the memory profiler to shut down.
     Exit,
 }
@@ -0,0 +1,194 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Serialization of canvas bitmaps, shared by `toDataURL` and `toBlob`.
+//!
+//! <https://html.spec.whatwg.org/multipage/#a-serialisation-of-the-bitmap-as-a-file>
+
+use image::ColorType;
+use image::jpeg::JPEGEncoder;
+use image::png::PNGEncoder;
+
+/// The quality used for lossy formats when none (or an invalid one) is given.
+const DEFAULT_QUALITY: f64 = 0.92;
+
+/// The largest dimension a VP8L bitstream can describe.
+const WEBP_MAX_DIMENSION: u32 = 1 << 14;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EncodedImageType {
+    Png,
+    Jpeg(f64),
+    WebP,
+}
+
+impl EncodedImageType {
+    /// Picks the serialization format for the requested MIME type, falling back
+    /// to PNG for anything unsupported. `quality` is only honoured when it lies
+    /// within the range 0.0 to 1.0.
+    pub fn from_mime_type(mime_type: Option<&str>, quality: Option<f64>) -> EncodedImageType {
+        let mime_type = mime_type.map(|mime| mime.to_ascii_lowercase());
+        let quality = quality
+            .filter(|quality| *quality >= 0. && *quality <= 1.)
+            .unwrap_or(DEFAULT_QUALITY);
+        match mime_type.as_ref().map(|mime| &**mime) {
+            Some("image/jpeg") => EncodedImageType::Jpeg(quality),
+            Some("image/webp") => EncodedImageType::WebP,
+            _ => EncodedImageType::Png,
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match *self {
+            EncodedImageType::Png => "image/png",
+            EncodedImageType::Jpeg(_) => "image/jpeg",
+            EncodedImageType::WebP => "image/webp",
+        }
+    }
+}
+
+/// Encodes non-premultiplied RGBA pixels, returning the type actually used
+/// along with the encoded bytes.
+pub fn encode_image(
+    image_type: EncodedImageType,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+) -> (EncodedImageType, Vec<u8>) {
+    let mut encoded = Vec::new();
+    match image_type {
+        EncodedImageType::Jpeg(quality) => {
+            // JPEG has no alpha channel, so composite onto solid black.
+            let rgb: Vec<u8> = pixels.chunks(4).flat_map(|pixel| {
+                let alpha = pixel[3] as u32;
+                (0..3).map(move |i| ((pixel[i] as u32 * alpha + 127) / 255) as u8)
+            }).collect();
+            let quality = (quality * 100.).round().max(1.) as u8;
+            JPEGEncoder::new_with_quality(&mut encoded, quality)
+                .encode(&rgb, width, height, ColorType::RGB(8))
+                .unwrap();
+        },
+        EncodedImageType::WebP if width <= WEBP_MAX_DIMENSION && height <= WEBP_MAX_DIMENSION => {
+            encoded = encode_webp_lossless(pixels, width, height);
+        },
+        EncodedImageType::Png | EncodedImageType::WebP => {
+            PNGEncoder::new(&mut encoded)
+                .encode(pixels, width, height, ColorType::RGBA(8))
+                .unwrap();
+            return (EncodedImageType::Png, encoded);
+        },
+    }
+    (image_type, encoded)
+}
+
+/// A little-endian bit writer as used by the VP8L bitstream.
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    used: u32,
+}
+
+impl BitWriter {
+    fn new(capacity: usize) -> BitWriter {
+        BitWriter { bytes: Vec::with_capacity(capacity), accumulator: 0, used: 0 }
+    }
+
+    fn write(&mut self, value: u32, bits: u32) {
+        debug_assert!(bits <= 32 && (bits == 32 || value >> bits == 0));
+        self.accumulator |= (value as u64) << self.used;
+        self.used += bits;
+        while self.used >= 8 {
+            self.bytes.push(self.accumulator as u8);
+            self.accumulator >>= 8;
+            self.used -= 8;
+        }
+    }
+
+    /// Writes a prefix code, which VP8L reads most significant bit first.
+    fn write_code(&mut self, code: u32, bits: u32) {
+        for bit in (0..bits).rev() {
+            self.write((code >> bit) & 1, 1);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.accumulator as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Writes a normal prefix code in which every symbol below `used_symbols` has
+/// length 8 and the rest of the `alphabet_size` symbols are unused.
+fn write_flat_prefix_code(writer: &mut BitWriter, used_symbols: u32, alphabet_size: u32) {
+    // https://developers.google.com/speed/webp/docs/webp_lossless_bitstream_specification#decoding_of_meta_prefix_codes
+    const CODE_LENGTH_ORDER: [u32; 12] = [17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8];
+    // Not a simple code.
+    writer.write(0, 1);
+    // The code length code only uses lengths 0 and 8, each with a 1-bit code.
+    writer.write(CODE_LENGTH_ORDER.len() as u32 - 4, 4);
+    for &length in CODE_LENGTH_ORDER.iter() {
+        writer.write(if length == 0 || length == 8 { 1 } else { 0 }, 3);
+    }
+    // Code lengths are given for the whole alphabet.
+    writer.write(0, 1);
+    for symbol in 0..alphabet_size {
+        writer.write_code(if symbol < used_symbols { 1 } else { 0 }, 1);
+    }
+}
+
+/// Encodes RGBA pixels as a lossless WebP image. No transforms or backward
+/// references are used, so this trades file size for simplicity.
+fn encode_webp_lossless(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    // https://developers.google.com/speed/webp/docs/webp_lossless_bitstream_specification
+    let mut writer = BitWriter::new(pixels.len() + 64);
+    writer.write(0x2f, 8);
+    writer.write(width - 1, 14);
+    writer.write(height - 1, 14);
+    let has_alpha = pixels.chunks(4).any(|pixel| pixel[3] != 0xff);
+    writer.write(has_alpha as u32, 1);
+    // Version.
+    writer.write(0, 3);
+    // No transforms, no color cache, no meta prefix codes.
+    writer.write(0, 1);
+    writer.write(0, 1);
+    writer.write(0, 1);
+
+    // Green and length prefixes, red, blue and alpha.
+    write_flat_prefix_code(&mut writer, 256, 256 + 24);
+    write_flat_prefix_code(&mut writer, 256, 256);
+    write_flat_prefix_code(&mut writer, 256, 256);
+    write_flat_prefix_code(&mut writer, 256, 256);
+    // Distance: a simple code with the single symbol 0, which takes no bits.
+    writer.write(1, 1);
+    writer.write(0, 1);
+    writer.write(0, 1);
+    writer.write(0, 1);
+
+    for pixel in pixels.chunks(4) {
+        writer.write_code(pixel[1] as u32, 8);
+        writer.write_code(pixel[0] as u32, 8);
+        writer.write_code(pixel[2] as u32, 8);
+        writer.write_code(pixel[3] as u32, 8);
+    }
+    let bitstream = writer.finish();
+
+    let padding = bitstream.len() % 2;
+    let mut webp = Vec::with_capacity(bitstream.len() + 20 + padding);
+    webp.extend_from_slice(b"RIFF");
+    push_u32_le(&mut webp, (4 + 8 + bitstream.len() + padding) as u32);
+    webp.extend_from_slice(b"WEBP");
+    webp.extend_from_slice(b"VP8L");
+    push_u32_le(&mut webp, bitstream.len() as u32);
+    webp.extend_from_slice(&bitstream);
+    if padding == 1 {
+        webp.push(0);
+    }
+    webp
+}
+
+fn push_u32_le(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
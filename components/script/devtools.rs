@@ -3,36 +3,65 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use devtools_traits::{AutoMargins, CachedConsoleMessage, CachedConsoleMessageTypes};
-use devtools_traits::{ComputedNodeLayout, ConsoleAPI, PageError};
-use devtools_traits::{EvaluateJSReply, Modification, NodeInfo, TimelineMarker};
-use devtools_traits::TimelineMarkerType;
+use devtools_traits::{ComputedNodeLayout, ConsoleAPI, DevtoolsInputEvent, DevtoolsMouseButton};
+use devtools_traits::{DevtoolsMouseEventType, DomMutation, EvaluateJSReply, HeapGraph, HeapGraphNode};
+use devtools_traits::{Modification, NodeInfo, ObjectPreview, PageError, TimelineMarker};
+use devtools_traits::{TimelineMarkerType, WebGLProgramInfo, WebGLProgramKey};
 use dom::bindings::codegen::Bindings::CSSStyleDeclarationBinding::CSSStyleDeclarationMethods;
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
 use dom::bindings::codegen::Bindings::DOMRectBinding::DOMRectMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventConstants;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
-use dom::bindings::conversions::{ConversionResult, FromJSValConvertible, jsstring_to_str};
+use dom::bindings::conversions::{ConversionBehavior, ConversionResult, FromJSValConvertible};
+use dom::bindings::conversions::{get_property_jsval, is_array_like, jsid_to_string, jsstring_to_str};
+use dom::bindings::conversions::root_from_object;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
-use dom::document::AnimationFrameCallback;
+use dom::bindings::trace::RootedTraceableBox;
+use dom::characterdata::CharacterData;
+use dom::document::{AnimationFrameCallback, Document};
 use dom::element::Element;
 use dom::globalscope::GlobalScope;
+use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::keyboardevent::key_from_string;
+use dom::mutationobserver::Mutation;
 use dom::node::{Node, window_from_node};
+use dom::webglprogram::WebGLProgram;
+use dom::webglrenderingcontext::WebGLRenderingContext;
 use dom::window::Window;
+use euclid::Point2D;
 use ipc_channel::ipc::IpcSender;
-use js::jsapi::JSAutoCompartment;
+use js::JSCLASS_IS_PROXY;
+use js::jsapi::{Heap, JSAutoCompartment, JSContext, JSGCParamKey, JSITER_HIDDEN, JSITER_OWNONLY};
+use js::jsapi::{JSObject, JS_ClearPendingException, JS_GC, JS_GetGCParameter, JS_GetRuntime};
+use js::jsapi::{JSPROP_GETTER, JSPROP_SETTER, JS_SetGCParameter, PropertyDescriptor};
 use js::jsval::UndefinedValue;
-use js::rust::wrappers::ObjectClassName;
+use js::rust::{HandleId, HandleObject, HandleValue, IdVector, MutableHandleValue, get_object_class};
+use js::rust::wrappers::{GetPropertyKeys, JS_GetOwnPropertyDescriptor, JS_GetOwnPropertyDescriptorById};
+use js::rust::wrappers::{JS_GetPropertyById, JS_GetPrototype, ObjectClassName};
 use msg::constellation_msg::PipelineId;
+use script_layout_interface::message::NodesFromPointQueryType;
+use script_runtime::get_size;
 use script_thread::Documents;
-use std::ffi::CStr;
+use script_traits::{CompositorEvent, MouseButton, MouseEventType};
+use servo_url::ServoUrl;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::ptr;
 use std::str;
 use style::properties::longhands::{margin_bottom, margin_left, margin_right, margin_top};
 use uuid::Uuid;
 
 
+/// The maximum number of properties included in an object preview.
+pub const OBJECT_PREVIEW_MAX_ITEMS: usize = 10;
+
 #[allow(unsafe_code)]
 pub fn handle_evaluate_js(global: &GlobalScope, eval: String, reply: IpcSender<EvaluateJSReply>) {
     // global.get_cx() returns a valid `JSContext` pointer, so this is safe.
@@ -42,35 +71,113 @@ pub fn handle_evaluate_js(global: &GlobalScope, eval: String, reply: IpcSender<E
         let _ac = JSAutoCompartment::new(cx, globalhandle.get());
         rooted!(in(cx) let mut rval = UndefinedValue());
         global.evaluate_js_on_global_with_result(&eval, rval.handle_mut());
+        jsval_to_devtools_value(cx, rval.handle(), 0, 0)
+    };
+    reply.send(result).unwrap();
+}
 
-        if rval.is_undefined() {
-            EvaluateJSReply::VoidValue
-        } else if rval.is_boolean() {
-            EvaluateJSReply::BooleanValue(rval.to_boolean())
-        } else if rval.is_double() || rval.is_int32() {
-            EvaluateJSReply::NumberValue(
-                match FromJSValConvertible::from_jsval(cx, rval.handle(), ()) {
-                    Ok(ConversionResult::Success(v)) => v,
-                    _ => unreachable!(),
-                })
-        } else if rval.is_string() {
-            EvaluateJSReply::StringValue(String::from(jsstring_to_str(cx, rval.to_string())))
-        } else if rval.is_null() {
-            EvaluateJSReply::NullValue
+/// Serialize a JS value for devtools. Objects are identified by a fresh uuid and, if
+/// `preview_depth` is non-zero, carry a preview of up to `max_items` of their own
+/// enumerable properties, recursively.
+#[allow(unsafe_code)]
+pub unsafe fn jsval_to_devtools_value(cx: *mut JSContext,
+                                      value: HandleValue,
+                                      preview_depth: u32,
+                                      max_items: usize)
+                                      -> EvaluateJSReply {
+    if value.is_undefined() {
+        EvaluateJSReply::VoidValue
+    } else if value.is_boolean() {
+        EvaluateJSReply::BooleanValue(value.to_boolean())
+    } else if value.is_double() || value.is_int32() {
+        EvaluateJSReply::NumberValue(
+            match FromJSValConvertible::from_jsval(cx, value, ()) {
+                Ok(ConversionResult::Success(v)) => v,
+                _ => unreachable!(),
+            })
+    } else if value.is_string() {
+        EvaluateJSReply::StringValue(String::from(jsstring_to_str(cx, value.to_string())))
+    } else if value.is_null() {
+        EvaluateJSReply::NullValue
+    } else if value.is_symbol() {
+        EvaluateJSReply::StringValue("Symbol()".to_owned())
+    } else {
+        assert!(value.is_object());
+
+        rooted!(in(cx) let obj = value.to_object());
+        let class_name = CStr::from_ptr(ObjectClassName(cx, obj.handle()));
+        let class_name = str::from_utf8(class_name.to_bytes()).unwrap();
+
+        let preview = if preview_depth > 0 {
+            Some(object_preview(cx, value, preview_depth - 1, max_items))
         } else {
-            assert!(rval.is_object());
+            None
+        };
 
-            rooted!(in(cx) let obj = rval.to_object());
-            let class_name = CStr::from_ptr(ObjectClassName(cx, obj.handle()));
-            let class_name = str::from_utf8(class_name.to_bytes()).unwrap();
+        EvaluateJSReply::ActorValue {
+            class: class_name.to_owned(),
+            uuid: Uuid::new_v4().to_string(),
+            preview: preview,
+        }
+    }
+}
 
-            EvaluateJSReply::ActorValue {
-                class: class_name.to_owned(),
-                uuid: Uuid::new_v4().to_string(),
-            }
+#[allow(unsafe_code)]
+unsafe fn object_preview(cx: *mut JSContext,
+                         value: HandleValue,
+                         preview_depth: u32,
+                         max_items: usize)
+                         -> ObjectPreview {
+    rooted!(in(cx) let object = value.to_object());
+
+    let length = if is_array_like(cx, value) {
+        rooted!(in(cx) let mut length = UndefinedValue());
+        match get_property_jsval(cx, object.handle(), "length", length.handle_mut()) {
+            Ok(()) => match FromJSValConvertible::from_jsval(cx, length.handle(), ConversionBehavior::Default) {
+                Ok(ConversionResult::Success(length)) => Some(length),
+                _ => None,
+            },
+            Err(_) => {
+                JS_ClearPendingException(cx);
+                None
+            },
         }
+    } else {
+        None
     };
-    reply.send(result).unwrap();
+
+    let ids = IdVector::new(cx);
+    if !GetPropertyKeys(cx, object.handle(), JSITER_OWNONLY, ids.get()) {
+        JS_ClearPendingException(cx);
+        return ObjectPreview {
+            length: length,
+            ownProperties: vec![],
+            ownPropertiesLength: 0,
+        };
+    }
+
+    let mut properties = vec![];
+    for id in ids.iter().take(max_items) {
+        rooted!(in(cx) let id = *id);
+        let name = match jsid_to_string(cx, id.handle()) {
+            Some(name) => String::from(name),
+            // Symbol-keyed properties are not previewed.
+            None => continue,
+        };
+        rooted!(in(cx) let mut property = UndefinedValue());
+        if !JS_GetPropertyById(cx, object.handle(), id.handle(), property.handle_mut()) {
+            // A throwing getter shouldn't turn into an exception in the page.
+            JS_ClearPendingException(cx);
+            continue;
+        }
+        properties.push((name, jsval_to_devtools_value(cx, property.handle(), preview_depth, max_items)));
+    }
+
+    ObjectPreview {
+        length: length,
+        ownProperties: properties,
+        ownPropertiesLength: ids.len() as u32,
+    }
 }
 
 pub fn handle_get_root_node(documents: &Documents, pipeline: PipelineId, reply: IpcSender<Option<NodeInfo>>) {
@@ -251,6 +358,64 @@ pub fn handle_drop_timeline_markers(documents: &Documents,
     }
 }
 
+pub fn handle_set_mutation_watcher(documents: &Documents,
+                                   pipeline: PipelineId,
+                                   reply: IpcSender<Vec<DomMutation>>) {
+    if let Some(window) = documents.find_window(pipeline) {
+        window.set_devtools_mutation_sender(reply);
+    }
+}
+
+pub fn handle_drop_mutation_watcher(documents: &Documents, pipeline: PipelineId) {
+    if let Some(window) = documents.find_window(pipeline) {
+        window.drop_devtools_mutation_sender();
+    }
+}
+
+/// Describe a mutation that is about to be queued for `MutationObserver`s. Attribute
+/// and character data values are left empty, since the change has not happened yet;
+/// they are filled in by `complete_dom_mutation` when the mutations are flushed.
+pub fn summarize_dom_mutation(target: &Node, mutation: &Mutation) -> DomMutation {
+    match *mutation {
+        Mutation::Attribute { ref name, .. } => DomMutation::Attributes {
+            target: target.unique_id(),
+            attributeName: name.to_string(),
+            newValue: None,
+        },
+        Mutation::CharacterData { .. } => DomMutation::CharacterData {
+            target: target.unique_id(),
+            newValue: String::new(),
+        },
+        Mutation::ChildList { added, removed, .. } => DomMutation::ChildList {
+            target: target.unique_id(),
+            added: added.unwrap_or(&[]).iter().map(|node| node.summarize()).collect(),
+            removed: removed.unwrap_or(&[]).iter().map(|node| node.unique_id()).collect(),
+            numChildren: target.children_count() as usize,
+        },
+    }
+}
+
+/// Fill in the current attribute or character data value of a mutation of `node`.
+/// Returns `None` if the node is no longer part of the document.
+pub fn complete_dom_mutation(node: &Node, mutation: DomMutation) -> Option<DomMutation> {
+    if !node.is_in_doc() {
+        return None;
+    }
+    match mutation {
+        DomMutation::Attributes { target, attributeName, .. } => {
+            let new_value = node.downcast::<Element>()?
+                                .get_attribute_by_name(DOMString::from(&*attributeName))
+                                .map(|attr| String::from(&**attr.value()));
+            Some(DomMutation::Attributes { target, attributeName, newValue: new_value })
+        },
+        DomMutation::CharacterData { target, .. } => {
+            let new_value = String::from(node.downcast::<CharacterData>()?.Data());
+            Some(DomMutation::CharacterData { target, newValue: new_value })
+        },
+        mutation @ DomMutation::ChildList { .. } => Some(mutation),
+    }
+}
+
 pub fn handle_request_animation_frame(documents: &Documents,
                                       id: PipelineId,
                                       actor_name: String) {
@@ -265,3 +430,336 @@ pub fn handle_reload(documents: &Documents,
         win.Location().reload_without_origin_check();
     }
 }
+
+pub fn handle_navigate(documents: &Documents,
+                       id: PipelineId,
+                       url: ServoUrl) {
+    if let Some(win) = documents.find_window(id) {
+        win.load_url(url, false, false, None);
+    }
+}
+
+pub fn handle_resize_window(documents: &Documents,
+                            id: PipelineId,
+                            width: i32,
+                            height: i32) {
+    if let Some(win) = documents.find_window(id) {
+        win.ResizeTo(width, height);
+    }
+}
+
+/// The WebGL contexts of the canvases of a document.
+fn webgl_contexts(document: &Document) -> Vec<DomRoot<WebGLRenderingContext>> {
+    document.upcast::<Node>()
+        .traverse_preorder()
+        .filter_map(DomRoot::downcast::<HTMLCanvasElement>)
+        .filter_map(|canvas| canvas.get_base_webgl_context())
+        .collect()
+}
+
+fn shader_source(program: &WebGLProgram, shader_type: u32) -> String {
+    program.attached_shaders()
+        .ok()
+        .and_then(|shaders| shaders.into_iter().find(|shader| shader.gl_type() == shader_type))
+        .map_or(String::new(), |shader| shader.source().into())
+}
+
+pub fn handle_get_webgl_programs(documents: &Documents,
+                                 pipeline: PipelineId,
+                                 reply: IpcSender<Vec<WebGLProgramInfo>>) {
+    let mut programs = vec![];
+    if let Some(document) = documents.find_document(pipeline) {
+        for context in webgl_contexts(&document) {
+            let context_id = context.context_id().0;
+            for program in context.linked_programs() {
+                programs.push(WebGLProgramInfo {
+                    key: WebGLProgramKey {
+                        contextId: context_id,
+                        programId: program.id().get(),
+                    },
+                    vertexShaderSource: shader_source(&program, constants::VERTEX_SHADER),
+                    fragmentShaderSource: shader_source(&program, constants::FRAGMENT_SHADER),
+                });
+            }
+        }
+    }
+    reply.send(programs).unwrap();
+}
+
+pub fn handle_replace_webgl_shader_source(documents: &Documents,
+                                          pipeline: PipelineId,
+                                          key: WebGLProgramKey,
+                                          shader_type: u32,
+                                          source: String,
+                                          reply: IpcSender<Result<(), String>>) {
+    let contexts = documents.find_document(pipeline).map_or(vec![], |document| webgl_contexts(&document));
+    let result = contexts.iter()
+        .filter(|context| context.context_id().0 == key.contextId)
+        .flat_map(|context| {
+            context.linked_programs()
+                .into_iter()
+                .find(|program| program.id().get() == key.programId)
+                .map(|program| context.replace_shader_source(&program, shader_type, source.clone().into()))
+        })
+        .next()
+        .unwrap_or_else(|| Err("The program no longer exists".to_owned()));
+    reply.send(result).unwrap();
+}
+
+/// The maximum number of objects captured by a heap snapshot.
+const HEAP_SNAPSHOT_MAX_OBJECTS: usize = 100000;
+
+/// Estimates, not measurements, of the GC heap size of a JS object and of each of its
+/// slots: the size of a `NativeObject` header and of a `Value` on 64-bit platforms.
+/// SpiderMonkey doesn't expose the size of individual objects through the JSAPI, so
+/// dynamic slots, elements and out-of-line string data are not accounted for.
+const ESTIMATED_JS_OBJECT_SIZE: u64 = 32;
+const ESTIMATED_JS_SLOT_SIZE: u64 = 8;
+
+#[allow(unsafe_code)]
+pub fn handle_take_heap_snapshot(documents: &Documents,
+                                 pipeline: PipelineId,
+                                 reply: IpcSender<Option<HeapGraph>>) {
+    let graph = documents.find_window(pipeline).map(|window| {
+        unsafe { take_heap_snapshot(window.upcast::<GlobalScope>()) }
+    });
+    reply.send(graph).unwrap();
+}
+
+/// Walk the objects reachable from `global` through own properties, prototypes and
+/// the DOM tree. Closure environments are not visible through the JSAPI, so objects
+/// only reachable from them are missed.
+#[allow(unsafe_code)]
+unsafe fn take_heap_snapshot(global: &GlobalScope) -> HeapGraph {
+    let cx = global.get_cx();
+    let globalhandle = global.reflector().get_jsobject();
+    let _ac = JSAutoCompartment::new(cx, globalhandle.get());
+    let rt = JS_GetRuntime(cx);
+
+    // Object addresses identify the nodes of the graph, so empty the nursery and stop
+    // compacting GCs from moving objects while the heap is walked.
+    JS_GC(rt);
+    let compacting = JS_GetGCParameter(rt, JSGCParamKey::JSGC_COMPACTING_ENABLED);
+    JS_SetGCParameter(rt, JSGCParamKey::JSGC_COMPACTING_ENABLED, 0);
+
+    let mut objects = RootedTraceableBox::new(Vec::<Box<Heap<*mut JSObject>>>::new());
+    let mut indices = HashMap::new();
+    let mut nodes = vec![];
+    node_index(&mut objects, &mut indices, globalhandle.get());
+
+    let mut next = 0;
+    while next < objects.len() {
+        rooted!(in(cx) let object = objects[next].get());
+        next += 1;
+
+        let mut edges = vec![];
+        let mut slots = 0;
+
+        // Enumerating the properties or the prototype of a proxy runs its handler's
+        // traps, which may be page script, so proxies are leaves of the graph.
+        if !is_proxy(object.get()) {
+            let ids = IdVector::new(cx);
+            if GetPropertyKeys(cx, object.handle(), JSITER_OWNONLY | JSITER_HIDDEN, ids.get()) {
+                slots = ids.len() as u64;
+                for id in ids.iter() {
+                    rooted!(in(cx) let id = *id);
+                    for property in own_property_objects(cx, object.handle(), id.handle()) {
+                        edges.extend(node_index(&mut objects, &mut indices, property));
+                    }
+                }
+            } else {
+                JS_ClearPendingException(cx);
+            }
+
+            rooted!(in(cx) let mut proto = ptr::null_mut::<JSObject>());
+            if JS_GetPrototype(cx, object.handle(), proto.handle_mut()) {
+                if !proto.is_null() {
+                    edges.extend(node_index(&mut objects, &mut indices, proto.get()));
+                }
+            } else {
+                JS_ClearPendingException(cx);
+            }
+        }
+
+        // DOM nodes hold their relatives in Rust, out of sight of the JSAPI.
+        if let Ok(node) = root_from_object::<Node>(object.get()) {
+            for relative in node.GetParentNode().into_iter().chain(node.children()) {
+                let reflector = relative.reflector().get_jsobject().get();
+                edges.extend(node_index(&mut objects, &mut indices, reflector));
+            }
+        }
+
+        nodes.push(HeapGraphNode {
+            className: constructor_name(cx, object.handle()),
+            size: ESTIMATED_JS_OBJECT_SIZE + slots * ESTIMATED_JS_SLOT_SIZE + get_size(object.get()) as u64,
+            edges: edges,
+        });
+    }
+
+    JS_SetGCParameter(rt, JSGCParamKey::JSGC_COMPACTING_ENABLED, compacting);
+
+    HeapGraph {
+        nodes: nodes,
+    }
+}
+
+/// The index of `object` in the snapshot, adding it if it hasn't been seen yet and
+/// there is room left.
+fn node_index(objects: &mut Vec<Box<Heap<*mut JSObject>>>,
+              indices: &mut HashMap<*mut JSObject, u32>,
+              object: *mut JSObject)
+              -> Option<u32> {
+    if let Some(index) = indices.get(&object) {
+        return Some(*index);
+    }
+    if objects.len() >= HEAP_SNAPSHOT_MAX_OBJECTS {
+        return None;
+    }
+    let index = objects.len() as u32;
+    objects.push(Heap::boxed(object));
+    indices.insert(object, index);
+    Some(index)
+}
+
+/// Whether `object` is a proxy, including cross-compartment wrappers.
+#[allow(unsafe_code)]
+unsafe fn is_proxy(object: *mut JSObject) -> bool {
+    ((*get_object_class(object)).flags & JSCLASS_IS_PROXY) != 0
+}
+
+/// The objects held by the own property `id` of the non-proxy `object`: the value of
+/// a data property, or the getter and setter functions of an accessor property. The
+/// property descriptor is read without invoking any accessor.
+#[allow(unsafe_code)]
+unsafe fn own_property_objects(cx: *mut JSContext, object: HandleObject, id: HandleId) -> Vec<*mut JSObject> {
+    rooted!(in(cx) let mut desc = PropertyDescriptor::default());
+    if !JS_GetOwnPropertyDescriptorById(cx, object, id, desc.handle_mut()) {
+        JS_ClearPendingException(cx);
+        return vec![];
+    }
+    if desc.obj.is_null() {
+        return vec![];
+    }
+    if desc.attrs & (JSPROP_GETTER | JSPROP_SETTER) == 0 {
+        return if desc.value.is_object() { vec![desc.value.to_object()] } else { vec![] };
+    }
+    // With `JSPROP_GETTER` and `JSPROP_SETTER`, the getter and setter fields hold the
+    // accessor function objects rather than native callbacks.
+    let mut accessors = vec![];
+    if desc.attrs & JSPROP_GETTER != 0 {
+        accessors.extend(desc.getter.map(|getter| getter as usize as *mut JSObject));
+    }
+    if desc.attrs & JSPROP_SETTER != 0 {
+        accessors.extend(desc.setter.map(|setter| setter as usize as *mut JSObject));
+    }
+    accessors
+}
+
+/// The value of the data property `name` found on `object` or its prototype chain,
+/// without invoking accessors or proxy traps. Undefined if the lookup would need to.
+#[allow(unsafe_code)]
+unsafe fn data_property(cx: *mut JSContext, object: HandleObject, name: &str, mut rval: MutableHandleValue) {
+    rval.set(UndefinedValue());
+    let name = CString::new(name).unwrap();
+    rooted!(in(cx) let mut current = object.get());
+    while !current.is_null() && !is_proxy(current.get()) {
+        rooted!(in(cx) let mut desc = PropertyDescriptor::default());
+        if !JS_GetOwnPropertyDescriptor(cx, current.handle(), name.as_ptr(), desc.handle_mut()) {
+            JS_ClearPendingException(cx);
+            return;
+        }
+        if !desc.obj.is_null() {
+            if desc.attrs & (JSPROP_GETTER | JSPROP_SETTER) == 0 {
+                rval.set(desc.value);
+            }
+            return;
+        }
+        rooted!(in(cx) let mut proto = ptr::null_mut::<JSObject>());
+        if !JS_GetPrototype(cx, current.handle(), proto.handle_mut()) {
+            JS_ClearPendingException(cx);
+            return;
+        }
+        current.set(proto.get());
+    }
+}
+
+/// The name of the constructor of a plain object, or the class name of other objects.
+/// Only data properties are looked at, so no page script runs.
+#[allow(unsafe_code)]
+unsafe fn constructor_name(cx: *mut JSContext, object: HandleObject) -> String {
+    let class_name = CStr::from_ptr(ObjectClassName(cx, object));
+    let class_name = str::from_utf8(class_name.to_bytes()).unwrap().to_owned();
+    if class_name != "Object" || is_proxy(object.get()) {
+        return class_name;
+    }
+
+    rooted!(in(cx) let mut constructor = UndefinedValue());
+    data_property(cx, object, "constructor", constructor.handle_mut());
+    if !constructor.is_object() {
+        return class_name;
+    }
+    rooted!(in(cx) let constructor = constructor.to_object());
+    rooted!(in(cx) let mut name = UndefinedValue());
+    data_property(cx, constructor.handle(), "name", name.handle_mut());
+    if name.is_string() {
+        let name = String::from(jsstring_to_str(cx, name.to_string()));
+        if !name.is_empty() {
+            return name;
+        }
+    }
+    class_name
+}
+
+/// Translate a synthetic devtools input event into the compositor events that the
+/// embedder would have produced for the same user interaction.
+pub fn compositor_events_for_input(documents: &Documents,
+                                   id: PipelineId,
+                                   event: DevtoolsInputEvent)
+                                   -> Vec<CompositorEvent> {
+    let document = match documents.find_document(id) {
+        Some(document) => document,
+        None => return vec![],
+    };
+    match event {
+        DevtoolsInputEvent::Mouse { event_type, button, x, y, click_count } => {
+            let point = Point2D::new(x, y);
+            let node_address = document.nodes_from_point(&point, NodesFromPointQueryType::Topmost)
+                                       .first()
+                                       .cloned();
+            let button = match button {
+                Some(DevtoolsMouseButton::Left) | None => MouseButton::Left,
+                Some(DevtoolsMouseButton::Middle) => MouseButton::Middle,
+                Some(DevtoolsMouseButton::Right) => MouseButton::Right,
+            };
+            match event_type {
+                DevtoolsMouseEventType::Moved => {
+                    vec![CompositorEvent::MouseMoveEvent(Some(point), node_address)]
+                }
+                DevtoolsMouseEventType::Pressed => {
+                    vec![CompositorEvent::MouseButtonEvent(MouseEventType::MouseDown, button,
+                                                           point, node_address, None)]
+                }
+                DevtoolsMouseEventType::Released => {
+                    let mut events = vec![CompositorEvent::MouseButtonEvent(MouseEventType::MouseUp,
+                                                                            button, point,
+                                                                            node_address, None)];
+                    if click_count > 0 {
+                        events.push(CompositorEvent::MouseButtonEvent(MouseEventType::Click, button,
+                                                                      point, node_address, None));
+                    }
+                    events
+                }
+            }
+        }
+        DevtoolsInputEvent::Key { key, text, state, modifiers } => {
+            let location = KeyboardEventConstants::DOM_KEY_LOCATION_STANDARD;
+            match key_from_string(&key, location) {
+                Some(key) => vec![CompositorEvent::KeyEvent(text, key, state, modifiers)],
+                None => {
+                    warn!("Unsupported key {} in devtools input event", key);
+                    vec![]
+                }
+            }
+        }
+    }
+}
@@ -11,7 +11,10 @@ use dom::bindings::reflector::DomObject;
 use dom::bindings::root::DomRoot;
 use dom::blob::{Blob, BlobImpl};
 use dom::globalscope::GlobalScope;
-use js::jsapi::{JSAutoCompartment, JSContext};
+use dom::imagebitmap::ImageBitmap;
+use dom::offscreencanvas::{OffscreenCanvas, OffscreenCanvasPlaceholder};
+use euclid::Size2D;
+use js::jsapi::{HandleValueArray, JSAutoCompartment, JSContext, JS_NewArrayObject};
 use js::jsapi::{JSStructuredCloneCallbacks, JSStructuredCloneReader, JSStructuredCloneWriter};
 use js::jsapi::{JS_ClearPendingException, JSObject};
 use js::jsapi::{JS_ReadBytes, JS_WriteBytes};
@@ -20,7 +23,8 @@ use js::jsapi::HandleObject as RawHandleObject;
 use js::jsapi::JS_STRUCTURED_CLONE_VERSION;
 use js::jsapi::MutableHandleObject as RawMutableHandleObject;
 use js::jsapi::TransferableOwnership;
-use js::rust::{Handle, HandleValue, MutableHandleValue};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{Handle, HandleValue, MutableHandleObject, MutableHandleValue};
 use js::rust::wrappers::{JS_WriteStructuredClone, JS_ReadStructuredClone};
 use libc::size_t;
 use std::os::raw;
@@ -36,6 +40,8 @@ enum StructuredCloneTags {
     /// To support additional types, add new tags with values incremented from the last one before Max.
     Min = 0xFFFF8000,
     DomBlob = 0xFFFF8001,
+    DomImageBitmap = 0xFFFF8002,
+    DomOffscreenCanvas = 0xFFFF8003,
     Max = 0xFFFFFFFF,
 }
 
@@ -125,6 +131,39 @@ unsafe fn write_blob(blob: DomRoot<Blob>,
     return Ok(())
 }
 
+unsafe fn read_image_bitmap(cx: *mut JSContext,
+                            r: *mut JSStructuredCloneReader)
+                            -> *mut JSObject {
+    let structured_reader = StructuredCloneReader { r: r };
+    let mut width: u32 = 0;
+    let mut height: u32 = 0;
+    assert!(JS_ReadUint32Pair(r, &mut width as *mut u32, &mut height as *mut u32));
+    let bitmap_data = structured_reader.read_bytes();
+    let target_global = GlobalScope::from_context(cx);
+    let image_bitmap = ImageBitmap::new(&target_global, Size2D::new(width, height), bitmap_data, true);
+    return image_bitmap.reflector().get_jsobject().get()
+}
+
+/// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:serializable-objects>
+unsafe fn write_image_bitmap(image_bitmap: DomRoot<ImageBitmap>,
+                             w: *mut JSStructuredCloneWriter)
+                             -> Result<(), ()> {
+    // Bitmaps that are not origin-clean cannot be serialized.
+    if !image_bitmap.origin_is_clean() {
+        return Err(())
+    }
+    let bitmap_data = image_bitmap.bitmap_data().ok_or(())?;
+    let structured_writer = StructuredCloneWriter { w: w };
+    let size = image_bitmap.size();
+    assert!(JS_WriteUint32Pair(w, StructuredCloneTags::DomImageBitmap as u32, 0));
+    assert!(JS_WriteUint32Pair(w, size.width, size.height));
+    structured_writer.write_slice(&bitmap_data);
+    return Ok(())
+}
+
+/// The state of an `OffscreenCanvas` while it is being transferred.
+type TransferredOffscreenCanvas = (u64, u64, Option<OffscreenCanvasPlaceholder>);
+
 unsafe extern "C" fn read_callback(cx: *mut JSContext,
                                    r: *mut JSStructuredCloneReader,
                                    tag: u32,
@@ -136,6 +175,9 @@ unsafe extern "C" fn read_callback(cx: *mut JSContext,
     if tag == StructuredCloneTags::DomBlob as u32 {
         return read_blob(cx, r)
     }
+    if tag == StructuredCloneTags::DomImageBitmap as u32 {
+        return read_image_bitmap(cx, r)
+    }
     return ptr::null_mut()
 }
 
@@ -147,36 +189,61 @@ unsafe extern "C" fn write_callback(_cx: *mut JSContext,
     if let Ok(blob) = root_from_handleobject::<Blob>(Handle::from_raw(obj)) {
         return write_blob(blob, w).is_ok()
     }
+    if let Ok(image_bitmap) = root_from_handleobject::<ImageBitmap>(Handle::from_raw(obj)) {
+        return write_image_bitmap(image_bitmap, w).is_ok()
+    }
     return false
 }
 
-unsafe extern "C" fn read_transfer_callback(_cx: *mut JSContext,
+unsafe extern "C" fn read_transfer_callback(cx: *mut JSContext,
                                             _r: *mut JSStructuredCloneReader,
-                                            _tag: u32,
-                                            _content: *mut raw::c_void,
+                                            tag: u32,
+                                            content: *mut raw::c_void,
                                             _extra_data: u64,
                                             _closure: *mut raw::c_void,
-                                            _return_object: RawMutableHandleObject)
+                                            return_object: RawMutableHandleObject)
                                             -> bool {
+    if tag == StructuredCloneTags::DomOffscreenCanvas as u32 {
+        let (width, height, placeholder) = *Box::from_raw(content as *mut TransferredOffscreenCanvas);
+        let target_global = GlobalScope::from_context(cx);
+        let canvas = OffscreenCanvas::new(&target_global, width, height, placeholder);
+        MutableHandleObject::from_raw(return_object).set(canvas.reflector().get_jsobject().get());
+        return true
+    }
     false
 }
 
+/// <https://html.spec.whatwg.org/multipage/#the-offscreencanvas-interface:transfer-steps>
 unsafe extern "C" fn write_transfer_callback(_cx: *mut JSContext,
-                                             _obj: RawHandleObject,
+                                             obj: RawHandleObject,
                                              _closure: *mut raw::c_void,
-                                             _tag: *mut u32,
-                                             _ownership: *mut TransferableOwnership,
-                                             _content:  *mut *mut raw::c_void,
-                                             _extra_data: *mut u64)
+                                             tag: *mut u32,
+                                             ownership: *mut TransferableOwnership,
+                                             content:  *mut *mut raw::c_void,
+                                             extra_data: *mut u64)
                                              -> bool {
+    if let Ok(canvas) = root_from_handleobject::<OffscreenCanvas>(Handle::from_raw(obj)) {
+        let transferred: TransferredOffscreenCanvas = match canvas.transfer() {
+            Some(transferred) => transferred,
+            None => return false,
+        };
+        *tag = StructuredCloneTags::DomOffscreenCanvas as u32;
+        *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+        *content = Box::into_raw(Box::new(transferred)) as *mut raw::c_void;
+        *extra_data = 0;
+        return true
+    }
     false
 }
 
-unsafe extern "C" fn free_transfer_callback(_tag: u32,
+unsafe extern "C" fn free_transfer_callback(tag: u32,
                                             _ownership: TransferableOwnership,
-                                            _content: *mut raw::c_void,
+                                            content: *mut raw::c_void,
                                             _extra_data: u64,
                                             _closure: *mut raw::c_void) {
+    if tag == StructuredCloneTags::DomOffscreenCanvas as u32 && !content.is_null() {
+        drop(Box::from_raw(content as *mut TransferredOffscreenCanvas));
+    }
 }
 
 unsafe extern "C" fn report_error_callback(_cx: *mut JSContext, _errorid: u32) {
@@ -202,8 +269,25 @@ pub enum StructuredCloneData {
 impl StructuredCloneData {
     /// Writes a structured clone. Returns a `DataClone` error if that fails.
     pub fn write(cx: *mut JSContext, message: HandleValue) -> Fallible<StructuredCloneData> {
+        StructuredCloneData::write_with_transfer(cx, message, &[])
+    }
+
+    /// Writes a structured clone, transferring the objects of `transfer`.
+    /// Returns a `DataClone` error if that fails.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+    pub fn write_with_transfer(cx: *mut JSContext,
+                               message: HandleValue,
+                               transfer: &[*mut JSObject])
+                               -> Fallible<StructuredCloneData> {
         let mut data = ptr::null_mut();
         let mut nbytes = 0;
+        rooted!(in(cx) let mut transfer_list = UndefinedValue());
+        if !transfer.is_empty() {
+            let transfer_values: Vec<_> = transfer.iter().map(|object| ObjectValue(*object)).collect();
+            let transfer_array = unsafe { HandleValueArray::from_rooted_slice(&*transfer_values) };
+            transfer_list.set(ObjectValue(unsafe { JS_NewArrayObject(cx, &transfer_array) }));
+        }
         let result = unsafe {
             JS_WriteStructuredClone(cx,
                                     message,
@@ -211,7 +295,7 @@ impl StructuredCloneData {
                                     &mut nbytes,
                                     &STRUCTURED_CLONE_CALLBACKS,
                                     ptr::null_mut(),
-                                    HandleValue::undefined())
+                                    transfer_list.handle())
         };
         if !result {
             unsafe {
@@ -2,19 +2,26 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use canvas_traits::canvas::{Canvas2dMsg, CanvasMsg, CanvasId};
-use canvas_traits::canvas::{CompositionOrBlending, FillOrStrokeStyle, FillRule};
-use canvas_traits::canvas::{LineCapStyle, LineJoinStyle, LinearGradientStyle};
-use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle, byte_swap_and_premultiply};
-use cssparser::{Parser, ParserInput, RGBA};
+use canvas_traits::canvas::{Canvas2dMsg, CanvasFontStyle, CanvasMsg, CanvasId};
+use canvas_traits::canvas::{CompositionOrBlending, Direction, FillOrStrokeStyle, FillRule};
+use canvas_traits::canvas::{FilterFunction, LineCapStyle, LineJoinStyle, LinearGradientStyle};
+use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle, byte_swap, byte_swap_and_premultiply};
+use canvas_traits::canvas::{SmoothingQuality, TextAlign, TextBaseline};
+use canvas_traits::canvas::TextMetrics as CanvasTextMetrics;
+use cssparser::{ParseError, Parser, ParserInput, RGBA, Token};
 use cssparser::Color as CSSColor;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasDirection;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasFillRule;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasImageSource;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextAlign;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextBaseline;
+use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::ImageSmoothingQuality;
+use dom::bindings::codegen::Bindings::DOMMatrixBinding::DOMMatrix2DInit;
 use dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
 use dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
@@ -25,13 +32,17 @@ use dom::bindings::root::{Dom, DomRoot, LayoutDom};
 use dom::bindings::str::DOMString;
 use dom::canvasgradient::{CanvasGradient, CanvasGradientStyle, ToFillOrStrokeStyle};
 use dom::canvaspattern::CanvasPattern;
+use dom::dommatrix::DOMMatrix;
+use dom::dommatrixreadonly::dommatrix2dinit_to_matrix;
 use dom::element::Element;
 use dom::globalscope::GlobalScope;
 use dom::htmlcanvaselement::HTMLCanvasElement;
 use dom::imagedata::ImageData;
 use dom::node::{Node, NodeDamage, window_from_node};
+use dom::path2d::Path2D;
+use dom::textmetrics::TextMetrics;
 use dom_struct::dom_struct;
-use euclid::{Transform2D, Point2D, Vector2D, Rect, Size2D, vec2};
+use euclid::{Transform2D, Transform3D, Point2D, Vector2D, Rect, Size2D, vec2};
 use ipc_channel::ipc::IpcSender;
 use net_traits::image::base::PixelFormat;
 use net_traits::image_cache::CanRequestImages;
@@ -46,8 +57,12 @@ use script_traits::ScriptMsg;
 use servo_url::ServoUrl;
 use std::{cmp, fmt, mem};
 use std::cell::Cell;
+use std::f32::consts::PI;
 use std::str::FromStr;
 use std::sync::Arc;
+use style::computed_values::font_variant_caps::T as FontVariantCaps;
+use style::values::computed::font::{FontStretch, FontStyle, FontWeight};
+use style_traits::ToCss;
 use unpremultiplytable::UNPREMULTIPLY_TABLE;
 
 #[must_root]
@@ -66,10 +81,11 @@ pub struct CanvasRenderingContext2D {
     #[ignore_malloc_size_of = "Defined in ipc-channel"]
     ipc_renderer: IpcSender<CanvasMsg>,
     /// For rendering contexts created by an HTML canvas element, this is Some,
-    /// for ones created by a paint worklet, this is None.
+    /// for ones created by a paint worklet or an offscreen canvas, this is None.
     canvas: Option<Dom<HTMLCanvasElement>>,
+    /// The image cache used to draw images, which is None in workers.
     #[ignore_malloc_size_of = "Arc"]
-    image_cache: Arc<ImageCache>,
+    image_cache: Option<Arc<ImageCache>>,
     /// Any missing image URLs.
     missing_image_urls: DomRefCell<Vec<ServoUrl>>,
     /// The base URL for resolving CSS image URL values.
@@ -79,6 +95,8 @@ pub struct CanvasRenderingContext2D {
     saved_states: DomRefCell<Vec<CanvasContextState>>,
     origin_clean: Cell<bool>,
     canvas_id: CanvasId,
+    /// The dimensions of the output bitmap.
+    bitmap_size: Cell<Size2D<i32>>,
 }
 
 #[must_root]
@@ -87,6 +105,7 @@ struct CanvasContextState {
     global_alpha: f64,
     global_composition: CompositionOrBlending,
     image_smoothing_enabled: bool,
+    image_smoothing_quality: SmoothingQuality,
     fill_style: CanvasFillOrStrokeStyle,
     stroke_style: CanvasFillOrStrokeStyle,
     line_width: f64,
@@ -98,6 +117,13 @@ struct CanvasContextState {
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: RGBA,
+    /// The value of the `filter` attribute, as last set to a valid value.
+    filter: String,
+    font: String,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
+    /// The `direction` attribute, `None` for `inherit`.
+    direction: Option<Direction>,
 }
 
 impl CanvasContextState {
@@ -107,6 +133,7 @@ impl CanvasContextState {
             global_alpha: 1.0,
             global_composition: CompositionOrBlending::default(),
             image_smoothing_enabled: true,
+            image_smoothing_quality: SmoothingQuality::default(),
             fill_style: CanvasFillOrStrokeStyle::Color(black),
             stroke_style: CanvasFillOrStrokeStyle::Color(black),
             line_width: 1.0,
@@ -118,6 +145,11 @@ impl CanvasContextState {
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: RGBA::transparent(),
+            filter: "none".to_owned(),
+            font: "10px sans-serif".to_owned(),
+            text_align: TextAlign::default(),
+            text_baseline: TextBaseline::default(),
+            direction: None,
         }
     }
 }
@@ -125,7 +157,7 @@ impl CanvasContextState {
 impl CanvasRenderingContext2D {
     pub fn new_inherited(global: &GlobalScope,
                          canvas: Option<&HTMLCanvasElement>,
-                         image_cache: Arc<ImageCache>,
+                         image_cache: Option<Arc<ImageCache>>,
                          base_url: ServoUrl,
                          size: Size2D<i32>)
                          -> CanvasRenderingContext2D {
@@ -147,6 +179,7 @@ impl CanvasRenderingContext2D {
             saved_states: DomRefCell::new(Vec::new()),
             origin_clean: Cell::new(true),
             canvas_id: canvas_id,
+            bitmap_size: Cell::new(size),
         }
     }
 
@@ -158,7 +191,7 @@ impl CanvasRenderingContext2D {
         let image_cache = window.image_cache();
         let base_url = window.get_url();
         let boxed = Box::new(CanvasRenderingContext2D::new_inherited(
-            global, Some(canvas), image_cache, base_url, size
+            global, Some(canvas), Some(image_cache), base_url, size
         ));
         reflect_dom_object(boxed, global, CanvasRenderingContext2DBinding::Wrap)
     }
@@ -166,6 +199,7 @@ impl CanvasRenderingContext2D {
     // https://html.spec.whatwg.org/multipage/#concept-canvas-set-bitmap-dimensions
     pub fn set_bitmap_dimensions(&self, size: Size2D<i32>) {
         self.reset_to_initial_state();
+        self.bitmap_size.set(size);
         self.ipc_renderer
             .send(CanvasMsg::Recreate(size, self.get_canvas_id()))
             .unwrap();
@@ -183,10 +217,54 @@ impl CanvasRenderingContext2D {
         }
     }
 
+    /// Clears the whole bitmap to transparent black, whatever the current
+    /// transformation matrix is.
+    pub fn clear_bitmap(&self) {
+        let size = self.bitmap_size.get();
+        let rect = Rect::new(Point2D::zero(), Size2D::new(size.width as f32, size.height as f32));
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTransform(Transform2D::identity()));
+        self.send_canvas_2d_msg(Canvas2dMsg::ClearRect(rect));
+        self.update_transform();
+    }
+
+    /// The smoothing applied to scaled images, `None` if it is disabled.
+    fn image_smoothing(&self) -> Option<SmoothingQuality> {
+        let state = self.state.borrow();
+        if state.image_smoothing_enabled {
+            Some(state.image_smoothing_quality)
+        } else {
+            None
+        }
+    }
+
     fn update_transform(&self) {
         self.send_canvas_2d_msg(Canvas2dMsg::SetTransform(self.state.borrow().transform))
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn resolved_direction(&self) -> Direction {
+        if let Some(direction) = self.state.borrow().direction {
+            return direction;
+        }
+
+        // "inherit" uses the directionality of the canvas element, which is
+        // the first `dir` attribute found on it or its ancestors.
+        if let Some(ref canvas) = self.canvas {
+            let ancestors = canvas.upcast::<Node>().inclusive_ancestors()
+                .filter_map(DomRoot::downcast::<Element>);
+            for element in ancestors {
+                let dir = element.get_string_attribute(&local_name!("dir"));
+                if dir.eq_ignore_ascii_case("rtl") {
+                    return Direction::Rtl;
+                }
+                if dir.eq_ignore_ascii_case("ltr") {
+                    return Direction::Ltr;
+                }
+            }
+        }
+        Direction::Ltr
+    }
+
     // It is used by DrawImage to calculate the size of the source and destination rectangles based
     // on the drawImage call arguments
     // source rectangle = area of the original image to be copied
@@ -252,6 +330,8 @@ impl CanvasRenderingContext2D {
                 let image_origin = image.get_origin().expect("Image's origin is missing");
                 image_origin.same_origin(GlobalScope::entry().origin())
             }
+            CanvasImageSource::ImageBitmap(bitmap) => bitmap.origin_is_clean(),
+            CanvasImageSource::OffscreenCanvas(canvas) => canvas.origin_is_clean(),
             CanvasImageSource::CSSStyleValue(_) => true,
         }
     }
@@ -308,6 +388,20 @@ impl CanvasRenderingContext2D {
                                                sx, sy, sw, sh,
                                                dx, dy, dw, dh)
             }
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                // A closed ImageBitmap is no longer usable.
+                let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                self.draw_bitmap_data(data, bitmap.size().to_i32(),
+                                      sx, sy, sw, sh,
+                                      dx, dy, dw, dh)
+            }
+            CanvasImageSource::OffscreenCanvas(ref canvas) => {
+                // A detached or zero-sized OffscreenCanvas is not usable.
+                let (data, size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+                self.draw_bitmap_data(data, size,
+                                      sx, sy, sw, sh,
+                                      dx, dy, dw, dh)
+            }
             CanvasImageSource::CSSStyleValue(ref value) => {
                 let url = value.get_url(self.base_url.clone()).ok_or(Error::InvalidState)?;
                 self.fetch_and_draw_image_data(url,
@@ -360,11 +454,11 @@ impl CanvasRenderingContext2D {
             return Ok(());
         }
 
-        let smoothing_enabled = self.state.borrow().image_smoothing_enabled;
+        let smoothing = self.image_smoothing();
 
         if self.canvas.as_ref().map_or(false, |c| &**c == canvas) {
             self.send_canvas_2d_msg(Canvas2dMsg::DrawImageSelf(
-                image_size, dest_rect, source_rect, smoothing_enabled));
+                image_size, dest_rect, source_rect, smoothing));
         } else {
             let context = match canvas.get_or_init_2d_context() {
                 Some(context) => context,
@@ -377,7 +471,7 @@ impl CanvasRenderingContext2D {
                     image_size,
                     dest_rect,
                     source_rect,
-                    smoothing_enabled
+                    smoothing
                 ),
                 context.get_canvas_id()
             );
@@ -425,6 +519,33 @@ impl CanvasRenderingContext2D {
                              dx, dy, dw, dh)
     }
 
+    /// Draws a bitmap in the premultiplied BGRA layout used by canvas
+    /// backends, such as the contents of an ImageBitmap or an OffscreenCanvas.
+    fn draw_bitmap_data(&self,
+                        mut image_data: Vec<u8>,
+                        image_size: Size2D<i32>,
+                        sx: f64,
+                        sy: f64,
+                        sw: Option<f64>,
+                        sh: Option<f64>,
+                        dx: f64,
+                        dy: f64,
+                        dw: Option<f64>,
+                        dh: Option<f64>)
+                        -> ErrorResult {
+        // drawImage expects RGBA, so swap the color values.
+        byte_swap(&mut image_data);
+        let image_size = Size2D::new(image_size.width as f64, image_size.height as f64);
+        let dw = dw.unwrap_or(image_size.width);
+        let dh = dh.unwrap_or(image_size.height);
+        let sw = sw.unwrap_or(image_size.width);
+        let sh = sh.unwrap_or(image_size.height);
+        self.draw_image_data(image_data,
+                             image_size,
+                             sx, sy, sw, sh,
+                             dx, dy, dw, dh)
+    }
+
     fn draw_image_data(&self,
                        image_data: Vec<u8>,
                        image_size: Size2D<f64>,
@@ -452,13 +573,12 @@ impl CanvasRenderingContext2D {
             return Ok(());
         }
 
-        let smoothing_enabled = self.state.borrow().image_smoothing_enabled;
         self.send_canvas_2d_msg(Canvas2dMsg::DrawImage(
             image_data.into(),
             image_size,
             dest_rect,
             source_rect,
-            smoothing_enabled,
+            self.image_smoothing(),
         ));
         self.mark_as_dirty();
         Ok(())
@@ -487,7 +607,11 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     fn request_image_from_cache(&self, url: ServoUrl) -> ImageResponse {
-        let response = self.image_cache
+        let image_cache = match self.image_cache {
+            Some(ref image_cache) => image_cache,
+            None => return ImageResponse::None,
+        };
+        let response = image_cache
             .find_image_or_metadata(url.clone(),
                                     UsePlaceholder::No,
                                     CanRequestImages::No);
@@ -573,7 +697,7 @@ impl CanvasRenderingContext2D {
         self.origin_clean.get()
     }
 
-    fn set_origin_unclean(&self) {
+    pub fn set_origin_unclean(&self) {
         self.origin_clean.set(false)
     }
 }
@@ -692,6 +816,37 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.update_transform()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-settransform-matrix
+    fn SetTransform_(&self, transform: &DOMMatrix2DInit) -> ErrorResult {
+        // Step 1.
+        let matrix = dommatrix2dinit_to_matrix(transform)?;
+        let transform = Transform2D::row_major(matrix.m11 as f32, matrix.m12 as f32,
+                                               matrix.m21 as f32, matrix.m22 as f32,
+                                               matrix.m41 as f32, matrix.m42 as f32);
+
+        // Step 2.
+        if !transform.to_row_major_array().iter().all(|value| value.is_finite()) {
+            return Ok(());
+        }
+
+        // Step 3.
+        self.state.borrow_mut().transform = transform;
+        self.update_transform();
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-gettransform
+    fn GetTransform(&self) -> DomRoot<DOMMatrix> {
+        let transform = self.state.borrow().transform;
+        let matrix = Transform3D::row_major(
+            transform.m11 as f64, transform.m12 as f64, 0.0, 0.0,
+            transform.m21 as f64, transform.m22 as f64, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            transform.m31 as f64, transform.m32 as f64, 0.0, 1.0,
+        );
+        DOMMatrix::new(&self.global(), true, matrix)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-resettransform
     fn ResetTransform(&self) {
         self.state.borrow_mut().transform = Transform2D::identity();
@@ -766,9 +921,14 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
-    fn Fill(&self, _: CanvasFillRule) {
-        // TODO: Process fill rule
-        self.send_canvas_2d_msg(Canvas2dMsg::Fill);
+    fn Fill(&self, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::Fill(to_fill_rule(fill_rule)));
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::FillPath2D(path.segments(), to_fill_rule(fill_rule)));
         self.mark_as_dirty();
     }
 
@@ -778,30 +938,84 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokePath2D(path.segments()));
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip(&self, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::Clip(to_fill_rule(fill_rule)));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
-    fn Clip(&self, _: CanvasFillRule) {
-        // TODO: Process fill rule
-        self.send_canvas_2d_msg(Canvas2dMsg::Clip);
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.send_canvas_2d_msg(Canvas2dMsg::ClipPath2D(path.segments(), to_fill_rule(fill_rule)));
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
-        let fill_rule = match fill_rule {
-            CanvasFillRule::Nonzero => FillRule::Nonzero,
-            CanvasFillRule::Evenodd => FillRule::Evenodd,
-        };
         let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
-        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath(x, y, fill_rule, sender));
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath(x, y, to_fill_rule(fill_rule), sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        let fill_rule = to_fill_rule(fill_rule);
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPath2D(path.segments(), x, y, fill_rule, sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinstroke
+    fn IsPointInStroke(&self, x: f64, y: f64) -> bool {
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInStroke(x, y, sender));
+        receiver.recv().unwrap()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinstroke
+    fn IsPointInStroke_(&self, path: &Path2D, x: f64, y: f64) -> bool {
+        let (sender, receiver) = ipc::channel::<bool>(self.global().time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInStroke2D(path.segments(), x, y, sender));
         receiver.recv().unwrap()
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
     fn FillText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+
         let parsed_text: String = text.into();
-        self.send_canvas_2d_msg(Canvas2dMsg::FillText(parsed_text, x, y, max_width));
+        let direction = self.resolved_direction();
+        self.send_canvas_2d_msg(Canvas2dMsg::FillText(parsed_text, x, y, max_width, direction));
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    fn StrokeText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+
+        let parsed_text: String = text.into();
+        let direction = self.resolved_direction();
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokeText(parsed_text, x, y, max_width, direction));
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    fn MeasureText(&self, text: DOMString) -> DomRoot<TextMetrics> {
+        let (sender, receiver) =
+            ipc::channel::<CanvasTextMetrics>(self.global().time_profiler_chan().clone()).unwrap();
+        let direction = self.resolved_direction();
+        self.send_canvas_2d_msg(Canvas2dMsg::MeasureText(text.into(), direction, sender));
+        TextMetrics::new(&self.global(), receiver.recv().unwrap())
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage(&self,
                  image: CanvasImageSource,
@@ -970,6 +1184,24 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.state.borrow_mut().image_smoothing_enabled = value;
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingquality
+    fn ImageSmoothingQuality(&self) -> ImageSmoothingQuality {
+        match self.state.borrow().image_smoothing_quality {
+            SmoothingQuality::Low => ImageSmoothingQuality::Low,
+            SmoothingQuality::Medium => ImageSmoothingQuality::Medium,
+            SmoothingQuality::High => ImageSmoothingQuality::High,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-imagesmoothingquality
+    fn SetImageSmoothingQuality(&self, value: ImageSmoothingQuality) {
+        self.state.borrow_mut().image_smoothing_quality = match value {
+            ImageSmoothingQuality::Low => SmoothingQuality::Low,
+            ImageSmoothingQuality::Medium => SmoothingQuality::Medium,
+            ImageSmoothingQuality::High => SmoothingQuality::High,
+        };
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokestyle
     fn StrokeStyle(&self) -> StringOrCanvasGradientOrCanvasPattern {
         match self.state.borrow().stroke_style {
@@ -1110,7 +1342,7 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
         let dest_rect = Rect::new(Point2D::new(sx.to_i32().unwrap(), sy.to_i32().unwrap()),
                                   Size2D::new(sw as i32, sh as i32));
-        let canvas_size = self.canvas.as_ref().map(|c| c.get_size()).unwrap_or(Size2D::zero());
+        let canvas_size = self.bitmap_size.get();
         let canvas_size = Size2D::new(canvas_size.width as f64, canvas_size.height as f64);
         self.send_canvas_2d_msg(Canvas2dMsg::GetImageData(dest_rect, canvas_size, sender));
         let mut data = receiver.recv().unwrap();
@@ -1222,6 +1454,13 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
 
                 canvas.fetch_all_data().ok_or(Error::InvalidState)?
             }
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                (data, bitmap.size().to_i32())
+            }
+            CanvasImageSource::OffscreenCanvas(ref canvas) => {
+                canvas.fetch_all_data().ok_or(Error::InvalidState)?
+            }
             CanvasImageSource::CSSStyleValue(ref value) => {
                 value.get_url(self.base_url.clone())
                     .and_then(|url| self.fetch_image_data(url))
@@ -1372,6 +1611,117 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
             self.send_canvas_2d_msg(Canvas2dMsg::SetShadowColor(color))
         }
     }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-filter
+    fn Filter(&self) -> DOMString {
+        DOMString::from(self.state.borrow().filter.clone())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-filter
+    fn SetFilter(&self, value: DOMString) {
+        if let Ok(filters) = parse_filter(&value) {
+            self.state.borrow_mut().filter = value.into();
+            self.send_canvas_2d_msg(Canvas2dMsg::SetFilter(filters))
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn Font(&self) -> DOMString {
+        DOMString::from(self.state.borrow().font.clone())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn SetFont(&self, value: DOMString) {
+        // CSS-wide keywords are ignored rather than resolved.
+        let keyword = value.trim();
+        if ["inherit", "initial", "unset"].iter().any(|k| keyword.eq_ignore_ascii_case(k)) {
+            return;
+        }
+
+        // Relative values are resolved against the canvas element, so a
+        // context without one (such as a paint worklet's) cannot resolve a font.
+        let canvas = match self.canvas {
+            Some(ref canvas) => canvas,
+            None => return,
+        };
+        let window = window_from_node(&**canvas);
+        let resolved = match window.resolved_font_style_query(canvas.upcast::<Node>(), value.into()) {
+            Some(resolved) => resolved,
+            None => return,
+        };
+
+        let font = CanvasFontStyle::from_style_struct(&resolved);
+        self.state.borrow_mut().font = serialize_font(&font);
+        self.send_canvas_2d_msg(Canvas2dMsg::SetFont(font))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn TextAlign(&self) -> CanvasTextAlign {
+        match self.state.borrow().text_align {
+            TextAlign::Start => CanvasTextAlign::Start,
+            TextAlign::End => CanvasTextAlign::End,
+            TextAlign::Left => CanvasTextAlign::Left,
+            TextAlign::Right => CanvasTextAlign::Right,
+            TextAlign::Center => CanvasTextAlign::Center,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn SetTextAlign(&self, value: CanvasTextAlign) {
+        let text_align = match value {
+            CanvasTextAlign::Start => TextAlign::Start,
+            CanvasTextAlign::End => TextAlign::End,
+            CanvasTextAlign::Left => TextAlign::Left,
+            CanvasTextAlign::Right => TextAlign::Right,
+            CanvasTextAlign::Center => TextAlign::Center,
+        };
+        self.state.borrow_mut().text_align = text_align;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextAlign(text_align))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn TextBaseline(&self) -> CanvasTextBaseline {
+        match self.state.borrow().text_baseline {
+            TextBaseline::Top => CanvasTextBaseline::Top,
+            TextBaseline::Hanging => CanvasTextBaseline::Hanging,
+            TextBaseline::Middle => CanvasTextBaseline::Middle,
+            TextBaseline::Alphabetic => CanvasTextBaseline::Alphabetic,
+            TextBaseline::Ideographic => CanvasTextBaseline::Ideographic,
+            TextBaseline::Bottom => CanvasTextBaseline::Bottom,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn SetTextBaseline(&self, value: CanvasTextBaseline) {
+        let text_baseline = match value {
+            CanvasTextBaseline::Top => TextBaseline::Top,
+            CanvasTextBaseline::Hanging => TextBaseline::Hanging,
+            CanvasTextBaseline::Middle => TextBaseline::Middle,
+            CanvasTextBaseline::Alphabetic => TextBaseline::Alphabetic,
+            CanvasTextBaseline::Ideographic => TextBaseline::Ideographic,
+            CanvasTextBaseline::Bottom => TextBaseline::Bottom,
+        };
+        self.state.borrow_mut().text_baseline = text_baseline;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextBaseline(text_baseline))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn Direction(&self) -> CanvasDirection {
+        match self.state.borrow().direction {
+            Some(Direction::Ltr) => CanvasDirection::Ltr,
+            Some(Direction::Rtl) => CanvasDirection::Rtl,
+            None => CanvasDirection::Inherit,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn SetDirection(&self, value: CanvasDirection) {
+        self.state.borrow_mut().direction = match value {
+            CanvasDirection::Ltr => Some(Direction::Ltr),
+            CanvasDirection::Rtl => Some(Direction::Rtl),
+            CanvasDirection::Inherit => None,
+        };
+    }
 }
 
 impl Drop for CanvasRenderingContext2D {
@@ -1397,6 +1747,160 @@ pub fn parse_color(string: &str) -> Result<RGBA, ()> {
     }
 }
 
+/// Parses the value of the `filter` attribute into the filter functions to
+/// apply, which are none for `none`. Only absolute lengths are supported, as
+/// there is no element to resolve relative lengths against.
+///
+/// <https://drafts.fxtf.org/filter-effects/#typedef-filter-value-list>
+fn parse_filter(string: &str) -> Result<Vec<FilterFunction>, ()> {
+    let mut input = ParserInput::new(string);
+    let mut parser = Parser::new(&mut input);
+    if parser.try(|parser| parser.expect_ident_matching("none")).is_ok() {
+        return parser.expect_exhausted().map(|_| vec![]).map_err(|_| ());
+    }
+
+    let mut filters = vec![];
+    loop {
+        let name = parser.expect_function().map_err(|_| ())?.to_ascii_lowercase();
+        let filter = parser.parse_nested_block(|parser| parse_filter_function(&name, parser)).map_err(|_| ())?;
+        filters.push(filter);
+        if parser.is_exhausted() {
+            return Ok(filters);
+        }
+    }
+}
+
+// https://drafts.fxtf.org/filter-effects/#supported-filter-functions
+fn parse_filter_function<'i, 't>(
+    name: &str,
+    parser: &mut Parser<'i, 't>,
+) -> Result<FilterFunction, ParseError<'i, ()>> {
+    // Arguments are optional, and amounts above 100% are clamped for the
+    // functions where they are meaningless.
+    let amount_or = |parser: &mut Parser<'i, 't>, default: f32| {
+        if parser.is_exhausted() { Ok(default) } else { parse_filter_amount(parser) }
+    };
+    Ok(match name {
+        "blur" => {
+            let std_deviation = if parser.is_exhausted() { 0. } else { parse_filter_length(parser)? };
+            if std_deviation < 0. {
+                return Err(parser.new_custom_error(()));
+            }
+            FilterFunction::Blur(std_deviation)
+        },
+        "brightness" => FilterFunction::Brightness(amount_or(parser, 1.)?),
+        "contrast" => FilterFunction::Contrast(amount_or(parser, 1.)?),
+        "drop-shadow" => {
+            let mut color = parser.try(|parser| CSSColor::parse(parser)).ok();
+            let offset = Vector2D::new(parse_filter_length(parser)?, parse_filter_length(parser)?);
+            let blur = parser.try(|parser| parse_filter_length(parser)).unwrap_or(0.);
+            if blur < 0. {
+                return Err(parser.new_custom_error(()));
+            }
+            if color.is_none() {
+                color = parser.try(|parser| CSSColor::parse(parser)).ok();
+            }
+            // The current color of a canvas filter is black.
+            let color = match color {
+                Some(CSSColor::RGBA(rgba)) => rgba,
+                _ => RGBA::new(0, 0, 0, 255),
+            };
+            FilterFunction::DropShadow(offset, blur, color)
+        },
+        "grayscale" => FilterFunction::Grayscale(amount_or(parser, 1.)?.min(1.)),
+        "hue-rotate" => {
+            let angle = if parser.is_exhausted() { 0. } else { parse_filter_angle(parser)? };
+            FilterFunction::HueRotate(angle)
+        },
+        "invert" => FilterFunction::Invert(amount_or(parser, 1.)?.min(1.)),
+        "opacity" => FilterFunction::Opacity(amount_or(parser, 1.)?.min(1.)),
+        "saturate" => FilterFunction::Saturate(amount_or(parser, 1.)?),
+        "sepia" => FilterFunction::Sepia(amount_or(parser, 1.)?.min(1.)),
+        _ => return Err(parser.new_custom_error(())),
+    })
+}
+
+/// Parses a non-negative `<number-percentage>`, as a number.
+fn parse_filter_amount<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<f32, ParseError<'i, ()>> {
+    let amount = match parser.next()?.clone() {
+        Token::Number { value, .. } => value,
+        Token::Percentage { unit_value, .. } => unit_value,
+        _ => return Err(parser.new_custom_error(())),
+    };
+    if amount < 0. {
+        return Err(parser.new_custom_error(()));
+    }
+    Ok(amount)
+}
+
+/// Parses an absolute `<length>`, in pixels.
+fn parse_filter_length<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<f32, ParseError<'i, ()>> {
+    match parser.next()?.clone() {
+        Token::Number { value, .. } if value == 0. => Ok(0.),
+        Token::Dimension { value, ref unit, .. } => {
+            let px_per_unit = match &*unit.to_ascii_lowercase() {
+                "px" => 1.,
+                "in" => 96.,
+                "cm" => 96. / 2.54,
+                "mm" => 96. / 25.4,
+                "q" => 96. / 101.6,
+                "pt" => 96. / 72.,
+                "pc" => 96. / 6.,
+                _ => return Err(parser.new_custom_error(())),
+            };
+            Ok(value * px_per_unit)
+        },
+        _ => Err(parser.new_custom_error(())),
+    }
+}
+
+/// Parses an `<angle>`, in radians.
+fn parse_filter_angle<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<f32, ParseError<'i, ()>> {
+    match parser.next()?.clone() {
+        Token::Number { value, .. } if value == 0. => Ok(0.),
+        Token::Dimension { value, ref unit, .. } => {
+            match &*unit.to_ascii_lowercase() {
+                "deg" => Ok(value.to_radians()),
+                "grad" => Ok(value * PI / 200.),
+                "rad" => Ok(value),
+                "turn" => Ok(value * 2. * PI),
+                _ => Err(parser.new_custom_error(())),
+            }
+        },
+        _ => Err(parser.new_custom_error(())),
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+// The font is serialized as a CSS `font` shorthand without line-height,
+// leaving out the components that have their initial value.
+fn serialize_font(font: &CanvasFontStyle) -> String {
+    let mut components = vec![];
+    if font.font_style != FontStyle::normal() {
+        components.push(font.font_style.to_css_string());
+    }
+    if font.font_variant_caps != FontVariantCaps::Normal {
+        components.push(font.font_variant_caps.to_css_string());
+    }
+    if font.font_weight != FontWeight::normal() {
+        components.push(font.font_weight.to_css_string());
+    }
+    if font.font_stretch != FontStretch::hundred() {
+        components.push(font.font_stretch.to_css_string());
+    }
+    components.push(format!("{}px", font.font_size.to_f64_px()));
+    let families: Vec<String> = font.font_family.iter().map(|family| family.to_css_string()).collect();
+    components.push(families.join(", "));
+    components.join(" ")
+}
+
+fn to_fill_rule(fill_rule: CanvasFillRule) -> FillRule {
+    match fill_rule {
+        CanvasFillRule::Nonzero => FillRule::Nonzero,
+        CanvasFillRule::Evenodd => FillRule::Evenodd,
+    }
+}
+
 // Used by drawImage to determine if a source or destination rectangle is valid
 // Origin coordinates and size cannot be negative. Size has to be greater than zero
 fn is_rect_valid(rect: Rect<f64>) -> bool {
@@ -2,20 +2,29 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use devtools_traits::{ConsoleMessage, LogLevel, ScriptToDevtoolsControlMsg};
+use devtools::{OBJECT_PREVIEW_MAX_ITEMS, jsval_to_devtools_value};
+use devtools_traits::{ConsoleCounter, ConsoleMessage, ConsoleTimer, EvaluateJSReply};
+use devtools_traits::{LogLevel, ScriptToDevtoolsControlMsg, StackFrame};
+use dom::bindings::conversions::jsstring_to_str;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::str::DOMString;
 use dom::globalscope::GlobalScope;
 use dom::workerglobalscope::WorkerGlobalScope;
+use js::jsapi::{BuildStackString, CaptureCurrentStack, JSContext, JSObject, JSString};
+use js::jsapi::{JS_ClearPendingException, StackFormat};
+use js::rust::HandleValue;
 use std::io;
+use std::ptr;
+
+/// The maximum number of rows and columns shown by `console.table`.
+const TABLE_MAX_ITEMS: usize = 1000;
 
 // https://developer.mozilla.org/en-US/docs/Web/API/Console
 pub struct Console(());
 
 impl Console {
-    fn send_to_devtools(global: &GlobalScope, level: LogLevel, message: DOMString) {
+    fn send_to_devtools(global: &GlobalScope, console_message: ConsoleMessage) {
         if let Some(chan) = global.devtools_chan() {
-            let console_message = prepare_message(level, message);
             let worker_id = global.downcast::<WorkerGlobalScope>().map(|worker| {
                 worker.get_worker_id()
             });
@@ -26,6 +35,16 @@ impl Console {
             chan.send(devtools_message).unwrap();
         }
     }
+
+    /// Print `arguments` to stdout, substituting format specifiers, and forward them
+    /// to devtools.
+    fn log(global: &GlobalScope, level: LogLevel, arguments: Vec<EvaluateJSReply>) {
+        with_stderr_lock(move || {
+            print(global, &format_arguments(&arguments));
+            let stacktrace = capture_stack(global, false);
+            Self::send_to_devtools(global, prepare_message(global, level, arguments, stacktrace));
+        })
+    }
 }
 
 // In order to avoid interleaving the stdout output of the Console API methods
@@ -41,73 +60,195 @@ fn with_stderr_lock<F>(f: F) where F: FnOnce() {
 
 impl Console {
     // https://developer.mozilla.org/en-US/docs/Web/API/Console/log
-    pub fn Log(global: &GlobalScope, messages: Vec<DOMString>) {
-        with_stderr_lock(move || {
-            for message in messages {
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Log, message);
-            }
-        })
+    #[allow(unsafe_code)]
+    pub unsafe fn Log(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        Self::log(global, LogLevel::Log, devtools_values(cx, &data, 1))
     }
 
     // https://developer.mozilla.org/en-US/docs/Web/API/Console
-    pub fn Debug(global: &GlobalScope, messages: Vec<DOMString>) {
+    #[allow(unsafe_code)]
+    pub unsafe fn Debug(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        Self::log(global, LogLevel::Debug, devtools_values(cx, &data, 1))
+    }
+
+    // https://developer.mozilla.org/en-US/docs/Web/API/Console/info
+    #[allow(unsafe_code)]
+    pub unsafe fn Info(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        Self::log(global, LogLevel::Info, devtools_values(cx, &data, 1))
+    }
+
+    // https://developer.mozilla.org/en-US/docs/Web/API/Console/warn
+    #[allow(unsafe_code)]
+    pub unsafe fn Warn(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        Self::log(global, LogLevel::Warn, devtools_values(cx, &data, 1))
+    }
+
+    // https://developer.mozilla.org/en-US/docs/Web/API/Console/error
+    #[allow(unsafe_code)]
+    pub unsafe fn Error(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        Self::log(global, LogLevel::Error, devtools_values(cx, &data, 1))
+    }
+
+    // https://console.spec.whatwg.org/#assert
+    #[allow(unsafe_code)]
+    pub unsafe fn Assert(cx: *mut JSContext, global: &GlobalScope, condition: bool, data: Vec<HandleValue>) {
+        if condition {
+            return;
+        }
+        let mut arguments = devtools_values(cx, &data, 1);
+        let first = match arguments.first() {
+            Some(&EvaluateJSReply::StringValue(ref first)) => Some(format!("Assertion failed: {}", first)),
+            _ => None,
+        };
+        match first {
+            Some(message) => arguments[0] = EvaluateJSReply::StringValue(message),
+            None => arguments.insert(0, EvaluateJSReply::StringValue("Assertion failed".to_owned())),
+        }
+        Self::log(global, LogLevel::Assert, arguments)
+    }
+
+    // https://console.spec.whatwg.org/#trace
+    #[allow(unsafe_code)]
+    pub unsafe fn Trace(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        let arguments = devtools_values(cx, &data, 1);
         with_stderr_lock(move || {
-            for message in messages {
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Debug, message);
+            let stacktrace = capture_stack(global, true);
+            let mut output = format_arguments(&arguments);
+            if output.is_empty() {
+                output.push_str("console.trace()");
+            }
+            for frame in stacktrace.iter().flat_map(|frames| frames) {
+                output.push_str(&format!("\n    {}@{}:{}:{}",
+                                         frame.functionName,
+                                         frame.filename,
+                                         frame.lineNumber,
+                                         frame.columnNumber));
             }
+            print(global, &output);
+            Self::send_to_devtools(global, prepare_message(global, LogLevel::Trace, arguments, stacktrace));
         })
     }
 
-    // https://developer.mozilla.org/en-US/docs/Web/API/Console/info
-    pub fn Info(global: &GlobalScope, messages: Vec<DOMString>) {
+    // https://console.spec.whatwg.org/#table
+    #[allow(unsafe_code)]
+    pub unsafe fn Table(cx: *mut JSContext,
+                        global: &GlobalScope,
+                        tabular_data: HandleValue,
+                        properties: Option<Vec<DOMString>>) {
+        if !tabular_data.is_object() {
+            return Self::log(global, LogLevel::Log, devtools_values(cx, &[tabular_data], 1));
+        }
+        let data = jsval_to_devtools_value(cx, tabular_data, 2, TABLE_MAX_ITEMS);
         with_stderr_lock(move || {
-            for message in messages {
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Info, message);
-            }
+            let properties = properties.as_ref().map(|properties| &**properties);
+            print(global, &format_table(&data, properties));
+            let stacktrace = capture_stack(global, false);
+            Self::send_to_devtools(global, prepare_message(global, LogLevel::Table, vec![data], stacktrace));
         })
     }
 
-    // https://developer.mozilla.org/en-US/docs/Web/API/Console/warn
-    pub fn Warn(global: &GlobalScope, messages: Vec<DOMString>) {
+    // https://console.spec.whatwg.org/#dir
+    #[allow(unsafe_code)]
+    pub unsafe fn Dir(cx: *mut JSContext, global: &GlobalScope, item: HandleValue) {
+        let arguments = vec![jsval_to_devtools_value(cx, item, 1, OBJECT_PREVIEW_MAX_ITEMS)];
         with_stderr_lock(move || {
-            for message in messages {
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Warn, message);
-            }
+            print(global, &stringify(&arguments[0], true));
+            let stacktrace = capture_stack(global, false);
+            Self::send_to_devtools(global, prepare_message(global, LogLevel::Dir, arguments, stacktrace));
         })
     }
 
-    // https://developer.mozilla.org/en-US/docs/Web/API/Console/error
-    pub fn Error(global: &GlobalScope, messages: Vec<DOMString>) {
+    // https://console.spec.whatwg.org/#dirxml
+    #[allow(unsafe_code)]
+    pub unsafe fn Dirxml(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        Self::log(global, LogLevel::DirXml, devtools_values(cx, &data, 1))
+    }
+
+    // https://console.spec.whatwg.org/#count
+    pub fn Count(global: &GlobalScope, label: DOMString) {
+        let count = global.increment_console_count(&label);
         with_stderr_lock(move || {
-            for message in messages {
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Error, message);
-            }
+            print(global, &format!("{}: {}", label, count));
+            let stacktrace = capture_stack(global, false);
+            let mut console_message = prepare_message(global,
+                                                      LogLevel::Count,
+                                                      vec![EvaluateJSReply::StringValue(label.to_string())],
+                                                      stacktrace);
+            console_message.counter = Some(ConsoleCounter {
+                label: String::from(label),
+                count: count,
+            });
+            Self::send_to_devtools(global, console_message);
         })
     }
 
-    // https://developer.mozilla.org/en-US/docs/Web/API/Console/assert
-    pub fn Assert(global: &GlobalScope, condition: bool, message: Option<DOMString>) {
+    // https://console.spec.whatwg.org/#countreset
+    pub fn CountReset(global: &GlobalScope, label: DOMString) {
         with_stderr_lock(move || {
-            if !condition {
-                let message = message.unwrap_or_else(|| DOMString::from("no message"));
-                println!("Assertion failed: {}", message);
-                Self::send_to_devtools(global, LogLevel::Error, message);
+            if global.reset_console_count(&label).is_err() {
+                let message = format!("Counter “{}” doesn’t exist.", label);
+                print(global, &message);
+                let stacktrace = capture_stack(global, false);
+                Self::send_to_devtools(global, prepare_message(global,
+                                                               LogLevel::Warn,
+                                                               vec![EvaluateJSReply::StringValue(message)],
+                                                               stacktrace));
+                return;
             }
+            let stacktrace = capture_stack(global, false);
+            let mut console_message = prepare_message(global,
+                                                      LogLevel::CountReset,
+                                                      vec![EvaluateJSReply::StringValue(label.to_string())],
+                                                      stacktrace);
+            console_message.counter = Some(ConsoleCounter {
+                label: String::from(label),
+                count: 0,
+            });
+            Self::send_to_devtools(global, console_message);
         })
     }
 
+    // https://console.spec.whatwg.org/#group
+    #[allow(unsafe_code)]
+    pub unsafe fn Group(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        let arguments = devtools_values(cx, &data, 1);
+        let label = format_arguments(&arguments);
+        Self::log(global, LogLevel::Group, arguments);
+        global.push_console_group(DOMString::from(label));
+    }
+
+    // https://console.spec.whatwg.org/#groupcollapsed
+    #[allow(unsafe_code)]
+    pub unsafe fn GroupCollapsed(cx: *mut JSContext, global: &GlobalScope, data: Vec<HandleValue>) {
+        let arguments = devtools_values(cx, &data, 1);
+        let label = format_arguments(&arguments);
+        Self::log(global, LogLevel::GroupCollapsed, arguments);
+        global.push_console_group(DOMString::from(label));
+    }
+
+    // https://console.spec.whatwg.org/#groupend
+    pub fn GroupEnd(global: &GlobalScope) {
+        if global.pop_console_group().is_some() {
+            let stacktrace = capture_stack(global, false);
+            Self::send_to_devtools(global, prepare_message(global, LogLevel::GroupEnd, vec![], stacktrace));
+        }
+    }
+
     // https://developer.mozilla.org/en-US/docs/Web/API/Console/time
     pub fn Time(global: &GlobalScope, label: DOMString) {
         with_stderr_lock(move || {
             if let Ok(()) = global.time(label.clone()) {
-                let message = DOMString::from(format!("{}: timer started", label));
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Log, message);
+                print(global, &format!("{}: timer started", label));
+                let stacktrace = capture_stack(global, false);
+                let mut console_message = prepare_message(global,
+                                                          LogLevel::Time,
+                                                          vec![EvaluateJSReply::StringValue(label.to_string())],
+                                                          stacktrace);
+                console_message.timer = Some(ConsoleTimer {
+                    name: String::from(label),
+                    duration: None,
+                });
+                Self::send_to_devtools(global, console_message);
             }
         })
     }
@@ -116,23 +257,287 @@ impl Console {
     pub fn TimeEnd(global: &GlobalScope, label: DOMString) {
         with_stderr_lock(move || {
             if let Ok(delta) = global.time_end(&label) {
-                let message = DOMString::from(
-                    format!("{}: {}ms", label, delta)
-                );
-                println!("{}", message);
-                Self::send_to_devtools(global, LogLevel::Log, message);
+                print(global, &format!("{}: {}ms", label, delta));
+                let stacktrace = capture_stack(global, false);
+                let mut console_message = prepare_message(global,
+                                                          LogLevel::TimeEnd,
+                                                          vec![EvaluateJSReply::StringValue(label.to_string())],
+                                                          stacktrace);
+                console_message.timer = Some(ConsoleTimer {
+                    name: String::from(label),
+                    duration: Some(delta),
+                });
+                Self::send_to_devtools(global, console_message);
             };
         })
     }
 }
 
-fn prepare_message(log_level: LogLevel, message: DOMString) -> ConsoleMessage {
-    // TODO: Sending fake values for filename, lineNumber and columnNumber in LogMessage; adjust later
+#[allow(unsafe_code)]
+unsafe fn devtools_values(cx: *mut JSContext, data: &[HandleValue], preview_depth: u32) -> Vec<EvaluateJSReply> {
+    data.iter().map(|value| {
+        jsval_to_devtools_value(cx, *value, preview_depth, OBJECT_PREVIEW_MAX_ITEMS)
+    }).collect()
+}
+
+/// Print a line to stdout, indented by the number of open groups.
+fn print(global: &GlobalScope, message: &str) {
+    let indent = "  ".repeat(global.console_group_depth());
+    for line in message.lines() {
+        println!("{}{}", indent, line);
+    }
+    if message.is_empty() {
+        println!("{}", indent);
+    }
+}
+
+/// Join the arguments into a single message, substituting the format specifiers
+/// of the first argument if it is a string.
+/// <https://console.spec.whatwg.org/#formatter>
+fn format_arguments(arguments: &[EvaluateJSReply]) -> String {
+    let (mut output, rest) = match arguments.split_first() {
+        Some((&EvaluateJSReply::StringValue(ref format), rest)) => {
+            let mut rest = rest.iter();
+            let mut output = String::new();
+            let mut chars = format.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    output.push(c);
+                    continue;
+                }
+                let specifier = match chars.peek() {
+                    Some(&specifier) => specifier,
+                    None => {
+                        output.push(c);
+                        break;
+                    },
+                };
+                if specifier == '%' {
+                    chars.next();
+                    output.push('%');
+                    continue;
+                }
+                if !"sdifoOc".contains(specifier) {
+                    output.push(c);
+                    continue;
+                }
+                let argument = match rest.next() {
+                    Some(argument) => argument,
+                    None => {
+                        output.push(c);
+                        continue;
+                    },
+                };
+                chars.next();
+                match specifier {
+                    's' => output.push_str(&stringify(argument, false)),
+                    'd' | 'i' => output.push_str(&number_to_string(to_number(argument).trunc())),
+                    'f' => output.push_str(&number_to_string(to_number(argument))),
+                    'o' | 'O' => output.push_str(&stringify(argument, true)),
+                    // Styling only applies to devtools.
+                    _ => {},
+                }
+            }
+            (output, rest.collect::<Vec<_>>())
+        },
+        _ => (String::new(), arguments.iter().collect()),
+    };
+    for argument in rest {
+        if !output.is_empty() {
+            output.push(' ');
+        }
+        output.push_str(&stringify(argument, false));
+    }
+    output
+}
+
+fn to_number(value: &EvaluateJSReply) -> f64 {
+    match *value {
+        EvaluateJSReply::NumberValue(value) => value,
+        EvaluateJSReply::BooleanValue(value) => if value { 1. } else { 0. },
+        EvaluateJSReply::NullValue => 0.,
+        EvaluateJSReply::StringValue(ref value) => value.trim().parse().unwrap_or(::std::f64::NAN),
+        _ => ::std::f64::NAN,
+    }
+}
+
+fn number_to_string(value: f64) -> String {
+    if value.is_infinite() {
+        if value > 0. { "Infinity".to_owned() } else { "-Infinity".to_owned() }
+    } else {
+        value.to_string()
+    }
+}
+
+/// A textual representation of a value; `nested` values are shown as literals,
+/// so that strings are quoted.
+fn stringify(value: &EvaluateJSReply, nested: bool) -> String {
+    match *value {
+        EvaluateJSReply::VoidValue => "undefined".to_owned(),
+        EvaluateJSReply::NullValue => "null".to_owned(),
+        EvaluateJSReply::BooleanValue(value) => value.to_string(),
+        EvaluateJSReply::NumberValue(value) => number_to_string(value),
+        EvaluateJSReply::StringValue(ref value) if nested => format!("\"{}\"", value),
+        EvaluateJSReply::StringValue(ref value) => value.clone(),
+        EvaluateJSReply::ActorValue { ref class, preview: None, .. } => format!("[object {}]", class),
+        EvaluateJSReply::ActorValue { ref class, preview: Some(ref preview), .. } => {
+            let mut items: Vec<String> = preview.ownProperties.iter().map(|&(ref name, ref value)| {
+                if preview.length.is_some() {
+                    stringify(value, true)
+                } else {
+                    format!("{}: {}", name, stringify(value, true))
+                }
+            }).collect();
+            if preview.ownPropertiesLength as usize > items.len() {
+                items.push("…".to_owned());
+            }
+            if preview.length.is_some() {
+                format!("[{}]", items.join(", "))
+            } else if class == "Object" {
+                format!("{{{}}}", items.join(", "))
+            } else {
+                format!("{} {{{}}}", class, items.join(", "))
+            }
+        },
+    }
+}
+
+/// Render the rows of a previewed object as a text table.
+fn format_table(data: &EvaluateJSReply, properties: Option<&[DOMString]>) -> String {
+    let rows = match *data {
+        EvaluateJSReply::ActorValue { preview: Some(ref preview), .. } => &preview.ownProperties,
+        _ => return stringify(data, true),
+    };
+
+    let mut columns: Vec<String> = vec![];
+    let mut has_values = false;
+    for &(_, ref value) in rows {
+        match *value {
+            EvaluateJSReply::ActorValue { preview: Some(ref preview), .. } => {
+                for &(ref name, _) in &preview.ownProperties {
+                    let wanted = properties.map_or(true, |properties| {
+                        properties.iter().any(|property| &**property == name)
+                    });
+                    if wanted && !columns.contains(name) {
+                        columns.push(name.clone());
+                    }
+                }
+            },
+            _ => has_values = true,
+        }
+    }
+
+    let mut header = vec!["(index)".to_owned()];
+    header.extend(columns.iter().cloned());
+    if has_values {
+        header.push("Values".to_owned());
+    }
+    let mut table = vec![header];
+    for &(ref index, ref value) in rows {
+        let mut row = vec![index.clone()];
+        let cells: &[(String, EvaluateJSReply)] = match *value {
+            EvaluateJSReply::ActorValue { preview: Some(ref preview), .. } => &*preview.ownProperties,
+            _ => &[],
+        };
+        for column in &columns {
+            row.push(cells.iter()
+                          .find(|&&(ref name, _)| name == column)
+                          .map_or(String::new(), |&(_, ref cell)| stringify(cell, true)));
+        }
+        if has_values {
+            row.push(match *value {
+                EvaluateJSReply::ActorValue { preview: Some(_), .. } => String::new(),
+                _ => stringify(value, true),
+            });
+        }
+        table.push(row);
+    }
+
+    let widths: Vec<usize> = (0..table[0].len()).map(|column| {
+        table.iter().map(|row| row[column].chars().count()).max().unwrap_or(0)
+    }).collect();
+    let separator = widths.iter().map(|width| "-".repeat(width + 2)).collect::<Vec<_>>().join("+");
+    let mut output = format!("+{}+", separator);
+    for (i, row) in table.iter().enumerate() {
+        let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| {
+            format!(" {}{} ", cell, " ".repeat(width - cell.chars().count()))
+        }).collect();
+        output.push_str(&format!("\n|{}|", cells.join("|")));
+        if i == 0 {
+            output.push_str(&format!("\n+{}+", separator));
+        }
+    }
+    output.push_str(&format!("\n+{}+", separator));
+    output
+}
+
+/// Capture the JS stack of the caller, innermost frame first. The stack is only
+/// needed by devtools unless `always` is set, so it is not captured otherwise.
+#[allow(unsafe_code)]
+fn capture_stack(global: &GlobalScope, always: bool) -> Option<Vec<StackFrame>> {
+    if !always && global.devtools_chan().is_none() {
+        return None;
+    }
+    Some(unsafe { current_stack(global.get_cx()) })
+}
+
+/// The scripted frames of the current JS stack, innermost first. The stack is read
+/// from the engine's saved frames, so page script can't forge it by overriding
+/// `Error` or `Error.prototype.stack`.
+#[allow(unsafe_code)]
+pub unsafe fn current_stack(cx: *mut JSContext) -> Vec<StackFrame> {
+    rooted!(in(cx) let mut stack = ptr::null_mut::<JSObject>());
+    if !CaptureCurrentStack(cx, stack.handle_mut().into(), 0) || stack.is_null() {
+        JS_ClearPendingException(cx);
+        return vec![];
+    }
+    rooted!(in(cx) let mut string = ptr::null_mut::<JSString>());
+    if !BuildStackString(cx, stack.handle().into(), string.handle_mut().into(), 0, StackFormat::SpiderMonkey) ||
+       string.is_null() {
+        JS_ClearPendingException(cx);
+        return vec![];
+    }
+    let stack = String::from(jsstring_to_str(cx, string.get()));
+    stack.lines().filter_map(parse_stack_frame).collect()
+}
+
+/// Parse a `functionName@filename:line:column` frame of a SpiderMonkey stack string.
+fn parse_stack_frame(frame: &str) -> Option<StackFrame> {
+    let mut parts = frame.rsplitn(3, ':');
+    let column_number = parts.next()?.parse().ok()?;
+    let line_number = parts.next()?.parse().ok()?;
+    let location = parts.next()?;
+    let (function_name, filename) = match location.find('@') {
+        Some(index) => (&location[..index], &location[index + 1..]),
+        None => ("", location),
+    };
+    Some(StackFrame {
+        filename: filename.to_owned(),
+        functionName: function_name.to_owned(),
+        lineNumber: line_number,
+        columnNumber: column_number,
+    })
+}
+
+fn prepare_message(global: &GlobalScope,
+                   log_level: LogLevel,
+                   arguments: Vec<EvaluateJSReply>,
+                   stacktrace: Option<Vec<StackFrame>>)
+                   -> ConsoleMessage {
+    let (filename, line_number, column_number) = match stacktrace.as_ref().and_then(|frames| frames.first()) {
+        Some(frame) => (frame.filename.clone(), frame.lineNumber as usize, frame.columnNumber as usize),
+        None => (global.get_url().into_string(), 0, 0),
+    };
+    // Only traces show their stack in devtools; for other messages it locates the caller.
+    let stacktrace = if log_level == LogLevel::Trace { stacktrace } else { None };
     ConsoleMessage {
-        message: String::from(message),
+        arguments: arguments,
         logLevel: log_level,
-        filename: "test".to_owned(),
-        lineNumber: 1,
-        columnNumber: 1,
+        filename: filename,
+        lineNumber: line_number,
+        columnNumber: column_number,
+        stacktrace: stacktrace,
+        counter: None,
+        timer: None,
     }
 }
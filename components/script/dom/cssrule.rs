@@ -13,6 +13,7 @@ use dom::csskeyframerule::CSSKeyframeRule;
 use dom::csskeyframesrule::CSSKeyframesRule;
 use dom::cssmediarule::CSSMediaRule;
 use dom::cssnamespacerule::CSSNamespaceRule;
+use dom::csspagerule::CSSPageRule;
 use dom::cssstylerule::CSSStyleRule;
 use dom::cssstylesheet::CSSStyleSheet;
 use dom::csssupportsrule::CSSSupportsRule;
@@ -58,6 +59,8 @@ impl CSSRule {
             rule as &SpecificCSSRule
         } else if let Some(rule) = self.downcast::<CSSViewportRule>() {
             rule as &SpecificCSSRule
+        } else if let Some(rule) = self.downcast::<CSSPageRule>() {
+            rule as &SpecificCSSRule
         } else if let Some(rule) = self.downcast::<CSSKeyframeRule>() {
             rule as &SpecificCSSRule
         } else if let Some(rule) = self.downcast::<CSSImportRule>() {
@@ -85,7 +88,7 @@ impl CSSRule {
             StyleCssRule::Namespace(s) => DomRoot::upcast(CSSNamespaceRule::new(window, parent_stylesheet, s)),
             StyleCssRule::Viewport(s) => DomRoot::upcast(CSSViewportRule::new(window, parent_stylesheet, s)),
             StyleCssRule::Supports(s) => DomRoot::upcast(CSSSupportsRule::new(window, parent_stylesheet, s)),
-            StyleCssRule::Page(_) => unreachable!(),
+            StyleCssRule::Page(s) => DomRoot::upcast(CSSPageRule::new(window, parent_stylesheet, s)),
             StyleCssRule::Document(_) => unimplemented!(), // TODO
         }
     }
@@ -44,6 +44,7 @@ use dom::errorevent::ErrorEvent;
 use dom::event::{Event, EventBubbles, EventCancelable, EventDefault, EventStatus};
 use dom::eventtarget::EventTarget;
 use dom::focusevent::FocusEvent;
+use dom::fontfaceset::FontFaceSet;
 use dom::globalscope::GlobalScope;
 use dom::hashchangeevent::HashChangeEvent;
 use dom::htmlanchorelement::HTMLAnchorElement;
@@ -282,6 +283,8 @@ pub struct Document {
     /// List of stylesheets associated with nodes in this document. |None| if the list needs to be refreshed.
     stylesheets: DomRefCell<DocumentStylesheetSet<StyleSheetInDocument>>,
     stylesheet_list: MutNullableDom<StyleSheetList>,
+    /// <https://drafts.csswg.org/css-font-loading/#font-face-source>
+    fonts: MutNullableDom<FontFaceSet>,
     ready_state: Cell<DocumentReadyState>,
     /// Whether the DOMContentLoaded event has already been dispatched.
     domcontentloaded_dispatched: Cell<bool>,
@@ -1775,6 +1778,14 @@ impl Document {
         self.decr_ignore_opens_during_unload_counter();
     }
 
+    /// Lets the `FontFaceSet` of this document, if script has accessed it, know that the
+    /// document may have finished loading its fonts.
+    pub fn web_fonts_updated(&self) {
+        if let Some(fonts) = self.fonts.get() {
+            fonts.document_fonts_updated();
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#the-end
     pub fn maybe_queue_document_completion(&self) {
         if self.loader.borrow().is_blocked() {
@@ -1827,6 +1838,9 @@ impl Document {
 
                 window.reflow(ReflowGoal::Full, ReflowReason::DocumentLoaded);
 
+                // Layout now knows about the fonts of all the stylesheets of the document.
+                document.web_fonts_updated();
+
                 document.notify_constellation_load();
 
                 if let Some(fragment) = document.url().fragment() {
@@ -2417,6 +2431,7 @@ impl Document {
             },
             stylesheets: DomRefCell::new(DocumentStylesheetSet::new()),
             stylesheet_list: MutNullableDom::new(None),
+            fonts: MutNullableDom::new(None),
             ready_state: Cell::new(ready_state),
             domcontentloaded_dispatched: Cell::new(domcontentloaded_dispatched),
             possibly_focused: Default::default(),
@@ -2944,6 +2959,11 @@ impl DocumentMethods for Document {
         self.stylesheet_list.or_init(|| StyleSheetList::new(&self.window, Dom::from_ref(&self)))
     }
 
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfacesource-fonts
+    fn Fonts(&self) -> DomRoot<FontFaceSet> {
+        self.fonts.or_init(|| FontFaceSet::new(&self.window))
+    }
+
     // https://dom.spec.whatwg.org/#dom-document-implementation
     fn Implementation(&self) -> DomRoot<DOMImplementation> {
         self.implementation.or_init(|| DOMImplementation::new(self))
@@ -3308,7 +3328,7 @@ impl DocumentMethods for Document {
             "uievent" | "uievents" =>
                 Ok(DomRoot::upcast(UIEvent::new_uninitialized(&self.window))),
             "webglcontextevent" =>
-                Ok(DomRoot::upcast(WebGLContextEvent::new_uninitialized(&self.window))),
+                Ok(DomRoot::upcast(WebGLContextEvent::new_uninitialized(self.window.upcast()))),
             _ =>
                 Err(Error::NotSupported),
         }
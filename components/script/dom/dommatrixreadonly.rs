@@ -3,7 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use dom::bindings::cell::DomRefCell;
-use dom::bindings::codegen::Bindings::DOMMatrixBinding::{DOMMatrixInit, DOMMatrixMethods};
+use dom::bindings::codegen::Bindings::DOMMatrixBinding::{DOMMatrix2DInit, DOMMatrixInit, DOMMatrixMethods};
 use dom::bindings::codegen::Bindings::DOMMatrixReadOnlyBinding::{DOMMatrixReadOnlyMethods, Wrap};
 use dom::bindings::codegen::Bindings::DOMPointBinding::DOMPointInit;
 use dom::bindings::error;
@@ -633,52 +633,71 @@ pub fn entries_to_matrix(entries: &[f64]) -> Fallible<(bool, Transform3D<f64>)>
 }
 
 
-// https://drafts.fxtf.org/geometry-1/#validate-and-fixup
-pub fn dommatrixinit_to_matrix(dict: &DOMMatrixInit) -> Fallible<(bool, Transform3D<f64>)> {
+// https://drafts.fxtf.org/geometry-1/#validate-and-fixup-2d
+fn validate_and_fixup_2d(dict: &DOMMatrix2DInit) -> Fallible<(f64, f64, f64, f64, f64, f64)> {
     // Step 1.
     if dict.a.is_some() && dict.m11.is_some() && dict.a.unwrap() != dict.m11.unwrap() ||
        dict.b.is_some() && dict.m12.is_some() && dict.b.unwrap() != dict.m12.unwrap() ||
        dict.c.is_some() && dict.m21.is_some() && dict.c.unwrap() != dict.m21.unwrap() ||
        dict.d.is_some() && dict.m22.is_some() && dict.d.unwrap() != dict.m22.unwrap() ||
        dict.e.is_some() && dict.m41.is_some() && dict.e.unwrap() != dict.m41.unwrap() ||
-       dict.f.is_some() && dict.m42.is_some() && dict.f.unwrap() != dict.m42.unwrap() ||
-       dict.is2D.is_some() && dict.is2D.unwrap() &&
+       dict.f.is_some() && dict.m42.is_some() && dict.f.unwrap() != dict.m42.unwrap() {
+        return Err(error::Error::Type("Invalid matrix initializer.".to_owned()));
+    }
+    // Step 2.
+    let m11 = dict.m11.unwrap_or(dict.a.unwrap_or(1.0));
+    // Step 3.
+    let m12 = dict.m12.unwrap_or(dict.b.unwrap_or(0.0));
+    // Step 4.
+    let m21 = dict.m21.unwrap_or(dict.c.unwrap_or(0.0));
+    // Step 5.
+    let m22 = dict.m22.unwrap_or(dict.d.unwrap_or(1.0));
+    // Step 6.
+    let m41 = dict.m41.unwrap_or(dict.e.unwrap_or(0.0));
+    // Step 7.
+    let m42 = dict.m42.unwrap_or(dict.f.unwrap_or(0.0));
+    Ok((m11, m12, m21, m22, m41, m42))
+}
+
+// https://drafts.fxtf.org/geometry-1/#create-a-dommatrix-from-the-2d-dictionary
+pub fn dommatrix2dinit_to_matrix(dict: &DOMMatrix2DInit) -> Fallible<Transform3D<f64>> {
+    validate_and_fixup_2d(dict).map(|(m11, m12, m21, m22, m41, m42)| {
+        Transform3D::row_major(m11, m12, 0.0, 0.0,
+                               m21, m22, 0.0, 0.0,
+                               0.0, 0.0, 1.0, 0.0,
+                               m41, m42, 0.0, 1.0)
+    })
+}
+
+// https://drafts.fxtf.org/geometry-1/#validate-and-fixup
+pub fn dommatrixinit_to_matrix(dict: &DOMMatrixInit) -> Fallible<(bool, Transform3D<f64>)> {
+    // Step 1.
+    let (m11, m12, m21, m22, m41, m42) = validate_and_fixup_2d(&dict.parent)?;
+    // Step 2.
+    if dict.is2D.is_some() && dict.is2D.unwrap() &&
        (dict.m31 != 0.0 || dict.m32 != 0.0 || dict.m13 != 0.0 || dict.m23 != 0.0 ||
         dict.m43 != 0.0 || dict.m14 != 0.0 || dict.m24 != 0.0 || dict.m34 != 0.0 ||
         dict.m33 != 1.0 || dict.m44 != 1.0) {
-            Err(error::Error::Type("Invalid matrix initializer.".to_owned()))
-    } else {
-        let mut is2D = dict.is2D;
-        // Step 2.
-        let m11 = dict.m11.unwrap_or(dict.a.unwrap_or(1.0));
-        // Step 3.
-        let m12 = dict.m12.unwrap_or(dict.b.unwrap_or(0.0));
-        // Step 4.
-        let m21 = dict.m21.unwrap_or(dict.c.unwrap_or(0.0));
-        // Step 5.
-        let m22 = dict.m22.unwrap_or(dict.d.unwrap_or(1.0));
-        // Step 6.
-        let m41 = dict.m41.unwrap_or(dict.e.unwrap_or(0.0));
-        // Step 7.
-        let m42 = dict.m42.unwrap_or(dict.f.unwrap_or(0.0));
-        // Step 8.
-        if is2D.is_none() &&
-            (dict.m31 != 0.0 || dict.m32 != 0.0 || dict.m13 != 0.0 ||
-             dict.m23 != 0.0 || dict.m43 != 0.0 || dict.m14 != 0.0 ||
-             dict.m24 != 0.0 || dict.m34 != 0.0 ||
-             dict.m33 != 1.0 || dict.m44 != 1.0) {
-                 is2D = Some(false);
-        }
-        // Step 9.
-        if is2D.is_none() {
-            is2D = Some(true);
-        }
-        let matrix = Transform3D::row_major(m11,      m12,      dict.m13, dict.m14,
-                                         m21,      m22,      dict.m23, dict.m24,
-                                         dict.m31, dict.m32, dict.m33, dict.m34,
-                                         m41,      m42,      dict.m43, dict.m44);
-        Ok((is2D.unwrap(), matrix))
-    }
+        return Err(error::Error::Type("Invalid matrix initializer.".to_owned()));
+    }
+    let mut is2D = dict.is2D;
+    // Step 3.
+    if is2D.is_none() &&
+        (dict.m31 != 0.0 || dict.m32 != 0.0 || dict.m13 != 0.0 ||
+         dict.m23 != 0.0 || dict.m43 != 0.0 || dict.m14 != 0.0 ||
+         dict.m24 != 0.0 || dict.m34 != 0.0 ||
+         dict.m33 != 1.0 || dict.m44 != 1.0) {
+             is2D = Some(false);
+    }
+    // Step 4.
+    if is2D.is_none() {
+        is2D = Some(true);
+    }
+    let matrix = Transform3D::row_major(m11,      m12,      dict.m13, dict.m14,
+                                        m21,      m22,      dict.m23, dict.m24,
+                                        dict.m31, dict.m32, dict.m33, dict.m34,
+                                        m41,      m42,      dict.m43, dict.m44);
+    Ok((is2D.unwrap(), matrix))
 }
 
 
@@ -11,13 +11,15 @@ use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::AttrBinding::AttrMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use dom::bindings::codegen::Bindings::ElementBinding;
+use dom::bindings::codegen::Bindings::ElementBinding::{ScrollIntoViewOptions, ScrollLogicalPosition};
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
-use dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, ScrollToOptions};
+use dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, ScrollOptions, ScrollToOptions};
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::codegen::UnionTypes::BooleanOrScrollIntoViewOptions;
 use dom::bindings::codegen::UnionTypes::NodeOrString;
 use dom::bindings::conversions::DerivedFrom;
 use dom::bindings::error::{Error, ErrorResult, Fallible};
@@ -104,6 +106,7 @@ use std::str::FromStr;
 use style::CaseSensitivityExt;
 use style::applicable_declarations::ApplicableDeclarationBlock;
 use style::attr::{AttrValue, LengthOrPercentageOrAuto};
+use style::computed_values::scroll_behavior::T as StyleScrollBehavior;
 use style::context::QuirksMode;
 use style::dom_apis;
 use style::element_state::ElementState;
@@ -521,6 +524,17 @@ impl LayoutElementHelpers for LayoutDom<Element> {
         let document = self.upcast::<Node>().owner_doc_for_layout();
         let shared_lock = document.style_shared_lock();
 
+        // The language of the content, which selects the hyphenation dictionary among other
+        // things, is inherited from the nearest ancestor with a `lang` attribute.
+        let lang = (*self.unsafe_get()).get_attr_val_for_layout(&ns!(xml), &local_name!("lang"))
+            .or_else(|| (*self.unsafe_get()).get_attr_val_for_layout(&ns!(), &local_name!("lang")));
+        if let Some(lang) = lang {
+            hints.push(from_declaration(
+                shared_lock,
+                PropertyDeclaration::XLang(specified::XLang(Atom::from(lang)))
+            ));
+        }
+
         let bgcolor = if let Some(this) = self.downcast::<HTMLBodyElement>() {
             this.get_background_color()
         } else if let Some(this) = self.downcast::<HTMLTableElement>() {
@@ -1531,6 +1545,42 @@ impl Element {
         win.scroll_node(node, x, y, behavior);
     }
 
+    // https://drafts.csswg.org/cssom-view/#scroll-an-element-into-view
+    fn scroll_into_view(&self, options: &ScrollIntoViewOptions) {
+        let doc = document_from_node(self);
+        let win = match doc.GetDefaultView() {
+            None => return,
+            Some(win) => win,
+        };
+
+        // TODO: Scroll the scrolling boxes of the ancestors of this element, not just the
+        // viewport.
+        let element_box = self.upcast::<Node>().bounding_content_box_or_zero();
+        let x = scroll_position_for_alignment(options.inline,
+                                              element_box.origin.x.to_f64_px(),
+                                              element_box.max_x().to_f64_px(),
+                                              win.ScrollX() as f64,
+                                              win.InnerWidth() as f64);
+        let y = scroll_position_for_alignment(options.block,
+                                              element_box.origin.y.to_f64_px(),
+                                              element_box.max_y().to_f64_px(),
+                                              win.ScrollY() as f64,
+                                              win.InnerHeight() as f64);
+
+        // The scroll is associated with this element, so its scroll-behavior applies rather
+        // than the one of the root element.
+        let behavior = match options.parent.behavior {
+            ScrollBehavior::Auto => {
+                let smooth = self.style().map_or(false, |style| {
+                    style.get_box().scroll_behavior == StyleScrollBehavior::Smooth
+                });
+                if smooth { ScrollBehavior::Smooth } else { ScrollBehavior::Instant }
+            },
+            behavior => behavior,
+        };
+        win.scroll(x, y, behavior);
+    }
+
     // https://w3c.github.io/DOM-Parsing/#parsing
     pub fn parse_fragment(&self, markup: DOMString) -> Fallible<DomRoot<DocumentFragment>> {
         // Steps 1-2.
@@ -1891,6 +1941,33 @@ impl ElementMethods for Element {
                      rect.size.height.to_f64_px())
     }
 
+    // https://drafts.csswg.org/cssom-view/#dom-element-scrollintoview
+    fn ScrollIntoView(&self, arg: BooleanOrScrollIntoViewOptions) {
+        // Steps 1-3
+        let options = match arg {
+            BooleanOrScrollIntoViewOptions::ScrollIntoViewOptions(options) => options,
+            BooleanOrScrollIntoViewOptions::Boolean(align_to_top) => ScrollIntoViewOptions {
+                parent: ScrollOptions {
+                    behavior: ScrollBehavior::Auto,
+                },
+                block: if align_to_top {
+                    ScrollLogicalPosition::Start
+                } else {
+                    ScrollLogicalPosition::End
+                },
+                inline: ScrollLogicalPosition::Nearest,
+            },
+        };
+
+        // Step 4
+        if !self.has_css_layout_box() {
+            return;
+        }
+
+        // Step 5
+        self.scroll_into_view(&options);
+    }
+
     // https://drafts.csswg.org/cssom-view/#dom-element-scroll
     fn Scroll(&self, options: &ScrollToOptions) {
         // Step 1
@@ -2427,7 +2504,8 @@ impl VirtualMethods for Element {
     fn attribute_affects_presentational_hints(&self, attr: &Attr) -> bool {
         // FIXME: This should be more fine-grained, not all elements care about these.
         if attr.local_name() == &local_name!("width") ||
-           attr.local_name() == &local_name!("height") {
+           attr.local_name() == &local_name!("height") ||
+           attr.local_name() == &local_name!("lang") {
             return true;
         }
 
@@ -3244,6 +3322,38 @@ impl TaskOnce for ElementPerformFullscreenExit {
     }
 }
 
+/// Returns the scroll position along one axis that aligns the edges of an element, from
+/// `element_start` to `element_end`, with the scrolling box as requested.
+///
+/// <https://drafts.csswg.org/cssom-view/#scroll-an-element-into-view>
+fn scroll_position_for_alignment(alignment: ScrollLogicalPosition,
+                                 element_start: f64,
+                                 element_end: f64,
+                                 scrollport_start: f64,
+                                 scrollport_size: f64)
+                                 -> f64 {
+    let element_size = element_end - element_start;
+    let align_start = element_start;
+    let align_end = element_end - scrollport_size;
+    match alignment {
+        ScrollLogicalPosition::Start => align_start,
+        ScrollLogicalPosition::End => align_end,
+        ScrollLogicalPosition::Center => element_start + (element_size - scrollport_size) / 2.0,
+        ScrollLogicalPosition::Nearest => {
+            let scrollport_end = scrollport_start + scrollport_size;
+            if (element_start <= scrollport_start && element_end >= scrollport_end) ||
+               (element_start >= scrollport_start && element_end <= scrollport_end) {
+                scrollport_start
+            } else if (element_start < scrollport_start && element_size <= scrollport_size) ||
+                      (element_end > scrollport_end && element_size > scrollport_size) {
+                align_start
+            } else {
+                align_end
+            }
+        },
+    }
+}
+
 pub fn reflect_cross_origin_attribute(element: &Element) -> Option<DOMString> {
     let attr = element.get_attribute(&ns!(), &local_name!("crossorigin"));
 
@@ -0,0 +1,348 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cssparser::{Parser, ParserInput};
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::FontFaceBinding;
+use dom::bindings::codegen::Bindings::FontFaceBinding::{FontFaceDescriptors, FontFaceLoadStatus};
+use dom::bindings::codegen::Bindings::FontFaceBinding::FontFaceMethods;
+use dom::bindings::codegen::Bindings::WindowBinding::WindowBinding::WindowMethods;
+use dom::bindings::codegen::UnionTypes::ArrayBufferOrArrayBufferViewOrString;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::globalscope::GlobalScope;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
+use script_layout_interface::message::Msg;
+use servo_atoms::Atom;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+use style::context::QuirksMode;
+use style::font_face::{EffectiveSources, FontDisplay, Source};
+use style::parser::{Parse, ParserContext};
+use style::stylesheets::CssRuleType;
+use style::values::computed::font::{FamilyName, FamilyNameSyntax};
+use style_traits::{ParseError, ParsingMode};
+use task_source::{TaskSource, TaskSourceName};
+
+/// The number of faces that have been created by this process, used to give each of them a
+/// unique key in the font cache.
+static FONT_FACE_COUNT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Where the data of a font face comes from.
+#[derive(Clone, JSTraceable)]
+enum FontFaceSource {
+    /// The `url()` and `local()` sources of a `src` descriptor.
+    Sources(EffectiveSources),
+    /// The font data itself.
+    Data(Vec<u8>),
+}
+
+// https://drafts.csswg.org/css-font-loading/#fontface-interface
+#[dom_struct]
+pub struct FontFace {
+    reflector_: Reflector,
+    family: DOMString,
+    style: DOMString,
+    weight: DOMString,
+    stretch: DOMString,
+    unicode_range: DOMString,
+    variant: DOMString,
+    feature_settings: DOMString,
+    variation_settings: DOMString,
+    display: DOMString,
+    /// The parsed `display` descriptor, which is passed to the font cache with the sources.
+    font_display: FontDisplay,
+    status: Cell<FontFaceLoadStatus>,
+    /// The source to load the font from, which is kept to add the face to the font cache
+    /// again if it is added back to `document.fonts`.
+    #[ignore_malloc_size_of = "Defined in style"]
+    source: Option<FontFaceSource>,
+    /// The key that identifies this face in the font cache.
+    #[ignore_malloc_size_of = "Defined in servo_atoms"]
+    key: Atom,
+    /// Whether this face was added to the font cache and hasn't been removed from it since.
+    registered: Cell<bool>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontface-fontstatuspromise-slot>
+    #[ignore_malloc_size_of = "promises are hard"]
+    loaded: Rc<Promise>,
+}
+
+impl FontFace {
+    fn new_inherited(family: DOMString,
+                     source: Option<FontFaceSource>,
+                     key: Atom,
+                     descriptors: &FontFaceDescriptors,
+                     font_display: FontDisplay,
+                     loaded: Rc<Promise>) -> FontFace {
+        FontFace {
+            reflector_: Reflector::new(),
+            family: family,
+            style: descriptors.style.clone(),
+            weight: descriptors.weight.clone(),
+            stretch: descriptors.stretch.clone(),
+            unicode_range: descriptors.unicodeRange.clone(),
+            variant: descriptors.variant.clone(),
+            feature_settings: descriptors.featureSettings.clone(),
+            variation_settings: descriptors.variationSettings.clone(),
+            display: descriptors.display.clone(),
+            font_display: font_display,
+            status: Cell::new(FontFaceLoadStatus::Unloaded),
+            source: source,
+            key: key,
+            registered: Cell::new(false),
+            loaded: loaded,
+        }
+    }
+
+    fn new(global: &GlobalScope,
+           family: DOMString,
+           source: Option<FontFaceSource>,
+           descriptors: &FontFaceDescriptors,
+           font_display: FontDisplay) -> DomRoot<FontFace> {
+        let loaded = Promise::new(global);
+        let count = FONT_FACE_COUNT.fetch_add(1, Ordering::SeqCst);
+        let key = Atom::from(format!("font-face:{}:{}", global.pipeline_id(), count));
+        let face = FontFace::new_inherited(family, source, key, descriptors, font_display, loaded);
+        reflect_dom_object(Box::new(face),
+                           global,
+                           FontFaceBinding::Wrap)
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-fontface
+    pub fn Constructor(window: &Window,
+                       family: DOMString,
+                       source: ArrayBufferOrArrayBufferViewOrString,
+                       descriptors: &FontFaceDescriptors) -> Fallible<DomRoot<FontFace>> {
+        let font_display = parse_descriptor(window, &descriptors.display, |context, input| {
+            FontDisplay::parse(context, input)
+        });
+        let source = match source {
+            ArrayBufferOrArrayBufferViewOrString::String(ref source) => {
+                parse_descriptor(window, source, |context, input| Vec::<Source>::parse(context, input))
+                    .map(|sources| FontFaceSource::Sources(EffectiveSources::new(&sources)))
+            },
+            ArrayBufferOrArrayBufferViewOrString::ArrayBuffer(ref buffer) => {
+                Some(FontFaceSource::Data(buffer.to_vec()))
+            },
+            ArrayBufferOrArrayBufferViewOrString::ArrayBufferView(ref view) => {
+                Some(FontFaceSource::Data(view.to_vec()))
+            },
+        };
+        let is_data = match source {
+            Some(FontFaceSource::Data(_)) => true,
+            _ => false,
+        };
+        // A face whose descriptors can't be parsed is never loaded.
+        let source = if font_display.is_some() { source } else { None };
+        let face = FontFace::new(window.upcast::<GlobalScope>(),
+                                 family,
+                                 source,
+                                 descriptors,
+                                 font_display.unwrap_or(FontDisplay::Auto));
+
+        if face.source.is_none() {
+            // The source or a descriptor could not be parsed.
+            face.status.set(FontFaceLoadStatus::Error);
+            face.loaded.reject_error(Error::Syntax);
+        } else if is_data {
+            // Binary data is loaded straight away rather than when the face is first used.
+            face.start_load();
+        }
+        Ok(face)
+    }
+
+    /// Returns whether this face is for the given font family, which is matched
+    /// case-insensitively like in the font cache.
+    pub fn is_for_family(&self, family: &Atom) -> bool {
+        self.family.to_lowercase() == family.to_lowercase()
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#font-face-load>
+    ///
+    /// The font cache loads fonts as it adds them, so a face that isn't in `document.fonts`
+    /// is removed from it again once it has loaded.
+    pub fn start_load(&self) {
+        if self.status.get() != FontFaceLoadStatus::Unloaded || self.source.is_none() {
+            return;
+        }
+        self.status.set(FontFaceLoadStatus::Loading);
+
+        let global = self.global();
+        let window = global.as_window();
+        window.Document().Fonts().font_face_load_started(self);
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let face = Trusted::new(self);
+        let task_source = window.networking_task_source();
+        let task_canceller = window.task_canceller(TaskSourceName::Networking);
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let face = face.clone();
+            let loaded = message.to().unwrap();
+            let _ = task_source.queue_with_canceller(
+                task!(font_face_load_finished: move || {
+                    face.root().finish_load(loaded);
+                }),
+                &task_canceller,
+            );
+        }));
+        self.register(sender);
+    }
+
+    fn finish_load(&self, loaded: bool) {
+        if loaded {
+            self.status.set(FontFaceLoadStatus::Loaded);
+            self.loaded.resolve_native(&DomRoot::from_ref(self));
+        } else {
+            self.status.set(FontFaceLoadStatus::Error);
+            self.loaded.reject_error(Error::Network);
+        }
+
+        let global = self.global();
+        let window = global.as_window();
+        let fonts = window.Document().Fonts();
+        if !fonts.contains(self) {
+            self.unregister();
+        }
+        fonts.font_face_load_finished(self);
+    }
+
+    /// Adds this face to the font cache, so that text in its family can be rendered with it.
+    /// The sender receives whether the font could be loaded.
+    fn register(&self, sender: IpcSender<bool>) {
+        let source = match self.source {
+            Some(ref source) => source.clone(),
+            None => return,
+        };
+        self.registered.set(true);
+
+        let family = FamilyName {
+            name: Atom::from(&*self.family),
+            syntax: FamilyNameSyntax::Quoted,
+        };
+        let key = self.key.clone();
+        let msg = match source {
+            FontFaceSource::Sources(sources) => {
+                Msg::AddWebFont(family, sources, self.font_display, key, sender)
+            },
+            FontFaceSource::Data(bytes) => Msg::AddWebFontData(family, bytes, key, sender),
+        };
+        self.global().as_window().layout_chan().send(msg).unwrap();
+    }
+
+    /// Removes this face from the font cache. A face that is still loading is removed once
+    /// it has loaded, if it isn't in `document.fonts` by then.
+    fn unregister(&self) {
+        if !self.registered.get() || self.status.get() == FontFaceLoadStatus::Loading {
+            return;
+        }
+        self.registered.set(false);
+        let msg = Msg::RemoveWebFont(self.key.clone());
+        self.global().as_window().layout_chan().send(msg).unwrap();
+    }
+
+    /// Called when this face is added to `document.fonts`, to add a face that has already
+    /// loaded back to the font cache.
+    pub fn added_to_document(&self) {
+        if self.status.get() == FontFaceLoadStatus::Loaded && !self.registered.get() {
+            // The face has loaded before, so the result of the load isn't needed.
+            let (sender, _) = ipc::channel().unwrap();
+            self.register(sender);
+        }
+    }
+
+    /// Called when this face is removed from `document.fonts`, so that its family stops
+    /// being rendered with it.
+    pub fn removed_from_document(&self) {
+        self.unregister();
+    }
+}
+
+/// Parses the value of a descriptor, such as `src` or `display`, with the given parse function.
+fn parse_descriptor<T, F>(window: &Window, value: &str, parse: F) -> Option<T>
+    where F: for<'i, 't> FnOnce(&ParserContext, &mut Parser<'i, 't>) -> Result<T, ParseError<'i>>
+{
+    let url = window.Document().url();
+    let context = ParserContext::new_for_cssom(
+        &url,
+        Some(CssRuleType::FontFace),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        None,
+    );
+    let mut input = ParserInput::new(value);
+    let mut input = Parser::new(&mut input);
+    input.parse_entirely(|input| parse(&context, input)).ok()
+}
+
+impl FontFaceMethods for FontFace {
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-family
+    fn Family(&self) -> DOMString {
+        self.family.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-style
+    fn Style(&self) -> DOMString {
+        self.style.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-weight
+    fn Weight(&self) -> DOMString {
+        self.weight.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-stretch
+    fn Stretch(&self) -> DOMString {
+        self.stretch.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-unicoderange
+    fn UnicodeRange(&self) -> DOMString {
+        self.unicode_range.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variant
+    fn Variant(&self) -> DOMString {
+        self.variant.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-featuresettings
+    fn FeatureSettings(&self) -> DOMString {
+        self.feature_settings.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variationsettings
+    fn VariationSettings(&self) -> DOMString {
+        self.variation_settings.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-display
+    fn Display(&self) -> DOMString {
+        self.display.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-status
+    fn Status(&self) -> FontFaceLoadStatus {
+        self.status.get()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-load
+    fn Load(&self) -> Rc<Promise> {
+        self.start_load();
+        self.loaded.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-loaded
+    fn Loaded(&self) -> Rc<Promise> {
+        self.loaded.clone()
+    }
+}
@@ -0,0 +1,322 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
+use dom::bindings::codegen::Bindings::FontFaceBinding::FontFaceLoadStatus;
+use dom::bindings::codegen::Bindings::FontFaceBinding::FontFaceMethods;
+use dom::bindings::codegen::Bindings::FontFaceSetBinding;
+use dom::bindings::codegen::Bindings::FontFaceSetBinding::{FontFaceSetLoadStatus, FontFaceSetMethods};
+use dom::bindings::codegen::Bindings::WindowBinding::WindowBinding::WindowMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::event::Event;
+use dom::eventtarget::EventTarget;
+use dom::fontface::FontFace;
+use dom::fontfacesetloadevent::FontFaceSetLoadEvent;
+use dom::globalscope::GlobalScope;
+use dom::node::Node;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+use std::cell::Cell;
+use std::mem;
+use std::rc::Rc;
+use style::values::computed::font::SingleFontFamily;
+use task_source::TaskSource;
+
+/// A promise returned by `FontFaceSet.load()`, which settles once all of its
+/// faces have loaded or one of them has failed to.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct PendingLoad {
+    #[ignore_malloc_size_of = "promises are hard"]
+    promise: Rc<Promise>,
+    faces: Vec<Dom<FontFace>>,
+}
+
+// https://drafts.csswg.org/css-font-loading/#FontFaceSet-interface
+#[dom_struct]
+pub struct FontFaceSet {
+    eventtarget: EventTarget,
+    faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-loadingfonts-slot>
+    loading_faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-loadedfonts-slot>
+    loaded_faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-failedfonts-slot>
+    failed_faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-readypromise-slot>
+    #[ignore_malloc_size_of = "promises are hard"]
+    ready: DomRefCell<Rc<Promise>>,
+    pending_loads: DomRefCell<Vec<PendingLoad>>,
+    status: Cell<FontFaceSetLoadStatus>,
+    /// Whether the document may still be loading fonts for its own text, such as those of
+    /// its `@font-face` rules, which keeps the set loading.
+    document_fonts_pending: Cell<bool>,
+}
+
+impl FontFaceSet {
+    fn new_inherited(ready: Rc<Promise>) -> FontFaceSet {
+        FontFaceSet {
+            eventtarget: EventTarget::new_inherited(),
+            faces: DomRefCell::new(vec![]),
+            loading_faces: DomRefCell::new(vec![]),
+            loaded_faces: DomRefCell::new(vec![]),
+            failed_faces: DomRefCell::new(vec![]),
+            ready: DomRefCell::new(ready),
+            pending_loads: DomRefCell::new(vec![]),
+            status: Cell::new(FontFaceSetLoadStatus::Loading),
+            document_fonts_pending: Cell::new(true),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<FontFaceSet> {
+        let ready = Promise::new(window.upcast::<GlobalScope>());
+        let set = reflect_dom_object(Box::new(FontFaceSet::new_inherited(ready)),
+                                     window,
+                                     FontFaceSetBinding::Wrap);
+        set.document_fonts_updated();
+        set
+    }
+
+    pub fn contains(&self, face: &FontFace) -> bool {
+        self.faces.borrow().iter().any(|f| *f == face)
+    }
+
+    /// Called when the document has loaded and whenever a web font finishes loading, so
+    /// that the set only stops loading once the document has no pending font loads.
+    ///
+    /// <https://drafts.csswg.org/css-font-loading/#fontfaceset-pending-on-the-environment>
+    pub fn document_fonts_updated(&self) {
+        let global = self.global();
+        let window = global.as_window();
+        let pending = window.Document().ReadyState() != DocumentReadyState::Complete ||
+            window.has_pending_web_fonts();
+        self.document_fonts_pending.set(pending);
+        if pending {
+            self.switch_to_loading();
+        } else {
+            self.switch_to_loaded_if_done();
+        }
+    }
+
+    /// Called when a face starts loading, whether or not it is in this set.
+    pub fn font_face_load_started(&self, face: &FontFace) {
+        if !self.contains(face) {
+            return;
+        }
+        self.loading_faces.borrow_mut().push(Dom::from_ref(face));
+        self.switch_to_loading();
+    }
+
+    /// Called when a face finishes loading, whether or not it is in this set.
+    pub fn font_face_load_finished(&self, face: &FontFace) {
+        let was_loading = {
+            let mut loading_faces = self.loading_faces.borrow_mut();
+            let count = loading_faces.len();
+            loading_faces.retain(|f| *f != face);
+            loading_faces.len() != count
+        };
+        if was_loading {
+            if face.Status() == FontFaceLoadStatus::Loaded {
+                self.loaded_faces.borrow_mut().push(Dom::from_ref(face));
+            } else {
+                self.failed_faces.borrow_mut().push(Dom::from_ref(face));
+            }
+        }
+        self.settle_pending_loads();
+        self.switch_to_loaded_if_done();
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#switch-the-fontfaceset-to-loading>
+    fn switch_to_loading(&self) {
+        if self.status.get() == FontFaceSetLoadStatus::Loading {
+            return;
+        }
+        self.status.set(FontFaceSetLoadStatus::Loading);
+        if self.ready.borrow().is_fulfilled() {
+            *self.ready.borrow_mut() = Promise::new(&self.global());
+        }
+
+        let window = self.global();
+        let set = Trusted::new(self);
+        let _ = window.as_window().dom_manipulation_task_source().queue(
+            task!(fire_font_face_set_loading: move || {
+                set.root().fire_load_event("loading", &[]);
+            }),
+            &window,
+        );
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#fire-a-font-load-event>
+    fn fire_load_event(&self, name: &str, faces: &[DomRoot<FontFace>]) {
+        let event = FontFaceSetLoadEvent::new(&self.global(), Atom::from(name), false, false, faces);
+        event.upcast::<Event>().fire(self.upcast());
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#switch-the-fontfaceset-to-loaded>
+    fn switch_to_loaded_if_done(&self) {
+        if self.status.get() != FontFaceSetLoadStatus::Loading ||
+           self.document_fonts_pending.get() ||
+           !self.loading_faces.borrow().is_empty() {
+            return;
+        }
+        self.status.set(FontFaceSetLoadStatus::Loaded);
+        self.ready.borrow().resolve_native(&DomRoot::from_ref(self));
+
+        let loaded_faces = take_faces(&self.loaded_faces);
+        let failed_faces = take_faces(&self.failed_faces);
+        self.fire_load_event("loadingdone", &loaded_faces);
+        if !failed_faces.is_empty() {
+            self.fire_load_event("loadingerror", &failed_faces);
+        }
+    }
+
+    /// Resolves the promises returned by `load()` whose faces have all loaded, and rejects
+    /// those with a face that failed to load.
+    #[allow(unrooted_must_root)]
+    fn settle_pending_loads(&self) {
+        let pending_loads = mem::replace(&mut *self.pending_loads.borrow_mut(), vec![]);
+        for load in pending_loads {
+            let statuses: Vec<_> = load.faces.iter().map(|face| face.Status()).collect();
+            if statuses.iter().any(|status| *status == FontFaceLoadStatus::Error) {
+                load.promise.reject_error(Error::Network);
+            } else if statuses.iter().all(|status| *status == FontFaceLoadStatus::Loaded) {
+                let faces: Vec<_> = load.faces.iter().map(|face| DomRoot::from_ref(&**face)).collect();
+                load.promise.resolve_native(&faces);
+            } else {
+                self.pending_loads.borrow_mut().push(load);
+            }
+        }
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#find-the-matching-font-faces>
+    ///
+    /// Only the families of `font` are matched, since the font cache doesn't
+    /// distinguish faces by any other descriptor. Returns `None` if `font` isn't
+    /// a valid value of the `font` shorthand.
+    fn find_matching_faces(&self, font: DOMString) -> Option<Vec<DomRoot<FontFace>>> {
+        let global = self.global();
+        let window = global.as_window();
+        // Relative values are resolved against the root element, so a document
+        // without one cannot resolve a font.
+        let root = window.Document().GetDocumentElement()?;
+        let font = window.resolved_font_style_query(root.upcast::<Node>(), font.into())?;
+        let faces = self.faces.borrow();
+        Some(faces.iter().filter(|face| {
+            font.font_family.0.iter().any(|family| match *family {
+                SingleFontFamily::FamilyName(ref name) => face.is_for_family(&name.name),
+                SingleFontFamily::Generic(_) => false,
+            })
+        }).map(|face| DomRoot::from_ref(&**face)).collect())
+    }
+}
+
+/// Empties a list of faces, returning the faces it contained.
+fn take_faces(faces: &DomRefCell<Vec<Dom<FontFace>>>) -> Vec<DomRoot<FontFace>> {
+    faces.borrow_mut().drain(..).map(|face| DomRoot::from_ref(&*face)).collect()
+}
+
+impl FontFaceSetMethods for FontFaceSet {
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-add
+    fn Add(&self, font: &FontFace) -> DomRoot<FontFaceSet> {
+        if !self.contains(font) {
+            self.faces.borrow_mut().push(Dom::from_ref(font));
+            font.added_to_document();
+            if font.Status() == FontFaceLoadStatus::Loading {
+                self.loading_faces.borrow_mut().push(Dom::from_ref(font));
+                self.switch_to_loading();
+            }
+        }
+        DomRoot::from_ref(self)
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-delete
+    fn Delete(&self, font: &FontFace) -> bool {
+        if !self.contains(font) {
+            return false;
+        }
+        self.faces.borrow_mut().retain(|f| *f != font);
+        self.loading_faces.borrow_mut().retain(|f| *f != font);
+        self.loaded_faces.borrow_mut().retain(|f| *f != font);
+        self.failed_faces.borrow_mut().retain(|f| *f != font);
+        font.removed_from_document();
+        self.switch_to_loaded_if_done();
+        true
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-clear
+    fn Clear(&self) {
+        for face in take_faces(&self.faces) {
+            face.removed_from_document();
+        }
+        self.loading_faces.borrow_mut().clear();
+        self.loaded_faces.borrow_mut().clear();
+        self.failed_faces.borrow_mut().clear();
+        self.switch_to_loaded_if_done();
+    }
+
+    // https://heycam.github.io/webidl/#es-setlike-has
+    fn Has(&self, font: &FontFace) -> bool {
+        self.contains(font)
+    }
+
+    // https://heycam.github.io/webidl/#es-setlike-size
+    fn Size(&self) -> u32 {
+        self.faces.borrow().len() as u32
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloading
+    event_handler!(loading, GetOnloading, SetOnloading);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloadingdone
+    event_handler!(loadingdone, GetOnloadingdone, SetOnloadingdone);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloadingerror
+    event_handler!(loadingerror, GetOnloadingerror, SetOnloadingerror);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-load
+    #[allow(unrooted_must_root)]
+    fn Load(&self, font: DOMString, _text: DOMString) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let faces = match self.find_matching_faces(font) {
+            Some(faces) => faces,
+            None => {
+                promise.reject_error(Error::Syntax);
+                return promise;
+            },
+        };
+        for face in &faces {
+            face.start_load();
+        }
+        self.pending_loads.borrow_mut().push(PendingLoad {
+            promise: promise.clone(),
+            faces: faces.iter().map(|face| Dom::from_ref(&**face)).collect(),
+        });
+        self.settle_pending_loads();
+        promise
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-check
+    fn Check(&self, font: DOMString, _text: DOMString) -> Fallible<bool> {
+        let faces = self.find_matching_faces(font).ok_or(Error::Syntax)?;
+        Ok(faces.iter().all(|face| face.Status() == FontFaceLoadStatus::Loaded))
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-status
+    fn Status(&self) -> FontFaceSetLoadStatus {
+        self.status.get()
+    }
+}
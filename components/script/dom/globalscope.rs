@@ -2,30 +2,41 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use canvas_traits::canvas::{byte_swap, byte_swap_and_premultiply};
+use canvas_traits::webgl::WebGLChan;
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EventSourceBinding::EventSourceBinding::EventSourceMethods;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use dom::bindings::conversions::root_from_object;
-use dom::bindings::error::{ErrorInfo, report_pending_exception};
+use dom::bindings::error::{Error, ErrorInfo, report_pending_exception};
 use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::TrustedPromise;
 use dom::bindings::reflector::DomObject;
 use dom::bindings::root::{DomRoot, MutNullableDom};
 use dom::bindings::settings_stack::{AutoEntryScript, entry_global, incumbent_global};
 use dom::bindings::str::DOMString;
 use dom::bindings::weakref::DOMTracker;
+use dom::blob::Blob;
 use dom::crypto::Crypto;
 use dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use dom::errorevent::ErrorEvent;
 use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use dom::eventsource::EventSource;
 use dom::eventtarget::EventTarget;
+use dom::htmlcanvaselement::utils as canvas_utils;
+use dom::imagebitmap::{ImageBitmap, ImageBitmapFormatting, crop_and_format};
+use dom::node::window_from_node;
 use dom::performance::Performance;
+use dom::promise::Promise;
 use dom::window::Window;
 use dom::workerglobalscope::WorkerGlobalScope;
 use dom::workletglobalscope::WorkletGlobalScope;
 use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D};
+use image;
 use ipc_channel::ipc::IpcSender;
 use js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
 use js::glue::{IsWrapper, UnwrapObject};
@@ -41,6 +52,8 @@ use libc;
 use microtask::{Microtask, MicrotaskQueue};
 use msg::constellation_msg::PipelineId;
 use net_traits::{CoreResourceThread, ResourceThreads, IpcSend};
+use net_traits::image::base::PixelFormat;
+use net_traits::image_cache::ImageResponse;
 use profile_traits::{mem, time};
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort};
 use script_thread::{MainThreadScriptChan, ScriptThread};
@@ -54,8 +67,9 @@ use std::ffi::CString;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 use task::TaskCanceller;
-use task_source::TaskSourceName;
+use task_source::{TaskSource, TaskSourceName};
 use task_source::file_reading::FileReadingTaskSource;
 use task_source::networking::NetworkingTaskSource;
 use task_source::performance_timeline::PerformanceTimelineTaskSource;
@@ -91,6 +105,12 @@ pub struct GlobalScope {
     /// Timers used by the Console API.
     console_timers: DomRefCell<HashMap<DOMString, u64>>,
 
+    /// The counters backing `console.count`, keyed by label.
+    console_count_map: DomRefCell<HashMap<DOMString, u32>>,
+
+    /// The labels of the currently open `console.group`s, innermost last.
+    console_group_stack: DomRefCell<Vec<DOMString>>,
+
     /// For providing instructions to an optional devtools server.
     #[ignore_malloc_size_of = "channels are hard"]
     devtools_chan: Option<IpcSender<ScriptToDevtoolsControlMsg>>,
@@ -159,6 +179,8 @@ impl GlobalScope {
             pipeline_id,
             devtools_wants_updates: Default::default(),
             console_timers: DomRefCell::new(Default::default()),
+            console_count_map: DomRefCell::new(Default::default()),
+            console_group_stack: DomRefCell::new(vec![]),
             devtools_chan,
             mem_profiler_chan,
             time_profiler_chan,
@@ -281,6 +303,40 @@ impl GlobalScope {
         })
     }
 
+    /// <https://console.spec.whatwg.org/#count>
+    pub fn increment_console_count(&self, label: &DOMString) -> u32 {
+        let mut map = self.console_count_map.borrow_mut();
+        let count = map.entry(label.clone()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// <https://console.spec.whatwg.org/#countreset>
+    pub fn reset_console_count(&self, label: &DOMString) -> Result<(), ()> {
+        match self.console_count_map.borrow_mut().get_mut(label) {
+            Some(count) => {
+                *count = 0;
+                Ok(())
+            },
+            None => Err(()),
+        }
+    }
+
+    /// <https://console.spec.whatwg.org/#group>
+    pub fn push_console_group(&self, label: DOMString) {
+        self.console_group_stack.borrow_mut().push(label);
+    }
+
+    /// <https://console.spec.whatwg.org/#groupend>
+    pub fn pop_console_group(&self) -> Option<DOMString> {
+        self.console_group_stack.borrow_mut().pop()
+    }
+
+    /// The number of currently open console groups.
+    pub fn console_group_depth(&self) -> usize {
+        self.console_group_stack.borrow().len()
+    }
+
     /// Get an `&IpcSender<ScriptToDevtoolsControlMsg>` to send messages
     /// to the devtools thread when available.
     pub fn devtools_chan(&self) -> Option<&IpcSender<ScriptToDevtoolsControlMsg>> {
@@ -665,6 +721,159 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// Channel to the WebGL thread, if WebGL is available to this global.
+    pub fn webgl_chan(&self) -> Option<WebGLChan> {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.webgl_chan();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.webgl_chan();
+        }
+        None
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-createimagebitmap>
+    #[allow(unrooted_must_root)]
+    pub fn create_image_bitmap(
+        &self,
+        image: ImageBitmapSource,
+        crop: Option<Rect<i32>>,
+        options: &ImageBitmapOptions,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(self);
+
+        // Step 1.
+        let crop = match crop {
+            Some(rect) if rect.size.width == 0 || rect.size.height == 0 => {
+                promise.reject_error(Error::Range("The source rectangle must not be empty".to_owned()));
+                return promise;
+            },
+            Some(rect) => {
+                let (x, width) = normalize_span(rect.origin.x, rect.size.width);
+                let (y, height) = normalize_span(rect.origin.y, rect.size.height);
+                Some(Rect::new(Point2D::new(x, y), Size2D::new(width, height)))
+            },
+            None => None,
+        };
+
+        // Step 2.
+        if options.resizeWidth == Some(0) || options.resizeHeight == Some(0) {
+            promise.reject_error(Error::InvalidState);
+            return promise;
+        }
+        let formatting = ImageBitmapFormatting::new(options);
+
+        // Steps 3-6.
+        let source = match image {
+            ImageBitmapSource::HTMLImageElement(ref image) => {
+                // https://html.spec.whatwg.org/multipage/#check-the-usability-of-the-image-argument
+                let url = image.get_url();
+                let window = window_from_node(&**image);
+                let image_data = url.and_then(|url| match canvas_utils::request_image_from_cache(&window, url) {
+                    ImageResponse::Loaded(image, _) => Some(image),
+                    _ => None,
+                }).and_then(|image| match image.format {
+                    PixelFormat::BGRA8 if image.width > 0 && image.height > 0 => {
+                        Some((image.bytes.to_vec(), Size2D::new(image.width, image.height)))
+                    },
+                    _ => None,
+                });
+                image_data.map(|(mut data, size)| {
+                    // Images are not premultiplied in the cache, unlike bitmaps.
+                    byte_swap_and_premultiply(&mut data);
+                    byte_swap(&mut data);
+                    let origin_clean = image.get_origin().map_or(false, |origin| {
+                        origin.same_origin(self.origin())
+                    });
+                    (data, size, origin_clean)
+                })
+            },
+            ImageBitmapSource::HTMLCanvasElement(ref canvas) => {
+                canvas.fetch_all_data().map(|(data, size)| (data, size.to_u32(), canvas.origin_is_clean()))
+            },
+            ImageBitmapSource::ImageBitmap(ref bitmap) => {
+                bitmap.bitmap_data().map(|data| (data, bitmap.size(), bitmap.origin_is_clean()))
+            },
+            ImageBitmapSource::OffscreenCanvas(ref canvas) => {
+                canvas.fetch_all_data().map(|(data, size)| (data, size.to_u32(), canvas.origin_is_clean()))
+            },
+            ImageBitmapSource::ImageData(ref image_data) => {
+                let mut data = image_data.get_data_array();
+                byte_swap_and_premultiply(&mut data);
+                Some((data, image_data.get_size().to_u32(), true))
+            },
+            ImageBitmapSource::Blob(ref blob) => {
+                self.decode_image_bitmap(blob, crop, formatting, &promise);
+                return promise;
+            },
+        };
+
+        // Step 7.
+        match source {
+            Some((data, size, origin_clean)) => {
+                let (data, size) = crop_and_format(data, size, crop, formatting);
+                promise.resolve_native(&ImageBitmap::new(self, size, data, origin_clean));
+            },
+            None => promise.reject_error(Error::InvalidState),
+        }
+        promise
+    }
+
+    /// Decodes the image in a blob off the main thread for `createImageBitmap()`.
+    #[allow(unrooted_must_root)]
+    fn decode_image_bitmap(
+        &self,
+        blob: &Blob,
+        crop: Option<Rect<i32>>,
+        formatting: ImageBitmapFormatting,
+        promise: &Rc<Promise>,
+    ) {
+        let bytes = match blob.get_bytes() {
+            Ok(bytes) => bytes,
+            Err(()) => return promise.reject_error(Error::InvalidState),
+        };
+
+        let task_source = self.file_reading_task_source();
+        let canceller = self.task_canceller(TaskSourceName::FileReading);
+        let trusted_promise = TrustedPromise::new(promise.clone());
+        thread::Builder::new()
+            .name("ImageBitmapDecoder".to_owned())
+            .spawn(move || {
+                let decoded = image::load_from_memory(&bytes).ok()
+                    .map(|image| image.to_rgba())
+                    .filter(|image| image.width() > 0 && image.height() > 0)
+                    .map(|image| {
+                        let size = Size2D::new(image.width(), image.height());
+                        let mut data = image.into_raw();
+                        byte_swap_and_premultiply(&mut data);
+                        crop_and_format(data, size, crop, formatting)
+                    });
+                let _ = task_source.queue_with_canceller(
+                    task!(image_bitmap_decoded: move || {
+                        let promise = trusted_promise.root();
+                        match decoded {
+                            Some((data, size)) => {
+                                let bitmap = ImageBitmap::new(&promise.global(), size, data, true);
+                                promise.resolve_native(&bitmap);
+                            },
+                            None => promise.reject_error(Error::InvalidState),
+                        }
+                    }),
+                    &canceller,
+                );
+            })
+            .expect("Thread spawning failed");
+    }
+}
+
+/// Turns a span with a negative length into the equivalent one with a
+/// positive length, as done for the source rectangle of `createImageBitmap()`.
+fn normalize_span(start: i32, length: i32) -> (i32, i32) {
+    if length < 0 {
+        (start.saturating_add(length), length.checked_abs().unwrap_or(i32::max_value()))
+    } else {
+        (start, length)
+    }
 }
 
 fn timestamp_in_ms(time: Timespec) -> u64 {
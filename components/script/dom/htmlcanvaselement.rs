@@ -3,35 +3,40 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use base64;
-use canvas_traits::canvas::{CanvasMsg, CanvasId, FromScriptMsg};
+use canvas_encoding::{EncodedImageType, encode_image};
+use canvas_traits::canvas::{CanvasMsg, CanvasId, FromScriptMsg, byte_swap_and_premultiply};
 use canvas_traits::webgl::WebGLVersion;
 use dom::attr::Attr;
+use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
 use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding;
-use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::{HTMLCanvasElementMethods, RenderingContext};
+use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::{BlobCallback, HTMLCanvasElementMethods};
+use dom::bindings::codegen::Bindings::HTMLCanvasElementBinding::RenderingContext;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLContextAttributes;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
 use dom::bindings::conversions::ConversionResult;
-use dom::bindings::error::{Error, Fallible};
+use dom::bindings::error::{Error, ErrorResult, Fallible};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::num::Finite;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::DomObject;
-use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, RootedReference};
 use dom::bindings::str::DOMString;
+use dom::blob::{Blob, BlobImpl};
 use dom::canvasrenderingcontext2d::{CanvasRenderingContext2D, LayoutCanvasRenderingContext2DHelpers};
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element, RawLayoutElementHelpers};
 use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
-use dom::node::{Node, window_from_node};
+use dom::node::{Node, NodeDamage, window_from_node};
+use dom::offscreencanvas::{OffscreenCanvas, OffscreenCanvasPlaceholder};
 use dom::virtualmethods::VirtualMethods;
 use dom::webgl2renderingcontext::WebGL2RenderingContext;
 use dom::webglrenderingcontext::{LayoutCanvasWebGLRenderingContextHelpers, WebGLRenderingContext};
 use dom_struct::dom_struct;
 use euclid::Size2D;
 use html5ever::{LocalName, Prefix};
-use image::ColorType;
-use image::png::PNGEncoder;
 use js::error::throw_type_error;
 use js::jsapi::JSContext;
 use js::rust::HandleValue;
@@ -39,8 +44,13 @@ use offscreen_gl_context::GLContextAttributes;
 use profile_traits::ipc;
 use script_layout_interface::{HTMLCanvasData, HTMLCanvasDataSource};
 use servo_config::prefs::PREFS;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::iter::repeat;
+use std::rc::Rc;
+use std::thread;
 use style::attr::{AttrValue, LengthOrPercentageOrAuto};
+use task_source::{TaskSource, TaskSourceName};
 
 const DEFAULT_WIDTH: u32 = 300;
 const DEFAULT_HEIGHT: u32 = 150;
@@ -51,12 +61,19 @@ pub enum CanvasContext {
     Context2d(Dom<CanvasRenderingContext2D>),
     WebGL(Dom<WebGLRenderingContext>),
     WebGL2(Dom<WebGL2RenderingContext>),
+    /// The context receiving the frames committed by the OffscreenCanvas this
+    /// canvas transferred its control to.
+    Placeholder(Dom<CanvasRenderingContext2D>),
 }
 
 #[dom_struct]
 pub struct HTMLCanvasElement {
     htmlelement: HTMLElement,
     context: DomRefCell<Option<CanvasContext>>,
+    /// Callbacks of pending `toBlob` calls, waiting for their image to be encoded.
+    #[ignore_malloc_size_of = "Rc"]
+    blob_callbacks: DomRefCell<HashMap<u32, Rc<BlobCallback>>>,
+    next_blob_callback_id: Cell<u32>,
 }
 
 impl HTMLCanvasElement {
@@ -66,6 +83,8 @@ impl HTMLCanvasElement {
         HTMLCanvasElement {
             htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
             context: DomRefCell::new(None),
+            blob_callbacks: Default::default(),
+            next_blob_callback_id: Cell::new(0),
         }
     }
 
@@ -82,7 +101,8 @@ impl HTMLCanvasElement {
         let size = self.get_size();
         if let Some(ref context) = *self.context.borrow() {
             match *context {
-                CanvasContext::Context2d(ref context) => context.set_bitmap_dimensions(size),
+                CanvasContext::Context2d(ref context) |
+                CanvasContext::Placeholder(ref context) => context.set_bitmap_dimensions(size),
                 CanvasContext::WebGL(ref context) => context.recreate(size),
                 CanvasContext::WebGL2(ref context) => context.recreate(size),
             }
@@ -95,10 +115,61 @@ impl HTMLCanvasElement {
 
     pub fn origin_is_clean(&self) -> bool {
         match *self.context.borrow() {
-            Some(CanvasContext::Context2d(ref context)) => context.origin_is_clean(),
+            Some(CanvasContext::Context2d(ref context)) |
+            Some(CanvasContext::Placeholder(ref context)) => context.origin_is_clean(),
             _ => true,
         }
     }
+
+    /// Returns a snapshot of the canvas bitmap as non-premultiplied RGBA, or
+    /// `None` when there is no image to serialize.
+    ///
+    /// WebGL drawing buffers are never cleared after being presented, so they
+    /// are always read as if `preserveDrawingBuffer` had been set.
+    fn get_image_data(&self) -> Fallible<Option<Vec<u8>>> {
+        if self.Width() == 0 || self.Height() == 0 {
+            return Ok(None);
+        }
+
+        let data = match *self.context.borrow() {
+            Some(CanvasContext::Context2d(ref context)) |
+            Some(CanvasContext::Placeholder(ref context)) => {
+                let image_data = context.GetImageData(Finite::wrap(0f64), Finite::wrap(0f64),
+                                                           Finite::wrap(self.Width() as f64),
+                                                           Finite::wrap(self.Height() as f64))?;
+                Some(image_data.get_data_array())
+            }
+            Some(CanvasContext::WebGL(ref context)) => {
+                context.get_image_data(self.Width(), self.Height())
+            }
+            Some(CanvasContext::WebGL2(ref context)) => {
+                context.base_context().get_image_data(self.Width(), self.Height())
+            }
+            None => {
+                // Each pixel is fully-transparent black.
+                Some(vec![0; (self.Width() * self.Height() * 4) as usize])
+            }
+        };
+        Ok(data)
+    }
+
+    fn resolve_blob_callback(&self, id: u32, encoded: Option<(&'static str, Vec<u8>)>) {
+        let callback = match self.blob_callbacks.borrow_mut().remove(&id) {
+            Some(callback) => callback,
+            None => return,
+        };
+        let blob = encoded.map(|(mime_type, bytes)| {
+            Blob::new(&self.global(), BlobImpl::new_from_bytes(bytes), mime_type.to_owned())
+        });
+        let _ = callback.Call__(blob.r(), ExceptionHandling::Report);
+    }
+}
+
+/// The encoding requested by the `type` and `quality` arguments of
+/// `toDataURL` and `toBlob`.
+fn requested_image_type(mime_type: Option<DOMString>, quality: HandleValue) -> EncodedImageType {
+    let quality = if quality.is_number() { Some(quality.to_number()) } else { None };
+    EncodedImageType::from_mime_type(mime_type.as_ref().map(|mime| &**mime), quality)
 }
 
 pub trait LayoutHTMLCanvasElementHelpers {
@@ -114,7 +185,8 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
         unsafe {
             let canvas = &*self.unsafe_get();
             let source = match canvas.context.borrow_for_layout().as_ref() {
-                Some(&CanvasContext::Context2d(ref context)) => {
+                Some(&CanvasContext::Context2d(ref context)) |
+                Some(&CanvasContext::Placeholder(ref context)) => {
                     HTMLCanvasDataSource::Image(Some(context.to_layout().get_ipc_renderer()))
                 },
                 Some(&CanvasContext::WebGL(ref context)) => {
@@ -163,10 +235,10 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
     fn get_canvas_id_for_layout(&self) -> CanvasId {
         unsafe {
             let canvas = &*self.unsafe_get();
-            if let &Some(CanvasContext::Context2d(ref context)) = canvas.context.borrow_for_layout() {
-                context.to_layout().get_canvas_id()
-            } else {
-                CanvasId(0)
+            match *canvas.context.borrow_for_layout() {
+                Some(CanvasContext::Context2d(ref context)) |
+                Some(CanvasContext::Placeholder(ref context)) => context.to_layout().get_canvas_id(),
+                _ => CanvasId(0),
             }
         }
     }
@@ -174,6 +246,9 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
 
 
 impl HTMLCanvasElement {
+    /// Returns the 2D context backing this canvas, creating it if the canvas
+    /// has no context yet. For a placeholder canvas, this is the context the
+    /// frames of its OffscreenCanvas are committed to.
     pub fn get_or_init_2d_context(&self) -> Option<DomRoot<CanvasRenderingContext2D>> {
         if self.context.borrow().is_none() {
             let window = window_from_node(self);
@@ -183,7 +258,8 @@ impl HTMLCanvasElement {
         }
 
         match *self.context.borrow().as_ref().unwrap() {
-            CanvasContext::Context2d(ref context) => Some(DomRoot::from_ref(&*context)),
+            CanvasContext::Context2d(ref context) |
+            CanvasContext::Placeholder(ref context) => Some(DomRoot::from_ref(&*context)),
             _   => None,
         }
     }
@@ -197,7 +273,8 @@ impl HTMLCanvasElement {
             let window = window_from_node(self);
             let size = self.get_size();
             let attrs = Self::get_gl_attributes(cx, attrs)?;
-            let maybe_ctx = WebGLRenderingContext::new(&window, self, WebGLVersion::WebGL1, size, attrs);
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(DomRoot::from_ref(self));
+            let maybe_ctx = WebGLRenderingContext::new(window.upcast(), &canvas, WebGLVersion::WebGL1, size, attrs);
 
             *self.context.borrow_mut() = maybe_ctx.map( |ctx| CanvasContext::WebGL(Dom::from_ref(&*ctx)));
         }
@@ -221,7 +298,8 @@ impl HTMLCanvasElement {
             let window = window_from_node(self);
             let size = self.get_size();
             let attrs = Self::get_gl_attributes(cx, attrs)?;
-            let maybe_ctx = WebGL2RenderingContext::new(&window, self, size, attrs);
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(DomRoot::from_ref(self));
+            let maybe_ctx = WebGL2RenderingContext::new(window.upcast(), &canvas, size, attrs);
 
             *self.context.borrow_mut() = maybe_ctx.map( |ctx| CanvasContext::WebGL2(Dom::from_ref(&*ctx)));
         }
@@ -243,7 +321,7 @@ impl HTMLCanvasElement {
     }
 
     #[allow(unsafe_code)]
-    fn get_gl_attributes(cx: *mut JSContext, attrs: Option<HandleValue>) -> Option<GLContextAttributes> {
+    pub fn get_gl_attributes(cx: *mut JSContext, attrs: Option<HandleValue>) -> Option<GLContextAttributes> {
         let webgl_attributes = match attrs {
             Some(attrs) => attrs,
             None => return Some(GLContextAttributes::default()),
@@ -261,6 +339,17 @@ impl HTMLCanvasElement {
         }
     }
 
+    /// Repaints a placeholder canvas once its OffscreenCanvas has committed
+    /// a frame, tainting it if that frame was not origin-clean.
+    pub fn placeholder_frame_committed(&self, origin_clean: bool) {
+        if let Some(CanvasContext::Placeholder(ref context)) = *self.context.borrow() {
+            if !origin_clean {
+                context.set_origin_unclean();
+            }
+        }
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
     pub fn is_valid(&self) -> bool {
         self.Height() != 0 && self.Width() != 0
     }
@@ -273,20 +362,23 @@ impl HTMLCanvasElement {
         }
 
         let data = match self.context.borrow().as_ref() {
-            Some(&CanvasContext::Context2d(ref context)) => {
+            Some(&CanvasContext::Context2d(ref context)) |
+            Some(&CanvasContext::Placeholder(ref context)) => {
                 let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
                 let msg = CanvasMsg::FromScript(FromScriptMsg::SendPixels(sender), context.get_canvas_id());
                 context.get_ipc_renderer().send(msg).unwrap();
 
                 receiver.recv().unwrap()?.into()
             },
-            Some(&CanvasContext::WebGL(_)) => {
-                // TODO: add a method in WebGLRenderingContext to get the pixels.
-                return None;
+            Some(&CanvasContext::WebGL(ref context)) => {
+                let mut data = context.get_image_data(size.width as u32, size.height as u32)?;
+                byte_swap_and_premultiply(&mut data);
+                data
             },
-            Some(&CanvasContext::WebGL2(_)) => {
-                // TODO: add a method in WebGL2RenderingContext to get the pixels.
-                return None;
+            Some(&CanvasContext::WebGL2(ref context)) => {
+                let mut data = context.base_context().get_image_data(size.width as u32, size.height as u32)?;
+                byte_swap_and_premultiply(&mut data);
+                data
             },
             None => {
                 repeat(0xffu8).take((size.height as usize) * (size.width as usize) * 4).collect()
@@ -316,8 +408,12 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
                   cx: *mut JSContext,
                   id: DOMString,
                   attributes: Vec<HandleValue>)
-        -> Option<RenderingContext> {
-        match &*id {
+        -> Fallible<Option<RenderingContext>> {
+        if let Some(CanvasContext::Placeholder(_)) = *self.context.borrow() {
+            return Err(Error::InvalidState);
+        }
+
+        let context = match &*id {
             "2d" => {
                 self.get_or_init_2d_context()
                     .map(RenderingContext::CanvasRenderingContext2D)
@@ -331,64 +427,104 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
                     .map(RenderingContext::WebGL2RenderingContext)
             }
             _ => None
+        };
+        Ok(context)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-canvas-transfercontroltooffscreen
+    fn TransferControlToOffscreen(&self) -> Fallible<DomRoot<OffscreenCanvas>> {
+        // Step 1.
+        if self.context.borrow().is_some() {
+            return Err(Error::InvalidState);
         }
+
+        // Steps 2-4.
+        let window = window_from_node(self);
+        let context = CanvasRenderingContext2D::new(window.upcast(), self, self.get_size());
+        let placeholder = OffscreenCanvasPlaceholder {
+            ipc_renderer: context.get_ipc_renderer(),
+            canvas_id: context.get_canvas_id(),
+            canvas: Trusted::new(self),
+            task_source: window.dom_manipulation_task_source(),
+            canceller: window.task_canceller(TaskSourceName::DOMManipulation),
+        };
+        *self.context.borrow_mut() = Some(CanvasContext::Placeholder(Dom::from_ref(&*context)));
+
+        // Step 5.
+        Ok(OffscreenCanvas::new(window.upcast(),
+                                self.Width() as u64,
+                                self.Height() as u64,
+                                Some(placeholder)))
     }
 
     #[allow(unsafe_code)]
     // https://html.spec.whatwg.org/multipage/#dom-canvas-todataurl
     unsafe fn ToDataURL(&self,
                  _context: *mut JSContext,
-                 _mime_type: Option<DOMString>,
-                 _arguments: Vec<HandleValue>) -> Fallible<DOMString> {
+                 mime_type: Option<DOMString>,
+                 quality: HandleValue) -> Fallible<DOMString> {
         // Step 1.
-        if let Some(CanvasContext::Context2d(ref context)) = *self.context.borrow() {
-            if !context.origin_is_clean() {
-                return Err(Error::Security);
-            }
+        if !self.origin_is_clean() {
+            return Err(Error::Security);
         }
 
         // Step 2.
-        if self.Width() == 0 || self.Height() == 0 {
-            return Ok(DOMString::from("data:,"));
-        }
+        let raw_data = match self.get_image_data()? {
+            Some(data) => data,
+            None => return Ok(DOMString::from("data:,")),
+        };
 
         // Step 3.
-        let raw_data = match *self.context.borrow() {
-            Some(CanvasContext::Context2d(ref context)) => {
-                let image_data = context.GetImageData(Finite::wrap(0f64), Finite::wrap(0f64),
-                                                           Finite::wrap(self.Width() as f64),
-                                                           Finite::wrap(self.Height() as f64))?;
-                image_data.get_data_array()
-            }
-            Some(CanvasContext::WebGL(ref context)) => {
-                match context.get_image_data(self.Width(), self.Height()) {
-                    Some(data) => data,
-                    None => return Ok("data:,".into()),
-                }
-            }
-            Some(CanvasContext::WebGL2(ref context)) => {
-                match context.base_context().get_image_data(self.Width(), self.Height()) {
-                    Some(data) => data,
-                    None => return Ok("data:,".into()),
-                }
-            }
-            None => {
-                // Each pixel is fully-transparent black.
-                vec![0; (self.Width() * self.Height() * 4) as usize]
-            }
-        };
+        let image_type = requested_image_type(mime_type, quality);
+        let (image_type, encoded) = encode_image(image_type, &raw_data, self.Width(), self.Height());
 
-        // Only handle image/png for now.
-        let mime_type = "image/png";
+        let encoded = base64::encode(&encoded);
+        Ok(DOMString::from(format!("data:{};base64,{}", image_type.mime_type(), encoded)))
+    }
 
-        let mut encoded = Vec::new();
-        {
-            let encoder: PNGEncoder<&mut Vec<u8>> = PNGEncoder::new(&mut encoded);
-            encoder.encode(&raw_data, self.Width(), self.Height(), ColorType::RGBA(8)).unwrap();
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-canvas-toblob
+    unsafe fn ToBlob(&self,
+              _context: *mut JSContext,
+              callback: Rc<BlobCallback>,
+              mime_type: Option<DOMString>,
+              quality: HandleValue) -> ErrorResult {
+        // Step 1.
+        if !self.origin_is_clean() {
+            return Err(Error::Security);
         }
 
-        let encoded = base64::encode(&encoded);
-        Ok(DOMString::from(format!("data:{};base64,{}", mime_type, encoded)))
+        // Step 2.
+        let raw_data = self.get_image_data()?;
+        let (width, height) = (self.Width(), self.Height());
+        let image_type = requested_image_type(mime_type, quality);
+
+        let id = self.next_blob_callback_id.get();
+        self.next_blob_callback_id.set(id.wrapping_add(1));
+        self.blob_callbacks.borrow_mut().insert(id, callback);
+
+        // Step 3.
+        let window = window_from_node(self);
+        let canceller = window.task_canceller(TaskSourceName::DOMManipulation);
+        let task_source = window.dom_manipulation_task_source();
+        let this = Trusted::new(self);
+        thread::Builder::new()
+            .name("CanvasBlobEncoder".to_owned())
+            .spawn(move || {
+                let encoded = raw_data.map(|data| {
+                    let (image_type, bytes) = encode_image(image_type, &data, width, height);
+                    (image_type.mime_type(), bytes)
+                });
+                let _ = task_source.queue_with_canceller(
+                    task!(canvas_blob_serialized: move || {
+                        this.root().resolve_blob_callback(id, encoded);
+                    }),
+                    &canceller,
+                );
+            })
+            .expect("Thread spawning failed");
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,185 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapMethods, ImageBitmapOptions};
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageOrientation, ResizeQuality};
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use euclid::{Rect, Size2D};
+use image::{FilterType, ImageBuffer, Rgba};
+use image::imageops;
+use std::cmp;
+
+// https://html.spec.whatwg.org/multipage/#imagebitmap
+#[dom_struct]
+pub struct ImageBitmap {
+    reflector_: Reflector,
+    width: u32,
+    height: u32,
+    /// The bitmap, as premultiplied BGRA, or `None` once the bitmap has been
+    /// closed or transferred.
+    bitmap_data: DomRefCell<Option<Vec<u8>>>,
+    origin_clean: bool,
+}
+
+impl ImageBitmap {
+    fn new_inherited(size: Size2D<u32>, bitmap_data: Vec<u8>, origin_clean: bool) -> ImageBitmap {
+        debug_assert_eq!(bitmap_data.len(), (size.width * size.height * 4) as usize);
+        ImageBitmap {
+            reflector_: Reflector::new(),
+            width: size.width,
+            height: size.height,
+            bitmap_data: DomRefCell::new(Some(bitmap_data)),
+            origin_clean: origin_clean,
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        size: Size2D<u32>,
+        bitmap_data: Vec<u8>,
+        origin_clean: bool,
+    ) -> DomRoot<ImageBitmap> {
+        reflect_dom_object(Box::new(ImageBitmap::new_inherited(size, bitmap_data, origin_clean)),
+                           global,
+                           ImageBitmapBinding::Wrap)
+    }
+
+    pub fn size(&self) -> Size2D<u32> {
+        Size2D::new(self.width, self.height)
+    }
+
+    /// A copy of the premultiplied BGRA bitmap, or `None` if it was closed.
+    pub fn bitmap_data(&self) -> Option<Vec<u8>> {
+        self.bitmap_data.borrow().clone()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.bitmap_data.borrow().is_none()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-imagebitmap-origin-clean
+    pub fn origin_is_clean(&self) -> bool {
+        self.origin_clean
+    }
+}
+
+impl ImageBitmapMethods for ImageBitmap {
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-width
+    fn Width(&self) -> u32 {
+        if self.is_closed() { 0 } else { self.width }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-height
+    fn Height(&self) -> u32 {
+        if self.is_closed() { 0 } else { self.height }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-close
+    fn Close(&self) {
+        *self.bitmap_data.borrow_mut() = None;
+    }
+}
+
+/// The formatting options of `createImageBitmap()`, in a form that can be
+/// sent to the thread decoding a blob.
+#[derive(Clone, Copy)]
+pub struct ImageBitmapFormatting {
+    flip_y: bool,
+    resize_width: Option<u32>,
+    resize_height: Option<u32>,
+    filter: FilterType,
+}
+
+impl ImageBitmapFormatting {
+    pub fn new(options: &ImageBitmapOptions) -> ImageBitmapFormatting {
+        ImageBitmapFormatting {
+            flip_y: options.imageOrientation == ImageOrientation::FlipY,
+            resize_width: options.resizeWidth,
+            resize_height: options.resizeHeight,
+            filter: match options.resizeQuality {
+                ResizeQuality::Pixelated => FilterType::Nearest,
+                ResizeQuality::Low => FilterType::Triangle,
+                ResizeQuality::Medium => FilterType::CatmullRom,
+                ResizeQuality::High => FilterType::Lanczos3,
+            },
+        }
+    }
+}
+
+/// Crops a premultiplied BGRA bitmap to the source rectangle, if any, then
+/// resizes and flips it as requested. Parts of the source rectangle outside
+/// of the bitmap are transparent black.
+///
+/// <https://html.spec.whatwg.org/multipage/#cropped-to-the-source-rectangle-with-formatting>
+pub fn crop_and_format(
+    data: Vec<u8>,
+    size: Size2D<u32>,
+    crop: Option<Rect<i32>>,
+    formatting: ImageBitmapFormatting,
+) -> (Vec<u8>, Size2D<u32>) {
+    let (mut data, size) = match crop {
+        Some(rect) => {
+            let cropped_size = Size2D::new(rect.size.width as u32, rect.size.height as u32);
+            (crop_bitmap(&data, size, rect), cropped_size)
+        },
+        None => (data, size),
+    };
+
+    let output_size = match (formatting.resize_width, formatting.resize_height) {
+        (Some(width), Some(height)) => Size2D::new(width, height),
+        (Some(width), None) => {
+            let height = (size.height as f64 * width as f64 / size.width as f64).ceil();
+            Size2D::new(width, height as u32)
+        },
+        (None, Some(height)) => {
+            let width = (size.width as f64 * height as f64 / size.height as f64).ceil();
+            Size2D::new(width as u32, height)
+        },
+        (None, None) => size,
+    };
+
+    if output_size != size || formatting.flip_y {
+        let mut buffer = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(size.width, size.height, data)
+            .expect("Bitmap data does not match its size");
+        if output_size != size {
+            buffer = imageops::resize(&buffer, output_size.width, output_size.height, formatting.filter);
+        }
+        if formatting.flip_y {
+            buffer = imageops::flip_vertical(&buffer);
+        }
+        data = buffer.into_raw();
+    }
+
+    (data, output_size)
+}
+
+fn crop_bitmap(data: &[u8], size: Size2D<u32>, rect: Rect<i32>) -> Vec<u8> {
+    let row_length = rect.size.width as usize * 4;
+    let mut cropped = vec![0; row_length * rect.size.height as usize];
+
+    // The horizontal span of the source rectangle lying within the bitmap.
+    let left = cmp::max(rect.origin.x as i64, 0);
+    let right = cmp::min(rect.origin.x as i64 + rect.size.width as i64, size.width as i64);
+    if left >= right {
+        return cropped;
+    }
+    let span = (right - left) as usize * 4;
+    let dest_offset = (left - rect.origin.x as i64) as usize * 4;
+
+    for row in 0..rect.size.height as i64 {
+        let y = rect.origin.y as i64 + row;
+        if y < 0 || y >= size.height as i64 {
+            continue;
+        }
+        let source_start = (y as usize * size.width as usize + left as usize) * 4;
+        let dest_start = row as usize * row_length + dest_offset;
+        cropped[dest_start..dest_start + span].copy_from_slice(&data[source_start..source_start + span]);
+    }
+    cropped
+}
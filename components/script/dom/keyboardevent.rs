@@ -338,7 +338,7 @@ pub fn key_value(ch: Option<char>, key: Key, mods: KeyModifiers) -> Cow<'static,
     })
 }
 
-fn key_from_string(key_string: &str, location: u32) -> Option<Key> {
+pub fn key_from_string(key_string: &str, location: u32) -> Option<Key> {
     match key_string {
         " " => Some(Key::Space),
         "\"" => Some(Key::Apostrophe),
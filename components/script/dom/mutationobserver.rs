@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use devtools;
 use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::MutationObserverBinding;
@@ -109,7 +110,12 @@ impl MutationObserver {
 
     /// <https://dom.spec.whatwg.org/#queueing-a-mutation-record>
     pub fn queue_a_mutation_record(target: &Node, attr_type: Mutation) {
-        if !target.global().as_window().get_exists_mut_observer() {
+        let global = target.global();
+        let window = global.as_window();
+        if window.need_emit_devtools_mutations() {
+            window.queue_devtools_mutation(target, devtools::summarize_dom_mutation(target, &attr_type));
+        }
+        if !window.get_exists_mut_observer() {
             return;
         }
         // Step 1
@@ -0,0 +1,477 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_encoding::{EncodedImageType, encode_image};
+use canvas_traits::canvas::{Canvas2dMsg, CanvasId, CanvasMsg, FromScriptMsg, byte_swap_and_premultiply};
+use canvas_traits::webgl::WebGLVersion;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding;
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{ImageEncodeOptions, OffscreenCanvasMethods};
+use dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{OffscreenRenderingContext, OffscreenRenderingContextId};
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::refcounted::{Trusted, TrustedPromise};
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::blob::{Blob, BlobImpl};
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::imagebitmap::ImageBitmap;
+use dom::offscreencanvasrenderingcontext2d::OffscreenCanvasRenderingContext2D;
+use dom::promise::Promise;
+use dom::webgl2renderingcontext::WebGL2RenderingContext;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D};
+use ipc_channel::ipc::IpcSender;
+use js::jsapi::JSContext;
+use js::rust::HandleValue;
+use profile_traits::ipc;
+use servo_config::prefs::PREFS;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use task::TaskCanceller;
+use task_source::{TaskSource, TaskSourceName};
+use task_source::dom_manipulation::DOMManipulationTaskSource;
+
+#[must_root]
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub enum OffscreenCanvasContext {
+    Context2d(Dom<OffscreenCanvasRenderingContext2D>),
+    WebGL(Dom<WebGLRenderingContext>),
+    WebGL2(Dom<WebGL2RenderingContext>),
+}
+
+/// What an OffscreenCanvas needs to present its frames in the
+/// [placeholder canvas element](https://html.spec.whatwg.org/multipage/#offscreencanvas-placeholder)
+/// it was created for. This is sent along when the OffscreenCanvas is
+/// transferred to a worker, so it only holds thread-safe handles.
+pub struct OffscreenCanvasPlaceholder {
+    pub ipc_renderer: IpcSender<CanvasMsg>,
+    pub canvas_id: CanvasId,
+    pub canvas: Trusted<HTMLCanvasElement>,
+    pub task_source: DOMManipulationTaskSource,
+    pub canceller: TaskCanceller,
+}
+
+impl OffscreenCanvasPlaceholder {
+    /// Lets the placeholder canvas element know that a new frame is in its
+    /// bitmap, so that it gets repainted.
+    fn frame_committed(&self, origin_clean: bool) {
+        let canvas = self.canvas.clone();
+        let _ = self.task_source.queue_with_canceller(
+            task!(offscreen_canvas_committed: move || {
+                canvas.root().placeholder_frame_committed(origin_clean);
+            }),
+            &self.canceller,
+        );
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/#the-offscreencanvas-interface
+#[dom_struct]
+pub struct OffscreenCanvas {
+    eventtarget: EventTarget,
+    width: Cell<u64>,
+    height: Cell<u64>,
+    context: DomRefCell<Option<OffscreenCanvasContext>>,
+    #[ignore_malloc_size_of = "Defined in ipc-channel"]
+    placeholder: DomRefCell<Option<OffscreenCanvasPlaceholder>>,
+    /// <https://html.spec.whatwg.org/multipage/#concept-offscreencanvas-detached>
+    detached: Cell<bool>,
+    /// Whether a task has been queued to commit the frame of the WebGL context
+    /// to the placeholder canvas element.
+    webgl_commit_pending: Cell<bool>,
+}
+
+impl OffscreenCanvas {
+    fn new_inherited(width: u64, height: u64, placeholder: Option<OffscreenCanvasPlaceholder>) -> OffscreenCanvas {
+        OffscreenCanvas {
+            eventtarget: EventTarget::new_inherited(),
+            width: Cell::new(width),
+            height: Cell::new(height),
+            context: DomRefCell::new(None),
+            placeholder: DomRefCell::new(placeholder),
+            detached: Cell::new(false),
+            webgl_commit_pending: Cell::new(false),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        width: u64,
+        height: u64,
+        placeholder: Option<OffscreenCanvasPlaceholder>,
+    ) -> DomRoot<OffscreenCanvas> {
+        reflect_dom_object(Box::new(OffscreenCanvas::new_inherited(width, height, placeholder)),
+                           global,
+                           OffscreenCanvasBinding::Wrap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas
+    pub fn Constructor(global: &GlobalScope, width: u64, height: u64) -> Fallible<DomRoot<OffscreenCanvas>> {
+        Ok(OffscreenCanvas::new(global, width, height, None))
+    }
+
+    pub fn get_size(&self) -> Size2D<i32> {
+        Size2D::new(self.width.get() as i32, self.height.get() as i32)
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.get()
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::Context2d(ref context)) => context.origin_is_clean(),
+            _ => true,
+        }
+    }
+
+    /// Detaches this OffscreenCanvas so that it can be recreated in another
+    /// global, returning its dimensions and placeholder. Only canvases
+    /// without a rendering context can be transferred.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+    pub fn transfer(&self) -> Option<(u64, u64, Option<OffscreenCanvasPlaceholder>)> {
+        if self.detached.get() || self.context.borrow().is_some() {
+            return None;
+        }
+        self.detached.set(true);
+        let placeholder = self.placeholder.borrow_mut().take();
+        Some((self.width.get(), self.height.get(), placeholder))
+    }
+
+    /// Returns a copy of the bitmap as premultiplied BGRA, the layout used by
+    /// canvas backends, or `None` if there is no image.
+    pub fn fetch_all_data(&self) -> Option<(Vec<u8>, Size2D<i32>)> {
+        let size = self.get_size();
+
+        if self.detached.get() || size.width == 0 || size.height == 0 {
+            return None;
+        }
+
+        let data = match *self.context.borrow() {
+            Some(OffscreenCanvasContext::Context2d(ref context)) => {
+                let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
+                let msg = CanvasMsg::FromScript(FromScriptMsg::SendPixels(sender), context.get_canvas_id());
+                context.get_ipc_renderer().send(msg).unwrap();
+
+                receiver.recv().unwrap()?.into()
+            },
+            Some(OffscreenCanvasContext::WebGL(ref context)) => {
+                let mut data = context.get_image_data(size.width as u32, size.height as u32)?;
+                byte_swap_and_premultiply(&mut data);
+                data
+            },
+            Some(OffscreenCanvasContext::WebGL2(ref context)) => {
+                let mut data = context.base_context().get_image_data(size.width as u32, size.height as u32)?;
+                byte_swap_and_premultiply(&mut data);
+                data
+            },
+            None => vec![0; (size.width * size.height * 4) as usize],
+        };
+
+        Some((data, size))
+    }
+
+    /// Returns a copy of the bitmap as non-premultiplied RGBA, or `None` if
+    /// there is no image to serialize.
+    fn get_image_data(&self) -> Option<Vec<u8>> {
+        let size = self.get_size();
+        if size.width == 0 || size.height == 0 {
+            return None;
+        }
+
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::Context2d(ref context)) => context.get_image_data(size),
+            Some(OffscreenCanvasContext::WebGL(ref context)) => {
+                context.get_image_data(size.width as u32, size.height as u32)
+            },
+            Some(OffscreenCanvasContext::WebGL2(ref context)) => {
+                context.base_context().get_image_data(size.width as u32, size.height as u32)
+            },
+            None => Some(vec![0; (size.width * size.height * 4) as usize]),
+        }
+    }
+
+    /// Presents the bitmap of the 2D context in the placeholder canvas element,
+    /// if there is one.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#offscreencontext2d-commit>
+    pub fn commit_to_placeholder(&self, context: &OffscreenCanvasRenderingContext2D) {
+        let placeholder = self.placeholder.borrow();
+        let placeholder = match *placeholder {
+            Some(ref placeholder) => placeholder,
+            None => return,
+        };
+
+        let size = self.get_size();
+        let image_size = Size2D::new(size.width as f64, size.height as f64);
+        let rect = Rect::new(Point2D::zero(), image_size);
+
+        // The placeholder's bitmap takes the dimensions of the committed frame.
+        placeholder.ipc_renderer
+            .send(CanvasMsg::Recreate(size, placeholder.canvas_id.clone()))
+            .unwrap();
+        let msg = CanvasMsg::Canvas2d(
+            Canvas2dMsg::DrawImageInOther(placeholder.canvas_id.clone(), image_size, rect, rect, None),
+            context.get_canvas_id(),
+        );
+        context.get_ipc_renderer().send(msg).unwrap();
+
+        placeholder.frame_committed(context.origin_is_clean());
+    }
+
+    /// Schedules the frame of the WebGL context to be presented in the
+    /// placeholder canvas element, if there is one. WebGL has no `commit()`,
+    /// so the frame is committed once the current task is done, which batches
+    /// all the drawing of a task into a single frame.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#offscreencanvas-placeholder>
+    pub fn queue_webgl_commit(&self) {
+        if self.placeholder.borrow().is_none() || self.webgl_commit_pending.get() {
+            return;
+        }
+        self.webgl_commit_pending.set(true);
+
+        let global = self.global();
+        let this = Trusted::new(self);
+        let _ = global.remote_event_task_source().queue(
+            task!(offscreen_canvas_commit_webgl: move || {
+                let this = this.root();
+                this.webgl_commit_pending.set(false);
+                this.commit_webgl_to_placeholder();
+            }),
+            &global,
+        );
+    }
+
+    fn commit_webgl_to_placeholder(&self) {
+        let placeholder = self.placeholder.borrow();
+        let placeholder = match *placeholder {
+            Some(ref placeholder) => placeholder,
+            None => return,
+        };
+
+        let (data, size) = match self.fetch_all_data() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let image_size = Size2D::new(size.width as f64, size.height as f64);
+        let rect = Rect::new(Point2D::zero(), image_size);
+
+        // The pixels are read back in this thread, unlike for 2D contexts, since
+        // the WebGL bitmap does not live in the canvas paint thread.
+        placeholder.ipc_renderer
+            .send(CanvasMsg::Recreate(size, placeholder.canvas_id.clone()))
+            .unwrap();
+        let msg = CanvasMsg::Canvas2d(
+            Canvas2dMsg::DrawImage(data.into(), image_size, rect, rect, None),
+            placeholder.canvas_id.clone(),
+        );
+        placeholder.ipc_renderer.send(msg).unwrap();
+
+        // WebGL contexts can't draw cross-origin images.
+        placeholder.frame_committed(true);
+    }
+
+    fn recreate_contexts(&self) {
+        let size = self.get_size();
+        if let Some(ref context) = *self.context.borrow() {
+            match *context {
+                OffscreenCanvasContext::Context2d(ref context) => context.set_bitmap_dimensions(size),
+                OffscreenCanvasContext::WebGL(ref context) => context.recreate(size),
+                OffscreenCanvasContext::WebGL2(ref context) => context.recreate(size),
+            }
+        }
+    }
+
+    fn get_or_init_2d_context(&self) -> Option<DomRoot<OffscreenCanvasRenderingContext2D>> {
+        if self.context.borrow().is_none() {
+            let context = OffscreenCanvasRenderingContext2D::new(&self.global(), self, self.get_size());
+            *self.context.borrow_mut() = Some(OffscreenCanvasContext::Context2d(Dom::from_ref(&*context)));
+        }
+
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::Context2d(ref context)) => Some(DomRoot::from_ref(&*context)),
+            _ => None,
+        }
+    }
+
+    fn get_or_init_webgl_context(
+        &self,
+        cx: *mut JSContext,
+        options: HandleValue,
+    ) -> Option<DomRoot<WebGLRenderingContext>> {
+        if self.context.borrow().is_none() {
+            let attrs = HTMLCanvasElement::get_gl_attributes(cx, Some(options))?;
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(DomRoot::from_ref(self));
+            let maybe_ctx = WebGLRenderingContext::new(
+                &self.global(), &canvas, WebGLVersion::WebGL1, self.get_size(), attrs);
+
+            *self.context.borrow_mut() = maybe_ctx.map(|ctx| OffscreenCanvasContext::WebGL(Dom::from_ref(&*ctx)));
+        }
+
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::WebGL(ref context)) => Some(DomRoot::from_ref(&*context)),
+            _ => None,
+        }
+    }
+
+    fn get_or_init_webgl2_context(
+        &self,
+        cx: *mut JSContext,
+        options: HandleValue,
+    ) -> Option<DomRoot<WebGL2RenderingContext>> {
+        if !PREFS.is_webgl2_enabled() {
+            return None
+        }
+        if self.context.borrow().is_none() {
+            let attrs = HTMLCanvasElement::get_gl_attributes(cx, Some(options))?;
+            let canvas = HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(DomRoot::from_ref(self));
+            let maybe_ctx = WebGL2RenderingContext::new(&self.global(), &canvas, self.get_size(), attrs);
+
+            *self.context.borrow_mut() = maybe_ctx.map(|ctx| OffscreenCanvasContext::WebGL2(Dom::from_ref(&*ctx)));
+        }
+
+        match *self.context.borrow() {
+            Some(OffscreenCanvasContext::WebGL2(ref context)) => Some(DomRoot::from_ref(&*context)),
+            _ => None,
+        }
+    }
+}
+
+impl OffscreenCanvasMethods for OffscreenCanvas {
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
+    fn Width(&self) -> u64 {
+        self.width.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
+    fn SetWidth(&self, value: u64) {
+        self.width.set(value);
+        self.recreate_contexts();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-height
+    fn Height(&self) -> u64 {
+        self.height.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-height
+    fn SetHeight(&self, value: u64) {
+        self.height.set(value);
+        self.recreate_contexts();
+    }
+
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-getcontext
+    unsafe fn GetContext(
+        &self,
+        cx: *mut JSContext,
+        id: OffscreenRenderingContextId,
+        options: HandleValue,
+    ) -> Fallible<Option<OffscreenRenderingContext>> {
+        // Step 1.
+        if self.detached.get() {
+            return Err(Error::InvalidState);
+        }
+
+        let context = match id {
+            OffscreenRenderingContextId::_2d => {
+                self.get_or_init_2d_context()
+                    .map(OffscreenRenderingContext::OffscreenCanvasRenderingContext2D)
+            }
+            OffscreenRenderingContextId::Webgl => {
+                self.get_or_init_webgl_context(cx, options)
+                    .map(OffscreenRenderingContext::WebGLRenderingContext)
+            }
+            OffscreenRenderingContextId::Webgl2 => {
+                self.get_or_init_webgl2_context(cx, options)
+                    .map(OffscreenRenderingContext::WebGL2RenderingContext)
+            }
+        };
+        Ok(context)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-transfertoimagebitmap
+    fn TransferToImageBitmap(&self) -> Fallible<DomRoot<ImageBitmap>> {
+        // Steps 1-2.
+        if self.detached.get() || self.context.borrow().is_none() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        let (data, size) = self.fetch_all_data().ok_or(Error::InvalidState)?;
+        let bitmap = ImageBitmap::new(&self.global(),
+                                      Size2D::new(size.width as u32, size.height as u32),
+                                      data,
+                                      self.origin_is_clean());
+
+        // Step 4.
+        if let Some(OffscreenCanvasContext::Context2d(ref context)) = *self.context.borrow() {
+            context.clear_bitmap();
+        }
+
+        // Step 5.
+        Ok(bitmap)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-converttoblob
+    fn ConvertToBlob(&self, options: &ImageEncodeOptions) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+
+        // Step 1.
+        if self.detached.get() {
+            promise.reject_error(Error::InvalidState);
+            return promise;
+        }
+
+        // Step 2.
+        if !self.origin_is_clean() {
+            promise.reject_error(Error::Security);
+            return promise;
+        }
+
+        // Step 3.
+        let raw_data = match self.get_image_data() {
+            Some(data) => data,
+            None => {
+                promise.reject_error(Error::IndexSize);
+                return promise;
+            },
+        };
+
+        // Steps 4-6.
+        let image_type = EncodedImageType::from_mime_type(Some(&*options.type_), Some(options.quality));
+        let (width, height) = (self.width.get() as u32, self.height.get() as u32);
+        let task_source = global.file_reading_task_source();
+        let canceller = global.task_canceller(TaskSourceName::FileReading);
+        let trusted_promise = TrustedPromise::new(promise.clone());
+        thread::Builder::new()
+            .name("OffscreenCanvasBlobEncoder".to_owned())
+            .spawn(move || {
+                let (image_type, bytes) = encode_image(image_type, &raw_data, width, height);
+                let mime_type = image_type.mime_type();
+                let _ = task_source.queue_with_canceller(
+                    task!(offscreen_canvas_blob_serialized: move || {
+                        let promise = trusted_promise.root();
+                        let blob = Blob::new(&promise.global(),
+                                             BlobImpl::new_from_bytes(bytes),
+                                             mime_type.to_owned());
+                        promise.resolve_native(&blob);
+                    }),
+                    &canceller,
+                );
+            })
+            .expect("Thread spawning failed");
+
+        promise
+    }
+}
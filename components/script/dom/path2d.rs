@@ -0,0 +1,482 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::canvas::PathSegment;
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::DOMMatrixBinding::DOMMatrix2DInit;
+use dom::bindings::codegen::Bindings::Path2DBinding;
+use dom::bindings::codegen::Bindings::Path2DBinding::Path2DMethods;
+use dom::bindings::codegen::UnionTypes::Path2DOrString;
+use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::dommatrixreadonly::dommatrix2dinit_to_matrix;
+use dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use euclid::{Point2D, Transform2D, Vector2D};
+
+// https://html.spec.whatwg.org/multipage/#path2d-objects
+#[dom_struct]
+pub struct Path2D {
+    reflector_: Reflector,
+    segments: DomRefCell<Vec<PathSegment>>,
+}
+
+impl Path2D {
+    fn new_inherited(segments: Vec<PathSegment>) -> Path2D {
+        Path2D {
+            reflector_: Reflector::new(),
+            segments: DomRefCell::new(segments),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, segments: Vec<PathSegment>) -> DomRoot<Path2D> {
+        reflect_dom_object(Box::new(Path2D::new_inherited(segments)),
+                           global,
+                           Path2DBinding::Wrap)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d
+    pub fn Constructor(global: &GlobalScope, path: Option<Path2DOrString>) -> DomRoot<Path2D> {
+        let segments = match path {
+            Some(Path2DOrString::Path2D(path)) => path.segments(),
+            Some(Path2DOrString::String(data)) => parse_path_data(&data),
+            None => vec![],
+        };
+        Path2D::new(global, segments)
+    }
+
+    /// The segments of this path, to be sent to the canvas paint thread.
+    pub fn segments(&self) -> Vec<PathSegment> {
+        self.segments.borrow().clone()
+    }
+
+    fn push(&self, segment: PathSegment) {
+        self.segments.borrow_mut().push(segment);
+    }
+}
+
+impl Path2DMethods for Path2D {
+    // https://html.spec.whatwg.org/multipage/#dom-path2d-addpath
+    fn AddPath(&self, path: &Path2D, transform: &DOMMatrix2DInit) -> ErrorResult {
+        // Step 2.
+        let matrix = dommatrix2dinit_to_matrix(transform)?;
+        let transform = Transform2D::row_major(matrix.m11 as f32, matrix.m12 as f32,
+                                               matrix.m21 as f32, matrix.m22 as f32,
+                                               matrix.m41 as f32, matrix.m42 as f32);
+        if !transform.to_row_major_array().iter().all(|value| value.is_finite()) {
+            return Ok(());
+        }
+
+        // Steps 3-4. The segments of `path` are collected first, as it may be
+        // this path itself.
+        let added = path.segments();
+        let mut segments = self.segments.borrow_mut();
+        segments.push(PathSegment::Transform(transform));
+        segments.extend(added.into_iter().map(|segment| match segment {
+            PathSegment::Transform(inner) => PathSegment::Transform(inner.post_mul(&transform)),
+            segment => segment,
+        }));
+        segments.push(PathSegment::Transform(Transform2D::identity()));
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-closepath
+    fn ClosePath(&self) {
+        self.push(PathSegment::ClosePath);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-moveto
+    fn MoveTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::MoveTo(Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-lineto
+    fn LineTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::LineTo(Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-quadraticcurveto
+    fn QuadraticCurveTo(&self, cpx: f64, cpy: f64, x: f64, y: f64) {
+        if !(cpx.is_finite() && cpy.is_finite() && x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::QuadraticCurveTo(Point2D::new(cpx as f32, cpy as f32),
+                                                Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-beziercurveto
+    fn BezierCurveTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        if !(cp1x.is_finite() && cp1y.is_finite() && cp2x.is_finite() && cp2y.is_finite() &&
+             x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::BezierCurveTo(Point2D::new(cp1x as f32, cp1y as f32),
+                                             Point2D::new(cp2x as f32, cp2y as f32),
+                                             Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+    fn ArcTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, r: f64) -> ErrorResult {
+        if !([cp1x, cp1y, cp2x, cp2y, r].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+        self.push(PathSegment::ArcTo(Point2D::new(cp1x as f32, cp1y as f32),
+                                     Point2D::new(cp2x as f32, cp2y as f32),
+                                     r as f32));
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-rect
+    fn Rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        if ![x, y, width, height].iter().all(|val| val.is_finite()) {
+            return;
+        }
+        let (x, y, width, height) = (x as f32, y as f32, width as f32, height as f32);
+        let mut segments = self.segments.borrow_mut();
+        segments.push(PathSegment::MoveTo(Point2D::new(x, y)));
+        segments.push(PathSegment::LineTo(Point2D::new(x + width, y)));
+        segments.push(PathSegment::LineTo(Point2D::new(x + width, y + height)));
+        segments.push(PathSegment::LineTo(Point2D::new(x, y + height)));
+        segments.push(PathSegment::ClosePath);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arc
+    fn Arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        if !([x, y, r, start, end].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+        self.push(PathSegment::Ellipse(Point2D::new(x as f32, y as f32),
+                                       r as f32,
+                                       r as f32,
+                                       0.,
+                                       start as f32,
+                                       end as f32,
+                                       ccw));
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
+    fn Ellipse(&self, x: f64, y: f64, rx: f64, ry: f64, rotation: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        if !([x, y, rx, ry, rotation, start, end].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if rx < 0.0 || ry < 0.0 {
+            return Err(Error::IndexSize);
+        }
+        self.push(PathSegment::Ellipse(Point2D::new(x as f32, y as f32),
+                                       rx as f32,
+                                       ry as f32,
+                                       rotation as f32,
+                                       start as f32,
+                                       end as f32,
+                                       ccw));
+        Ok(())
+    }
+}
+
+/// Parses SVG path data into path segments. As required by SVG, everything
+/// up to the first error is kept.
+/// <https://www.w3.org/TR/SVG/paths.html#PathDataBNF>
+fn parse_path_data(data: &str) -> Vec<PathSegment> {
+    let mut parser = PathDataParser {
+        data: data.as_bytes(),
+        position: 0,
+        segments: vec![],
+        current_point: Point2D::zero(),
+        subpath_start: Point2D::zero(),
+        last_cubic_control_point: None,
+        last_quadratic_control_point: None,
+    };
+
+    let mut command = None;
+    loop {
+        parser.skip_whitespace();
+        if parser.position == parser.data.len() {
+            break;
+        }
+
+        let next = match parser.data[parser.position] {
+            byte if is_path_command(byte) => {
+                parser.position += 1;
+                byte
+            },
+            // Coordinates following a command repeat it, with subsequent
+            // pairs after a moveto being treated as lineto.
+            _ => match command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(b'Z') | Some(b'z') | None => break,
+                Some(command) => command,
+            },
+        };
+        // The path data must start with a moveto.
+        if command.is_none() && next != b'M' && next != b'm' {
+            break;
+        }
+        command = Some(next);
+
+        if parser.parse_command(next).is_none() {
+            break;
+        }
+    }
+    parser.segments
+}
+
+fn is_path_command(byte: u8) -> bool {
+    b"MmZzLlHhVvCcSsQqTtAa".contains(&byte)
+}
+
+struct PathDataParser<'a> {
+    data: &'a [u8],
+    position: usize,
+    segments: Vec<PathSegment>,
+    current_point: Point2D<f32>,
+    subpath_start: Point2D<f32>,
+    /// The second control point of the last curve, if it was a cubic one.
+    last_cubic_control_point: Option<Point2D<f32>>,
+    /// The control point of the last curve, if it was a quadratic one.
+    last_quadratic_control_point: Option<Point2D<f32>>,
+}
+
+impl<'a> PathDataParser<'a> {
+    fn parse_command(&mut self, command: u8) -> Option<()> {
+        let origin = if command.is_ascii_lowercase() {
+            self.current_point.to_vector()
+        } else {
+            Vector2D::zero()
+        };
+        let mut cubic_control_point = None;
+        let mut quadratic_control_point = None;
+
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let point = self.parse_point()? + origin;
+                self.segments.push(PathSegment::MoveTo(point));
+                self.subpath_start = point;
+                self.current_point = point;
+            },
+            b'Z' => {
+                self.segments.push(PathSegment::ClosePath);
+                self.current_point = self.subpath_start;
+            },
+            b'L' => {
+                let point = self.parse_point()? + origin;
+                self.line_to(point);
+            },
+            b'H' => {
+                let x = self.parse_number()? + origin.x;
+                let point = Point2D::new(x, self.current_point.y);
+                self.line_to(point);
+            },
+            b'V' => {
+                let y = self.parse_number()? + origin.y;
+                let point = Point2D::new(self.current_point.x, y);
+                self.line_to(point);
+            },
+            b'C' | b'S' => {
+                let cp1 = if command.to_ascii_uppercase() == b'C' {
+                    self.parse_point()? + origin
+                } else {
+                    self.reflected(self.last_cubic_control_point)
+                };
+                let cp2 = self.parse_point()? + origin;
+                let point = self.parse_point()? + origin;
+                self.segments.push(PathSegment::BezierCurveTo(cp1, cp2, point));
+                self.current_point = point;
+                cubic_control_point = Some(cp2);
+            },
+            b'Q' | b'T' => {
+                let cp = if command.to_ascii_uppercase() == b'Q' {
+                    self.parse_point()? + origin
+                } else {
+                    self.reflected(self.last_quadratic_control_point)
+                };
+                let point = self.parse_point()? + origin;
+                self.segments.push(PathSegment::QuadraticCurveTo(cp, point));
+                self.current_point = point;
+                quadratic_control_point = Some(cp);
+            },
+            b'A' => {
+                let radius_x = self.parse_number()?;
+                let radius_y = self.parse_number()?;
+                let rotation = self.parse_number()?.to_radians();
+                let large_arc = self.parse_flag()?;
+                let sweep = self.parse_flag()?;
+                let point = self.parse_point()? + origin;
+                self.arc_to(radius_x, radius_y, rotation, large_arc, sweep, point);
+            },
+            _ => return None,
+        }
+
+        self.last_cubic_control_point = cubic_control_point;
+        self.last_quadratic_control_point = quadratic_control_point;
+        Some(())
+    }
+
+    fn line_to(&mut self, point: Point2D<f32>) {
+        self.segments.push(PathSegment::LineTo(point));
+        self.current_point = point;
+    }
+
+    /// The reflection of the previous control point about the current point,
+    /// for the smooth curve commands.
+    fn reflected(&self, control_point: Option<Point2D<f32>>) -> Point2D<f32> {
+        match control_point {
+            Some(control_point) => self.current_point + (self.current_point - control_point),
+            None => self.current_point,
+        }
+    }
+
+    /// Appends an elliptical arc given in SVG's endpoint parameterization.
+    /// <https://www.w3.org/TR/SVG/implnote.html#ArcImplementationNotes>
+    fn arc_to(
+        &mut self,
+        radius_x: f32,
+        radius_y: f32,
+        rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        point: Point2D<f32>,
+    ) {
+        let start = self.current_point;
+        if start == point {
+            return;
+        }
+        let (mut radius_x, mut radius_y) = (radius_x.abs(), radius_y.abs());
+        if radius_x == 0. || radius_y == 0. {
+            return self.line_to(point);
+        }
+
+        // Step 1: compute the midpoint in the rotated coordinate system.
+        let (sin, cos) = rotation.sin_cos();
+        let half = (start - point) / 2.;
+        let x1 = cos * half.x + sin * half.y;
+        let y1 = -sin * half.x + cos * half.y;
+
+        // Correct out-of-range radii.
+        let lambda = (x1 * x1) / (radius_x * radius_x) + (y1 * y1) / (radius_y * radius_y);
+        if lambda > 1. {
+            radius_x *= lambda.sqrt();
+            radius_y *= lambda.sqrt();
+        }
+
+        // Step 2: compute the center in the rotated coordinate system.
+        let (rx2, ry2) = (radius_x * radius_x, radius_y * radius_y);
+        let numerator = rx2 * ry2 - rx2 * y1 * y1 - ry2 * x1 * x1;
+        let denominator = rx2 * y1 * y1 + ry2 * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * radius_x * y1 / radius_y;
+        let cy1 = -coefficient * radius_y * x1 / radius_x;
+
+        // Step 3: compute the center.
+        let middle = start.lerp(point, 0.5);
+        let center = Point2D::new(cos * cx1 - sin * cy1 + middle.x,
+                                  sin * cx1 + cos * cy1 + middle.y);
+
+        // Step 4: compute the angles, in the unrotated ellipse space.
+        let start_angle = ((y1 - cy1) / radius_y).atan2((x1 - cx1) / radius_x);
+        let end_angle = ((-y1 - cy1) / radius_y).atan2((-x1 - cx1) / radius_x);
+
+        self.segments.push(PathSegment::Ellipse(center, radius_x, radius_y, rotation,
+                                                start_angle, end_angle, !sweep));
+        self.current_point = point;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.data.len() && self.data[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comma_whitespace(&mut self) {
+        self.skip_whitespace();
+        if self.position < self.data.len() && self.data[self.position] == b',' {
+            self.position += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn parse_point(&mut self) -> Option<Point2D<f32>> {
+        let x = self.parse_number()?;
+        let y = self.parse_number()?;
+        Some(Point2D::new(x, y))
+    }
+
+    fn parse_flag(&mut self) -> Option<bool> {
+        self.skip_whitespace();
+        let flag = match self.data.get(self.position) {
+            Some(&b'0') => false,
+            Some(&b'1') => true,
+            _ => return None,
+        };
+        self.position += 1;
+        self.skip_comma_whitespace();
+        Some(flag)
+    }
+
+    fn skip_sign(&mut self) {
+        match self.data.get(self.position) {
+            Some(&b'+') | Some(&b'-') => self.position += 1,
+            _ => {},
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<f32> {
+        self.skip_whitespace();
+        let start = self.position;
+        let is_digit = |data: &[u8], position: usize| {
+            data.get(position).map_or(false, |byte| byte.is_ascii_digit())
+        };
+
+        self.skip_sign();
+        let mut has_digits = false;
+        while is_digit(self.data, self.position) {
+            self.position += 1;
+            has_digits = true;
+        }
+        if self.data.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            while is_digit(self.data, self.position) {
+                self.position += 1;
+                has_digits = true;
+            }
+        }
+        if !has_digits {
+            return None;
+        }
+        if let Some(&b'e') | Some(&b'E') = self.data.get(self.position) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            self.skip_sign();
+            if is_digit(self.data, self.position) {
+                while is_digit(self.data, self.position) {
+                    self.position += 1;
+                }
+            } else {
+                self.position = mantissa_end;
+            }
+        }
+
+        let number = ::std::str::from_utf8(&self.data[start..self.position]).ok()?
+            .parse::<f32>().ok()?;
+        self.skip_comma_whitespace();
+        if number.is_finite() { Some(number) } else { None }
+    }
+}
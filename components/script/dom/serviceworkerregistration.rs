@@ -68,7 +68,7 @@ impl ServiceWorkerRegistration {
 
         let worker_id = global.get_next_worker_id();
         let devtools_chan = global.devtools_chan().cloned();
-        let init = prepare_workerscope_init(&global, None);
+        let init = prepare_workerscope_init(&global, None, None);
         ScopeThings {
             script_url: script_url,
             init: init,
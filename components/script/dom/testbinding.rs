@@ -20,6 +20,7 @@ use dom::bindings::codegen::UnionTypes::{HTMLElementOrUnsignedLongOrStringOrBool
 use dom::bindings::codegen::UnionTypes::{StringOrLongSequence, StringOrStringSequence, StringSequenceOrUnsignedLong};
 use dom::bindings::codegen::UnionTypes::{StringOrUnsignedLong, StringOrBoolean, UnsignedLongOrBoolean};
 use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
 use dom::bindings::mozmap::MozMap;
 use dom::bindings::num::Finite;
 use dom::bindings::refcounted::TrustedPromise;
@@ -29,10 +30,12 @@ use dom::bindings::str::{ByteString, DOMString, USVString};
 use dom::bindings::trace::RootedTraceableBox;
 use dom::bindings::weakref::MutableWeakRef;
 use dom::blob::{Blob, BlobImpl};
+use dom::console;
 use dom::globalscope::GlobalScope;
 use dom::promise::Promise;
 use dom::promisenativehandler::{PromiseNativeHandler, Callback};
 use dom::url::URL;
+use dom::window::Window;
 use dom_struct::dom_struct;
 use js::jsapi::{Heap, JSContext, JSObject};
 use js::jsapi::{JS_NewPlainObject, JS_NewUint8ClampedArray};
@@ -810,6 +813,22 @@ impl TestBindingMethods for TestBinding {
     fn IncumbentGlobal(&self) -> DomRoot<GlobalScope> {
         GlobalScope::incumbent().unwrap()
     }
+
+    #[allow(unsafe_code)]
+    fn CurrentStack(&self) -> Vec<DOMString> {
+        let frames = unsafe { console::current_stack(self.global().get_cx()) };
+        frames.into_iter().map(|frame| {
+            DOMString::from(format!("{}@{}:{}:{}",
+                                    frame.functionName,
+                                    frame.filename,
+                                    frame.lineNumber,
+                                    frame.columnNumber))
+        }).collect()
+    }
+
+    fn PrintToPdf(&self) -> Option<ByteString> {
+        self.global().downcast::<Window>()?.print_to_pdf().map(ByteString::new)
+    }
 }
 
 impl TestBinding {
@@ -3,88 +3,657 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 /// https://www.khronos.org/registry/webgl/specs/latest/2.0/webgl.idl
-use canvas_traits::webgl::WebGLVersion;
+use canvas_traits::webgl::{WebGLResult, WebGLVersion};
+use canvas_traits::webgl::{webgl_channel, ParameterInt, WebGLCommand, WebGLFramebufferBindingRequest};
+use canvas_traits::webgl::WebGLError::*;
 use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextMethods;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLContextAttributes;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextMethods;
 use dom::bindings::codegen::UnionTypes::ArrayBufferViewOrArrayBuffer;
 use dom::bindings::codegen::UnionTypes::Float32ArrayOrUnrestrictedFloatSequence;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
 use dom::bindings::codegen::UnionTypes::ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement;
 use dom::bindings::codegen::UnionTypes::Int32ArrayOrLongSequence;
+use dom::bindings::conversions::ToJSValConvertible;
 use dom::bindings::error::{ErrorResult, Fallible};
-use dom::bindings::reflector::{reflect_dom_object, Reflector};
-use dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom};
 use dom::bindings::str::DOMString;
-use dom::htmlcanvaselement::HTMLCanvasElement;
+use dom::globalscope::GlobalScope;
 use dom::htmliframeelement::HTMLIFrameElement;
+use dom::webgl_validations::WebGLValidator;
+use dom::webgl_validations::tex_image_3d::{TexImage3DLimits, TexImage3DValidator, TexImage3DValidatorResult};
+use dom::webgl_validations::tex_image_3d::TexStorageValidator;
+use dom::webgl_validations::types::{TexImageTarget, bytes_per_pixel};
 use dom::webglactiveinfo::WebGLActiveInfo;
 use dom::webglbuffer::WebGLBuffer;
-use dom::webglframebuffer::WebGLFramebuffer;
+use dom::webglframebuffer::{WebGLFramebuffer, WebGLFramebufferAttachmentRoot};
 use dom::webglprogram::WebGLProgram;
+use dom::webglquery::WebGLQuery;
 use dom::webglrenderbuffer::WebGLRenderbuffer;
 use dom::webglrenderingcontext::{LayoutCanvasWebGLRenderingContextHelpers, WebGLRenderingContext};
+use dom::webglsampler::WebGLSampler;
 use dom::webglshader::WebGLShader;
 use dom::webglshaderprecisionformat::WebGLShaderPrecisionFormat;
-use dom::webgltexture::WebGLTexture;
+use dom::webglsync::WebGLSync;
+use dom::webgltexture::{TexParameterValue, WebGLTexture};
+use dom::webgltransformfeedback::WebGLTransformFeedback;
 use dom::webgluniformlocation::WebGLUniformLocation;
-use dom::window::Window;
 use dom_struct::dom_struct;
 use euclid::Size2D;
-use js::jsapi::{JSContext, JSObject};
-use js::jsval::JSVal;
+use js::jsapi::{JSContext, JSObject, Type};
+use js::jsval::{BooleanValue, DoubleValue, Int32Value, JSVal, NullValue, ObjectValue, UInt32Value};
 use js::rust::CustomAutoRooterGuard;
-use js::typedarray::ArrayBufferView;
+use js::typedarray::{ArrayBufferView, CreateWith, Int32Array};
 use offscreen_gl_context::GLContextAttributes;
 use script_layout_interface::HTMLCanvasDataSource;
-use std::ptr::NonNull;
+use std::cell::Cell;
+use std::ptr::{self, NonNull};
+
+/// The longest timeout clientWaitSync accepts, in nanoseconds. Blocking the
+/// script thread on the GPU is never a good idea, so only polling is allowed.
+const MAX_CLIENT_WAIT_TIMEOUT_WEBGL: u64 = 0;
 
 #[dom_struct]
 pub struct WebGL2RenderingContext {
     reflector_: Reflector,
     base: Dom<WebGLRenderingContext>,
+    texture_limits: TexImage3DLimits,
+    max_color_attachments: u32,
+    max_transform_feedback_separate_attribs: u32,
+    uniform_buffer_offset_alignment: u32,
+    bound_copy_read_buffer: MutNullableDom<WebGLBuffer>,
+    bound_copy_write_buffer: MutNullableDom<WebGLBuffer>,
+    bound_pixel_pack_buffer: MutNullableDom<WebGLBuffer>,
+    bound_pixel_unpack_buffer: MutNullableDom<WebGLBuffer>,
+    bound_transform_feedback_buffer: MutNullableDom<WebGLBuffer>,
+    bound_uniform_buffer: MutNullableDom<WebGLBuffer>,
+    /// The indexed UNIFORM_BUFFER bindings.
+    uniform_buffers: Box<[MutNullableDom<WebGLBuffer>]>,
+    /// The indexed TRANSFORM_FEEDBACK_BUFFER bindings of the default
+    /// transform feedback object.
+    transform_feedback_buffers: Box<[MutNullableDom<WebGLBuffer>]>,
+    /// The draw framebuffer binding lives in the base context.
+    bound_read_framebuffer: MutNullableDom<WebGLFramebuffer>,
+    /// The readBuffer of the default framebuffer, BACK or NONE.
+    default_read_buffer: Cell<u32>,
+    /// The sampler bound to each texture unit.
+    samplers: Box<[MutNullableDom<WebGLSampler>]>,
+    /// The active ANY_SAMPLES_PASSED or ANY_SAMPLES_PASSED_CONSERVATIVE
+    /// query, only one of them can be active at a time.
+    occlusion_query: MutNullableDom<WebGLQuery>,
+    /// The active TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN query.
+    primitives_query: MutNullableDom<WebGLQuery>,
+    bound_transform_feedback: MutNullableDom<WebGLTransformFeedback>,
+    /// The program transform feedback was begun with.
+    transform_feedback_program: MutNullableDom<WebGLProgram>,
+    /// The primitive mode of the active transform feedback, if any.
+    transform_feedback_mode: Cell<Option<u32>>,
+    transform_feedback_paused: Cell<bool>,
+}
+
+fn new_bindings<T: DomObject>(count: u32) -> Box<[MutNullableDom<T>]> {
+    (0..count).map(|_| MutNullableDom::new(None)).collect::<Vec<_>>().into()
+}
+
+fn get_parameter_int(base: &WebGLRenderingContext, parameter: ParameterInt) -> u32 {
+    let (sender, receiver) = webgl_channel().unwrap();
+    base.send_command(WebGLCommand::GetParameterInt(parameter, sender));
+    receiver.recv().unwrap() as u32
+}
+
+/// Returns the size in bytes of the elements of a typed array.
+fn array_element_size(array_type: Type) -> u32 {
+    match array_type {
+        Type::Int8 | Type::Uint8 | Type::Uint8Clamped => 1,
+        Type::Int16 | Type::Uint16 => 2,
+        Type::Int32 | Type::Uint32 | Type::Float32 => 4,
+        Type::Float64 => 8,
+        _ => unreachable!(),
+    }
+}
+
+/// Returns whether `array_type` is the typed array type that pixels of type
+/// `data_type` must be passed with, as per
+/// https://www.khronos.org/registry/webgl/specs/latest/2.0/#TEXTURE_PIXELS_TYPE_TABLE
+fn is_array_type_valid_for_data_type(array_type: Type, data_type: u32) -> bool {
+    match data_type {
+        constants::UNSIGNED_BYTE => array_type == Type::Uint8 || array_type == Type::Uint8Clamped,
+        constants::BYTE => array_type == Type::Int8,
+        constants::SHORT => array_type == Type::Int16,
+        constants::UNSIGNED_SHORT |
+        constants::UNSIGNED_SHORT_5_6_5 |
+        constants::UNSIGNED_SHORT_4_4_4_4 |
+        constants::UNSIGNED_SHORT_5_5_5_1 |
+        constants::HALF_FLOAT => array_type == Type::Uint16,
+        constants::INT => array_type == Type::Int32,
+        constants::UNSIGNED_INT |
+        constants::UNSIGNED_INT_2_10_10_10_REV |
+        constants::UNSIGNED_INT_10F_11F_11F_REV |
+        constants::UNSIGNED_INT_5_9_9_9_REV |
+        constants::UNSIGNED_INT_24_8 => array_type == Type::Uint32,
+        constants::FLOAT => array_type == Type::Float32,
+        _ => false,
+    }
 }
 
 impl WebGL2RenderingContext {
     fn new_inherited(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         size: Size2D<i32>,
         attrs: GLContextAttributes
     ) -> Option<WebGL2RenderingContext> {
-        let base = WebGLRenderingContext::new(window, canvas, WebGLVersion::WebGL2, size, attrs)?;
+        let base = WebGLRenderingContext::new(global, canvas, WebGLVersion::WebGL2, size, attrs)?;
+        let texture_limits = TexImage3DLimits {
+            max_3d_texture_size: get_parameter_int(&base, ParameterInt::Max3DTextureSize),
+            max_array_texture_layers: get_parameter_int(&base, ParameterInt::MaxArrayTextureLayers),
+        };
+        let max_transform_feedback_separate_attribs =
+            get_parameter_int(&base, ParameterInt::MaxTransformFeedbackSeparateAttribs);
+        let max_uniform_buffer_bindings = get_parameter_int(&base, ParameterInt::MaxUniformBufferBindings);
+        let max_texture_units = base.limits().max_combined_texture_image_units;
         Some(WebGL2RenderingContext {
             reflector_: Reflector::new(),
+            texture_limits: texture_limits,
+            max_color_attachments: get_parameter_int(&base, ParameterInt::MaxColorAttachments),
+            max_transform_feedback_separate_attribs: max_transform_feedback_separate_attribs,
+            uniform_buffer_offset_alignment:
+                get_parameter_int(&base, ParameterInt::MaxUniformBufferOffsetAlignment),
+            bound_copy_read_buffer: MutNullableDom::new(None),
+            bound_copy_write_buffer: MutNullableDom::new(None),
+            bound_pixel_pack_buffer: MutNullableDom::new(None),
+            bound_pixel_unpack_buffer: MutNullableDom::new(None),
+            bound_transform_feedback_buffer: MutNullableDom::new(None),
+            bound_uniform_buffer: MutNullableDom::new(None),
+            uniform_buffers: new_bindings(max_uniform_buffer_bindings),
+            transform_feedback_buffers: new_bindings(max_transform_feedback_separate_attribs),
+            bound_read_framebuffer: MutNullableDom::new(None),
+            default_read_buffer: Cell::new(constants::BACK),
+            samplers: new_bindings(max_texture_units),
+            occlusion_query: MutNullableDom::new(None),
+            primitives_query: MutNullableDom::new(None),
+            bound_transform_feedback: MutNullableDom::new(None),
+            transform_feedback_program: MutNullableDom::new(None),
+            transform_feedback_mode: Cell::new(None),
+            transform_feedback_paused: Cell::new(false),
             base: Dom::from_ref(&*base),
         })
     }
 
     #[allow(unrooted_must_root)]
     pub fn new(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         size: Size2D<i32>,
         attrs: GLContextAttributes
     ) -> Option<DomRoot<WebGL2RenderingContext>> {
-        WebGL2RenderingContext::new_inherited(window, canvas, size, attrs).map(|ctx| {
-            reflect_dom_object(Box::new(ctx), window, WebGL2RenderingContextBinding::Wrap)
+        WebGL2RenderingContext::new_inherited(global, canvas, size, attrs).map(|ctx| {
+            let ctx = reflect_dom_object(Box::new(ctx), global, WebGL2RenderingContextBinding::Wrap);
+            ctx.base.set_webgl2_context(&ctx);
+            ctx
         })
     }
 }
 
 impl WebGL2RenderingContext {
     pub fn recreate(&self, size: Size2D<i32>) {
-        self.base.recreate(size)
+        if self.base.is_context_lost() {
+            return;
+        }
+        self.base.recreate(size);
+
+        // The base context only restores the FRAMEBUFFER binding, which also
+        // replaces the read framebuffer.
+        match self.bound_read_framebuffer.get() {
+            Some(framebuffer) => framebuffer.bind(constants::READ_FRAMEBUFFER),
+            None => self.base.send_command(WebGLCommand::BindFramebuffer(
+                constants::READ_FRAMEBUFFER,
+                WebGLFramebufferBindingRequest::Default,
+            )),
+        }
+    }
+
+    /// Resets the WebGL 2.0 state when the base context is restored.
+    pub fn reset_state(&self) {
+        self.bound_copy_read_buffer.set(None);
+        self.bound_copy_write_buffer.set(None);
+        self.bound_pixel_pack_buffer.set(None);
+        self.bound_pixel_unpack_buffer.set(None);
+        self.bound_transform_feedback_buffer.set(None);
+        self.bound_uniform_buffer.set(None);
+        for binding in self.uniform_buffers.iter().chain(self.transform_feedback_buffers.iter()) {
+            binding.set(None);
+        }
+        self.bound_read_framebuffer.set(None);
+        self.default_read_buffer.set(constants::BACK);
+        for sampler in self.samplers.iter() {
+            sampler.set(None);
+        }
+        self.occlusion_query.set(None);
+        self.primitives_query.set(None);
+        self.bound_transform_feedback.set(None);
+        self.transform_feedback_program.set(None);
+        self.transform_feedback_mode.set(None);
+        self.transform_feedback_paused.set(false);
     }
 
     pub fn base_context(&self) -> DomRoot<WebGLRenderingContext> {
         DomRoot::from_ref(&*self.base)
     }
+
+    /// Returns the binding slot of the buffer targets introduced by WebGL 2.0.
+    fn buffer_slot(&self, target: u32) -> Option<&MutNullableDom<WebGLBuffer>> {
+        match target {
+            constants::COPY_READ_BUFFER => Some(&self.bound_copy_read_buffer),
+            constants::COPY_WRITE_BUFFER => Some(&self.bound_copy_write_buffer),
+            constants::PIXEL_PACK_BUFFER => Some(&self.bound_pixel_pack_buffer),
+            constants::PIXEL_UNPACK_BUFFER => Some(&self.bound_pixel_unpack_buffer),
+            constants::TRANSFORM_FEEDBACK_BUFFER => Some(&self.bound_transform_feedback_buffer),
+            constants::UNIFORM_BUFFER => Some(&self.bound_uniform_buffer),
+            _ => None,
+        }
+    }
+
+    fn bound_buffer(&self, target: u32) -> WebGLResult<Option<DomRoot<WebGLBuffer>>> {
+        match self.buffer_slot(target) {
+            Some(slot) => Ok(slot.get()),
+            None => self.base.bound_buffer(target),
+        }
+    }
+
+    /// Returns the framebuffer bound to `target`, which is the draw one for
+    /// FRAMEBUFFER.
+    fn bound_framebuffer(&self, target: u32) -> WebGLResult<Option<DomRoot<WebGLFramebuffer>>> {
+        match target {
+            constants::FRAMEBUFFER | constants::DRAW_FRAMEBUFFER => Ok(self.base.bound_framebuffer()),
+            constants::READ_FRAMEBUFFER => Ok(self.bound_read_framebuffer.get()),
+            _ => Err(InvalidEnum),
+        }
+    }
+
+    /// Checks that the read framebuffer is complete and has a color image
+    /// selected to read from, as readPixels and copyTex(Sub)Image* require.
+    fn validate_read_framebuffer(&self) -> bool {
+        let read_buffer = match self.bound_read_framebuffer.get() {
+            Some(framebuffer) => {
+                if framebuffer.check_status() != constants::FRAMEBUFFER_COMPLETE {
+                    self.base.webgl_error(InvalidFramebufferOperation);
+                    return false;
+                }
+                let attachment = match framebuffer.read_buffer() {
+                    constants::NONE => None,
+                    read_buffer => framebuffer.attachment(read_buffer),
+                };
+                attachment.is_some()
+            }
+            None => self.default_read_buffer.get() != constants::NONE,
+        };
+        if !read_buffer {
+            self.base.webgl_error(InvalidOperation);
+        }
+        read_buffer
+    }
+
+    fn is_pixel_unpack_buffer_bound(&self) -> bool {
+        self.bound_pixel_unpack_buffer.get().is_some()
+    }
+
+    fn is_transform_feedback_active(&self) -> bool {
+        self.transform_feedback_mode.get().is_some()
+    }
+
+    fn is_transform_feedback_active_and_unpaused(&self) -> bool {
+        self.is_transform_feedback_active() && !self.transform_feedback_paused.get()
+    }
+
+    /// Draw calls must use the primitive mode of the active transform
+    /// feedback, as per the GLES 3.0.5 spec, section 2.15.2.
+    fn validate_transform_feedback_draw_mode(&self, mode: u32) -> bool {
+        match self.transform_feedback_mode.get() {
+            Some(tf_mode) if !self.transform_feedback_paused.get() => {
+                let matches = match tf_mode {
+                    constants::POINTS => mode == constants::POINTS,
+                    constants::LINES => mode == constants::LINES,
+                    _ => mode == constants::TRIANGLES,
+                };
+                if !matches {
+                    self.base.webgl_error(InvalidOperation);
+                }
+                matches
+            }
+            _ => true,
+        }
+    }
+
+    /// Runs `f` with the indexed TRANSFORM_FEEDBACK_BUFFER bindings of the
+    /// bound transform feedback object.
+    fn with_transform_feedback_buffers<F, R>(&self, f: F) -> R
+        where F: FnOnce(&[MutNullableDom<WebGLBuffer>]) -> R
+    {
+        match self.bound_transform_feedback.get() {
+            Some(tf) => f(tf.buffers()),
+            None => f(&self.transform_feedback_buffers),
+        }
+    }
+
+    fn query_slot(&self, target: u32) -> WebGLResult<&MutNullableDom<WebGLQuery>> {
+        match target {
+            constants::ANY_SAMPLES_PASSED |
+            constants::ANY_SAMPLES_PASSED_CONSERVATIVE => Ok(&self.occlusion_query),
+            constants::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN => Ok(&self.primitives_query),
+            _ => Err(InvalidEnum),
+        }
+    }
+
+    fn is_query_active(&self, query: &WebGLQuery) -> bool {
+        [&self.occlusion_query, &self.primitives_query]
+            .iter()
+            .any(|slot| slot.get().map_or(false, |active| active.id() == query.id()))
+    }
+
+    /// Binds `buffer` to both the indexed and the generic binding points of
+    /// `target`, for bindBufferBase and bindBufferRange.
+    fn bind_buffer_indexed(
+        &self,
+        target: u32,
+        index: u32,
+        buffer: Option<&WebGLBuffer>,
+        range: Option<(i64, i64)>,
+    ) {
+        let max_index = match target {
+            constants::TRANSFORM_FEEDBACK_BUFFER => {
+                if self.is_transform_feedback_active() {
+                    return self.base.webgl_error(InvalidOperation);
+                }
+                self.max_transform_feedback_separate_attribs
+            }
+            constants::UNIFORM_BUFFER => self.uniform_buffers.len() as u32,
+            _ => return self.base.webgl_error(InvalidEnum),
+        };
+        if index >= max_index {
+            return self.base.webgl_error(InvalidValue);
+        }
+
+        if let Some(buffer) = buffer {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(buffer), return);
+            if buffer.is_marked_for_deletion() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+            if let Some((offset, size)) = range {
+                if offset < 0 || size <= 0 {
+                    return self.base.webgl_error(InvalidValue);
+                }
+                let alignment = match target {
+                    constants::TRANSFORM_FEEDBACK_BUFFER => {
+                        if size % 4 != 0 {
+                            return self.base.webgl_error(InvalidValue);
+                        }
+                        4
+                    }
+                    _ => self.uniform_buffer_offset_alignment as i64,
+                };
+                if offset % alignment != 0 {
+                    return self.base.webgl_error(InvalidValue);
+                }
+            }
+            handle_potential_webgl_error!(self.base, buffer.set_target(target), return);
+        }
+
+        let bind = |slot: &MutNullableDom<WebGLBuffer>| {
+            if let Some(buffer) = buffer {
+                buffer.increment_attached_counter();
+            }
+            if let Some(old) = slot.get() {
+                old.decrement_attached_counter();
+            }
+            slot.set(buffer);
+        };
+        bind(self.buffer_slot(target).unwrap());
+        match target {
+            constants::TRANSFORM_FEEDBACK_BUFFER => {
+                self.with_transform_feedback_buffers(|buffers| bind(&buffers[index as usize]))
+            }
+            _ => bind(&self.uniform_buffers[index as usize]),
+        }
+
+        let id = buffer.map(|buffer| buffer.id());
+        self.base.send_command(match range {
+            Some((offset, size)) => WebGLCommand::BindBufferRange(target, index, id, offset, size),
+            None => WebGLCommand::BindBufferBase(target, index, id),
+        });
+    }
+
+    /// Returns the bytes of `view` from element `src_offset` on, and of
+    /// `length` elements if not 0.
+    #[allow(unsafe_code)]
+    fn array_buffer_view_data<'a>(
+        &self,
+        view: &'a ArrayBufferView,
+        src_offset: u32,
+        length: u32,
+    ) -> WebGLResult<&'a [u8]> {
+        let element_size = array_element_size(view.get_array_type()) as usize;
+        // The typed array is rooted, so we can safely temporarily retrieve its slice
+        let data = unsafe { view.as_slice() };
+        let len = data.len() / element_size;
+        let start = src_offset as usize;
+        if start > len {
+            return Err(InvalidValue);
+        }
+        let end = match length {
+            0 => len,
+            length => start + length as usize,
+        };
+        if end > len {
+            return Err(InvalidValue);
+        }
+        Ok(&data[start * element_size..end * element_size])
+    }
+
+    /// Returns the number of bytes texImage3D and texSubImage3D read for
+    /// an image, given the current UNPACK_ALIGNMENT.
+    fn tex_image_3d_byte_length(&self, validated: &TexImage3DValidatorResult) -> WebGLResult<usize> {
+        let bytes_per_pixel = bytes_per_pixel(validated.format, validated.data_type).ok_or(InvalidEnum)?;
+        if validated.width == 0 || validated.height == 0 || validated.depth == 0 {
+            return Ok(0);
+        }
+        let alignment = self.base.texture_unpacking_alignment() as usize;
+        let row_size = validated.width as usize * bytes_per_pixel as usize;
+        let stride = (row_size + alignment - 1) & !(alignment - 1);
+        let image_size = stride * validated.height as usize;
+        Ok(image_size * (validated.depth as usize - 1) + stride * (validated.height as usize - 1) + row_size)
+    }
+
+    /// Validates the client data of texImage3D and texSubImage3D, and
+    /// returns the bytes to upload from it.
+    fn tex_image_3d_data(
+        &self,
+        validated: &TexImage3DValidatorResult,
+        src_data: Option<&ArrayBufferView>,
+        src_offset: u32,
+    ) -> WebGLResult<Vec<u8>> {
+        // Uploads from client data can't happen while a PIXEL_UNPACK_BUFFER
+        // is bound.
+        if self.is_pixel_unpack_buffer_bound() {
+            return Err(InvalidOperation);
+        }
+        // Neither of these is supported for three-dimensional uploads.
+        if self.base.is_unpack_flip_y_or_premultiply_alpha_enabled() {
+            return Err(InvalidOperation);
+        }
+
+        let expected_byte_length = self.tex_image_3d_byte_length(validated)?;
+
+        let src_data = match src_data {
+            Some(src_data) => src_data,
+            None => return Ok(vec![0u8; expected_byte_length]),
+        };
+        if !is_array_type_valid_for_data_type(src_data.get_array_type(), validated.data_type) {
+            return Err(InvalidOperation);
+        }
+        let data = self.array_buffer_view_data(src_data, src_offset, 0)?;
+        if data.len() < expected_byte_length {
+            return Err(InvalidOperation);
+        }
+        Ok(data[..expected_byte_length].to_vec())
+    }
+
+    /// Checks that the given region fits in the image of level `level` of
+    /// `texture`, for texSubImage3D and copyTexSubImage3D.
+    fn validate_tex_sub_image_3d_region(
+        &self,
+        texture: &WebGLTexture,
+        target: &TexImageTarget,
+        level: u32,
+        offset: (i32, i32, i32),
+        size: (u32, u32, u32),
+    ) -> WebGLResult<()> {
+        let (xoffset, yoffset, zoffset) = offset;
+        if xoffset < 0 || yoffset < 0 || zoffset < 0 {
+            return Err(InvalidValue);
+        }
+        let image_info = texture.image_info_for_target(target, level);
+        if xoffset as u32 + size.0 > image_info.width() ||
+            yoffset as u32 + size.1 > image_info.height() ||
+            zoffset as u32 + size.2 > image_info.depth()
+        {
+            return Err(InvalidValue);
+        }
+        Ok(())
+    }
+
+    /// Uploads a validated texImage3D image.
+    fn tex_image_3d(&self, validated: TexImage3DValidatorResult, data: Vec<u8>) {
+        let TexImage3DValidatorResult {
+            texture,
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+        } = validated;
+
+        handle_potential_webgl_error!(
+            self.base,
+            texture.initialize_sized(target, width, height, depth, internal_format, level),
+            return
+        );
+
+        let alignment = self.base.texture_unpacking_alignment();
+        self.base.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, alignment as i32));
+        self.base.send_command(WebGLCommand::TexImage3D {
+            target: target.as_gl_constant(),
+            level: level as i32,
+            internal_format: internal_format.as_gl_constant() as i32,
+            width: width as i32,
+            height: height as i32,
+            depth: depth as i32,
+            format: format.as_gl_constant(),
+            data_type: data_type,
+            data: data.into(),
+        });
+    }
+
+    /// Validates the arguments shared by both texSubImage3D overloads.
+    fn validate_tex_sub_image_3d(
+        &self,
+        target: u32,
+        level: i32,
+        offset: (i32, i32, i32),
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+    ) -> Option<TexImage3DValidatorResult> {
+        let validator = TexImage3DValidator::new(
+            &self.base,
+            self.texture_limits,
+            target,
+            level,
+            None,
+            width,
+            height,
+            depth,
+            0,
+            format,
+            data_type,
+        );
+        let validated = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return None, // NB: The validator sets the correct error for us.
+        };
+        handle_potential_webgl_error!(
+            self.base,
+            self.validate_tex_sub_image_3d_region(
+                &validated.texture,
+                &validated.target,
+                validated.level,
+                offset,
+                (validated.width, validated.height, validated.depth),
+            ),
+            return None
+        );
+        Some(validated)
+    }
+
+    /// Checks that the attachments passed to invalidateFramebuffer and
+    /// invalidateSubFramebuffer exist, and translates the ones of the default
+    /// framebuffer, which is actually a framebuffer object.
+    fn validate_invalidation_attachments(&self, target: u32, attachments: Vec<u32>) -> WebGLResult<Vec<u32>> {
+        let framebuffer = self.bound_framebuffer(target)?;
+        attachments.into_iter().map(|attachment| {
+            match framebuffer {
+                None => match attachment {
+                    constants::COLOR => Ok(constants::COLOR_ATTACHMENT0),
+                    constants::DEPTH => Ok(constants::DEPTH_ATTACHMENT),
+                    constants::STENCIL => Ok(constants::STENCIL_ATTACHMENT),
+                    _ => Err(InvalidEnum),
+                },
+                Some(_) => match attachment {
+                    constants::DEPTH_ATTACHMENT |
+                    constants::STENCIL_ATTACHMENT |
+                    constants::DEPTH_STENCIL_ATTACHMENT => Ok(attachment),
+                    constants::COLOR_ATTACHMENT0...constants::COLOR_ATTACHMENT15 => {
+                        if attachment - constants::COLOR_ATTACHMENT0 >= self.max_color_attachments {
+                            return Err(InvalidOperation);
+                        }
+                        Ok(attachment)
+                    }
+                    _ => Err(InvalidEnum),
+                },
+            }
+        }).collect()
+    }
+}
+
+/// Returns the internal formats of the depth and stencil images of a
+/// framebuffer, which have to match for blitFramebuffer to copy them.
+fn depth_stencil_formats(framebuffer: &WebGLFramebuffer) -> [Option<u32>; 3] {
+    let format = |attachment: u32| {
+        framebuffer.attachment(attachment).map(|attachment| match attachment {
+            WebGLFramebufferAttachmentRoot::Renderbuffer(renderbuffer) => renderbuffer.internal_format(),
+            WebGLFramebufferAttachmentRoot::Texture(texture) => {
+                texture.image_info_at_face(0, 0).sized_internal_format().map_or(0, |sized| sized.as_gl_constant())
+            }
+        })
+    };
+    [
+        format(constants::DEPTH_ATTACHMENT),
+        format(constants::STENCIL_ATTACHMENT),
+        format(constants::DEPTH_STENCIL_ATTACHMENT),
+    ]
 }
 
 impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
-    fn Canvas(&self) -> DomRoot<HTMLCanvasElement> {
+    fn Canvas(&self) -> HTMLCanvasElementOrOffscreenCanvas {
         self.base.Canvas()
     }
 
@@ -111,13 +680,62 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     #[allow(unsafe_code)]
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     unsafe fn GetBufferParameter(&self, _cx: *mut JSContext, target: u32, parameter: u32) -> JSVal {
-        self.base.GetBufferParameter(_cx, target, parameter)
+        self.base.get_buffer_parameter(self.bound_buffer(target), parameter)
     }
 
     #[allow(unsafe_code)]
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
     unsafe fn GetParameter(&self, cx: *mut JSContext, parameter: u32) -> JSVal {
-        self.base.GetParameter(cx, parameter)
+        match parameter {
+            constants::COPY_READ_BUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_copy_read_buffer.get())
+            }
+            constants::COPY_WRITE_BUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_copy_write_buffer.get())
+            }
+            constants::PIXEL_PACK_BUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_pixel_pack_buffer.get())
+            }
+            constants::PIXEL_UNPACK_BUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_pixel_unpack_buffer.get())
+            }
+            constants::TRANSFORM_FEEDBACK_BUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_transform_feedback_buffer.get())
+            }
+            constants::UNIFORM_BUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_uniform_buffer.get())
+            }
+            constants::READ_FRAMEBUFFER_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_read_framebuffer.get())
+            }
+            constants::READ_BUFFER => {
+                let read_buffer = match self.bound_read_framebuffer.get() {
+                    Some(framebuffer) => framebuffer.read_buffer(),
+                    None => self.default_read_buffer.get(),
+                };
+                Int32Value(read_buffer as i32)
+            }
+            constants::SAMPLER_BINDING => {
+                let unit = self.base.active_texture_unit() as usize;
+                let sampler = self.samplers.get(unit).and_then(|slot| slot.get());
+                optional_root_object_to_js_or_null!(cx, sampler)
+            }
+            constants::TEXTURE_BINDING_3D => {
+                let texture = self.base.bound_texture(constants::TEXTURE_3D);
+                optional_root_object_to_js_or_null!(cx, texture)
+            }
+            constants::TEXTURE_BINDING_2D_ARRAY => {
+                let texture = self.base.bound_texture(constants::TEXTURE_2D_ARRAY);
+                optional_root_object_to_js_or_null!(cx, texture)
+            }
+            constants::TRANSFORM_FEEDBACK_BINDING => {
+                optional_root_object_to_js_or_null!(cx, &self.bound_transform_feedback.get())
+            }
+            constants::TRANSFORM_FEEDBACK_ACTIVE => BooleanValue(self.is_transform_feedback_active()),
+            constants::TRANSFORM_FEEDBACK_PAUSED => BooleanValue(self.transform_feedback_paused.get()),
+            constants::MAX_CLIENT_WAIT_TIMEOUT_WEBGL => DoubleValue(MAX_CLIENT_WAIT_TIMEOUT_WEBGL as f64),
+            _ => self.base.GetParameter(cx, parameter),
+        }
     }
 
     #[allow(unsafe_code)]
@@ -136,6 +754,11 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         self.base.GetContextAttributes()
     }
 
+    /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.13
+    fn IsContextLost(&self) -> bool {
+        self.base.IsContextLost()
+    }
+
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.14
     fn GetSupportedExtensions(&self) -> Option<Vec<DOMString>> {
         self.base.GetSupportedExtensions()
@@ -217,12 +840,34 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn BindBuffer(&self, target: u32, buffer: Option<&WebGLBuffer>) {
-        self.base.BindBuffer(target, buffer)
+        match self.buffer_slot(target) {
+            Some(slot) => self.base.bind_buffer_maybe(slot, target, buffer),
+            None => self.base.BindBuffer(target, buffer),
+        }
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
     fn BindFramebuffer(&self, target: u32, framebuffer: Option<&WebGLFramebuffer>) {
-        self.base.BindFramebuffer(target, framebuffer)
+        if let Some(framebuffer) = framebuffer {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(framebuffer), return);
+            if framebuffer.is_deleted() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+        match target {
+            // FRAMEBUFFER binds both the draw and the read framebuffers.
+            constants::FRAMEBUFFER => {
+                self.base.bind_framebuffer_to(target, framebuffer, self.base.draw_framebuffer_slot());
+                self.bound_read_framebuffer.set(framebuffer);
+            }
+            constants::DRAW_FRAMEBUFFER => {
+                self.base.bind_framebuffer_to(target, framebuffer, self.base.draw_framebuffer_slot())
+            }
+            constants::READ_FRAMEBUFFER => {
+                self.base.bind_framebuffer_to(target, framebuffer, &self.bound_read_framebuffer)
+            }
+            _ => self.base.webgl_error(InvalidEnum),
+        }
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
@@ -243,17 +888,22 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     #[allow(unsafe_code)]
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     unsafe fn BufferData(&self, cx: *mut JSContext, target: u32, data: *mut JSObject, usage: u32) -> Fallible<()> {
-        self.base.BufferData(cx, target, data, usage)
+        self.base.buffer_data(cx, target, data, usage, self.bound_buffer(target))
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn BufferData_(&self, target: u32, size: i64, usage: u32) -> Fallible<()> {
-        self.base.BufferData_(target, size, usage)
+        self.base.buffer_data_(target, size, usage, self.bound_buffer(target))
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn BufferSubData(&self, target: u32, offset: i64, data: ArrayBufferViewOrArrayBuffer) {
-        self.base.BufferSubData(target, offset, data)
+        let data_vec = match data {
+            // Typed array is rooted, so we can safely temporarily retrieve its slice
+            ArrayBufferViewOrArrayBuffer::ArrayBuffer(mut inner) => inner.to_vec(),
+            ArrayBufferViewOrArrayBuffer::ArrayBufferView(mut inner) => inner.to_vec(),
+        };
+        self.base.buffer_sub_data(target, offset, data_vec, self.bound_buffer(target))
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
@@ -273,12 +923,18 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
     fn CopyTexImage2D(&self, target: u32, level: i32, internal_format: u32,
                       x: i32, y: i32, width: i32, height: i32, border: i32) {
+        if !self.validate_read_framebuffer() {
+            return;
+        }
         self.base.CopyTexImage2D(target, level, internal_format, x, y, width, height, border)
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
     fn CopyTexSubImage2D(&self, target: u32, level: i32, xoffset: i32, yoffset: i32,
                          x: i32, y: i32, width: i32, height: i32) {
+        if !self.validate_read_framebuffer() {
+            return;
+        }
         self.base.CopyTexSubImage2D(target, level, xoffset, yoffset, x, y, width, height)
     }
 
@@ -378,21 +1034,65 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn DeleteBuffer(&self, buffer: Option<&WebGLBuffer>) {
+        if let Some(buffer) = buffer {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(buffer), return);
+            if buffer.is_marked_for_deletion() {
+                return;
+            }
+            // Deleting a buffer unbinds it from the generic binding points,
+            // the indexed ones keep it alive until they are rebound.
+            let slots = [
+                &self.bound_copy_read_buffer,
+                &self.bound_copy_write_buffer,
+                &self.bound_pixel_pack_buffer,
+                &self.bound_pixel_unpack_buffer,
+                &self.bound_transform_feedback_buffer,
+                &self.bound_uniform_buffer,
+            ];
+            for slot in slots.iter() {
+                if slot.get().map_or(false, |bound| bound.id() == buffer.id()) {
+                    slot.set(None);
+                    buffer.decrement_attached_counter();
+                }
+            }
+        }
         self.base.DeleteBuffer(buffer)
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
     fn DeleteFramebuffer(&self, framebuffer: Option<&WebGLFramebuffer>) {
+        if let Some(framebuffer) = framebuffer {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(framebuffer), return);
+            if self.bound_read_framebuffer.get().map_or(false, |bound| bound.id() == framebuffer.id()) {
+                self.bound_read_framebuffer.set(None);
+                self.base.send_command(WebGLCommand::BindFramebuffer(
+                    constants::READ_FRAMEBUFFER,
+                    WebGLFramebufferBindingRequest::Default,
+                ));
+            }
+        }
         self.base.DeleteFramebuffer(framebuffer)
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
     fn DeleteRenderbuffer(&self, renderbuffer: Option<&WebGLRenderbuffer>) {
+        if let Some(renderbuffer) = renderbuffer {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(renderbuffer), return);
+            if let Some(framebuffer) = self.bound_read_framebuffer.get() {
+                framebuffer.detach_renderbuffer(renderbuffer);
+            }
+        }
         self.base.DeleteRenderbuffer(renderbuffer)
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
     fn DeleteTexture(&self, texture: Option<&WebGLTexture>) {
+        if let Some(texture) = texture {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(texture), return);
+            if let Some(framebuffer) = self.bound_read_framebuffer.get() {
+                framebuffer.detach_texture(texture);
+            }
+        }
         self.base.DeleteTexture(texture)
     }
 
@@ -408,11 +1108,18 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
     fn DrawArrays(&self, mode: u32, first: i32, count: i32) {
+        if !self.validate_transform_feedback_draw_mode(mode) {
+            return;
+        }
         self.base.DrawArrays(mode, first, count)
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
     fn DrawElements(&self, mode: u32, count: i32, type_: u32, offset: i64) {
+        // Only drawArrays can capture transform feedback.
+        if self.is_transform_feedback_active_and_unpaused() {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.DrawElements(mode, count, type_, offset)
     }
 
@@ -550,6 +1257,14 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.12
     fn ReadPixels(&self, x: i32, y: i32, width: i32, height: i32, format: u32, pixel_type: u32,
                   pixels: CustomAutoRooterGuard<Option<ArrayBufferView>>) {
+        // Reading into client data can't happen while a PIXEL_PACK_BUFFER is
+        // bound.
+        if self.bound_pixel_pack_buffer.get().is_some() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if !self.validate_read_framebuffer() {
+            return;
+        }
         self.base.ReadPixels(x, y, width, height, format, pixel_type, pixels)
     }
 
@@ -595,6 +1310,12 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
     fn LinkProgram(&self, program: &WebGLProgram) {
+        // The program capturing transform feedback can't be relinked.
+        if self.is_transform_feedback_active() &&
+            self.transform_feedback_program.get().map_or(false, |tf_program| tf_program.id() == program.id())
+        {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.LinkProgram(program)
     }
 
@@ -779,6 +1500,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
     fn UseProgram(&self, program: Option<&WebGLProgram>) {
+        if self.is_transform_feedback_active_and_unpaused() {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.UseProgram(program)
     }
 
@@ -849,6 +1573,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
                   format: u32,
                   data_type: u32,
                   pixels: CustomAutoRooterGuard<Option<ArrayBufferView>>) -> Fallible<()> {
+        if self.is_pixel_unpack_buffer_bound() {
+            return Ok(self.base.webgl_error(InvalidOperation));
+        }
         self.base.TexImage2D(target, level, internal_format, width, height, border, format, data_type, pixels)
     }
 
@@ -862,6 +1589,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         data_type: u32,
         source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
     ) -> ErrorResult {
+        if self.is_pixel_unpack_buffer_bound() {
+            return Ok(self.base.webgl_error(InvalidOperation));
+        }
         self.base.TexImage2D_(target, level, internal_format, format, data_type, source)
     }
 
@@ -889,6 +1619,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
                      format: u32,
                      data_type: u32,
                      pixels: CustomAutoRooterGuard<Option<ArrayBufferView>>) -> Fallible<()> {
+        if self.is_pixel_unpack_buffer_bound() {
+            return Ok(self.base.webgl_error(InvalidOperation));
+        }
         self.base.TexSubImage2D(target, level, xoffset, yoffset, width, height, format, data_type, pixels)
     }
 
@@ -903,6 +1636,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         data_type: u32,
         source: ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement,
     ) -> ErrorResult {
+        if self.is_pixel_unpack_buffer_bound() {
+            return Ok(self.base.webgl_error(InvalidOperation));
+        }
         self.base.TexSubImage2D_(target, level, xoffset, yoffset, format, data_type, source)
     }
 
@@ -918,7 +1654,10 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
     fn CheckFramebufferStatus(&self, target: u32) -> u32 {
-        self.base.CheckFramebufferStatus(target)
+        match handle_potential_webgl_error!(self.base, self.bound_framebuffer(target), return 0) {
+            Some(framebuffer) => framebuffer.check_status(),
+            None => constants::FRAMEBUFFER_COMPLETE,
+        }
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
@@ -931,14 +1670,34 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     fn FramebufferRenderbuffer(&self, target: u32, attachment: u32,
                                renderbuffertarget: u32,
                                rb: Option<&WebGLRenderbuffer>) {
-        self.base.FramebufferRenderbuffer(target, attachment, renderbuffertarget, rb)
+        if let Some(rb) = rb {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(rb), return);
+        }
+
+        if renderbuffertarget != constants::RENDERBUFFER {
+            return self.base.webgl_error(InvalidEnum);
+        }
+
+        match handle_potential_webgl_error!(self.base, self.bound_framebuffer(target), return) {
+            Some(fb) => handle_potential_webgl_error!(self.base, fb.renderbuffer(target, attachment, rb)),
+            None => self.base.webgl_error(InvalidOperation),
+        };
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
     fn FramebufferTexture2D(&self, target: u32, attachment: u32,
                             textarget: u32, texture: Option<&WebGLTexture>,
                             level: i32) {
-        self.base.FramebufferTexture2D(target, attachment, textarget, texture, level)
+        if let Some(texture) = texture {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(texture), return);
+        }
+
+        match handle_potential_webgl_error!(self.base, self.bound_framebuffer(target), return) {
+            Some(fb) => {
+                handle_potential_webgl_error!(self.base, fb.texture2d(target, attachment, textarget, texture, level))
+            }
+            None => self.base.webgl_error(InvalidOperation),
+        };
     }
 
     /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
@@ -957,6 +1716,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         count: i32,
         primcount: i32,
     ) {
+        if !self.validate_transform_feedback_draw_mode(mode) {
+            return;
+        }
         self.base.draw_arrays_instanced(mode, first, count, primcount);
     }
 
@@ -969,6 +1731,9 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
         offset: i64,
         primcount: i32,
     ) {
+        if self.is_transform_feedback_active_and_unpaused() {
+            return self.base.webgl_error(InvalidOperation);
+        }
         self.base.draw_elements_instanced(mode, count, type_, offset, primcount);
     }
 
@@ -976,6 +1741,1122 @@ impl WebGL2RenderingContextMethods for WebGL2RenderingContext {
     fn VertexAttribDivisor(&self, index: u32, divisor: u32) {
         self.base.vertex_attrib_divisor(index, divisor);
     }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.3
+    fn BufferData__(
+        &self,
+        target: u32,
+        data: CustomAutoRooterGuard<ArrayBufferView>,
+        usage: u32,
+        elem_offset: u32,
+        length: u32,
+    ) {
+        let bound_buffer = handle_potential_webgl_error!(self.base, self.bound_buffer(target), return);
+        let bound_buffer = match bound_buffer {
+            Some(bound_buffer) => bound_buffer,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        let data = handle_potential_webgl_error!(
+            self.base,
+            self.array_buffer_view_data(&data, elem_offset, length),
+            return
+        );
+        handle_potential_webgl_error!(self.base, bound_buffer.buffer_data(target, data, usage));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.3
+    fn BufferSubData_(
+        &self,
+        target: u32,
+        dst_byte_offset: i64,
+        src_data: CustomAutoRooterGuard<ArrayBufferView>,
+        src_elem_offset: u32,
+        length: u32,
+    ) {
+        let data = handle_potential_webgl_error!(
+            self.base,
+            self.array_buffer_view_data(&src_data, src_elem_offset, length),
+            return
+        );
+        self.base.buffer_sub_data(target, dst_byte_offset, data.to_vec(), self.bound_buffer(target))
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.3
+    fn CopyBufferSubData(
+        &self,
+        read_target: u32,
+        write_target: u32,
+        read_offset: i64,
+        write_offset: i64,
+        size: i64,
+    ) {
+        let read_buffer = handle_potential_webgl_error!(self.base, self.bound_buffer(read_target), return);
+        let write_buffer = handle_potential_webgl_error!(self.base, self.bound_buffer(write_target), return);
+        let (read_buffer, write_buffer) = match (read_buffer, write_buffer) {
+            (Some(read_buffer), Some(write_buffer)) => (read_buffer, write_buffer),
+            _ => return self.base.webgl_error(InvalidOperation),
+        };
+
+        if read_offset < 0 || write_offset < 0 || size < 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let (read_offset, write_offset, size) = (read_offset as u64, write_offset as u64, size as u64);
+        if read_offset + size > read_buffer.capacity() as u64 ||
+            write_offset + size > write_buffer.capacity() as u64
+        {
+            return self.base.webgl_error(InvalidValue);
+        }
+        // The source and destination ranges of a buffer can't overlap.
+        if read_buffer.id() == write_buffer.id() &&
+            read_offset < write_offset + size &&
+            write_offset < read_offset + size
+        {
+            return self.base.webgl_error(InvalidValue);
+        }
+        // Element array buffers can't be copied to or from other buffers.
+        // https://www.khronos.org/registry/webgl/specs/latest/2.0/#5.1
+        let is_element_array = |buffer: &WebGLBuffer| buffer.target() == Some(constants::ELEMENT_ARRAY_BUFFER);
+        if is_element_array(&read_buffer) != is_element_array(&write_buffer) {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        self.base.send_command(WebGLCommand::CopyBufferSubData(
+            read_target,
+            write_target,
+            read_offset as i64,
+            write_offset as i64,
+            size as i64,
+        ));
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.3
+    fn GetBufferSubData(
+        &self,
+        target: u32,
+        src_byte_offset: i64,
+        mut dst_buffer: CustomAutoRooterGuard<ArrayBufferView>,
+        dst_elem_offset: u32,
+        length: u32,
+    ) {
+        let bound_buffer = handle_potential_webgl_error!(self.base, self.bound_buffer(target), return);
+        let bound_buffer = match bound_buffer {
+            Some(bound_buffer) => bound_buffer,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        if target == constants::TRANSFORM_FEEDBACK_BUFFER && self.is_transform_feedback_active() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if src_byte_offset < 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+
+        let element_size = array_element_size(dst_buffer.get_array_type()) as usize;
+        // The typed array is rooted and we should have a unique reference to it,
+        // so retrieving its mutable slice is safe here
+        let dst = unsafe { dst_buffer.as_mut_slice() };
+        let dst_len = dst.len() / element_size;
+        let dst_offset = dst_elem_offset as usize;
+        if dst_offset > dst_len {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let length = match length {
+            0 => dst_len - dst_offset,
+            length => length as usize,
+        };
+        if dst_offset + length > dst_len {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let byte_length = length * element_size;
+        if src_byte_offset as u64 + byte_length as u64 > bound_buffer.capacity() as u64 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        if byte_length == 0 {
+            return;
+        }
+
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.base.send_command(WebGLCommand::GetBufferSubData(
+            target,
+            src_byte_offset as usize,
+            byte_length,
+            sender,
+        ));
+        let data = receiver.recv().unwrap();
+        let dst_byte_offset = dst_offset * element_size;
+        dst[dst_byte_offset..dst_byte_offset + byte_length].copy_from_slice(&data);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.4
+    fn BlitFramebuffer(
+        &self,
+        src_x0: i32,
+        src_y0: i32,
+        src_x1: i32,
+        src_y1: i32,
+        dst_x0: i32,
+        dst_y0: i32,
+        dst_x1: i32,
+        dst_y1: i32,
+        mask: u32,
+        filter: u32,
+    ) {
+        let depth_stencil_mask = constants::DEPTH_BUFFER_BIT | constants::STENCIL_BUFFER_BIT;
+        if mask & !(constants::COLOR_BUFFER_BIT | depth_stencil_mask) != 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        match filter {
+            constants::NEAREST => {},
+            // Depth and stencil values can't be interpolated.
+            constants::LINEAR if mask & depth_stencil_mask == 0 => {},
+            constants::LINEAR => return self.base.webgl_error(InvalidOperation),
+            _ => return self.base.webgl_error(InvalidEnum),
+        }
+
+        let read_framebuffer = self.bound_read_framebuffer.get();
+        let draw_framebuffer = self.base.bound_framebuffer();
+        if read_framebuffer.as_ref().map_or(false, |fb| fb.check_status() != constants::FRAMEBUFFER_COMPLETE) {
+            return self.base.webgl_error(InvalidFramebufferOperation);
+        }
+        if !self.base.validate_framebuffer_complete() {
+            return;
+        }
+        // Blitting within the same framebuffer would read and write the same
+        // images.
+        if read_framebuffer.as_ref().map(|fb| fb.id()) == draw_framebuffer.as_ref().map(|fb| fb.id()) {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if mask & depth_stencil_mask != 0 {
+            // The depth and stencil formats of the default framebuffer aren't
+            // known here, so only framebuffer objects can be compared.
+            let formats_match = match (&read_framebuffer, &draw_framebuffer) {
+                (&Some(ref read), &Some(ref draw)) => depth_stencil_formats(read) == depth_stencil_formats(draw),
+                _ => false,
+            };
+            if !formats_match {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+
+        self.base.send_command(WebGLCommand::BlitFramebuffer(
+            src_x0,
+            src_y0,
+            src_x1,
+            src_y1,
+            dst_x0,
+            dst_y0,
+            dst_x1,
+            dst_y1,
+            mask,
+            filter,
+        ));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.4
+    fn FramebufferTextureLayer(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture: Option<&WebGLTexture>,
+        level: i32,
+        layer: i32,
+    ) {
+        if let Some(texture) = texture {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(texture), return);
+        }
+
+        let framebuffer = match handle_potential_webgl_error!(self.base, self.bound_framebuffer(target), return) {
+            Some(framebuffer) => framebuffer,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+
+        if let Some(texture) = texture {
+            let limits = match texture.target() {
+                Some(constants::TEXTURE_3D) => {
+                    Some((self.texture_limits.max_3d_texture_size, self.texture_limits.max_3d_texture_size))
+                }
+                Some(constants::TEXTURE_2D_ARRAY) => {
+                    Some((self.base.limits().max_tex_size, self.texture_limits.max_array_texture_layers))
+                }
+                _ => None,
+            };
+            if let Some((max_size, max_layers)) = limits {
+                let max_level = 31 - max_size.leading_zeros();
+                if layer >= max_layers as i32 || level > max_level as i32 {
+                    return self.base.webgl_error(InvalidValue);
+                }
+            }
+        }
+
+        handle_potential_webgl_error!(
+            self.base,
+            framebuffer.texture_layer(target, attachment, texture, level, layer)
+        );
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.4
+    fn InvalidateFramebuffer(&self, target: u32, attachments: Vec<u32>) {
+        let attachments = handle_potential_webgl_error!(
+            self.base,
+            self.validate_invalidation_attachments(target, attachments),
+            return
+        );
+        self.base.send_command(WebGLCommand::InvalidateFramebuffer(target, attachments));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.4
+    fn InvalidateSubFramebuffer(
+        &self,
+        target: u32,
+        attachments: Vec<u32>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) {
+        let attachments = handle_potential_webgl_error!(
+            self.base,
+            self.validate_invalidation_attachments(target, attachments),
+            return
+        );
+        if width < 0 || height < 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        self.base.send_command(WebGLCommand::InvalidateSubFramebuffer(target, attachments, x, y, width, height));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.11
+    fn DrawBuffers(&self, buffers: Vec<u32>) {
+        self.base.draw_buffers(buffers)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.4
+    fn ReadBuffer(&self, src: u32) {
+        let is_color_attachment = src >= constants::COLOR_ATTACHMENT0 && src <= constants::COLOR_ATTACHMENT15;
+        if src != constants::BACK && src != constants::NONE && !is_color_attachment {
+            return self.base.webgl_error(InvalidEnum);
+        }
+
+        match self.bound_read_framebuffer.get() {
+            None => {
+                // The default framebuffer only has a back buffer to read from.
+                if is_color_attachment {
+                    return self.base.webgl_error(InvalidOperation);
+                }
+                self.default_read_buffer.set(src);
+                // It's actually a framebuffer object, whose color image is
+                // the back buffer.
+                let src = if src == constants::BACK { constants::COLOR_ATTACHMENT0 } else { constants::NONE };
+                self.base.send_command(WebGLCommand::ReadBuffer(src));
+            }
+            Some(framebuffer) => {
+                if src == constants::BACK ||
+                    (is_color_attachment && src - constants::COLOR_ATTACHMENT0 >= self.max_color_attachments)
+                {
+                    return self.base.webgl_error(InvalidOperation);
+                }
+                framebuffer.set_read_buffer(src);
+                self.base.send_command(WebGLCommand::ReadBuffer(src));
+            }
+        }
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.5
+    unsafe fn GetInternalformatParameter(
+        &self,
+        cx: *mut JSContext,
+        target: u32,
+        internal_format: u32,
+        pname: u32,
+    ) -> JSVal {
+        if target != constants::RENDERBUFFER || pname != constants::SAMPLES {
+            self.base.webgl_error(InvalidEnum);
+            return NullValue();
+        }
+
+        let samples = match internal_format {
+            // Multisampling is not supported for integer formats.
+            constants::R8UI |
+            constants::R8I |
+            constants::R16UI |
+            constants::R16I |
+            constants::R32UI |
+            constants::R32I |
+            constants::RG8UI |
+            constants::RG8I |
+            constants::RG16UI |
+            constants::RG16I |
+            constants::RG32UI |
+            constants::RG32I |
+            constants::RGBA8UI |
+            constants::RGBA8I |
+            constants::RGB10_A2UI |
+            constants::RGBA16UI |
+            constants::RGBA16I |
+            constants::RGBA32I |
+            constants::RGBA32UI => vec![],
+            constants::R8 |
+            constants::RG8 |
+            constants::RGB8 |
+            constants::RGB565 |
+            constants::RGBA8 |
+            constants::SRGB8_ALPHA8 |
+            constants::RGB5_A1 |
+            constants::RGBA4 |
+            constants::RGB10_A2 |
+            constants::DEPTH_COMPONENT16 |
+            constants::DEPTH_COMPONENT24 |
+            constants::DEPTH_COMPONENT32F |
+            constants::DEPTH24_STENCIL8 |
+            constants::DEPTH32F_STENCIL8 |
+            constants::STENCIL_INDEX8 => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.base.send_command(WebGLCommand::GetInternalFormatSamples(target, internal_format, sender));
+                receiver.recv().unwrap()
+            }
+            _ => {
+                self.base.webgl_error(InvalidEnum);
+                return NullValue();
+            }
+        };
+
+        rooted!(in(cx) let mut rval = ptr::null_mut::<JSObject>());
+        let _ = Int32Array::create(cx, CreateWith::Slice(&samples), rval.handle_mut()).unwrap();
+        ObjectValue(rval.get())
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.5
+    fn RenderbufferStorageMultisample(
+        &self,
+        target: u32,
+        samples: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+    ) {
+        self.base.renderbuffer_storage(target, samples, internal_format, width, height)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexStorage2D(&self, target: u32, levels: i32, internal_format: u32, width: i32, height: i32) {
+        let validator = TexStorageValidator::new(
+            &self.base,
+            self.texture_limits,
+            2,
+            target,
+            levels,
+            internal_format,
+            width,
+            height,
+            1,
+        );
+        let result = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return, // NB: The validator sets the correct error for us.
+        };
+        handle_potential_webgl_error!(
+            self.base,
+            result.texture.storage(
+                result.target,
+                result.levels,
+                result.internal_format,
+                result.width,
+                result.height,
+                result.depth,
+            )
+        );
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexStorage3D(
+        &self,
+        target: u32,
+        levels: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+    ) {
+        let validator = TexStorageValidator::new(
+            &self.base,
+            self.texture_limits,
+            3,
+            target,
+            levels,
+            internal_format,
+            width,
+            height,
+            depth,
+        );
+        let result = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return, // NB: The validator sets the correct error for us.
+        };
+        handle_potential_webgl_error!(
+            self.base,
+            result.texture.storage(
+                result.target,
+                result.levels,
+                result.internal_format,
+                result.width,
+                result.height,
+                result.depth,
+            )
+        );
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexImage3D(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        src_data: CustomAutoRooterGuard<Option<ArrayBufferView>>,
+    ) {
+        let validator = TexImage3DValidator::new(
+            &self.base,
+            self.texture_limits,
+            target,
+            level,
+            Some(internal_format as u32),
+            width,
+            height,
+            depth,
+            border,
+            format,
+            data_type,
+        );
+        let validated = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return, // NB: The validator sets the correct error for us.
+        };
+        let data = handle_potential_webgl_error!(
+            self.base,
+            self.tex_image_3d_data(&validated, (*src_data).as_ref(), 0),
+            return
+        );
+        self.tex_image_3d(validated, data)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexImage3D_(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        src_data: CustomAutoRooterGuard<ArrayBufferView>,
+        src_offset: u32,
+    ) {
+        let validator = TexImage3DValidator::new(
+            &self.base,
+            self.texture_limits,
+            target,
+            level,
+            Some(internal_format as u32),
+            width,
+            height,
+            depth,
+            border,
+            format,
+            data_type,
+        );
+        let validated = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return, // NB: The validator sets the correct error for us.
+        };
+        let data = handle_potential_webgl_error!(
+            self.base,
+            self.tex_image_3d_data(&validated, Some(&*src_data), src_offset),
+            return
+        );
+        self.tex_image_3d(validated, data)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexSubImage3D(
+        &self,
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pbo_offset: i64,
+    ) {
+        // This overload reads the pixels from the bound PIXEL_UNPACK_BUFFER.
+        let buffer = match self.bound_pixel_unpack_buffer.get() {
+            Some(buffer) => buffer,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        let validated = match self.validate_tex_sub_image_3d(
+            target,
+            level,
+            (xoffset, yoffset, zoffset),
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+        ) {
+            Some(validated) => validated,
+            None => return,
+        };
+        if pbo_offset < 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let byte_length = handle_potential_webgl_error!(self.base, self.tex_image_3d_byte_length(&validated), return);
+        if pbo_offset as u64 + byte_length as u64 > buffer.capacity() as u64 {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        let alignment = self.base.texture_unpacking_alignment();
+        self.base.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, alignment as i32));
+        self.base.send_command(WebGLCommand::TexSubImage3DPbo {
+            target: target,
+            level: level,
+            xoffset: xoffset,
+            yoffset: yoffset,
+            zoffset: zoffset,
+            width: width,
+            height: height,
+            depth: depth,
+            format: format,
+            data_type: data_type,
+            offset: pbo_offset,
+        });
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn TexSubImage3D_(
+        &self,
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        src_data: CustomAutoRooterGuard<Option<ArrayBufferView>>,
+        src_offset: u32,
+    ) {
+        let validated = match self.validate_tex_sub_image_3d(
+            target,
+            level,
+            (xoffset, yoffset, zoffset),
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+        ) {
+            Some(validated) => validated,
+            None => return,
+        };
+        let src_data = match *src_data {
+            Some(ref src_data) => src_data,
+            None => return self.base.webgl_error(InvalidValue),
+        };
+        let data = handle_potential_webgl_error!(
+            self.base,
+            self.tex_image_3d_data(&validated, Some(src_data), src_offset),
+            return
+        );
+
+        let alignment = self.base.texture_unpacking_alignment();
+        self.base.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, alignment as i32));
+        self.base.send_command(WebGLCommand::TexSubImage3D {
+            target: target,
+            level: level,
+            xoffset: xoffset,
+            yoffset: yoffset,
+            zoffset: zoffset,
+            width: width,
+            height: height,
+            depth: depth,
+            format: format,
+            data_type: data_type,
+            data: data.into(),
+        });
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.6
+    fn CopyTexSubImage3D(
+        &self,
+        target: u32,
+        level: i32,
+        xoffset: i32,
+        yoffset: i32,
+        zoffset: i32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) {
+        let texture_target = match TexImageTarget::from_gl_constant(target) {
+            Some(texture_target) if texture_target.is_3d() => texture_target,
+            _ => return self.base.webgl_error(InvalidEnum),
+        };
+        let texture = match self.base.bound_texture_for_target(&texture_target) {
+            Some(texture) => texture,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        if level < 0 || width < 0 || height < 0 {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let image_info = texture.image_info_for_target(&texture_target, level as u32);
+        let internal_format = match image_info.sized_internal_format() {
+            Some(internal_format) => internal_format,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        // Depth and integer images can't be copied from the color buffers
+        // that readBuffer can select.
+        if internal_format.is_depth() || internal_format.format_and_data_types().0.is_integer() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(
+            self.base,
+            self.validate_tex_sub_image_3d_region(
+                &texture,
+                &texture_target,
+                level as u32,
+                (xoffset, yoffset, zoffset),
+                (width as u32, height as u32, 1),
+            ),
+            return
+        );
+        if !self.validate_read_framebuffer() {
+            return;
+        }
+
+        self.base.send_command(WebGLCommand::CopyTexSubImage3D(
+            target,
+            level,
+            xoffset,
+            yoffset,
+            zoffset,
+            x,
+            y,
+            width,
+            height,
+        ));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn CreateQuery(&self) -> Option<DomRoot<WebGLQuery>> {
+        WebGLQuery::maybe_new(&self.base)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn DeleteQuery(&self, query: Option<&WebGLQuery>) {
+        if let Some(query) = query {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(query), return);
+            // An active query is ended before being deleted.
+            for slot in [&self.occlusion_query, &self.primitives_query].iter() {
+                if slot.get().map_or(false, |active| active.id() == query.id()) {
+                    if let Some(target) = query.target() {
+                        self.base.send_command(WebGLCommand::EndQuery(target));
+                    }
+                    slot.set(None);
+                }
+            }
+            query.delete()
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn IsQuery(&self, query: Option<&WebGLQuery>) -> bool {
+        match query {
+            Some(query) => {
+                self.base.validate_ownership(query).is_ok() && !query.is_deleted() && query.target().is_some()
+            }
+            None => false,
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn BeginQuery(&self, target: u32, query: &WebGLQuery) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(query), return);
+        let slot = handle_potential_webgl_error!(self.base, self.query_slot(target), return);
+        if slot.get().is_some() || self.is_query_active(query) {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(self.base, query.begin(target), return);
+        slot.set(Some(query));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn EndQuery(&self, target: u32) {
+        let slot = handle_potential_webgl_error!(self.base, self.query_slot(target), return);
+        if !slot.get().map_or(false, |query| query.target() == Some(target)) {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        slot.set(None);
+        self.base.send_command(WebGLCommand::EndQuery(target));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    fn GetQuery(&self, target: u32, pname: u32) -> Option<DomRoot<WebGLQuery>> {
+        let slot = handle_potential_webgl_error!(self.base, self.query_slot(target), return None);
+        if pname != constants::CURRENT_QUERY {
+            self.base.webgl_error(InvalidEnum);
+            return None;
+        }
+        slot.get().filter(|query| query.target() == Some(target))
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.12
+    unsafe fn GetQueryParameter(&self, _cx: *mut JSContext, query: &WebGLQuery, pname: u32) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(query), return NullValue());
+        if self.is_query_active(query) {
+            self.base.webgl_error(InvalidOperation);
+            return NullValue();
+        }
+        let value = handle_potential_webgl_error!(self.base, query.get_parameter(pname), return NullValue());
+        match pname {
+            constants::QUERY_RESULT_AVAILABLE => BooleanValue(value != 0),
+            _ => UInt32Value(value),
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn CreateSampler(&self) -> Option<DomRoot<WebGLSampler>> {
+        WebGLSampler::maybe_new(&self.base)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn DeleteSampler(&self, sampler: Option<&WebGLSampler>) {
+        if let Some(sampler) = sampler {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+            // A deleted sampler is unbound from all the texture units it was
+            // bound to.
+            for slot in self.samplers.iter() {
+                if slot.get().map_or(false, |bound| bound.id() == sampler.id()) {
+                    slot.set(None);
+                }
+            }
+            sampler.delete()
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn IsSampler(&self, sampler: Option<&WebGLSampler>) -> bool {
+        match sampler {
+            Some(sampler) => self.base.validate_ownership(sampler).is_ok() && !sampler.is_deleted(),
+            None => false,
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn BindSampler(&self, unit: u32, sampler: Option<&WebGLSampler>) {
+        if let Some(sampler) = sampler {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+            if sampler.is_deleted() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+        let slot = match self.samplers.get(unit as usize) {
+            Some(slot) => slot,
+            None => return self.base.webgl_error(InvalidValue),
+        };
+        slot.set(sampler);
+        self.base.send_command(WebGLCommand::BindSampler(unit, sampler.map(|sampler| sampler.id())));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn SamplerParameteri(&self, sampler: &WebGLSampler, pname: u32, param: i32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+        if sampler.is_deleted() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(self.base, sampler.set_parameter(pname, TexParameterValue::Int(param)));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    fn SamplerParameterf(&self, sampler: &WebGLSampler, pname: u32, param: f32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return);
+        if sampler.is_deleted() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        handle_potential_webgl_error!(self.base, sampler.set_parameter(pname, TexParameterValue::Float(param)));
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.13
+    unsafe fn GetSamplerParameter(&self, _cx: *mut JSContext, sampler: &WebGLSampler, pname: u32) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sampler), return NullValue());
+        if sampler.is_deleted() {
+            self.base.webgl_error(InvalidOperation);
+            return NullValue();
+        }
+        match handle_potential_webgl_error!(self.base, sampler.get_parameter(pname), return NullValue()) {
+            TexParameterValue::Int(value) => Int32Value(value),
+            TexParameterValue::Float(value) => DoubleValue(value as f64),
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn FenceSync(&self, condition: u32, flags: u32) -> Option<DomRoot<WebGLSync>> {
+        if condition != constants::SYNC_GPU_COMMANDS_COMPLETE {
+            self.base.webgl_error(InvalidEnum);
+            return None;
+        }
+        if flags != 0 {
+            self.base.webgl_error(InvalidValue);
+            return None;
+        }
+        WebGLSync::maybe_new(&self.base)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn IsSync(&self, sync: Option<&WebGLSync>) -> bool {
+        match sync {
+            Some(sync) => self.base.validate_ownership(sync).is_ok() && !sync.is_deleted(),
+            None => false,
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn DeleteSync(&self, sync: Option<&WebGLSync>) {
+        if let Some(sync) = sync {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return);
+            sync.delete()
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn ClientWaitSync(&self, sync: &WebGLSync, flags: u32, timeout: u64) -> u32 {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return constants::WAIT_FAILED);
+        if sync.is_deleted() {
+            self.base.webgl_error(InvalidOperation);
+            return constants::WAIT_FAILED;
+        }
+        if flags & !constants::SYNC_FLUSH_COMMANDS_BIT != 0 {
+            self.base.webgl_error(InvalidValue);
+            return constants::WAIT_FAILED;
+        }
+        if timeout > MAX_CLIENT_WAIT_TIMEOUT_WEBGL {
+            self.base.webgl_error(InvalidOperation);
+            return constants::WAIT_FAILED;
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.base.send_command(WebGLCommand::ClientWaitSync(sync.id(), flags, timeout, sender));
+        receiver.recv().unwrap()
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    fn WaitSync(&self, sync: &WebGLSync, flags: u32, timeout: i64) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return);
+        if sync.is_deleted() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        if flags != 0 || timeout != constants::TIMEOUT_IGNORED {
+            return self.base.webgl_error(InvalidValue);
+        }
+        self.base.send_command(WebGLCommand::WaitSync(sync.id()));
+    }
+
+    #[allow(unsafe_code)]
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.14
+    unsafe fn GetSyncParameter(&self, _cx: *mut JSContext, sync: &WebGLSync, pname: u32) -> JSVal {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(sync), return NullValue());
+        if sync.is_deleted() {
+            self.base.webgl_error(InvalidOperation);
+            return NullValue();
+        }
+        match pname {
+            constants::OBJECT_TYPE => UInt32Value(constants::SYNC_FENCE),
+            constants::SYNC_STATUS => {
+                UInt32Value(if sync.is_signaled() { constants::SIGNALED } else { constants::UNSIGNALED })
+            }
+            constants::SYNC_CONDITION => UInt32Value(constants::SYNC_GPU_COMMANDS_COMPLETE),
+            constants::SYNC_FLAGS => UInt32Value(0),
+            _ => {
+                self.base.webgl_error(InvalidEnum);
+                NullValue()
+            }
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn CreateTransformFeedback(&self) -> Option<DomRoot<WebGLTransformFeedback>> {
+        WebGLTransformFeedback::maybe_new(&self.base, self.max_transform_feedback_separate_attribs)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn DeleteTransformFeedback(&self, tf: Option<&WebGLTransformFeedback>) {
+        if let Some(tf) = tf {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(tf), return);
+            if self.bound_transform_feedback.get().map_or(false, |bound| bound.id() == tf.id()) {
+                // The active transform feedback object can't be deleted.
+                if self.is_transform_feedback_active() {
+                    return self.base.webgl_error(InvalidOperation);
+                }
+                self.bound_transform_feedback.set(None);
+                self.base.send_command(WebGLCommand::BindTransformFeedback(constants::TRANSFORM_FEEDBACK, None));
+            }
+            if !tf.is_deleted() {
+                for slot in tf.buffers() {
+                    if let Some(buffer) = slot.get() {
+                        slot.set(None);
+                        buffer.decrement_attached_counter();
+                    }
+                }
+            }
+            tf.delete()
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn IsTransformFeedback(&self, tf: Option<&WebGLTransformFeedback>) -> bool {
+        match tf {
+            Some(tf) => self.base.validate_ownership(tf).is_ok() && tf.ever_bound() && !tf.is_deleted(),
+            None => false,
+        }
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn BindTransformFeedback(&self, target: u32, tf: Option<&WebGLTransformFeedback>) {
+        if target != constants::TRANSFORM_FEEDBACK {
+            return self.base.webgl_error(InvalidEnum);
+        }
+        if let Some(tf) = tf {
+            handle_potential_webgl_error!(self.base, self.base.validate_ownership(tf), return);
+            if tf.is_deleted() {
+                return self.base.webgl_error(InvalidOperation);
+            }
+        }
+        // GLES 3.0 allows switching to another transform feedback object
+        // while the active one is paused, but we don't keep track of the
+        // state of each object, so we don't.
+        if self.is_transform_feedback_active() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        match tf {
+            Some(tf) => tf.bind(target),
+            None => self.base.send_command(WebGLCommand::BindTransformFeedback(target, None)),
+        }
+        self.bound_transform_feedback.set(tf);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn BeginTransformFeedback(&self, primitive_mode: u32) {
+        match primitive_mode {
+            constants::POINTS | constants::LINES | constants::TRIANGLES => {},
+            _ => return self.base.webgl_error(InvalidEnum),
+        }
+        if self.is_transform_feedback_active() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        let program = match self.base.current_program() {
+            Some(program) => program,
+            None => return self.base.webgl_error(InvalidOperation),
+        };
+        let (varying_count, buffer_mode) = program.transform_feedback();
+        if varying_count == 0 {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        // Interleaved varyings are all captured to the first buffer, and
+        // separate ones each to their own.
+        let buffer_count = match buffer_mode {
+            constants::INTERLEAVED_ATTRIBS => 1,
+            _ => varying_count as usize,
+        };
+        let has_buffers = self.with_transform_feedback_buffers(|buffers| {
+            buffers.len() >= buffer_count && buffers[..buffer_count].iter().all(|slot| slot.get().is_some())
+        });
+        if !has_buffers {
+            return self.base.webgl_error(InvalidOperation);
+        }
+
+        self.base.send_command(WebGLCommand::BeginTransformFeedback(primitive_mode));
+        self.transform_feedback_mode.set(Some(primitive_mode));
+        self.transform_feedback_paused.set(false);
+        self.transform_feedback_program.set(Some(&program));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn EndTransformFeedback(&self) {
+        if !self.is_transform_feedback_active() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        self.base.send_command(WebGLCommand::EndTransformFeedback);
+        self.transform_feedback_mode.set(None);
+        self.transform_feedback_paused.set(false);
+        self.transform_feedback_program.set(None);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn TransformFeedbackVaryings(&self, program: &WebGLProgram, varyings: Vec<DOMString>, buffer_mode: u32) {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return);
+        if buffer_mode == constants::SEPARATE_ATTRIBS &&
+            varyings.len() as u32 > self.max_transform_feedback_separate_attribs
+        {
+            return self.base.webgl_error(InvalidValue);
+        }
+        let varyings = varyings.into_iter().map(String::from).collect();
+        handle_potential_webgl_error!(self.base, program.transform_feedback_varyings(varyings, buffer_mode));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn GetTransformFeedbackVarying(&self, program: &WebGLProgram, index: u32) -> Option<DomRoot<WebGLActiveInfo>> {
+        handle_potential_webgl_error!(self.base, self.base.validate_ownership(program), return None);
+        handle_potential_webgl_error!(self.base, program.get_transform_feedback_varying(index).map(Some), None)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn PauseTransformFeedback(&self) {
+        if !self.is_transform_feedback_active() || self.transform_feedback_paused.get() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        self.base.send_command(WebGLCommand::PauseTransformFeedback);
+        self.transform_feedback_paused.set(true);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.15
+    fn ResumeTransformFeedback(&self) {
+        if !self.is_transform_feedback_active() || !self.transform_feedback_paused.get() {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        // Transform feedback can only be resumed with the program it was
+        // begun with.
+        let program_id = self.base.current_program().map(|program| program.id());
+        if program_id != self.transform_feedback_program.get().map(|program| program.id()) {
+            return self.base.webgl_error(InvalidOperation);
+        }
+        self.base.send_command(WebGLCommand::ResumeTransformFeedback);
+        self.transform_feedback_paused.set(false);
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn BindBufferBase(&self, target: u32, index: u32, buffer: Option<&WebGLBuffer>) {
+        self.bind_buffer_indexed(target, index, buffer, None)
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.7.16
+    fn BindBufferRange(&self, target: u32, index: u32, buffer: Option<&WebGLBuffer>, offset: i64, size: i64) {
+        self.bind_buffer_indexed(target, index, buffer, Some((offset, size)))
+    }
 }
 
 
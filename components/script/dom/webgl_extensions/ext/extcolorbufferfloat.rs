@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::WebGLVersion;
+use dom::bindings::codegen::Bindings::EXTColorBufferFloatBinding;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as webgl2;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct EXTColorBufferFloat {
+    reflector_: Reflector,
+}
+
+impl EXTColorBufferFloat {
+    fn new_inherited() -> EXTColorBufferFloat {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for EXTColorBufferFloat {
+    type Extension = EXTColorBufferFloat;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<EXTColorBufferFloat> {
+        reflect_dom_object(Box::new(EXTColorBufferFloat::new_inherited()),
+                           &*ctx.global(),
+                           EXTColorBufferFloatBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::Specific(WebGLVersion::WebGL2)
+    }
+
+    fn is_supported(ext: &WebGLExtensions) -> bool {
+        ext.supports_any_gl_extension(&["GL_EXT_color_buffer_float",
+                                        "GL_ARB_color_buffer_float"])
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        for format in &[webgl2::R16F, webgl2::RG16F, webgl2::RGBA16F,
+                        webgl2::R32F, webgl2::RG32F, webgl2::RGBA32F,
+                        webgl2::R11F_G11F_B10F] {
+            ext.enable_renderbuffer_format(*format);
+        }
+    }
+
+    fn name() -> &'static str {
+        "EXT_color_buffer_float"
+    }
+}
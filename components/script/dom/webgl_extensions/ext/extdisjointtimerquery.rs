@@ -0,0 +1,154 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::{WebGLCommand, WebGLError, WebGLVersion};
+use dom::bindings::codegen::Bindings::EXTDisjointTimerQueryBinding::{self, EXTDisjointTimerQueryMethods};
+use dom::bindings::codegen::Bindings::EXTDisjointTimerQueryBinding::EXTDisjointTimerQueryConstants as constants;
+use dom::bindings::conversions::ToJSValConvertible;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webgltimerqueryext::{TimerQueryResult, WebGLTimerQueryEXT};
+use dom_struct::dom_struct;
+use js::jsapi::JSContext;
+use js::jsval::{BooleanValue, DoubleValue, Int32Value, JSVal, NullValue};
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+// gleam doesn't expose glGetQueryiv, but both the desktop and the ES timer
+// queries are backed by 64-bit counters.
+const QUERY_COUNTER_BITS: i32 = 64;
+
+#[dom_struct]
+pub struct EXTDisjointTimerQuery {
+    reflector_: Reflector,
+    ctx: Dom<WebGLRenderingContext>,
+    /// The TIME_ELAPSED_EXT query between beginQueryEXT and endQueryEXT.
+    active_query: MutNullableDom<WebGLTimerQueryEXT>,
+}
+
+impl EXTDisjointTimerQuery {
+    fn new_inherited(ctx: &WebGLRenderingContext) -> EXTDisjointTimerQuery {
+        Self {
+            reflector_: Reflector::new(),
+            ctx: Dom::from_ref(ctx),
+            active_query: MutNullableDom::new(None),
+        }
+    }
+}
+
+impl EXTDisjointTimerQueryMethods for EXTDisjointTimerQuery {
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    fn CreateQueryEXT(&self) -> Option<DomRoot<WebGLTimerQueryEXT>> {
+        WebGLTimerQueryEXT::maybe_new(&self.ctx)
+    }
+
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    fn DeleteQueryEXT(&self, query: Option<&WebGLTimerQueryEXT>) {
+        if let Some(query) = query {
+            if self.active_query.get().map_or(false, |active| active.id() == query.id()) {
+                self.ctx.send_command(WebGLCommand::EndQuery(constants::TIME_ELAPSED_EXT));
+                self.active_query.set(None);
+            }
+            query.delete();
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    fn IsQueryEXT(&self, query: Option<&WebGLTimerQueryEXT>) -> bool {
+        query.map_or(false, |query| !query.is_deleted() && query.target().is_some())
+    }
+
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    fn BeginQueryEXT(&self, target: u32, query: &WebGLTimerQueryEXT) {
+        if target != constants::TIME_ELAPSED_EXT {
+            return self.ctx.webgl_error(WebGLError::InvalidEnum);
+        }
+        if self.active_query.get().is_some() {
+            return self.ctx.webgl_error(WebGLError::InvalidOperation);
+        }
+        handle_potential_webgl_error!(self.ctx, query.begin(target), return);
+        self.active_query.set(Some(query));
+    }
+
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    fn EndQueryEXT(&self, target: u32) {
+        if target != constants::TIME_ELAPSED_EXT {
+            return self.ctx.webgl_error(WebGLError::InvalidEnum);
+        }
+        if self.active_query.get().is_none() {
+            return self.ctx.webgl_error(WebGLError::InvalidOperation);
+        }
+        self.ctx.send_command(WebGLCommand::EndQuery(target));
+        self.active_query.set(None);
+    }
+
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    fn QueryCounterEXT(&self, query: &WebGLTimerQueryEXT, target: u32) {
+        if target != constants::TIMESTAMP_EXT {
+            return self.ctx.webgl_error(WebGLError::InvalidEnum);
+        }
+        handle_potential_webgl_error!(self.ctx, query.query_counter(target));
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    unsafe fn GetQueryEXT(&self, cx: *mut JSContext, target: u32, pname: u32) -> JSVal {
+        match (target, pname) {
+            (constants::TIME_ELAPSED_EXT, constants::CURRENT_QUERY_EXT) => {
+                optional_root_object_to_js_or_null!(cx, self.active_query.get())
+            }
+            (constants::TIMESTAMP_EXT, constants::CURRENT_QUERY_EXT) => {
+                NullValue()
+            }
+            (constants::TIME_ELAPSED_EXT, constants::QUERY_COUNTER_BITS_EXT) |
+            (constants::TIMESTAMP_EXT, constants::QUERY_COUNTER_BITS_EXT) => {
+                Int32Value(QUERY_COUNTER_BITS)
+            }
+            _ => {
+                self.ctx.webgl_error(WebGLError::InvalidEnum);
+                NullValue()
+            }
+        }
+    }
+
+    #[allow(unsafe_code)]
+    // https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+    unsafe fn GetQueryObjectEXT(&self, _cx: *mut JSContext, query: &WebGLTimerQueryEXT, pname: u32) -> JSVal {
+        if self.active_query.get().map_or(false, |active| active.id() == query.id()) {
+            self.ctx.webgl_error(WebGLError::InvalidOperation);
+            return NullValue();
+        }
+        match handle_potential_webgl_error!(self.ctx, query.get_parameter(pname), return NullValue()) {
+            TimerQueryResult::Available(available) => BooleanValue(available),
+            TimerQueryResult::Time(time) => DoubleValue(time as f64),
+        }
+    }
+}
+
+impl WebGLExtension for EXTDisjointTimerQuery {
+    type Extension = EXTDisjointTimerQuery;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<EXTDisjointTimerQuery> {
+        reflect_dom_object(Box::new(EXTDisjointTimerQuery::new_inherited(ctx)),
+                           &*ctx.global(),
+                           EXTDisjointTimerQueryBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::Specific(WebGLVersion::WebGL1)
+    }
+
+    fn is_supported(ext: &WebGLExtensions) -> bool {
+        ext.supports_any_gl_extension(&["GL_EXT_disjoint_timer_query",
+                                        "GL_ARB_timer_query"])
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        ext.enable_get_parameter_name(constants::TIMESTAMP_EXT);
+        ext.enable_get_parameter_name(constants::GPU_DISJOINT_EXT);
+    }
+
+    fn name() -> &'static str {
+        "EXT_disjoint_timer_query"
+    }
+}
@@ -0,0 +1,53 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::WebGLVersion;
+use dom::bindings::codegen::Bindings::EXTsRGBBinding::{self, EXTsRGBConstants};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct EXTsRGB {
+    reflector_: Reflector,
+}
+
+impl EXTsRGB {
+    fn new_inherited() -> EXTsRGB {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for EXTsRGB {
+    type Extension = EXTsRGB;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<EXTsRGB> {
+        reflect_dom_object(Box::new(EXTsRGB::new_inherited()),
+                           &*ctx.global(),
+                           EXTsRGBBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::Specific(WebGLVersion::WebGL1)
+    }
+
+    fn is_supported(ext: &WebGLExtensions) -> bool {
+        ext.supports_any_gl_extension(&["GL_EXT_sRGB",
+                                        "GL_EXT_texture_sRGB",
+                                        "GL_ARB_framebuffer_sRGB"])
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        ext.enable_tex_format(EXTsRGBConstants::SRGB_EXT);
+        ext.enable_tex_format(EXTsRGBConstants::SRGB_ALPHA_EXT);
+        ext.enable_renderbuffer_format(EXTsRGBConstants::SRGB8_ALPHA8_EXT);
+    }
+
+    fn name() -> &'static str {
+        "EXT_sRGB"
+    }
+}
@@ -7,7 +7,10 @@ use super::{ext_constants, WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
 
 pub mod angleinstancedarrays;
 pub mod extblendminmax;
+pub mod extcolorbufferfloat;
+pub mod extdisjointtimerquery;
 pub mod extshadertexturelod;
+pub mod extsrgb;
 pub mod exttexturefilteranisotropic;
 pub mod oeselementindexuint;
 pub mod oesstandardderivatives;
@@ -16,3 +19,10 @@ pub mod oestexturefloatlinear;
 pub mod oestexturehalffloat;
 pub mod oestexturehalffloatlinear;
 pub mod oesvertexarrayobject;
+pub mod webglcompressedtextureetc;
+pub mod webglcompressedtextures3tc;
+pub mod webgldebugrendererinfo;
+pub mod webgldebugshaders;
+pub mod webgldepthtexture;
+pub mod webgldrawbuffers;
+pub mod webgllosecontext;
@@ -0,0 +1,62 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::WEBGLCompressedTextureETCBinding;
+use dom::bindings::codegen::Bindings::WEBGLCompressedTextureETCBinding::WEBGLCompressedTextureETCConstants as etc;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct WEBGLCompressedTextureETC {
+    reflector_: Reflector,
+}
+
+impl WEBGLCompressedTextureETC {
+    fn new_inherited() -> WEBGLCompressedTextureETC {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for WEBGLCompressedTextureETC {
+    type Extension = WEBGLCompressedTextureETC;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<WEBGLCompressedTextureETC> {
+        reflect_dom_object(Box::new(WEBGLCompressedTextureETC::new_inherited()),
+                           &*ctx.global(),
+                           WEBGLCompressedTextureETCBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::All
+    }
+
+    fn is_supported(ext: &WebGLExtensions) -> bool {
+        // ETC2 and EAC are core in OpenGL ES 3.0 and OpenGL 4.3.
+        ext.supports_any_gl_extension(&["GL_ARB_ES3_compatibility",
+                                        "GL_OES_compressed_ETC2_RGB8_texture"])
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        ext.add_tex_compression_formats(&[
+            etc::COMPRESSED_R11_EAC,
+            etc::COMPRESSED_SIGNED_R11_EAC,
+            etc::COMPRESSED_RG11_EAC,
+            etc::COMPRESSED_SIGNED_RG11_EAC,
+            etc::COMPRESSED_RGB8_ETC2,
+            etc::COMPRESSED_SRGB8_ETC2,
+            etc::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            etc::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            etc::COMPRESSED_RGBA8_ETC2_EAC,
+            etc::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        ]);
+    }
+
+    fn name() -> &'static str {
+        "WEBGL_compressed_texture_etc"
+    }
+}
@@ -0,0 +1,50 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::WEBGLDebugRendererInfoBinding::{self, WEBGLDebugRendererInfoConstants};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct WEBGLDebugRendererInfo {
+    reflector_: Reflector,
+}
+
+impl WEBGLDebugRendererInfo {
+    fn new_inherited() -> WEBGLDebugRendererInfo {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for WEBGLDebugRendererInfo {
+    type Extension = WEBGLDebugRendererInfo;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<WEBGLDebugRendererInfo> {
+        reflect_dom_object(Box::new(WEBGLDebugRendererInfo::new_inherited()),
+                           &*ctx.global(),
+                           WEBGLDebugRendererInfoBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::All
+    }
+
+    fn is_supported(_ext: &WebGLExtensions) -> bool {
+        // The strings are read when the GL context is created.
+        true
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        ext.enable_get_parameter_name(WEBGLDebugRendererInfoConstants::UNMASKED_VENDOR_WEBGL);
+        ext.enable_get_parameter_name(WEBGLDebugRendererInfoConstants::UNMASKED_RENDERER_WEBGL);
+    }
+
+    fn name() -> &'static str {
+        "WEBGL_debug_renderer_info"
+    }
+}
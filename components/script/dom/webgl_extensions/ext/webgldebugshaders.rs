@@ -0,0 +1,59 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::WEBGLDebugShadersBinding::{self, WEBGLDebugShadersMethods};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webglshader::WebGLShader;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct WEBGLDebugShaders {
+    reflector_: Reflector,
+    ctx: Dom<WebGLRenderingContext>,
+}
+
+impl WEBGLDebugShaders {
+    fn new_inherited(ctx: &WebGLRenderingContext) -> WEBGLDebugShaders {
+        Self {
+            reflector_: Reflector::new(),
+            ctx: Dom::from_ref(ctx),
+        }
+    }
+}
+
+impl WEBGLDebugShadersMethods for WEBGLDebugShaders {
+    // https://www.khronos.org/registry/webgl/extensions/WEBGL_debug_shaders/
+    fn GetTranslatedShaderSource(&self, shader: &WebGLShader) -> DOMString {
+        handle_potential_webgl_error!(self.ctx, self.ctx.validate_ownership(shader), return DOMString::new());
+        shader.translated_source()
+    }
+}
+
+impl WebGLExtension for WEBGLDebugShaders {
+    type Extension = WEBGLDebugShaders;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<WEBGLDebugShaders> {
+        reflect_dom_object(Box::new(WEBGLDebugShaders::new_inherited(ctx)),
+                           &*ctx.global(),
+                           WEBGLDebugShadersBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::All
+    }
+
+    fn is_supported(_ext: &WebGLExtensions) -> bool {
+        // Shaders are always translated by ANGLE in the script thread.
+        true
+    }
+
+    fn enable(_ext: &WebGLExtensions) {}
+
+    fn name() -> &'static str {
+        "WEBGL_debug_shaders"
+    }
+}
@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::WebGLVersion;
+use dom::bindings::codegen::Bindings::WEBGLDepthTextureBinding::{self, WEBGLDepthTextureConstants};
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{constants as webgl, WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct WEBGLDepthTexture {
+    reflector_: Reflector,
+}
+
+impl WEBGLDepthTexture {
+    fn new_inherited() -> WEBGLDepthTexture {
+        Self {
+            reflector_: Reflector::new(),
+        }
+    }
+}
+
+impl WebGLExtension for WEBGLDepthTexture {
+    type Extension = WEBGLDepthTexture;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<WEBGLDepthTexture> {
+        reflect_dom_object(Box::new(WEBGLDepthTexture::new_inherited()),
+                           &*ctx.global(),
+                           WEBGLDepthTextureBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::Specific(WebGLVersion::WebGL1)
+    }
+
+    fn is_supported(ext: &WebGLExtensions) -> bool {
+        ext.supports_any_gl_extension(&["GL_OES_depth_texture",
+                                        "GL_ARB_depth_texture",
+                                        "GL_ANGLE_depth_texture"])
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        ext.enable_tex_format(webgl::DEPTH_COMPONENT);
+        ext.enable_tex_format(webgl::DEPTH_STENCIL);
+        ext.enable_tex_type(webgl::UNSIGNED_SHORT);
+        ext.enable_tex_type(webgl::UNSIGNED_INT);
+        ext.enable_tex_type(WEBGLDepthTextureConstants::UNSIGNED_INT_24_8_WEBGL);
+    }
+
+    fn name() -> &'static str {
+        "WEBGL_depth_texture"
+    }
+}
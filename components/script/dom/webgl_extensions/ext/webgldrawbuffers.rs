@@ -0,0 +1,66 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::WebGLVersion;
+use dom::bindings::codegen::Bindings::WEBGLDrawBuffersBinding::{self, WEBGLDrawBuffersMethods};
+use dom::bindings::codegen::Bindings::WEBGLDrawBuffersBinding::WEBGLDrawBuffersConstants;
+use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use super::{WebGLExtension, WebGLExtensions, WebGLExtensionSpec};
+
+#[dom_struct]
+pub struct WEBGLDrawBuffers {
+    reflector_: Reflector,
+    ctx: Dom<WebGLRenderingContext>,
+}
+
+impl WEBGLDrawBuffers {
+    fn new_inherited(ctx: &WebGLRenderingContext) -> WEBGLDrawBuffers {
+        Self {
+            reflector_: Reflector::new(),
+            ctx: Dom::from_ref(ctx),
+        }
+    }
+}
+
+impl WEBGLDrawBuffersMethods for WEBGLDrawBuffers {
+    // https://www.khronos.org/registry/webgl/extensions/WEBGL_draw_buffers/
+    fn DrawBuffersWEBGL(&self, buffers: Vec<u32>) {
+        self.ctx.draw_buffers(buffers);
+    }
+}
+
+impl WebGLExtension for WEBGLDrawBuffers {
+    type Extension = WEBGLDrawBuffers;
+    fn new(ctx: &WebGLRenderingContext) -> DomRoot<WEBGLDrawBuffers> {
+        reflect_dom_object(Box::new(WEBGLDrawBuffers::new_inherited(ctx)),
+                           &*ctx.global(),
+                           WEBGLDrawBuffersBinding::Wrap)
+    }
+
+    fn spec() -> WebGLExtensionSpec {
+        WebGLExtensionSpec::Specific(WebGLVersion::WebGL1)
+    }
+
+    fn is_supported(ext: &WebGLExtensions) -> bool {
+        let capabilities = ext.capabilities();
+        capabilities.max_draw_buffers > 1 &&
+        capabilities.max_color_attachments >= capabilities.max_draw_buffers &&
+        ext.supports_any_gl_extension(&["GL_EXT_draw_buffers",
+                                        "GL_ARB_draw_buffers",
+                                        "GL_NV_draw_buffers"])
+    }
+
+    fn enable(ext: &WebGLExtensions) {
+        ext.enable_draw_buffers();
+        ext.enable_get_parameter_name(WEBGLDrawBuffersConstants::MAX_COLOR_ATTACHMENTS_WEBGL);
+        ext.enable_get_parameter_name(WEBGLDrawBuffersConstants::MAX_DRAW_BUFFERS_WEBGL);
+    }
+
+    fn name() -> &'static str {
+        "WEBGL_draw_buffers"
+    }
+}
@@ -2,15 +2,22 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use canvas_traits::webgl::WebGLVersion;
+use canvas_traits::webgl::{GLContextCapabilities, WebGLVersion};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::ANGLEInstancedArraysBinding::ANGLEInstancedArraysConstants;
+use dom::bindings::codegen::Bindings::EXTDisjointTimerQueryBinding::EXTDisjointTimerQueryConstants;
 use dom::bindings::codegen::Bindings::EXTTextureFilterAnisotropicBinding::EXTTextureFilterAnisotropicConstants;
+use dom::bindings::codegen::Bindings::EXTsRGBBinding::EXTsRGBConstants;
 use dom::bindings::codegen::Bindings::OESStandardDerivativesBinding::OESStandardDerivativesConstants;
 use dom::bindings::codegen::Bindings::OESTextureHalfFloatBinding::OESTextureHalfFloatConstants;
 use dom::bindings::codegen::Bindings::OESVertexArrayObjectBinding::OESVertexArrayObjectConstants;
+use dom::bindings::codegen::Bindings::WEBGLDebugRendererInfoBinding::WEBGLDebugRendererInfoConstants;
+use dom::bindings::codegen::Bindings::WEBGLDepthTextureBinding::WEBGLDepthTextureConstants;
+use dom::bindings::codegen::Bindings::WEBGLDrawBuffersBinding::WEBGLDrawBuffersConstants;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::trace::JSTraceable;
+use dom::webgl_validations::types::{TexCompression, TexFormat};
 use dom::webglrenderingcontext::WebGLRenderingContext;
 use fnv::{FnvHashMap, FnvHashSet};
 use gleam::gl::GLenum;
@@ -25,8 +32,22 @@ use super::wrapper::{WebGLExtensionWrapper, TypedWebGLExtensionWrapper};
 // Data types that are implemented for texImage2D and texSubImage2D in a WebGL 1.0 context
 // but must trigger a InvalidValue error until the related WebGL Extensions are enabled.
 // Example: https://www.khronos.org/registry/webgl/extensions/OES_texture_float/
-const DEFAULT_DISABLED_TEX_TYPES_WEBGL1: [GLenum; 2] = [
-    constants::FLOAT, OESTextureHalfFloatConstants::HALF_FLOAT_OES
+const DEFAULT_DISABLED_TEX_TYPES_WEBGL1: [GLenum; 5] = [
+    constants::FLOAT, OESTextureHalfFloatConstants::HALF_FLOAT_OES,
+    constants::UNSIGNED_SHORT, constants::UNSIGNED_INT, WEBGLDepthTextureConstants::UNSIGNED_INT_24_8_WEBGL,
+];
+
+// Texture formats that are implemented for texImage2D and texSubImage2D in a WebGL 1.0 context
+// but must trigger a InvalidEnum error until the related WebGL Extensions are enabled.
+// Example: https://www.khronos.org/registry/webgl/extensions/WEBGL_depth_texture/
+const DEFAULT_DISABLED_TEX_FORMATS_WEBGL1: [GLenum; 4] = [
+    constants::DEPTH_COMPONENT, constants::DEPTH_STENCIL,
+    EXTsRGBConstants::SRGB_EXT, EXTsRGBConstants::SRGB_ALPHA_EXT,
+];
+
+// The unsized sRGB formats only exist in WebGL 1.0 through EXT_sRGB.
+const DEFAULT_DISABLED_TEX_FORMATS_WEBGL2: [GLenum; 2] = [
+    EXTsRGBConstants::SRGB_EXT, EXTsRGBConstants::SRGB_ALPHA_EXT,
 ];
 
 // Data types that are implemented for textures in WebGLRenderingContext
@@ -39,10 +60,56 @@ const DEFAULT_NOT_FILTERABLE_TEX_TYPES: [GLenum; 2] = [
 // Param names that are implemented for glGetParameter in a WebGL 1.0 context
 // but must trigger a InvalidEnum error until the related WebGL Extensions are enabled.
 // Example: https://www.khronos.org/registry/webgl/extensions/OES_standard_derivatives/
-const DEFAULT_DISABLED_GET_PARAMETER_NAMES_WEBGL1: [GLenum; 3] = [
+const DEFAULT_DISABLED_GET_PARAMETER_NAMES_WEBGL1: [GLenum; 6] = [
+    EXTDisjointTimerQueryConstants::GPU_DISJOINT_EXT,
+    EXTDisjointTimerQueryConstants::TIMESTAMP_EXT,
     EXTTextureFilterAnisotropicConstants::MAX_TEXTURE_MAX_ANISOTROPY_EXT,
     OESStandardDerivativesConstants::FRAGMENT_SHADER_DERIVATIVE_HINT_OES,
     OESVertexArrayObjectConstants::VERTEX_ARRAY_BINDING_OES,
+    WEBGLDrawBuffersConstants::MAX_DRAW_BUFFERS_WEBGL,
+];
+
+// Param names that are implemented for glGetParameter in both WebGL 1.0 and 2.0 contexts
+// but must trigger a InvalidEnum error until the related WebGL Extensions are enabled.
+// Example: https://www.khronos.org/registry/webgl/extensions/WEBGL_debug_renderer_info/
+const DEFAULT_DISABLED_GET_PARAMETER_NAMES: [GLenum; 2] = [
+    WEBGLDebugRendererInfoConstants::UNMASKED_VENDOR_WEBGL,
+    WEBGLDebugRendererInfoConstants::UNMASKED_RENDERER_WEBGL,
+];
+
+// Param names that are only part of WebGL 2.0 and must trigger a InvalidEnum error
+// in a WebGL 1.0 context.
+const WEBGL2_ONLY_GET_PARAMETER_NAMES: [GLenum; 16] = [
+    WebGl2Constants::MAX_3D_TEXTURE_SIZE,
+    WebGl2Constants::MAX_ARRAY_TEXTURE_LAYERS,
+    WebGl2Constants::MAX_COLOR_ATTACHMENTS,
+    WebGl2Constants::MAX_SAMPLES,
+    WebGl2Constants::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS,
+    WebGl2Constants::MAX_UNIFORM_BUFFER_BINDINGS,
+    WebGl2Constants::UNIFORM_BUFFER_OFFSET_ALIGNMENT,
+    WebGl2Constants::PACK_ROW_LENGTH,
+    WebGl2Constants::PACK_SKIP_PIXELS,
+    WebGl2Constants::PACK_SKIP_ROWS,
+    WebGl2Constants::READ_BUFFER,
+    WebGl2Constants::UNPACK_IMAGE_HEIGHT,
+    WebGl2Constants::UNPACK_ROW_LENGTH,
+    WebGl2Constants::UNPACK_SKIP_IMAGES,
+    WebGl2Constants::UNPACK_SKIP_PIXELS,
+    WebGl2Constants::UNPACK_SKIP_ROWS,
+];
+
+// Param names that are only part of WebGL 2.0 and must trigger a InvalidEnum error
+// in a WebGL 1.0 context.
+const WEBGL2_ONLY_GET_TEX_PARAMETER_NAMES: [GLenum; 9] = [
+    WebGl2Constants::TEXTURE_BASE_LEVEL,
+    WebGl2Constants::TEXTURE_COMPARE_FUNC,
+    WebGl2Constants::TEXTURE_COMPARE_MODE,
+    WebGl2Constants::TEXTURE_IMMUTABLE_FORMAT,
+    WebGl2Constants::TEXTURE_IMMUTABLE_LEVELS,
+    WebGl2Constants::TEXTURE_MAX_LEVEL,
+    WebGl2Constants::TEXTURE_MAX_LOD,
+    WebGl2Constants::TEXTURE_MIN_LOD,
+    WebGl2Constants::TEXTURE_WRAP_R,
 ];
 
 // Param names that are implemented for glGetTexParameter in a WebGL 1.0 context
@@ -64,6 +131,12 @@ const DEFAULT_DISABLED_GET_VERTEX_ATTRIB_NAMES_WEBGL1: [GLenum; 1] = [
 struct WebGLExtensionFeatures {
     gl_extensions: FnvHashSet<String>,
     disabled_tex_types: FnvHashSet<GLenum>,
+    /// Texture formats that must be enabled by extensions.
+    disabled_tex_formats: FnvHashSet<GLenum>,
+    /// Compressed texture formats enabled by extensions.
+    tex_compression_formats: FnvHashSet<GLenum>,
+    /// Renderbuffer internal formats enabled by extensions.
+    renderbuffer_formats: FnvHashSet<GLenum>,
     not_filterable_tex_types: FnvHashSet<GLenum>,
     effective_tex_internal_formats: FnvHashMap<TexFormatType, u32>,
     /// WebGL Hint() targets enabled by extensions.
@@ -78,34 +151,49 @@ struct WebGLExtensionFeatures {
     element_index_uint_enabled: bool,
     /// WebGL EXT_blend_minmax extension.
     blend_minmax_enabled: bool,
+    /// WebGL WEBGL_draw_buffers extension.
+    draw_buffers_enabled: bool,
 }
 
 impl WebGLExtensionFeatures {
     fn new(webgl_version: WebGLVersion) -> Self {
         let (
             disabled_tex_types,
+            disabled_tex_formats,
             disabled_get_parameter_names,
             disabled_get_tex_parameter_names,
             disabled_get_vertex_attrib_names,
             element_index_uint_enabled,
             blend_minmax_enabled,
+            draw_buffers_enabled,
         ) = match webgl_version {
             WebGLVersion::WebGL1 => {
                 (
                     DEFAULT_DISABLED_TEX_TYPES_WEBGL1.iter().cloned().collect(),
-                    DEFAULT_DISABLED_GET_PARAMETER_NAMES_WEBGL1.iter().cloned().collect(),
-                    DEFAULT_DISABLED_GET_TEX_PARAMETER_NAMES_WEBGL1.iter().cloned().collect(),
+                    DEFAULT_DISABLED_TEX_FORMATS_WEBGL1.iter().cloned().collect(),
+                    DEFAULT_DISABLED_GET_PARAMETER_NAMES_WEBGL1.iter()
+                        .chain(DEFAULT_DISABLED_GET_PARAMETER_NAMES.iter())
+                        .chain(WEBGL2_ONLY_GET_PARAMETER_NAMES.iter())
+                        .cloned()
+                        .collect(),
+                    DEFAULT_DISABLED_GET_TEX_PARAMETER_NAMES_WEBGL1.iter()
+                        .chain(WEBGL2_ONLY_GET_TEX_PARAMETER_NAMES.iter())
+                        .cloned()
+                        .collect(),
                     DEFAULT_DISABLED_GET_VERTEX_ATTRIB_NAMES_WEBGL1.iter().cloned().collect(),
                     false,
                     false,
+                    false,
                 )
             },
             WebGLVersion::WebGL2 => {
                 (
                     Default::default(),
+                    DEFAULT_DISABLED_TEX_FORMATS_WEBGL2.iter().cloned().collect(),
+                    DEFAULT_DISABLED_GET_PARAMETER_NAMES.iter().cloned().collect(),
                     Default::default(),
                     Default::default(),
-                    Default::default(),
+                    true,
                     true,
                     true,
                 )
@@ -114,6 +202,9 @@ impl WebGLExtensionFeatures {
         Self {
             gl_extensions: Default::default(),
             disabled_tex_types,
+            disabled_tex_formats,
+            tex_compression_formats: Default::default(),
+            renderbuffer_formats: Default::default(),
             not_filterable_tex_types: DEFAULT_NOT_FILTERABLE_TEX_TYPES.iter().cloned().collect(),
             effective_tex_internal_formats: Default::default(),
             hint_targets: Default::default(),
@@ -122,6 +213,7 @@ impl WebGLExtensionFeatures {
             disabled_get_vertex_attrib_names,
             element_index_uint_enabled,
             blend_minmax_enabled,
+            draw_buffers_enabled,
         }
     }
 }
@@ -133,17 +225,28 @@ pub struct WebGLExtensions {
     extensions: DomRefCell<HashMap<String, Box<WebGLExtensionWrapper>>>,
     features: DomRefCell<WebGLExtensionFeatures>,
     webgl_version: WebGLVersion,
+    capabilities: GLContextCapabilities,
 }
 
 impl WebGLExtensions {
-    pub fn new(webgl_version: WebGLVersion) -> WebGLExtensions {
+    pub fn new(webgl_version: WebGLVersion, capabilities: GLContextCapabilities) -> WebGLExtensions {
         Self {
             extensions: DomRefCell::new(HashMap::new()),
             features: DomRefCell::new(WebGLExtensionFeatures::new(webgl_version)),
             webgl_version,
+            capabilities,
         }
     }
 
+    pub fn webgl_version(&self) -> WebGLVersion {
+        self.webgl_version
+    }
+
+    /// The implementation values reported by the GL context backing this WebGL context.
+    pub fn capabilities(&self) -> &GLContextCapabilities {
+        &self.capabilities
+    }
+
     pub fn init_once<F>(&self, cb: F) where F: FnOnce() -> String {
         if self.extensions.borrow().len() == 0 {
             let gl_str = cb();
@@ -158,16 +261,19 @@ impl WebGLExtensions {
         self.extensions.borrow_mut().insert(name, Box::new(TypedWebGLExtensionWrapper::<T>::new()));
     }
 
+    /// Whether an extension is specified for this WebGL version and supported by the GL context.
+    fn is_available(&self, extension: &WebGLExtensionWrapper) -> bool {
+        if let WebGLExtensionSpec::Specific(version) = extension.spec() {
+            if self.webgl_version != version {
+                return false;
+            }
+        }
+        extension.is_supported(&self)
+    }
+
     pub fn get_suported_extensions(&self) -> Vec<&'static str> {
         self.extensions.borrow().iter()
-                                .filter(|ref v| {
-                                    if let WebGLExtensionSpec::Specific(version) = v.1.spec() {
-                                        if self.webgl_version != version {
-                                            return false;
-                                        }
-                                    }
-                                    v.1.is_supported(&self)
-                                })
+                                .filter(|ref v| self.is_available(&**v.1))
                                 .map(|ref v| v.1.name())
                                 .collect()
     }
@@ -175,7 +281,7 @@ impl WebGLExtensions {
     pub fn get_or_init_extension(&self, name: &str, ctx: &WebGLRenderingContext) -> Option<NonNull<JSObject>> {
         let name = name.to_uppercase();
         self.extensions.borrow().get(&name).and_then(|extension| {
-            if extension.is_supported(self) {
+            if self.is_available(&**extension) {
                 Some(extension.instance_or_init(ctx, self))
             } else {
                 None
@@ -208,6 +314,38 @@ impl WebGLExtensions {
         self.features.borrow().disabled_tex_types.get(&data_type).is_none()
     }
 
+    pub fn enable_tex_format(&self, format: GLenum) {
+        self.features.borrow_mut().disabled_tex_formats.remove(&format);
+    }
+
+    pub fn is_tex_format_enabled(&self, format: GLenum) -> bool {
+        !self.features.borrow().disabled_tex_formats.contains(&format)
+    }
+
+    pub fn add_tex_compression_formats(&self, formats: &[GLenum]) {
+        self.features.borrow_mut().tex_compression_formats.extend(formats.iter().cloned());
+    }
+
+    /// Returns the block layout of `format` if it is an enabled compressed texture format.
+    pub fn get_tex_compression_format(&self, format: GLenum) -> Option<TexCompression> {
+        if !self.features.borrow().tex_compression_formats.contains(&format) {
+            return None;
+        }
+        TexFormat::from_gl_constant(format).and_then(|format| format.compression())
+    }
+
+    pub fn get_tex_compression_formats(&self) -> Vec<GLenum> {
+        self.features.borrow().tex_compression_formats.iter().cloned().collect()
+    }
+
+    pub fn enable_renderbuffer_format(&self, format: GLenum) {
+        self.features.borrow_mut().renderbuffer_formats.insert(format);
+    }
+
+    pub fn is_renderbuffer_format_enabled(&self, format: GLenum) -> bool {
+        self.features.borrow().renderbuffer_formats.contains(&format)
+    }
+
     pub fn add_effective_tex_internal_format(&self,
                                              source_internal_format: u32,
                                              source_data_type: u32,
@@ -270,7 +408,10 @@ impl WebGLExtensions {
     fn register_all_extensions(&self) {
         self.register::<ext::angleinstancedarrays::ANGLEInstancedArrays>();
         self.register::<ext::extblendminmax::EXTBlendMinmax>();
+        self.register::<ext::extcolorbufferfloat::EXTColorBufferFloat>();
+        self.register::<ext::extdisjointtimerquery::EXTDisjointTimerQuery>();
         self.register::<ext::extshadertexturelod::EXTShaderTextureLod>();
+        self.register::<ext::extsrgb::EXTsRGB>();
         self.register::<ext::exttexturefilteranisotropic::EXTTextureFilterAnisotropic>();
         self.register::<ext::oeselementindexuint::OESElementIndexUint>();
         self.register::<ext::oesstandardderivatives::OESStandardDerivatives>();
@@ -279,6 +420,13 @@ impl WebGLExtensions {
         self.register::<ext::oestexturehalffloat::OESTextureHalfFloat>();
         self.register::<ext::oestexturehalffloatlinear::OESTextureHalfFloatLinear>();
         self.register::<ext::oesvertexarrayobject::OESVertexArrayObject>();
+        self.register::<ext::webglcompressedtextureetc::WEBGLCompressedTextureETC>();
+        self.register::<ext::webglcompressedtextures3tc::WEBGLCompressedTextureS3TC>();
+        self.register::<ext::webgldebugrendererinfo::WEBGLDebugRendererInfo>();
+        self.register::<ext::webgldebugshaders::WEBGLDebugShaders>();
+        self.register::<ext::webgldepthtexture::WEBGLDepthTexture>();
+        self.register::<ext::webgldrawbuffers::WEBGLDrawBuffers>();
+        self.register::<ext::webgllosecontext::WEBGLLoseContext>();
     }
 
     pub fn enable_element_index_uint(&self) {
@@ -296,6 +444,16 @@ impl WebGLExtensions {
     pub fn is_blend_minmax_enabled(&self) -> bool {
         self.features.borrow().blend_minmax_enabled
     }
+
+    pub fn enable_draw_buffers(&self) {
+        self.features.borrow_mut().draw_buffers_enabled = true;
+    }
+
+    /// Whether more than one color attachment and draw buffer can be used,
+    /// which is always the case in WebGL 2.0.
+    pub fn is_draw_buffers_enabled(&self) -> bool {
+        self.features.borrow().draw_buffers_enabled
+    }
 }
 
 // Helper structs
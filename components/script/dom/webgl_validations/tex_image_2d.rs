@@ -3,12 +3,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use canvas_traits::webgl::WebGLError::*;
+use canvas_traits::webgl::WebGLVersion;
 use dom::bindings::root::DomRoot;
 use dom::webglrenderingcontext::WebGLRenderingContext;
 use dom::webgltexture::WebGLTexture;
 use std::{self, fmt};
 use super::WebGLValidator;
-use super::types::{TexImageTarget, TexDataType, TexFormat};
+use super::types::{TexCompression, TexCompressionValidation, TexImageTarget, TexDataType, TexFormat};
 
 /// The errors that the texImage* family of functions can generate.
 #[derive(Debug)]
@@ -40,6 +41,10 @@ pub enum TexImageValidationError {
     InvalidBorder,
     /// Expected a power of two texture.
     NonPotTexture,
+    /// The data length doesn't match the compressed format.
+    InvalidCompressedDataSize,
+    /// The region isn't aligned to the blocks of the compressed format.
+    InvalidCompressedRegion,
 }
 
 impl std::error::Error for TexImageValidationError {
@@ -72,6 +77,10 @@ impl std::error::Error for TexImageValidationError {
                 => "Invalid border",
             NonPotTexture
                 => "Expected a power of two texture",
+            InvalidCompressedDataSize
+                => "Invalid data length for the compressed format",
+            InvalidCompressedRegion
+                => "Invalid region for the compressed format",
         }
     }
 }
@@ -82,6 +91,13 @@ impl fmt::Display for TexImageValidationError {
     }
 }
 
+/// Returns whether `format` can be used by texImage2D in this context, which
+/// for some formats depends on the extensions that were enabled.
+fn is_format_enabled(context: &WebGLRenderingContext, format: TexFormat) -> bool {
+    format.is_webgl1_format() &&
+    context.extension_manager().is_tex_format_enabled(format.as_gl_constant())
+}
+
 fn log2(n: u32) -> u32 {
     31 - n.leading_zeros()
 }
@@ -115,8 +131,8 @@ impl<'a> WebGLValidator for CommonTexImage2DValidator<'a> {
         // GL_TEXTURE_CUBE_MAP_POSITIVE_Y, GL_TEXTURE_CUBE_MAP_NEGATIVE_Y,
         // GL_TEXTURE_CUBE_MAP_POSITIVE_Z, or GL_TEXTURE_CUBE_MAP_NEGATIVE_Z.
         let target = match TexImageTarget::from_gl_constant(self.target) {
-            Some(target) => target,
-            None => {
+            Some(target) if !target.is_3d() => target,
+            _ => {
                 self.context.webgl_error(InvalidEnum);
                 return Err(TexImageValidationError::InvalidTextureTarget(self.target));
             }
@@ -144,8 +160,8 @@ impl<'a> WebGLValidator for CommonTexImage2DValidator<'a> {
         // GL_INVALID_ENUM is generated if internal_format is not an accepted
         // format.
         let internal_format = match TexFormat::from_gl_constant(self.internal_format) {
-            Some(format) => format,
-            None => {
+            Some(format) if is_format_enabled(self.context, format) => format,
+            _ => {
                 self.context.webgl_error(InvalidEnum);
                 return Err(TexImageValidationError::InvalidTextureFormat);
             }
@@ -305,8 +321,8 @@ impl<'a> WebGLValidator for TexImage2DValidator<'a> {
         };
 
         let format = match TexFormat::from_gl_constant(self.format) {
-            Some(format) => format,
-            None => {
+            Some(format) if is_format_enabled(context, format) => format,
+            _ => {
                 context.webgl_error(InvalidEnum);
                 return Err(TexImageValidationError::InvalidTextureFormat);
             }
@@ -339,6 +355,31 @@ impl<'a> WebGLValidator for TexImage2DValidator<'a> {
             _ => {},
         }
 
+        // From the WEBGL_depth_texture spec:
+        //
+        //     "If the format is DEPTH_COMPONENT, the type must be
+        //      UNSIGNED_SHORT or UNSIGNED_INT. If the format is
+        //      DEPTH_STENCIL, the type must be UNSIGNED_INT_24_8_WEBGL.
+        //      Otherwise, INVALID_OPERATION is generated."
+        let is_depth_type_valid = match data_type {
+            TexDataType::UnsignedShort | TexDataType::UnsignedInt => format == TexFormat::DepthComponent,
+            TexDataType::UnsignedInt248 => format == TexFormat::DepthStencil,
+            _ => !format.is_depth(),
+        };
+        if !is_depth_type_valid {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidTypeForFormat);
+        }
+
+        //     "The target must be TEXTURE_2D and the level must be 0.
+        //      Otherwise, INVALID_OPERATION is generated."
+        if format.is_depth() && context.webgl_version() == WebGLVersion::WebGL1 &&
+            (target != TexImageTarget::Texture2D || level != 0)
+        {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidTypeForFormat);
+        }
+
         Ok(TexImage2DValidatorResult {
             width: width,
             height: height,
@@ -351,3 +392,199 @@ impl<'a> WebGLValidator for TexImage2DValidator<'a> {
         })
     }
 }
+
+pub struct CompressedTexImage2DValidator<'a> {
+    common_validator: CommonTexImage2DValidator<'a>,
+    format: u32,
+    data_len: usize,
+}
+
+impl<'a> CompressedTexImage2DValidator<'a> {
+    pub fn new(context: &'a WebGLRenderingContext,
+               target: u32,
+               level: i32,
+               width: i32,
+               height: i32,
+               border: i32,
+               format: u32,
+               data_len: usize) -> Self {
+        CompressedTexImage2DValidator {
+            // NB: We use a dummy (valid) format in order to reuse the common
+            // validations, the compressed format is checked separately.
+            common_validator: CommonTexImage2DValidator::new(context, target, level,
+                                                             TexFormat::RGBA.as_gl_constant(),
+                                                             width, height, border),
+            format: format,
+            data_len: data_len,
+        }
+    }
+}
+
+/// The validated result of a CompressedTexImage2DValidator-validated call.
+pub struct CompressedTexImage2DValidatorResult {
+    pub texture: DomRoot<WebGLTexture>,
+    pub target: TexImageTarget,
+    pub level: u32,
+    pub width: u32,
+    pub height: u32,
+    pub compression: TexCompression,
+}
+
+/// Returns the block layout of `format` if it was enabled by an extension.
+fn validate_compression_format(context: &WebGLRenderingContext, format: u32)
+                               -> Result<TexCompression, TexImageValidationError> {
+    match context.extension_manager().get_tex_compression_format(format) {
+        Some(compression) => Ok(compression),
+        None => {
+            context.webgl_error(InvalidEnum);
+            Err(TexImageValidationError::InvalidTextureFormat)
+        }
+    }
+}
+
+/// As per the compressed texture extensions, the data must hold exactly the
+/// blocks needed to cover the image.
+fn validate_compressed_data_size(context: &WebGLRenderingContext,
+                                 compression: &TexCompression,
+                                 width: u32,
+                                 height: u32,
+                                 data_len: usize) -> Result<(), TexImageValidationError> {
+    if compression.data_size(width, height) as usize != data_len {
+        context.webgl_error(InvalidValue);
+        return Err(TexImageValidationError::InvalidCompressedDataSize);
+    }
+    Ok(())
+}
+
+/// compressedTexImage2D validator as per
+/// <https://www.khronos.org/registry/webgl/specs/latest/1.0/#COMPRESSED_TEXTURE_SUPPORT>
+impl<'a> WebGLValidator for CompressedTexImage2DValidator<'a> {
+    type ValidatedOutput = CompressedTexImage2DValidatorResult;
+    type Error = TexImageValidationError;
+
+    fn validate(self) -> Result<Self::ValidatedOutput, TexImageValidationError> {
+        let context = self.common_validator.context;
+        let compression = validate_compression_format(context, self.format)?;
+        let CommonTexImage2DValidatorResult {
+            texture,
+            target,
+            level,
+            width,
+            height,
+            ..
+        } = self.common_validator.validate()?;
+
+        validate_compressed_data_size(context, &compression, width, height, self.data_len)?;
+
+        // From the WEBGL_compressed_texture_s3tc spec:
+        //
+        //     "If level is 0, width and height must each be a multiple of
+        //      4. If level is greater than 0, width and height must be 0,
+        //      1, 2 or a multiple of 4. If they are not an
+        //      INVALID_OPERATION error is generated."
+        if compression.validation == TexCompressionValidation::S3TC {
+            let is_valid = |size: u32| {
+                size % compression.block_width == 0 || (level > 0 && size <= 2)
+            };
+            if !is_valid(width) || !is_valid(height) {
+                context.webgl_error(InvalidOperation);
+                return Err(TexImageValidationError::InvalidCompressedRegion);
+            }
+        }
+
+        Ok(CompressedTexImage2DValidatorResult {
+            texture: texture,
+            target: target,
+            level: level,
+            width: width,
+            height: height,
+            compression: compression,
+        })
+    }
+}
+
+pub struct CompressedTexSubImage2DValidator<'a> {
+    compressed_validator: CompressedTexImage2DValidator<'a>,
+    xoffset: i32,
+    yoffset: i32,
+}
+
+impl<'a> CompressedTexSubImage2DValidator<'a> {
+    pub fn new(context: &'a WebGLRenderingContext,
+               target: u32,
+               level: i32,
+               xoffset: i32,
+               yoffset: i32,
+               width: i32,
+               height: i32,
+               format: u32,
+               data_len: usize) -> Self {
+        CompressedTexSubImage2DValidator {
+            compressed_validator: CompressedTexImage2DValidator::new(context, target, level,
+                                                                     width, height, 0,
+                                                                     format, data_len),
+            xoffset: xoffset,
+            yoffset: yoffset,
+        }
+    }
+}
+
+/// compressedTexSubImage2D validator as per
+/// <https://www.khronos.org/registry/webgl/specs/latest/1.0/#COMPRESSED_TEXTURE_SUPPORT>
+impl<'a> WebGLValidator for CompressedTexSubImage2DValidator<'a> {
+    type ValidatedOutput = CompressedTexImage2DValidatorResult;
+    type Error = TexImageValidationError;
+
+    fn validate(self) -> Result<Self::ValidatedOutput, TexImageValidationError> {
+        let context = self.compressed_validator.common_validator.context;
+        let compression = validate_compression_format(context, self.compressed_validator.format)?;
+        let data_len = self.compressed_validator.data_len;
+        let CommonTexImage2DValidatorResult {
+            texture,
+            target,
+            level,
+            width,
+            height,
+            ..
+        } = self.compressed_validator.common_validator.validate()?;
+
+        // GL_INVALID_OPERATION is generated if the image wasn't specified
+        // with the same compressed format.
+        let image_info = texture.image_info_for_target(&target, level);
+        if image_info.internal_format() != Some(compression.format) {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::TextureFormatMismatch);
+        }
+
+        // GL_INVALID_VALUE is generated if the region isn't inside the image.
+        if self.xoffset < 0 || self.yoffset < 0 ||
+            self.xoffset as u32 + width > image_info.width() ||
+            self.yoffset as u32 + height > image_info.height()
+        {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeDimension);
+        }
+
+        validate_compressed_data_size(context, &compression, width, height, data_len)?;
+
+        // The region must be aligned to blocks, unless it reaches the edge
+        // of the image, otherwise GL_INVALID_OPERATION is generated.
+        let (xoffset, yoffset) = (self.xoffset as u32, self.yoffset as u32);
+        if xoffset % compression.block_width != 0 || yoffset % compression.block_height != 0 ||
+            (width % compression.block_width != 0 && xoffset + width != image_info.width()) ||
+            (height % compression.block_height != 0 && yoffset + height != image_info.height())
+        {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidCompressedRegion);
+        }
+
+        Ok(CompressedTexImage2DValidatorResult {
+            texture: texture,
+            target: target,
+            level: level,
+            width: width,
+            height: height,
+            compression: compression,
+        })
+    }
+}
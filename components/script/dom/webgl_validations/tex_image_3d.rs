@@ -0,0 +1,383 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use canvas_traits::webgl::WebGLError::*;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::root::DomRoot;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webgltexture::WebGLTexture;
+use std::cmp;
+use super::WebGLValidator;
+use super::tex_image_2d::TexImageValidationError;
+use super::types::{TexFormat, TexImageTarget, TexInternalFormat, bytes_per_pixel};
+
+fn log2(n: u32) -> u32 {
+    31 - n.leading_zeros()
+}
+
+/// The implementation limits that apply to three-dimensional textures, which
+/// WebGL 1.0's `GLLimits` don't cover.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+pub struct TexImage3DLimits {
+    pub max_3d_texture_size: u32,
+    pub max_array_texture_layers: u32,
+}
+
+/// Checks the dimensions of a texture image against the implementation
+/// limits for the given target.
+fn validate_texture_size(
+    context: &WebGLRenderingContext,
+    limits: TexImage3DLimits,
+    target: TexImageTarget,
+    width: u32,
+    height: u32,
+    depth: u32,
+) -> bool {
+    let (max_size, max_depth) = match target {
+        TexImageTarget::Texture3D => (limits.max_3d_texture_size, limits.max_3d_texture_size),
+        TexImageTarget::Texture2DArray => (context.limits().max_tex_size, limits.max_array_texture_layers),
+        TexImageTarget::Texture2D => (context.limits().max_tex_size, 1),
+        _ => (context.limits().max_cube_map_tex_size, 1),
+    };
+    width <= max_size && height <= max_size && depth <= max_depth
+}
+
+pub struct TexStorageValidator<'a> {
+    context: &'a WebGLRenderingContext,
+    limits: TexImage3DLimits,
+    dimensions: u8,
+    target: u32,
+    levels: i32,
+    internal_format: u32,
+    width: i32,
+    height: i32,
+    depth: i32,
+}
+
+impl<'a> TexStorageValidator<'a> {
+    /// Creates a validator for texStorage2D when `dimensions` is 2, and for
+    /// texStorage3D when it's 3.
+    pub fn new(context: &'a WebGLRenderingContext,
+               limits: TexImage3DLimits,
+               dimensions: u8,
+               target: u32,
+               levels: i32,
+               internal_format: u32,
+               width: i32,
+               height: i32,
+               depth: i32) -> Self {
+        TexStorageValidator {
+            context: context,
+            limits: limits,
+            dimensions: dimensions,
+            target: target,
+            levels: levels,
+            internal_format: internal_format,
+            width: width,
+            height: height,
+            depth: depth,
+        }
+    }
+}
+
+pub struct TexStorageValidatorResult {
+    pub texture: DomRoot<WebGLTexture>,
+    pub target: u32,
+    pub levels: u32,
+    pub internal_format: TexInternalFormat,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+}
+
+/// texStorage2D and texStorage3D validator as per
+/// <https://www.khronos.org/registry/OpenGL/specs/es/3.0/es_spec_3.0.pdf#nameddest=subsection.3.8.4>
+impl<'a> WebGLValidator for TexStorageValidator<'a> {
+    type ValidatedOutput = TexStorageValidatorResult;
+    type Error = TexImageValidationError;
+
+    fn validate(self) -> Result<Self::ValidatedOutput, TexImageValidationError> {
+        let context = self.context;
+
+        // GL_INVALID_ENUM is generated if target is not one of the accepted
+        // targets for the number of dimensions.
+        let target = match (self.dimensions, TexImageTarget::from_gl_constant(self.target)) {
+            (2, Some(TexImageTarget::Texture2D)) => TexImageTarget::Texture2D,
+            // The cube map target isn't a TexImageTarget, all of its faces
+            // share the binding and size limits of the positive X one.
+            (2, _) if self.target == constants::TEXTURE_CUBE_MAP => TexImageTarget::CubeMapPositiveX,
+            (3, Some(target)) if target.is_3d() => target,
+            _ => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidTextureTarget(self.target));
+            }
+        };
+
+        // GL_INVALID_OPERATION is generated if the default texture object is
+        // bound to target.
+        let texture = match context.bound_texture_for_target(&target) {
+            Some(texture) => texture,
+            None => {
+                context.webgl_error(InvalidOperation);
+                return Err(TexImageValidationError::TextureTargetNotBound(self.target));
+            }
+        };
+
+        // GL_INVALID_ENUM is generated if internal_format is not a valid
+        // sized internal format.
+        let internal_format = match TexInternalFormat::from_gl_constant(self.internal_format) {
+            Some(format) if format.is_sized() => format,
+            _ => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidTextureFormat);
+            }
+        };
+
+        // GL_INVALID_VALUE is generated if width, height, depth or levels
+        // are less than 1.
+        if self.levels < 1 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeLevel);
+        }
+        if self.width < 1 || self.height < 1 || self.depth < 1 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeDimension);
+        }
+
+        let levels = self.levels as u32;
+        let width = self.width as u32;
+        let height = self.height as u32;
+        let depth = self.depth as u32;
+
+        if target.is_cubic() && width != height {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::InvalidCubicTextureDimensions);
+        }
+
+        if !validate_texture_size(context, self.limits, target, width, height, depth) {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::TextureTooBig);
+        }
+
+        // GL_INVALID_OPERATION is generated if levels is greater than
+        // floor(log2(max(width, height, depth))) + 1. The layers of an array
+        // texture don't count towards its number of levels.
+        let largest = match target {
+            TexImageTarget::Texture3D => cmp::max(cmp::max(width, height), depth),
+            _ => cmp::max(width, height),
+        };
+        if levels > log2(largest) + 1 {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::LevelTooHigh);
+        }
+
+        // Depth and stencil formats can't be used with three-dimensional
+        // textures.
+        if target == TexImageTarget::Texture3D && internal_format.is_depth() {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidTextureFormat);
+        }
+
+        Ok(TexStorageValidatorResult {
+            texture: texture,
+            target: self.target,
+            levels: levels,
+            internal_format: internal_format,
+            width: width,
+            height: height,
+            depth: depth,
+        })
+    }
+}
+
+pub struct TexImage3DValidator<'a> {
+    context: &'a WebGLRenderingContext,
+    limits: TexImage3DLimits,
+    target: u32,
+    level: i32,
+    internal_format: Option<u32>,
+    width: i32,
+    height: i32,
+    depth: i32,
+    border: i32,
+    format: u32,
+    data_type: u32,
+}
+
+impl<'a> TexImage3DValidator<'a> {
+    /// Creates a validator for texImage3D. `internal_format` is `None` for
+    /// texSubImage3D, which uses the one of the existing image instead.
+    pub fn new(context: &'a WebGLRenderingContext,
+               limits: TexImage3DLimits,
+               target: u32,
+               level: i32,
+               internal_format: Option<u32>,
+               width: i32,
+               height: i32,
+               depth: i32,
+               border: i32,
+               format: u32,
+               data_type: u32) -> Self {
+        TexImage3DValidator {
+            context: context,
+            limits: limits,
+            target: target,
+            level: level,
+            internal_format: internal_format,
+            width: width,
+            height: height,
+            depth: depth,
+            border: border,
+            format: format,
+            data_type: data_type,
+        }
+    }
+}
+
+/// The validated result of a TexImage3DValidator-validated call.
+pub struct TexImage3DValidatorResult {
+    pub texture: DomRoot<WebGLTexture>,
+    pub target: TexImageTarget,
+    pub level: u32,
+    pub internal_format: TexInternalFormat,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub format: TexFormat,
+    pub data_type: u32,
+}
+
+/// texImage3D and texSubImage3D validator as per
+/// <https://www.khronos.org/registry/OpenGL/specs/es/3.0/es_spec_3.0.pdf#nameddest=subsection.3.8.3>
+impl<'a> WebGLValidator for TexImage3DValidator<'a> {
+    type ValidatedOutput = TexImage3DValidatorResult;
+    type Error = TexImageValidationError;
+
+    fn validate(self) -> Result<Self::ValidatedOutput, TexImageValidationError> {
+        let context = self.context;
+
+        // GL_INVALID_ENUM is generated if target is not GL_TEXTURE_3D or
+        // GL_TEXTURE_2D_ARRAY.
+        let target = match TexImageTarget::from_gl_constant(self.target) {
+            Some(target) if target.is_3d() => target,
+            _ => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidTextureTarget(self.target));
+            }
+        };
+
+        let texture = match context.bound_texture_for_target(&target) {
+            Some(texture) => texture,
+            None => {
+                context.webgl_error(InvalidOperation);
+                return Err(TexImageValidationError::TextureTargetNotBound(self.target));
+            }
+        };
+
+        // GL_INVALID_VALUE is generated if level is less than 0 or greater
+        // than log2(max), where max is the maximum texture size.
+        if self.level < 0 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeLevel);
+        }
+        let level = self.level as u32;
+        let max_size = match target {
+            TexImageTarget::Texture3D => self.limits.max_3d_texture_size,
+            _ => context.limits().max_tex_size,
+        };
+        if level > log2(max_size) {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::LevelTooHigh);
+        }
+
+        // GL_INVALID_VALUE is generated if width, height or depth is less
+        // than 0 or greater than the maximum size.
+        if self.width < 0 || self.height < 0 || self.depth < 0 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::NegativeDimension);
+        }
+        let width = self.width as u32;
+        let height = self.height as u32;
+        let depth = self.depth as u32;
+        if !validate_texture_size(context, self.limits, target, width, height, depth) {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::TextureTooBig);
+        }
+
+        // GL_INVALID_VALUE is generated if border is not 0.
+        if self.border != 0 {
+            context.webgl_error(InvalidValue);
+            return Err(TexImageValidationError::InvalidBorder);
+        }
+
+        let internal_format = match self.internal_format {
+            Some(internal_format) => {
+                match TexInternalFormat::from_gl_constant(internal_format) {
+                    Some(format) => format,
+                    None => {
+                        context.webgl_error(InvalidEnum);
+                        return Err(TexImageValidationError::InvalidTextureFormat);
+                    }
+                }
+            }
+            None => {
+                // GL_INVALID_OPERATION is generated if the texture image
+                // being modified wasn't previously specified with a sized
+                // internal format.
+                let image_info = texture.image_info_for_target(&target, level);
+                match image_info.sized_internal_format() {
+                    Some(format) => format,
+                    None => {
+                        context.webgl_error(InvalidOperation);
+                        return Err(TexImageValidationError::TextureFormatMismatch);
+                    }
+                }
+            }
+        };
+
+        if target == TexImageTarget::Texture3D && internal_format.is_depth() {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidTextureFormat);
+        }
+
+        // GL_INVALID_ENUM is generated if format is not an accepted value.
+        let format = match TexFormat::from_gl_constant(self.format) {
+            Some(format) => format,
+            None => {
+                context.webgl_error(InvalidEnum);
+                return Err(TexImageValidationError::InvalidTextureFormat);
+            }
+        };
+
+        if bytes_per_pixel(format, self.data_type).is_none() {
+            context.webgl_error(InvalidEnum);
+            return Err(TexImageValidationError::InvalidDataType);
+        }
+
+        // GL_INVALID_OPERATION is generated if the combination of
+        // internal_format, format and type is not one of the valid ones
+        // listed in table 3.2 of the GLES 3.0 spec.
+        let (expected_format, data_types) = internal_format.format_and_data_types();
+        if format != expected_format {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::TextureFormatMismatch);
+        }
+        if !data_types.contains(&self.data_type) {
+            context.webgl_error(InvalidOperation);
+            return Err(TexImageValidationError::InvalidTypeForFormat);
+        }
+
+        Ok(TexImage3DValidatorResult {
+            texture: texture,
+            target: target,
+            level: level,
+            internal_format: internal_format,
+            width: width,
+            height: height,
+            depth: depth,
+            format: format,
+            data_type: self.data_type,
+        })
+    }
+}
@@ -2,7 +2,11 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::bindings::codegen::Bindings::EXTsRGBBinding::EXTsRGBConstants;
 use dom::bindings::codegen::Bindings::OESTextureHalfFloatBinding::OESTextureHalfFloatConstants;
+use dom::bindings::codegen::Bindings::WEBGLCompressedTextureETCBinding::WEBGLCompressedTextureETCConstants as etc;
+use dom::bindings::codegen::Bindings::WEBGLCompressedTextureS3TCBinding::WEBGLCompressedTextureS3TCConstants as s3tc;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 
 /// This macro creates type-safe wrappers for WebGL types, associating variants
@@ -41,15 +45,28 @@ type_safe_wrapper! { TexImageTarget,
     CubeMapNegativeY => constants::TEXTURE_CUBE_MAP_NEGATIVE_Y,
     CubeMapPositiveZ => constants::TEXTURE_CUBE_MAP_POSITIVE_Z,
     CubeMapNegativeZ => constants::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+    Texture3D => WebGl2Constants::TEXTURE_3D,
+    Texture2DArray => WebGl2Constants::TEXTURE_2D_ARRAY,
 }
 
 impl TexImageTarget {
     pub fn is_cubic(&self) -> bool {
         match *self {
-            TexImageTarget::Texture2D => false,
+            TexImageTarget::Texture2D |
+            TexImageTarget::Texture3D |
+            TexImageTarget::Texture2DArray => false,
             _ => true,
         }
     }
+
+    /// Returns whether this target takes three-dimensional images, as
+    /// texImage3D does.
+    pub fn is_3d(&self) -> bool {
+        match *self {
+            TexImageTarget::Texture3D | TexImageTarget::Texture2DArray => true,
+            _ => false,
+        }
+    }
 }
 
 type_safe_wrapper! { TexDataType,
@@ -59,6 +76,9 @@ type_safe_wrapper! { TexDataType,
     UnsignedShort565 => constants::UNSIGNED_SHORT_5_6_5,
     Float => constants::FLOAT,
     HalfFloat => OESTextureHalfFloatConstants::HALF_FLOAT_OES,
+    UnsignedShort => constants::UNSIGNED_SHORT,
+    UnsignedInt => constants::UNSIGNED_INT,
+    UnsignedInt248 => WebGl2Constants::UNSIGNED_INT_24_8,
 }
 
 impl TexDataType {
@@ -72,6 +92,8 @@ impl TexDataType {
             UnsignedShort565 => 2,
             Float => 4,
             HalfFloat => 2,
+            UnsignedShort => 2,
+            UnsignedInt | UnsignedInt248 => 4,
         }
     }
 
@@ -86,6 +108,17 @@ impl TexDataType {
             UnsignedShort4444 => 4,
             Float => 1,
             HalfFloat => 1,
+            UnsignedShort | UnsignedInt => 1,
+            UnsignedInt248 => 2,
+        }
+    }
+
+    /// Returns whether this data type can only be used to upload depth textures.
+    pub fn is_depth(&self) -> bool {
+        use self::TexDataType::*;
+        match *self {
+            UnsignedShort | UnsignedInt | UnsignedInt248 => true,
+            _ => false,
         }
     }
 }
@@ -97,6 +130,29 @@ type_safe_wrapper! { TexFormat,
     RGBA => constants::RGBA,
     Luminance => constants::LUMINANCE,
     LuminanceAlpha => constants::LUMINANCE_ALPHA,
+    DepthStencil => constants::DEPTH_STENCIL,
+    Red => WebGl2Constants::RED,
+    RedInteger => WebGl2Constants::RED_INTEGER,
+    RG => WebGl2Constants::RG,
+    RGInteger => WebGl2Constants::RG_INTEGER,
+    RGBInteger => WebGl2Constants::RGB_INTEGER,
+    RGBAInteger => WebGl2Constants::RGBA_INTEGER,
+    SRGB => EXTsRGBConstants::SRGB_EXT,
+    SRGBAlpha => EXTsRGBConstants::SRGB_ALPHA_EXT,
+    CompressedRgbS3tcDxt1 => s3tc::COMPRESSED_RGB_S3TC_DXT1_EXT,
+    CompressedRgbaS3tcDxt1 => s3tc::COMPRESSED_RGBA_S3TC_DXT1_EXT,
+    CompressedRgbaS3tcDxt3 => s3tc::COMPRESSED_RGBA_S3TC_DXT3_EXT,
+    CompressedRgbaS3tcDxt5 => s3tc::COMPRESSED_RGBA_S3TC_DXT5_EXT,
+    CompressedR11Eac => etc::COMPRESSED_R11_EAC,
+    CompressedSignedR11Eac => etc::COMPRESSED_SIGNED_R11_EAC,
+    CompressedRG11Eac => etc::COMPRESSED_RG11_EAC,
+    CompressedSignedRG11Eac => etc::COMPRESSED_SIGNED_RG11_EAC,
+    CompressedRgb8Etc2 => etc::COMPRESSED_RGB8_ETC2,
+    CompressedSrgb8Etc2 => etc::COMPRESSED_SRGB8_ETC2,
+    CompressedRgb8PunchthroughAlpha1Etc2 => etc::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+    CompressedSrgb8PunchthroughAlpha1Etc2 => etc::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+    CompressedRgba8Etc2Eac => etc::COMPRESSED_RGBA8_ETC2_EAC,
+    CompressedSrgb8Alpha8Etc2Eac => etc::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
 }
 
 impl TexFormat {
@@ -106,11 +162,295 @@ impl TexFormat {
         use self::TexFormat::*;
         match *self {
             DepthComponent => 1,
+            DepthStencil => 2,
             Alpha => 1,
             Luminance => 1,
             LuminanceAlpha => 2,
-            RGB => 3,
-            RGBA => 4,
+            Red | RedInteger => 1,
+            RG | RGInteger => 2,
+            RGB | RGBInteger | SRGB => 3,
+            RGBA | RGBAInteger | SRGBAlpha => 4,
+            CompressedR11Eac | CompressedSignedR11Eac => 1,
+            CompressedRG11Eac | CompressedSignedRG11Eac => 2,
+            CompressedRgbS3tcDxt1 | CompressedRgb8Etc2 | CompressedSrgb8Etc2 => 3,
+            _ => 4,
+        }
+    }
+
+    /// Returns whether this is a depth or depth-stencil format.
+    pub fn is_depth(&self) -> bool {
+        match *self {
+            TexFormat::DepthComponent | TexFormat::DepthStencil => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether this is one of the block-compressed formats, whose
+    /// images can only be specified through compressedTexImage2D.
+    pub fn is_compressed(&self) -> bool {
+        self.compression().is_some()
+    }
+
+    /// Returns the block layout of this format if it is a compressed one.
+    pub fn compression(&self) -> Option<TexCompression> {
+        use self::TexFormat::*;
+        let (bytes_per_block, validation) = match *self {
+            CompressedRgbS3tcDxt1 | CompressedRgbaS3tcDxt1 => (8, TexCompressionValidation::S3TC),
+            CompressedRgbaS3tcDxt3 | CompressedRgbaS3tcDxt5 => (16, TexCompressionValidation::S3TC),
+            CompressedR11Eac | CompressedSignedR11Eac |
+            CompressedRgb8Etc2 | CompressedSrgb8Etc2 |
+            CompressedRgb8PunchthroughAlpha1Etc2 |
+            CompressedSrgb8PunchthroughAlpha1Etc2 => (8, TexCompressionValidation::None),
+            CompressedRG11Eac | CompressedSignedRG11Eac |
+            CompressedRgba8Etc2Eac | CompressedSrgb8Alpha8Etc2Eac => (16, TexCompressionValidation::None),
+            _ => return None,
+        };
+        Some(TexCompression {
+            format: *self,
+            bytes_per_block,
+            block_width: 4,
+            block_height: 4,
+            validation,
+        })
+    }
+
+    /// Returns whether this is the format of an unnormalized integer texture.
+    pub fn is_integer(&self) -> bool {
+        use self::TexFormat::*;
+        match *self {
+            RedInteger | RGInteger | RGBInteger | RGBAInteger => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether this format can be used in a WebGL 1.0 texImage2D call.
+    pub fn is_webgl1_format(&self) -> bool {
+        use self::TexFormat::*;
+        match *self {
+            DepthComponent | DepthStencil | Alpha | Luminance | LuminanceAlpha |
+            RGB | RGBA | SRGB | SRGBAlpha => true,
+            _ => false,
+        }
+    }
+}
+
+type_safe_wrapper! { TexInternalFormat,
+    RGB => constants::RGB,
+    RGBA => constants::RGBA,
+    Luminance => constants::LUMINANCE,
+    LuminanceAlpha => constants::LUMINANCE_ALPHA,
+    Alpha => constants::ALPHA,
+    R8 => WebGl2Constants::R8,
+    R8SNorm => WebGl2Constants::R8_SNORM,
+    R16F => WebGl2Constants::R16F,
+    R32F => WebGl2Constants::R32F,
+    R8UI => WebGl2Constants::R8UI,
+    R8I => WebGl2Constants::R8I,
+    R16UI => WebGl2Constants::R16UI,
+    R16I => WebGl2Constants::R16I,
+    R32UI => WebGl2Constants::R32UI,
+    R32I => WebGl2Constants::R32I,
+    RG8 => WebGl2Constants::RG8,
+    RG8SNorm => WebGl2Constants::RG8_SNORM,
+    RG16F => WebGl2Constants::RG16F,
+    RG32F => WebGl2Constants::RG32F,
+    RG8UI => WebGl2Constants::RG8UI,
+    RG8I => WebGl2Constants::RG8I,
+    RG16UI => WebGl2Constants::RG16UI,
+    RG16I => WebGl2Constants::RG16I,
+    RG32UI => WebGl2Constants::RG32UI,
+    RG32I => WebGl2Constants::RG32I,
+    RGB8 => WebGl2Constants::RGB8,
+    SRGB8 => WebGl2Constants::SRGB8,
+    RGB565 => constants::RGB565,
+    RGB8SNorm => WebGl2Constants::RGB8_SNORM,
+    R11FG11FB10F => WebGl2Constants::R11F_G11F_B10F,
+    RGB9E5 => WebGl2Constants::RGB9_E5,
+    RGB16F => WebGl2Constants::RGB16F,
+    RGB32F => WebGl2Constants::RGB32F,
+    RGB8UI => WebGl2Constants::RGB8UI,
+    RGB8I => WebGl2Constants::RGB8I,
+    RGB16UI => WebGl2Constants::RGB16UI,
+    RGB16I => WebGl2Constants::RGB16I,
+    RGB32UI => WebGl2Constants::RGB32UI,
+    RGB32I => WebGl2Constants::RGB32I,
+    RGBA8 => WebGl2Constants::RGBA8,
+    SRGB8Alpha8 => WebGl2Constants::SRGB8_ALPHA8,
+    RGBA8SNorm => WebGl2Constants::RGBA8_SNORM,
+    RGB5A1 => constants::RGB5_A1,
+    RGBA4 => constants::RGBA4,
+    RGB10A2 => WebGl2Constants::RGB10_A2,
+    RGBA16F => WebGl2Constants::RGBA16F,
+    RGBA32F => WebGl2Constants::RGBA32F,
+    RGBA8UI => WebGl2Constants::RGBA8UI,
+    RGBA8I => WebGl2Constants::RGBA8I,
+    RGB10A2UI => WebGl2Constants::RGB10_A2UI,
+    RGBA16UI => WebGl2Constants::RGBA16UI,
+    RGBA16I => WebGl2Constants::RGBA16I,
+    RGBA32I => WebGl2Constants::RGBA32I,
+    RGBA32UI => WebGl2Constants::RGBA32UI,
+    DepthComponent16 => constants::DEPTH_COMPONENT16,
+    DepthComponent24 => WebGl2Constants::DEPTH_COMPONENT24,
+    DepthComponent32F => WebGl2Constants::DEPTH_COMPONENT32F,
+    Depth24Stencil8 => WebGl2Constants::DEPTH24_STENCIL8,
+    Depth32FStencil8 => WebGl2Constants::DEPTH32F_STENCIL8,
+}
+
+impl TexInternalFormat {
+    /// Returns whether this is one of the unsized formats inherited from WebGL 1.0.
+    /// Those can't be used to allocate immutable storage.
+    pub fn is_sized(&self) -> bool {
+        use self::TexInternalFormat::*;
+        match *self {
+            RGB | RGBA | Luminance | LuminanceAlpha | Alpha => false,
+            _ => true,
         }
     }
+
+    pub fn is_depth(&self) -> bool {
+        use self::TexInternalFormat::*;
+        match *self {
+            DepthComponent16 | DepthComponent24 | DepthComponent32F |
+            Depth24Stencil8 | Depth32FStencil8 => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the format and the data types which can be used to upload
+    /// pixels into a texture with this internal format.
+    /// https://www.khronos.org/registry/OpenGL/specs/es/3.0/es_spec_3.0.pdf#nameddest=subsection.3.8.3
+    pub fn format_and_data_types(&self) -> (TexFormat, &'static [u32]) {
+        use self::TexInternalFormat::*;
+        const UNSIGNED_BYTE: &'static [u32] = &[constants::UNSIGNED_BYTE];
+        const BYTE: &'static [u32] = &[constants::BYTE];
+        const UNSIGNED_SHORT: &'static [u32] = &[constants::UNSIGNED_SHORT];
+        const SHORT: &'static [u32] = &[constants::SHORT];
+        const UNSIGNED_INT: &'static [u32] = &[constants::UNSIGNED_INT];
+        const INT: &'static [u32] = &[constants::INT];
+        const FLOAT: &'static [u32] = &[constants::FLOAT];
+        const HALF_FLOAT_OR_FLOAT: &'static [u32] = &[WebGl2Constants::HALF_FLOAT, constants::FLOAT];
+        match *self {
+            RGB => (TexFormat::RGB, &[constants::UNSIGNED_BYTE, constants::UNSIGNED_SHORT_5_6_5]),
+            RGBA => (TexFormat::RGBA, &[
+                constants::UNSIGNED_BYTE,
+                constants::UNSIGNED_SHORT_4_4_4_4,
+                constants::UNSIGNED_SHORT_5_5_5_1,
+            ]),
+            Luminance => (TexFormat::Luminance, UNSIGNED_BYTE),
+            LuminanceAlpha => (TexFormat::LuminanceAlpha, UNSIGNED_BYTE),
+            Alpha => (TexFormat::Alpha, UNSIGNED_BYTE),
+            R8 => (TexFormat::Red, UNSIGNED_BYTE),
+            R8SNorm => (TexFormat::Red, BYTE),
+            R16F => (TexFormat::Red, HALF_FLOAT_OR_FLOAT),
+            R32F => (TexFormat::Red, FLOAT),
+            R8UI => (TexFormat::RedInteger, UNSIGNED_BYTE),
+            R8I => (TexFormat::RedInteger, BYTE),
+            R16UI => (TexFormat::RedInteger, UNSIGNED_SHORT),
+            R16I => (TexFormat::RedInteger, SHORT),
+            R32UI => (TexFormat::RedInteger, UNSIGNED_INT),
+            R32I => (TexFormat::RedInteger, INT),
+            RG8 => (TexFormat::RG, UNSIGNED_BYTE),
+            RG8SNorm => (TexFormat::RG, BYTE),
+            RG16F => (TexFormat::RG, HALF_FLOAT_OR_FLOAT),
+            RG32F => (TexFormat::RG, FLOAT),
+            RG8UI => (TexFormat::RGInteger, UNSIGNED_BYTE),
+            RG8I => (TexFormat::RGInteger, BYTE),
+            RG16UI => (TexFormat::RGInteger, UNSIGNED_SHORT),
+            RG16I => (TexFormat::RGInteger, SHORT),
+            RG32UI => (TexFormat::RGInteger, UNSIGNED_INT),
+            RG32I => (TexFormat::RGInteger, INT),
+            RGB8 | SRGB8 => (TexFormat::RGB, UNSIGNED_BYTE),
+            RGB565 => (TexFormat::RGB, &[constants::UNSIGNED_BYTE, constants::UNSIGNED_SHORT_5_6_5]),
+            RGB8SNorm => (TexFormat::RGB, BYTE),
+            R11FG11FB10F => (TexFormat::RGB, &[
+                WebGl2Constants::UNSIGNED_INT_10F_11F_11F_REV,
+                WebGl2Constants::HALF_FLOAT,
+                constants::FLOAT,
+            ]),
+            RGB9E5 => (TexFormat::RGB, &[
+                WebGl2Constants::UNSIGNED_INT_5_9_9_9_REV,
+                WebGl2Constants::HALF_FLOAT,
+                constants::FLOAT,
+            ]),
+            RGB16F => (TexFormat::RGB, HALF_FLOAT_OR_FLOAT),
+            RGB32F => (TexFormat::RGB, FLOAT),
+            RGB8UI => (TexFormat::RGBInteger, UNSIGNED_BYTE),
+            RGB8I => (TexFormat::RGBInteger, BYTE),
+            RGB16UI => (TexFormat::RGBInteger, UNSIGNED_SHORT),
+            RGB16I => (TexFormat::RGBInteger, SHORT),
+            RGB32UI => (TexFormat::RGBInteger, UNSIGNED_INT),
+            RGB32I => (TexFormat::RGBInteger, INT),
+            RGBA8 | SRGB8Alpha8 => (TexFormat::RGBA, UNSIGNED_BYTE),
+            RGBA8SNorm => (TexFormat::RGBA, BYTE),
+            RGB5A1 => (TexFormat::RGBA, &[
+                constants::UNSIGNED_BYTE,
+                constants::UNSIGNED_SHORT_5_5_5_1,
+                WebGl2Constants::UNSIGNED_INT_2_10_10_10_REV,
+            ]),
+            RGBA4 => (TexFormat::RGBA, &[constants::UNSIGNED_BYTE, constants::UNSIGNED_SHORT_4_4_4_4]),
+            RGB10A2 => (TexFormat::RGBA, &[WebGl2Constants::UNSIGNED_INT_2_10_10_10_REV]),
+            RGBA16F => (TexFormat::RGBA, HALF_FLOAT_OR_FLOAT),
+            RGBA32F => (TexFormat::RGBA, FLOAT),
+            RGBA8UI => (TexFormat::RGBAInteger, UNSIGNED_BYTE),
+            RGBA8I => (TexFormat::RGBAInteger, BYTE),
+            RGB10A2UI => (TexFormat::RGBAInteger, &[WebGl2Constants::UNSIGNED_INT_2_10_10_10_REV]),
+            RGBA16UI => (TexFormat::RGBAInteger, UNSIGNED_SHORT),
+            RGBA16I => (TexFormat::RGBAInteger, SHORT),
+            RGBA32I => (TexFormat::RGBAInteger, INT),
+            RGBA32UI => (TexFormat::RGBAInteger, UNSIGNED_INT),
+            DepthComponent16 => (TexFormat::DepthComponent, &[constants::UNSIGNED_SHORT, constants::UNSIGNED_INT]),
+            DepthComponent24 => (TexFormat::DepthComponent, UNSIGNED_INT),
+            DepthComponent32F => (TexFormat::DepthComponent, FLOAT),
+            Depth24Stencil8 => (TexFormat::DepthStencil, &[WebGl2Constants::UNSIGNED_INT_24_8]),
+            Depth32FStencil8 => (TexFormat::DepthStencil, &[WebGl2Constants::FLOAT_32_UNSIGNED_INT_24_8_REV]),
+        }
+    }
+}
+
+/// Extra restrictions on the dimensions of a compressed image, besides its
+/// data matching the block layout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TexCompressionValidation {
+    None,
+    /// https://www.khronos.org/registry/webgl/extensions/WEBGL_compressed_texture_s3tc/
+    S3TC,
+}
+
+/// The block layout of a compressed texture format.
+#[derive(Clone, Copy, Debug)]
+pub struct TexCompression {
+    pub format: TexFormat,
+    pub bytes_per_block: u32,
+    pub block_width: u32,
+    pub block_height: u32,
+    pub validation: TexCompressionValidation,
+}
+
+impl TexCompression {
+    /// Returns the number of bytes an image of the given size must have.
+    pub fn data_size(&self, width: u32, height: u32) -> u32 {
+        let blocks_wide = (width + self.block_width - 1) / self.block_width;
+        let blocks_high = (height + self.block_height - 1) / self.block_height;
+        blocks_wide * blocks_high * self.bytes_per_block
+    }
+}
+
+/// Returns the size in bytes of a single pixel uploaded with the given format
+/// and data type, or `None` if the data type is unknown.
+pub fn bytes_per_pixel(format: TexFormat, data_type: u32) -> Option<u32> {
+    let bytes_per_component = match data_type {
+        constants::UNSIGNED_SHORT_4_4_4_4 |
+        constants::UNSIGNED_SHORT_5_5_5_1 |
+        constants::UNSIGNED_SHORT_5_6_5 => return Some(2),
+        WebGl2Constants::UNSIGNED_INT_2_10_10_10_REV |
+        WebGl2Constants::UNSIGNED_INT_10F_11F_11F_REV |
+        WebGl2Constants::UNSIGNED_INT_5_9_9_9_REV |
+        WebGl2Constants::UNSIGNED_INT_24_8 => return Some(4),
+        WebGl2Constants::FLOAT_32_UNSIGNED_INT_24_8_REV => return Some(8),
+        constants::BYTE | constants::UNSIGNED_BYTE => 1,
+        constants::SHORT | constants::UNSIGNED_SHORT | WebGl2Constants::HALF_FLOAT => 2,
+        constants::INT | constants::UNSIGNED_INT | constants::FLOAT => 4,
+        _ => return None,
+    };
+    Some(bytes_per_component * format.components())
 }
@@ -8,7 +8,7 @@ use dom::bindings::codegen::Bindings::WebGLActiveInfoBinding::WebGLActiveInfoMet
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
 use dom::bindings::root::DomRoot;
 use dom::bindings::str::DOMString;
-use dom::window::Window;
+use dom::globalscope::GlobalScope;
 use dom_struct::dom_struct;
 
 #[dom_struct]
@@ -30,10 +30,10 @@ impl WebGLActiveInfo {
         }
     }
 
-    pub fn new(window: &Window, size: i32, ty: u32, name: DOMString) -> DomRoot<WebGLActiveInfo> {
+    pub fn new(global: &GlobalScope, size: i32, ty: u32, name: DOMString) -> DomRoot<WebGLActiveInfo> {
         reflect_dom_object(
             Box::new(WebGLActiveInfo::new_inherited(size, ty, name)),
-            window,
+            global,
             WebGLActiveInfoBinding::Wrap
         )
     }
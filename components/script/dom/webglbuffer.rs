@@ -4,7 +4,8 @@
 
 // https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
 use canvas_traits::webgl::{WebGLBufferId, WebGLCommand, WebGLError, WebGLResult};
-use canvas_traits::webgl::webgl_channel;
+use canvas_traits::webgl::{WebGLVersion, webgl_channel};
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants;
 use dom::bindings::codegen::Bindings::WebGLBufferBinding;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants;
 use dom::bindings::inheritance::Castable;
@@ -66,23 +67,27 @@ impl WebGLBuffer {
     where
         T: Into<Vec<u8>>,
     {
+        let is_webgl2 = self.upcast::<WebGLObject>().context().webgl_version() == WebGLVersion::WebGL2;
         match usage {
             WebGLRenderingContextConstants::STREAM_DRAW |
             WebGLRenderingContextConstants::STATIC_DRAW |
             WebGLRenderingContextConstants::DYNAMIC_DRAW => (),
+            WebGL2RenderingContextConstants::STREAM_READ |
+            WebGL2RenderingContextConstants::STREAM_COPY |
+            WebGL2RenderingContextConstants::STATIC_READ |
+            WebGL2RenderingContextConstants::STATIC_COPY |
+            WebGL2RenderingContextConstants::DYNAMIC_READ |
+            WebGL2RenderingContextConstants::DYNAMIC_COPY if is_webgl2 => (),
             _ => return Err(WebGLError::InvalidEnum),
         }
 
-        if let Some(previous_target) = self.target.get() {
-            if target != previous_target {
-                return Err(WebGLError::InvalidOperation);
-            }
+        if !self.is_compatible_target(target) {
+            return Err(WebGLError::InvalidOperation);
         }
         let data = data.into();
         self.capacity.set(data.len());
         self.usage.set(usage);
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::BufferData(target, data.into(), usage));
         Ok(())
     }
@@ -104,7 +109,6 @@ impl WebGLBuffer {
     fn delete(&self) {
         assert!(self.is_deleted());
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::DeleteBuffer(self.id));
     }
 
@@ -120,11 +124,31 @@ impl WebGLBuffer {
         self.target.get()
     }
 
+    /// Returns whether this buffer can be bound to `target`. In WebGL 1.0 a
+    /// buffer can only ever be bound to one target, while WebGL 2.0 only keeps
+    /// element array buffers apart from the other ones.
+    /// https://www.khronos.org/registry/webgl/specs/latest/2.0/#5.1
+    fn is_compatible_target(&self, target: u32) -> bool {
+        let previous_target = match self.target.get() {
+            Some(previous_target) => previous_target,
+            None => return true,
+        };
+        if previous_target == target {
+            return true;
+        }
+        let is_webgl2 = self.upcast::<WebGLObject>().context().webgl_version() == WebGLVersion::WebGL2;
+        is_webgl2 &&
+            (previous_target == WebGLRenderingContextConstants::ELEMENT_ARRAY_BUFFER) ==
+            (target == WebGLRenderingContextConstants::ELEMENT_ARRAY_BUFFER)
+    }
+
     pub fn set_target(&self, target: u32) -> WebGLResult<()> {
-        if self.target.get().map_or(false, |t| t != target) {
+        if !self.is_compatible_target(target) {
             return Err(WebGLError::InvalidOperation);
         }
-        self.target.set(Some(target));
+        if self.target.get().is_none() {
+            self.target.set(Some(target));
+        }
         Ok(())
     }
 
@@ -6,11 +6,13 @@
 use canvas_traits::webgl::{WebGLCommand, WebGLFramebufferBindingRequest, WebGLFramebufferId};
 use canvas_traits::webgl::{WebGLResult, WebGLError, webgl_channel};
 use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLFramebufferBinding;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{DomObject, reflect_dom_object};
 use dom::bindings::root::{Dom, DomRoot};
+use dom::webgl_validations::types::TexFormat;
 use dom::webglobject::WebGLObject;
 use dom::webglrenderbuffer::WebGLRenderbuffer;
 use dom::webglrenderingcontext::WebGLRenderingContext;
@@ -35,21 +37,33 @@ pub enum WebGLFramebufferAttachmentRoot {
 pub struct WebGLFramebuffer {
     webgl_object: WebGLObject,
     id: WebGLFramebufferId,
-    /// target can only be gl::FRAMEBUFFER at the moment
+    /// The target this framebuffer was last bound to: FRAMEBUFFER, or in
+    /// WebGL 2.0 also READ_FRAMEBUFFER or DRAW_FRAMEBUFFER.
     target: Cell<Option<u32>>,
     is_deleted: Cell<bool>,
     size: Cell<Option<(i32, i32)>>,
     status: Cell<u32>,
     // The attachment points for textures and renderbuffers on this
-    // FBO.
-    color: DomRefCell<Option<WebGLFramebufferAttachment>>,
+    // FBO. There is one color attachment point per MAX_COLOR_ATTACHMENTS,
+    // though only the first one is usable unless multiple render targets
+    // are enabled.
+    colors: Box<[DomRefCell<Option<WebGLFramebufferAttachment>>]>,
     depth: DomRefCell<Option<WebGLFramebufferAttachment>>,
     stencil: DomRefCell<Option<WebGLFramebufferAttachment>>,
     depthstencil: DomRefCell<Option<WebGLFramebufferAttachment>>,
+    /// The color buffer selected by WebGL 2.0's readBuffer.
+    read_buffer: Cell<u32>,
+    /// The color buffers selected by drawBuffers, one per draw buffer.
+    draw_buffers: DomRefCell<Vec<u32>>,
 }
 
 impl WebGLFramebuffer {
+    #[allow(unrooted_must_root)]
     fn new_inherited(context: &WebGLRenderingContext, id: WebGLFramebufferId) -> Self {
+        let capabilities = context.extension_manager().capabilities();
+        let colors = (0..capabilities.max_color_attachments).map(|_| DomRefCell::new(None)).collect::<Vec<_>>();
+        let mut draw_buffers = vec![constants::NONE; capabilities.max_draw_buffers as usize];
+        draw_buffers[0] = constants::COLOR_ATTACHMENT0;
         Self {
             webgl_object: WebGLObject::new_inherited(context),
             id: id,
@@ -57,10 +71,12 @@ impl WebGLFramebuffer {
             is_deleted: Cell::new(false),
             size: Cell::new(None),
             status: Cell::new(constants::FRAMEBUFFER_UNSUPPORTED),
-            color: DomRefCell::new(None),
+            colors: colors.into_boxed_slice(),
             depth: DomRefCell::new(None),
             stencil: DomRefCell::new(None),
             depthstencil: DomRefCell::new(None),
+            read_buffer: Cell::new(constants::COLOR_ATTACHMENT0),
+            draw_buffers: DomRefCell::new(draw_buffers),
         }
     }
 
@@ -95,7 +111,7 @@ impl WebGLFramebuffer {
         self.update_status();
 
         self.target.set(Some(target));
-        self.upcast::<WebGLObject>().context().send_command(
+        self.upcast::<WebGLObject>().send_command(
             WebGLCommand::BindFramebuffer(target, WebGLFramebufferBindingRequest::Explicit(self.id)),
         );
     }
@@ -104,7 +120,6 @@ impl WebGLFramebuffer {
         if !self.is_deleted.get() {
             self.is_deleted.set(true);
             self.upcast::<WebGLObject>()
-                .context()
                 .send_command(WebGLCommand::DeleteFramebuffer(self.id));
         }
     }
@@ -117,16 +132,28 @@ impl WebGLFramebuffer {
         self.size.get()
     }
 
+    /// All the attachment points of this framebuffer, color ones first.
+    fn attachment_bindings<'a>(&'a self)
+        -> impl Iterator<Item = &'a DomRefCell<Option<WebGLFramebufferAttachment>>>
+    {
+        self.colors.iter()
+            .chain(Some(&self.depth))
+            .chain(Some(&self.stencil))
+            .chain(Some(&self.depthstencil))
+    }
+
     fn update_status(&self) {
-        let c = self.color.borrow();
-        let z = self.depth.borrow();
-        let s = self.stencil.borrow();
-        let zs = self.depthstencil.borrow();
-        let has_c = c.is_some();
-        let has_z = z.is_some();
-        let has_s = s.is_some();
-        let has_zs = zs.is_some();
-        let attachments = [&*c, &*z, &*s, &*zs];
+        let has_c = self.colors.iter().any(|c| c.borrow().is_some());
+        let has_z = self.depth.borrow().is_some();
+        let has_s = self.stencil.borrow().is_some();
+        let has_zs = self.depthstencil.borrow().is_some();
+        let points = (0..self.colors.len() as u32)
+            .map(|i| constants::COLOR_ATTACHMENT0 + i)
+            .chain(vec![
+                constants::DEPTH_ATTACHMENT,
+                constants::STENCIL_ATTACHMENT,
+                constants::DEPTH_STENCIL_ATTACHMENT,
+            ]);
 
         // From the WebGL spec, 6.6 ("Framebuffer Object Attachments"):
         //
@@ -148,9 +175,22 @@ impl WebGLFramebuffer {
         }
 
         let mut fb_size = None;
-        for attachment in &attachments {
+        for (binding, point) in self.attachment_bindings().zip(points) {
+            let attachment = binding.borrow();
+
+            // Textures must have a format that can be rendered into at
+            // the point they are attached to, e.g. depth textures are only
+            // allowed as depth attachments.
+            if let Some(WebGLFramebufferAttachment::Texture { ref texture, level }) = *attachment {
+                let format = texture.image_info_at_face(0, level as u32).internal_format();
+                if !is_format_attachable(format, point) {
+                    self.status.set(constants::FRAMEBUFFER_INCOMPLETE_ATTACHMENT);
+                    return;
+                }
+            }
+
             // Get the size of this attachment.
-            let size = match **attachment {
+            let size = match *attachment {
                 Some(WebGLFramebufferAttachment::Renderbuffer(ref att_rb)) => {
                     att_rb.size()
                 }
@@ -189,14 +229,25 @@ impl WebGLFramebuffer {
         return self.status.get();
     }
 
-    pub fn renderbuffer(&self, attachment: u32, rb: Option<&WebGLRenderbuffer>) -> WebGLResult<()> {
-        let binding = match attachment {
-            constants::COLOR_ATTACHMENT0 => &self.color,
-            constants::DEPTH_ATTACHMENT => &self.depth,
-            constants::STENCIL_ATTACHMENT => &self.stencil,
-            constants::DEPTH_STENCIL_ATTACHMENT => &self.depthstencil,
-            _ => return Err(WebGLError::InvalidEnum),
-        };
+    fn attachment_binding(&self, attachment: u32) -> Option<&DomRefCell<Option<WebGLFramebufferAttachment>>> {
+        match attachment {
+            constants::COLOR_ATTACHMENT0...WebGl2Constants::COLOR_ATTACHMENT15 => {
+                let index = (attachment - constants::COLOR_ATTACHMENT0) as usize;
+                let context = self.upcast::<WebGLObject>().context();
+                if index > 0 && !context.extension_manager().is_draw_buffers_enabled() {
+                    return None;
+                }
+                self.colors.get(index)
+            },
+            constants::DEPTH_ATTACHMENT => Some(&self.depth),
+            constants::STENCIL_ATTACHMENT => Some(&self.stencil),
+            constants::DEPTH_STENCIL_ATTACHMENT => Some(&self.depthstencil),
+            _ => None,
+        }
+    }
+
+    pub fn renderbuffer(&self, target: u32, attachment: u32, rb: Option<&WebGLRenderbuffer>) -> WebGLResult<()> {
+        let binding = self.attachment_binding(attachment).ok_or(WebGLError::InvalidEnum)?;
 
         let rb_id = match rb {
             Some(rb) => {
@@ -210,9 +261,9 @@ impl WebGLFramebuffer {
             }
         };
 
-        self.upcast::<WebGLObject>().context().send_command(
+        self.upcast::<WebGLObject>().send_command(
             WebGLCommand::FramebufferRenderbuffer(
-                constants::FRAMEBUFFER,
+                target,
                 attachment,
                 constants::RENDERBUFFER,
                 rb_id,
@@ -224,13 +275,7 @@ impl WebGLFramebuffer {
     }
 
     pub fn attachment(&self, attachment: u32) -> Option<WebGLFramebufferAttachmentRoot> {
-        let binding = match attachment {
-            constants::COLOR_ATTACHMENT0 => &self.color,
-            constants::DEPTH_ATTACHMENT => &self.depth,
-            constants::STENCIL_ATTACHMENT => &self.stencil,
-            constants::DEPTH_STENCIL_ATTACHMENT => &self.depthstencil,
-            _ => return None,
-        };
+        let binding = self.attachment_binding(attachment)?;
 
         binding.borrow().as_ref().map(|bin| {
             match bin {
@@ -242,15 +287,9 @@ impl WebGLFramebuffer {
         })
     }
 
-    pub fn texture2d(&self, attachment: u32, textarget: u32, texture: Option<&WebGLTexture>,
+    pub fn texture2d(&self, target: u32, attachment: u32, textarget: u32, texture: Option<&WebGLTexture>,
                      level: i32) -> WebGLResult<()> {
-        let binding = match attachment {
-            constants::COLOR_ATTACHMENT0 => &self.color,
-            constants::DEPTH_ATTACHMENT => &self.depth,
-            constants::STENCIL_ATTACHMENT => &self.stencil,
-            constants::DEPTH_STENCIL_ATTACHMENT => &self.depthstencil,
-            _ => return Err(WebGLError::InvalidEnum),
-        };
+        let binding = self.attachment_binding(attachment).ok_or(WebGLError::InvalidEnum)?;
 
         let tex_id = match texture {
             // Note, from the GLES 2.0.25 spec, page 113:
@@ -309,9 +348,9 @@ impl WebGLFramebuffer {
             }
         };
 
-        self.upcast::<WebGLObject>().context().send_command(
+        self.upcast::<WebGLObject>().send_command(
             WebGLCommand::FramebufferTexture2D(
-                constants::FRAMEBUFFER,
+                target,
                 attachment,
                 textarget,
                 tex_id,
@@ -323,15 +362,55 @@ impl WebGLFramebuffer {
         Ok(())
     }
 
+    pub fn texture_layer(&self, target: u32, attachment: u32, texture: Option<&WebGLTexture>,
+                         level: i32, layer: i32) -> WebGLResult<()> {
+        let binding = self.attachment_binding(attachment).ok_or(WebGLError::InvalidEnum)?;
+
+        let tex_id = match texture {
+            Some(texture) => {
+                // From the GLES 3.0.5 spec, section 4.4.2.4:
+                //
+                //     "An INVALID_VALUE error is generated if texture is
+                //      not zero and layer is negative."
+                if level < 0 || layer < 0 {
+                    return Err(WebGLError::InvalidValue);
+                }
+
+                //     "An INVALID_OPERATION error is generated if texture is
+                //      non-zero and is not the name of a three-dimensional
+                //      texture or two-dimensional array texture."
+                match texture.target() {
+                    Some(WebGl2Constants::TEXTURE_3D) |
+                    Some(WebGl2Constants::TEXTURE_2D_ARRAY) => {},
+                    _ => return Err(WebGLError::InvalidOperation),
+                }
+
+                *binding.borrow_mut() = Some(WebGLFramebufferAttachment::Texture {
+                    texture: Dom::from_ref(texture),
+                    level: level }
+                );
+
+                Some(texture.id())
+            }
+
+            _ => {
+                *binding.borrow_mut() = None;
+                None
+            }
+        };
+
+        self.upcast::<WebGLObject>().send_command(
+            WebGLCommand::FramebufferTextureLayer(target, attachment, tex_id, level, layer),
+        );
+
+        self.update_status();
+        Ok(())
+    }
+
     fn with_matching_renderbuffers<F>(&self, rb: &WebGLRenderbuffer, mut closure: F)
         where F: FnMut(&DomRefCell<Option<WebGLFramebufferAttachment>>)
     {
-        let attachments = [&self.color,
-                           &self.depth,
-                           &self.stencil,
-                           &self.depthstencil];
-
-        for attachment in &attachments {
+        for attachment in self.attachment_bindings() {
             let matched = {
                 match *attachment.borrow() {
                     Some(WebGLFramebufferAttachment::Renderbuffer(ref att_rb))
@@ -349,12 +428,7 @@ impl WebGLFramebuffer {
     fn with_matching_textures<F>(&self, texture: &WebGLTexture, mut closure: F)
         where F: FnMut(&DomRefCell<Option<WebGLFramebufferAttachment>>)
     {
-        let attachments = [&self.color,
-                           &self.depth,
-                           &self.stencil,
-                           &self.depthstencil];
-
-        for attachment in &attachments {
+        for attachment in self.attachment_bindings() {
             let matched = {
                 match *attachment.borrow() {
                     Some(WebGLFramebufferAttachment::Texture { texture: ref att_texture, .. })
@@ -398,6 +472,43 @@ impl WebGLFramebuffer {
     pub fn target(&self) -> Option<u32> {
         self.target.get()
     }
+
+    pub fn read_buffer(&self) -> u32 {
+        self.read_buffer.get()
+    }
+
+    pub fn set_read_buffer(&self, buffer: u32) {
+        self.read_buffer.set(buffer);
+    }
+
+    /// Returns the color buffer the given draw buffer writes to.
+    pub fn draw_buffer(&self, index: usize) -> Option<u32> {
+        self.draw_buffers.borrow().get(index).cloned()
+    }
+
+    /// Sets the color buffers selected by drawBuffers, the remaining draw
+    /// buffers write nowhere.
+    pub fn set_draw_buffers(&self, buffers: &[u32]) {
+        let mut draw_buffers = self.draw_buffers.borrow_mut();
+        for (i, draw_buffer) in draw_buffers.iter_mut().enumerate() {
+            *draw_buffer = buffers.get(i).cloned().unwrap_or(constants::NONE);
+        }
+    }
+}
+
+/// Returns whether a texture of the given format can be attached to the
+/// given attachment point of a complete framebuffer.
+fn is_format_attachable(format: Option<TexFormat>, attachment: u32) -> bool {
+    let format = match format {
+        Some(format) => format,
+        None => return false,
+    };
+    match attachment {
+        constants::DEPTH_ATTACHMENT => format.is_depth(),
+        constants::STENCIL_ATTACHMENT |
+        constants::DEPTH_STENCIL_ATTACHMENT => format == TexFormat::DepthStencil,
+        _ => !format.is_depth() && !format.is_compressed(),
+    }
 }
 
 impl Drop for WebGLFramebuffer {
@@ -3,6 +3,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 // https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
+use canvas_traits::webgl::{WebGLCommand, WebGLContextId, WebGLMsgSender};
 use dom::bindings::reflector::Reflector;
 use dom::bindings::root::Dom;
 use dom::webglrenderingcontext::WebGLRenderingContext;
@@ -12,6 +13,10 @@ use dom_struct::dom_struct;
 pub struct WebGLObject {
     reflector_: Reflector,
     context: Dom<WebGLRenderingContext>,
+    /// Sender to the GL context this object was created in, which isn't the
+    /// current one anymore once the WebGL context is restored.
+    #[ignore_malloc_size_of = "Channels are hard"]
+    webgl_sender: WebGLMsgSender,
 }
 
 impl WebGLObject {
@@ -19,10 +24,21 @@ impl WebGLObject {
         WebGLObject {
             reflector_: Reflector::new(),
             context: Dom::from_ref(context),
+            webgl_sender: context.webgl_sender(),
         }
     }
 
     pub fn context(&self) -> &WebGLRenderingContext {
         &self.context
     }
+
+    /// The GL context this object belongs to.
+    pub fn context_id(&self) -> WebGLContextId {
+        self.webgl_sender.context_id()
+    }
+
+    /// Sends a command to the GL context this object belongs to.
+    pub fn send_command(&self, command: WebGLCommand) {
+        self.webgl_sender.send(command).unwrap();
+    }
 }
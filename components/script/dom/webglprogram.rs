@@ -6,6 +6,7 @@
 use canvas_traits::webgl::{ActiveAttribInfo, ActiveUniformInfo, WebGLCommand, WebGLError};
 use canvas_traits::webgl::{WebGLProgramId, WebGLResult, webgl_channel};
 use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLProgramBinding;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::inheritance::Castable;
@@ -34,6 +35,12 @@ pub struct WebGLProgram {
     vertex_shader: MutNullableDom<WebGLShader>,
     active_attribs: DomRefCell<Box<[ActiveAttribInfo]>>,
     active_uniforms: DomRefCell<Box<[ActiveUniformInfo]>>,
+    /// The number of varyings and the buffer mode given to the last
+    /// transformFeedbackVaryings call, applied on the next link.
+    pending_transform_feedback: Cell<(u32, u32)>,
+    /// The number of transform feedback varyings and the buffer mode this
+    /// program was last successfully linked with.
+    transform_feedback: Cell<(u32, u32)>,
 }
 
 impl WebGLProgram {
@@ -50,6 +57,8 @@ impl WebGLProgram {
             vertex_shader: Default::default(),
             active_attribs: DomRefCell::new(vec![].into()),
             active_uniforms: DomRefCell::new(vec![].into()),
+            pending_transform_feedback: Cell::new((0, WebGl2Constants::INTERLEAVED_ATTRIBS)),
+            transform_feedback: Cell::new((0, WebGl2Constants::INTERLEAVED_ATTRIBS)),
         }
     }
 
@@ -81,7 +90,6 @@ impl WebGLProgram {
         }
         self.marked_for_deletion.set(true);
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::DeleteProgram(self.id));
         if self.is_deleted() {
             self.detach_shaders();
@@ -145,7 +153,6 @@ impl WebGLProgram {
 
         let (sender, receiver) = webgl_channel().unwrap();
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::LinkProgram(self.id, sender));
         let link_info = receiver.recv().unwrap();
 
@@ -179,6 +186,9 @@ impl WebGLProgram {
         }
 
         self.linked.set(link_info.linked);
+        if link_info.linked {
+            self.transform_feedback.set(self.pending_transform_feedback.get());
+        }
         *self.active_attribs.borrow_mut() = link_info.active_attribs;
         *self.active_uniforms.borrow_mut() = link_info.active_uniforms;
         Ok(())
@@ -198,7 +208,6 @@ impl WebGLProgram {
             return Err(WebGLError::InvalidOperation);
         }
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::ValidateProgram(self.id));
         Ok(())
     }
@@ -225,7 +234,6 @@ impl WebGLProgram {
         shader.increment_attached_counter();
 
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::AttachShader(self.id, shader.id()));
 
         Ok(())
@@ -254,7 +262,6 @@ impl WebGLProgram {
         shader.decrement_attached_counter();
 
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::DetachShader(self.id, shader.id()));
 
         Ok(())
@@ -275,7 +282,6 @@ impl WebGLProgram {
         }
 
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::BindAttribLocation(self.id, index, name.into()));
         Ok(())
     }
@@ -287,7 +293,7 @@ impl WebGLProgram {
         let uniforms = self.active_uniforms.borrow();
         let data = uniforms.get(index as usize).ok_or(WebGLError::InvalidValue)?;
         Ok(WebGLActiveInfo::new(
-            self.global().as_window(),
+            &self.global(),
             data.size.unwrap_or(1),
             data.type_,
             data.name().into(),
@@ -302,13 +308,49 @@ impl WebGLProgram {
         let attribs = self.active_attribs.borrow();
         let data = attribs.get(index as usize).ok_or(WebGLError::InvalidValue)?;
         Ok(WebGLActiveInfo::new(
-            self.global().as_window(),
+            &self.global(),
             data.size,
             data.type_,
             data.name.clone().into(),
         ))
     }
 
+    /// glTransformFeedbackVaryings
+    pub fn transform_feedback_varyings(&self, varyings: Vec<String>, buffer_mode: u32) -> WebGLResult<()> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        match buffer_mode {
+            WebGl2Constants::INTERLEAVED_ATTRIBS | WebGl2Constants::SEPARATE_ATTRIBS => {},
+            _ => return Err(WebGLError::InvalidEnum),
+        }
+        self.pending_transform_feedback.set((varyings.len() as u32, buffer_mode));
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::TransformFeedbackVaryings(self.id, varyings, buffer_mode));
+        Ok(())
+    }
+
+    /// Returns the number of transform feedback varyings captured by this
+    /// program and the mode they are written to buffers with.
+    pub fn transform_feedback(&self) -> (u32, u32) {
+        self.transform_feedback.get()
+    }
+
+    /// glGetTransformFeedbackVarying
+    pub fn get_transform_feedback_varying(&self, index: u32) -> WebGLResult<DomRoot<WebGLActiveInfo>> {
+        if self.is_deleted() {
+            return Err(WebGLError::InvalidValue);
+        }
+        if !self.is_linked() || index >= self.transform_feedback.get().0 {
+            return Err(WebGLError::InvalidValue);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::GetTransformFeedbackVarying(self.id, index, sender));
+        let (size, type_, name) = receiver.recv().unwrap();
+        Ok(WebGLActiveInfo::new(&self.global(), size, type_, name.into()))
+    }
+
     /// glGetAttribLocation
     pub fn get_attrib_location(&self, name: DOMString) -> WebGLResult<i32> {
         if !self.is_linked() || self.is_deleted() {
@@ -375,12 +417,11 @@ impl WebGLProgram {
 
         let (sender, receiver) = webgl_channel().unwrap();
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::GetUniformLocation(self.id, name.into(), sender));
         let location = receiver.recv().unwrap();
 
         Ok(Some(WebGLUniformLocation::new(
-            self.global().as_window(),
+            &self.global(),
             location,
             self.id,
             self.link_generation.get(),
@@ -405,7 +446,6 @@ impl WebGLProgram {
         }
         let (sender, receiver) = webgl_channel().unwrap();
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::GetProgramInfoLog(self.id, sender));
         Ok(receiver.recv().unwrap())
     }
@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.2
+use canvas_traits::webgl::{webgl_channel, WebGLCommand, WebGLError, WebGLQueryId, WebGLResult};
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
+use dom::bindings::codegen::Bindings::WebGLQueryBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLQuery {
+    webgl_object: WebGLObject,
+    id: WebGLQueryId,
+    /// The target this query was first begun with. A query can't be used
+    /// with any other target afterwards.
+    target: Cell<Option<u32>>,
+    is_deleted: Cell<bool>,
+}
+
+impl WebGLQuery {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLQueryId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id: id,
+            target: Cell::new(None),
+            is_deleted: Cell::new(false),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateQuery(sender));
+        receiver.recv().unwrap().map(|id| WebGLQuery::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLQueryId) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLQuery::new_inherited(context, id)),
+            &*context.global(),
+            WebGLQueryBinding::Wrap,
+        )
+    }
+}
+
+impl WebGLQuery {
+    pub fn id(&self) -> WebGLQueryId {
+        self.id
+    }
+
+    pub fn target(&self) -> Option<u32> {
+        self.target.get()
+    }
+
+    pub fn begin(&self, target: u32) -> WebGLResult<()> {
+        if self.is_deleted.get() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        if self.target.get().map_or(false, |t| t != target) {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.target.set(Some(target));
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::BeginQuery(target, self.id));
+        Ok(())
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .send_command(WebGLCommand::DeleteQuery(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    /// Returns QUERY_RESULT or QUERY_RESULT_AVAILABLE for this query.
+    pub fn get_parameter(&self, pname: u32) -> WebGLResult<u32> {
+        match pname {
+            WebGl2Constants::QUERY_RESULT | WebGl2Constants::QUERY_RESULT_AVAILABLE => {},
+            _ => return Err(WebGLError::InvalidEnum),
+        }
+        if self.is_deleted.get() || self.target.get().is_none() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::GetQueryState(self.id, pname, sender));
+        Ok(receiver.recv().unwrap())
+    }
+}
+
+impl Drop for WebGLQuery {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
@@ -4,6 +4,7 @@
 
 // https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
 use canvas_traits::webgl::{webgl_channel, WebGLCommand, WebGLError, WebGLRenderbufferId, WebGLResult};
+use canvas_traits::webgl::WebGLVersion;
 use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLRenderbufferBinding;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
@@ -69,7 +70,6 @@ impl WebGLRenderbuffer {
     pub fn bind(&self, target: u32) {
         self.ever_bound.set(true);
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::BindRenderbuffer(target, Some(self.id)));
     }
 
@@ -77,7 +77,6 @@ impl WebGLRenderbuffer {
         if !self.is_deleted.get() {
             self.is_deleted.set(true);
             self.upcast::<WebGLObject>()
-                .context()
                 .send_command(WebGLCommand::DeleteRenderbuffer(self.id));
         }
     }
@@ -90,7 +89,12 @@ impl WebGLRenderbuffer {
         self.ever_bound.get()
     }
 
-    pub fn storage(&self, internal_format: u32, width: i32, height: i32) -> WebGLResult<()> {
+    /// Allocates storage for this renderbuffer. A non-zero `samples` count is
+    /// only passed by WebGL 2.0's renderbufferStorageMultisample.
+    pub fn storage(&self, samples: i32, internal_format: u32, width: i32, height: i32) -> WebGLResult<()> {
+        let context = self.upcast::<WebGLObject>().context();
+        let is_webgl2 = context.webgl_version() == WebGLVersion::WebGL2;
+
         // Validate the internal_format, and save it for completeness
         // validation.
         let actual_format = match internal_format {
@@ -115,21 +119,81 @@ impl WebGLRenderbuffer {
                     WebGl2Constants::RGB8
                 }
             }
+            // https://www.khronos.org/registry/OpenGL/specs/es/3.0/es_spec_3.0.pdf#nameddest=subsection.3.8.3
+            WebGl2Constants::R8UI |
+            WebGl2Constants::R8I |
+            WebGl2Constants::R16UI |
+            WebGl2Constants::R16I |
+            WebGl2Constants::R32UI |
+            WebGl2Constants::R32I |
+            WebGl2Constants::RG8UI |
+            WebGl2Constants::RG8I |
+            WebGl2Constants::RG16UI |
+            WebGl2Constants::RG16I |
+            WebGl2Constants::RG32UI |
+            WebGl2Constants::RG32I |
+            WebGl2Constants::RGBA8UI |
+            WebGl2Constants::RGBA8I |
+            WebGl2Constants::RGB10_A2UI |
+            WebGl2Constants::RGBA16UI |
+            WebGl2Constants::RGBA16I |
+            WebGl2Constants::RGBA32I |
+            WebGl2Constants::RGBA32UI if is_webgl2 => {
+                // Multisampling is not supported for integer formats.
+                if samples > 0 {
+                    return Err(WebGLError::InvalidOperation);
+                }
+                internal_format
+            }
+            WebGl2Constants::R8 |
+            WebGl2Constants::RG8 |
+            WebGl2Constants::RGB8 |
+            WebGl2Constants::RGBA8 |
+            WebGl2Constants::SRGB8_ALPHA8 |
+            WebGl2Constants::RGB10_A2 |
+            WebGl2Constants::DEPTH_COMPONENT24 |
+            WebGl2Constants::DEPTH_COMPONENT32F |
+            WebGl2Constants::DEPTH24_STENCIL8 |
+            WebGl2Constants::DEPTH32F_STENCIL8 if is_webgl2 => internal_format,
+            // Formats enabled by EXT_sRGB and EXT_color_buffer_float.
+            _ if context.extension_manager().is_renderbuffer_format_enabled(internal_format) => internal_format,
             _ => return Err(WebGLError::InvalidEnum),
         };
 
+        if samples > 0 {
+            // GL_INVALID_OPERATION is generated if samples is greater than the
+            // maximum number of samples supported for internal_format.
+            let (sender, receiver) = webgl_channel().unwrap();
+            self.upcast::<WebGLObject>().send_command(
+                WebGLCommand::GetInternalFormatSamples(constants::RENDERBUFFER, actual_format, sender),
+            );
+            let max_samples = receiver.recv().unwrap().into_iter().max().unwrap_or(0);
+            if samples > max_samples {
+                return Err(WebGLError::InvalidOperation);
+            }
+        }
+
         self.internal_format.set(Some(internal_format));
 
         // FIXME: Invalidate completeness after the call
 
-        self.upcast::<WebGLObject>().context().send_command(
+        let command = if samples > 0 {
+            WebGLCommand::RenderbufferStorageMultisample(
+                constants::RENDERBUFFER,
+                samples,
+                actual_format,
+                width,
+                height,
+            )
+        } else {
             WebGLCommand::RenderbufferStorage(
                 constants::RENDERBUFFER,
                 actual_format,
                 width,
                 height,
             )
-        );
+        };
+        self.upcast::<WebGLObject>().send_command(command);
 
         self.size.set(Some((width, height)));
 
@@ -5,7 +5,7 @@
 use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
 use canvas_traits::canvas::{byte_swap, multiply_u8_pixel};
 use canvas_traits::webgl::{DOMToTextureCommand, Parameter};
-use canvas_traits::webgl::{TexParameter, WebGLCommand, WebGLContextShareMode, WebGLError};
+use canvas_traits::webgl::{TexParameter, WebGLCommand, WebGLContextId, WebGLContextShareMode, WebGLError};
 use canvas_traits::webgl::{WebGLFramebufferBindingRequest, WebGLMsg, WebGLMsgSender};
 use canvas_traits::webgl::{WebGLProgramId, WebGLResult, WebGLSLVersion, WebGLSender};
 use canvas_traits::webgl::{WebGLVersion, WebVRCommand, webgl_channel};
@@ -13,29 +13,42 @@ use canvas_traits::webgl::WebGLError::*;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::ANGLEInstancedArraysBinding::ANGLEInstancedArraysConstants;
 use dom::bindings::codegen::Bindings::EXTBlendMinmaxBinding::EXTBlendMinmaxConstants;
+use dom::bindings::codegen::Bindings::EXTDisjointTimerQueryBinding::EXTDisjointTimerQueryConstants;
+use dom::bindings::codegen::Bindings::EXTsRGBBinding::EXTsRGBConstants;
 use dom::bindings::codegen::Bindings::OESVertexArrayObjectBinding::OESVertexArrayObjectConstants;
+use dom::bindings::codegen::Bindings::WEBGLDebugRendererInfoBinding::WEBGLDebugRendererInfoConstants;
+use dom::bindings::codegen::Bindings::WEBGLDrawBuffersBinding::WEBGLDrawBuffersConstants;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::{self, WebGLContextAttributes};
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextMethods;
 use dom::bindings::codegen::UnionTypes::ArrayBufferViewOrArrayBuffer;
 use dom::bindings::codegen::UnionTypes::Float32ArrayOrUnrestrictedFloatSequence;
+use dom::bindings::codegen::UnionTypes::HTMLCanvasElementOrOffscreenCanvas;
 use dom::bindings::codegen::UnionTypes::ImageDataOrHTMLImageElementOrHTMLCanvasElementOrHTMLVideoElement;
 use dom::bindings::codegen::UnionTypes::Int32ArrayOrLongSequence;
 use dom::bindings::conversions::{DerivedFrom, ToJSValConvertible};
 use dom::bindings::error::{Error, ErrorResult};
 use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::reflector::{DomObject, Reflector, reflect_dom_object};
-use dom::bindings::root::{Dom, DomOnceCell, DomRoot, LayoutDom, MutNullableDom};
+use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom};
 use dom::bindings::str::DOMString;
-use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
+use dom::globalscope::GlobalScope;
 use dom::htmlcanvaselement::HTMLCanvasElement;
 use dom::htmlcanvaselement::utils as canvas_utils;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::node::{Node, NodeDamage, window_from_node};
+use dom::offscreencanvas::OffscreenCanvas;
+use dom::webgl2renderingcontext::WebGL2RenderingContext;
 use dom::webgl_extensions::WebGLExtensions;
+use dom::webgl_extensions::ext::extsrgb::EXTsRGB;
 use dom::webgl_validations::WebGLValidator;
 use dom::webgl_validations::tex_image_2d::{CommonTexImage2DValidator, CommonTexImage2DValidatorResult};
+use dom::webgl_validations::tex_image_2d::{CompressedTexImage2DValidator, CompressedTexImage2DValidatorResult};
 use dom::webgl_validations::tex_image_2d::{TexImage2DValidator, TexImage2DValidatorResult};
+use dom::webgl_validations::tex_image_2d::CompressedTexSubImage2DValidator;
 use dom::webgl_validations::types::{TexDataType, TexFormat, TexImageTarget};
 use dom::webglactiveinfo::WebGLActiveInfo;
 use dom::webglbuffer::WebGLBuffer;
@@ -54,6 +67,8 @@ use dom_struct::dom_struct;
 use euclid::Size2D;
 use fnv::FnvHashMap;
 use half::f16;
+use ipc_channel::ipc::{self, IpcReceiver};
+use ipc_channel::router::ROUTER;
 use js::jsapi::{JSContext, JSObject, Type};
 use js::jsval::{BooleanValue, DoubleValue, Int32Value, UInt32Value, JSVal};
 use js::jsval::{ObjectValue, NullValue, UndefinedValue};
@@ -65,10 +80,12 @@ use net_traits::image_cache::ImageResponse;
 use offscreen_gl_context::{GLContextAttributes, GLLimits};
 use script_layout_interface::HTMLCanvasDataSource;
 use serde::{Deserialize, Serialize};
+use servo_atoms::Atom;
 use servo_config::prefs::PREFS;
 use std::cell::Cell;
 use std::cmp;
 use std::ptr::{self, NonNull};
+use task_source::{TaskSource, TaskSourceName};
 use webrender_api;
 
 pub fn is_gles() -> bool {
@@ -77,6 +94,19 @@ pub fn is_gles() -> bool {
     cfg!(any(target_os = "android", target_os = "ios"))
 }
 
+/// Returns the format the pixels of a texture with the given format are
+/// uploaded with.
+///
+/// EXT_sRGB uses the sRGB formats both as the internal format and the pixel
+/// format, but desktop GL only accepts them as the internal format.
+fn tex_upload_format(format: TexFormat) -> u32 {
+    match format {
+        TexFormat::SRGB if !is_gles() => constants::RGB,
+        TexFormat::SRGBAlpha if !is_gles() => constants::RGBA,
+        format => format.as_gl_constant(),
+    }
+}
+
 type ImagePixelResult = Result<(Vec<u8>, Size2D<i32>, bool), ()>;
 pub const MAX_UNIFORM_AND_ATTRIBUTE_LEN: usize = 256;
 
@@ -103,18 +133,6 @@ macro_rules! handle_object_deletion {
 }
 
 
-macro_rules! optional_root_object_to_js_or_null {
-    ($cx: expr, $binding:expr) => {
-        {
-            rooted!(in($cx) let mut rval = NullValue());
-            if let Some(object) = $binding {
-                object.to_jsval($cx, rval.handle_mut());
-            }
-            rval.get()
-        }
-    };
-}
-
 fn has_invalid_blend_constants(arg1: u32, arg2: u32) -> bool {
     match (arg1, arg2) {
         (constants::CONSTANT_COLOR, constants::CONSTANT_ALPHA) => true,
@@ -141,6 +159,8 @@ bitflags! {
 struct TextureUnitBindings {
     bound_texture_2d: MutNullableDom<WebGLTexture>,
     bound_texture_cube_map: MutNullableDom<WebGLTexture>,
+    bound_texture_3d: MutNullableDom<WebGLTexture>,
+    bound_texture_2d_array: MutNullableDom<WebGLTexture>,
 }
 
 impl TextureUnitBindings {
@@ -148,6 +168,8 @@ impl TextureUnitBindings {
         Self {
             bound_texture_2d: MutNullableDom::new(None),
             bound_texture_cube_map: MutNullableDom::new(None),
+            bound_texture_3d: MutNullableDom::new(None),
+            bound_texture_2d_array: MutNullableDom::new(None),
         }
     }
 
@@ -155,7 +177,9 @@ impl TextureUnitBindings {
     /// Returns the GL target of the cleared slot, if any.
     fn clear_slot(&self, texture: &WebGLTexture) -> Option<u32> {
         let fields = [(&self.bound_texture_2d, constants::TEXTURE_2D),
-                      (&self.bound_texture_cube_map, constants::TEXTURE_CUBE_MAP)];
+                      (&self.bound_texture_cube_map, constants::TEXTURE_CUBE_MAP),
+                      (&self.bound_texture_3d, WebGl2Constants::TEXTURE_3D),
+                      (&self.bound_texture_2d_array, WebGl2Constants::TEXTURE_2D_ARRAY)];
 
         fields.iter().find(|field| {
             match field.0.get() {
@@ -169,12 +193,38 @@ impl TextureUnitBindings {
     }
 }
 
+/// The canvas a WebGL context renders into.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+enum WebGLCanvas {
+    HTMLCanvasElement(Dom<HTMLCanvasElement>),
+    OffscreenCanvas(Dom<OffscreenCanvas>),
+}
+
+/// What made a WebGL context lose its GL context.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContextLossCause {
+    /// The page called `WEBGL_lose_context.loseContext()`.
+    Extension,
+    /// The WebGL thread lost or evicted the GL context.
+    WebGLThread,
+}
 
 #[dom_struct]
 pub struct WebGLRenderingContext {
     reflector_: Reflector,
+    /// Replaced with a sender to a new GL context when the context is restored.
     #[ignore_malloc_size_of = "Channels are hard"]
-    webgl_sender: WebGLMsgSender,
+    webgl_sender: DomRefCell<WebGLMsgSender>,
+    /// The receiving end of the notifications the WebGL thread sends when it
+    /// loses the GL context on its own, taken once the context is reflected.
+    #[ignore_malloc_size_of = "Channels are hard"]
+    loss_receiver: DomRefCell<Option<IpcReceiver<WebGLContextId>>>,
+    /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#webgl-context-lost-flag
+    context_lost: Cell<bool>,
+    /// Whether the default action of the last webglcontextlost event was
+    /// prevented, which is required to restore the context.
+    restore_allowed: Cell<bool>,
     #[ignore_malloc_size_of = "Defined in webrender"]
     webrender_image: Cell<Option<webrender_api::ImageKey>>,
     share_mode: WebGLContextShareMode,
@@ -182,7 +232,7 @@ pub struct WebGLRenderingContext {
     glsl_version: WebGLSLVersion,
     #[ignore_malloc_size_of = "Defined in offscreen_gl_context"]
     limits: GLLimits,
-    canvas: Dom<HTMLCanvasElement>,
+    canvas: WebGLCanvas,
     #[ignore_malloc_size_of = "Defined in canvas_traits"]
     last_error: Cell<Option<WebGLError>>,
     texture_unpacking_settings: Cell<TextureUnpacking>,
@@ -193,6 +243,9 @@ pub struct WebGLRenderingContext {
     bound_texture_unit: Cell<u32>,
     bound_buffer_array: MutNullableDom<WebGLBuffer>,
     current_program: MutNullableDom<WebGLProgram>,
+    /// The programs that were successfully linked and not deleted since, as listed by
+    /// the devtools shader editor.
+    linked_programs: DomRefCell<Vec<Dom<WebGLProgram>>>,
     /// https://www.khronos.org/webgl/wiki/WebGL_and_OpenGL_Differences#Vertex_Attribute_0
     #[ignore_malloc_size_of = "Because it's small"]
     current_vertex_attrib_0: Cell<(f32, f32, f32, f32)>,
@@ -202,14 +255,19 @@ pub struct WebGLRenderingContext {
     current_clear_color: Cell<(f32, f32, f32, f32)>,
     extension_manager: WebGLExtensions,
     capabilities: Capabilities,
-    default_vao: DomOnceCell<WebGLVertexArrayObjectOES>,
+    default_vao: MutNullableDom<WebGLVertexArrayObjectOES>,
     current_vao: MutNullableDom<WebGLVertexArrayObjectOES>,
+    /// The buffer selected by drawBuffers for the default framebuffer.
+    default_draw_buffer: Cell<u32>,
+    /// The WebGL 2.0 context wrapping this one, if any, whose own state
+    /// must be reset when the context is restored.
+    webgl2: MutNullableDom<WebGL2RenderingContext>,
 }
 
 impl WebGLRenderingContext {
     pub fn new_inherited(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         webgl_version: WebGLVersion,
         size: Size2D<i32>,
         attrs: GLContextAttributes
@@ -218,26 +276,38 @@ impl WebGLRenderingContext {
             return Err("WebGL context creation error forced by pref `webgl.testing.context_creation_error`".into());
         }
 
-        let webgl_chan = match window.webgl_chan() {
+        let webgl_chan = match global.webgl_chan() {
             Some(chan) => chan,
             None => return Err("WebGL initialization failed early on".into()),
         };
 
+        let (loss_sender, loss_receiver) = ipc::channel().unwrap();
         let (sender, receiver) = webgl_channel().unwrap();
-        webgl_chan.send(WebGLMsg::CreateContext(webgl_version, size, attrs, sender))
+        webgl_chan.send(WebGLMsg::CreateContext(webgl_version, size, attrs, loss_sender, sender))
                   .unwrap();
         let result = receiver.recv().unwrap();
 
         result.map(|ctx_data| {
+            let canvas = match *canvas {
+                HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(ref canvas) => {
+                    WebGLCanvas::HTMLCanvasElement(Dom::from_ref(&**canvas))
+                },
+                HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(ref canvas) => {
+                    WebGLCanvas::OffscreenCanvas(Dom::from_ref(&**canvas))
+                },
+            };
             WebGLRenderingContext {
                 reflector_: Reflector::new(),
-                webgl_sender: ctx_data.sender,
+                webgl_sender: DomRefCell::new(ctx_data.sender),
+                loss_receiver: DomRefCell::new(Some(loss_receiver)),
+                context_lost: Cell::new(false),
+                restore_allowed: Cell::new(false),
                 webrender_image: Cell::new(None),
                 share_mode: ctx_data.share_mode,
                 webgl_version,
                 glsl_version: ctx_data.glsl_version,
                 limits: ctx_data.limits,
-                canvas: Dom::from_ref(canvas),
+                canvas,
                 last_error: Cell::new(None),
                 texture_unpacking_settings: Cell::new(TextureUnpacking::CONVERT_COLORSPACE),
                 texture_unpacking_alignment: Cell::new(4),
@@ -247,35 +317,51 @@ impl WebGLRenderingContext {
                 bound_buffer_array: MutNullableDom::new(None),
                 bound_renderbuffer: MutNullableDom::new(None),
                 current_program: MutNullableDom::new(None),
+                linked_programs: DomRefCell::new(vec![]),
                 current_vertex_attrib_0: Cell::new((0f32, 0f32, 0f32, 1f32)),
                 current_scissor: Cell::new((0, 0, size.width, size.height)),
                 current_clear_color: Cell::new((0.0, 0.0, 0.0, 0.0)),
-                extension_manager: WebGLExtensions::new(webgl_version),
+                extension_manager: WebGLExtensions::new(webgl_version, ctx_data.capabilities),
                 capabilities: Default::default(),
                 default_vao: Default::default(),
                 current_vao: Default::default(),
+                default_draw_buffer: Cell::new(constants::BACK),
+                webgl2: MutNullableDom::new(None),
             }
         })
     }
 
     #[allow(unrooted_must_root)]
     pub fn new(
-        window: &Window,
-        canvas: &HTMLCanvasElement,
+        global: &GlobalScope,
+        canvas: &HTMLCanvasElementOrOffscreenCanvas,
         webgl_version: WebGLVersion,
         size: Size2D<i32>,
         attrs: GLContextAttributes
     ) -> Option<DomRoot<WebGLRenderingContext>> {
-        match WebGLRenderingContext::new_inherited(window, canvas, webgl_version, size, attrs) {
-            Ok(ctx) => Some(reflect_dom_object(Box::new(ctx), window, WebGLRenderingContextBinding::Wrap)),
+        match WebGLRenderingContext::new_inherited(global, canvas, webgl_version, size, attrs) {
+            Ok(ctx) => {
+                let ctx = reflect_dom_object(Box::new(ctx), global, WebGLRenderingContextBinding::Wrap);
+                if let Some(loss_receiver) = ctx.loss_receiver.borrow_mut().take() {
+                    ctx.listen_for_context_loss(loss_receiver);
+                }
+                Some(ctx)
+            },
             Err(msg) => {
                 error!("Couldn't create WebGLRenderingContext: {}", msg);
-                let event = WebGLContextEvent::new(window,
+                let event = WebGLContextEvent::new(global,
                                                    atom!("webglcontextcreationerror"),
                                                    EventBubbles::DoesNotBubble,
                                                    EventCancelable::Cancelable,
                                                    DOMString::from(msg));
-                event.upcast::<Event>().fire(canvas.upcast());
+                match *canvas {
+                    HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(ref canvas) => {
+                        event.upcast::<Event>().fire(canvas.upcast());
+                    },
+                    HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(ref canvas) => {
+                        event.upcast::<Event>().fire(canvas.upcast());
+                    },
+                }
                 None
             }
         }
@@ -285,15 +371,102 @@ impl WebGLRenderingContext {
         &self.limits
     }
 
+    pub fn webgl_version(&self) -> WebGLVersion {
+        self.webgl_version
+    }
+
+    pub fn extension_manager(&self) -> &WebGLExtensions {
+        &self.extension_manager
+    }
+
+    /// Returns the buffer written by the draw buffer at `index` of the bound
+    /// framebuffer, or `None` if there's no such draw buffer.
+    pub fn draw_buffer(&self, index: u32) -> Option<u32> {
+        if !self.extension_manager.is_draw_buffers_enabled() ||
+            index >= self.extension_manager.capabilities().max_draw_buffers
+        {
+            return None;
+        }
+        match self.bound_framebuffer.get() {
+            Some(fb) => fb.draw_buffer(index as usize),
+            None if index == 0 => Some(self.default_draw_buffer.get()),
+            None => Some(constants::NONE),
+        }
+    }
+
+    // https://www.khronos.org/registry/webgl/extensions/WEBGL_draw_buffers/
+    pub fn draw_buffers(&self, buffers: Vec<u32>) {
+        let is_valid_buffer = |buffer: u32| {
+            buffer == constants::NONE || buffer == constants::BACK ||
+                (buffer >= constants::COLOR_ATTACHMENT0 && buffer <= WebGl2Constants::COLOR_ATTACHMENT15)
+        };
+        if !buffers.iter().all(|&buffer| is_valid_buffer(buffer)) {
+            return self.webgl_error(InvalidEnum);
+        }
+
+        let fb = match self.bound_framebuffer.get() {
+            Some(fb) => fb,
+            None => {
+                // The default framebuffer only has the back buffer, which
+                // is backed by the first color attachment of our FBO.
+                let buffer = match *buffers {
+                    [constants::BACK] => constants::COLOR_ATTACHMENT0,
+                    [constants::NONE] => constants::NONE,
+                    _ => return self.webgl_error(InvalidOperation),
+                };
+                self.default_draw_buffer.set(buffers[0]);
+                return self.send_command(WebGLCommand::DrawBuffers(vec![buffer]));
+            }
+        };
+
+        if buffers.len() > self.extension_manager.capabilities().max_draw_buffers as usize {
+            return self.webgl_error(InvalidValue);
+        }
+
+        // The i-th buffer can only be NONE or the i-th color attachment.
+        let is_valid_attachment = |(i, &buffer): (usize, &u32)| {
+            buffer == constants::NONE || buffer == constants::COLOR_ATTACHMENT0 + i as u32
+        };
+        if !buffers.iter().enumerate().all(is_valid_attachment) {
+            return self.webgl_error(InvalidOperation);
+        }
+
+        fb.set_draw_buffers(&buffers);
+        self.send_command(WebGLCommand::DrawBuffers(buffers));
+    }
+
+    pub fn texture_unpacking_alignment(&self) -> u32 {
+        self.texture_unpacking_alignment.get()
+    }
+
+    /// Returns whether UNPACK_FLIP_Y_WEBGL or UNPACK_PREMULTIPLY_ALPHA_WEBGL
+    /// is enabled, which uploads of three-dimensional textures don't support.
+    pub fn is_unpack_flip_y_or_premultiply_alpha_enabled(&self) -> bool {
+        self.texture_unpacking_settings.get()
+            .intersects(TextureUnpacking::FLIP_Y_AXIS | TextureUnpacking::PREMULTIPLY_ALPHA)
+    }
+
+    pub fn current_program(&self) -> Option<DomRoot<WebGLProgram>> {
+        self.current_program.get()
+    }
+
+    /// The draw framebuffer binding, which is also the read one in WebGL 1.0.
+    pub fn draw_framebuffer_slot(&self) -> &MutNullableDom<WebGLFramebuffer> {
+        &self.bound_framebuffer
+    }
+
+    /// Returns the index of the active texture unit, starting from 0.
+    pub fn active_texture_unit(&self) -> u32 {
+        self.bound_texture_unit.get() - constants::TEXTURE0
+    }
+
     fn current_vao(&self) -> DomRoot<WebGLVertexArrayObjectOES> {
         self.current_vao.or_init(|| {
-            DomRoot::from_ref(
-                self.default_vao.init_once(|| WebGLVertexArrayObjectOES::new(self, None)),
-            )
+            self.default_vao.or_init(|| WebGLVertexArrayObjectOES::new(self, None))
         })
     }
 
-    fn bound_texture(&self, target: u32) -> Option<DomRoot<WebGLTexture>> {
+    pub fn bound_texture(&self, target: u32) -> Option<DomRoot<WebGLTexture>> {
         match target {
             constants::TEXTURE_2D => {
                 self.bound_textures.borrow().get(&self.bound_texture_unit.get()).and_then(|t| {
@@ -305,6 +478,16 @@ impl WebGLRenderingContext {
                     t.bound_texture_cube_map.get()
                 })
             },
+            WebGl2Constants::TEXTURE_3D if self.webgl_version == WebGLVersion::WebGL2 => {
+                self.bound_textures.borrow().get(&self.bound_texture_unit.get()).and_then(|t| {
+                    t.bound_texture_3d.get()
+                })
+            },
+            WebGl2Constants::TEXTURE_2D_ARRAY if self.webgl_version == WebGLVersion::WebGL2 => {
+                self.bound_textures.borrow().get(&self.bound_texture_unit.get()).and_then(|t| {
+                    t.bound_texture_2d_array.get()
+                })
+            },
             _ => None,
         }
     }
@@ -319,13 +502,20 @@ impl WebGLRenderingContext {
                 TexImageTarget::CubeMapNegativeY |
                 TexImageTarget::CubeMapPositiveZ |
                 TexImageTarget::CubeMapNegativeZ => binding.bound_texture_cube_map.get(),
+                TexImageTarget::Texture3D => binding.bound_texture_3d.get(),
+                TexImageTarget::Texture2DArray => binding.bound_texture_2d_array.get(),
             }
         })
     }
 
     pub fn recreate(&self, size: Size2D<i32>) {
+        // A lost context is created with the size of the canvas when restored.
+        if self.context_lost.get() {
+            return;
+        }
+
         let (sender, receiver) = webgl_channel().unwrap();
-        self.webgl_sender.send_resize(size, sender).unwrap();
+        self.webgl_sender.borrow().send_resize(size, sender).unwrap();
 
         if let Err(msg) = receiver.recv().unwrap() {
             error!("Error resizing WebGLContext: {}", msg);
@@ -362,20 +552,236 @@ impl WebGLRenderingContext {
     }
 
     pub fn webgl_sender(&self) -> WebGLMsgSender {
-        self.webgl_sender.clone()
+        self.webgl_sender.borrow().clone()
+    }
+
+    /// The GL context currently backing this context.
+    pub fn context_id(&self) -> WebGLContextId {
+        self.webgl_sender.borrow().context_id()
+    }
+
+    pub fn is_context_lost(&self) -> bool {
+        self.context_lost.get()
+    }
+
+    pub fn set_webgl2_context(&self, context: &WebGL2RenderingContext) {
+        self.webgl2.set(Some(context));
+    }
+
+    pub fn linked_programs(&self) -> Vec<DomRoot<WebGLProgram>> {
+        self.linked_programs.borrow().iter().map(|program| DomRoot::from_ref(&**program)).collect()
+    }
+
+    /// Replaces the source of the shader of the given type attached to a linked program
+    /// and relinks the program, as requested by the devtools shader editor. If the new
+    /// source doesn't compile or link, the previous one is restored and the info log is
+    /// returned. As with any relink, the uniform locations of the program are reset.
+    pub fn replace_shader_source(
+        &self,
+        program: &WebGLProgram,
+        shader_type: u32,
+        source: DOMString,
+    ) -> Result<(), String> {
+        if self.context_lost.get() {
+            return Err("The context is lost".to_owned());
+        }
+        let shader = program.attached_shaders().ok().and_then(|shaders| {
+            shaders.into_iter().find(|shader| shader.gl_type() == shader_type)
+        });
+        let shader = match shader {
+            Some(shader) => shader,
+            None => return Err("No shader of this type is attached to the program".to_owned()),
+        };
+
+        let previous_source = shader.source();
+        let result = self.compile_and_link(program, &shader, source);
+        if result.is_err() {
+            let _ = self.compile_and_link(program, &shader, previous_source);
+        }
+        result
+    }
+
+    fn compile_and_link(
+        &self,
+        program: &WebGLProgram,
+        shader: &WebGLShader,
+        source: DOMString,
+    ) -> Result<(), String> {
+        shader.set_source(source);
+        shader
+            .compile(self.webgl_version, self.glsl_version, &self.limits, &self.extension_manager)
+            .map_err(|error| format!("{:?}", error))?;
+        if !shader.successfully_compiled() {
+            return Err(shader.info_log().into());
+        }
+        program.link().map_err(|error| format!("{:?}", error))?;
+        if !program.is_linked() {
+            return Err(program.get_info_log().unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    /// Routes the notification the WebGL thread sends when it loses the GL
+    /// context on its own to the thread of this context.
+    fn listen_for_context_loss(&self, loss_receiver: IpcReceiver<WebGLContextId>) {
+        let global = self.global();
+        let context = Trusted::new(self);
+        let task_source = global.remote_event_task_source();
+        let canceller = global.task_canceller(TaskSourceName::RemoteEvent);
+        ROUTER.add_route(loss_receiver.to_opaque(), Box::new(move |message| {
+            let context = context.clone();
+            let context_id: WebGLContextId = message.to().unwrap();
+            let _ = task_source.queue_with_canceller(
+                task!(lose_webgl_context: move || {
+                    let context = context.root();
+                    // Ignore the loss of a GL context this context doesn't use anymore.
+                    if context.context_id() == context_id {
+                        context.lose_context(ContextLossCause::WebGLThread);
+                    }
+                }),
+                &canceller,
+            );
+        }));
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#CONTEXT_LOST
+    pub fn lose_context(&self, cause: ContextLossCause) {
+        if self.context_lost.replace(true) {
+            return;
+        }
+        self.restore_allowed.set(false);
+        self.last_error.set(Some(ContextLost));
+        let _ = self.webgl_sender.borrow().send_lose();
+        self.webrender_image.set(None);
+        self.mark_as_dirty();
+
+        let context = Trusted::new(self);
+        let global = self.global();
+        let _ = global.remote_event_task_source().queue(
+            task!(fire_webglcontextlost: move || {
+                let context = context.root();
+                let status = context.fire_context_event(atom!("webglcontextlost"), EventCancelable::Cancelable);
+                // The context can only be restored if the page asked for it.
+                if let EventStatus::NotCanceled = status {
+                    return;
+                }
+                match cause {
+                    // A simulated loss is only undone by `restoreContext()`.
+                    ContextLossCause::Extension => context.restore_allowed.set(true),
+                    ContextLossCause::WebGLThread => context.queue_restore_context(),
+                }
+            }),
+            &global,
+        );
+    }
+
+    /// https://www.khronos.org/registry/webgl/extensions/WEBGL_lose_context/
+    pub fn request_restore_context(&self) {
+        // The context can only be restored if the page prevented the default
+        // action of the webglcontextlost event.
+        if !self.context_lost.get() || !self.restore_allowed.get() {
+            // This error is recorded even while the context is lost.
+            if self.last_error.get().is_none() {
+                self.last_error.set(Some(InvalidOperation));
+            }
+            return;
+        }
+
+        self.restore_allowed.set(false);
+        self.queue_restore_context();
+    }
+
+    fn queue_restore_context(&self) {
+        let context = Trusted::new(self);
+        let global = self.global();
+        let _ = global.remote_event_task_source().queue(
+            task!(restore_webgl_context: move || {
+                context.root().restore_context();
+            }),
+            &global,
+        );
+    }
+
+    /// https://www.khronos.org/registry/webgl/specs/latest/1.0/#restore-the-drawing-buffer
+    fn restore_context(&self) {
+        if !self.context_lost.get() {
+            return;
+        }
+
+        let size = match self.canvas {
+            WebGLCanvas::HTMLCanvasElement(ref canvas) => canvas.get_size(),
+            WebGLCanvas::OffscreenCanvas(ref canvas) => canvas.get_size(),
+        };
+        let (loss_sender, loss_receiver) = ipc::channel().unwrap();
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.webgl_sender.borrow().send_restore(size, loss_sender, sender).unwrap();
+        let ctx_data = match receiver.recv().unwrap() {
+            Ok(ctx_data) => ctx_data,
+            Err(msg) => {
+                // The context stays lost and may be restored again later.
+                error!("Couldn't restore WebGLRenderingContext: {}", msg);
+                return;
+            },
+        };
+        *self.webgl_sender.borrow_mut() = ctx_data.sender;
+        self.listen_for_context_loss(loss_receiver);
+
+        // All the state of the context is reset to its default values.
+        self.texture_unpacking_settings.set(TextureUnpacking::CONVERT_COLORSPACE);
+        self.texture_unpacking_alignment.set(4);
+        self.bound_framebuffer.set(None);
+        self.bound_renderbuffer.set(None);
+        self.bound_textures.borrow_mut().clear();
+        self.bound_texture_unit.set(constants::TEXTURE0);
+        self.bound_buffer_array.set(None);
+        self.current_program.set(None);
+        self.linked_programs.borrow_mut().clear();
+        self.current_vertex_attrib_0.set((0f32, 0f32, 0f32, 1f32));
+        self.current_scissor.set((0, 0, size.width, size.height));
+        self.current_clear_color.set((0.0, 0.0, 0.0, 0.0));
+        self.capabilities.value.set(Default::default());
+        self.default_vao.set(None);
+        self.current_vao.set(None);
+        self.default_draw_buffer.set(constants::BACK);
+        if let Some(context) = self.webgl2.get() {
+            context.reset_state();
+        }
+
+        self.last_error.set(None);
+        self.webrender_image.set(None);
+        self.context_lost.set(false);
+        self.mark_as_dirty();
+        self.fire_context_event(atom!("webglcontextrestored"), EventCancelable::NotCancelable);
+    }
+
+    fn fire_context_event(&self, name: Atom, cancelable: EventCancelable) -> EventStatus {
+        let event = WebGLContextEvent::new(&self.global(),
+                                           name,
+                                           EventBubbles::DoesNotBubble,
+                                           cancelable,
+                                           DOMString::new());
+        match self.canvas {
+            WebGLCanvas::HTMLCanvasElement(ref canvas) => event.upcast::<Event>().fire(canvas.upcast()),
+            WebGLCanvas::OffscreenCanvas(ref canvas) => event.upcast::<Event>().fire(canvas.upcast()),
+        }
     }
 
     #[inline]
     pub fn send_command(&self, command: WebGLCommand) {
-        self.webgl_sender.send(command).unwrap();
+        self.webgl_sender.borrow().send(command).unwrap();
     }
 
     #[inline]
     pub fn send_vr_command(&self, command: WebVRCommand) {
-        self.webgl_sender.send_vr(command).unwrap();
+        self.webgl_sender.borrow().send_vr(command).unwrap();
     }
 
     pub fn webgl_error(&self, err: WebGLError) {
+        // Only CONTEXT_LOST_WEBGL is reported until the context is restored.
+        if self.context_lost.get() {
+            return;
+        }
+
         // TODO(emilio): Add useful debug messages to this
         warn!("WebGL error: {:?}, previous error was {:?}", err, self.last_error.get());
 
@@ -404,7 +810,7 @@ impl WebGLRenderingContext {
     //
     // The WebGL spec mentions a couple more operations that trigger
     // this: clear() and getParameter(IMPLEMENTATION_COLOR_READ_*).
-    fn validate_framebuffer_complete(&self) -> bool {
+    pub fn validate_framebuffer_complete(&self) -> bool {
         match self.bound_framebuffer.get() {
             Some(fb) => match fb.check_status() {
                 constants::FRAMEBUFFER_COMPLETE => return true,
@@ -418,11 +824,14 @@ impl WebGLRenderingContext {
         }
     }
 
-    fn validate_ownership<T>(&self, object: &T) -> WebGLResult<()>
+    pub fn validate_ownership<T>(&self, object: &T) -> WebGLResult<()>
     where
         T: DerivedFrom<WebGLObject>,
     {
-        if self != object.upcast().context() {
+        // Objects created before the context was lost belong to a GL
+        // context which doesn't exist anymore.
+        let object = object.upcast();
+        if self.context_lost.get() || self != object.context() || object.context_id() != self.context_id() {
             return Err(InvalidOperation);
         }
         Ok(())
@@ -447,11 +856,10 @@ impl WebGLRenderingContext {
     }
 
     fn tex_parameter(&self, target: u32, param: u32, value: TexParameterValue) {
-        let texture = match target {
-            constants::TEXTURE_2D |
-            constants::TEXTURE_CUBE_MAP => self.bound_texture(target),
-            _ => return self.webgl_error(InvalidEnum),
-        };
+        if !self.is_texture_target_enabled(target) {
+            return self.webgl_error(InvalidEnum);
+        }
+        let texture = self.bound_texture(target);
 
         let texture = match texture {
             Some(tex) => tex,
@@ -484,8 +892,26 @@ impl WebGLRenderingContext {
         }
     }
 
+    /// Returns whether `target` is a texture target of this context's version
+    /// of WebGL.
+    fn is_texture_target_enabled(&self, target: u32) -> bool {
+        match target {
+            constants::TEXTURE_2D | constants::TEXTURE_CUBE_MAP => true,
+            WebGl2Constants::TEXTURE_3D |
+            WebGl2Constants::TEXTURE_2D_ARRAY => self.webgl_version == WebGLVersion::WebGL2,
+            _ => false,
+        }
+    }
+
     fn mark_as_dirty(&self) {
-        self.canvas.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        match self.canvas {
+            WebGLCanvas::HTMLCanvasElement(ref canvas) => {
+                canvas.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+            },
+            // An offscreen canvas has no node to repaint, but it may present
+            // its frames in the placeholder canvas it was transferred from.
+            WebGLCanvas::OffscreenCanvas(ref canvas) => canvas.queue_webgl_commit(),
+        }
     }
 
     fn vertex_attrib(&self, indx: u32, x: f32, y: f32, z: f32, w: f32) {
@@ -576,8 +1002,10 @@ impl WebGLRenderingContext {
         let pixel_count = pixels.len() / 4;
 
         match (format, data_type) {
-            (TexFormat::RGBA, TexDataType::UnsignedByte) => pixels,
-            (TexFormat::RGB, TexDataType::UnsignedByte) => {
+            (TexFormat::RGBA, TexDataType::UnsignedByte) |
+            (TexFormat::SRGBAlpha, TexDataType::UnsignedByte) => pixels,
+            (TexFormat::RGB, TexDataType::UnsignedByte) |
+            (TexFormat::SRGB, TexDataType::UnsignedByte) => {
                 // Remove alpha channel
                 let mut rgb8 = Vec::<u8>::with_capacity(pixel_count * 3);
                 for rgba8 in pixels.chunks(4) {
@@ -768,7 +1196,7 @@ impl WebGLRenderingContext {
                     None => return Err(()),
                 };
 
-                let window = window_from_node(&*self.canvas);
+                let window = window_from_node(&*image);
 
                 let img = match canvas_utils::request_image_from_cache(&window, img_url) {
                     ImageResponse::Loaded(img, _) => img,
@@ -825,7 +1253,8 @@ impl WebGLRenderingContext {
         // data to be read.
         // If it is UNSIGNED_BYTE, a Uint8Array must be supplied;
         // if it is UNSIGNED_SHORT_5_6_5, UNSIGNED_SHORT_4_4_4_4,
-        // or UNSIGNED_SHORT_5_5_5_1, a Uint16Array must be supplied.
+        // UNSIGNED_SHORT_5_5_5_1 or UNSIGNED_SHORT, a Uint16Array must be supplied.
+        // If it is UNSIGNED_INT or UNSIGNED_INT_24_8_WEBGL, a Uint32Array must be supplied.
         // or FLOAT, a Float32Array must be supplied.
         // If the types do not match, an INVALID_OPERATION error is generated.
         let received_size = match *data {
@@ -833,7 +1262,7 @@ impl WebGLRenderingContext {
             Some(ref buffer) => match buffer.get_array_type() {
                 Type::Uint8 => 1,
                 Type::Uint16 => 2,
-                Type::Float32 => 4,
+                Type::Uint32 | Type::Float32 => 4,
                 _ => {
                     self.webgl_error(InvalidOperation);
                     return Err(());
@@ -902,7 +1331,8 @@ impl WebGLRenderingContext {
         }
 
         match (format, data_type) {
-            (TexFormat::RGBA, TexDataType::UnsignedByte) => {
+            (TexFormat::RGBA, TexDataType::UnsignedByte) |
+            (TexFormat::SRGBAlpha, TexDataType::UnsignedByte) => {
                 let mut premul = Vec::<u8>::with_capacity(pixels.len());
                 for rgba in pixels.chunks(4) {
                     premul.push(multiply_u8_pixel(rgba[0], rgba[3]));
@@ -1020,7 +1450,7 @@ impl WebGLRenderingContext {
                                                                height, 1,
                                                                internal_format,
                                                                level,
-                                                               Some(data_type)));
+                                                               Some(data_type)), return);
 
         // Set the unpack alignment.  For textures coming from arrays,
         // this will be the current value of the context's
@@ -1029,9 +1459,12 @@ impl WebGLRenderingContext {
         // will be 1.
         self.send_command(WebGLCommand::PixelStorei(constants::UNPACK_ALIGNMENT, unpacking_alignment as i32));
 
-        let format = internal_format.as_gl_constant();
+        let format = tex_upload_format(internal_format);
         let data_type = data_type.as_gl_constant();
-        let internal_format = self.extension_manager.get_effective_tex_internal_format(format, data_type);
+        let internal_format = self.extension_manager.get_effective_tex_internal_format(
+            internal_format.as_gl_constant(),
+            data_type,
+        );
 
         // TODO(emilio): convert colorspace if requested
         let msg = WebGLCommand::TexImage2D(
@@ -1096,7 +1529,7 @@ impl WebGLRenderingContext {
             yoffset,
             width as i32,
             height as i32,
-            format.as_gl_constant(),
+            tex_upload_format(format),
             data_type.as_gl_constant(),
             pixels.into(),
         );
@@ -1116,7 +1549,7 @@ impl WebGLRenderingContext {
                 // WR using ExternalTexture requires a single update message.
                 self.webrender_image.get().unwrap_or_else(|| {
                     let (sender, receiver) = webgl_channel().unwrap();
-                    self.webgl_sender.send_update_wr_image(sender).unwrap();
+                    self.webgl_sender.borrow().send_update_wr_image(sender).unwrap();
                     let image_key = receiver.recv().unwrap();
                     self.webrender_image.set(Some(image_key));
 
@@ -1127,7 +1560,7 @@ impl WebGLRenderingContext {
                 // WR using Readback requires to update WR image every frame
                 // in order to send the new raw pixels.
                 let (sender, receiver) = webgl_channel().unwrap();
-                self.webgl_sender.send_update_wr_image(sender).unwrap();
+                self.webgl_sender.borrow().send_update_wr_image(sender).unwrap();
                 receiver.recv().unwrap()
             }
         }
@@ -1366,6 +1799,188 @@ impl WebGLRenderingContext {
         self.current_vao.set(vao);
     }
 
+    /// Binds `buffer` to `target`, keeping track of it in `slot`. Used by
+    /// bindBuffer for all the buffer targets of WebGL 1.0 and 2.0.
+    pub fn bind_buffer_maybe(
+        &self,
+        slot: &MutNullableDom<WebGLBuffer>,
+        target: u32,
+        buffer: Option<&WebGLBuffer>,
+    ) {
+        if let Some(buffer) = buffer {
+            handle_potential_webgl_error!(self, self.validate_ownership(buffer), return);
+            if buffer.is_marked_for_deletion() {
+                return self.webgl_error(InvalidOperation);
+            }
+            handle_potential_webgl_error!(self, buffer.set_target(target), return);
+            buffer.increment_attached_counter();
+        }
+        self.send_command(WebGLCommand::BindBuffer(target, buffer.map(|b| b.id())));
+        if let Some(old) = slot.get() {
+            old.decrement_attached_counter();
+        }
+        slot.set(buffer);
+    }
+
+    pub fn bound_framebuffer(&self) -> Option<DomRoot<WebGLFramebuffer>> {
+        self.bound_framebuffer.get()
+    }
+
+    /// Binds `framebuffer` to `target`, keeping track of it in `slot`. In
+    /// WebGL 2.0, `target` may also be READ_FRAMEBUFFER or DRAW_FRAMEBUFFER.
+    pub fn bind_framebuffer_to(
+        &self,
+        target: u32,
+        framebuffer: Option<&WebGLFramebuffer>,
+        slot: &MutNullableDom<WebGLFramebuffer>,
+    ) {
+        if let Some(framebuffer) = framebuffer {
+            handle_potential_webgl_error!(self, self.validate_ownership(framebuffer), return);
+            if framebuffer.is_deleted() {
+                // From the WebGL spec:
+                //
+                //     "An attempt to bind a deleted framebuffer will
+                //      generate an INVALID_OPERATION error, and the
+                //      current binding will remain untouched."
+                return self.webgl_error(InvalidOperation);
+            }
+            framebuffer.bind(target);
+        } else {
+            // Bind the default framebuffer
+            let cmd = WebGLCommand::BindFramebuffer(target, WebGLFramebufferBindingRequest::Default);
+            self.send_command(cmd);
+        }
+        slot.set(framebuffer);
+    }
+
+    #[allow(unsafe_code)]
+    pub unsafe fn buffer_data(
+        &self,
+        cx: *mut JSContext,
+        target: u32,
+        data: *mut JSObject,
+        usage: u32,
+        bound_buffer: WebGLResult<Option<DomRoot<WebGLBuffer>>>,
+    ) -> ErrorResult {
+        if data.is_null() {
+            return Ok(self.webgl_error(InvalidValue));
+        }
+
+        typedarray!(in(cx) let array_buffer: ArrayBuffer = data);
+        let data_vec = match array_buffer {
+            Ok(mut data) => data.to_vec(),
+            Err(_) => fallible_array_buffer_view_to_vec(cx, data)?,
+        };
+
+        let bound_buffer = handle_potential_webgl_error!(self, bound_buffer, return Ok(()));
+        let bound_buffer = match bound_buffer {
+            Some(bound_buffer) => bound_buffer,
+            None => return Ok(self.webgl_error(InvalidOperation)),
+        };
+
+        handle_potential_webgl_error!(self, bound_buffer.buffer_data(target, data_vec, usage));
+        Ok(())
+    }
+
+    pub fn buffer_data_(
+        &self,
+        target: u32,
+        size: i64,
+        usage: u32,
+        bound_buffer: WebGLResult<Option<DomRoot<WebGLBuffer>>>,
+    ) -> ErrorResult {
+        let bound_buffer = handle_potential_webgl_error!(self, bound_buffer, return Ok(()));
+        let bound_buffer = match bound_buffer {
+            Some(bound_buffer) => bound_buffer,
+            None => return Ok(self.webgl_error(InvalidOperation)),
+        };
+
+        if size < 0 {
+            return Ok(self.webgl_error(InvalidValue));
+        }
+
+        // FIXME: Allocating a buffer based on user-requested size is
+        // not great, but we don't have a fallible allocation to try.
+        let data = vec![0u8; size as usize];
+        handle_potential_webgl_error!(self, bound_buffer.buffer_data(target, data, usage));
+        Ok(())
+    }
+
+    pub fn buffer_sub_data(
+        &self,
+        target: u32,
+        offset: i64,
+        data: Vec<u8>,
+        bound_buffer: WebGLResult<Option<DomRoot<WebGLBuffer>>>,
+    ) {
+        let bound_buffer = handle_potential_webgl_error!(self, bound_buffer, return);
+        let bound_buffer = match bound_buffer {
+            Some(bound_buffer) => bound_buffer,
+            None => return self.webgl_error(InvalidOperation),
+        };
+
+        if offset < 0 {
+            return self.webgl_error(InvalidValue);
+        }
+
+        if (offset as usize) + data.len() > bound_buffer.capacity() {
+            return self.webgl_error(InvalidValue);
+        }
+        self.send_command(WebGLCommand::BufferSubData(
+            target,
+            offset as isize,
+            data.into(),
+        ));
+    }
+
+    pub fn get_buffer_parameter(
+        &self,
+        bound_buffer: WebGLResult<Option<DomRoot<WebGLBuffer>>>,
+        parameter: u32,
+    ) -> JSVal {
+        let buffer = handle_potential_webgl_error!(
+            self,
+            bound_buffer.and_then(|buf| buf.ok_or(InvalidOperation)),
+            return NullValue()
+        );
+
+        match parameter {
+            constants::BUFFER_SIZE => Int32Value(buffer.capacity() as i32),
+            constants::BUFFER_USAGE => Int32Value(buffer.usage() as i32),
+            _ => {
+                self.webgl_error(InvalidEnum);
+                NullValue()
+            }
+        }
+    }
+
+    /// Allocates storage for the bound renderbuffer, multisampled if
+    /// `samples` is non-zero.
+    pub fn renderbuffer_storage(&self, target: u32, samples: i32, internal_format: u32, width: i32, height: i32) {
+        if target != constants::RENDERBUFFER {
+            return self.webgl_error(InvalidEnum);
+        }
+
+        let max = self.limits.max_renderbuffer_size;
+
+        if samples < 0 || width < 0 || width as u32 > max || height < 0 || height as u32 > max {
+            return self.webgl_error(InvalidValue);
+        }
+
+        match self.bound_renderbuffer.get() {
+            Some(rb) => {
+                handle_potential_webgl_error!(self, rb.storage(samples, internal_format, width, height));
+                if let Some(fb) = self.bound_framebuffer.get() {
+                    fb.invalidate_renderbuffer(&*rb);
+                }
+            }
+            None => self.webgl_error(InvalidOperation),
+        };
+
+        // FIXME: We need to clear the renderbuffer before it can be
+        // accessed.  See https://github.com/servo/servo/issues/13710
+    }
+
     fn validate_blend_mode(&self, mode: u32) -> WebGLResult<()> {
         match mode {
             constants::FUNC_ADD |
@@ -1384,7 +1999,7 @@ impl WebGLRenderingContext {
 
 impl Drop for WebGLRenderingContext {
     fn drop(&mut self) {
-        let _ = self.webgl_sender.send_remove();
+        let _ = self.webgl_sender.borrow().send_remove();
     }
 }
 
@@ -1403,8 +2018,15 @@ unsafe fn fallible_array_buffer_view_to_vec(
 
 impl WebGLRenderingContextMethods for WebGLRenderingContext {
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.1
-    fn Canvas(&self) -> DomRoot<HTMLCanvasElement> {
-        DomRoot::from_ref(&*self.canvas)
+    fn Canvas(&self) -> HTMLCanvasElementOrOffscreenCanvas {
+        match self.canvas {
+            WebGLCanvas::HTMLCanvasElement(ref canvas) => {
+                HTMLCanvasElementOrOffscreenCanvas::HTMLCanvasElement(DomRoot::from_ref(&*canvas))
+            },
+            WebGLCanvas::OffscreenCanvas(ref canvas) => {
+                HTMLCanvasElementOrOffscreenCanvas::OffscreenCanvas(DomRoot::from_ref(&*canvas))
+            },
+        }
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.11
@@ -1441,25 +2063,16 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         target: u32,
         parameter: u32,
     ) -> JSVal {
-        let buffer = handle_potential_webgl_error!(
-            self,
-            self.bound_buffer(target).and_then(|buf| buf.ok_or(InvalidOperation)),
-            return NullValue()
-        );
-
-        match parameter {
-            constants::BUFFER_SIZE => Int32Value(buffer.capacity() as i32),
-            constants::BUFFER_USAGE => Int32Value(buffer.usage() as i32),
-            _ => {
-                self.webgl_error(InvalidEnum);
-                NullValue()
-            }
-        }
+        self.get_buffer_parameter(self.bound_buffer(target), parameter)
     }
 
     #[allow(unsafe_code)]
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.3
     unsafe fn GetParameter(&self, cx: *mut JSContext, parameter: u32) -> JSVal {
+        if self.context_lost.get() {
+            return NullValue();
+        }
+
         if !self.extension_manager.is_get_parameter_name_enabled(parameter) {
             self.webgl_error(WebGLError::InvalidEnum);
             return NullValue();
@@ -1514,11 +2127,11 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                 }
             }
             constants::COMPRESSED_TEXTURE_FORMATS => {
-                // FIXME(nox): https://github.com/servo/servo/issues/20594
+                let formats = self.extension_manager.get_tex_compression_formats();
                 rooted!(in(cx) let mut rval = ptr::null_mut::<JSObject>());
                 let _ = Uint32Array::create(
                     cx,
-                    CreateWith::Slice(&[]),
+                    CreateWith::Slice(&formats),
                     rval.handle_mut(),
                 ).unwrap();
                 return ObjectValue(rval.get());
@@ -1538,6 +2151,36 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                 "WebGL GLSL ES 1.0".to_jsval(cx, rval.handle_mut());
                 return rval.get();
             }
+            WEBGLDebugRendererInfoConstants::UNMASKED_VENDOR_WEBGL => {
+                rooted!(in(cx) let mut rval = UndefinedValue());
+                self.extension_manager.capabilities().vendor.to_jsval(cx, rval.handle_mut());
+                return rval.get();
+            }
+            WEBGLDebugRendererInfoConstants::UNMASKED_RENDERER_WEBGL => {
+                rooted!(in(cx) let mut rval = UndefinedValue());
+                self.extension_manager.capabilities().renderer.to_jsval(cx, rval.handle_mut());
+                return rval.get();
+            }
+            WEBGLDrawBuffersConstants::DRAW_BUFFER0_WEBGL...WEBGLDrawBuffersConstants::DRAW_BUFFER15_WEBGL => {
+                let index = parameter - WEBGLDrawBuffersConstants::DRAW_BUFFER0_WEBGL;
+                match self.draw_buffer(index) {
+                    Some(buffer) => return Int32Value(buffer as i32),
+                    None => {
+                        self.webgl_error(InvalidEnum);
+                        return NullValue();
+                    }
+                }
+            }
+            EXTDisjointTimerQueryConstants::GPU_DISJOINT_EXT => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetGpuDisjoint(sender));
+                return BooleanValue(receiver.recv().unwrap());
+            }
+            EXTDisjointTimerQueryConstants::TIMESTAMP_EXT => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.send_command(WebGLCommand::GetTimestamp(sender));
+                return DoubleValue(receiver.recv().unwrap() as f64);
+            }
             constants::UNPACK_FLIP_Y_WEBGL => {
                 let unpack = self.texture_unpacking_settings.get();
                 return BooleanValue(unpack.contains(TextureUnpacking::FLIP_Y_AXIS));
@@ -1572,6 +2215,8 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                 Some(self.limits.max_vertex_texture_image_units),
             constants::MAX_VERTEX_UNIFORM_VECTORS =>
                 Some(self.limits.max_vertex_uniform_vectors),
+            WEBGLDrawBuffersConstants::MAX_DRAW_BUFFERS_WEBGL =>
+                Some(self.extension_manager.capabilities().max_draw_buffers),
             _ => None,
         };
         if let Some(limit) = limit {
@@ -1655,14 +2300,10 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
     #[allow(unsafe_code)]
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
     unsafe fn GetTexParameter(&self, _cx: *mut JSContext, target: u32, pname: u32) -> JSVal {
-        match target {
-            constants::TEXTURE_2D |
-            constants::TEXTURE_CUBE_MAP => {},
-            _ => {
-                self.webgl_error(InvalidEnum);
-                return NullValue();
-            }
-        };
+        if !self.is_texture_target_enabled(target) {
+            self.webgl_error(InvalidEnum);
+            return NullValue();
+        }
 
         if !self.extension_manager.is_get_tex_parameter_name_enabled(pname) {
             self.webgl_error(InvalidEnum);
@@ -1680,6 +2321,8 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         match pname {
             constants::TEXTURE_MAG_FILTER => return UInt32Value(texture.mag_filter()),
             constants::TEXTURE_MIN_FILTER => return UInt32Value(texture.min_filter()),
+            WebGl2Constants::TEXTURE_IMMUTABLE_FORMAT => return BooleanValue(texture.is_immutable()),
+            WebGl2Constants::TEXTURE_IMMUTABLE_LEVELS => return UInt32Value(texture.immutable_levels()),
             _ => {}
         }
 
@@ -1715,16 +2358,26 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         error_code
     }
 
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.13
+    fn IsContextLost(&self) -> bool {
+        self.context_lost.get()
+    }
+
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.2
     fn GetContextAttributes(&self) -> Option<WebGLContextAttributes> {
         let (sender, receiver) = webgl_channel().unwrap();
 
         // If the send does not succeed, assume context lost
-        if self.webgl_sender.send(WebGLCommand::GetContextAttributes(sender)).is_err() {
+        if self.context_lost.get() ||
+           self.webgl_sender.borrow().send(WebGLCommand::GetContextAttributes(sender)).is_err() {
             return None;
         }
 
-        let attrs = receiver.recv().unwrap();
+        // The WebGL thread drops the sender if it lost the context in the meantime.
+        let attrs = match receiver.recv() {
+            Ok(attrs) => attrs,
+            Err(_) => return None,
+        };
 
         Some(WebGLContextAttributes {
             alpha: attrs.alpha,
@@ -1740,6 +2393,9 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.14
     fn GetSupportedExtensions(&self) -> Option<Vec<DOMString>> {
+        if self.context_lost.get() {
+            return None;
+        }
         self.extension_manager.init_once(|| {
             self.get_gl_extensions()
         });
@@ -1751,6 +2407,9 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.14
     unsafe fn GetExtension(&self, _cx: *mut JSContext, name: DOMString)
                     -> Option<NonNull<JSObject>> {
+        if self.context_lost.get() {
+            return None;
+        }
         self.extension_manager.init_once(|| {
             self.get_gl_extensions()
         });
@@ -1837,10 +2496,6 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn BindBuffer(&self, target: u32, buffer: Option<&WebGLBuffer>) {
-        if let Some(buffer) = buffer {
-            handle_potential_webgl_error!(self, self.validate_ownership(buffer), return);
-        }
-
         let current_vao;
         let slot = match target {
             constants::ARRAY_BUFFER => {
@@ -1852,49 +2507,15 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             }
             _ => return self.webgl_error(InvalidEnum),
         };
-
-        if let Some(buffer) = buffer {
-            if buffer.is_marked_for_deletion() {
-                return self.webgl_error(InvalidOperation);
-            }
-            handle_potential_webgl_error!(self, buffer.set_target(target), return);
-            buffer.increment_attached_counter();
-        }
-        self.send_command(WebGLCommand::BindBuffer(target, buffer.map(|b| b.id())));
-        if let Some(old) = slot.get() {
-            old.decrement_attached_counter();
-        }
-        slot.set(buffer);
+        self.bind_buffer_maybe(slot, target, buffer);
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
     fn BindFramebuffer(&self, target: u32, framebuffer: Option<&WebGLFramebuffer>) {
-        if let Some(fb) = framebuffer {
-            handle_potential_webgl_error!(self, self.validate_ownership(fb), return);
-        }
-
         if target != constants::FRAMEBUFFER {
             return self.webgl_error(InvalidEnum);
         }
-
-        if let Some(framebuffer) = framebuffer {
-            if framebuffer.is_deleted() {
-                // From the WebGL spec:
-                //
-                //     "An attempt to bind a deleted framebuffer will
-                //      generate an INVALID_OPERATION error, and the
-                //      current binding will remain untouched."
-                return self.webgl_error(InvalidOperation);
-            } else {
-                framebuffer.bind(target);
-                self.bound_framebuffer.set(Some(framebuffer));
-            }
-        } else {
-            // Bind the default framebuffer
-            let cmd = WebGLCommand::BindFramebuffer(target, WebGLFramebufferBindingRequest::Default);
-            self.send_command(cmd);
-            self.bound_framebuffer.set(framebuffer);
-        }
+        self.bind_framebuffer_to(target, framebuffer, &self.bound_framebuffer);
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
@@ -1935,6 +2556,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         let slot = match target {
             constants::TEXTURE_2D => &binding.bound_texture_2d,
             constants::TEXTURE_CUBE_MAP => &binding.bound_texture_cube_map,
+            WebGl2Constants::TEXTURE_3D if self.webgl_version == WebGLVersion::WebGL2 => {
+                &binding.bound_texture_3d
+            }
+            WebGl2Constants::TEXTURE_2D_ARRAY if self.webgl_version == WebGLVersion::WebGL2 => {
+                &binding.bound_texture_2d_array
+            }
             _ => return self.webgl_error(InvalidEnum),
         };
 
@@ -1952,11 +2579,10 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
     fn GenerateMipmap(&self, target: u32) {
-        let texture = match target {
-            constants::TEXTURE_2D |
-            constants::TEXTURE_CUBE_MAP => self.bound_texture(target),
-            _ => return self.webgl_error(InvalidEnum),
-        };
+        if !self.is_texture_target_enabled(target) {
+            return self.webgl_error(InvalidEnum);
+        }
+        let texture = self.bound_texture(target);
 
         match texture {
             Some(texture) => handle_potential_webgl_error!(self, texture.generate_mipmap()),
@@ -1973,43 +2599,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         data: *mut JSObject,
         usage: u32,
     ) -> ErrorResult {
-        if data.is_null() {
-            return Ok(self.webgl_error(InvalidValue));
-        }
-
-        typedarray!(in(cx) let array_buffer: ArrayBuffer = data);
-        let data_vec = match array_buffer {
-            Ok(mut data) => data.to_vec(),
-            Err(_) => fallible_array_buffer_view_to_vec(cx, data)?,
-        };
-
-        let bound_buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return Ok(()));
-        let bound_buffer = match bound_buffer {
-            Some(bound_buffer) => bound_buffer,
-            None => return Ok(self.webgl_error(InvalidOperation)),
-        };
-
-        handle_potential_webgl_error!(self, bound_buffer.buffer_data(target, data_vec, usage));
-        Ok(())
+        self.buffer_data(cx, target, data, usage, self.bound_buffer(target))
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
     fn BufferData_(&self, target: u32, size: i64, usage: u32) -> ErrorResult {
-        let bound_buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return Ok(()));
-        let bound_buffer = match bound_buffer {
-            Some(bound_buffer) => bound_buffer,
-            None => return Ok(self.webgl_error(InvalidOperation)),
-        };
-
-        if size < 0 {
-            return Ok(self.webgl_error(InvalidValue));
-        }
-
-        // FIXME: Allocating a buffer based on user-requested size is
-        // not great, but we don't have a fallible allocation to try.
-        let data = vec![0u8; size as usize];
-        handle_potential_webgl_error!(self, bound_buffer.buffer_data(target, data, usage));
-        Ok(())
+        self.buffer_data_(target, size, usage, self.bound_buffer(target))
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.5
@@ -2019,43 +2614,80 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             ArrayBufferViewOrArrayBuffer::ArrayBuffer(mut inner) => inner.to_vec(),
             ArrayBufferViewOrArrayBuffer::ArrayBufferView(mut inner) => inner.to_vec(),
         };
+        self.buffer_sub_data(target, offset, data_vec, self.bound_buffer(target))
+    }
 
-        let bound_buffer = handle_potential_webgl_error!(self, self.bound_buffer(target), return);
-        let bound_buffer = match bound_buffer {
-            Some(bound_buffer) => bound_buffer,
-            None => return self.webgl_error(InvalidOperation),
+    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
+    fn CompressedTexImage2D(&self, target: u32, level: i32, internal_format: u32,
+                            width: i32, height: i32, border: i32,
+                            mut data: CustomAutoRooterGuard<ArrayBufferView>) {
+        let data = data.to_vec();
+        let validator = CompressedTexImage2DValidator::new(self, target, level, width, height,
+                                                           border, internal_format, data.len());
+        let CompressedTexImage2DValidatorResult {
+            texture,
+            target,
+            level,
+            width,
+            height,
+            compression,
+        } = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return,
         };
 
-        if offset < 0 {
-            return self.webgl_error(InvalidValue);
-        }
+        handle_potential_webgl_error!(self, texture.initialize(target,
+                                                               width,
+                                                               height, 1,
+                                                               compression.format,
+                                                               level,
+                                                               None), return);
+
+        self.send_command(WebGLCommand::CompressedTexImage2D {
+            target: target.as_gl_constant(),
+            level: level as i32,
+            internal_format: compression.format.as_gl_constant(),
+            width: width as i32,
+            height: height as i32,
+            data: data.into(),
+        });
 
-        if (offset as usize) + data_vec.len() > bound_buffer.capacity() {
-            return self.webgl_error(InvalidValue);
+        if let Some(fb) = self.bound_framebuffer.get() {
+            fb.invalidate_texture(&*texture);
         }
-        self.send_command(WebGLCommand::BufferSubData(
-            target,
-            offset as isize,
-            data_vec.into(),
-        ));
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
-    fn CompressedTexImage2D(&self, _target: u32, _level: i32, _internal_format: u32,
-                            _width: i32, _height: i32, _border: i32,
-                            _data: CustomAutoRooterGuard<ArrayBufferView>) {
-        // FIXME: No compressed texture format is currently supported, so error out as per
-        // https://www.khronos.org/registry/webgl/specs/latest/1.0/#COMPRESSED_TEXTURE_SUPPORT
-        self.webgl_error(InvalidEnum);
-    }
+    fn CompressedTexSubImage2D(&self, target: u32, level: i32, xoffset: i32,
+                               yoffset: i32, width: i32, height: i32, format: u32,
+                               mut data: CustomAutoRooterGuard<ArrayBufferView>) {
+        let data = data.to_vec();
+        let validator = CompressedTexSubImage2DValidator::new(self, target, level,
+                                                              xoffset, yoffset,
+                                                              width, height,
+                                                              format, data.len());
+        let CompressedTexImage2DValidatorResult {
+            target,
+            level,
+            width,
+            height,
+            compression,
+            ..
+        } = match validator.validate() {
+            Ok(result) => result,
+            Err(_) => return,
+        };
 
-    // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
-    fn CompressedTexSubImage2D(&self, _target: u32, _level: i32, _xoffset: i32,
-                                     _yoffset: i32, _width: i32, _height: i32, _format: u32,
-                               _data: CustomAutoRooterGuard<ArrayBufferView>) {
-        // FIXME: No compressed texture format is currently supported, so error out as per
-        // https://www.khronos.org/registry/webgl/specs/latest/1.0/#COMPRESSED_TEXTURE_SUPPORT
-        self.webgl_error(InvalidEnum);
+        self.send_command(WebGLCommand::CompressedTexSubImage2D {
+            target: target.as_gl_constant(),
+            level: level as i32,
+            xoffset: xoffset,
+            yoffset: yoffset,
+            width: width as i32,
+            height: height as i32,
+            format: compression.format.as_gl_constant(),
+            data: data.into(),
+        });
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.8
@@ -2081,6 +2713,11 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             Err(_) => return,
         };
 
+        // WEBGL_depth_texture doesn't allow copying into depth textures.
+        if internal_format.is_depth() {
+            return self.webgl_error(InvalidOperation);
+        }
+
         let image_info = texture.image_info_for_target(&target, level);
 
         // The color buffer components can be dropped during the conversion to
@@ -2103,7 +2740,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                                                                height as u32, 1,
                                                                internal_format,
                                                                level as u32,
-                                                               None));
+                                                               None), return);
 
         let msg = WebGLCommand::CopyTexImage2D(target.as_gl_constant(),
                                                level as i32,
@@ -2151,6 +2788,13 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                 return;
         }
 
+        // Neither compressed nor depth textures can be the destination of a copy.
+        if let Some(format) = image_info.internal_format() {
+            if format.is_compressed() || format.is_depth() {
+                return self.webgl_error(InvalidOperation);
+            }
+        }
+
         let msg = WebGLCommand::CopyTexSubImage2D(target.as_gl_constant(),
                                                   level as i32, xoffset, yoffset,
                                                   x, y,
@@ -2402,7 +3046,8 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
     fn DeleteProgram(&self, program: Option<&WebGLProgram>) {
         if let Some(program) = program {
             handle_potential_webgl_error!(self, self.validate_ownership(program), return);
-            program.mark_for_deletion()
+            program.mark_for_deletion();
+            self.linked_programs.borrow_mut().retain(|linked| linked.id() != program.id());
         }
     }
 
@@ -2491,12 +3136,17 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             _ => false
         };
         let attachment_matches = match attachment {
-            // constants::MAX_COLOR_ATTACHMENTS ... gl::COLOR_ATTACHMENT0 |
             // constants::BACK |
             constants::COLOR_ATTACHMENT0 |
             constants::DEPTH_STENCIL_ATTACHMENT |
             constants::DEPTH_ATTACHMENT |
             constants::STENCIL_ATTACHMENT => true,
+            // The other color attachments are exposed by WEBGL_draw_buffers.
+            WebGl2Constants::COLOR_ATTACHMENT1...WebGl2Constants::COLOR_ATTACHMENT15 => {
+                self.extension_manager.is_draw_buffers_enabled() &&
+                    attachment - constants::COLOR_ATTACHMENT0 <
+                    self.extension_manager.capabilities().max_color_attachments
+            }
             _ => false,
         };
         let pname_matches = match pname {
@@ -2513,6 +3163,9 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE |
             constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_CUBE_MAP_FACE |
             constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_LEVEL => true,
+            EXTsRGBConstants::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING_EXT => {
+                self.extension_manager.is_enabled::<EXTsRGB>()
+            }
             _ => false
         };
 
@@ -2522,7 +3175,8 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                     WebGLFramebufferAttachmentRoot::Renderbuffer(_) => {
                         match pname {
                             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE |
-                            constants::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME => true,
+                            constants::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME |
+                            EXTsRGBConstants::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING_EXT => true,
                             _ => false
                         }
                     },
@@ -2531,7 +3185,8 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE |
                             constants::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME |
                             constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_LEVEL |
-                            constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_CUBE_MAP_FACE => true,
+                            constants::FRAMEBUFFER_ATTACHMENT_TEXTURE_CUBE_MAP_FACE |
+                            EXTsRGBConstants::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING_EXT => true,
                             _ => false
                         }
                     }
@@ -2723,7 +3378,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
                                                                  sender));
 
         let (range_min, range_max, precision) = receiver.recv().unwrap();
-        Some(WebGLShaderPrecisionFormat::new(self.global().as_window(), range_min, range_max, precision))
+        Some(WebGLShaderPrecisionFormat::new(&self.global(), range_min, range_max, precision))
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.10
@@ -2943,7 +3598,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             Some(ref mut data) => (data.get_array_type(), unsafe { data.as_mut_slice() }),
         };
 
-        if !self.validate_framebuffer_complete() {
+        if self.context_lost.get() || !self.validate_framebuffer_complete() {
             return;
         }
 
@@ -3125,6 +3780,10 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             return self.webgl_error(InvalidValue);
         }
         handle_potential_webgl_error!(self, program.link());
+        let mut linked_programs = self.linked_programs.borrow_mut();
+        if program.is_linked() && !linked_programs.iter().any(|linked| linked.id() == program.id()) {
+            linked_programs.push(Dom::from_ref(program));
+        }
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.9
@@ -3835,6 +4494,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             Err(_) => return Ok(()), // NB: The validator sets the correct error for us.
         };
 
+        // From the WEBGL_depth_texture spec, depth textures can't be
+        // specified with pixel data in WebGL 1.0.
+        if format.is_depth() && self.webgl_version() == WebGLVersion::WebGL1 && pixels.is_some() {
+            return Ok(self.webgl_error(InvalidOperation));
+        }
+
         let unpacking_alignment = self.texture_unpacking_alignment.get();
 
         let expected_byte_length =
@@ -3918,6 +4583,11 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             return Ok(()); // The validator sets the correct error for use
         }
 
+        // Depth textures can't be specified from a DOM source.
+        if format.is_depth() {
+            return Ok(self.webgl_error(InvalidOperation));
+        }
+
         let unpacking_alignment = 1;
         let pixels = self.prepare_pixels(format, data_type, width, height,
                                          unpacking_alignment, premultiplied, true, pixels);
@@ -3956,12 +4626,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
 
         texture.set_attached_to_dom();
 
-        let command = DOMToTextureCommand::Attach(self.webgl_sender.context_id(),
+        let command = DOMToTextureCommand::Attach(self.webgl_sender.borrow().context_id(),
                                                   texture.id(),
                                                   document_id,
                                                   pipeline_id.to_webrender(),
                                                   Size2D::new(width, height));
-        self.webgl_sender.send_dom_to_texture(command).unwrap();
+        self.webgl_sender.borrow().send_dom_to_texture(command).unwrap();
 
         Ok(())
     }
@@ -3996,6 +4666,12 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             Err(_) => return Ok(()), // NB: The validator sets the correct error for us.
         };
 
+        // From the WEBGL_depth_texture spec, depth textures can't be
+        // updated in WebGL 1.0.
+        if format.is_depth() && self.webgl_version() == WebGLVersion::WebGL1 {
+            return Ok(self.webgl_error(InvalidOperation));
+        }
+
         let unpacking_alignment = self.texture_unpacking_alignment.get();
 
         let expected_byte_length =
@@ -4065,6 +4741,11 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
             Err(_) => return Ok(()), // NB: The validator sets the correct error for us.
         };
 
+        // Depth textures can't be specified from a DOM source.
+        if format.is_depth() {
+            return Ok(self.webgl_error(InvalidOperation));
+        }
+
         let unpacking_alignment = 1;
         let pixels = self.prepare_pixels(format, data_type, width, height,
                                          unpacking_alignment, premultiplied, true, pixels);
@@ -4104,28 +4785,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.7
     fn RenderbufferStorage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
-        if target != constants::RENDERBUFFER {
-            return self.webgl_error(InvalidEnum);
-        }
-
-        let max = self.limits.max_renderbuffer_size;
-
-        if width < 0 || width as u32 > max || height < 0 || height as u32 > max {
-            return self.webgl_error(InvalidValue);
-        }
-
-        match self.bound_renderbuffer.get() {
-            Some(rb) => {
-                handle_potential_webgl_error!(self, rb.storage(internal_format, width, height));
-                if let Some(fb) = self.bound_framebuffer.get() {
-                    fb.invalidate_renderbuffer(&*rb);
-                }
-            }
-            None => self.webgl_error(InvalidOperation),
-        };
-
-        // FIXME: We need to clear the renderbuffer before it can be
-        // accessed.  See https://github.com/servo/servo/issues/13710
+        self.renderbuffer_storage(target, 0, internal_format, width, height)
     }
 
     // https://www.khronos.org/registry/webgl/specs/latest/1.0/#5.14.6
@@ -4145,7 +4805,7 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         }
 
         match self.bound_framebuffer.get() {
-            Some(fb) => handle_potential_webgl_error!(self, fb.renderbuffer(attachment, rb)),
+            Some(fb) => handle_potential_webgl_error!(self, fb.renderbuffer(target, attachment, rb)),
             None => self.webgl_error(InvalidOperation),
         };
     }
@@ -4168,7 +4828,9 @@ impl WebGLRenderingContextMethods for WebGLRenderingContext {
         }
 
         match self.bound_framebuffer.get() {
-            Some(fb) => handle_potential_webgl_error!(self, fb.texture2d(attachment, textarget, texture, level)),
+            Some(fb) => {
+                handle_potential_webgl_error!(self, fb.texture2d(target, attachment, textarget, texture, level))
+            }
             None => self.webgl_error(InvalidOperation),
         };
     }
@@ -0,0 +1,164 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.3
+use canvas_traits::webgl::{webgl_channel, WebGLCommand, WebGLError, WebGLResult, WebGLSamplerId};
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
+use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLSamplerBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom::webgltexture::TexParameterValue;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLSampler {
+    webgl_object: WebGLObject,
+    id: WebGLSamplerId,
+    is_deleted: Cell<bool>,
+}
+
+impl WebGLSampler {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLSamplerId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id: id,
+            is_deleted: Cell::new(false),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateSampler(sender));
+        receiver.recv().unwrap().map(|id| WebGLSampler::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLSamplerId) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLSampler::new_inherited(context, id)),
+            &*context.global(),
+            WebGLSamplerBinding::Wrap,
+        )
+    }
+}
+
+impl WebGLSampler {
+    pub fn id(&self) -> WebGLSamplerId {
+        self.id
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .send_command(WebGLCommand::DeleteSampler(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    /// Validates and sets a sampler parameter.
+    /// https://www.khronos.org/registry/OpenGL/specs/es/3.0/es_spec_3.0.pdf#nameddest=subsection.3.8.2
+    pub fn set_parameter(&self, pname: u32, value: TexParameterValue) -> WebGLResult<()> {
+        let (int_value, float_value) = match value {
+            TexParameterValue::Int(int_value) => (int_value, int_value as f32),
+            TexParameterValue::Float(float_value) => (float_value as i32, float_value),
+        };
+
+        let command = match pname {
+            constants::TEXTURE_MIN_FILTER => {
+                match int_value as u32 {
+                    constants::NEAREST |
+                    constants::LINEAR |
+                    constants::NEAREST_MIPMAP_NEAREST |
+                    constants::LINEAR_MIPMAP_NEAREST |
+                    constants::NEAREST_MIPMAP_LINEAR |
+                    constants::LINEAR_MIPMAP_LINEAR => {},
+                    _ => return Err(WebGLError::InvalidEnum),
+                }
+                WebGLCommand::SetSamplerParameterInt(self.id, pname, int_value)
+            }
+            constants::TEXTURE_MAG_FILTER => {
+                match int_value as u32 {
+                    constants::NEAREST | constants::LINEAR => {},
+                    _ => return Err(WebGLError::InvalidEnum),
+                }
+                WebGLCommand::SetSamplerParameterInt(self.id, pname, int_value)
+            }
+            constants::TEXTURE_WRAP_S | constants::TEXTURE_WRAP_T | WebGl2Constants::TEXTURE_WRAP_R => {
+                match int_value as u32 {
+                    constants::CLAMP_TO_EDGE | constants::MIRRORED_REPEAT | constants::REPEAT => {},
+                    _ => return Err(WebGLError::InvalidEnum),
+                }
+                WebGLCommand::SetSamplerParameterInt(self.id, pname, int_value)
+            }
+            WebGl2Constants::TEXTURE_COMPARE_MODE => {
+                match int_value as u32 {
+                    constants::NONE | WebGl2Constants::COMPARE_REF_TO_TEXTURE => {},
+                    _ => return Err(WebGLError::InvalidEnum),
+                }
+                WebGLCommand::SetSamplerParameterInt(self.id, pname, int_value)
+            }
+            WebGl2Constants::TEXTURE_COMPARE_FUNC => {
+                match int_value as u32 {
+                    constants::LEQUAL |
+                    constants::GEQUAL |
+                    constants::LESS |
+                    constants::GREATER |
+                    constants::EQUAL |
+                    constants::NOTEQUAL |
+                    constants::ALWAYS |
+                    constants::NEVER => {},
+                    _ => return Err(WebGLError::InvalidEnum),
+                }
+                WebGLCommand::SetSamplerParameterInt(self.id, pname, int_value)
+            }
+            WebGl2Constants::TEXTURE_MIN_LOD | WebGl2Constants::TEXTURE_MAX_LOD => {
+                WebGLCommand::SetSamplerParameterFloat(self.id, pname, float_value)
+            }
+            _ => return Err(WebGLError::InvalidEnum),
+        };
+
+        self.upcast::<WebGLObject>().send_command(command);
+        Ok(())
+    }
+
+    /// Returns the value of a sampler parameter, as a float for the LOD
+    /// parameters and as an integer for all the others.
+    pub fn get_parameter(&self, pname: u32) -> WebGLResult<TexParameterValue> {
+        match pname {
+            constants::TEXTURE_MIN_FILTER |
+            constants::TEXTURE_MAG_FILTER |
+            constants::TEXTURE_WRAP_S |
+            constants::TEXTURE_WRAP_T |
+            WebGl2Constants::TEXTURE_WRAP_R |
+            WebGl2Constants::TEXTURE_COMPARE_MODE |
+            WebGl2Constants::TEXTURE_COMPARE_FUNC => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.upcast::<WebGLObject>()
+                    .send_command(WebGLCommand::GetSamplerParameterInt(self.id, pname, sender));
+                Ok(TexParameterValue::Int(receiver.recv().unwrap()))
+            }
+            WebGl2Constants::TEXTURE_MIN_LOD | WebGl2Constants::TEXTURE_MAX_LOD => {
+                let (sender, receiver) = webgl_channel().unwrap();
+                self.upcast::<WebGLObject>()
+                    .send_command(WebGLCommand::GetSamplerParameterFloat(self.id, pname, sender));
+                Ok(TexParameterValue::Float(receiver.recv().unwrap()))
+            }
+            _ => Err(WebGLError::InvalidEnum),
+        }
+    }
+}
+
+impl Drop for WebGLSampler {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
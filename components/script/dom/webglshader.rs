@@ -15,10 +15,16 @@ use dom::bindings::str::DOMString;
 use dom::webgl_extensions::WebGLExtensions;
 use dom::webgl_extensions::ext::extshadertexturelod::EXTShaderTextureLod;
 use dom::webgl_extensions::ext::oesstandardderivatives::OESStandardDerivatives;
+use dom::webgl_extensions::ext::webgldrawbuffers::WEBGLDrawBuffers;
 use dom::webglobject::WebGLObject;
 use dom::webglrenderingcontext::WebGLRenderingContext;
 use dom_struct::dom_struct;
 use mozangle::shaders::{BuiltInResources, Output, ShaderValidator};
+use mozangle::shaders::ffi::{SH_CLAMP_INDIRECT_ARRAY_BOUNDS, SH_EMULATE_ABS_INT_FUNCTION};
+use mozangle::shaders::ffi::{SH_EMULATE_ATAN2_FLOAT_FUNCTION, SH_EMULATE_ISNAN_FLOAT_FUNCTION};
+use mozangle::shaders::ffi::{SH_ENFORCE_PACKING_RESTRICTIONS, SH_INIT_GL_POSITION};
+use mozangle::shaders::ffi::{SH_LIMIT_CALL_STACK_DEPTH, SH_LIMIT_EXPRESSION_COMPLEXITY};
+use mozangle::shaders::ffi::{SH_LINE_DIRECTIVES, SH_OBJECT_CODE, SH_VALIDATE};
 use offscreen_gl_context::GLLimits;
 use std::cell::Cell;
 use std::os::raw::c_int;
@@ -37,10 +43,16 @@ pub struct WebGLShader {
     id: WebGLShaderId,
     gl_type: u32,
     source: DomRefCell<DOMString>,
+    /// The source ANGLE translated the last compilation to.
+    translated_source: DomRefCell<DOMString>,
     info_log: DomRefCell<DOMString>,
     marked_for_deletion: Cell<bool>,
     attached_counter: Cell<u32>,
     compilation_status: Cell<ShaderCompilationStatus>,
+    /// Whether the driver may still reject the last compilation that ANGLE accepted.
+    /// Its verdict is only fetched when the compilation status or log is needed, so
+    /// that `compileShader` doesn't wait for the WebGL thread.
+    driver_status_pending: Cell<bool>,
 }
 
 static GLSLANG_INITIALIZATION: Once = ONCE_INIT;
@@ -57,10 +69,12 @@ impl WebGLShader {
             id: id,
             gl_type: shader_type,
             source: Default::default(),
+            translated_source: Default::default(),
             info_log: Default::default(),
             marked_for_deletion: Cell::new(false),
             attached_counter: Cell::new(0),
             compilation_status: Cell::new(ShaderCompilationStatus::NotCompiled),
+            driver_status_pending: Cell::new(false),
         }
     }
 
@@ -110,6 +124,11 @@ impl WebGLShader {
 
         let source = self.source.borrow();
 
+        let max_draw_buffers = if ext.is_draw_buffers_enabled() {
+            ext.capabilities().max_draw_buffers
+        } else {
+            1
+        };
         let params = BuiltInResources {
             MaxVertexAttribs: limits.max_vertex_attribs as c_int,
             MaxVertexUniformVectors: limits.max_vertex_uniform_vectors as c_int,
@@ -120,6 +139,8 @@ impl WebGLShader {
             MaxFragmentUniformVectors: limits.max_fragment_uniform_vectors as c_int,
             OES_standard_derivatives: ext.is_enabled::<OESStandardDerivatives>() as c_int,
             EXT_shader_texture_lod: ext.is_enabled::<EXTShaderTextureLod>() as c_int,
+            EXT_draw_buffers: ext.is_enabled::<WEBGLDrawBuffers>() as c_int,
+            MaxDrawBuffers: max_draw_buffers as c_int,
             FragmentPrecisionHigh: 1,
             ..BuiltInResources::default()
         };
@@ -158,28 +179,54 @@ impl WebGLShader {
             },
         };
 
-        match validator.compile_and_translate(&[&source]) {
-            Ok(translated_source) => {
+        // The options of `compile_and_translate`, plus `#line` directives so that the
+        // driver reports errors at the lines of the original source.
+        let options = SH_VALIDATE | SH_OBJECT_CODE | SH_LINE_DIRECTIVES |
+            SH_EMULATE_ABS_INT_FUNCTION | SH_EMULATE_ISNAN_FLOAT_FUNCTION |
+            SH_EMULATE_ATAN2_FLOAT_FUNCTION | SH_CLAMP_INDIRECT_ARRAY_BOUNDS |
+            SH_INIT_GL_POSITION | SH_ENFORCE_PACKING_RESTRICTIONS |
+            SH_LIMIT_EXPRESSION_COMPLEXITY | SH_LIMIT_CALL_STACK_DEPTH;
+        match validator.compile(&[&source], options) {
+            Ok(()) => {
+                let translated_source = validator.object_code();
                 debug!("Shader translated: {}", translated_source);
-                // NOTE: At this point we should be pretty sure that the compilation in the paint thread
-                // will succeed.
-                // It could be interesting to retrieve the info log from the paint thread though
-                self.upcast::<WebGLObject>()
-                    .context()
-                    .send_command(WebGLCommand::CompileShader(self.id, translated_source));
+                self.upcast::<WebGLObject>().send_command(
+                    WebGLCommand::CompileShader(self.id, translated_source.clone()),
+                );
                 self.compilation_status.set(ShaderCompilationStatus::Succeeded);
+                self.driver_status_pending.set(true);
+                *self.info_log.borrow_mut() = validator.info_log().into();
+                *self.translated_source.borrow_mut() = translated_source.into();
             },
             Err(error) => {
                 self.compilation_status.set(ShaderCompilationStatus::Failed);
+                self.driver_status_pending.set(false);
                 debug!("Shader {} compilation failed: {}", self.id, error);
+                *self.info_log.borrow_mut() = validator.info_log().into();
+                *self.translated_source.borrow_mut() = DOMString::new();
             },
         }
 
-        *self.info_log.borrow_mut() = validator.info_log().into();
-
         Ok(())
     }
 
+    /// Ask the WebGL thread whether the driver accepted the last compilation, if
+    /// that is not known yet. ANGLE may accept a shader the driver rejects, in which
+    /// case the driver's errors are reported instead of ANGLE's.
+    fn fetch_driver_status(&self) {
+        if !self.driver_status_pending.replace(false) {
+            return;
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        self.upcast::<WebGLObject>().send_command(WebGLCommand::GetShaderCompileStatus(self.id, sender));
+        // A closed channel means the context was lost, which fails the compilation too.
+        if let Err(log) = receiver.recv().unwrap_or(Err(String::new())) {
+            self.compilation_status.set(ShaderCompilationStatus::Failed);
+            debug!("Shader {} rejected by the driver: {}", self.id, log);
+            *self.info_log.borrow_mut() = log.into();
+        }
+    }
+
     /// Mark this shader as deleted (if it wasn't previously)
     /// and delete it as if calling glDeleteShader.
     /// Currently does not check if shader is attached
@@ -187,7 +234,6 @@ impl WebGLShader {
         if !self.marked_for_deletion.get() {
             self.marked_for_deletion.set(true);
             self.upcast::<WebGLObject>()
-                .context()
                 .send_command(WebGLCommand::DeleteShader(self.id));
         }
     }
@@ -215,6 +261,7 @@ impl WebGLShader {
 
     /// glGetShaderInfoLog
     pub fn info_log(&self) -> DOMString {
+        self.fetch_driver_status();
         self.info_log.borrow().clone()
     }
 
@@ -223,12 +270,20 @@ impl WebGLShader {
         self.source.borrow().clone()
     }
 
+    /// Get the source ANGLE translated the last compilation to, or the empty string if
+    /// ANGLE rejected the shader. A shader only the driver rejects keeps its
+    /// translation, which is what the driver's errors refer to.
+    pub fn translated_source(&self) -> DOMString {
+        self.translated_source.borrow().clone()
+    }
+
     /// glShaderSource
     pub fn set_source(&self, source: DOMString) {
         *self.source.borrow_mut() = source;
     }
 
     pub fn successfully_compiled(&self) -> bool {
+        self.fetch_driver_status();
         self.compilation_status.get() == ShaderCompilationStatus::Succeeded
     }
 }
@@ -9,7 +9,7 @@ use dom::bindings::codegen::Bindings::WebGLShaderPrecisionFormatBinding;
 use dom::bindings::codegen::Bindings::WebGLShaderPrecisionFormatBinding::WebGLShaderPrecisionFormatMethods;
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
 use dom::bindings::root::DomRoot;
-use dom::window::Window;
+use dom::globalscope::GlobalScope;
 use dom_struct::dom_struct;
 
 #[dom_struct]
@@ -30,13 +30,13 @@ impl WebGLShaderPrecisionFormat {
         }
     }
 
-    pub fn new(window: &Window,
+    pub fn new(global: &GlobalScope,
                range_min: i32,
                range_max: i32,
                precision: i32) -> DomRoot<WebGLShaderPrecisionFormat> {
         reflect_dom_object(
             Box::new(WebGLShaderPrecisionFormat::new_inherited(range_min, range_max, precision)),
-            window,
+            global,
             WebGLShaderPrecisionFormatBinding::Wrap)
     }
 }
@@ -5,15 +5,16 @@
 // https://www.khronos.org/registry/webgl/specs/latest/1.0/webgl.idl
 
 use canvas_traits::webgl::{DOMToTextureCommand, WebGLCommand, WebGLError};
-use canvas_traits::webgl::{WebGLResult, WebGLTextureId, webgl_channel};
+use canvas_traits::webgl::{WebGLResult, WebGLTextureId, WebGLVersion, webgl_channel};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EXTTextureFilterAnisotropicBinding::EXTTextureFilterAnisotropicConstants;
+use dom::bindings::codegen::Bindings::WebGL2RenderingContextBinding::WebGL2RenderingContextConstants as WebGl2Constants;
 use dom::bindings::codegen::Bindings::WebGLRenderingContextBinding::WebGLRenderingContextConstants as constants;
 use dom::bindings::codegen::Bindings::WebGLTextureBinding;
 use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::{DomObject, reflect_dom_object};
 use dom::bindings::root::DomRoot;
-use dom::webgl_validations::types::{TexImageTarget, TexFormat, TexDataType};
+use dom::webgl_validations::types::{TexImageTarget, TexFormat, TexDataType, TexInternalFormat};
 use dom::webglobject::WebGLObject;
 use dom::webglrenderingcontext::WebGLRenderingContext;
 use dom_struct::dom_struct;
@@ -48,6 +49,8 @@ pub struct WebGLTexture {
     mag_filter: Cell<u32>,
     /// True if this texture is used for the DOMToTexture feature.
     attached_to_dom: Cell<bool>,
+    /// The number of levels allocated by texStorage2D or texStorage3D, if any.
+    immutable_levels: Cell<Option<u32>>,
 }
 
 impl WebGLTexture {
@@ -63,6 +66,7 @@ impl WebGLTexture {
             mag_filter: Cell::new(constants::LINEAR),
             image_info_array: DomRefCell::new([ImageInfo::new(); MAX_LEVEL_COUNT * MAX_FACE_COUNT]),
             attached_to_dom: Cell::new(false),
+            immutable_levels: Cell::new(None),
         }
     }
 
@@ -100,7 +104,9 @@ impl WebGLTexture {
         } else {
             // This is the first time binding
             let face_count = match target {
-                constants::TEXTURE_2D => 1,
+                constants::TEXTURE_2D |
+                WebGl2Constants::TEXTURE_3D |
+                WebGl2Constants::TEXTURE_2D_ARRAY => 1,
                 constants::TEXTURE_CUBE_MAP => 6,
                 _ => return Err(WebGLError::InvalidEnum)
             };
@@ -109,7 +115,6 @@ impl WebGLTexture {
         }
 
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::BindTexture(target, Some(self.id)));
 
         Ok(())
@@ -123,11 +128,18 @@ impl WebGLTexture {
                       internal_format: TexFormat,
                       level: u32,
                       data_type: Option<TexDataType>) -> WebGLResult<()> {
+        // Textures with immutable storage can only be updated through the
+        // texSubImage* family of functions.
+        if self.is_immutable() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
         let image_info = ImageInfo {
             width: width,
             height: height,
             depth: depth,
             internal_format: Some(internal_format),
+            sized_internal_format: None,
             is_initialized: true,
             data_type: data_type,
         };
@@ -137,6 +149,109 @@ impl WebGLTexture {
         Ok(())
     }
 
+    /// Like `initialize`, but for the images specified with a WebGL 2.0 sized
+    /// internal format, as texImage3D does.
+    pub fn initialize_sized(&self,
+                            target: TexImageTarget,
+                            width: u32,
+                            height: u32,
+                            depth: u32,
+                            internal_format: TexInternalFormat,
+                            level: u32) -> WebGLResult<()> {
+        if self.is_immutable() {
+            return Err(WebGLError::InvalidOperation);
+        }
+
+        let (format, _) = internal_format.format_and_data_types();
+        let image_info = ImageInfo {
+            width: width,
+            height: height,
+            depth: depth,
+            internal_format: Some(format),
+            sized_internal_format: Some(internal_format),
+            is_initialized: true,
+            data_type: None,
+        };
+
+        let face_index = self.face_index_for_target(&target);
+        self.set_image_infos_at_level_and_face(level, face_index, image_info);
+        Ok(())
+    }
+
+    /// Allocates immutable storage for all the levels of this texture, as done by
+    /// texStorage2D and texStorage3D.
+    pub fn storage(
+        &self,
+        target: u32,
+        levels: u32,
+        internal_format: TexInternalFormat,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> WebGLResult<()> {
+        if self.immutable_levels.get().is_some() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        if levels as usize > MAX_LEVEL_COUNT {
+            return Err(WebGLError::InvalidValue);
+        }
+
+        let command = match target {
+            constants::TEXTURE_2D | constants::TEXTURE_CUBE_MAP => {
+                WebGLCommand::TexStorage2D(
+                    target,
+                    levels as i32,
+                    internal_format.as_gl_constant(),
+                    width as i32,
+                    height as i32,
+                )
+            }
+            _ => {
+                WebGLCommand::TexStorage3D(
+                    target,
+                    levels as i32,
+                    internal_format.as_gl_constant(),
+                    width as i32,
+                    height as i32,
+                    depth as i32,
+                )
+            }
+        };
+        self.upcast::<WebGLObject>().send_command(command);
+
+        let (format, _) = internal_format.format_and_data_types();
+        let (mut width, mut height, mut depth) = (width, height, depth);
+        for level in 0..levels {
+            let image_info = ImageInfo {
+                width,
+                height,
+                depth,
+                internal_format: Some(format),
+                sized_internal_format: Some(internal_format),
+                is_initialized: true,
+                data_type: None,
+            };
+            self.set_image_infos_at_level(level, image_info);
+
+            width = cmp::max(1, width / 2);
+            height = cmp::max(1, height / 2);
+            // The layers of an array texture aren't mipmapped.
+            if target == WebGl2Constants::TEXTURE_3D {
+                depth = cmp::max(1, depth / 2);
+            }
+        }
+        self.immutable_levels.set(Some(levels));
+        Ok(())
+    }
+
+    pub fn is_immutable(&self) -> bool {
+        self.immutable_levels.get().is_some()
+    }
+
+    pub fn immutable_levels(&self) -> u32 {
+        self.immutable_levels.get().unwrap_or(0)
+    }
+
     pub fn generate_mipmap(&self) -> WebGLResult<()> {
         let target = match self.target.get() {
             Some(target) => target,
@@ -156,16 +271,24 @@ impl WebGLTexture {
             return Err(WebGLError::InvalidOperation);
         }
 
-        if !base_image_info.is_power_of_two() {
+        // WebGL 2.0 lifts the power-of-two restriction, but integer and
+        // depth formats can't be filtered to generate the mipmap levels.
+        let context = self.upcast::<WebGLObject>().context();
+        if context.webgl_version() == WebGLVersion::WebGL1 && !base_image_info.is_power_of_two() {
             return Err(WebGLError::InvalidOperation);
         }
 
-        if base_image_info.is_compressed_format() {
+        if let Some(format) = base_image_info.sized_internal_format() {
+            if format.is_depth() || format.format_and_data_types().0.is_integer() {
+                return Err(WebGLError::InvalidOperation);
+            }
+        }
+
+        if base_image_info.is_compressed_format() || base_image_info.is_depth_format() {
             return Err(WebGLError::InvalidOperation);
         }
 
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::GenerateMipmap(target));
 
         if self.base_mipmap_level + base_image_info.get_max_mimap_levels() == 0 {
@@ -186,7 +309,7 @@ impl WebGLTexture {
                     DOMToTextureCommand::Detach(self.id),
                 );
             }
-            context.send_command(WebGLCommand::DeleteTexture(self.id));
+            self.upcast::<WebGLObject>().send_command(WebGLCommand::DeleteTexture(self.id));
         }
     }
 
@@ -219,7 +342,6 @@ impl WebGLTexture {
             }
             filter.set(int_value as u32);
             self.upcast::<WebGLObject>()
-                .context()
                 .send_command(WebGLCommand::TexParameteri(target, param, int_value));
             Ok(())
         };
@@ -241,26 +363,64 @@ impl WebGLTexture {
                     _ => return Err(WebGLError::InvalidEnum),
                 }
             }
-            constants::TEXTURE_WRAP_S | constants::TEXTURE_WRAP_T => {
+            constants::TEXTURE_WRAP_S | constants::TEXTURE_WRAP_T | WebGl2Constants::TEXTURE_WRAP_R => {
                 match int_value as u32 {
                     constants::CLAMP_TO_EDGE |
                     constants::MIRRORED_REPEAT |
                     constants::REPEAT => {
                         self.upcast::<WebGLObject>()
-                            .context()
                             .send_command(WebGLCommand::TexParameteri(target, param, int_value));
                         Ok(())
                     }
                     _ => Err(WebGLError::InvalidEnum),
                 }
             }
+            WebGl2Constants::TEXTURE_BASE_LEVEL | WebGl2Constants::TEXTURE_MAX_LEVEL => {
+                if int_value < 0 {
+                    return Err(WebGLError::InvalidValue);
+                }
+                self.upcast::<WebGLObject>()
+                    .send_command(WebGLCommand::TexParameteri(target, param, int_value));
+                Ok(())
+            }
+            WebGl2Constants::TEXTURE_COMPARE_MODE => {
+                match int_value as u32 {
+                    constants::NONE | WebGl2Constants::COMPARE_REF_TO_TEXTURE => {
+                        self.upcast::<WebGLObject>()
+                            .send_command(WebGLCommand::TexParameteri(target, param, int_value));
+                        Ok(())
+                    }
+                    _ => Err(WebGLError::InvalidEnum),
+                }
+            }
+            WebGl2Constants::TEXTURE_COMPARE_FUNC => {
+                match int_value as u32 {
+                    constants::LEQUAL |
+                    constants::GEQUAL |
+                    constants::LESS |
+                    constants::GREATER |
+                    constants::EQUAL |
+                    constants::NOTEQUAL |
+                    constants::ALWAYS |
+                    constants::NEVER => {
+                        self.upcast::<WebGLObject>()
+                            .send_command(WebGLCommand::TexParameteri(target, param, int_value));
+                        Ok(())
+                    }
+                    _ => Err(WebGLError::InvalidEnum),
+                }
+            }
+            WebGl2Constants::TEXTURE_MIN_LOD | WebGl2Constants::TEXTURE_MAX_LOD => {
+                self.upcast::<WebGLObject>()
+                    .send_command(WebGLCommand::TexParameterf(target, param, float_value));
+                Ok(())
+            }
             EXTTextureFilterAnisotropicConstants::TEXTURE_MAX_ANISOTROPY_EXT => {
                 // NaN is not less than 1., what a time to be alive.
                 if !(float_value >= 1.) {
                     return Err(WebGLError::InvalidValue);
                 }
                 self.upcast::<WebGLObject>()
-                    .context()
                     .send_command(WebGLCommand::TexParameterf(target, param, float_value));
                 Ok(())
             }
@@ -315,6 +475,7 @@ impl WebGLTexture {
                 height: ref_height,
                 depth: 0,
                 internal_format: base_image_info.internal_format,
+                sized_internal_format: base_image_info.sized_internal_format,
                 is_initialized: base_image_info.is_initialized(),
                 data_type: base_image_info.data_type,
             };
@@ -356,6 +517,8 @@ impl WebGLTexture {
                              target: &TexImageTarget) -> u8 {
         match *target {
             TexImageTarget::Texture2D => 0,
+            TexImageTarget::Texture3D => 0,
+            TexImageTarget::Texture2DArray => 0,
             TexImageTarget::CubeMapPositiveX => 0,
             TexImageTarget::CubeMapNegativeX => 1,
             TexImageTarget::CubeMapPositiveY => 2,
@@ -412,6 +575,9 @@ pub struct ImageInfo {
     height: u32,
     depth: u32,
     internal_format: Option<TexFormat>,
+    /// The sized internal format given to texImage3D or texStorage*, which
+    /// determines the format and types texSubImage3D accepts.
+    sized_internal_format: Option<TexInternalFormat>,
     is_initialized: bool,
     data_type: Option<TexDataType>,
 }
@@ -423,6 +589,7 @@ impl ImageInfo {
             height: 0,
             depth: 0,
             internal_format: None,
+            sized_internal_format: None,
             is_initialized: false,
             data_type: None,
         }
@@ -436,6 +603,10 @@ impl ImageInfo {
         self.height
     }
 
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
     pub fn internal_format(&self) -> Option<TexFormat> {
         self.internal_format
     }
@@ -444,6 +615,10 @@ impl ImageInfo {
         self.data_type
     }
 
+    pub fn sized_internal_format(&self) -> Option<TexInternalFormat> {
+        self.sized_internal_format
+    }
+
     fn is_power_of_two(&self) -> bool {
         self.width.is_power_of_two() &&
         self.height.is_power_of_two() &&
@@ -468,7 +643,10 @@ impl ImageInfo {
     }
 
     fn is_compressed_format(&self) -> bool {
-        // TODO: Once Servo supports compressed formats, check for them here
-        false
+        self.internal_format.map_or(false, |format| format.is_compressed())
+    }
+
+    fn is_depth_format(&self) -> bool {
+        self.internal_format.map_or(false, |format| format.is_depth())
     }
 }
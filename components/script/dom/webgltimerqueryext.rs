@@ -0,0 +1,130 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/extensions/EXT_disjoint_timer_query/
+use canvas_traits::webgl::{webgl_channel, WebGLCommand, WebGLError, WebGLQueryId, WebGLResult};
+use dom::bindings::codegen::Bindings::EXTDisjointTimerQueryBinding::EXTDisjointTimerQueryConstants as constants;
+use dom::bindings::codegen::Bindings::WebGLTimerQueryEXTBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+/// The result of a timer query, as returned by getQueryObjectEXT.
+pub enum TimerQueryResult {
+    Available(bool),
+    /// The elapsed time or timestamp, in nanoseconds.
+    Time(u64),
+}
+
+#[dom_struct]
+pub struct WebGLTimerQueryEXT {
+    webgl_object: WebGLObject,
+    id: WebGLQueryId,
+    /// TIME_ELAPSED_EXT or TIMESTAMP_EXT, once the query has been used.
+    target: Cell<Option<u32>>,
+    is_deleted: Cell<bool>,
+}
+
+impl WebGLTimerQueryEXT {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLQueryId) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id: id,
+            target: Cell::new(None),
+            is_deleted: Cell::new(false),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateQuery(sender));
+        receiver.recv().unwrap().map(|id| WebGLTimerQueryEXT::new(context, id))
+    }
+
+    pub fn new(context: &WebGLRenderingContext, id: WebGLQueryId) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLTimerQueryEXT::new_inherited(context, id)),
+            &*context.global(),
+            WebGLTimerQueryEXTBinding::Wrap,
+        )
+    }
+}
+
+impl WebGLTimerQueryEXT {
+    pub fn id(&self) -> WebGLQueryId {
+        self.id
+    }
+
+    pub fn target(&self) -> Option<u32> {
+        self.target.get()
+    }
+
+    /// Checks that this query can be used with `target`, remembering it
+    /// if it is the first one.
+    fn use_with_target(&self, target: u32) -> WebGLResult<()> {
+        if self.is_deleted.get() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        if self.target.get().map_or(false, |t| t != target) {
+            return Err(WebGLError::InvalidOperation);
+        }
+        self.target.set(Some(target));
+        Ok(())
+    }
+
+    pub fn begin(&self, target: u32) -> WebGLResult<()> {
+        self.use_with_target(target)?;
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::BeginQuery(target, self.id));
+        Ok(())
+    }
+
+    pub fn query_counter(&self, target: u32) -> WebGLResult<()> {
+        self.use_with_target(target)?;
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::QueryCounter(self.id, target));
+        Ok(())
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .send_command(WebGLCommand::DeleteQuery(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn get_parameter(&self, pname: u32) -> WebGLResult<TimerQueryResult> {
+        match pname {
+            constants::QUERY_RESULT_EXT | constants::QUERY_RESULT_AVAILABLE_EXT => {},
+            _ => return Err(WebGLError::InvalidEnum),
+        }
+        if self.is_deleted.get() || self.target.get().is_none() {
+            return Err(WebGLError::InvalidOperation);
+        }
+        let object = self.upcast::<WebGLObject>();
+        if pname == constants::QUERY_RESULT_AVAILABLE_EXT {
+            let (sender, receiver) = webgl_channel().unwrap();
+            object.send_command(WebGLCommand::GetQueryState(self.id, pname, sender));
+            return Ok(TimerQueryResult::Available(receiver.recv().unwrap() != 0));
+        }
+        let (sender, receiver) = webgl_channel().unwrap();
+        object.send_command(WebGLCommand::GetQueryStateU64(self.id, pname, sender));
+        Ok(TimerQueryResult::Time(receiver.recv().unwrap()))
+    }
+}
+
+impl Drop for WebGLTimerQueryEXT {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
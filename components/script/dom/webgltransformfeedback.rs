@@ -0,0 +1,94 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// https://www.khronos.org/registry/webgl/specs/latest/2.0/#3.5
+use canvas_traits::webgl::{webgl_channel, WebGLCommand, WebGLTransformFeedbackId};
+use dom::bindings::codegen::Bindings::WebGLTransformFeedbackBinding;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutNullableDom};
+use dom::webglbuffer::WebGLBuffer;
+use dom::webglobject::WebGLObject;
+use dom::webglrenderingcontext::WebGLRenderingContext;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+#[dom_struct]
+pub struct WebGLTransformFeedback {
+    webgl_object: WebGLObject,
+    id: WebGLTransformFeedbackId,
+    ever_bound: Cell<bool>,
+    is_deleted: Cell<bool>,
+    /// The indexed TRANSFORM_FEEDBACK_BUFFER bindings, which are part of the
+    /// transform feedback object state.
+    buffers: Box<[MutNullableDom<WebGLBuffer>]>,
+}
+
+impl WebGLTransformFeedback {
+    fn new_inherited(context: &WebGLRenderingContext, id: WebGLTransformFeedbackId, max_buffers: u32) -> Self {
+        Self {
+            webgl_object: WebGLObject::new_inherited(context),
+            id: id,
+            ever_bound: Cell::new(false),
+            is_deleted: Cell::new(false),
+            buffers: (0..max_buffers).map(|_| MutNullableDom::new(None)).collect::<Vec<_>>().into(),
+        }
+    }
+
+    pub fn maybe_new(context: &WebGLRenderingContext, max_buffers: u32) -> Option<DomRoot<Self>> {
+        let (sender, receiver) = webgl_channel().unwrap();
+        context.send_command(WebGLCommand::CreateTransformFeedback(sender));
+        receiver.recv().unwrap().map(|id| WebGLTransformFeedback::new(context, id, max_buffers))
+    }
+
+    pub fn new(
+        context: &WebGLRenderingContext,
+        id: WebGLTransformFeedbackId,
+        max_buffers: u32,
+    ) -> DomRoot<Self> {
+        reflect_dom_object(
+            Box::new(WebGLTransformFeedback::new_inherited(context, id, max_buffers)),
+            &*context.global(),
+            WebGLTransformFeedbackBinding::Wrap,
+        )
+    }
+}
+
+impl WebGLTransformFeedback {
+    pub fn id(&self) -> WebGLTransformFeedbackId {
+        self.id
+    }
+
+    pub fn bind(&self, target: u32) {
+        self.ever_bound.set(true);
+        self.upcast::<WebGLObject>()
+            .send_command(WebGLCommand::BindTransformFeedback(target, Some(self.id)));
+    }
+
+    pub fn delete(&self) {
+        if !self.is_deleted.get() {
+            self.is_deleted.set(true);
+            self.upcast::<WebGLObject>()
+                .send_command(WebGLCommand::DeleteTransformFeedback(self.id));
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted.get()
+    }
+
+    pub fn ever_bound(&self) -> bool {
+        self.ever_bound.get()
+    }
+
+    pub fn buffers(&self) -> &[MutNullableDom<WebGLBuffer>] {
+        &self.buffers
+    }
+}
+
+impl Drop for WebGLTransformFeedback {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
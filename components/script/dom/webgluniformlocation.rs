@@ -7,7 +7,7 @@ use canvas_traits::webgl::WebGLProgramId;
 use dom::bindings::codegen::Bindings::WebGLUniformLocationBinding;
 use dom::bindings::reflector::{Reflector, reflect_dom_object};
 use dom::bindings::root::DomRoot;
-use dom::window::Window;
+use dom::globalscope::GlobalScope;
 use dom_struct::dom_struct;
 
 #[dom_struct]
@@ -39,7 +39,7 @@ impl WebGLUniformLocation {
     }
 
     pub fn new(
-        window: &Window,
+        global: &GlobalScope,
         id: i32,
         program_id: WebGLProgramId,
         link_generation: u64,
@@ -48,7 +48,7 @@ impl WebGLUniformLocation {
     ) -> DomRoot<Self> {
         reflect_dom_object(
             Box::new(Self::new_inherited(id, program_id, link_generation, size, type_)),
-            window,
+            global,
             WebGLUniformLocationBinding::Wrap,
         )
     }
@@ -63,7 +63,6 @@ impl WebGLVertexArrayObjectOES {
         self.is_deleted.set(true);
 
         self.upcast::<WebGLObject>()
-            .context()
             .send_command(WebGLCommand::DeleteVertexArray(self.id.unwrap()));
 
         for attrib_data in &**self.vertex_attribs.borrow() {
@@ -7,11 +7,13 @@ use base64;
 use bluetooth_traits::BluetoothRequest;
 use canvas_traits::webgl::WebGLChan;
 use cssparser::{Parser, ParserInput};
-use devtools_traits::{ScriptToDevtoolsControlMsg, TimelineMarker, TimelineMarkerType};
+use devtools;
+use devtools_traits::{DomMutation, ScriptToDevtoolsControlMsg, TimelineMarker, TimelineMarkerType};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use dom::bindings::codegen::Bindings::HistoryBinding::HistoryBinding::HistoryMethods;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::MediaQueryListBinding::MediaQueryListBinding::MediaQueryListMethods;
 use dom::bindings::codegen::Bindings::PermissionStatusBinding::PermissionState;
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
@@ -65,7 +67,7 @@ use js::jsapi::{JS_GC, JS_GetRuntime};
 use js::jsval::UndefinedValue;
 use js::rust::HandleValue;
 use layout_image::fetch_image_for_layout;
-use microtask::MicrotaskQueue;
+use microtask::{Microtask, MicrotaskQueue};
 use msg::constellation_msg::PipelineId;
 use net_traits::{ResourceThreads, ReferrerPolicy};
 use net_traits::image_cache::{ImageCache, ImageResponder, ImageResponse};
@@ -106,10 +108,12 @@ use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Sender, channel};
 use std::sync::mpsc::TryRecvError::{Disconnected, Empty};
+use style::computed_values::scroll_behavior::T as StyleScrollBehavior;
 use style::error_reporting::ParseErrorReporter;
 use style::media_queries;
 use style::parser::ParserContext as CssParserContext;
-use style::properties::{ComputedValues, PropertyId};
+use style::properties::{ComputedValues, PropertyId, ShorthandId};
+use style::properties::style_structs::Font;
 use style::selector_parser::PseudoElement;
 use style::str::HTML_SPACE_CHARACTERS;
 use style::stylesheets::CssRuleType;
@@ -160,6 +164,7 @@ pub enum ReflowReason {
     IFrameLoadEvent,
     MissingExplicitReflow,
     ElementStateChanged,
+    PrintToPdf,
 }
 
 #[dom_struct]
@@ -205,6 +210,15 @@ pub struct Window {
     #[ignore_malloc_size_of = "channels are hard"]
     devtools_marker_sender: DomRefCell<Option<IpcSender<Option<TimelineMarker>>>>,
 
+    /// For sending DOM mutations to the inspector. Will be ignored if
+    /// no devtools server
+    #[ignore_malloc_size_of = "channels are hard"]
+    devtools_mutation_sender: DomRefCell<Option<IpcSender<Vec<DomMutation>>>>,
+    /// DOM mutations waiting for the next microtask checkpoint to be sent, with
+    /// their targets.
+    #[ignore_malloc_size_of = "defined in devtools_traits"]
+    pending_devtools_mutations: DomRefCell<Vec<(Trusted<Node>, DomMutation)>>,
+
     /// Pending resize event, if any.
     resize_event: Cell<Option<(WindowSizeData, WindowSizeType)>>,
 
@@ -1068,6 +1082,27 @@ impl WindowMethods for Window {
         fetch::Fetch(&self.upcast(), input, init)
     }
 
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(&self, image: ImageBitmapSource, options: &ImageBitmapOptions) -> Rc<Promise> {
+        self.upcast::<GlobalScope>().create_image_bitmap(image, None, options)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(
+        &self,
+        image: ImageBitmapSource,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        options: &ImageBitmapOptions,
+    ) -> Rc<Promise> {
+        let crop = Rect::new(Point2D::new(sx, sy), Size2D::new(sw, sh));
+        self.upcast::<GlobalScope>().create_image_bitmap(image, Some(crop), options)
+    }
+
     fn TestRunner(&self) -> DomRoot<TestRunner> {
         self.test_runner.or_init(|| TestRunner::new(self.upcast()))
     }
@@ -1224,15 +1259,32 @@ impl Window {
                             x: f32,
                             y: f32,
                             scroll_id: ExternalScrollId,
-                            _behavior: ScrollBehavior,
-                            _element: Option<&Element>) {
-        // TODO Step 1
-        // TODO(mrobinson, #18709): Add smooth scrolling support to WebRender so that we can
-        // properly process ScrollBehavior here.
-        self.layout_chan.send(Msg::UpdateScrollStateFromScript(ScrollState {
+                            behavior: ScrollBehavior,
+                            element: Option<&Element>) {
+        // Step 1
+        let smooth = match behavior {
+            ScrollBehavior::Smooth => true,
+            ScrollBehavior::Instant => false,
+            ScrollBehavior::Auto => {
+                // The viewport uses the scroll-behavior of the root element.
+                let element = element.map(DomRoot::from_ref)
+                    .or_else(|| self.Document().GetDocumentElement());
+                element.and_then(|element| element.style()).map_or(false, |style| {
+                    style.get_box().scroll_behavior == StyleScrollBehavior::Smooth
+                })
+            },
+        };
+
+        let scroll_state = ScrollState {
             scroll_id,
             scroll_offset: Vector2D::new(-x, -y),
-        })).unwrap();
+        };
+        if smooth {
+            // The compositor animates the scroll, and lets layout know where it ends up.
+            self.send_to_constellation(ScriptMsg::SmoothScroll(scroll_state));
+        } else {
+            self.layout_chan.send(Msg::UpdateScrollStateFromScript(scroll_state)).unwrap();
+        }
     }
 
     pub fn update_viewport_for_scroll(&self, x: f32, y: f32) {
@@ -1427,6 +1479,7 @@ impl Window {
         // that this pipeline is ready to write the image (from the script thread
         // perspective at least).
         if (opts::get().output_file.is_some() ||
+            opts::get().print_to_pdf.is_some() ||
             opts::get().exit_after_load ||
             opts::get().webdriver_port.is_some()) && for_display {
             let document = self.Document();
@@ -1458,6 +1511,15 @@ impl Window {
         &*self.layout_rpc
     }
 
+    /// Lays the document out in pages for print media and returns them as a PDF document, or
+    /// `None` if there is nothing to print yet.
+    pub fn print_to_pdf(&self) -> Option<Vec<u8>> {
+        if !self.reflow(ReflowGoal::Print, ReflowReason::PrintToPdf) {
+            return None;
+        }
+        self.layout_rpc.pdf()
+    }
+
     pub fn content_box_query(&self, content_box_request: TrustedNodeAddress) -> Option<Rect<Au>> {
         if !self.layout_reflow(QueryMsg::ContentBoxQuery(content_box_request)) {
             return None;
@@ -1522,7 +1584,7 @@ impl Window {
                               y_.to_f32().unwrap_or(0.0f32),
                               scroll_id,
                               behavior,
-                              None);
+                              node.downcast::<Element>());
     }
 
     pub fn resolved_style_query(&self,
@@ -1536,6 +1598,16 @@ impl Window {
         DOMString::from(resolved)
     }
 
+    pub fn resolved_font_style_query(&self,
+                                     node: &Node,
+                                     value: String) -> Option<servo_arc::Arc<Font>> {
+        let id = PropertyId::Shorthand(ShorthandId::Font);
+        if !self.layout_reflow(QueryMsg::ResolvedFontStyleQuery(node.to_trusted_node_address(), id, value)) {
+            return None;
+        }
+        self.layout_rpc.resolved_font_style()
+    }
+
     #[allow(unsafe_code)]
     pub fn offset_parent_query(&self, node: TrustedNodeAddress) -> (Option<DomRoot<Element>>, Rect<Au>) {
         if !self.layout_reflow(QueryMsg::OffsetParentQuery(node)) {
@@ -1679,6 +1751,13 @@ impl Window {
         &self.layout_chan
     }
 
+    /// Returns whether layout is still loading web fonts for this window's document.
+    pub fn has_pending_web_fonts(&self) -> bool {
+        let (sender, receiver) = ProfiledIpc::channel(self.global().time_profiler_chan().clone()).unwrap();
+        self.layout_chan.send(Msg::GetWebFontLoadState(sender)).unwrap();
+        receiver.recv().unwrap_or(false)
+    }
+
     pub fn windowproxy_handler(&self) -> WindowProxyHandler {
         WindowProxyHandler(self.dom_static.windowproxy_handler.0)
     }
@@ -1784,6 +1863,44 @@ impl Window {
         }
     }
 
+    pub fn need_emit_devtools_mutations(&self) -> bool {
+        self.devtools_mutation_sender.borrow().is_some()
+    }
+
+    /// Queue a DOM mutation for the inspector, to be sent at the next microtask checkpoint.
+    pub fn queue_devtools_mutation(&self, target: &Node, mutation: DomMutation) {
+        let mut pending = self.pending_devtools_mutations.borrow_mut();
+        if pending.is_empty() {
+            let pipeline = self.upcast::<GlobalScope>().pipeline_id();
+            ScriptThread::enqueue_microtask(Microtask::NotifyDevtoolsMutations(pipeline));
+        }
+        pending.push((Trusted::new(target), mutation));
+    }
+
+    pub fn flush_devtools_mutations(&self) {
+        let pending = mem::replace(&mut *self.pending_devtools_mutations.borrow_mut(), vec![]);
+        let sender = self.devtools_mutation_sender.borrow();
+        let sender = match *sender {
+            Some(ref sender) => sender,
+            None => return,
+        };
+        let mutations: Vec<DomMutation> = pending.into_iter()
+            .filter_map(|(target, mutation)| devtools::complete_dom_mutation(&target.root(), mutation))
+            .collect();
+        if !mutations.is_empty() {
+            let _ = sender.send(mutations);
+        }
+    }
+
+    pub fn set_devtools_mutation_sender(&self, reply: IpcSender<Vec<DomMutation>>) {
+        *self.devtools_mutation_sender.borrow_mut() = Some(reply);
+    }
+
+    pub fn drop_devtools_mutation_sender(&self) {
+        *self.devtools_mutation_sender.borrow_mut() = None;
+        self.pending_devtools_mutations.borrow_mut().clear();
+    }
+
     pub fn set_webdriver_script_chan(&self, chan: Option<IpcSender<WebDriverJSResult>>) {
         *self.webdriver_script_chan.borrow_mut() = chan;
     }
@@ -1950,6 +2067,8 @@ impl Window {
             current_state: Cell::new(WindowState::Alive),
             devtools_marker_sender: Default::default(),
             devtools_markers: Default::default(),
+            devtools_mutation_sender: Default::default(),
+            pending_devtools_mutations: Default::default(),
             webdriver_script_chan: Default::default(),
             ignore_further_async_events: Default::default(),
             error_reporter,
@@ -2000,6 +2119,7 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
     debug_msg.push_str(match *reflow_goal {
         ReflowGoal::Full => "\tFull",
         ReflowGoal::TickAnimations => "\tTickAnimations",
+        ReflowGoal::Print => "\tPrint",
         ReflowGoal::LayoutQuery(ref query_msg, _) => match query_msg {
             &QueryMsg::ContentBoxQuery(_n) => "\tContentBoxQuery",
             &QueryMsg::ContentBoxesQuery(_n) => "\tContentBoxesQuery",
@@ -2008,6 +2128,7 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
             &QueryMsg::NodeScrollGeometryQuery(_n) => "\tNodeScrollGeometryQuery",
             &QueryMsg::NodeScrollIdQuery(_n) => "\tNodeScrollIdQuery",
             &QueryMsg::ResolvedStyleQuery(_, _, _) => "\tResolvedStyleQuery",
+            &QueryMsg::ResolvedFontStyleQuery(..) => "\tResolvedFontStyleQuery",
             &QueryMsg::OffsetParentQuery(_n) => "\tOffsetParentQuery",
             &QueryMsg::StyleQuery(_n) => "\tStyleQuery",
             &QueryMsg::TextIndexQuery(..) => "\tTextIndexQuery",
@@ -2036,6 +2157,7 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
         ReflowReason::IFrameLoadEvent => "\tIFrameLoadEvent",
         ReflowReason::MissingExplicitReflow => "\tMissingExplicitReflow",
         ReflowReason::ElementStateChanged => "\tElementStateChanged",
+        ReflowReason::PrintToPdf => "\tPrintToPdf",
     });
 
     println!("{}", debug_msg);
@@ -2,8 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use canvas_traits::webgl::WebGLChan;
 use devtools_traits::{DevtoolScriptControlMsg, WorkerId};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use dom::bindings::codegen::Bindings::ImageBitmapBinding::{ImageBitmapOptions, ImageBitmapSource};
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use dom::bindings::codegen::UnionTypes::RequestOrUSVString;
@@ -24,6 +26,7 @@ use dom::window::{base64_atob, base64_btoa};
 use dom::workerlocation::WorkerLocation;
 use dom::workernavigator::WorkerNavigator;
 use dom_struct::dom_struct;
+use euclid::{Point2D, Rect, Size2D};
 use fetch;
 use ipc_channel::ipc::IpcSender;
 use js::jsapi::{JSAutoCompartment, JSContext, JSRuntime};
@@ -51,7 +54,8 @@ use time::precise_time_ns;
 use timers::{IsInterval, TimerCallback};
 
 pub fn prepare_workerscope_init(global: &GlobalScope,
-                                devtools_sender: Option<IpcSender<DevtoolScriptControlMsg>>) -> WorkerGlobalScopeInit {
+                                devtools_sender: Option<IpcSender<DevtoolScriptControlMsg>>,
+                                webgl_chan: Option<WebGLChan>) -> WorkerGlobalScopeInit {
     let init = WorkerGlobalScopeInit {
             resource_threads: global.resource_threads().clone(),
             mem_profiler_chan: global.mem_profiler_chan().clone(),
@@ -63,6 +67,7 @@ pub fn prepare_workerscope_init(global: &GlobalScope,
             worker_id: global.get_next_worker_id(),
             pipeline_id: global.pipeline_id(),
             origin: global.origin().immutable().clone(),
+            webgl_chan: webgl_chan,
         };
 
     init
@@ -94,6 +99,10 @@ pub struct WorkerGlobalScope {
 
     navigation_start_precise: u64,
     performance: MutNullableDom<Performance>,
+
+    #[ignore_malloc_size_of = "Defined in canvas_traits"]
+    /// A channel to the WebGL thread, if WebGL is available.
+    webgl_chan: Option<WebGLChan>,
 }
 
 impl WorkerGlobalScope {
@@ -128,6 +137,7 @@ impl WorkerGlobalScope {
             from_devtools_receiver,
             navigation_start_precise: precise_time_ns(),
             performance: Default::default(),
+            webgl_chan: init.webgl_chan,
         }
     }
 
@@ -172,6 +182,10 @@ impl WorkerGlobalScope {
     pub fn pipeline_id(&self) -> PipelineId {
         self.globalscope.pipeline_id()
     }
+
+    pub fn webgl_chan(&self) -> Option<WebGLChan> {
+        self.webgl_chan.clone()
+    }
 }
 
 impl WorkerGlobalScopeMethods for WorkerGlobalScope {
@@ -320,6 +334,27 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
         fetch::Fetch(self.upcast(), input, init)
     }
 
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(&self, image: ImageBitmapSource, options: &ImageBitmapOptions) -> Rc<Promise> {
+        self.upcast::<GlobalScope>().create_image_bitmap(image, None, options)
+    }
+
+    #[allow(unrooted_must_root)]
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(
+        &self,
+        image: ImageBitmapSource,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        options: &ImageBitmapOptions,
+    ) -> Rc<Promise> {
+        let crop = Rect::new(Point2D::new(sx, sy), Size2D::new(sw, sh));
+        self.upcast::<GlobalScope>().create_image_bitmap(image, Some(crop), options)
+    }
+
     // https://w3c.github.io/hr-time/#the-performance-attribute
     fn Performance(&self) -> DomRoot<Performance> {
         self.performance.or_init(|| {
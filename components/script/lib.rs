@@ -108,6 +108,7 @@ extern crate xml5ever;
 #[macro_use]
 mod task;
 mod body;
+mod canvas_encoding;
 pub mod clipboard_provider;
 mod devtools;
 pub mod document_loader;
@@ -9,11 +9,13 @@
 use dom::bindings::callback::ExceptionHandling;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::PromiseBinding::PromiseJobCallback;
+use dom::bindings::inheritance::Castable;
 use dom::bindings::root::DomRoot;
 use dom::globalscope::GlobalScope;
 use dom::htmlimageelement::ImageElementMicrotask;
 use dom::htmlmediaelement::MediaElementMicrotask;
 use dom::mutationobserver::MutationObserver;
+use dom::window::Window;
 use msg::constellation_msg::PipelineId;
 use script_thread::ScriptThread;
 use std::cell::Cell;
@@ -36,6 +38,7 @@ pub enum Microtask {
     ImageElement(ImageElementMicrotask),
     CustomElementReaction,
     NotifyMutationObservers,
+    NotifyDevtoolsMutations(PipelineId),
 }
 
 pub trait MicrotaskRunnable {
@@ -94,6 +97,13 @@ impl MicrotaskQueue {
                     },
                     Microtask::NotifyMutationObservers => {
                         MutationObserver::notify_mutation_observers();
+                    },
+                    Microtask::NotifyDevtoolsMutations(pipeline) => {
+                        if let Some(target) = target_provider(pipeline) {
+                            if let Some(window) = target.downcast::<Window>() {
+                                window.flush_devtools_mutations();
+                            }
+                        }
                     }
                 }
             }
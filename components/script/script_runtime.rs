@@ -315,8 +315,10 @@ pub unsafe fn new_rt_and_cx() -> Runtime {
     Runtime(runtime)
 }
 
+/// Measure the heap allocations of the DOM object reflected by `obj`, or 0 if it
+/// is not a DOM object.
 #[allow(unsafe_code)]
-unsafe extern "C" fn get_size(obj: *mut JSObject) -> usize {
+pub unsafe extern "C" fn get_size(obj: *mut JSObject) -> usize {
     match get_dom_class(obj) {
         Ok(v) => {
             let dom_object = private_from_object(obj) as *const c_void;
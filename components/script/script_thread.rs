@@ -84,11 +84,11 @@ use net_traits::{Metadata, NetworkError, ReferrerPolicy, ResourceThreads};
 use net_traits::image_cache::{ImageCache, PendingImageResponse};
 use net_traits::request::{CredentialsMode, Destination, RedirectMode, RequestInit};
 use net_traits::storage_thread::StorageType;
-use profile_traits::mem::{self, OpaqueSender, ReportsChan};
+use profile_traits::mem::{self, OpaqueSender, Report, ReportKind, ReportsChan};
 use profile_traits::time::{self, ProfilerCategory, profile};
 use script_layout_interface::message::{self, Msg, NewLayoutThreadInfo, ReflowGoal};
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCategory};
-use script_runtime::{ScriptPort, get_reports, new_rt_and_cx, Runtime};
+use script_runtime::{ScriptPort, get_reports, get_size, new_rt_and_cx, Runtime};
 use script_traits::{CompositorEvent, ConstellationControlMsg};
 use script_traits::{DiscardBrowsingContext, DocumentActivity, EventResult};
 use script_traits::{InitialScriptState, JsEvalResult, LayoutMsg, LoadData};
@@ -1193,6 +1193,7 @@ impl ScriptThread {
                     DispatchStorageEvent(id, ..) => Some(id),
                     ReportCSSError(id, ..) => Some(id),
                     Reload(id, ..) => Some(id),
+                    PrintToPdf(id, ..) => Some(id),
                     WebVREvents(id, ..) => Some(id),
                     PaintMetric(..) => None,
                 }
@@ -1333,6 +1334,8 @@ impl ScriptThread {
                 self.handle_css_error_reporting(pipeline_id, filename, line, column, msg),
             ConstellationControlMsg::Reload(pipeline_id) =>
                 self.handle_reload(pipeline_id),
+            ConstellationControlMsg::PrintToPdf(pipeline_id, sender) =>
+                self.handle_print_to_pdf(pipeline_id, sender),
             ConstellationControlMsg::ExitPipeline(pipeline_id, discard_browsing_context) =>
                 self.handle_exit_pipeline_msg(pipeline_id, discard_browsing_context),
             ConstellationControlMsg::WebVREvents(pipeline_id, events) =>
@@ -1429,10 +1432,32 @@ impl ScriptThread {
                 devtools::handle_set_timeline_markers(&*documents, id, marker_types, reply),
             DevtoolScriptControlMsg::DropTimelineMarkers(id, marker_types) =>
                 devtools::handle_drop_timeline_markers(&*documents, id, marker_types),
+            DevtoolScriptControlMsg::SetMutationWatcher(id, reply) =>
+                devtools::handle_set_mutation_watcher(&*documents, id, reply),
+            DevtoolScriptControlMsg::DropMutationWatcher(id) =>
+                devtools::handle_drop_mutation_watcher(&*documents, id),
             DevtoolScriptControlMsg::RequestAnimationFrame(id, name) =>
                 devtools::handle_request_animation_frame(&*documents, id, name),
             DevtoolScriptControlMsg::Reload(id) =>
                 devtools::handle_reload(&*documents, id),
+            DevtoolScriptControlMsg::Navigate(id, url) =>
+                devtools::handle_navigate(&*documents, id, url),
+            DevtoolScriptControlMsg::ResizeWindow(id, width, height) =>
+                devtools::handle_resize_window(&*documents, id, width, height),
+            DevtoolScriptControlMsg::TakeHeapSnapshot(id, reply) =>
+                devtools::handle_take_heap_snapshot(&*documents, id, reply),
+            DevtoolScriptControlMsg::GetWebGLPrograms(id, reply) =>
+                devtools::handle_get_webgl_programs(&*documents, id, reply),
+            DevtoolScriptControlMsg::ReplaceWebGLShaderSource(id, key, shader_type, source, reply) =>
+                devtools::handle_replace_webgl_shader_source(&*documents, id, key, shader_type, source, reply),
+            DevtoolScriptControlMsg::DispatchInputEvent(id, event) => {
+                let events = devtools::compositor_events_for_input(&*documents, id, event);
+                // Event handlers may need to access the documents, so release them first.
+                drop(documents);
+                for event in events {
+                    self.handle_event(id, event);
+                }
+            }
         }
     }
 
@@ -1596,6 +1621,7 @@ impl ScriptThread {
         }
     }
 
+    #[allow(unsafe_code)]
     fn collect_reports(&self, reports_chan: ReportsChan) {
         let documents = self.documents.borrow();
         let urls = itertools::join(documents.iter().map(|(_, d)| d.url().to_string()), ", ");
@@ -1603,6 +1629,19 @@ impl ScriptThread {
 
         let mut reports = vec![];
         reports.extend(get_reports(self.get_cx(), path_seg));
+
+        for (_, document) in documents.iter() {
+            let mut dom_tree_size = 0;
+            for node in document.upcast::<Node>().traverse_preorder() {
+                dom_tree_size += unsafe { get_size(node.reflector().get_jsobject().get()) };
+            }
+            reports.push(Report {
+                path: path![format!("url({})", document.url()), "dom-tree"],
+                kind: ReportKind::ExplicitJemallocHeapSize,
+                size: dom_tree_size,
+            });
+        }
+
         reports_chan.send(reports);
     }
 
@@ -1859,6 +1898,9 @@ impl ScriptThread {
         debug!("shutting down layout for page {}", id);
         chan.send(message::Msg::ExitNow).ok();
         self.script_sender.send((id, ScriptMsg::PipelineExited)).ok();
+        if let Some(ref chan) = self.devtools_chan {
+            chan.send(ScriptToDevtoolsControlMsg::PipelineExited(id)).ok();
+        }
 
         // Now that layout is shut down, it's OK to remove the document.
         if let Some(document) = document {
@@ -1960,6 +2002,7 @@ impl ScriptThread {
         let document = self.documents.borrow().find_document(pipeline_id);
         if let Some(document) = document {
             self.rebuild_and_force_reflow(&document, ReflowReason::WebFontLoaded);
+            document.web_fonts_updated();
         }
     }
 
@@ -2209,7 +2252,10 @@ impl ScriptThread {
             .unwrap();
 
         // Notify devtools that a new script global exists.
-        self.notify_devtools(document.Title(), final_url.clone(), (incomplete.pipeline_id, None));
+        self.notify_devtools(document.Title(),
+                             final_url.clone(),
+                             incomplete.browsing_context_id,
+                             (incomplete.pipeline_id, None));
 
         let parse_input = DOMString::new();
 
@@ -2235,11 +2281,16 @@ impl ScriptThread {
         document.get_current_parser().unwrap()
     }
 
-    fn notify_devtools(&self, title: DOMString, url: ServoUrl, ids: (PipelineId, Option<WorkerId>)) {
+    fn notify_devtools(&self,
+                       title: DOMString,
+                       url: ServoUrl,
+                       browsing_context_id: BrowsingContextId,
+                       ids: (PipelineId, Option<WorkerId>)) {
         if let Some(ref chan) = self.devtools_chan {
             let page_info = DevtoolsPageInfo {
                 title: String::from(title),
                 url: url,
+                browsing_context: Some(browsing_context_id),
             };
             chan.send(ScriptToDevtoolsControlMsg::NewGlobal(
                         ids,
@@ -2619,6 +2670,14 @@ impl ScriptThread {
         }
     }
 
+    fn handle_print_to_pdf(&self, pipeline_id: PipelineId, sender: IpcSender<Option<Vec<u8>>>) {
+        let window = self.documents.borrow().find_window(pipeline_id);
+        let pdf = window.and_then(|window| window.print_to_pdf());
+        if let Err(e) = sender.send(pdf) {
+            warn!("Sending PDF to the constellation failed ({:?}).", e);
+        }
+    }
+
     fn handle_webvr_events(&self, pipeline_id: PipelineId, events: Vec<WebVREvent>) {
         let window = self.documents.borrow().find_window(pipeline_id);
         if let Some(window) = window {
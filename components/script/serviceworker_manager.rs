@@ -86,6 +86,7 @@ impl ServiceWorkerManager {
                 let page_info = DevtoolsPageInfo {
                     title: title,
                     url: scope_things.script_url.clone(),
+                    browsing_context: None,
                 };
                 let _ = chan.send(ScriptToDevtoolsControlMsg::NewGlobal((scope_things.init.pipeline_id,
                                                                          Some(scope_things.worker_id)),
@@ -21,9 +21,11 @@ use servo_url::ServoUrl;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
 use style::context::QuirksMode;
+use style::font_face::{EffectiveSources, FontDisplay};
 use style::properties::PropertyId;
 use style::selector_parser::PseudoElement;
 use style::stylesheets::Stylesheet;
+use style::values::computed::font::FamilyName;
 
 /// Asynchronous messages that script can send to layout.
 pub enum Msg {
@@ -78,6 +80,18 @@ pub enum Msg {
     /// false otherwise).
     GetWebFontLoadState(IpcSender<bool>),
 
+    /// Adds a web font created by script from a list of sources, as with a `FontFace` whose
+    /// source is a string, which is displayed while it loads as its `display` descriptor
+    /// specifies. The sender receives whether one of the sources could be loaded.
+    AddWebFont(FamilyName, EffectiveSources, FontDisplay, Atom, IpcSender<bool>),
+
+    /// Adds a web font created by script from binary data, as with a `FontFace` whose source
+    /// is an `ArrayBuffer`. The sender receives whether the data was a usable font.
+    AddWebFontData(FamilyName, Vec<u8>, Atom, IpcSender<bool>),
+
+    /// Removes a web font that was added by script with the given key, once it has loaded.
+    RemoveWebFont(Atom),
+
     /// Creates a new layout thread.
     ///
     /// This basically exists to keep the script-layout dependency one-way.
@@ -114,6 +128,7 @@ pub enum QueryMsg {
     NodeGeometryQuery(TrustedNodeAddress),
     NodeScrollGeometryQuery(TrustedNodeAddress),
     ResolvedStyleQuery(TrustedNodeAddress, Option<PseudoElement>, PropertyId),
+    ResolvedFontStyleQuery(TrustedNodeAddress, PropertyId, String),
     OffsetParentQuery(TrustedNodeAddress),
     StyleQuery(TrustedNodeAddress),
     TextIndexQuery(TrustedNodeAddress, Point2D<f32>),
@@ -127,6 +142,8 @@ pub enum ReflowGoal {
     Full,
     TickAnimations,
     LayoutQuery(QueryMsg, u64),
+    /// Lay the document out in pages for the print media type and serialise them as a PDF.
+    Print,
 }
 
 impl ReflowGoal {
@@ -135,6 +152,7 @@ impl ReflowGoal {
     pub fn needs_display_list(&self) -> bool {
         match *self {
             ReflowGoal::Full | ReflowGoal::TickAnimations => true,
+            ReflowGoal::Print => false,
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::NodesFromPointQuery(..) |
                 &QueryMsg::TextIndexQuery(..) |
@@ -145,6 +163,7 @@ impl ReflowGoal {
                 &QueryMsg::NodeScrollGeometryQuery(_) |
                 &QueryMsg::NodeScrollIdQuery(_) |
                 &QueryMsg::ResolvedStyleQuery(..) |
+                &QueryMsg::ResolvedFontStyleQuery(..) |
                 &QueryMsg::OffsetParentQuery(_) |
                 &QueryMsg::StyleQuery(_) => false,
             },
@@ -156,6 +175,7 @@ impl ReflowGoal {
     pub fn needs_display(&self) -> bool {
         match *self {
             ReflowGoal::Full | ReflowGoal::TickAnimations => true,
+            ReflowGoal::Print => false,
             ReflowGoal::LayoutQuery(ref querymsg, _) => match querymsg {
                 &QueryMsg::NodesFromPointQuery(..) |
                 &QueryMsg::TextIndexQuery(..) |
@@ -166,6 +186,7 @@ impl ReflowGoal {
                 &QueryMsg::NodeScrollGeometryQuery(_) |
                 &QueryMsg::NodeScrollIdQuery(_) |
                 &QueryMsg::ResolvedStyleQuery(..) |
+                &QueryMsg::ResolvedFontStyleQuery(..) |
                 &QueryMsg::OffsetParentQuery(_) |
                 &QueryMsg::StyleQuery(_) => false,
             },
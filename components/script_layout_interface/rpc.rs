@@ -8,6 +8,7 @@ use script_traits::UntrustedNodeAddress;
 use servo_arc::Arc;
 use style::properties::ComputedValues;
 use style::properties::longhands::overflow_x;
+use style::properties::style_structs::Font;
 use webrender_api::ExternalScrollId;
 
 /// Synchronous messages that script can send to layout.
@@ -31,6 +32,8 @@ pub trait LayoutRPC {
     fn node_scroll_id(&self) -> NodeScrollIdResponse;
     /// Query layout for the resolved value of a given CSS property
     fn resolved_style(&self) -> ResolvedStyleResponse;
+    /// Query layout for the computed value of a canvas `font`, resolved against an element.
+    fn resolved_font_style(&self) -> Option<Arc<Font>>;
     fn offset_parent(&self) -> OffsetParentResponse;
     /// Requests the styles for an element. Contains a `None` value if the element is in a `display:
     /// none` subtree.
@@ -40,6 +43,8 @@ pub trait LayoutRPC {
     fn nodes_from_point_response(&self) -> Vec<UntrustedNodeAddress>;
     /// Query layout to get the inner text for a given element.
     fn element_inner_text(&self) -> String;
+    /// Takes the PDF produced by the last print reflow, if the document could be paginated.
+    fn pdf(&self) -> Option<Vec<u8>>;
 }
 
 pub struct ContentBoxResponse(pub Option<Rect<Au>>);
@@ -40,9 +40,9 @@ mod script_msg;
 pub mod webdriver_msg;
 
 use bluetooth_traits::BluetoothRequest;
-use canvas_traits::webgl::WebGLPipeline;
+use canvas_traits::webgl::{WebGLChan, WebGLPipeline};
 use devtools_traits::{DevtoolScriptControlMsg, ScriptToDevtoolsControlMsg, WorkerId};
-use euclid::{Length, Point2D, Vector2D, Rect, TypedSize2D, TypedScale};
+use euclid::{Length, Point2D, Vector2D, Rect, Size2D, TypedSize2D, TypedScale};
 use gfx_traits::Epoch;
 use hyper::header::Headers;
 use hyper::method::Method;
@@ -324,6 +324,8 @@ pub enum ConstellationControlMsg {
     ReportCSSError(PipelineId, String, u32, u32, String),
     /// Reload the given page.
     Reload(PipelineId),
+    /// Lay the given page out for print media and send it back as a PDF document.
+    PrintToPdf(PipelineId, IpcSender<Option<Vec<u8>>>),
     /// Notifies the script thread of WebVR events.
     WebVREvents(PipelineId, Vec<WebVREvent>),
     /// Notifies the script thread about a new recorded paint metric.
@@ -362,6 +364,7 @@ impl fmt::Debug for ConstellationControlMsg {
             DispatchStorageEvent(..) => "DispatchStorageEvent",
             ReportCSSError(..) => "ReportCSSError",
             Reload(..) => "Reload",
+            PrintToPdf(..) => "PrintToPdf",
             WebVREvents(..) => "WebVREvents",
             PaintMetric(..) => "PaintMetric",
         };
@@ -625,6 +628,41 @@ pub struct ScrollState {
     pub scroll_offset: Vector2D<f32>,
 }
 
+/// How strictly a scroll container snaps to its snap positions along an axis.
+///
+/// <https://drafts.csswg.org/css-scroll-snap/#scroll-snap-type>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ScrollSnapStrictness {
+    /// The scroll position always comes to rest on a snap position.
+    Mandatory,
+    /// The scroll position only comes to rest on a snap position that is close to it.
+    Proximity,
+}
+
+/// The snap positions of a scroll container along one axis.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ScrollSnapAxis {
+    /// How strictly the scroll container snaps along this axis.
+    pub strictness: ScrollSnapStrictness,
+    /// The scroll positions to snap to, in CSS pixels from the start of the scrollable area.
+    pub positions: Vec<f32>,
+}
+
+/// The snap positions of a scroll frame, which the compositor snaps to once a scroll gesture
+/// comes to rest.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ScrollSnapPoints {
+    /// The ID of the scroll root.
+    pub scroll_id: ExternalScrollId,
+    /// The size of the scrollport, which determines how close a snap position has to be for
+    /// proximity snapping.
+    pub scrollport_size: Size2D<f32>,
+    /// The snap positions along the horizontal axis, if the scroll frame snaps horizontally.
+    pub x: Option<ScrollSnapAxis>,
+    /// The snap positions along the vertical axis, if the scroll frame snaps vertically.
+    pub y: Option<ScrollSnapAxis>,
+}
+
 /// Data about the window size.
 #[derive(Clone, Copy, Deserialize, MallocSizeOf, Serialize)]
 pub struct WindowSizeData {
@@ -695,6 +733,8 @@ pub enum ConstellationMsg {
     WebDriverCommand(WebDriverCommandMsg),
     /// Reload a top-level browsing context.
     Reload(TopLevelBrowsingContextId),
+    /// Print the active document of a top-level browsing context as a PDF document.
+    PrintToPdf(TopLevelBrowsingContextId, IpcSender<Option<Vec<u8>>>),
     /// A log entry, with the top-level browsing context id and thread name
     LogEntry(Option<TopLevelBrowsingContextId>, Option<String>, LogEntry),
     /// Dispatch WebVR events to the subscribed script threads.
@@ -729,6 +769,7 @@ impl fmt::Debug for ConstellationMsg {
             TickAnimation(..) => "TickAnimation",
             WebDriverCommand(..) => "WebDriverCommand",
             Reload(..) => "Reload",
+            PrintToPdf(..) => "PrintToPdf",
             LogEntry(..) => "LogEntry",
             WebVREvents(..) => "WebVREvents",
             NewBrowser(..) => "NewBrowser",
@@ -765,6 +806,8 @@ pub struct WorkerGlobalScopeInit {
     pub pipeline_id: PipelineId,
     /// The origin
     pub origin: ImmutableOrigin,
+    /// A channel to the WebGL thread, used by worker-side canvases
+    pub webgl_chan: Option<WebGLChan>,
 }
 
 /// Common entities representing a network load origin
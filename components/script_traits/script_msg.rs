@@ -8,6 +8,8 @@ use IFrameLoadInfo;
 use IFrameLoadInfoWithData;
 use LayoutControlMsg;
 use LoadData;
+use ScrollSnapPoints;
+use ScrollState;
 use WorkerGlobalScopeInit;
 use WorkerScriptLoadOrigin;
 use canvas_traits::canvas::{CanvasMsg, CanvasId};
@@ -40,6 +42,9 @@ pub enum LayoutMsg {
     PendingPaintMetric(PipelineId, Epoch),
     /// Requests that the constellation inform the compositor of the a cursor change.
     SetCursor(CursorKind),
+    /// Informs the constellation of the scroll snap points of the scroll frames of this pipeline,
+    /// for the compositor to snap to.
+    SetScrollSnapPoints(PipelineId, Vec<ScrollSnapPoints>),
     /// Notifies the constellation that the viewport has been constrained in some manner
     ViewportConstrained(PipelineId, ViewportConstraints),
 }
@@ -52,6 +57,7 @@ impl fmt::Debug for LayoutMsg {
             IFrameSizes(..) => "IFrameSizes",
             PendingPaintMetric(..) => "PendingPaintMetric",
             SetCursor(..) => "SetCursor",
+            SetScrollSnapPoints(..) => "SetScrollSnapPoints",
             ViewportConstrained(..) => "ViewportConstrained",
         };
         write!(formatter, "LayoutMsg::{}", variant)
@@ -145,6 +151,8 @@ pub enum ScriptMsg {
     SetDocumentState(DocumentState),
     /// Update the pipeline Url, which can change after redirections.
     SetFinalUrl(ServoUrl),
+    /// Requests that the compositor animate a scroll to the given scroll state.
+    SmoothScroll(ScrollState),
     /// Script has handled a touch event, and either prevented or allowed default actions.
     TouchEventProcessed(EventResult),
     /// A log entry, with the top-level browsing context id and thread name
@@ -200,6 +208,7 @@ impl fmt::Debug for ScriptMsg {
             ActivateDocument => "ActivateDocument",
             SetDocumentState(..) => "SetDocumentState",
             SetFinalUrl(..) => "SetFinalUrl",
+            SmoothScroll(..) => "SmoothScroll",
             TouchEventProcessed(..) => "TouchEventProcessed",
             LogEntry(..) => "LogEntry",
             DiscardDocument => "DiscardDocument",
@@ -151,9 +151,13 @@ impl<Window, Back> Servo<Window, Back> where Window: WindowMethods + 'static, Ba
         let debugger_chan = opts.debugger_port.map(|port| {
             debugger::start_server(port)
         });
-        let devtools_chan = opts.devtools_port.map(|port| {
-            devtools::start_server(port)
-        });
+        let devtools_chan = if opts.devtools_port.is_some() || opts.cdp_port.is_some() {
+            Some(devtools::start_server(opts.devtools_port,
+                                        opts.cdp_port,
+                                        mem_profiler_chan.clone()))
+        } else {
+            None
+        };
 
         let coordinates = window.get_coordinates();
 
@@ -325,6 +329,13 @@ impl<Window, Back> Servo<Window, Back> where Window: WindowMethods + 'static, Ba
                 }
             }
 
+            WindowEvent::PrintToPdf(top_level_browsing_context_id, sender) => {
+                let msg = ConstellationMsg::PrintToPdf(top_level_browsing_context_id, sender);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending print request to constellation failed ({}).", e);
+                }
+            }
+
             WindowEvent::ToggleWebRenderDebug(option) => {
                 self.compositor.toggle_webrender_debug(option);
             }
@@ -520,6 +531,17 @@ fn create_constellation<Back: gfx_hal::Backend>(
         webgl_threads
     });*/
 
+    // WebRender doesn't render with GL, so WebGL can't share its textures
+    // with it. In headless mode, WebGL renders with software OSMesa contexts
+    // and the frames are read back instead.
+    let webgl_threads = if opts::get().should_use_osmesa() {
+        Some(WebGLThreads::new_readback(GLContextFactory::headless_osmesa(),
+                                        webrender_api_sender.clone(),
+                                        webvr_compositor.map(|c| c as Box<_>)))
+    } else {
+        None
+    };
+
     let initial_state = InitialConstellationState {
         compositor_proxy,
         embedder_proxy,
@@ -534,7 +556,7 @@ fn create_constellation<Back: gfx_hal::Backend>(
         supports_clipboard,
         webrender_document,
         webrender_api_sender,
-        webgl_threads: None,
+        webgl_threads,
         webvr_chan,
     };
     let (constellation_chan, from_swmanager_sender) =
@@ -225,8 +225,23 @@ impl<'a> FontFace<'a> {
     /// sources which don't list any format hint, or the ones which list at
     /// least "truetype" or "opentype".
     pub fn effective_sources(&self) -> EffectiveSources {
+        EffectiveSources::new(self.sources())
+    }
+
+    /// Returns how this font face is displayed while it loads, which is `auto`
+    /// unless the rule has a `font-display` descriptor.
+    pub fn display(&self) -> FontDisplay {
+        self.0.display.unwrap_or(FontDisplay::Auto)
+    }
+}
+
+#[cfg(feature = "servo")]
+impl EffectiveSources {
+    /// Returns the effective sources among the given list of sources, as
+    /// described in `FontFace::effective_sources`.
+    pub fn new(sources: &[Source]) -> EffectiveSources {
         EffectiveSources(
-            self.sources()
+            sources
                 .iter()
                 .rev()
                 .filter(|source| {
@@ -309,6 +324,7 @@ impl Parse for Source {
     }
 }
 
+#[cfg(feature = "gecko")]
 macro_rules! is_descriptor_enabled {
     ("font-display") => {
         unsafe {
@@ -327,6 +343,13 @@ macro_rules! is_descriptor_enabled {
     };
 }
 
+#[cfg(feature = "servo")]
+macro_rules! is_descriptor_enabled {
+    ($name:tt) => {
+        true
+    };
+}
+
 macro_rules! font_face_descriptors_common {
     (
         $( #[$doc: meta] $name: tt $ident: ident / $gecko_ident: ident: $ty: ty, )*
@@ -491,5 +514,7 @@ font_face_descriptors! {
         "src" sources / mSrc: Vec<Source>,
     ]
     optional descriptors = [
+        /// The display of this font face.
+        "font-display" display / mDisplay: FontDisplay,
     ]
 }
@@ -25,9 +25,8 @@ use values::computed::FlexBasis as ComputedFlexBasis;
 use values::computed::basic_shape::ShapeRadius as ComputedShapeRadius;
 use values::generics::{CounterStyleOrNone, NonNegative};
 use values::generics::basic_shape::ShapeRadius;
-use values::generics::box_::Perspective;
+use values::generics::box_::{Perspective, ScrollSnapPoint};
 use values::generics::flex::FlexBasis;
-use values::generics::gecko::ScrollSnapPoint;
 use values::generics::grid::{TrackBreadth, TrackKeyword};
 
 /// A trait that defines an interface to convert from and to `nsStyleCoord`s.
@@ -453,7 +452,7 @@ impl GeckoStyleCoordConvertible for ScrollSnapPoint<LengthOrPercentage> {
 
     fn from_gecko_style_coord<T: CoordData>(coord: &T) -> Option<Self> {
         use gecko_bindings::structs::root::nsStyleUnit;
-        use values::generics::gecko::ScrollSnapPoint;
+        use values::generics::box_::ScrollSnapPoint;
 
         Some(match coord.unit() {
             nsStyleUnit::eStyleUnit_None => ScrollSnapPoint::None,
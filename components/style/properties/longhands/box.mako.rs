@@ -285,7 +285,6 @@ ${helpers.predefined_type("animation-delay",
         "computed::ScrollSnapPoint::none()",
         animation_value_type="discrete",
         gecko_pref="layout.css.scroll-snap.enabled",
-        products="gecko",
         spec="Nonstandard (https://www.w3.org/TR/2015/WD-css-snappoints-1-20150326/#scroll-snap-points)",
     )}
 % endfor
@@ -293,7 +292,6 @@ ${helpers.predefined_type("animation-delay",
 ${helpers.predefined_type("scroll-snap-destination",
                           "Position",
                           "computed::Position::zero()",
-                          products="gecko",
                           gecko_pref="layout.css.scroll-snap.enabled",
                           boxed=True,
                           spec="Nonstandard (https://developer.mozilla.org/en-US/docs/Web/CSS/scroll-snap-destination)",
@@ -304,7 +302,6 @@ ${helpers.predefined_type(
     "Position",
     "computed::Position::zero()",
     vector=True,
-    products="gecko",
     gecko_pref="layout.css.scroll-snap.enabled",
     spec="Nonstandard (https://developer.mozilla.org/en-US/docs/Web/CSS/scroll-snap-destination)",
     animation_value_type="discrete",
@@ -361,7 +358,6 @@ ${helpers.predefined_type(
 ${helpers.single_keyword("scroll-behavior",
                          "auto smooth",
                          gecko_pref="layout.css.scroll-behavior.property-enabled",
-                         products="gecko",
                          spec="https://drafts.csswg.org/cssom-view/#propdef-scroll-behavior",
                          animation_value_type="discrete")}
 
@@ -370,7 +366,6 @@ ${helpers.single_keyword("scroll-behavior",
         "scroll-snap-type-" + axis,
         "ScrollSnapType",
         "computed::ScrollSnapType::None",
-        products="gecko",
         needs_context=False,
         gecko_pref="layout.css.scroll-snap.enabled",
         spec="Nonstandard (https://developer.mozilla.org/en-US/docs/Web/CSS/scroll-snap-type-x)",
@@ -404,21 +399,32 @@ ${helpers.single_keyword("isolation",
 // TODO add support for logical values recto and verso
 ${helpers.single_keyword("page-break-after",
                          "auto always avoid left right",
-                         products="gecko",
                          spec="https://drafts.csswg.org/css2/page.html#propdef-page-break-after",
-                         animation_value_type="discrete")}
+                         animation_value_type="discrete",
+                         servo_restyle_damage="reflow")}
 ${helpers.single_keyword("page-break-before",
                          "auto always avoid left right",
-                         products="gecko",
                          spec="https://drafts.csswg.org/css2/page.html#propdef-page-break-before",
-                         animation_value_type="discrete")}
+                         animation_value_type="discrete",
+                         servo_restyle_damage="reflow")}
 ${helpers.single_keyword("page-break-inside",
                          "auto avoid",
-                         products="gecko",
                          gecko_ffi_name="mBreakInside",
                          gecko_constant_prefix="NS_STYLE_PAGE_BREAK",
                          spec="https://drafts.csswg.org/css2/page.html#propdef-page-break-inside",
-                         animation_value_type="discrete")}
+                         animation_value_type="discrete",
+                         servo_restyle_damage="reflow")}
+
+// Only meaningful inside `@page` rules, where it sets the size of the page box.
+${helpers.predefined_type(
+    "size",
+    "PageSize",
+    "computed::PageSize::auto()",
+    products="servo",
+    animation_value_type="none",
+    allowed_in_page_rule=True,
+    spec="https://drafts.csswg.org/css-page-3/#page-size-prop",
+)}
 
 // CSS Basic User Interface Module Level 3
 // http://dev.w3.org/csswg/css-ui
@@ -569,10 +575,10 @@ ${helpers.predefined_type(
 
 ${helpers.predefined_type(
     "shape-image-threshold", "Opacity", "0.0",
-    products="gecko",
     gecko_pref="layout.css.shape-outside.enabled",
     animation_value_type="ComputedValue",
     flags="APPLIES_TO_FIRST_LETTER",
+    servo_restyle_damage="reflow",
     spec="https://drafts.csswg.org/css-shapes/#shape-image-threshold-property",
 )}
 
@@ -580,10 +586,10 @@ ${helpers.predefined_type(
     "shape-margin",
     "NonNegativeLengthOrPercentage",
     "computed::NonNegativeLengthOrPercentage::zero()",
-    products="gecko",
     gecko_pref="layout.css.shape-outside.enabled",
     animation_value_type="NonNegativeLengthOrPercentage",
     flags="APPLIES_TO_FIRST_LETTER",
+    servo_restyle_damage="reflow",
     spec="https://drafts.csswg.org/css-shapes/#shape-margin-property",
 )}
 
@@ -591,11 +597,11 @@ ${helpers.predefined_type(
     "shape-outside",
     "basic_shape::FloatAreaShape",
     "generics::basic_shape::ShapeSource::None",
-    products="gecko",
     boxed=True,
     gecko_pref="layout.css.shape-outside.enabled",
     animation_value_type="ComputedValue",
     flags="APPLIES_TO_FIRST_LETTER",
+    servo_restyle_damage="reflow",
     spec="https://drafts.csswg.org/css-shapes/#shape-outside-property",
 )}
 
@@ -25,7 +25,6 @@ ${helpers.single_keyword(
     extra_gecko_aliases="lr=horizontal-tb lr-tb=horizontal-tb \
                          rl=horizontal-tb rl-tb=horizontal-tb \
                          tb=vertical-rl   tb-rl=vertical-rl",
-    servo_pref="layout.writing-mode.enabled",
     animation_value_type="none",
     spec="https://drafts.csswg.org/css-writing-modes/#propdef-writing-mode",
     servo_restyle_damage="rebuild_and_reflow",
@@ -47,9 +46,9 @@ ${helpers.single_keyword(
     "text-orientation",
     "mixed upright sideways",
     extra_gecko_aliases="sideways-right=sideways",
-    products="gecko",
     animation_value_type="discrete",
     spec="https://drafts.csswg.org/css-writing-modes/#propdef-text-orientation",
+    servo_restyle_damage="rebuild_and_reflow",
 )}
 
 // CSS Color Module Level 4
@@ -30,8 +30,17 @@ ${helpers.single_keyword("text-transform",
 
 ${helpers.single_keyword("hyphens", "manual none auto",
                          gecko_enum_prefix="StyleHyphens",
-                         products="gecko", animation_value_type="discrete", extra_prefixes="moz",
-                         spec="https://drafts.csswg.org/css-text/#propdef-hyphens")}
+                         animation_value_type="discrete", extra_prefixes="moz",
+                         spec="https://drafts.csswg.org/css-text/#propdef-hyphens",
+                         servo_restyle_damage="rebuild_and_reflow")}
+
+${helpers.predefined_type("hyphenate-character",
+                          "HyphenateCharacter",
+                          "computed::HyphenateCharacter::Auto",
+                          products="servo",
+                          animation_value_type="discrete",
+                          spec="https://drafts.csswg.org/css-text-4/#propdef-hyphenate-character",
+                          servo_restyle_damage="rebuild_and_reflow")}
 
 // TODO: Support <percentage>
 ${helpers.single_keyword("-moz-text-size-adjust", "auto none",
@@ -66,12 +75,10 @@ ${helpers.single_keyword("word-break",
                          spec="https://drafts.csswg.org/css-text/#propdef-word-break",
                          servo_restyle_damage="rebuild_and_reflow")}
 
-// TODO(pcwalton): Support `text-justify: distribute`.
 <%helpers:single_keyword
     name="text-justify"
-    values="auto none inter-word"
-    extra_gecko_values="inter-character"
-    extra_specified="${'distribute' if product == 'gecko' else ''}"
+    values="auto none inter-word inter-character"
+    extra_specified="distribute"
     gecko_enum_prefix="StyleTextJustify"
     animation_value_type="discrete"
     gecko_pref="layout.css.text-justify.enabled"
@@ -79,7 +86,6 @@ ${helpers.single_keyword("word-break",
     spec="https://drafts.csswg.org/css-text/#propdef-text-justify"
     servo_restyle_damage="rebuild_and_reflow"
 >
-    % if product == 'gecko':
     impl ToComputedValue for SpecifiedValue {
         type ComputedValue = computed_value::T;
 
@@ -103,7 +109,6 @@ ${helpers.single_keyword("word-break",
             }
         }
     }
-    % endif
 </%helpers:single_keyword>
 
 ${helpers.single_keyword("text-align-last",
@@ -292,8 +297,9 @@ ${helpers.single_keyword("ruby-position", "over under",
 // https://drafts.csswg.org/css-writing-modes-3/
 
 ${helpers.single_keyword("text-combine-upright", "none all",
-                         products="gecko", animation_value_type="discrete",
-                         spec="https://drafts.csswg.org/css-writing-modes-3/#text-combine-upright")}
+                         animation_value_type="discrete",
+                         spec="https://drafts.csswg.org/css-writing-modes-3/#text-combine-upright",
+                         servo_restyle_damage="rebuild_and_reflow")}
 
 // SVG 1.1: Section 11 - Painting: Filling, Stroking and Marker Symbols
 ${helpers.single_keyword("text-rendering",
@@ -311,3 +317,17 @@ ${helpers.single_keyword("-moz-control-character-visibility",
                          animation_value_type="none",
                          products="gecko",
                          spec="Nonstandard")}
+
+// CSS Fragmentation Module Level 3
+// https://drafts.csswg.org/css-break/
+% for name in ["orphans", "widows"]:
+    ${helpers.predefined_type(
+        name,
+        "PositiveInteger",
+        "computed::PositiveInteger::from(2)",
+        products="servo",
+        animation_value_type="discrete",
+        spec="https://drafts.csswg.org/css-break/#propdef-%s" % name,
+        servo_restyle_damage="reflow",
+    )}
+% endfor
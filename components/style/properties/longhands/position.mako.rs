@@ -82,7 +82,8 @@ ${helpers.single_keyword("flex-wrap", "nowrap wrap wrap-reverse",
 
 % if product == "servo":
     // FIXME: Update Servo to support the same Syntax as Gecko.
-    ${helpers.single_keyword("justify-content", "flex-start stretch flex-end center space-between space-around",
+    ${helpers.single_keyword("justify-content",
+                             "normal flex-start stretch flex-end center space-between space-around start end",
                              extra_prefixes="webkit",
                              spec="https://drafts.csswg.org/css-align/#propdef-justify-content",
                              animation_value_type="discrete",
@@ -99,18 +100,25 @@ ${helpers.single_keyword("flex-wrap", "nowrap wrap wrap-reverse",
 
 % if product == "servo":
     // FIXME: Update Servo to support the same Syntax as Gecko.
-    ${helpers.single_keyword("align-content", "stretch flex-start flex-end center space-between space-around",
+    ${helpers.single_keyword("align-content",
+                             "stretch flex-start flex-end center space-between space-around start end",
                              extra_prefixes="webkit",
                              spec="https://drafts.csswg.org/css-align/#propdef-align-content",
                              animation_value_type="discrete",
                              servo_restyle_damage = "reflow")}
 
     ${helpers.single_keyword("align-items",
-                             "stretch flex-start flex-end center baseline",
+                             "stretch flex-start flex-end center baseline start end",
                              extra_prefixes="webkit",
                              spec="https://drafts.csswg.org/css-flexbox/#align-items-property",
                              animation_value_type="discrete",
                              servo_restyle_damage = "reflow")}
+
+    ${helpers.single_keyword("justify-items",
+                             "stretch start end center",
+                             spec="https://drafts.csswg.org/css-align/#propdef-justify-items",
+                             animation_value_type="discrete",
+                             servo_restyle_damage = "reflow")}
 % else:
     ${helpers.predefined_type(name="align-content",
                               type="AlignContent",
@@ -161,11 +169,16 @@ ${helpers.predefined_type("flex-shrink", "NonNegativeNumber",
 // https://drafts.csswg.org/css-align/#align-self-property
 % if product == "servo":
     // FIXME: Update Servo to support the same syntax as Gecko.
-    ${helpers.single_keyword("align-self", "auto stretch flex-start flex-end center baseline",
+    ${helpers.single_keyword("align-self", "auto stretch flex-start flex-end center baseline start end",
                              extra_prefixes="webkit",
                              spec="https://drafts.csswg.org/css-flexbox/#propdef-align-self",
                              animation_value_type="discrete",
                              servo_restyle_damage = "reflow")}
+
+    ${helpers.single_keyword("justify-self", "auto stretch start end center",
+                             spec="https://drafts.csswg.org/css-align/#justify-self-property",
+                             animation_value_type="discrete",
+                             servo_restyle_damage = "reflow")}
 % else:
     ${helpers.predefined_type(name="align-self",
                               type="AlignSelf",
@@ -323,8 +336,8 @@ ${helpers.predefined_type("object-position",
                                   "Default::default()",
                                   animation_value_type="discrete",
                                   spec="https://drafts.csswg.org/css-grid/#propdef-grid-%s-%s" % (kind, range),
-                                  products="gecko",
-                                  boxed=True)}
+                                  boxed=True,
+                                  servo_restyle_damage="reflow")}
     % endfor
 
     // NOTE: According to the spec, this should handle multiple values of `<track-size>`,
@@ -334,18 +347,18 @@ ${helpers.predefined_type("object-position",
                               "Default::default()",
                               animation_value_type="discrete",
                               spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-%ss" % kind,
-                              products="gecko",
-                              boxed=True)}
+                              boxed=True,
+                              servo_restyle_damage="reflow")}
 
     ${helpers.predefined_type(
         "grid-template-%ss" % kind,
         "GridTemplateComponent",
         "specified::GenericGridTemplateComponent::None",
-        products="gecko",
         spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-%ss" % kind,
         boxed=True,
         flags="GETCS_NEEDS_LAYOUT_FLUSH",
-        animation_value_type="discrete"
+        animation_value_type="discrete",
+        servo_restyle_damage="reflow"
     )}
 
 % endfor
@@ -353,23 +366,22 @@ ${helpers.predefined_type("object-position",
 ${helpers.predefined_type("grid-auto-flow",
                           "GridAutoFlow",
                           initial_value="computed::GridAutoFlow::row()",
-                          products="gecko",
                           animation_value_type="discrete",
-                          spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-flow")}
+                          spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-flow",
+                          servo_restyle_damage="reflow")}
 
 ${helpers.predefined_type("grid-template-areas",
                           "GridTemplateAreas",
                           initial_value="computed::GridTemplateAreas::none()",
-                          products="gecko",
                           animation_value_type="discrete",
-                          spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-areas")}
+                          spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-areas",
+                          servo_restyle_damage="reflow")}
 
 ${helpers.predefined_type("column-gap",
                           "length::NonNegativeLengthOrPercentageOrNormal",
                           "Either::Second(Normal)",
-                          alias="grid-column-gap" if product == "gecko" else "",
+                          alias="grid-column-gap",
                           extra_prefixes="moz",
-                          servo_pref="layout.columns.enabled",
                           spec="https://drafts.csswg.org/css-align-3/#propdef-column-gap",
                           animation_value_type="NonNegativeLengthOrPercentageOrNormal",
                           servo_restyle_damage = "reflow")}
@@ -379,7 +391,6 @@ ${helpers.predefined_type("row-gap",
                           "length::NonNegativeLengthOrPercentageOrNormal",
                           "Either::Second(Normal)",
                           alias="grid-row-gap",
-                          products="gecko",
                           spec="https://drafts.csswg.org/css-align-3/#propdef-row-gap",
                           animation_value_type="NonNegativeLengthOrPercentageOrNormal",
                           servo_restyle_damage = "reflow")}
@@ -1138,15 +1138,6 @@ impl LonghandId {
     fn is_early_property(&self) -> bool {
         matches!(*self,
             % if product == 'gecko':
-
-            // Needed to properly compute the writing mode, to resolve logical
-            // properties, and similar stuff. In this block instead of along
-            // `WritingMode` and `Direction` just for convenience, since it's
-            // Gecko-only (for now at least).
-            //
-            // see WritingMode::new.
-            LonghandId::TextOrientation |
-
             // Needed to properly compute the zoomed font-size.
             //
             // FIXME(emilio): This could probably just be a cascade flag like
@@ -1180,8 +1171,11 @@ impl LonghandId {
 
             // Needed to properly compute the writing mode, to resolve logical
             // properties, and similar stuff.
+            //
+            // see WritingMode::new.
             LonghandId::WritingMode |
-            LonghandId::Direction
+            LonghandId::Direction |
+            LonghandId::TextOrientation
         )
     }
 
@@ -9,15 +9,15 @@
                     sub_properties="font-style font-variant-caps font-weight font-stretch
                                     font-size line-height font-family
                                     ${'font-size-adjust' if product == 'gecko' else ''}
-                                    ${'font-kerning' if product == 'gecko' else ''}
+                                    font-kerning
                                     ${'font-optical-sizing' if product == 'gecko' else ''}
                                     ${'font-variant-alternates' if product == 'gecko' else ''}
-                                    ${'font-variant-east-asian' if product == 'gecko' else ''}
-                                    ${'font-variant-ligatures' if product == 'gecko' else ''}
-                                    ${'font-variant-numeric' if product == 'gecko' else ''}
-                                    ${'font-variant-position' if product == 'gecko' else ''}
+                                    font-variant-east-asian
+                                    font-variant-ligatures
+                                    font-variant-numeric
+                                    font-variant-position
                                     ${'font-language-override' if product == 'gecko' else ''}
-                                    ${'font-feature-settings' if product == 'gecko' else ''}
+                                    font-feature-settings
                                     ${'font-variation-settings' if product == 'gecko' else ''}"
                     derive_value_info="False"
                     spec="https://drafts.csswg.org/css-fonts-3/#propdef-font">
@@ -31,17 +31,16 @@
     use values::specified::font::{FontStretch, FontStretchKeyword};
 
     <%
-        gecko_sub_properties = "kerning language_override size_adjust \
-                                variant_alternates variant_east_asian \
-                                variant_ligatures variant_numeric \
-                                variant_position feature_settings \
-                                variation_settings optical_sizing".split()
+        # Servo doesn't reset font-variation-settings through the shorthand.
+        reset_sub_properties = "kerning variant_east_asian variant_ligatures \
+                                variant_numeric variant_position feature_settings".split()
+        if product == "gecko":
+            reset_sub_properties += "language_override size_adjust variant_alternates \
+                                     variation_settings optical_sizing".split()
     %>
-    % if product == "gecko":
-        % for prop in gecko_sub_properties:
-            use properties::longhands::font_${prop};
-        % endfor
-    % endif
+    % for prop in reset_sub_properties:
+        use properties::longhands::font_${prop};
+    % endfor
     use self::font_family::SpecifiedValue as FontFamily;
 
     pub fn parse_value<'i, 't>(
@@ -135,11 +134,9 @@
             font_size: size,
             line_height: line_height.unwrap_or(LineHeight::normal()),
             font_family: family,
-            % if product == "gecko":
-                % for name in gecko_sub_properties:
-                    font_${name}: font_${name}::get_initial_specified_value(),
-                % endfor
-            % endif
+            % for name in reset_sub_properties:
+                font_${name}: font_${name}::get_initial_specified_value(),
+            % endfor
         })
     }
 
@@ -172,15 +169,15 @@
                     return Ok(());
                 }
             }
+            % endif
 
-            % for name in gecko_sub_properties:
-            % if name != "optical_sizing" and name != "variation_settings":
+            % for name in reset_sub_properties:
+            % if name not in ["optical_sizing", "variation_settings"]:
             if self.font_${name} != &font_${name}::get_initial_specified_value() {
                 return Ok(());
             }
             % endif
             % endfor
-            % endif
 
             // Only font-stretch keywords are allowed as part as the font
             // shorthand.
@@ -288,22 +285,21 @@
                     flags="SHORTHAND_IN_GETCS"
                     sub_properties="font-variant-caps
                                     ${'font-variant-alternates' if product == 'gecko' else ''}
-                                    ${'font-variant-east-asian' if product == 'gecko' else ''}
-                                    ${'font-variant-ligatures' if product == 'gecko' else ''}
-                                    ${'font-variant-numeric' if product == 'gecko' else ''}
-                                    ${'font-variant-position' if product == 'gecko' else ''}"
+                                    font-variant-east-asian
+                                    font-variant-ligatures
+                                    font-variant-numeric
+                                    font-variant-position"
                     spec="https://drafts.csswg.org/css-fonts-3/#propdef-font-variant">
-    <% gecko_sub_properties = "alternates east_asian ligatures numeric position".split() %>
     <%
         sub_properties = ["caps"]
         if product == "gecko":
-            sub_properties += gecko_sub_properties
+            sub_properties += ["alternates"]
+        sub_properties += "east_asian ligatures numeric position".split()
     %>
 
 % for prop in sub_properties:
     use properties::longhands::font_variant_${prop};
 % endfor
-    #[allow(unused_imports)]
     use values::specified::FontVariantLigatures;
 
     pub fn parse_value<'i, 't>(
@@ -319,9 +315,7 @@
         } else if input.try(|input| input.expect_ident_matching("none")).is_ok() {
             // The 'none' value sets 'font-variant-ligatures' to 'none' and resets all other sub properties
             // to their initial value.
-        % if product == "gecko":
             ligatures = Some(FontVariantLigatures::none());
-        % endif
         } else {
             let mut has_custom_value: bool = false;
             loop {
@@ -359,11 +353,7 @@
         fn to_css<W>(&self, dest: &mut CssWriter<W>) -> fmt::Result where W: fmt::Write {
 
             let has_none_ligatures =
-            % if product == "gecko":
                 self.font_variant_ligatures == &FontVariantLigatures::none();
-            % else:
-                false;
-            % endif
 
             const TOTAL_SUBPROPS: usize = ${len(sub_properties)};
             let mut nb_normals = 0;
@@ -109,8 +109,7 @@
 </%helpers:shorthand>
 
 <%helpers:shorthand name="gap" alias="grid-gap" sub_properties="row-gap column-gap"
-                    spec="https://drafts.csswg.org/css-align-3/#gap-shorthand"
-                    products="gecko">
+                    spec="https://drafts.csswg.org/css-align-3/#gap-shorthand">
   use properties::longhands::{row_gap, column_gap};
 
   pub fn parse_value<'i, 't>(context: &ParserContext, input: &mut Parser<'i, 't>)
@@ -140,8 +139,7 @@
 
 % for kind in ["row", "column"]:
 <%helpers:shorthand name="grid-${kind}" sub_properties="grid-${kind}-start grid-${kind}-end"
-                    spec="https://drafts.csswg.org/css-grid/#propdef-grid-${kind}"
-                    products="gecko">
+                    spec="https://drafts.csswg.org/css-grid/#propdef-grid-${kind}">
     use values::specified::GridLine;
     use parser::Parse;
 
@@ -182,8 +180,7 @@
 
 <%helpers:shorthand name="grid-area"
                     sub_properties="grid-row-start grid-row-end grid-column-start grid-column-end"
-                    spec="https://drafts.csswg.org/css-grid/#propdef-grid-area"
-                    products="gecko">
+                    spec="https://drafts.csswg.org/css-grid/#propdef-grid-area">
     use values::specified::GridLine;
     use parser::Parse;
 
@@ -249,8 +246,7 @@
 
 <%helpers:shorthand name="grid-template"
                     sub_properties="grid-template-rows grid-template-columns grid-template-areas"
-                    spec="https://drafts.csswg.org/css-grid/#propdef-grid-template"
-                    products="gecko">
+                    spec="https://drafts.csswg.org/css-grid/#propdef-grid-template">
     use parser::Parse;
     use servo_arc::Arc;
     use values::{Either, None_};
@@ -482,8 +478,7 @@
 <%helpers:shorthand name="grid"
                     sub_properties="grid-template-rows grid-template-columns grid-template-areas
                                     grid-auto-rows grid-auto-columns grid-auto-flow"
-                    spec="https://drafts.csswg.org/css-grid/#propdef-grid"
-                    products="gecko">
+                    spec="https://drafts.csswg.org/css-grid/#propdef-grid">
     use parser::Parse;
     use properties::longhands::{grid_auto_columns, grid_auto_rows, grid_auto_flow};
     use values::{Either, None_};
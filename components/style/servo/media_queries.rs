@@ -6,17 +6,18 @@
 
 use app_units::Au;
 use context::QuirksMode;
-use cssparser::{Parser, RGBA};
+use cssparser::{CowRcStr, Parser, RGBA, Token};
 use euclid::{Size2D, TypedScale, TypedSize2D};
 use media_queries::MediaType;
-use parser::ParserContext;
+use parser::{Parse, ParserContext};
 use properties::ComputedValues;
-use selectors::parser::SelectorParseErrorKind;
+use servo_config::prefs::PREFS;
 use std::fmt::{self, Write};
 use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
-use style_traits::{CSSPixel, CssWriter, DevicePixel, ParseError, ToCss};
+use str::starts_with_ignore_ascii_case;
+use style_traits::{CSSPixel, CssWriter, DevicePixel, ParseError, StyleParseErrorKind, ToCss};
 use style_traits::viewport::ViewportConstraints;
-use values::{specified, KeyframesName};
+use values::{specified, CSSFloat, KeyframesName};
 use values::computed::{self, ToComputedValue};
 use values::computed::font::FontSize;
 
@@ -144,6 +145,12 @@ impl Device {
         self.media_type.clone()
     }
 
+    /// Returns whether content is laid out into pages on this device, that is,
+    /// whether it is used for printing.
+    pub fn is_paged(&self) -> bool {
+        self.media_type == MediaType::print()
+    }
+
     /// Returns whether document colors are enabled.
     pub fn use_document_colors(&self) -> bool {
         true
@@ -153,21 +160,219 @@ impl Device {
     pub fn default_background_color(&self) -> RGBA {
         RGBA::new(255, 255, 255, 255)
     }
+
+    /// Returns the number of bits per color component of the device.
+    pub fn color_bits(&self) -> u32 {
+        8
+    }
+
+    /// Returns how accurately the primary pointing device of the user can
+    /// point, from the `layout.media.pointer` pref.
+    pub fn pointer(&self) -> Pointer {
+        match PREFS.get("layout.media.pointer").as_string() {
+            Some("none") => Pointer::None,
+            Some("coarse") => Pointer::Coarse,
+            _ => Pointer::Fine,
+        }
+    }
+
+    /// Returns whether the primary pointing device of the user can hover over
+    /// elements, from the `layout.media.hover` pref.
+    pub fn hover(&self) -> Hover {
+        match PREFS.get("layout.media.hover").as_string() {
+            Some("none") => Hover::None,
+            _ => Hover::Hover,
+        }
+    }
+
+    /// Returns whether the user prefers less motion on the page, from the
+    /// `layout.media.prefers-reduced-motion` pref set by the embedder.
+    pub fn prefers_reduced_motion(&self) -> PrefersReducedMotion {
+        match PREFS.get("layout.media.prefers-reduced-motion").as_boolean() {
+            Some(true) => PrefersReducedMotion::Reduce,
+            _ => PrefersReducedMotion::NoPreference,
+        }
+    }
+
+    /// Returns the color scheme the user prefers, from the
+    /// `layout.media.prefers-color-scheme` pref set by the embedder.
+    pub fn prefers_color_scheme(&self) -> PrefersColorScheme {
+        match PREFS.get("layout.media.prefers-color-scheme").as_string() {
+            Some("dark") => PrefersColorScheme::Dark,
+            _ => PrefersColorScheme::Light,
+        }
+    }
+
+    /// Returns how the document is presented, from the
+    /// `layout.media.display-mode` pref set by the embedder.
+    pub fn display_mode(&self) -> DisplayMode {
+        match PREFS.get("layout.media.display-mode").as_string() {
+            Some("fullscreen") => DisplayMode::Fullscreen,
+            Some("standalone") => DisplayMode::Standalone,
+            Some("minimal-ui") => DisplayMode::MinimalUi,
+            _ => DisplayMode::Browser,
+        }
+    }
+}
+
+/// The values of the `orientation` media feature.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq, ToCss)]
+#[allow(missing_docs)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+/// The values of the `hover` and `any-hover` media features.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq, ToCss)]
+#[allow(missing_docs)]
+pub enum Hover {
+    None,
+    Hover,
+}
+
+/// The values of the `pointer` and `any-pointer` media features.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq, ToCss)]
+#[allow(missing_docs)]
+pub enum Pointer {
+    None,
+    Coarse,
+    Fine,
+}
+
+/// The values of the `prefers-reduced-motion` media feature.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq, ToCss)]
+#[allow(missing_docs)]
+pub enum PrefersReducedMotion {
+    NoPreference,
+    Reduce,
+}
+
+/// The values of the `prefers-color-scheme` media feature.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq, ToCss)]
+#[allow(missing_docs)]
+pub enum PrefersColorScheme {
+    Light,
+    Dark,
+}
+
+/// The values of the `display-mode` media feature.
+///
+/// <https://w3c.github.io/manifest/#the-display-mode-media-feature>
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq, ToCss)]
+#[allow(missing_docs)]
+pub enum DisplayMode {
+    Fullscreen,
+    Standalone,
+    MinimalUi,
+    Browser,
+}
+
+/// A `<ratio>` value, as used by the `aspect-ratio` media feature.
+///
+/// <https://drafts.csswg.org/mediaqueries-4/#typedef-ratio>
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, PartialEq)]
+pub struct AspectRatio(pub u32, pub u32);
+
+impl AspectRatio {
+    fn to_f64(&self) -> f64 {
+        self.0 as f64 / self.1 as f64
+    }
+}
+
+impl Parse for AspectRatio {
+    fn parse<'i, 't>(
+        context: &ParserContext,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i>> {
+        let width = specified::Integer::parse_positive(context, input)?;
+        input.expect_delim('/')?;
+        let height = specified::Integer::parse_positive(context, input)?;
+        Ok(AspectRatio(width.value() as u32, height.value() as u32))
+    }
+}
+
+impl ToCss for AspectRatio {
+    fn to_css<W>(&self, dest: &mut CssWriter<W>) -> fmt::Result
+    where
+        W: Write,
+    {
+        write!(dest, "{}/{}", self.0, self.1)
+    }
 }
 
 /// A expression kind servo understands and parses.
 ///
+/// Range features are `None` and discrete features have no value when they
+/// are in a boolean context, as in `(hover)`.
+///
 /// Only `pub` for unit testing, please don't use it directly!
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "servo", derive(MallocSizeOf))]
 pub enum ExpressionKind {
-    /// <http://dev.w3.org/csswg/mediaqueries-3/#width>
-    Width(Range<specified::Length>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#width>
+    Width(Option<Range<specified::Length>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#height>
+    Height(Option<Range<specified::Length>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#aspect-ratio>
+    AspectRatio(Option<Range<AspectRatio>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#orientation>
+    Orientation(Option<Orientation>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#resolution>
+    Resolution(Option<Range<specified::Resolution>>),
+    /// The non-standard `-webkit-device-pixel-ratio`, which is the resolution
+    /// in `dppx`.
+    DevicePixelRatio(Option<Range<CSSFloat>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#color>
+    Color(Option<Range<u32>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#color-index>
+    ColorIndex(Option<Range<u32>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#monochrome>
+    Monochrome(Option<Range<u32>>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#hover>
+    Hover(Option<Hover>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#any-input>
+    AnyHover(Option<Hover>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#pointer>
+    Pointer(Option<Pointer>),
+    /// <https://drafts.csswg.org/mediaqueries-4/#any-input>
+    AnyPointer(Option<Pointer>),
+    /// <https://drafts.csswg.org/mediaqueries-5/#prefers-reduced-motion>
+    PrefersReducedMotion(Option<PrefersReducedMotion>),
+    /// <https://drafts.csswg.org/mediaqueries-5/#prefers-color-scheme>
+    PrefersColorScheme(Option<PrefersColorScheme>),
+    /// <https://w3c.github.io/manifest/#the-display-mode-media-feature>
+    DisplayMode(Option<DisplayMode>),
+}
+
+impl ExpressionKind {
+    /// Returns the name of the media feature, without a `min-` or `max-`
+    /// prefix.
+    fn name(&self) -> &'static str {
+        match *self {
+            ExpressionKind::Width(..) => "width",
+            ExpressionKind::Height(..) => "height",
+            ExpressionKind::AspectRatio(..) => "aspect-ratio",
+            ExpressionKind::Orientation(..) => "orientation",
+            ExpressionKind::Resolution(..) => "resolution",
+            ExpressionKind::DevicePixelRatio(..) => "-webkit-device-pixel-ratio",
+            ExpressionKind::Color(..) => "color",
+            ExpressionKind::ColorIndex(..) => "color-index",
+            ExpressionKind::Monochrome(..) => "monochrome",
+            ExpressionKind::Hover(..) => "hover",
+            ExpressionKind::AnyHover(..) => "any-hover",
+            ExpressionKind::Pointer(..) => "pointer",
+            ExpressionKind::AnyPointer(..) => "any-pointer",
+            ExpressionKind::PrefersReducedMotion(..) => "prefers-reduced-motion",
+            ExpressionKind::PrefersColorScheme(..) => "prefers-color-scheme",
+            ExpressionKind::DisplayMode(..) => "display-mode",
+        }
+    }
 }
 
 /// A single expression a per:
 ///
-/// <http://dev.w3.org/csswg/mediaqueries-3/#media1>
+/// <https://drafts.csswg.org/mediaqueries-4/#mq-features>
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "servo", derive(MallocSizeOf))]
 pub struct MediaFeatureExpression(pub ExpressionKind);
@@ -180,13 +385,14 @@ impl MediaFeatureExpression {
         &self.0
     }
 
-    /// Parse a media expression of the form:
+    /// Parse a media expression of one of the forms:
     ///
     /// ```
+    /// media-feature
     /// media-feature: media-value
+    /// media-feature operator media-value
+    /// media-value operator media-feature [operator media-value]
     /// ```
-    ///
-    /// Only supports width ranges for now.
     pub fn parse<'i, 't>(
         context: &ParserContext,
         input: &mut Parser<'i, 't>,
@@ -203,20 +409,74 @@ impl MediaFeatureExpression {
         context: &ParserContext,
         input: &mut Parser<'i, 't>,
     ) -> Result<Self, ParseError<'i>> {
-        let name = input.expect_ident_cloned()?;
-        input.expect_colon()?;
-        // TODO: Handle other media features
-        Ok(MediaFeatureExpression(match_ignore_ascii_case! { &name,
-            "min-width" => {
-                ExpressionKind::Width(Range::Min(specified::Length::parse_non_negative(context, input)?))
+        let location = input.current_source_location();
+        let (ident, syntax) = match input.try(feature_name_after_value) {
+            Ok(ident) => (ident, Syntax::ValueFirst),
+            Err(..) => (input.expect_ident_cloned()?, Syntax::NameFirst(None)),
+        };
+        let unknown_feature = || {
+            let name = ident.clone();
+            location.new_custom_error(StyleParseErrorKind::MediaQueryExpectedFeatureName(name))
+        };
+        // As in Gecko, `-webkit-device-pixel-ratio` is the only prefixed
+        // feature, and its `min-` and `max-` prefixes follow the vendor
+        // prefix, as in `-webkit-min-device-pixel-ratio`.
+        let (name, webkit_prefix) = match syntax {
+            Syntax::NameFirst(..) if starts_with_ignore_ascii_case(&ident, "-webkit-") => {
+                (&ident[8..], true)
+            },
+            _ => (&*ident, false),
+        };
+        let (name, syntax) = match syntax {
+            Syntax::NameFirst(..) if starts_with_ignore_ascii_case(name, "min-") => {
+                (&name[4..], Syntax::NameFirst(Some(Prefix::Min)))
+            },
+            Syntax::NameFirst(..) if starts_with_ignore_ascii_case(name, "max-") => {
+                (&name[4..], Syntax::NameFirst(Some(Prefix::Max)))
+            },
+            _ => (name, syntax),
+        };
+        if webkit_prefix && !name.eq_ignore_ascii_case("device-pixel-ratio") {
+            return Err(unknown_feature());
+        }
+
+        let length = specified::Length::parse_non_negative;
+        Ok(MediaFeatureExpression(match_ignore_ascii_case! { name,
+            "width" => ExpressionKind::Width(parse_range(context, input, syntax, length)?),
+            "height" => ExpressionKind::Height(parse_range(context, input, syntax, length)?),
+            "aspect-ratio" => {
+                ExpressionKind::AspectRatio(parse_range(context, input, syntax, AspectRatio::parse)?)
             },
-            "max-width" => {
-                ExpressionKind::Width(Range::Max(specified::Length::parse_non_negative(context, input)?))
+            "orientation" => ExpressionKind::Orientation(parse_discrete(context, input, syntax)?),
+            "resolution" => {
+                let resolution = specified::Resolution::parse;
+                ExpressionKind::Resolution(parse_range(context, input, syntax, resolution)?)
+            },
+            "device-pixel-ratio" => {
+                if !webkit_prefix {
+                    return Err(unknown_feature());
+                }
+                ExpressionKind::DevicePixelRatio(parse_range(context, input, syntax, parse_number)?)
             },
-            "width" => {
-                ExpressionKind::Width(Range::Eq(specified::Length::parse_non_negative(context, input)?))
+            "color" => ExpressionKind::Color(parse_range(context, input, syntax, parse_integer)?),
+            "color-index" => {
+                ExpressionKind::ColorIndex(parse_range(context, input, syntax, parse_integer)?)
             },
-            _ => return Err(input.new_custom_error(SelectorParseErrorKind::UnexpectedIdent(name.clone())))
+            "monochrome" => {
+                ExpressionKind::Monochrome(parse_range(context, input, syntax, parse_integer)?)
+            },
+            "hover" => ExpressionKind::Hover(parse_discrete(context, input, syntax)?),
+            "any-hover" => ExpressionKind::AnyHover(parse_discrete(context, input, syntax)?),
+            "pointer" => ExpressionKind::Pointer(parse_discrete(context, input, syntax)?),
+            "any-pointer" => ExpressionKind::AnyPointer(parse_discrete(context, input, syntax)?),
+            "prefers-reduced-motion" => {
+                ExpressionKind::PrefersReducedMotion(parse_discrete(context, input, syntax)?)
+            },
+            "prefers-color-scheme" => {
+                ExpressionKind::PrefersColorScheme(parse_discrete(context, input, syntax)?)
+            },
+            "display-mode" => ExpressionKind::DisplayMode(parse_discrete(context, input, syntax)?),
+            _ => return Err(unknown_feature()),
         }))
     }
 
@@ -224,14 +484,63 @@ impl MediaFeatureExpression {
     /// device.
     pub fn matches(&self, device: &Device, quirks_mode: QuirksMode) -> bool {
         let viewport_size = device.au_viewport_size();
-        let value = viewport_size.width;
+        let length = |length: &specified::Length| {
+            computed::Context::for_media_query_evaluation(device, quirks_mode, |context| {
+                Au::from(length.to_computed_value(&context))
+            })
+        };
+        let integer = |integer: &u32| *integer;
         match self.0 {
-            ExpressionKind::Width(ref range) => {
-                match range.to_computed_range(device, quirks_mode) {
-                    Range::Min(ref width) => value >= *width,
-                    Range::Max(ref width) => value <= *width,
-                    Range::Eq(ref width) => value == *width,
-                }
+            ExpressionKind::Width(ref range) => matches_range(range, viewport_size.width, &length),
+            ExpressionKind::Height(ref range) => matches_range(range, viewport_size.height, &length),
+            ExpressionKind::AspectRatio(ref range) => {
+                let ratio = viewport_size.width.0 as f64 / viewport_size.height.0 as f64;
+                matches_range(range, ratio, AspectRatio::to_f64)
+            },
+            ExpressionKind::Orientation(value) => {
+                // https://drafts.csswg.org/mediaqueries-4/#valdef-media-orientation-portrait
+                let orientation = if viewport_size.height >= viewport_size.width {
+                    Orientation::Portrait
+                } else {
+                    Orientation::Landscape
+                };
+                value.map_or(true, |value| value == orientation)
+            },
+            ExpressionKind::Resolution(ref range) => {
+                let dppx = device.device_pixel_ratio().get();
+                matches_range(range, dppx, specified::Resolution::to_dppx)
+            },
+            ExpressionKind::DevicePixelRatio(ref range) => {
+                matches_range(range, device.device_pixel_ratio().get(), |ratio: &CSSFloat| *ratio)
+            },
+            ExpressionKind::Color(ref range) => matches_range(range, device.color_bits(), &integer),
+            // Servo doesn't render to devices with a color lookup table or
+            // without color.
+            ExpressionKind::ColorIndex(ref range) |
+            ExpressionKind::Monochrome(ref range) => matches_range(range, 0, &integer),
+            // Servo only knows about the primary input device, so `any-hover`
+            // and `any-pointer` are approximated by `hover` and `pointer`.
+            ExpressionKind::Hover(value) |
+            ExpressionKind::AnyHover(value) => {
+                let hover = device.hover();
+                value.map_or(hover != Hover::None, |value| value == hover)
+            },
+            ExpressionKind::Pointer(value) |
+            ExpressionKind::AnyPointer(value) => {
+                let pointer = device.pointer();
+                value.map_or(pointer != Pointer::None, |value| value == pointer)
+            },
+            ExpressionKind::PrefersReducedMotion(value) => {
+                let prefers_reduced_motion = device.prefers_reduced_motion();
+                value.map_or(prefers_reduced_motion != PrefersReducedMotion::NoPreference, |value| {
+                    value == prefers_reduced_motion
+                })
+            },
+            ExpressionKind::PrefersColorScheme(value) => {
+                value.map_or(true, |value| value == device.prefers_color_scheme())
+            },
+            ExpressionKind::DisplayMode(value) => {
+                value.map_or(true, |value| value == device.display_mode())
             },
         }
     }
@@ -242,17 +551,96 @@ impl ToCss for MediaFeatureExpression {
     where
         W: Write,
     {
-        let (s, l) = match self.0 {
-            ExpressionKind::Width(Range::Min(ref l)) => ("(min-width: ", l),
-            ExpressionKind::Width(Range::Max(ref l)) => ("(max-width: ", l),
-            ExpressionKind::Width(Range::Eq(ref l)) => ("(width: ", l),
-        };
-        dest.write_str(s)?;
-        l.to_css(dest)?;
+        dest.write_char('(')?;
+        let name = self.0.name();
+        match self.0 {
+            ExpressionKind::Width(ref range) |
+            ExpressionKind::Height(ref range) => serialize_range(name, range, dest)?,
+            ExpressionKind::AspectRatio(ref range) => serialize_range(name, range, dest)?,
+            ExpressionKind::Orientation(ref value) => serialize_discrete(name, value, dest)?,
+            ExpressionKind::Resolution(ref range) => serialize_range(name, range, dest)?,
+            ExpressionKind::DevicePixelRatio(ref range) => serialize_range(name, range, dest)?,
+            ExpressionKind::Color(ref range) |
+            ExpressionKind::ColorIndex(ref range) |
+            ExpressionKind::Monochrome(ref range) => serialize_range(name, range, dest)?,
+            ExpressionKind::Hover(ref value) |
+            ExpressionKind::AnyHover(ref value) => serialize_discrete(name, value, dest)?,
+            ExpressionKind::Pointer(ref value) |
+            ExpressionKind::AnyPointer(ref value) => serialize_discrete(name, value, dest)?,
+            ExpressionKind::PrefersReducedMotion(ref value) => serialize_discrete(name, value, dest)?,
+            ExpressionKind::PrefersColorScheme(ref value) => serialize_discrete(name, value, dest)?,
+            ExpressionKind::DisplayMode(ref value) => serialize_discrete(name, value, dest)?,
+        }
         dest.write_char(')')
     }
 }
 
+/// How the value of a media feature is compared in the range syntax.
+///
+/// <https://drafts.csswg.org/mediaqueries-4/#mq-range-context>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "servo", derive(MallocSizeOf))]
+#[allow(missing_docs)]
+pub enum Operator {
+    Equal,
+    GreaterThan,
+    GreaterThanEqual,
+    LessThan,
+    LessThanEqual,
+}
+
+impl Operator {
+    fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, ParseError<'i>> {
+        let location = input.current_source_location();
+        let operator = match *input.next()? {
+            Token::Delim('=') => return Ok(Operator::Equal),
+            Token::Delim('>') => Operator::GreaterThan,
+            Token::Delim('<') => Operator::LessThan,
+            ref t => return Err(location.new_unexpected_token_error(t.clone())),
+        };
+        if input.try(|i| i.expect_delim('=')).is_err() {
+            return Ok(operator);
+        }
+        Ok(match operator {
+            Operator::GreaterThan => Operator::GreaterThanEqual,
+            _ => Operator::LessThanEqual,
+        })
+    }
+
+    fn is_less_than(self) -> bool {
+        self == Operator::LessThan || self == Operator::LessThanEqual
+    }
+
+    fn is_greater_than(self) -> bool {
+        self == Operator::GreaterThan || self == Operator::GreaterThanEqual
+    }
+
+    fn evaluate<T: PartialOrd>(self, one: T, other: T) -> bool {
+        match self {
+            Operator::Equal => one == other,
+            Operator::GreaterThan => one > other,
+            Operator::GreaterThanEqual => one >= other,
+            Operator::LessThan => one < other,
+            Operator::LessThanEqual => one <= other,
+        }
+    }
+}
+
+impl ToCss for Operator {
+    fn to_css<W>(&self, dest: &mut CssWriter<W>) -> fmt::Result
+    where
+        W: Write,
+    {
+        dest.write_str(match *self {
+            Operator::Equal => "=",
+            Operator::LessThan => "<",
+            Operator::LessThanEqual => "<=",
+            Operator::GreaterThan => ">",
+            Operator::GreaterThanEqual => ">=",
+        })
+    }
+}
+
 /// An enumeration that represents a ranged value.
 ///
 /// Only public for testing, implementation details of `MediaFeatureExpression`
@@ -266,14 +654,237 @@ pub enum Range<T> {
     Max(T),
     /// Exactly the inner value.
     Eq(T),
+    /// The value of the feature compared with the inner value, as in
+    /// `(width > 400px)`.
+    Compare(Operator, T),
+    /// The inner value compared with the value of the feature, as in
+    /// `(400px < width)`.
+    CompareReversed(T, Operator),
+    /// The value of the feature between two values, as in
+    /// `(400px < width <= 800px)`.
+    Between(T, Operator, Operator, T),
 }
 
-impl Range<specified::Length> {
-    fn to_computed_range(&self, device: &Device, quirks_mode: QuirksMode) -> Range<Au> {
-        computed::Context::for_media_query_evaluation(device, quirks_mode, |context| match *self {
-            Range::Min(ref width) => Range::Min(Au::from(width.to_computed_value(&context))),
-            Range::Max(ref width) => Range::Max(Au::from(width.to_computed_value(&context))),
-            Range::Eq(ref width) => Range::Eq(Au::from(width.to_computed_value(&context))),
-        })
+impl<T> Range<T> {
+    /// Returns whether `value` is in this range, where `compute` turns the
+    /// values of the range into values that can be compared with it.
+    fn matches<U, F>(&self, value: U, compute: F) -> bool
+    where
+        U: Copy + PartialOrd,
+        F: Fn(&T) -> U,
+    {
+        match *self {
+            Range::Min(ref min) => value >= compute(min),
+            Range::Max(ref max) => value <= compute(max),
+            Range::Eq(ref other) => value == compute(other),
+            Range::Compare(operator, ref other) => operator.evaluate(value, compute(other)),
+            Range::CompareReversed(ref other, operator) => operator.evaluate(compute(other), value),
+            Range::Between(ref start, start_operator, end_operator, ref end) => {
+                start_operator.evaluate(compute(start), value) &&
+                    end_operator.evaluate(value, compute(end))
+            },
+        }
+    }
+}
+
+/// Whether the feature of a range expression comes before its value, and
+/// which prefix it has if it does.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Syntax {
+    NameFirst(Option<Prefix>),
+    ValueFirst,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Prefix {
+    Min,
+    Max,
+}
+
+/// Returns the name of the feature of a range expression that starts with a
+/// value, as in `(400px < width)`, without consuming anything.
+fn feature_name_after_value<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CowRcStr<'i>, ()> {
+    let start = input.state();
+    match input.next() {
+        Ok(&Token::Ident(..)) | Err(..) => return Err(()),
+        Ok(..) => {},
+    }
+    let name = loop {
+        match input.next() {
+            Ok(&Token::Ident(ref name)) => break name.clone(),
+            Ok(..) => {},
+            Err(..) => return Err(()),
+        }
+    };
+    input.reset(&start);
+    Ok(name)
+}
+
+/// Parses what follows the name of a range feature, or the whole expression
+/// when it starts with a value.
+fn parse_range<'i, 't, T, F>(
+    context: &ParserContext,
+    input: &mut Parser<'i, 't>,
+    syntax: Syntax,
+    parse_value: F,
+) -> Result<Option<Range<T>>, ParseError<'i>>
+where
+    F: Fn(&ParserContext, &mut Parser<'i, 't>) -> Result<T, ParseError<'i>>,
+{
+    let prefix = match syntax {
+        Syntax::NameFirst(prefix) => prefix,
+        Syntax::ValueFirst => {
+            let start = parse_value(context, input)?;
+            let start_operator = Operator::parse(input)?;
+            // The name of the feature, which we already know.
+            input.expect_ident()?;
+            if input.is_exhausted() {
+                return Ok(Some(Range::CompareReversed(start, start_operator)));
+            }
+
+            let end_operator = Operator::parse(input)?;
+            let same_direction =
+                (start_operator.is_less_than() && end_operator.is_less_than()) ||
+                (start_operator.is_greater_than() && end_operator.is_greater_than());
+            if !same_direction {
+                return Err(input.new_custom_error(StyleParseErrorKind::MediaQueryUnexpectedOperator));
+            }
+            let end = parse_value(context, input)?;
+            return Ok(Some(Range::Between(start, start_operator, end_operator, end)));
+        },
+    };
+
+    if input.is_exhausted() {
+        if prefix.is_some() {
+            return Err(input.new_custom_error(StyleParseErrorKind::RangedExpressionWithNoValue));
+        }
+        return Ok(None);
+    }
+
+    if input.try(|i| i.expect_colon()).is_ok() {
+        let value = parse_value(context, input)?;
+        return Ok(Some(match prefix {
+            Some(Prefix::Min) => Range::Min(value),
+            Some(Prefix::Max) => Range::Max(value),
+            None => Range::Eq(value),
+        }));
+    }
+
+    if prefix.is_some() {
+        return Err(input.new_custom_error(StyleParseErrorKind::MediaQueryUnexpectedOperator));
+    }
+    let operator = Operator::parse(input)?;
+    let value = parse_value(context, input)?;
+    Ok(Some(Range::Compare(operator, value)))
+}
+
+/// Parses what follows the name of a discrete feature.
+fn parse_discrete<'i, 't, T: Parse>(
+    context: &ParserContext,
+    input: &mut Parser<'i, 't>,
+    syntax: Syntax,
+) -> Result<Option<T>, ParseError<'i>> {
+    if syntax != Syntax::NameFirst(None) {
+        return Err(input.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+    }
+    if input.is_exhausted() {
+        return Ok(None);
+    }
+    input.expect_colon()?;
+    T::parse(context, input).map(Some)
+}
+
+fn parse_number<'i, 't>(
+    context: &ParserContext,
+    input: &mut Parser<'i, 't>,
+) -> Result<CSSFloat, ParseError<'i>> {
+    Ok(specified::Number::parse_non_negative(context, input)?.get())
+}
+
+fn parse_integer<'i, 't>(
+    context: &ParserContext,
+    input: &mut Parser<'i, 't>,
+) -> Result<u32, ParseError<'i>> {
+    Ok(specified::Integer::parse_non_negative(context, input)?.value() as u32)
+}
+
+/// Returns whether a range feature with the given value matches. In a boolean
+/// context, that is, without a range, it matches unless the value is zero.
+fn matches_range<T, U, F>(range: &Option<Range<T>>, value: U, compute: F) -> bool
+where
+    U: Copy + Default + PartialOrd,
+    F: Fn(&T) -> U,
+{
+    match *range {
+        Some(ref range) => range.matches(value, compute),
+        None => value != U::default(),
+    }
+}
+
+fn serialize_range<T, W>(name: &str, range: &Option<Range<T>>, dest: &mut CssWriter<W>) -> fmt::Result
+where
+    T: ToCss,
+    W: Write,
+{
+    let range = match *range {
+        Some(ref range) => range,
+        None => return dest.write_str(name),
+    };
+    match *range {
+        Range::Min(ref value) | Range::Max(ref value) | Range::Eq(ref value) => {
+            // The `min-` and `max-` prefixes follow any vendor prefix.
+            let (vendor_prefix, name) = if name.starts_with("-webkit-") {
+                name.split_at(8)
+            } else {
+                ("", name)
+            };
+            dest.write_str(vendor_prefix)?;
+            match *range {
+                Range::Min(..) => dest.write_str("min-")?,
+                Range::Max(..) => dest.write_str("max-")?,
+                _ => {},
+            }
+            dest.write_str(name)?;
+            dest.write_str(": ")?;
+            value.to_css(dest)
+        },
+        Range::Compare(operator, ref value) => {
+            dest.write_str(name)?;
+            dest.write_char(' ')?;
+            operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            value.to_css(dest)
+        },
+        Range::CompareReversed(ref value, operator) => {
+            value.to_css(dest)?;
+            dest.write_char(' ')?;
+            operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            dest.write_str(name)
+        },
+        Range::Between(ref start, start_operator, end_operator, ref end) => {
+            start.to_css(dest)?;
+            dest.write_char(' ')?;
+            start_operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            dest.write_str(name)?;
+            dest.write_char(' ')?;
+            end_operator.to_css(dest)?;
+            dest.write_char(' ')?;
+            end.to_css(dest)
+        },
+    }
+}
+
+fn serialize_discrete<T, W>(name: &str, value: &Option<T>, dest: &mut CssWriter<W>) -> fmt::Result
+where
+    T: ToCss,
+    W: Write,
+{
+    dest.write_str(name)?;
+    if let Some(ref value) = *value {
+        dest.write_str(": ")?;
+        value.to_css(dest)?;
     }
+    Ok(())
 }
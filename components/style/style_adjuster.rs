@@ -31,13 +31,12 @@ pub struct StyleAdjuster<'a, 'b: 'a> {
     style: &'a mut StyleBuilder<'b>,
 }
 
-#[cfg(feature = "gecko")]
 fn is_topmost_svg_svg_element<E>(e: E) -> bool
 where
     E: TElement,
 {
     debug_assert!(e.is_svg_element());
-    if e.local_name() != &*atom!("svg") {
+    if e.local_name() != &*local_name!("svg") {
         return false;
     }
 
@@ -50,25 +49,24 @@ where
         return true;
     }
 
-    parent.local_name() == &*atom!("foreignObject")
+    parent.local_name() == &*local_name!("foreignObject")
 }
 
 // https://drafts.csswg.org/css-display/#unbox
-#[cfg(feature = "gecko")]
 fn is_effective_display_none_for_display_contents<E>(element: E) -> bool
 where
     E: TElement,
 {
-    use Atom;
+    use LocalName;
 
     // FIXME(emilio): This should be an actual static.
     lazy_static! {
-        static ref SPECIAL_HTML_ELEMENTS: [Atom; 16] = [
-            atom!("br"), atom!("wbr"), atom!("meter"), atom!("progress"),
-            atom!("canvas"), atom!("embed"), atom!("object"), atom!("audio"),
-            atom!("iframe"), atom!("img"), atom!("video"), atom!("frame"),
-            atom!("frameset"), atom!("input"), atom!("textarea"),
-            atom!("select"),
+        static ref SPECIAL_HTML_ELEMENTS: [LocalName; 16] = [
+            local_name!("br"), local_name!("wbr"), local_name!("meter"), local_name!("progress"),
+            local_name!("canvas"), local_name!("embed"), local_name!("object"), local_name!("audio"),
+            local_name!("iframe"), local_name!("img"), local_name!("video"), local_name!("frame"),
+            local_name!("frameset"), local_name!("input"), local_name!("textarea"),
+            local_name!("select"),
         ];
     }
 
@@ -78,16 +76,16 @@ where
     // know what that means, or to get that information from here, and no other
     // UA implements this either.
     lazy_static! {
-        static ref SPECIAL_SVG_ELEMENTS: [Atom; 6] = [
-            atom!("svg"), atom!("a"), atom!("g"), atom!("use"),
-            atom!("tspan"), atom!("textPath"),
+        static ref SPECIAL_SVG_ELEMENTS: [LocalName; 6] = [
+            local_name!("svg"), local_name!("a"), local_name!("g"), local_name!("use"),
+            local_name!("tspan"), local_name!("textPath"),
         ];
     }
 
     // https://drafts.csswg.org/css-display/#unbox-html
     if element.is_html_element() {
         let local_name = element.local_name();
-        return SPECIAL_HTML_ELEMENTS.iter().any(|name| &**name == local_name);
+        return SPECIAL_HTML_ELEMENTS.iter().any(|name| local_name == &**name);
     }
 
     // https://drafts.csswg.org/css-display/#unbox-svg
@@ -96,7 +94,7 @@ where
             return true;
         }
         let local_name = element.local_name();
-        return !SPECIAL_SVG_ELEMENTS.iter().any(|name| &**name == local_name);
+        return !SPECIAL_SVG_ELEMENTS.iter().any(|name| local_name == &**name);
     }
 
     // https://drafts.csswg.org/css-display/#unbox-mathml
@@ -220,7 +218,7 @@ impl<'a, 'b: 'a> StyleAdjuster<'a, 'b> {
 
         #[cfg(feature = "servo")]
         {
-            if self.style.get_parent_column().is_multicol() {
+            if self.style.get_parent_column().is_multicol() || self.style.device.is_paged() {
                 self.style
                     .flags
                     .insert(ComputedValueFlags::CAN_BE_FRAGMENTED);
@@ -345,19 +343,35 @@ impl<'a, 'b: 'a> StyleAdjuster<'a, 'b> {
     fn adjust_for_alignment(&mut self, layout_parent_style: &ComputedValues) {
         use computed_values::align_items::T as AlignItems;
         use computed_values::align_self::T as AlignSelf;
+        use computed_values::justify_items::T as JustifyItems;
+        use computed_values::justify_self::T as JustifySelf;
 
-        if self.style.get_position().clone_align_self() == AlignSelf::Auto &&
-            !self.style.out_of_flow_positioned()
-        {
+        if self.style.out_of_flow_positioned() {
+            return;
+        }
+
+        if self.style.get_position().clone_align_self() == AlignSelf::Auto {
             let self_align = match layout_parent_style.get_position().clone_align_items() {
                 AlignItems::Stretch => AlignSelf::Stretch,
                 AlignItems::Baseline => AlignSelf::Baseline,
                 AlignItems::FlexStart => AlignSelf::FlexStart,
                 AlignItems::FlexEnd => AlignSelf::FlexEnd,
                 AlignItems::Center => AlignSelf::Center,
+                AlignItems::Start => AlignSelf::Start,
+                AlignItems::End => AlignSelf::End,
             };
             self.style.mutate_position().set_align_self(self_align);
         }
+
+        if self.style.get_position().clone_justify_self() == JustifySelf::Auto {
+            let self_justify = match layout_parent_style.get_position().clone_justify_items() {
+                JustifyItems::Stretch => JustifySelf::Stretch,
+                JustifyItems::Start => JustifySelf::Start,
+                JustifyItems::End => JustifySelf::End,
+                JustifyItems::Center => JustifySelf::Center,
+            };
+            self.style.mutate_position().set_justify_self(self_justify);
+        }
     }
 
     /// The initial value of border-*-width may be changed at computed value
@@ -429,7 +443,6 @@ impl<'a, 'b: 'a> StyleAdjuster<'a, 'b> {
     /// https://drafts.csswg.org/css-display/#unbox-html
     ///
     /// And forbidding display: contents in pseudo-elements, at least for now.
-    #[cfg(feature = "gecko")]
     fn adjust_for_prohibited_display_contents<E>(&mut self, element: Option<E>)
     where
         E: TElement,
@@ -705,9 +718,9 @@ impl<'a, 'b: 'a> StyleAdjuster<'a, 'b> {
         // );
 
         self.adjust_for_visited(element);
+        self.adjust_for_prohibited_display_contents(element);
         #[cfg(feature = "gecko")]
         {
-            self.adjust_for_prohibited_display_contents(element);
             self.adjust_for_fieldset_content(layout_parent_style);
         }
         self.adjust_for_top_layer();
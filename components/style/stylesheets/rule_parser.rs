@@ -431,13 +431,7 @@ impl<'a, 'b, 'i> AtRuleParser<'i> for NestedRuleParser<'a, 'b> {
 
                 Ok(AtRuleType::WithBlock(AtRuleBlockPrelude::Keyframes(name, prefix)))
             },
-            "page" => {
-                if cfg!(feature = "gecko") {
-                    Ok(AtRuleType::WithBlock(AtRuleBlockPrelude::Page))
-                } else {
-                    Err(input.new_custom_error(StyleParseErrorKind::UnsupportedAtRule(name.clone())))
-                }
-            },
+            "page" => Ok(AtRuleType::WithBlock(AtRuleBlockPrelude::Page)),
             "-moz-document" => {
                 if !cfg!(feature = "gecko") {
                     return Err(input.new_custom_error(
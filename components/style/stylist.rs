@@ -21,7 +21,7 @@ use malloc_size_of::{MallocShallowSizeOf, MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of::MallocUnconditionalShallowSizeOf;
 use media_queries::Device;
 use properties::{self, CascadeMode, ComputedValues};
-use properties::{AnimationRules, PropertyDeclarationBlock};
+use properties::{AnimationRules, Importance, PropertyDeclarationBlock};
 use rule_cache::{RuleCache, RuleCacheConditions};
 use rule_tree::{CascadeLevel, RuleTree, ShadowCascadeOrder, StrongRuleNode, StyleSource};
 use selector_map::{PrecomputedHashMap, SelectorMap, SelectorMapEntry};
@@ -35,7 +35,7 @@ use selectors::parser::{AncestorHashes, Combinator, Component, Selector};
 use selectors::parser::{SelectorIter, Visit};
 use selectors::visitor::SelectorVisitor;
 use servo_arc::{Arc, ArcBorrow};
-use shared_lock::{Locked, SharedRwLockReadGuard, StylesheetGuards};
+use shared_lock::{Locked, SharedRwLock, SharedRwLockReadGuard, StylesheetGuards};
 use smallbitvec::SmallBitVec;
 use smallvec::SmallVec;
 use std::ops;
@@ -44,8 +44,8 @@ use style_traits::viewport::ViewportConstraints;
 use stylesheet_set::{DataValidity, DocumentStylesheetSet, SheetRebuildKind};
 use stylesheet_set::{DocumentStylesheetFlusher, SheetCollectionFlusher};
 #[cfg(feature = "gecko")]
-use stylesheets::{CounterStyleRule, FontFaceRule, FontFeatureValuesRule, PageRule};
-use stylesheets::{CssRule, Origin, OriginSet, PerOrigin, PerOriginIter};
+use stylesheets::{CounterStyleRule, FontFaceRule, FontFeatureValuesRule};
+use stylesheets::{CssRule, Origin, OriginSet, PageRule, PerOrigin, PerOriginIter};
 use stylesheets::StyleRule;
 use stylesheets::StylesheetInDocument;
 use stylesheets::keyframes_rule::KeyframesAnimation;
@@ -1572,6 +1572,35 @@ impl Stylist {
         )
     }
 
+    /// Computes the style of the page box from the `@page` rules of every
+    /// origin, inheriting from `parent_style` (the root element's style).
+    ///
+    /// Page selectors are not supported yet, so every rule applies to every
+    /// page, and `!important` is ignored.
+    ///
+    /// https://drafts.csswg.org/css-page-3/#page-model
+    pub fn compute_page_style<E>(
+        &self,
+        guards: &StylesheetGuards,
+        lock: &SharedRwLock,
+        parent_style: &ComputedValues,
+    ) -> Arc<ComputedValues>
+    where
+        E: TElement,
+    {
+        let mut block = PropertyDeclarationBlock::new();
+        for (data, origin) in self.iter_extra_data_origins_rev() {
+            let guard = guards.for_origin(origin);
+            for rule in &data.pages {
+                let rule = rule.read_with(guard);
+                for declaration in rule.block.read_with(guard).declarations() {
+                    block.push(declaration.clone(), Importance::Normal);
+                }
+            }
+        }
+        self.compute_for_declarations::<E>(guards, parent_style, Arc::new(lock.wrap(block)))
+    }
+
     /// Accessor for a shared reference to the device.
     #[inline]
     pub fn device(&self) -> &Device {
@@ -1623,7 +1652,7 @@ pub struct ExtraStyleData {
     pub counter_styles: PrecomputedHashMap<Atom, Arc<Locked<CounterStyleRule>>>,
 
     /// A map of effective page rules.
-    #[cfg(feature = "gecko")]
+    #[cfg_attr(feature = "servo", ignore_malloc_size_of = "Arc")]
     pub pages: Vec<Arc<Locked<PageRule>>>,
 }
 
@@ -1653,22 +1682,22 @@ impl ExtraStyleData {
         let name = rule.read_with(guard).name().0.clone();
         self.counter_styles.insert(name, rule.clone());
     }
+}
 
+impl ExtraStyleData {
     /// Add the given @page rule.
     fn add_page(&mut self, rule: &Arc<Locked<PageRule>>) {
         self.pages.push(rule.clone());
     }
-}
 
-impl ExtraStyleData {
     fn clear(&mut self) {
         #[cfg(feature = "gecko")]
         {
             self.font_faces.clear();
             self.font_feature_values.clear();
             self.counter_styles.clear();
-            self.pages.clear();
         }
+        self.pages.clear();
     }
 }
 
@@ -2301,7 +2330,6 @@ impl CascadeData {
                 CssRule::CounterStyle(ref rule) => {
                     self.extra_data.add_counter_style(guard, rule);
                 },
-                #[cfg(feature = "gecko")]
                 CssRule::Page(ref rule) => {
                     self.extra_data.add_page(rule);
                 },
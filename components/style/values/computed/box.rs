@@ -7,7 +7,9 @@
 use values::computed::{Context, Number, ToComputedValue};
 use values::computed::length::{LengthOrPercentage, NonNegativeLength};
 use values::generics::box_::AnimationIterationCount as GenericAnimationIterationCount;
+use values::generics::box_::PageSize as GenericPageSize;
 use values::generics::box_::Perspective as GenericPerspective;
+use values::generics::box_::ScrollSnapPoint as GenericScrollSnapPoint;
 use values::generics::box_::VerticalAlign as GenericVerticalAlign;
 
 pub use values::specified::box_::{AnimationName, Contain, Display, OverflowClipBox};
@@ -17,6 +19,9 @@ pub use values::specified::box_::{OverscrollBehavior, ScrollSnapType, TouchActio
 /// A computed value for the `vertical-align` property.
 pub type VerticalAlign = GenericVerticalAlign<LengthOrPercentage>;
 
+/// A computed value for the `scroll-snap-points-x` and `scroll-snap-points-y` properties.
+pub type ScrollSnapPoint = GenericScrollSnapPoint<LengthOrPercentage>;
+
 /// A computed value for the `animation-iteration-count` property.
 pub type AnimationIterationCount = GenericAnimationIterationCount<Number>;
 
@@ -31,6 +36,9 @@ impl AnimationIterationCount {
 /// A computed value for the `perspective` property.
 pub type Perspective = GenericPerspective<NonNegativeLength>;
 
+/// A computed value for the `size` property of `@page` rules.
+pub type PageSize = GenericPageSize<NonNegativeLength>;
+
 #[allow(missing_docs)]
 #[cfg_attr(feature = "servo", derive(Deserialize, Serialize))]
 #[derive(Clone, Copy, Debug, Eq, Hash, MallocSizeOf, Parse, PartialEq,
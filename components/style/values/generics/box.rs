@@ -75,3 +75,55 @@ impl<L> Perspective<L> {
         Perspective::None
     }
 }
+
+/// A generic value for the `size` property of `@page` rules.
+///
+/// https://drafts.csswg.org/css-page-3/#page-size-prop
+#[derive(Clone, Debug, MallocSizeOf, PartialEq, SpecifiedValueInfo,
+         ToComputedValue, ToCss)]
+pub enum PageSize<NonNegativeLength> {
+    /// The keyword `auto`.
+    Auto,
+    /// The keyword `portrait`, with the page size left to the user agent.
+    Portrait,
+    /// The keyword `landscape`, with the page size left to the user agent.
+    Landscape,
+    /// An explicit width and height, from lengths or a paper size keyword.
+    Size(NonNegativeLength, NonNegativeLength),
+}
+
+impl<L> PageSize<L> {
+    /// Returns `auto`.
+    #[inline]
+    pub fn auto() -> Self {
+        PageSize::Auto
+    }
+}
+
+/// A generic value for scroll snap points.
+#[derive(Clone, Copy, Debug, MallocSizeOf, PartialEq, SpecifiedValueInfo,
+         ToComputedValue, ToCss)]
+pub enum ScrollSnapPoint<LengthOrPercentage> {
+    /// `none`
+    None,
+    /// `repeat(<length-or-percentage>)`
+    #[css(function)]
+    Repeat(LengthOrPercentage),
+}
+
+impl<L> ScrollSnapPoint<L> {
+    /// Returns `none`.
+    #[inline]
+    pub fn none() -> Self {
+        ScrollSnapPoint::None
+    }
+
+    /// Returns the repeat argument, if any.
+    #[inline]
+    pub fn repeated(&self) -> Option<&L> {
+        match *self {
+            ScrollSnapPoint::None => None,
+            ScrollSnapPoint::Repeat(ref length) => Some(length),
+        }
+    }
+}
@@ -13,11 +13,14 @@ use selectors::parser::SelectorParseErrorKind;
 use std::fmt::{self, Write};
 use style_traits::{CssWriter, KeywordsCollectFn, ParseError, StyleParseErrorKind, SpecifiedValueInfo, ToCss};
 use values::{CustomIdent, KeyframesName};
+use values::generics::NonNegative;
 use values::generics::box_::AnimationIterationCount as GenericAnimationIterationCount;
+use values::generics::box_::PageSize as GenericPageSize;
 use values::generics::box_::Perspective as GenericPerspective;
+use values::generics::box_::ScrollSnapPoint as GenericScrollSnapPoint;
 use values::generics::box_::VerticalAlign as GenericVerticalAlign;
 use values::specified::{AllowQuirks, Number};
-use values::specified::length::{LengthOrPercentage, NonNegativeLength};
+use values::specified::length::{AbsoluteLength, Length, LengthOrPercentage, NoCalcLength, NonNegativeLength};
 
 #[cfg(feature = "gecko")]
 fn moz_display_values_enabled(context: &ParserContext) -> bool {
@@ -68,9 +71,7 @@ pub enum Display {
     Flex,
     #[parse(aliases = "-webkit-inline-flex")]
     InlineFlex,
-    #[cfg(feature = "gecko")]
     Grid,
-    #[cfg(feature = "gecko")]
     InlineGrid,
     #[cfg(feature = "gecko")]
     Ruby,
@@ -82,7 +83,6 @@ pub enum Display {
     RubyText,
     #[cfg(feature = "gecko")]
     RubyTextContainer,
-    #[cfg(feature = "gecko")]
     Contents,
     #[cfg(feature = "gecko")]
     FlowRoot,
@@ -139,7 +139,6 @@ impl Display {
     pub fn is_item_container(&self) -> bool {
         match *self {
             Display::Flex | Display::InlineFlex => true,
-            #[cfg(feature = "gecko")]
             Display::Grid | Display::InlineGrid => true,
             _ => false,
         }
@@ -150,9 +149,9 @@ impl Display {
     /// line as itself.
     pub fn is_line_participant(&self) -> bool {
         match *self {
-            Display::Inline => true,
+            Display::Inline | Display::Contents => true,
             #[cfg(feature = "gecko")]
-            Display::Contents | Display::Ruby | Display::RubyBaseContainer => true,
+            Display::Ruby | Display::RubyBaseContainer => true,
             _ => false,
         }
     }
@@ -209,27 +208,23 @@ impl Display {
             // Values that have a corresponding block-outside version.
             Display::InlineTable => Display::Table,
             Display::InlineFlex => Display::Flex,
-
-            #[cfg(feature = "gecko")]
             Display::InlineGrid => Display::Grid,
+
             #[cfg(feature = "gecko")]
             Display::WebkitInlineBox => Display::WebkitBox,
 
-            // Special handling for contents and list-item on the root
-            // element for Gecko.
+            // Special handling for contents on the root element, and for
+            // list-item on the root element for Gecko.
+            Display::Contents if _is_root_element => Display::Block,
             #[cfg(feature = "gecko")]
-            Display::Contents | Display::ListItem if _is_root_element =>
-            {
-                Display::Block
-            },
+            Display::ListItem if _is_root_element => Display::Block,
 
             // These are not changed by blockification.
-            Display::None | Display::Block | Display::Flex | Display::ListItem | Display::Table => {
-                *self
-            },
+            Display::None | Display::Block | Display::Flex | Display::Grid | Display::ListItem |
+            Display::Table | Display::Contents => *self,
 
             #[cfg(feature = "gecko")]
-            Display::Contents | Display::FlowRoot | Display::Grid | Display::WebkitBox => *self,
+            Display::FlowRoot | Display::WebkitBox => *self,
 
             // Everything else becomes block.
             _ => Display::Block,
@@ -259,11 +254,7 @@ impl Display {
     /// Returns true if the value is `Contents`
     #[inline]
     pub fn is_contents(&self) -> bool {
-        match *self {
-            #[cfg(feature = "gecko")]
-            Display::Contents => true,
-            _ => false,
-        }
+        *self == Display::Contents
     }
 
     /// Returns true if the value is `None`
@@ -376,6 +367,24 @@ impl Parse for AnimationName {
     }
 }
 
+/// A specified value for the `scroll-snap-points-x` and `scroll-snap-points-y` properties.
+pub type ScrollSnapPoint = GenericScrollSnapPoint<LengthOrPercentage>;
+
+impl Parse for ScrollSnapPoint {
+    fn parse<'i, 't>(
+        context: &ParserContext,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i>> {
+        if input.try(|i| i.expect_ident_matching("none")).is_ok() {
+            return Ok(GenericScrollSnapPoint::None);
+        }
+        input.expect_function_matching("repeat")?;
+        let length =
+            input.parse_nested_block(|i| LengthOrPercentage::parse_non_negative(context, i))?;
+        Ok(GenericScrollSnapPoint::Repeat(length))
+    }
+}
+
 #[allow(missing_docs)]
 #[cfg_attr(feature = "servo", derive(Deserialize, Serialize))]
 #[derive(Clone, Copy, Debug, Eq, MallocSizeOf, Parse, PartialEq,
@@ -749,6 +758,81 @@ impl Parse for Perspective {
     }
 }
 
+/// A specified value for the `size` property of `@page` rules.
+pub type PageSize = GenericPageSize<NonNegativeLength>;
+
+impl PageSize {
+    /// Returns the size of a `<page-size>` keyword, in portrait orientation.
+    ///
+    /// https://drafts.csswg.org/css-page-3/#typedef-page-size-page-size
+    fn paper<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(AbsoluteLength, AbsoluteLength), ParseError<'i>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "a5" => Ok((AbsoluteLength::Mm(148.), AbsoluteLength::Mm(210.))),
+            "a4" => Ok((AbsoluteLength::Mm(210.), AbsoluteLength::Mm(297.))),
+            "a3" => Ok((AbsoluteLength::Mm(297.), AbsoluteLength::Mm(420.))),
+            "b5" => Ok((AbsoluteLength::Mm(176.), AbsoluteLength::Mm(250.))),
+            "b4" => Ok((AbsoluteLength::Mm(250.), AbsoluteLength::Mm(353.))),
+            "letter" => Ok((AbsoluteLength::In(8.5), AbsoluteLength::In(11.))),
+            "legal" => Ok((AbsoluteLength::In(8.5), AbsoluteLength::In(14.))),
+            "ledger" => Ok((AbsoluteLength::In(11.), AbsoluteLength::In(17.))),
+        }
+    }
+}
+
+impl Parse for PageSize {
+    fn parse<'i, 't>(
+        context: &ParserContext,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i>> {
+        if input.try(|i| i.expect_ident_matching("auto")).is_ok() {
+            return Ok(GenericPageSize::Auto);
+        }
+
+        if let Ok(width) = input.try(|i| NonNegativeLength::parse(context, i)) {
+            let height = input
+                .try(|i| NonNegativeLength::parse(context, i))
+                .unwrap_or_else(|_| width.clone());
+            return Ok(GenericPageSize::Size(width, height));
+        }
+
+        // <page-size> || [ portrait | landscape ]
+        let mut paper = None;
+        let mut landscape = None;
+        loop {
+            if paper.is_none() {
+                if let Ok(size) = input.try(PageSize::paper) {
+                    paper = Some(size);
+                    continue;
+                }
+            }
+            if landscape.is_none() {
+                let orientation = input.try(|i| {
+                    try_match_ident_ignore_ascii_case! { i,
+                        "portrait" => Ok(false),
+                        "landscape" => Ok(true),
+                    }
+                });
+                if let Ok(orientation) = orientation {
+                    landscape = Some(orientation);
+                    continue;
+                }
+            }
+            break;
+        }
+
+        let to_length = |length| NonNegative(Length::NoCalc(NoCalcLength::Absolute(length)));
+        match (paper, landscape) {
+            (Some((width, height)), Some(true)) => {
+                Ok(GenericPageSize::Size(to_length(height), to_length(width)))
+            },
+            (Some((width, height)), _) => Ok(GenericPageSize::Size(to_length(width), to_length(height))),
+            (None, Some(true)) => Ok(GenericPageSize::Landscape),
+            (None, Some(false)) => Ok(GenericPageSize::Portrait),
+            (None, None) => Err(input.new_custom_error(StyleParseErrorKind::UnspecifiedError)),
+        }
+    }
+}
+
 /// A given transition property, that is either `All`, a longhand or shorthand
 /// property, or an unsupported or custom property.
 #[derive(Clone, Debug, Eq, Hash, MallocSizeOf, PartialEq, ToComputedValue)]
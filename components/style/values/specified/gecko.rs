@@ -13,27 +13,7 @@ use style_traits::{CssWriter, ParseError, StyleParseErrorKind, ToCss};
 use style_traits::values::SequenceWriter;
 use values::computed;
 use values::computed::length::CSSPixelLength;
-use values::generics::gecko::ScrollSnapPoint as GenericScrollSnapPoint;
 use values::generics::rect::Rect;
-use values::specified::length::LengthOrPercentage;
-
-/// A specified type for scroll snap points.
-pub type ScrollSnapPoint = GenericScrollSnapPoint<LengthOrPercentage>;
-
-impl Parse for ScrollSnapPoint {
-    fn parse<'i, 't>(
-        context: &ParserContext,
-        input: &mut Parser<'i, 't>,
-    ) -> Result<Self, ParseError<'i>> {
-        if input.try(|i| i.expect_ident_matching("none")).is_ok() {
-            return Ok(GenericScrollSnapPoint::None);
-        }
-        input.expect_function_matching("repeat")?;
-        let length =
-            input.parse_nested_block(|i| LengthOrPercentage::parse_non_negative(context, i))?;
-        Ok(GenericScrollSnapPoint::Repeat(length))
-    }
-}
 
 /// A component of an IntersectionObserverRootMargin.
 #[derive(Clone, Copy, Debug, PartialEq, ToCss)]
@@ -546,6 +546,29 @@ impl ToComputedValue for TextAlign {
     }
 }
 
+/// A specified value for the `hyphenate-character` property.
+#[derive(Clone, Debug, Eq, MallocSizeOf, PartialEq, SpecifiedValueInfo, ToComputedValue, ToCss)]
+pub enum HyphenateCharacter {
+    /// Use the hyphen appropriate for the content language.
+    Auto,
+    /// Use the given string.
+    String(Box<str>),
+}
+
+impl Parse for HyphenateCharacter {
+    fn parse<'i, 't>(
+        _context: &ParserContext,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<HyphenateCharacter, ParseError<'i>> {
+        if input.try(|input| input.expect_ident_matching("auto")).is_ok() {
+            return Ok(HyphenateCharacter::Auto);
+        }
+        Ok(HyphenateCharacter::String(
+            input.expect_string()?.as_ref().to_owned().into_boxed_str(),
+        ))
+    }
+}
+
 /// Specified value of text-emphasis-style property.
 #[derive(Clone, Debug, MallocSizeOf, PartialEq, SpecifiedValueInfo, ToCss)]
 pub enum TextEmphasisStyle {
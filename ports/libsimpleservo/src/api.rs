@@ -194,6 +194,15 @@ impl ServoGlue {
         self.process_event(event)
     }
 
+    /// Lay the page out for print media, and return it as a PDF document.
+    pub fn print_to_pdf(&mut self) -> Result<Vec<u8>, &'static str> {
+        debug!("print_to_pdf");
+        let (sender, receiver) = ipc::channel().map_err(|_| "Can't create ipc::channel")?;
+        let event = WindowEvent::PrintToPdf(self.browser_id, sender);
+        self.process_event(event)?;
+        receiver.recv().map_err(|_| "Can't receive PDF")?.ok_or("Can't print the page")
+    }
+
     /// Go back in history.
     pub fn go_back(&mut self) -> Result<(), &'static str> {
         debug!("go_back");
@@ -417,6 +426,9 @@ impl resources::ResourceReaderMethods for ResourceReader {
             Resource::PresentationalHintsCSS => "presentational-hints.css",
             Resource::QuirksModeCSS => "quirks-mode.css",
             Resource::RippyPNG => "rippy.png",
+            Resource::HyphenationPatternsEnUs => "hyphenation/hyph-en-us.pat.txt",
+            Resource::HyphenationPatternsEs => "hyphenation/hyph-es.pat.txt",
+            Resource::HyphenationPatternsFr => "hyphenation/hyph-fr.pat.txt",
         };
         debug!("ResourceReader::read({})", file);
         self.0.readfile(file)
@@ -29,6 +29,9 @@ fn filename(file: Resource) -> &'static str {
         Resource::PresentationalHintsCSS => "presentational-hints.css",
         Resource::QuirksModeCSS => "quirks-mode.css",
         Resource::RippyPNG => "rippy.png",
+        Resource::HyphenationPatternsEnUs => "hyphenation/hyph-en-us.pat.txt",
+        Resource::HyphenationPatternsEs => "hyphenation/hyph-es.pat.txt",
+        Resource::HyphenationPatternsFr => "hyphenation/hyph-fr.pat.txt",
     }
 }
 
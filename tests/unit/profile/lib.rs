@@ -10,4 +10,5 @@ extern crate profile_traits;
 extern crate servo_allocator;
 extern crate servo_config;
 
+mod mem;
 mod time;
@@ -0,0 +1,33 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use profile::mem;
+use profile_traits::mem::{ProfilerMsg, Report, ReportKind, Reporter, ReporterRequest};
+
+#[test]
+fn mem_profiler_report_test() {
+    let chan = mem::Profiler::create(None);
+
+    let (reporter_sender, reporter_receiver) = ipc::channel().unwrap();
+    ROUTER.add_route(reporter_receiver.to_opaque(), Box::new(|message| {
+        let request: ReporterRequest = message.to().unwrap();
+        request.reports_channel.send(vec![Report {
+            path: vec!["url(about:blank)".to_owned(), "test".to_owned()],
+            kind: ReportKind::ExplicitJemallocHeapSize,
+            size: 42,
+        }]);
+    }));
+    chan.send(ProfilerMsg::RegisterReporter("test".to_owned(), Reporter(reporter_sender)));
+
+    let (sender, receiver) = ipc::channel().unwrap();
+    chan.send(ProfilerMsg::Report(sender));
+    let reports = receiver.recv().unwrap();
+    let report = reports.iter().find(|report| report.path[0] == "url(about:blank)").unwrap();
+    assert_eq!(report.path[1], "test");
+    assert_eq!(report.size, 42);
+
+    chan.send(ProfilerMsg::Exit);
+}
@@ -8,6 +8,8 @@ importScripts("interfaces.js");
 // IMPORTANT: Do not change the list below without review from a DOM peer!
 test_interfaces([
   "Blob",
+  "CanvasGradient",
+  "CanvasPattern",
   "CloseEvent",
   "DOMMatrix",
   "DOMMatrixReadOnly",
@@ -30,8 +32,12 @@ test_interfaces([
   "FormData",
   "Headers",
   "History",
+  "ImageBitmap",
   "ImageData",
   "MessageEvent",
+  "OffscreenCanvas",
+  "OffscreenCanvasRenderingContext2D",
+  "Path2D",
   "Performance",
   "PerformanceEntry",
   "PerformanceMark",
@@ -46,6 +52,18 @@ test_interfaces([
   "TextEncoder",
   "URL",
   "URLSearchParams",
+  "WebGLActiveInfo",
+  "WebGLBuffer",
+  "WebGLContextEvent",
+  "WebGLFramebuffer",
+  "WebGLObject",
+  "WebGLProgram",
+  "WebGLRenderbuffer",
+  "WebGLRenderingContext",
+  "WebGLShader",
+  "WebGLShaderPrecisionFormat",
+  "WebGLTexture",
+  "WebGLUniformLocation",
   "WebSocket",
   "Worker",
   "WorkerGlobalScope",